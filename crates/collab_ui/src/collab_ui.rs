@@ -59,6 +59,7 @@ fn notification_window_options(
         titlebar: None,
         focus: false,
         show: true,
+        deferred: false,
         kind: WindowKind::PopUp,
         is_movable: false,
         display_id: Some(screen.id()),
@@ -66,5 +67,7 @@ fn notification_window_options(
         app_id: Some(app_id.to_owned()),
         window_min_size: None,
         window_decorations: Some(WindowDecorations::Client),
+        reactive_rendering: false,
+        anti_aliasing: Default::default(),
     }
 }
@@ -4,6 +4,7 @@ mod file_finder_tests;
 mod open_path_prompt_tests;
 
 pub mod file_finder_settings;
+pub mod launcher;
 mod new_path_prompt;
 mod open_path_prompt;
 
@@ -73,6 +74,7 @@ pub fn init_settings(cx: &mut App) {
 
 pub fn init(cx: &mut App) {
     init_settings(cx);
+    launcher::init(cx);
     cx.observe_new(FileFinder::register).detach();
     cx.observe_new(NewPathPrompt::register).detach();
     cx.observe_new(OpenPathPrompt::register).detach();
@@ -3,10 +3,17 @@ use schemars::JsonSchema;
 use serde_derive::{Deserialize, Serialize};
 use settings::{Settings, SettingsSources};
 
-#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[derive(Deserialize, Debug, Clone, PartialEq)]
 pub struct FileFinderSettings {
     pub file_icons: bool,
     pub modal_max_width: Option<FileFinderWidth>,
+    pub launcher: LauncherSettings,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct LauncherSettings {
+    pub global_shortcut: bool,
+    pub preferred_trigger: String,
 }
 
 #[derive(Clone, Default, Serialize, Deserialize, JsonSchema, Debug)]
@@ -19,6 +26,25 @@ pub struct FileFinderSettingsContent {
     ///
     /// Default: small
     pub modal_max_width: Option<FileFinderWidth>,
+    /// Settings for the launcher, a file finder in a window of its own that
+    /// opens files in Zed from anywhere on the desktop.
+    pub launcher: Option<LauncherSettingsContent>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, JsonSchema, Debug)]
+pub struct LauncherSettingsContent {
+    /// Whether to register a global shortcut with the desktop that toggles
+    /// the launcher, even when Zed isn't focused. Only supported on Linux,
+    /// through the GlobalShortcuts portal.
+    ///
+    /// Default: false
+    pub global_shortcut: Option<bool>,
+    /// The trigger to suggest for the global shortcut, following the
+    /// freedesktop shortcuts specification. The desktop may let the user pick
+    /// another one.
+    ///
+    /// Default: "LOGO+P"
+    pub preferred_trigger: Option<String>,
 }
 
 impl Settings for FileFinderSettings {
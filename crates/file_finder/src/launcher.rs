@@ -0,0 +1,277 @@
+//! A file finder in a window of its own, on a layer shell overlay, so it can be summoned while
+//! Zed isn't focused and open files in the running instance, like a system-wide fuzzy launcher.
+
+use std::sync::{
+    atomic::{self, AtomicBool},
+    Arc,
+};
+
+use fuzzy::PathMatch;
+use gpui::{
+    actions, point, px, size, Anchor, App, AppContext as _, Bounds, Context, Entity, FocusHandle,
+    Focusable, Global, KeyboardInteractivity, Layer, LayerShellSettings, Render, Task,
+    WindowBounds, WindowHandle, WindowKind, WindowOptions,
+};
+use picker::{Picker, PickerDelegate};
+use project::{PathMatchCandidateSet, Project, ProjectPath, WorktreeId};
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use util::ResultExt;
+use workspace::Workspace;
+
+actions!(file_finder, [ToggleLauncher]);
+
+pub(crate) fn init(cx: &mut App) {
+    cx.observe_new(|workspace: &mut Workspace, _window, _cx| {
+        workspace.register_action(|workspace, _: &ToggleLauncher, window, cx| {
+            if let Some(handle) = window.window_handle().downcast::<Workspace>() {
+                Launcher::toggle(handle, workspace.project().clone(), cx);
+            }
+        });
+    })
+    .detach();
+}
+
+/// Toggles the launcher for the active workspace window, or for the first one when none is
+/// active, like when a global shortcut is pressed while Zed isn't focused.
+pub fn toggle_launcher(cx: &mut App) {
+    let workspace = cx
+        .active_window()
+        .and_then(|window| window.downcast::<Workspace>())
+        .or_else(|| workspace::local_workspace_windows(cx).into_iter().next());
+    let Some(workspace) = workspace else {
+        return;
+    };
+    if let Some(project) = workspace
+        .read_with(cx, |workspace, _| workspace.project().clone())
+        .log_err()
+    {
+        Launcher::toggle(workspace, project, cx);
+    }
+}
+
+/// The launcher's window, while it's open.
+#[derive(Default)]
+struct LauncherWindow(Option<WindowHandle<Launcher>>);
+
+impl Global for LauncherWindow {}
+
+pub struct Launcher {
+    picker: Entity<Picker<LauncherDelegate>>,
+}
+
+impl Launcher {
+    fn toggle(workspace: WindowHandle<Workspace>, project: Entity<Project>, cx: &mut App) {
+        if let Some(handle) = cx.default_global::<LauncherWindow>().0.take() {
+            // The compositor may have closed the window already.
+            if handle
+                .update(cx, |_, window, _| window.remove_window())
+                .is_ok()
+            {
+                return;
+            }
+        }
+
+        let options = WindowOptions {
+            window_bounds: Some(WindowBounds::Windowed(Bounds {
+                origin: point(px(0.), px(0.)),
+                size: size(px(640.), px(420.)),
+            })),
+            titlebar: None,
+            // Without a display or an anchor, the compositor centers the window on the focused
+            // output.
+            kind: WindowKind::LayerShell(LayerShellSettings {
+                layer: Layer::Overlay,
+                anchor: Anchor::empty(),
+                keyboard_interactivity: KeyboardInteractivity::Exclusive,
+                namespace: "zed-launcher".to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let handle = cx
+            .open_window(options, |window, cx| {
+                cx.new(|cx| {
+                    let delegate = LauncherDelegate::new(workspace, project);
+                    let picker = cx.new(|cx| Picker::uniform_list(delegate, window, cx));
+                    window.focus(&picker.focus_handle(cx));
+                    Self { picker }
+                })
+            })
+            .log_err();
+        cx.default_global::<LauncherWindow>().0 = handle;
+    }
+}
+
+impl Focusable for Launcher {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl Render for Launcher {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
+            .size_full()
+            .bg(cx.theme().colors().elevated_surface_background)
+            .border_1()
+            .border_color(cx.theme().colors().border)
+            .rounded_lg()
+            .overflow_hidden()
+            .child(self.picker.clone())
+    }
+}
+
+pub struct LauncherDelegate {
+    workspace: WindowHandle<Workspace>,
+    project: Entity<Project>,
+    matches: Vec<PathMatch>,
+    selected_index: usize,
+    cancel_flag: Arc<AtomicBool>,
+}
+
+impl LauncherDelegate {
+    fn new(workspace: WindowHandle<Workspace>, project: Entity<Project>) -> Self {
+        Self {
+            workspace,
+            project,
+            matches: Vec::new(),
+            selected_index: 0,
+            cancel_flag: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+impl PickerDelegate for LauncherDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _window: &mut Window, _cx: &mut App) -> Arc<str> {
+        "Open a file in Zed...".into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(
+        &mut self,
+        ix: usize,
+        _window: &mut Window,
+        _: &mut Context<Picker<Self>>,
+    ) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(
+        &mut self,
+        query: String,
+        window: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) -> Task<()> {
+        let query = query.replace(' ', "");
+        if query.is_empty() {
+            self.matches.clear();
+            self.selected_index = 0;
+            cx.notify();
+            return Task::ready(());
+        }
+
+        let worktrees = self
+            .project
+            .read(cx)
+            .visible_worktrees(cx)
+            .collect::<Vec<_>>();
+        let include_root_name = worktrees.len() > 1;
+        let candidate_sets = worktrees
+            .into_iter()
+            .map(|worktree| {
+                let worktree = worktree.read(cx);
+                PathMatchCandidateSet {
+                    snapshot: worktree.snapshot(),
+                    include_ignored: worktree
+                        .root_entry()
+                        .map_or(false, |entry| entry.is_ignored),
+                    include_root_name,
+                    candidates: project::Candidates::Files,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        self.cancel_flag.store(true, atomic::Ordering::Relaxed);
+        self.cancel_flag = Arc::new(AtomicBool::new(false));
+        let cancel_flag = self.cancel_flag.clone();
+        cx.spawn_in(window, |picker, mut cx| async move {
+            let matches = fuzzy::match_path_sets(
+                candidate_sets.as_slice(),
+                &query,
+                None,
+                false,
+                100,
+                &cancel_flag,
+                cx.background_executor().clone(),
+            )
+            .await;
+            if cancel_flag.load(atomic::Ordering::Relaxed) {
+                return;
+            }
+            picker
+                .update(&mut cx, |picker, cx| {
+                    picker.delegate.matches = matches;
+                    picker.delegate.selected_index = 0;
+                    cx.notify();
+                })
+                .log_err();
+        })
+    }
+
+    fn confirm(&mut self, _: bool, window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        if let Some(path_match) = self.matches.get(self.selected_index) {
+            let project_path = ProjectPath {
+                worktree_id: WorktreeId::from_usize(path_match.worktree_id),
+                path: path_match.path.clone(),
+            };
+            self.workspace
+                .update(cx, |workspace, window, cx| {
+                    window.activate_window();
+                    workspace
+                        .open_path(project_path, None, true, window, cx)
+                        .detach_and_log_err(cx);
+                })
+                .log_err();
+        }
+        self.dismissed(window, cx);
+    }
+
+    fn dismissed(&mut self, window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        cx.default_global::<LauncherWindow>().0 = None;
+        window.remove_window();
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _window: &mut Window,
+        _cx: &mut Context<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let path_match = self.matches.get(ix)?;
+        let full_path = [
+            path_match.path_prefix.as_ref(),
+            path_match.path.to_string_lossy().as_ref(),
+        ]
+        .join("");
+        Some(
+            ListItem::new(ix)
+                .spacing(ListItemSpacing::Sparse)
+                .inset(true)
+                .toggle_state(selected)
+                .child(HighlightedLabel::new(
+                    full_path,
+                    path_match.positions.clone(),
+                )),
+        )
+    }
+}
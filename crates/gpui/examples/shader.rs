@@ -0,0 +1,48 @@
+use gpui::{
+    div, prelude::*, px, shader, size, App, Application, Bounds, Context, CustomShader, Window,
+    WindowBounds, WindowOptions,
+};
+
+const PLASMA: &str = r#"
+fn shade(input: ShaderInput) -> vec4<f32> {
+    let uv = input.position / input.size;
+    let mouse = input.mouse / input.size;
+    let wave = sin(uv.x * 10.0 + input.time) + sin(uv.y * 10.0 + input.time * 1.3);
+    let glow = 0.05 / max(distance(uv, mouse), 0.01);
+    let color = 0.5 + 0.5 * cos(wave + vec3<f32>(0.0, 2.0, 4.0));
+    return vec4<f32>(color + glow, 1.0);
+}
+"#;
+
+struct ShaderExample {
+    plasma: CustomShader,
+}
+
+impl Render for ShaderExample {
+    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+        div()
+            .size_full()
+            .p_4()
+            .child(shader(self.plasma.clone()).size_full())
+    }
+}
+
+fn main() {
+    Application::new().run(|cx: &mut App| {
+        let bounds = Bounds::centered(None, size(px(600.0), px(600.0)), cx);
+        cx.open_window(
+            WindowOptions {
+                window_bounds: Some(WindowBounds::Windowed(bounds)),
+                ..Default::default()
+            },
+            |_window, cx| {
+                cx.new(|_cx| ShaderExample {
+                    plasma: CustomShader::new(PLASMA),
+                })
+            },
+        )
+        .unwrap();
+
+        cx.activate(true);
+    });
+}
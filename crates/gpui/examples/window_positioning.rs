@@ -57,11 +57,14 @@ fn build_window_options(display_id: DisplayId, bounds: Bounds<Pixels>) -> Window
         window_background: WindowBackgroundAppearance::Transparent,
         focus: false,
         show: true,
+        deferred: false,
         kind: WindowKind::PopUp,
         is_movable: false,
         app_id: None,
         window_min_size: None,
         window_decorations: None,
+        reactive_rendering: false,
+        anti_aliasing: Default::default(),
     }
 }
 
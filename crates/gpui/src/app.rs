@@ -1,6 +1,6 @@
 use std::{
     any::{type_name, TypeId},
-    cell::{Ref, RefCell, RefMut},
+    cell::{Cell, Ref, RefCell, RefMut},
     marker::PhantomData,
     mem,
     ops::{Deref, DerefMut},
@@ -23,6 +23,8 @@ use slotmap::SlotMap;
 pub use async_context::*;
 use collections::{FxHashMap, FxHashSet, HashMap, VecDeque};
 pub use context::*;
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+pub use control_socket::*;
 pub use entity_map::*;
 use http_client::HttpClient;
 #[cfg(any(test, feature = "test-support"))]
@@ -32,16 +34,21 @@ use util::ResultExt;
 use crate::{
     current_platform, hash, init_app_menus, Action, ActionBuildError, ActionRegistry, Any, AnyView,
     AnyWindowHandle, AppContext, Asset, AssetSource, BackgroundExecutor, Bounds, ClipboardItem,
-    DispatchPhase, DisplayId, EventEmitter, FocusHandle, FocusMap, ForegroundExecutor, Global,
-    KeyBinding, Keymap, Keystroke, LayoutId, Menu, MenuItem, OwnedMenu, PathPromptOptions, Pixels,
-    Platform, PlatformDisplay, Point, PromptBuilder, PromptHandle, PromptLevel, Render,
-    RenderablePromptHandle, Reservation, ScreenCaptureSource, SharedString, SubscriberSet,
-    Subscription, SvgRenderer, Task, TextSystem, Window, WindowAppearance, WindowHandle, WindowId,
-    WindowInvalidator,
+    DesktopWorkspace, DesktopWorkspaceGroup, DesktopWorkspaceId, DesktopWorkspaceRequest,
+    DispatchPhase, DisplayGamma, DisplayId, EventEmitter, FocusHandle, FocusMap,
+    ForegroundExecutor, Global, KeyBinding, Keymap, Keystroke, LayoutId, Menu, MenuItem,
+    OutputConfiguration, OutputHead, OwnedMenu, PathPromptOptions, Pixels, Platform,
+    PlatformDisplay, Point, PopupAnchor, PromptBuilder, PromptHandle, PromptLevel, Render,
+    RenderImage, RenderablePromptHandle, Reservation, Rgba, ScreenCaptureSource, SharedString,
+    SubscriberSet, Subscription, SvgRenderer, Task, TextSystem, VirtualKeyboardInput,
+    VirtualPointerInput, Window, WindowAppearance, WindowBounds, WindowCreationError, WindowHandle,
+    WindowId, WindowInvalidator, WindowKind, WindowOptions,
 };
 
 mod async_context;
 mod context;
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+mod control_socket;
 mod entity_map;
 #[cfg(any(test, feature = "test-support"))]
 mod test_context;
@@ -219,6 +226,9 @@ pub(crate) type KeystrokeObserver =
     Box<dyn FnMut(&KeystrokeEvent, &mut Window, &mut App) -> bool + 'static>;
 type QuitHandler = Box<dyn FnOnce(&mut App) -> LocalBoxFuture<'static, ()> + 'static>;
 type WindowClosedHandler = Box<dyn FnMut(&mut App)>;
+type PlatformErrorHandler = Box<dyn FnMut(&anyhow::Error, &mut App) -> bool + 'static>;
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+type ControlCommandHandler = Box<dyn FnMut(&ControlCommand, &mut App) -> bool + 'static>;
 type ReleaseListener = Box<dyn FnOnce(&mut dyn Any, &mut App) + 'static>;
 type NewEntityListener = Box<dyn FnMut(AnyEntity, &mut Option<&mut Window>, &mut App) + 'static>;
 
@@ -248,6 +258,7 @@ pub struct App {
     pub(crate) focus_handles: Arc<FocusMap>,
     pub(crate) keymap: Rc<RefCell<Keymap>>,
     pub(crate) keyboard_layout: SharedString,
+    pub(crate) keyboard_layout_index: Option<usize>,
     pub(crate) global_action_listeners:
         FxHashMap<TypeId, Vec<Rc<dyn Fn(&dyn Any, DispatchPhase, &mut Self)>>>,
     pending_effects: VecDeque<Effect>,
@@ -258,12 +269,19 @@ pub struct App {
     pub(crate) event_listeners: SubscriberSet<EntityId, (TypeId, Listener)>,
     pub(crate) keystroke_observers: SubscriberSet<(), KeystrokeObserver>,
     pub(crate) keyboard_layout_observers: SubscriberSet<(), Handler>,
+    pub(crate) output_heads_observers: SubscriberSet<(), Handler>,
+    pub(crate) desktop_workspaces_observers: SubscriberSet<(), Handler>,
+    pub(crate) platform_error_observers: SubscriberSet<(), PlatformErrorHandler>,
+    #[cfg(any(target_os = "linux", target_os = "freebsd"))]
+    pub(crate) control_command_observers: SubscriberSet<(), ControlCommandHandler>,
     pub(crate) release_listeners: SubscriberSet<EntityId, ReleaseListener>,
     pub(crate) global_observers: SubscriberSet<TypeId, Handler>,
     pub(crate) quit_observers: SubscriberSet<(), QuitHandler>,
     pub(crate) window_closed_observers: SubscriberSet<(), WindowClosedHandler>,
     pub(crate) layout_id_buffer: Vec<LayoutId>, // We recycle this memory across layout requests.
     pub(crate) propagate_event: bool,
+    /// Shared with the windows, which skip presenting unchanged frames while it's set.
+    pub(crate) power_saving: Rc<Cell<bool>>,
     pub(crate) prompt_builder: Option<PromptBuilder>,
     pub(crate) window_invalidators_by_entity:
         FxHashMap<EntityId, FxHashMap<WindowId, WindowInvalidator>>,
@@ -289,6 +307,7 @@ impl App {
         let text_system = Arc::new(TextSystem::new(platform.text_system()));
         let entities = EntityMap::new();
         let keyboard_layout = SharedString::from(platform.keyboard_layout());
+        let keyboard_layout_index = platform.keyboard_layout_index();
 
         let app = Rc::new_cyclic(|this| AppCell {
             app: RefCell::new(App {
@@ -314,6 +333,7 @@ impl App {
                 focus_handles: Arc::new(RwLock::new(SlotMap::with_key())),
                 keymap: Rc::new(RefCell::new(Keymap::default())),
                 keyboard_layout,
+                keyboard_layout_index,
                 global_action_listeners: FxHashMap::default(),
                 pending_effects: VecDeque::new(),
                 pending_notifications: FxHashSet::default(),
@@ -325,11 +345,17 @@ impl App {
                 release_listeners: SubscriberSet::new(),
                 keystroke_observers: SubscriberSet::new(),
                 keyboard_layout_observers: SubscriberSet::new(),
+                output_heads_observers: SubscriberSet::new(),
+                desktop_workspaces_observers: SubscriberSet::new(),
+                platform_error_observers: SubscriberSet::new(),
+                #[cfg(any(target_os = "linux", target_os = "freebsd"))]
+                control_command_observers: SubscriberSet::new(),
                 global_observers: SubscriberSet::new(),
                 quit_observers: SubscriberSet::new(),
                 window_closed_observers: SubscriberSet::new(),
                 layout_id_buffer: Default::default(),
                 propagate_event: true,
+                power_saving: Rc::new(Cell::new(false)),
                 prompt_builder: Some(PromptBuilder::Default),
 
                 #[cfg(any(test, feature = "test-support", debug_assertions))]
@@ -345,6 +371,7 @@ impl App {
                 if let Some(app) = app.upgrade() {
                     let cx = &mut app.borrow_mut();
                     cx.keyboard_layout = SharedString::from(cx.platform.keyboard_layout());
+                    cx.keyboard_layout_index = cx.platform.keyboard_layout_index();
                     cx.keyboard_layout_observers
                         .clone()
                         .retain(&(), move |callback| (callback)(cx));
@@ -352,6 +379,42 @@ impl App {
             }
         }));
 
+        platform.on_output_heads_change(Box::new({
+            let app = Rc::downgrade(&app);
+            move || {
+                if let Some(app) = app.upgrade() {
+                    let cx = &mut app.borrow_mut();
+                    cx.output_heads_observers
+                        .clone()
+                        .retain(&(), move |callback| (callback)(cx));
+                }
+            }
+        }));
+
+        platform.on_desktop_workspaces_change(Box::new({
+            let app = Rc::downgrade(&app);
+            move || {
+                if let Some(app) = app.upgrade() {
+                    let cx = &mut app.borrow_mut();
+                    cx.desktop_workspaces_observers
+                        .clone()
+                        .retain(&(), move |callback| (callback)(cx));
+                }
+            }
+        }));
+
+        platform.on_platform_error(Box::new({
+            let app = Rc::downgrade(&app);
+            move |error| {
+                if let Some(app) = app.upgrade() {
+                    let cx = &mut app.borrow_mut();
+                    cx.platform_error_observers
+                        .clone()
+                        .retain(&(), |callback| (callback)(&error, cx));
+                }
+            }
+        }));
+
         platform.on_quit(Box::new({
             let cx = app.clone();
             move || {
@@ -390,6 +453,30 @@ impl App {
         &self.keyboard_layout
     }
 
+    /// Get the index of the current keyboard layout within [`App::keyboard_layouts`],
+    /// if the platform exposes it.
+    pub fn keyboard_layout_index(&self) -> Option<usize> {
+        self.keyboard_layout_index
+    }
+
+    /// Get the names of all keyboard layouts the user can switch between,
+    /// in the order used by [`App::keyboard_layout_index`].
+    pub fn keyboard_layouts(&self) -> Vec<SharedString> {
+        self.platform
+            .keyboard_layouts()
+            .into_iter()
+            .map(SharedString::from)
+            .collect()
+    }
+
+    /// Selects the GPU used to render windows opened after this call by its PCI device ID,
+    /// or the default GPU for `None`. This allows keeping lightweight windows on an integrated
+    /// GPU instead of waking a discrete one. The `GPUI_DEVICE` environment variable takes
+    /// precedence. Currently only implemented on Linux.
+    pub fn set_gpu_device(&self, device_id: Option<u32>) {
+        self.platform.set_gpu_device(device_id);
+    }
+
     /// Invokes a handler when the current keyboard layout changes
     pub fn on_keyboard_layout_change<F>(&self, mut callback: F) -> Subscription
     where
@@ -406,6 +493,171 @@ impl App {
         subscription
     }
 
+    /// Invokes `callback` on the main thread whenever `fd` becomes readable, which allows
+    /// watching sockets, inotify handles or D-Bus connections without spawning a thread.
+    /// The callback must drain the file descriptor, otherwise it is invoked again right away.
+    /// Dropping the returned subscription stops watching and closes the file descriptor.
+    ///
+    /// Timers don't need this, [`BackgroundExecutor::timer`] is already driven by the same event loop.
+    #[cfg(any(target_os = "linux", target_os = "freebsd"))]
+    pub fn watch_fd(
+        &self,
+        fd: std::os::fd::OwnedFd,
+        mut callback: impl FnMut(&mut App) + 'static,
+    ) -> Result<Subscription> {
+        let app = self.this.clone();
+        self.platform.watch_fd(
+            fd,
+            Box::new(move || {
+                if let Some(app) = app.upgrade() {
+                    callback(&mut app.borrow_mut());
+                }
+            }),
+        )
+    }
+
+    /// Invokes `callback` on the main thread with the paths that changed whenever the given files
+    /// or directories, or the files in the directories, are written, created, removed or
+    /// renamed, e.g. to hot-reload config or theme files. Files replaced by renaming another
+    /// file over them are noticed too. Dropping the returned subscription stops watching.
+    #[cfg(target_os = "linux")]
+    pub fn watch_paths(
+        &self,
+        paths: impl IntoIterator<Item = impl AsRef<Path>>,
+        mut callback: impl FnMut(Vec<PathBuf>, &mut App) + 'static,
+    ) -> Result<Subscription> {
+        let mut watcher =
+            crate::PathWatcher::new(paths.into_iter().map(|path| path.as_ref().to_owned()))?;
+        let fd = watcher.fd()?;
+        self.watch_fd(fd, move |cx| {
+            let changes = watcher.read_changes();
+            if !changes.is_empty() {
+                callback(changes, cx);
+            }
+        })
+    }
+
+    /// Invokes a handler when the connection to the windowing system is lost, e.g. due to a
+    /// protocol error or the compositor going away. The application will quit right after,
+    /// so this is the last chance to persist any state.
+    pub fn on_platform_error<F>(&self, mut callback: F) -> Subscription
+    where
+        F: 'static + FnMut(&anyhow::Error, &mut App),
+    {
+        let (subscription, activate) = self.platform_error_observers.insert(
+            (),
+            Box::new(move |error, cx| {
+                callback(error, cx);
+                true
+            }),
+        );
+        activate();
+        subscription
+    }
+
+    /// Returns the display connectors known to the compositor with their modes and current
+    /// configuration. Only Wayland compositors implementing wlr-output-management list any.
+    pub fn output_heads(&self) -> Vec<OutputHead> {
+        self.platform.output_heads()
+    }
+
+    /// Invokes a handler when the heads returned by [`App::output_heads`] change, because
+    /// a display was connected or disconnected, or the outputs were reconfigured.
+    pub fn on_output_heads_change<F>(&self, mut callback: F) -> Subscription
+    where
+        F: 'static + FnMut(&mut App),
+    {
+        let (subscription, activate) = self.output_heads_observers.insert(
+            (),
+            Box::new(move |cx| {
+                callback(cx);
+                true
+            }),
+        );
+        activate();
+        subscription
+    }
+
+    /// Changes the resolution, scale or position of outputs, or enables and disables them.
+    /// Resolves to an error when the compositor rejects the configuration, or when the outputs
+    /// changed in the meantime, in which case the configuration can be retried.
+    pub fn apply_output_configuration(
+        &self,
+        configuration: OutputConfiguration,
+    ) -> oneshot::Receiver<Result<()>> {
+        self.platform.configure_outputs(configuration, false)
+    }
+
+    /// Checks whether the compositor would accept the configuration, without applying it.
+    pub fn test_output_configuration(
+        &self,
+        configuration: OutputConfiguration,
+    ) -> oneshot::Receiver<Result<()>> {
+        self.platform.configure_outputs(configuration, true)
+    }
+
+    /// Returns the workspaces of the desktop, also called virtual desktops. Only Wayland
+    /// compositors implementing ext-workspace list any.
+    pub fn desktop_workspaces(&self) -> Vec<DesktopWorkspace> {
+        self.platform.desktop_workspaces()
+    }
+
+    /// Returns the groups of [`App::desktop_workspaces`], usually one per display.
+    pub fn desktop_workspace_groups(&self) -> Vec<DesktopWorkspaceGroup> {
+        self.platform.desktop_workspace_groups()
+    }
+
+    /// Invokes a handler when workspaces or their groups are added, removed or change state,
+    /// e.g. when another workspace is activated or one requests attention.
+    pub fn on_desktop_workspaces_change<F>(&self, mut callback: F) -> Subscription
+    where
+        F: 'static + FnMut(&mut App),
+    {
+        let (subscription, activate) = self.desktop_workspaces_observers.insert(
+            (),
+            Box::new(move |cx| {
+                callback(cx);
+                true
+            }),
+        );
+        activate();
+        subscription
+    }
+
+    /// Switches to the given workspace.
+    pub fn activate_desktop_workspace(&self, id: DesktopWorkspaceId) -> Result<()> {
+        self.update_desktop_workspaces(vec![DesktopWorkspaceRequest::Activate(id)])
+    }
+
+    /// Sends the requests to the compositor, which applies them together. Whether they
+    /// succeeded is only visible in the workspaces once they change.
+    pub fn update_desktop_workspaces(&self, requests: Vec<DesktopWorkspaceRequest>) -> Result<()> {
+        self.platform.update_desktop_workspaces(requests)
+    }
+
+    /// Moves the pointer, clicks and scrolls like a real pointing device would, e.g. for
+    /// accessibility and remote control tools. The input reaches whichever application is
+    /// under the pointer. Only supported on Wayland compositors implementing
+    /// wlr-virtual-pointer.
+    pub fn send_virtual_pointer_input(
+        &self,
+        inputs: impl IntoIterator<Item = VirtualPointerInput>,
+    ) -> Result<()> {
+        self.platform
+            .send_virtual_pointer_input(inputs.into_iter().collect())
+    }
+
+    /// Types text and presses key chords like a real keyboard would, e.g. for pasting a snippet
+    /// into another application from a launcher. The input reaches whichever application has
+    /// the keyboard focus. Only supported on Wayland compositors implementing virtual-keyboard.
+    pub fn send_virtual_keyboard_input(
+        &self,
+        inputs: impl IntoIterator<Item = VirtualKeyboardInput>,
+    ) -> Result<()> {
+        self.platform
+            .send_virtual_keyboard_input(inputs.into_iter().collect())
+    }
+
     /// Gracefully quit the application via the platform's standard routine.
     pub fn quit(&self) {
         self.platform.quit();
@@ -592,16 +844,79 @@ impl App {
 
     /// Opens a new window with the given option and the root view returned by the given function.
     /// The function is invoked with a `Window`, which can be used to interact with window-specific
-    /// functionality.
+    /// functionality. Fails with a [`WindowCreationError`] telling why the platform couldn't open
+    /// the window.
     pub fn open_window<V: 'static + Render>(
         &mut self,
         options: crate::WindowOptions,
         build_root_view: impl FnOnce(&mut Window, &mut App) -> Entity<V>,
-    ) -> anyhow::Result<WindowHandle<V>> {
+    ) -> Result<WindowHandle<V>, WindowCreationError> {
+        self.open_window_internal(options, None, build_root_view)
+    }
+
+    /// Opens a popup window, like a menu or a popover, placed against an area of the `parent`
+    /// window, usually the bounds of the element that opened it.
+    ///
+    /// The size of the popup is taken from `options.window_bounds`, its kind is always
+    /// [`WindowKind::PopUp`]. On Wayland the popup takes keyboard focus and is dismissed by the
+    /// compositor when the user clicks elsewhere if `options.focus` is set.
+    ///
+    /// Popups that would overflow the usable area of the display flip to the other side of
+    /// `anchor`, see [`Window::usable_display_area`]. They may still cover their parent window.
+    pub fn open_popup_window<V: 'static + Render>(
+        &mut self,
+        parent: &Window,
+        anchor: PopupAnchor,
+        mut options: WindowOptions,
+        build_root_view: impl FnOnce(&mut Window, &mut App) -> Entity<V>,
+    ) -> Result<WindowHandle<V>, WindowCreationError> {
+        let size = options
+            .window_bounds
+            .map(|bounds| bounds.get_bounds().size)
+            .ok_or_else(|| anyhow!("popup windows need a size"))?;
+        let window_bounds = Bounds {
+            origin: Point::default(),
+            size: parent.viewport_size(),
+        };
+        let area = parent
+            .usable_display_area(self)
+            .map(|area| area.union(&window_bounds));
+        let anchor = match area {
+            Some(area) => anchor.fit_within(size, area),
+            None => anchor,
+        };
+
+        // Platforms without a positioner place the popup themselves, in screen coordinates.
+        let mut bounds = anchor.popup_bounds(size);
+        if let Some(area) = area {
+            bounds.origin.x = bounds
+                .origin
+                .x
+                .min(area.right() - size.width)
+                .max(area.left());
+            bounds.origin.y = bounds
+                .origin
+                .y
+                .min(area.bottom() - size.height)
+                .max(area.top());
+        }
+        bounds.origin += parent.bounds().origin;
+        options.window_bounds = Some(WindowBounds::Windowed(bounds));
+        options.kind = WindowKind::PopUp;
+
+        self.open_window_internal(options, Some((parent.handle, anchor)), build_root_view)
+    }
+
+    fn open_window_internal<V: 'static + Render>(
+        &mut self,
+        options: WindowOptions,
+        popup_anchor: Option<(AnyWindowHandle, PopupAnchor)>,
+        build_root_view: impl FnOnce(&mut Window, &mut App) -> Entity<V>,
+    ) -> Result<WindowHandle<V>, WindowCreationError> {
         self.update(|cx| {
             let id = cx.windows.insert(None);
             let handle = WindowHandle::new(id);
-            match Window::new(handle.into(), options, cx) {
+            match Window::new(handle.into(), options, popup_anchor, cx) {
                 Ok(mut window) => {
                     cx.window_update_stack.push(id);
                     let root_view = build_root_view(&mut window, cx);
@@ -657,6 +972,19 @@ impl App {
         self.platform.screen_capture_sources()
     }
 
+    /// Captures a screenshot of the screen, relayed asynchronously via the returned oneshot
+    /// channel. With `interactive`, the user can pick what to capture, like a window or an area.
+    /// If cancelled, a `None` will be relayed instead.
+    ///
+    /// Uses the Screenshot portal on Linux, which works across compositors but may ask the user
+    /// for permission.
+    pub fn capture_screenshot(
+        &self,
+        interactive: bool,
+    ) -> oneshot::Receiver<Result<Option<Arc<RenderImage>>>> {
+        self.platform.capture_screenshot(interactive)
+    }
+
     /// Returns the display with the given ID, if one exists.
     pub fn find_display(&self, id: DisplayId) -> Option<Rc<dyn PlatformDisplay>> {
         self.displays()
@@ -665,11 +993,48 @@ impl App {
             .cloned()
     }
 
+    /// Returns whether the display is turned on. Only known on Wayland compositors
+    /// implementing wlr-output-power-management.
+    pub fn is_display_powered(&self, id: DisplayId) -> Option<bool> {
+        self.platform.is_display_powered(id)
+    }
+
+    /// Turns the display on or off, e.g. to blank the monitors of an idle or locked session.
+    /// Turned off displays keep their configuration, windows stay on them and keep rendering.
+    pub fn set_display_powered(&self, id: DisplayId, powered: bool) -> Result<()> {
+        self.platform.set_display_powered(id, powered)
+    }
+
+    /// Returns the color temperature and brightness the display was adjusted to with
+    /// [`App::set_display_gamma`], if any.
+    pub fn display_gamma(&self, id: DisplayId) -> Option<DisplayGamma> {
+        self.platform.display_gamma(id)
+    }
+
+    /// Adjusts the color temperature and brightness of the display, e.g. from a night light
+    /// slider. Only supported on Wayland compositors implementing wlr-gamma-control, which let
+    /// a single client adjust each display at a time. The adjustment lasts until it's reset
+    /// or the application quits.
+    pub fn set_display_gamma(&self, id: DisplayId, gamma: DisplayGamma) -> Result<()> {
+        self.platform.set_display_gamma(id, Some(gamma))
+    }
+
+    /// Undoes the adjustment of [`App::set_display_gamma`].
+    pub fn reset_display_gamma(&self, id: DisplayId) -> Result<()> {
+        self.platform.set_display_gamma(id, None)
+    }
+
     /// Returns the appearance of the application's windows.
     pub fn window_appearance(&self) -> WindowAppearance {
         self.platform.window_appearance()
     }
 
+    /// Returns the accent color picked by the user, if the platform exposes one. Windows notify
+    /// their appearance observers when it changes.
+    pub fn accent_color(&self) -> Option<Rgba> {
+        self.platform.accent_color()
+    }
+
     /// Writes data to the primary selection buffer.
     /// Only available on Linux.
     #[cfg(any(target_os = "linux", target_os = "freebsd"))]
@@ -773,6 +1138,15 @@ impl App {
         self.platform.prompt_for_new_path(directory)
     }
 
+    /// Lets the user pick the color of any pixel on the screen, e.g. for a color picker widget.
+    ///
+    /// The picked color is relayed asynchronously via the returned oneshot channel.
+    /// If cancelled, a `None` will be relayed instead.
+    /// Uses the Screenshot portal on Linux, which may return an error if it isn't available.
+    pub fn pick_color(&self) -> oneshot::Receiver<Result<Option<Rgba>>> {
+        self.platform.pick_color()
+    }
+
     /// Reveals the specified path at the platform level, such as in Finder on macOS.
     pub fn reveal_path(&self, path: &Path) {
         self.platform.reveal_path(path)
@@ -788,6 +1162,25 @@ impl App {
         self.platform.should_auto_hide_scrollbars()
     }
 
+    /// Lets windows save power by presenting frames only when their contents change, instead of
+    /// at the full refresh rate for a moment after input, e.g. while running on battery. On Linux,
+    /// a [`crate::PowerSupply`] turns this on and off with the power state.
+    pub fn set_power_saving(&mut self, power_saving: bool) {
+        self.power_saving.set(power_saving);
+    }
+
+    /// Returns whether windows save power, see [`App::set_power_saving`].
+    pub fn is_power_saving(&self) -> bool {
+        self.power_saving.get()
+    }
+
+    /// Returns whether the user asked for animations to be reduced at the platform level, in
+    /// which case [`crate::Animation`]s jump to their end. Windows notify their appearance
+    /// observers when it changes.
+    pub fn should_reduce_motion(&self) -> bool {
+        self.platform.should_reduce_motion()
+    }
+
     /// Restarts the application.
     pub fn restart(&self, binary_path: Option<PathBuf>) {
         self.platform.restart(binary_path)
@@ -885,6 +1278,13 @@ impl App {
                 }
             }
         }
+
+        // Windows using reactive rendering only get frames when they ask for them.
+        for window in self.windows.values().flatten() {
+            if window.reactive_rendering && window.invalidator.is_dirty() {
+                window.platform_window.request_frame();
+            }
+        }
     }
 
     /// Repeatedly called during `flush_effects` to release any entities whose
@@ -1009,6 +1409,11 @@ impl App {
             cx.window_update_stack.pop();
 
             if window.removed {
+                if let Some((_, Some(tooltip_popup))) = window.tooltip_popup.take() {
+                    tooltip_popup
+                        .update(cx, |_, window, _| window.remove_window())
+                        .ok();
+                }
                 cx.window_handles.remove(&id);
                 cx.windows.remove(id);
 
@@ -1524,10 +1929,13 @@ impl App {
         self.prompt_builder = Some(PromptBuilder::Custom(Box::new(renderer)))
     }
 
-    /// Remove an asset from GPUI's cache
-    pub fn remove_asset<A: Asset>(&mut self, source: &A::Source) {
+    /// Remove an asset from GPUI's cache, returning it if it had finished loading
+    pub fn remove_asset<A: Asset>(&mut self, source: &A::Source) -> Option<A::Output> {
         let asset_id = (TypeId::of::<A>(), hash(source));
-        self.loading_assets.remove(&asset_id);
+        let task = self.loading_assets.remove(&asset_id)?;
+        task.downcast::<Shared<Task<A::Output>>>()
+            .ok()?
+            .now_or_never()
     }
 
     /// Asynchronously load an asset, if the asset hasn't finished loading this will return None.
@@ -155,6 +155,7 @@ impl AsyncApp {
     }
 
     /// Open a window with the given options based on the root view returned by the given function.
+    /// Failures to create the window are a [`WindowCreationError`] in the returned error.
     pub fn open_window<V>(
         &self,
         options: crate::WindowOptions,
@@ -168,7 +169,7 @@ impl AsyncApp {
             .upgrade()
             .ok_or_else(|| anyhow!("app was released"))?;
         let mut lock = app.borrow_mut();
-        lock.open_window(options, build_root_view)
+        Ok(lock.open_window(options, build_root_view)?)
     }
 
     /// Schedule a future to be polled in the background.
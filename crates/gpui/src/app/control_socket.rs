@@ -0,0 +1,187 @@
+//! An opt-in control socket, which lets other processes like a second launch of the application
+//! or a keybinding of the compositor send commands to the running instance, and keeps a single
+//! instance of the application running.
+//!
+//! Commands are sent one per line as words separated by whitespace, e.g. `toggle launcher`.
+
+use std::{
+    fmt, fs,
+    io::{self, BufRead as _, BufReader, Write as _},
+    os::{
+        fd::AsFd as _,
+        unix::net::{UnixListener, UnixStream},
+    },
+    path::PathBuf,
+    time::Duration,
+};
+
+use anyhow::{Context as _, Result};
+
+use crate::{App, AppContext as _, Subscription};
+
+/// A command received on the control socket, see [`App::on_control_command`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ControlCommand {
+    /// The first word of the command, like `toggle`.
+    pub name: String,
+    /// The words following the name, like the window to toggle.
+    pub args: Vec<String>,
+}
+
+impl ControlCommand {
+    /// Creates a command from its name and arguments.
+    pub fn new(name: impl Into<String>, args: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self {
+            name: name.into(),
+            args: args.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Parses a line of the control socket protocol, returning `None` for blank lines.
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut words = line.split_whitespace();
+        let name = words.next()?;
+        Some(Self::new(name, words))
+    }
+}
+
+impl fmt::Display for ControlCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        for arg in &self.args {
+            write!(f, " {arg}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The outcome of [`App::bind_control_socket`].
+pub enum ControlSocket {
+    /// This is the only running instance, which receives commands until the subscription is
+    /// dropped.
+    Listening(Subscription),
+    /// Another instance is running already. Forward the request of this launch to it with
+    /// [`send_control_commands`] and exit.
+    AlreadyRunning,
+}
+
+/// Returns the path of the control socket of the application with the given id, in
+/// `$XDG_RUNTIME_DIR`.
+pub fn control_socket_path(app_id: &str) -> PathBuf {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir)
+        .join(format!("{app_id}.sock"))
+}
+
+/// Sends commands to the running instance of the application with the given id, e.g. from a
+/// second launch of it or from a small CLI.
+pub fn send_control_commands(app_id: &str, commands: &[ControlCommand]) -> Result<()> {
+    let path = control_socket_path(app_id);
+    let mut stream = UnixStream::connect(&path)
+        .with_context(|| format!("failed to connect to control socket {path:?}"))?;
+    for command in commands {
+        writeln!(stream, "{command}")?;
+    }
+    Ok(())
+}
+
+impl App {
+    /// Listens for commands on the control socket of the application with the given id, unless
+    /// another instance of it does already, see [`control_socket_path`]. Received commands are
+    /// delivered to the handlers registered with [`App::on_control_command`].
+    pub fn bind_control_socket(&mut self, app_id: &str) -> Result<ControlSocket> {
+        let path = control_socket_path(app_id);
+        if UnixStream::connect(&path).is_ok() {
+            return Ok(ControlSocket::AlreadyRunning);
+        }
+        // Nobody listens on the socket, it's left over by an instance that crashed.
+        match fs::remove_file(&path) {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => {
+                return Err(error).with_context(|| format!("failed to remove {path:?}"))
+            }
+            _ => {}
+        }
+
+        let listener = UnixListener::bind(&path)
+            .with_context(|| format!("failed to bind control socket {path:?}"))?;
+        listener.set_nonblocking(true)?;
+        let fd = listener.as_fd().try_clone_to_owned()?;
+        let watch = self.watch_fd(fd, move |cx| loop {
+            match listener.accept() {
+                Ok((stream, _)) => cx.read_control_commands(stream),
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+                Err(error) => {
+                    log::error!("failed to accept control socket connection: {error}");
+                    break;
+                }
+            }
+        })?;
+        let remove = Subscription::new(move || {
+            fs::remove_file(&path).ok();
+        });
+        Ok(ControlSocket::Listening(Subscription::join(watch, remove)))
+    }
+
+    /// Invokes a handler for every command received on the control socket, see
+    /// [`App::bind_control_socket`].
+    pub fn on_control_command<F>(&self, mut callback: F) -> Subscription
+    where
+        F: 'static + FnMut(&ControlCommand, &mut App),
+    {
+        let (subscription, activate) = self.control_command_observers.insert(
+            (),
+            Box::new(move |command, cx| {
+                callback(command, cx);
+                true
+            }),
+        );
+        activate();
+        subscription
+    }
+
+    fn read_control_commands(&self, stream: UnixStream) {
+        // Clients send their commands and hang up, read them off the main thread in case one
+        // takes its time.
+        let lines = self.background_spawn(async move {
+            stream.set_nonblocking(false)?;
+            stream.set_read_timeout(Some(Duration::from_secs(1)))?;
+            BufReader::new(stream)
+                .lines()
+                .collect::<io::Result<Vec<_>>>()
+        });
+        self.spawn(|cx| async move {
+            let lines = lines.await.context("failed to read control commands")?;
+            cx.update(|cx| {
+                for command in lines.iter().filter_map(|line| ControlCommand::parse(line)) {
+                    cx.control_command_observers
+                        .clone()
+                        .retain(&(), |callback| (callback)(&command, cx));
+                }
+            })
+        })
+        .detach_and_log_err(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ControlCommand;
+
+    #[test]
+    fn test_parse_control_command() {
+        assert_eq!(
+            ControlCommand::parse("  toggle   launcher\n"),
+            Some(ControlCommand::new("toggle", ["launcher"]))
+        );
+        assert_eq!(
+            ControlCommand::parse("quit"),
+            Some(ControlCommand::new("quit", Vec::<String>::new()))
+        );
+        assert_eq!(ControlCommand::parse(" \t"), None);
+        assert_eq!(
+            ControlCommand::new("toggle", ["launcher"]).to_string(),
+            "toggle launcher"
+        );
+    }
+}
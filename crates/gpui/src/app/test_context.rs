@@ -1,11 +1,11 @@
 use crate::{
     Action, AnyView, AnyWindowHandle, App, AppCell, AppContext, AsyncApp, AvailableSpace,
     BackgroundExecutor, BorrowAppContext, Bounds, ClipboardItem, DrawPhase, Drawable, Element,
-    Empty, EventEmitter, ForegroundExecutor, Global, InputEvent, Keystroke, Modifiers,
-    ModifiersChangedEvent, MouseButton, MouseDownEvent, MouseMoveEvent, MouseUpEvent, Pixels,
-    Platform, Point, Render, Result, Size, Task, TestDispatcher, TestPlatform,
-    TestScreenCaptureSource, TestWindow, TextSystem, VisualContext, Window, WindowBounds,
-    WindowHandle, WindowOptions,
+    Empty, EventEmitter, ForegroundExecutor, Global, InputEvent, InputRecording, Keystroke,
+    Modifiers, ModifiersChangedEvent, MouseButton, MouseDownEvent, MouseMoveEvent, MouseUpEvent,
+    Pixels, Platform, Point, Render, Result, SimulatedConfigure, Size, Task, TestDispatcher,
+    TestPlatform, TestScreenCaptureSource, TestWindow, TextSystem, VisualContext, Window,
+    WindowBounds, WindowControls, WindowDecorations, WindowHandle, WindowOptions,
 };
 use anyhow::{anyhow, bail};
 use futures::{channel::oneshot, Stream, StreamExt};
@@ -311,6 +311,79 @@ impl TestAppContext {
         self.test_window(window_handle).simulate_resize(size);
     }
 
+    /// Simulates the compositor configuring the window, like a Wayland compositor does when
+    /// the window is resized, maximized or tiled.
+    pub fn simulate_window_configure(
+        &self,
+        window_handle: AnyWindowHandle,
+        configure: SimulatedConfigure,
+    ) {
+        self.test_window(window_handle)
+            .simulate_configure(configure);
+    }
+
+    /// Simulates the compositor picking a preferred scale for the window.
+    pub fn simulate_window_scale_factor(&self, window_handle: AnyWindowHandle, scale_factor: f32) {
+        self.test_window(window_handle)
+            .simulate_scale_factor(scale_factor);
+    }
+
+    /// Simulates the window entering an output with the given scale.
+    pub fn simulate_window_output_enter(
+        &self,
+        window_handle: AnyWindowHandle,
+        output_id: u32,
+        scale: i32,
+    ) {
+        self.test_window(window_handle)
+            .simulate_output_enter(output_id, scale);
+    }
+
+    /// Simulates the window leaving an output it was on.
+    pub fn simulate_window_output_leave(&self, window_handle: AnyWindowHandle, output_id: u32) {
+        self.test_window(window_handle)
+            .simulate_output_leave(output_id);
+    }
+
+    /// Simulates the compositor switching the window between server and client side decorations.
+    pub fn simulate_window_decorations(
+        &self,
+        window_handle: AnyWindowHandle,
+        decorations: WindowDecorations,
+    ) {
+        self.test_window(window_handle)
+            .simulate_decorations(decorations);
+    }
+
+    /// Simulates the compositor announcing which window controls it supports.
+    pub fn simulate_window_controls(
+        &self,
+        window_handle: AnyWindowHandle,
+        window_controls: WindowControls,
+    ) {
+        self.test_window(window_handle)
+            .simulate_window_controls(window_controls);
+    }
+
+    /// Simulates the compositor closing the window, like it does with a layer surface when its
+    /// output is unplugged.
+    pub fn simulate_window_closed(&self, window_handle: AnyWindowHandle) {
+        self.test_window(window_handle).simulate_closed();
+    }
+
+    /// The margin the window last asked the compositor for, as a layer surface.
+    pub fn window_layer_shell_margin(
+        &self,
+        window_handle: AnyWindowHandle,
+    ) -> (Pixels, Pixels, Pixels, Pixels) {
+        self.test_window(window_handle).layer_shell_margin()
+    }
+
+    /// The exclusive zone the window last asked the compositor for, as a layer surface.
+    pub fn window_layer_shell_exclusive_zone(&self, window_handle: AnyWindowHandle) -> Pixels {
+        self.test_window(window_handle).layer_shell_exclusive_zone()
+    }
+
     /// Causes the given sources to be returned if the application queries for screen
     /// capture sources.
     pub fn set_screen_capture_sources(&self, sources: Vec<TestScreenCaptureSource>) {
@@ -815,6 +888,22 @@ impl VisualTestContext {
         self.background_executor.run_until_parked();
     }
 
+    /// Replays recorded input into the window, advancing the clock between events as much
+    /// as time passed between them while recording, so timeouts like the one of pending
+    /// multi-keystroke bindings behave as they did.
+    pub fn replay_input(&mut self, recording: &InputRecording) {
+        let mut elapsed = Duration::ZERO;
+        for recorded in &recording.events {
+            if let Some(delay) = recorded.elapsed.checked_sub(elapsed) {
+                self.background_executor.advance_clock(delay);
+            }
+            elapsed = elapsed.max(recorded.elapsed);
+            self.test_window(self.window)
+                .simulate_input(recorded.event.clone());
+            self.background_executor.run_until_parked();
+        }
+    }
+
     /// Simulates the user blurring the window.
     pub fn deactivate_window(&mut self) {
         if Some(self.window) == self.test_platform.active_window() {
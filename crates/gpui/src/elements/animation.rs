@@ -108,14 +108,17 @@ impl<E: IntoElement + 'static> Element for AnimationElement<E> {
         cx: &mut App,
     ) -> (crate::LayoutId, Self::RequestLayoutState) {
         window.with_element_state(global_id.unwrap(), |state, window| {
-            let state = state.unwrap_or_else(|| AnimationState {
-                start: Instant::now(),
-            });
-            let mut delta =
-                state.start.elapsed().as_secs_f32() / self.animation.duration.as_secs_f32();
+            let now = window.animation_time();
+            let state = state.unwrap_or(AnimationState { start: now });
+            let mut delta = now.saturating_duration_since(state.start).as_secs_f32()
+                / self.animation.duration.as_secs_f32();
 
             let mut done = false;
-            if delta > 1.0 {
+            if cx.should_reduce_motion() {
+                // Skip to the end of one-shot animations, and hold repeating ones still.
+                done = true;
+                delta = if self.animation.oneshot { 1.0 } else { 0.0 };
+            } else if delta > 1.0 {
                 if self.animation.oneshot {
                     done = true;
                     delta = 1.0;
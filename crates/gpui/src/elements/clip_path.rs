@@ -0,0 +1,115 @@
+use refineable::Refineable as _;
+use smallvec::SmallVec;
+
+use crate::{
+    AnyElement, App, Bounds, Element, ElementId, GlobalElementId, IntoElement, LayoutId,
+    ParentElement, PathBuilder, Pixels, Style, StyleRefinement, Styled, Window,
+};
+
+/// Create an element clipping itself and its children to the path drawn by `shape` within the
+/// bounds of the element, like circular avatars with a notch for a status badge, squircle
+/// icons or custom-shaped OSDs.
+///
+/// The path is filled, so `shape` only needs to draw its outline, see [`PathBuilder`]. Renderers
+/// without a GPU only clip to the bounds of the path.
+pub fn clip_path(shape: impl Fn(Bounds<Pixels>, &mut PathBuilder) + 'static) -> ClipPath {
+    ClipPath {
+        shape: Box::new(shape),
+        children: SmallVec::new(),
+        style: StyleRefinement::default(),
+    }
+}
+
+/// An element clipping itself and its children to a path, see [`clip_path`].
+pub struct ClipPath {
+    shape: Box<dyn Fn(Bounds<Pixels>, &mut PathBuilder)>,
+    children: SmallVec<[AnyElement; 2]>,
+    style: StyleRefinement,
+}
+
+impl Element for ClipPath {
+    type RequestLayoutState = Style;
+    type PrepaintState = ();
+
+    fn id(&self) -> Option<ElementId> {
+        None
+    }
+
+    fn request_layout(
+        &mut self,
+        _global_id: Option<&GlobalElementId>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> (LayoutId, Self::RequestLayoutState) {
+        let mut style = Style::default();
+        style.refine(&self.style);
+        let child_layout_ids = self
+            .children
+            .iter_mut()
+            .map(|child| child.request_layout(window, cx))
+            .collect::<SmallVec<[LayoutId; 2]>>();
+        let layout_id = window.request_layout(style.clone(), child_layout_ids, cx);
+        (layout_id, style)
+    }
+
+    fn prepaint(
+        &mut self,
+        _global_id: Option<&GlobalElementId>,
+        _bounds: Bounds<Pixels>,
+        _request_layout: &mut Self::RequestLayoutState,
+        window: &mut Window,
+        cx: &mut App,
+    ) {
+        for child in &mut self.children {
+            child.prepaint(window, cx);
+        }
+    }
+
+    fn paint(
+        &mut self,
+        _global_id: Option<&GlobalElementId>,
+        bounds: Bounds<Pixels>,
+        style: &mut Self::RequestLayoutState,
+        _prepaint: &mut Self::PrepaintState,
+        window: &mut Window,
+        cx: &mut App,
+    ) {
+        let mut builder = PathBuilder::fill();
+        (self.shape)(bounds, &mut builder);
+        let path = match builder.build() {
+            Ok(path) => path,
+            Err(error) => {
+                log::error!("failed to build clip path: {error}");
+                return;
+            }
+        };
+
+        window.with_clip_path(path, |window| {
+            style.paint(bounds, window, cx, |window, cx| {
+                for child in &mut self.children {
+                    child.paint(window, cx);
+                }
+            });
+        });
+    }
+}
+
+impl IntoElement for ClipPath {
+    type Element = Self;
+
+    fn into_element(self) -> Self::Element {
+        self
+    }
+}
+
+impl ParentElement for ClipPath {
+    fn extend(&mut self, elements: impl IntoIterator<Item = AnyElement>) {
+        self.children.extend(elements)
+    }
+}
+
+impl Styled for ClipPath {
+    fn style(&mut self) -> &mut StyleRefinement {
+        &mut self.style
+    }
+}
@@ -16,12 +16,12 @@
 //! constructed by combining these two systems into an all-in-one element.
 
 use crate::{
-    point, px, size, Action, AnyDrag, AnyElement, AnyTooltip, AnyView, App, Bounds, ClickEvent,
-    DispatchPhase, Element, ElementId, Entity, FocusHandle, Global, GlobalElementId, Hitbox,
-    HitboxId, IntoElement, IsZero, KeyContext, KeyDownEvent, KeyUpEvent, LayoutId,
-    ModifiersChangedEvent, MouseButton, MouseDownEvent, MouseMoveEvent, MouseUpEvent,
-    ParentElement, Pixels, Point, Render, ScrollWheelEvent, SharedString, Size, Style,
-    StyleRefinement, Styled, Task, TooltipId, Visibility, Window,
+    point, px, size, AccessibleNode, AccessibleRole, Action, AnyDrag, AnyElement, AnyTooltip,
+    AnyView, App, Bounds, ClickEvent, DispatchPhase, Element, ElementId, Entity, FocusHandle,
+    Global, GlobalElementId, Hitbox, HitboxId, IntoElement, IsZero, KeyContext, KeyDownEvent,
+    KeyUpEvent, LayoutId, ModifiersChangedEvent, MouseButton, MouseDownEvent, MouseMoveEvent,
+    MouseUpEvent, ParentElement, Pixels, Point, Render, ScrollWheelEvent, SharedString, Size,
+    Style, StyleRefinement, Styled, Task, TooltipId, Visibility, Window,
 };
 use collections::HashMap;
 use refineable::Refineable;
@@ -563,6 +563,27 @@ pub trait InteractiveElement: Sized {
         Stateful { element: self }
     }
 
+    /// Describe this element to assistive technologies like screen readers, which announce it
+    /// and let users activate it according to its role. Elements without a role are transparent
+    /// to them, the described elements within are attached to the closest described ancestor.
+    fn role(mut self, role: AccessibleRole) -> Self {
+        self.interactivity().accessible_role = Some(role);
+        self
+    }
+
+    /// Set the name assistive technologies announce for this element, like the action of an
+    /// icon button. Only used on elements with a [`InteractiveElement::role`].
+    fn accessible_label(mut self, label: impl Into<SharedString>) -> Self {
+        self.interactivity().accessible_label = Some(label.into());
+        self
+    }
+
+    /// Set whether this checkbox or switch is checked, for assistive technologies.
+    fn accessible_toggled(mut self, toggled: bool) -> Self {
+        self.interactivity().accessible_toggled = Some(toggled);
+        self
+    }
+
     /// Track the focus state of the given focus handle on this element.
     /// If the focus handle is focused by the application, this element will
     /// apply its focused styles.
@@ -1079,6 +1100,26 @@ pub trait FocusableElement: InteractiveElement {
         self.interactivity().in_focus_style = Some(Box::new(f(StyleRefinement::default())));
         self
     }
+
+    /// Let keyboard navigation focus this element, see [`Window::focus_next`] and
+    /// [`Window::focus_in_direction`]. Tab order goes through elements by increasing index,
+    /// and in the order they're painted for equal indices.
+    fn tab_index(mut self, index: isize) -> Self
+    where
+        Self: Sized,
+    {
+        self.interactivity().tab_index = Some(index);
+        self
+    }
+
+    /// Let keyboard navigation focus this element, in the order elements are painted. Same as
+    /// a tab index of 0.
+    fn tab_stop(self) -> Self
+    where
+        Self: Sized,
+    {
+        self.tab_index(0)
+    }
 }
 
 pub(crate) type MouseDownListener =
@@ -1339,6 +1380,10 @@ pub struct Interactivity {
     pub(crate) key_context: Option<KeyContext>,
     pub(crate) focusable: bool,
     pub(crate) tracked_focus_handle: Option<FocusHandle>,
+    pub(crate) tab_index: Option<isize>,
+    pub(crate) accessible_role: Option<AccessibleRole>,
+    pub(crate) accessible_label: Option<SharedString>,
+    pub(crate) accessible_toggled: Option<bool>,
     pub(crate) tracked_scroll_handle: Option<ScrollHandle>,
     pub(crate) scroll_anchor: Option<ScrollAnchor>,
     pub(crate) scroll_offset: Option<Rc<RefCell<Point<Pixels>>>>,
@@ -1457,48 +1502,61 @@ impl Interactivity {
         self.content_size = content_size;
         if let Some(focus_handle) = self.tracked_focus_handle.as_ref() {
             window.set_focus_handle(focus_handle, cx);
+            if let Some(tab_index) = self.tab_index {
+                window.insert_focus_stop(focus_handle, tab_index, bounds);
+            }
         }
-        window.with_optional_element_state::<InteractiveElementState, _>(
-            global_id,
-            |element_state, window| {
-                let mut element_state =
-                    element_state.map(|element_state| element_state.unwrap_or_default());
-                let style = self.compute_style_internal(None, element_state.as_mut(), window, cx);
+        let accessible_node = self.accessible_role.map(|role| AccessibleNode {
+            label: self.accessible_label.clone(),
+            toggled: self.accessible_toggled,
+            focus_id: self.tracked_focus_handle.as_ref().map(|handle| handle.id),
+            clickable: !self.click_listeners.is_empty(),
+            ..AccessibleNode::new(role, bounds)
+        });
+        window.with_accessible_node(global_id, accessible_node, |window| {
+            window.with_optional_element_state::<InteractiveElementState, _>(
+                global_id,
+                |element_state, window| {
+                    let mut element_state =
+                        element_state.map(|element_state| element_state.unwrap_or_default());
+                    let style =
+                        self.compute_style_internal(None, element_state.as_mut(), window, cx);
 
-                if let Some(element_state) = element_state.as_mut() {
-                    if let Some(clicked_state) = element_state.clicked_state.as_ref() {
-                        let clicked_state = clicked_state.borrow();
-                        self.active = Some(clicked_state.element);
-                    }
-                    if let Some(active_tooltip) = element_state.active_tooltip.as_ref() {
-                        if self.tooltip_builder.is_some() {
-                            self.tooltip_id = set_tooltip_on_window(active_tooltip, window);
-                        } else {
-                            // If there is no longer a tooltip builder, remove the active tooltip.
-                            element_state.active_tooltip.take();
+                    if let Some(element_state) = element_state.as_mut() {
+                        if let Some(clicked_state) = element_state.clicked_state.as_ref() {
+                            let clicked_state = clicked_state.borrow();
+                            self.active = Some(clicked_state.element);
+                        }
+                        if let Some(active_tooltip) = element_state.active_tooltip.as_ref() {
+                            if self.tooltip_builder.is_some() {
+                                self.tooltip_id = set_tooltip_on_window(active_tooltip, window);
+                            } else {
+                                // If there is no longer a tooltip builder, remove the active tooltip.
+                                element_state.active_tooltip.take();
+                            }
                         }
                     }
-                }
 
-                window.with_text_style(style.text_style().cloned(), |window| {
-                    window.with_content_mask(
-                        style.overflow_mask(bounds, window.rem_size()),
-                        |window| {
-                            let hitbox = if self.should_insert_hitbox(&style) {
-                                Some(window.insert_hitbox(bounds, self.occlude_mouse))
-                            } else {
-                                None
-                            };
+                    window.with_text_style(style.text_style().cloned(), |window| {
+                        window.with_content_mask(
+                            style.overflow_mask(bounds, window.rem_size()),
+                            |window| {
+                                let hitbox = if self.should_insert_hitbox(&style) {
+                                    Some(window.insert_hitbox(bounds, self.occlude_mouse))
+                                } else {
+                                    None
+                                };
 
-                            let scroll_offset =
-                                self.clamp_scroll_position(bounds, &style, window, cx);
-                            let result = f(&style, scroll_offset, hitbox, window, cx);
-                            (result, element_state)
-                        },
-                    )
-                })
-            },
-        )
+                                let scroll_offset =
+                                    self.clamp_scroll_position(bounds, &style, window, cx);
+                                let result = f(&style, scroll_offset, hitbox, window, cx);
+                                (result, element_state)
+                            },
+                        )
+                    })
+                },
+            )
+        })
     }
 
     fn should_insert_hitbox(&self, style: &Style) -> bool {
@@ -1604,46 +1662,48 @@ impl Interactivity {
                 }
 
                 window.with_element_opacity(style.opacity, |window| {
-                    style.paint(bounds, window, cx, |window: &mut Window, cx: &mut App| {
-                        window.with_text_style(style.text_style().cloned(), |window| {
-                            window.with_content_mask(
-                                style.overflow_mask(bounds, window.rem_size()),
-                                |window| {
-                                    if let Some(hitbox) = hitbox {
-                                        #[cfg(debug_assertions)]
-                                        self.paint_debug_info(
-                                            global_id, hitbox, &style, window, cx,
-                                        );
-
-                                        if !cx.has_active_drag() {
-                                            if let Some(mouse_cursor) = style.mouse_cursor {
-                                                window.set_cursor_style(mouse_cursor, hitbox);
+                    window.with_blend_mode(style.blend_mode, |window| {
+                        style.paint(bounds, window, cx, |window: &mut Window, cx: &mut App| {
+                            window.with_text_style(style.text_style().cloned(), |window| {
+                                window.with_content_mask(
+                                    style.overflow_mask(bounds, window.rem_size()),
+                                    |window| {
+                                        if let Some(hitbox) = hitbox {
+                                            #[cfg(debug_assertions)]
+                                            self.paint_debug_info(
+                                                global_id, hitbox, &style, window, cx,
+                                            );
+
+                                            if !cx.has_active_drag() {
+                                                if let Some(mouse_cursor) = style.mouse_cursor {
+                                                    window.set_cursor_style(mouse_cursor, hitbox);
+                                                }
                                             }
-                                        }
 
-                                        if let Some(group) = self.group.clone() {
-                                            GroupHitboxes::push(group, hitbox.id, cx);
-                                        }
+                                            if let Some(group) = self.group.clone() {
+                                                GroupHitboxes::push(group, hitbox.id, cx);
+                                            }
 
-                                        self.paint_mouse_listeners(
-                                            hitbox,
-                                            element_state.as_mut(),
-                                            window,
-                                            cx,
-                                        );
-                                        self.paint_scroll_listener(hitbox, &style, window, cx);
-                                    }
+                                            self.paint_mouse_listeners(
+                                                hitbox,
+                                                element_state.as_mut(),
+                                                window,
+                                                cx,
+                                            );
+                                            self.paint_scroll_listener(hitbox, &style, window, cx);
+                                        }
 
-                                    self.paint_keyboard_listeners(window, cx);
-                                    f(&style, window, cx);
+                                        self.paint_keyboard_listeners(window, cx);
+                                        f(&style, window, cx);
 
-                                    if hitbox.is_some() {
-                                        if let Some(group) = self.group.as_ref() {
-                                            GroupHitboxes::pop(group, cx);
+                                        if hitbox.is_some() {
+                                            if let Some(group) = self.group.as_ref() {
+                                                GroupHitboxes::pop(group, cx);
+                                            }
                                         }
-                                    }
-                                },
-                            );
+                                    },
+                                );
+                            });
                         });
                     });
                 });
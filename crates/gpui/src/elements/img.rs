@@ -130,6 +130,7 @@ pub struct ImageStyle {
     object_fit: ObjectFit,
     loading: Option<Box<dyn Fn() -> AnyElement>>,
     fallback: Option<Box<dyn Fn() -> AnyElement>>,
+    keep_previous: bool,
 }
 
 impl Default for ImageStyle {
@@ -139,6 +140,7 @@ impl Default for ImageStyle {
             object_fit: ObjectFit::Contain,
             loading: None,
             fallback: None,
+            keep_previous: false,
         }
     }
 }
@@ -171,6 +173,13 @@ pub trait StyledImage: Sized {
         self.image_style().loading = Some(Box::new(loading));
         self
     }
+
+    /// Keep displaying the last image that finished loading while a new source is decoded,
+    /// instead of the loading element. Requires the element to have an id.
+    fn keep_previous_while_loading(mut self, keep_previous: bool) -> Self {
+        self.image_style().keep_previous = keep_previous;
+        self
+    }
 }
 
 impl StyledImage for Img {
@@ -231,12 +240,14 @@ struct ImgState {
     frame_index: usize,
     last_frame_time: Option<Instant>,
     started_loading: Option<(Instant, Task<()>)>,
+    previous: Option<Arc<RenderImage>>,
 }
 
 /// The image layout state between frames
 pub struct ImgLayoutState {
     frame_index: usize,
     replacement: Option<AnyElement>,
+    previous: Option<Arc<RenderImage>>,
 }
 
 impl Element for Img {
@@ -256,6 +267,7 @@ impl Element for Img {
         let mut layout_state = ImgLayoutState {
             frame_index: 0,
             replacement: None,
+            previous: None,
         };
 
         window.with_optional_element_state(global_id, |state, window| {
@@ -264,6 +276,7 @@ impl Element for Img {
                     frame_index: 0,
                     last_frame_time: None,
                     started_loading: None,
+                    previous: None,
                 })
             });
 
@@ -281,7 +294,7 @@ impl Element for Img {
                             if let Some(state) = &mut state {
                                 let frame_count = data.frame_count();
                                 if frame_count > 1 {
-                                    let current_time = Instant::now();
+                                    let current_time = window.animation_time();
                                     if let Some(last_frame_time) = state.last_frame_time {
                                         let elapsed = current_time - last_frame_time;
                                         let frame_duration =
@@ -298,6 +311,9 @@ impl Element for Img {
                                     }
                                 }
                                 state.started_loading = None;
+                                if self.style.keep_previous {
+                                    state.previous = Some(data.clone());
+                                }
                             }
 
                             let image_size = data.size(frame_index);
@@ -342,11 +358,18 @@ impl Element for Img {
                             }
                             if let Some(state) = &mut state {
                                 state.started_loading = None;
+                                state.previous = None;
                             }
                         }
                         None => {
                             if let Some(state) = &mut state {
-                                if let Some((started_loading, _)) = state.started_loading {
+                                if let Some(previous) = state.previous.clone() {
+                                    let image_size = previous.size(0);
+                                    style.aspect_ratio = Some(
+                                        image_size.width.0 as f32 / image_size.height.0 as f32,
+                                    );
+                                    layout_state.previous = Some(previous);
+                                } else if let Some((started_loading, _)) = state.started_loading {
                                     if started_loading.elapsed() > LOADING_DELAY {
                                         if let Some(loading) = self.style.loading.as_ref() {
                                             let mut element = loading();
@@ -437,6 +460,11 @@ impl Element for Img {
                             self.style.grayscale,
                         )
                         .log_err();
+                } else if let Some(previous) = layout_state.previous.take() {
+                    let new_bounds = self.style.object_fit.get_bounds(bounds, previous.size(0));
+                    window
+                        .paint_image(new_bounds, corner_radii, previous, 0, self.style.grayscale)
+                        .log_err();
                 } else if let Some(replacement) = &mut layout_state.replacement {
                     replacement.paint(window, cx);
                 }
@@ -482,6 +510,24 @@ impl ImageSource {
             ImageSource::Image(data) => window.use_asset::<AssetLogger<ImageDecoder>>(data, cx),
         }
     }
+
+    /// Discards the decoded image of this source, so that it is loaded and decoded again on
+    /// the background executor the next time it is rendered. Elements that keep the previous
+    /// image while loading continue displaying the old one until then.
+    pub fn invalidate(&self, cx: &mut App) {
+        let image = match self {
+            ImageSource::Resource(resource) => cx.remove_asset::<ImgResourceLoader>(resource),
+            ImageSource::Image(data) => cx.remove_asset::<AssetLogger<ImageDecoder>>(data),
+            ImageSource::Render(data) => Some(Ok(data.clone())),
+            ImageSource::Custom(_) => None,
+        };
+        if let Some(Ok(image)) = image {
+            for window in cx.windows.values_mut().flatten() {
+                window.drop_image(image.clone()).log_err();
+            }
+        }
+        cx.refresh_windows();
+    }
 }
 
 #[derive(Clone)]
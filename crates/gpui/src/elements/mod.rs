@@ -1,11 +1,14 @@
 mod anchored;
 mod animation;
 mod canvas;
+mod clip_path;
 mod common;
 mod deferred;
 mod div;
 mod img;
 mod list;
+mod shader;
+mod sparkline;
 mod surface;
 mod svg;
 mod text;
@@ -14,11 +17,14 @@ mod uniform_list;
 pub use anchored::*;
 pub use animation::*;
 pub use canvas::*;
+pub use clip_path::*;
 pub use common::*;
 pub use deferred::*;
 pub use div::*;
 pub use img::*;
 pub use list::*;
+pub use shader::*;
+pub use sparkline::*;
 pub use surface::*;
 pub use svg::*;
 pub use text::*;
@@ -0,0 +1,146 @@
+use crate::{
+    hash, App, Bounds, Element, ElementId, GlobalElementId, IntoElement, LayoutId, Pixels,
+    SharedString, Style, StyleRefinement, Styled, Window,
+};
+use refineable::Refineable;
+use std::time::Instant;
+
+/// A WGSL fragment shader that can be painted with a [`shader`] element.
+///
+/// The source must define a function with the following signature, which is invoked for every
+/// pixel covered by the element:
+///
+/// ```wgsl
+/// fn shade(input: ShaderInput) -> vec4<f32>
+/// ```
+///
+/// `ShaderInput` is provided by GPUI and has the following fields, all in device pixels:
+///
+/// - `position: vec2<f32>`, the center of the pixel relative to the element's origin.
+/// - `size: vec2<f32>`, the size of the element.
+/// - `mouse: vec2<f32>`, the mouse position relative to the element's origin.
+/// - `time: f32`, the seconds elapsed since the shader was created.
+///
+/// The returned color is linear RGBA with straight alpha.
+///
+/// Custom shaders are only supported by the Blade renderer, other renderers skip them.
+#[derive(Clone, Debug)]
+pub struct CustomShader {
+    pub(crate) id: u64,
+    source: SharedString,
+    created_at: Instant,
+}
+
+impl CustomShader {
+    /// Creates a shader from WGSL source. Keep the shader around across frames, its `time`
+    /// uniform starts counting when it is created.
+    pub fn new(source: impl Into<SharedString>) -> Self {
+        let source = source.into();
+        Self {
+            id: hash(&source),
+            source,
+            created_at: Instant::now(),
+        }
+    }
+
+    /// Returns the WGSL source of this shader.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns the seconds elapsed since this shader was created.
+    pub fn time(&self) -> f32 {
+        self.created_at.elapsed().as_secs_f32()
+    }
+}
+
+impl PartialEq for CustomShader {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+/// An element that fills its bounds with a [`CustomShader`].
+pub struct Shader {
+    shader: CustomShader,
+    animated: bool,
+    style: StyleRefinement,
+}
+
+/// Create a new shader element.
+pub fn shader(shader: CustomShader) -> Shader {
+    Shader {
+        shader,
+        animated: true,
+        style: Default::default(),
+    }
+}
+
+impl Shader {
+    /// Set whether the element repaints on every frame, so that the shader sees the current
+    /// time and mouse position. Defaults to true.
+    pub fn animated(mut self, animated: bool) -> Self {
+        self.animated = animated;
+        self
+    }
+}
+
+impl Element for Shader {
+    type RequestLayoutState = ();
+    type PrepaintState = ();
+
+    fn id(&self) -> Option<ElementId> {
+        None
+    }
+
+    fn request_layout(
+        &mut self,
+        _global_id: Option<&GlobalElementId>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> (LayoutId, Self::RequestLayoutState) {
+        let mut style = Style::default();
+        style.refine(&self.style);
+        let layout_id = window.request_layout(style, [], cx);
+        (layout_id, ())
+    }
+
+    fn prepaint(
+        &mut self,
+        _global_id: Option<&GlobalElementId>,
+        _bounds: Bounds<Pixels>,
+        _request_layout: &mut Self::RequestLayoutState,
+        _window: &mut Window,
+        _cx: &mut App,
+    ) -> Self::PrepaintState {
+    }
+
+    fn paint(
+        &mut self,
+        _global_id: Option<&GlobalElementId>,
+        bounds: Bounds<Pixels>,
+        _: &mut Self::RequestLayoutState,
+        _: &mut Self::PrepaintState,
+        window: &mut Window,
+        _: &mut App,
+    ) {
+        window.paint_shader(bounds, self.shader.clone());
+        if self.animated {
+            window.request_animation_frame();
+        }
+    }
+}
+
+impl IntoElement for Shader {
+    type Element = Self;
+
+    fn into_element(self) -> Self::Element {
+        self
+    }
+}
+
+impl Styled for Shader {
+    fn style(&mut self) -> &mut StyleRefinement {
+        &mut self.style
+    }
+}
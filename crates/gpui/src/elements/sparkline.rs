@@ -0,0 +1,255 @@
+use std::{collections::VecDeque, sync::Arc};
+
+use refineable::Refineable as _;
+
+use crate::{
+    point, px, App, Background, Bounds, Element, ElementId, GlobalElementId, Hsla, IntoElement,
+    LayoutId, PathBuilder, Pixels, Point, Style, StyleRefinement, Styled, Window,
+};
+
+/// A fixed number of samples of a time series, like the CPU usage over the last minute. Pushing
+/// a sample once the series is full drops the oldest one.
+///
+/// Cloning a series is cheap, its samples are only copied when a clone is modified.
+#[derive(Clone, Debug)]
+pub struct TimeSeries {
+    samples: Arc<VecDeque<f32>>,
+    capacity: usize,
+}
+
+impl TimeSeries {
+    /// Creates an empty series holding up to `capacity` samples.
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            samples: Arc::new(VecDeque::with_capacity(capacity)),
+            capacity,
+        }
+    }
+
+    /// Returns the number of samples the series holds once it's full.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of samples in the series.
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Returns whether the series has no samples.
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Appends a sample, dropping the oldest one if the series is full.
+    pub fn push(&mut self, sample: f32) {
+        let samples = Arc::make_mut(&mut self.samples);
+        if samples.len() == self.capacity {
+            samples.pop_front();
+        }
+        samples.push_back(sample);
+    }
+
+    /// Removes all samples.
+    pub fn clear(&mut self) {
+        Arc::make_mut(&mut self.samples).clear();
+    }
+
+    /// Returns the samples, from the oldest to the newest.
+    pub fn samples(&self) -> impl DoubleEndedIterator<Item = f32> + ExactSizeIterator + '_ {
+        self.samples.iter().copied()
+    }
+
+    /// Returns the most recent sample.
+    pub fn last(&self) -> Option<f32> {
+        self.samples.back().copied()
+    }
+
+    /// Returns the smallest and largest finite samples.
+    pub fn range(&self) -> Option<(f32, f32)> {
+        self.samples()
+            .filter(|sample| sample.is_finite())
+            .fold(None, |range, sample| match range {
+                Some((min, max)) => Some((sample.min(min), sample.max(max))),
+                None => Some((sample, sample)),
+            })
+    }
+}
+
+impl Extend<f32> for TimeSeries {
+    fn extend<T: IntoIterator<Item = f32>>(&mut self, samples: T) {
+        for sample in samples {
+            self.push(sample);
+        }
+    }
+}
+
+/// Create a sparkline element plotting the given series.
+///
+/// The whole series is drawn as a single path rather than an element per sample, so graphs with
+/// hundreds of samples stay cheap to redraw as samples come in.
+pub fn sparkline(series: TimeSeries) -> Sparkline {
+    Sparkline {
+        series,
+        range: None,
+        line_color: None,
+        line_width: px(1.),
+        fill: None,
+        style: StyleRefinement::default(),
+    }
+}
+
+/// An element plotting a [`TimeSeries`] as a line, optionally filled below.
+///
+/// The newest sample is drawn at the right edge, and samples are spread so that a full series
+/// spans the whole width of the element.
+pub struct Sparkline {
+    series: TimeSeries,
+    range: Option<(f32, f32)>,
+    line_color: Option<Hsla>,
+    line_width: Pixels,
+    fill: Option<Background>,
+    style: StyleRefinement,
+}
+
+impl Sparkline {
+    /// Set the values at the bottom and top of the element. By default the range of the
+    /// samples is used, which exaggerates small variations.
+    pub fn range(mut self, min: f32, max: f32) -> Self {
+        self.range = Some((min, max));
+        self
+    }
+
+    /// Set the color of the line. No line is drawn by default.
+    pub fn line_color(mut self, color: impl Into<Hsla>) -> Self {
+        self.line_color = Some(color.into());
+        self
+    }
+
+    /// Set the width of the line. Defaults to 1 pixel.
+    pub fn line_width(mut self, width: Pixels) -> Self {
+        self.line_width = width;
+        self
+    }
+
+    /// Set the background of the area below the line. Nothing is filled by default.
+    pub fn fill(mut self, fill: impl Into<Background>) -> Self {
+        self.fill = Some(fill.into());
+        self
+    }
+
+    /// Returns the position of every sample within the given bounds.
+    fn points(&self, bounds: Bounds<Pixels>) -> Vec<Point<Pixels>> {
+        let Some((min, max)) = self.range.or_else(|| self.series.range()) else {
+            return Vec::new();
+        };
+        let span = if max > min { max - min } else { 1. };
+        let step = if self.series.capacity > 1 {
+            bounds.size.width / (self.series.capacity - 1) as f32
+        } else {
+            px(0.)
+        };
+        let count = self.series.len();
+        self.series
+            .samples()
+            .enumerate()
+            .filter(|(_, sample)| sample.is_finite())
+            .map(|(index, sample)| {
+                let fraction = ((sample - min) / span).clamp(0., 1.);
+                point(
+                    bounds.right() - step * (count - 1 - index) as f32,
+                    bounds.bottom() - bounds.size.height * fraction,
+                )
+            })
+            .collect()
+    }
+}
+
+impl Element for Sparkline {
+    type RequestLayoutState = Style;
+    type PrepaintState = Vec<Point<Pixels>>;
+
+    fn id(&self) -> Option<ElementId> {
+        None
+    }
+
+    fn request_layout(
+        &mut self,
+        _global_id: Option<&GlobalElementId>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> (LayoutId, Self::RequestLayoutState) {
+        let mut style = Style::default();
+        style.refine(&self.style);
+        let layout_id = window.request_layout(style.clone(), [], cx);
+        (layout_id, style)
+    }
+
+    fn prepaint(
+        &mut self,
+        _global_id: Option<&GlobalElementId>,
+        bounds: Bounds<Pixels>,
+        _request_layout: &mut Self::RequestLayoutState,
+        _window: &mut Window,
+        _cx: &mut App,
+    ) -> Self::PrepaintState {
+        self.points(bounds)
+    }
+
+    fn paint(
+        &mut self,
+        _global_id: Option<&GlobalElementId>,
+        bounds: Bounds<Pixels>,
+        style: &mut Self::RequestLayoutState,
+        points: &mut Self::PrepaintState,
+        window: &mut Window,
+        cx: &mut App,
+    ) {
+        style.paint(bounds, window, cx, |window, _| {
+            let (Some(first), Some(last)) = (points.first(), points.last()) else {
+                return;
+            };
+
+            if let Some(fill) = self.fill {
+                let mut builder = PathBuilder::fill();
+                builder.move_to(point(first.x, bounds.bottom()));
+                for point in points.iter() {
+                    builder.line_to(*point);
+                }
+                builder.line_to(point(last.x, bounds.bottom()));
+                builder.close();
+                match builder.build() {
+                    Ok(path) => window.paint_path(path, fill),
+                    Err(error) => log::error!("failed to build sparkline fill: {error}"),
+                }
+            }
+
+            if let Some(line_color) = self.line_color.filter(|_| points.len() > 1) {
+                let mut builder = PathBuilder::stroke(self.line_width);
+                builder.move_to(*first);
+                for point in &points[1..] {
+                    builder.line_to(*point);
+                }
+                match builder.build() {
+                    Ok(path) => window.paint_path(path, line_color),
+                    Err(error) => log::error!("failed to build sparkline line: {error}"),
+                }
+            }
+        });
+    }
+}
+
+impl IntoElement for Sparkline {
+    type Element = Self;
+
+    fn into_element(self) -> Self::Element {
+        self
+    }
+}
+
+impl Styled for Sparkline {
+    fn style(&mut self) -> &mut StyleRefinement {
+        &mut self.style
+    }
+}
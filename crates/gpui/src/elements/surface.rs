@@ -5,6 +5,10 @@ use crate::{
 #[cfg(target_os = "macos")]
 use media::core_video::CVImageBuffer;
 use refineable::Refineable;
+#[cfg(all(target_os = "linux", feature = "wayland"))]
+use std::sync::Arc;
+#[cfg(all(target_os = "linux", feature = "wayland"))]
+use util::ResultExt as _;
 
 /// A source of a surface's content.
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -12,6 +16,12 @@ pub enum SurfaceSource {
     /// A macOS image buffer from CoreVideo
     #[cfg(target_os = "macos")]
     Surface(CVImageBuffer),
+    /// A Linux DMA-BUF, copied into the sprite atlas
+    #[cfg(all(target_os = "linux", feature = "wayland"))]
+    Dmabuf(Arc<crate::Dmabuf>),
+    /// A Wayland surface drawn by an external producer
+    #[cfg(all(target_os = "linux", feature = "wayland"))]
+    Foreign(crate::ForeignSurface),
 }
 
 #[cfg(target_os = "macos")]
@@ -21,6 +31,20 @@ impl From<CVImageBuffer> for SurfaceSource {
     }
 }
 
+#[cfg(all(target_os = "linux", feature = "wayland"))]
+impl From<Arc<crate::Dmabuf>> for SurfaceSource {
+    fn from(value: Arc<crate::Dmabuf>) -> Self {
+        SurfaceSource::Dmabuf(value)
+    }
+}
+
+#[cfg(all(target_os = "linux", feature = "wayland"))]
+impl From<crate::ForeignSurface> for SurfaceSource {
+    fn from(value: crate::ForeignSurface) -> Self {
+        SurfaceSource::Foreign(value)
+    }
+}
+
 /// A surface element.
 pub struct Surface {
     source: SurfaceSource,
@@ -78,10 +102,18 @@ impl Element for Surface {
     fn paint(
         &mut self,
         _global_id: Option<&GlobalElementId>,
-        #[cfg_attr(not(target_os = "macos"), allow(unused_variables))] bounds: Bounds<Pixels>,
+        #[cfg_attr(
+            not(any(target_os = "macos", all(target_os = "linux", feature = "wayland"))),
+            allow(unused_variables)
+        )]
+        bounds: Bounds<Pixels>,
         _: &mut Self::RequestLayoutState,
         _: &mut Self::PrepaintState,
-        #[cfg_attr(not(target_os = "macos"), allow(unused_variables))] window: &mut Window,
+        #[cfg_attr(
+            not(any(target_os = "macos", all(target_os = "linux", feature = "wayland"))),
+            allow(unused_variables)
+        )]
+        window: &mut Window,
         _: &mut App,
     ) {
         match &self.source {
@@ -92,6 +124,19 @@ impl Element for Surface {
                 // TODO: Add support for corner_radii
                 window.paint_surface(new_bounds, surface.clone());
             }
+            #[cfg(all(target_os = "linux", feature = "wayland"))]
+            SurfaceSource::Dmabuf(dmabuf) => {
+                let size = crate::size(
+                    crate::DevicePixels(dmabuf.width as i32),
+                    crate::DevicePixels(dmabuf.height as i32),
+                );
+                let new_bounds = self.object_fit.get_bounds(bounds, size);
+                window.paint_dmabuf(new_bounds, dmabuf.clone()).log_err();
+            }
+            #[cfg(all(target_os = "linux", feature = "wayland"))]
+            SurfaceSource::Foreign(surface) => {
+                window.paint_foreign_surface(bounds, surface.clone());
+            }
             #[allow(unreachable_patterns)]
             _ => {}
         }
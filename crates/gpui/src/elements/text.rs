@@ -2,8 +2,7 @@ use crate::{
     register_tooltip_mouse_handlers, set_tooltip_on_window, ActiveTooltip, AnyView, App, Bounds,
     DispatchPhase, Element, ElementId, GlobalElementId, HighlightStyle, Hitbox, IntoElement,
     LayoutId, MouseDownEvent, MouseMoveEvent, MouseUpEvent, Pixels, Point, SharedString, Size,
-    TextOverflow, TextRun, TextStyle, TooltipId, WhiteSpace, Window, WrappedLine,
-    WrappedLineLayout,
+    TextRun, TextStyle, TooltipId, WhiteSpace, Window, WrappedLine, WrappedLineLayout,
 };
 use anyhow::anyhow;
 use parking_lot::{Mutex, MutexGuard};
@@ -324,22 +323,17 @@ impl TextLayout {
                     None
                 };
 
-                let (truncate_width, ellipsis) =
-                    if let Some(text_overflow) = text_style.text_overflow {
-                        let width = known_dimensions.width.or(match available_space.width {
-                            crate::AvailableSpace::Definite(x) => match text_style.line_clamp {
-                                Some(max_lines) => Some(x * max_lines),
-                                None => Some(x),
-                            },
-                            _ => None,
-                        });
-
-                        match text_overflow {
-                            TextOverflow::Ellipsis(s) => (width, Some(s)),
-                        }
-                    } else {
-                        (None, None)
-                    };
+                let truncate_width = if text_style.text_overflow.is_some() {
+                    known_dimensions.width.or(match available_space.width {
+                        crate::AvailableSpace::Definite(x) => match text_style.line_clamp {
+                            Some(max_lines) => Some(x * max_lines),
+                            None => Some(x),
+                        },
+                        _ => None,
+                    })
+                } else {
+                    None
+                };
 
                 if let Some(text_layout) = element_state.0.lock().as_ref() {
                     if text_layout.size.is_some()
@@ -350,10 +344,15 @@ impl TextLayout {
                 }
 
                 let mut line_wrapper = cx.text_system().line_wrapper(text_style.font(), font_size);
-                let text = if let Some(truncate_width) = truncate_width {
-                    line_wrapper.truncate_line(text.clone(), truncate_width, ellipsis, &mut runs)
-                } else {
-                    text.clone()
+                let text = match (truncate_width, text_style.text_overflow) {
+                    (Some(truncate_width), Some(text_overflow)) => line_wrapper
+                        .truncate_line_with_overflow(
+                            text.clone(),
+                            truncate_width,
+                            text_overflow,
+                            &mut runs,
+                        ),
+                    _ => text.clone(),
                 };
 
                 let Some(lines) = window
@@ -96,6 +96,7 @@ mod taffy;
 #[cfg(any(test, feature = "test-support"))]
 pub mod test;
 mod text_system;
+mod theme_variables;
 mod util;
 mod view;
 mod window;
@@ -151,9 +152,13 @@ pub use taffy::{AvailableSpace, LayoutId};
 #[cfg(any(test, feature = "test-support"))]
 pub use test::*;
 pub use text_system::*;
+pub use theme_variables::*;
 pub use util::arc_cow::ArcCow;
 pub use view::*;
 pub use window::*;
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+#[cfg(any(feature = "wayland", feature = "x11"))]
+pub use zbus;
 
 use std::{any::Any, borrow::BorrowMut, future::Future};
 use taffy::TaffyLayoutEngine;
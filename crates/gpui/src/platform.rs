@@ -2,7 +2,13 @@
 #![cfg_attr(windows, allow(dead_code))]
 
 mod app_menu;
+mod deferred_window;
+mod desktop_workspaces;
+mod display_gamma;
 mod keystroke;
+mod layer_shell;
+mod output_management;
+mod virtual_input;
 
 #[cfg(any(target_os = "linux", target_os = "freebsd"))]
 mod linux;
@@ -23,15 +29,23 @@ mod blade;
 #[cfg(any(test, feature = "test-support"))]
 mod test;
 
+#[cfg(any(
+    test,
+    feature = "test-support",
+    all(any(target_os = "linux", target_os = "freebsd"), feature = "wayland")
+))]
+mod compositor;
+
 #[cfg(target_os = "windows")]
 mod windows;
 
 use crate::{
-    point, Action, AnyWindowHandle, App, AsyncWindowContext, BackgroundExecutor, Bounds,
-    DevicePixels, DispatchEventResult, Font, FontId, FontMetrics, FontRun, ForegroundExecutor,
-    GlyphId, GpuSpecs, ImageSource, Keymap, LineLayout, Pixels, PlatformInput, Point,
-    RenderGlyphParams, RenderImage, RenderImageParams, RenderSvgParams, ScaledPixels, Scene,
-    SharedString, Size, SvgRenderer, SvgSize, Task, TaskLabel, Window, DEFAULT_WINDOW_SIZE,
+    point, AccessibilityRequest, AccessibilityTree, Action, AnyWindowHandle, App,
+    AsyncWindowContext, Axis, BackgroundExecutor, Bounds, Corner, DevicePixels,
+    DispatchEventResult, Font, FontId, FontMetrics, FontRun, ForegroundExecutor, GlyphId, GpuSpecs,
+    ImageSource, Keymap, LineLayout, Pixels, PlatformInput, Point, RenderGlyphParams, RenderImage,
+    RenderImageParams, RenderSvgParams, Rgba, ScaledPixels, Scene, SharedString, Size, SvgRenderer,
+    SvgSize, Task, TaskLabel, Window, DEFAULT_WINDOW_SIZE,
 };
 use anyhow::{anyhow, Result};
 use async_task::Runnable;
@@ -59,7 +73,21 @@ use strum::EnumIter;
 use uuid::Uuid;
 
 pub use app_menu::*;
+pub use desktop_workspaces::*;
+pub use display_gamma::*;
 pub use keystroke::*;
+pub use layer_shell::*;
+pub use output_management::*;
+pub use virtual_input::*;
+
+pub(crate) use deferred_window::*;
+
+#[cfg(any(
+    test,
+    feature = "test-support",
+    all(any(target_os = "linux", target_os = "freebsd"), feature = "wayland")
+))]
+pub(crate) use compositor::*;
 
 #[cfg(any(target_os = "linux", target_os = "freebsd"))]
 pub(crate) use linux::*;
@@ -72,11 +100,64 @@ pub(crate) use test::*;
 pub(crate) use windows::*;
 
 #[cfg(any(test, feature = "test-support"))]
-pub use test::TestScreenCaptureSource;
+pub use test::{SimulatedConfigure, TestScreenCaptureSource};
 
 #[cfg(target_os = "linux")]
 #[cfg(feature = "wayland")]
-pub use linux::window::{Anchor, KeyboardInteractivity, Layer, LayerShellSettings};
+pub use linux::dmabuf_feedback::{DmabufFeedback, DmabufFormat, DmabufTranche};
+#[cfg(target_os = "linux")]
+#[cfg(feature = "wayland")]
+pub use linux::dmabuf::{Dmabuf, DmabufPlane};
+#[cfg(target_os = "linux")]
+#[cfg(feature = "wayland")]
+pub use linux::subsurface::ForeignSurface;
+
+#[cfg(target_os = "linux")]
+#[cfg(feature = "wayland")]
+pub use linux::notification_stack::{NotificationStack, NotificationStackSettings};
+
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+#[cfg(any(feature = "wayland", feature = "x11"))]
+pub use linux::global_shortcuts::{
+    BoundGlobalShortcut, GlobalShortcut, GlobalShortcutEvent, GlobalShortcuts,
+};
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+#[cfg(any(feature = "wayland", feature = "x11"))]
+pub use linux::{session_bus, system_bus};
+
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+#[cfg(any(feature = "wayland", feature = "x11"))]
+pub use linux::mpris::{
+    LoopStatus, MediaPlayer, MediaPlayerEvent, MediaPlayers, PlaybackStatus, TrackMetadata,
+};
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+#[cfg(any(feature = "wayland", feature = "x11"))]
+pub use linux::notifications::{
+    Notification, NotificationAction, NotificationCloseReason, NotificationImage,
+    NotificationServer, NotificationServerEvent, NotificationServerOptions, NotificationUrgency,
+};
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+#[cfg(any(feature = "wayland", feature = "x11"))]
+#[cfg(feature = "screencast")]
+pub use linux::screencast::{Screencast, ScreencastOptions};
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+#[cfg(any(feature = "wayland", feature = "x11"))]
+pub use linux::status_notifier_host::{
+    TrayHost, TrayItem, TrayItemCategory, TrayItemStatus, TrayScrollOrientation, TrayTooltip,
+};
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+#[cfg(any(feature = "wayland", feature = "x11"))]
+pub use linux::status_notifier_item::{TrayIcon, TrayIconEvent, TrayIconOptions};
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+#[cfg(any(feature = "wayland", feature = "x11"))]
+pub use linux::timedate::TimeChange;
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+#[cfg(any(feature = "wayland", feature = "x11"))]
+pub use linux::upower::{ChargeState, PowerState, PowerSupply};
+
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+#[cfg(any(feature = "wayland", feature = "x11"))]
+pub use linux::dbusmenu::{ActivateDBusMenuItem, DBusMenu, DBusMenuItem, DBusMenuToggle};
 
 #[cfg(target_os = "macos")]
 pub(crate) fn current_platform(headless: bool) -> Rc<dyn Platform> {
@@ -91,7 +172,15 @@ pub(crate) fn current_platform(headless: bool) -> Rc<dyn Platform> {
 
     match guess_compositor() {
         #[cfg(feature = "wayland")]
-        "Wayland" => Rc::new(WaylandClient::new()),
+        "Wayland" => match WaylandClient::new() {
+            Ok(client) => Rc::new(client),
+            #[cfg(feature = "x11")]
+            Err(error) if std::env::var_os("DISPLAY").is_some() => {
+                log::error!("{error:#}, falling back to X11");
+                Rc::new(X11Client::new())
+            }
+            Err(error) => panic!("Failed to start the Wayland client: {error:#}"),
+        },
 
         #[cfg(feature = "x11")]
         "X11" => Rc::new(X11Client::new()),
@@ -152,6 +241,51 @@ pub(crate) trait Platform: 'static {
 
     fn displays(&self) -> Vec<Rc<dyn PlatformDisplay>>;
     fn primary_display(&self) -> Option<Rc<dyn PlatformDisplay>>;
+    /// Returns whether the display is turned on, if its power mode is known.
+    fn is_display_powered(&self, _display: DisplayId) -> Option<bool> {
+        None
+    }
+    /// Turns the display on or off, see [`App::set_display_powered`].
+    fn set_display_powered(&self, _display: DisplayId, _powered: bool) -> Result<()> {
+        Err(anyhow!(
+            "turning displays on and off is not supported on this platform"
+        ))
+    }
+    /// Returns the gamma adjustment of the display, if it's adjusted.
+    fn display_gamma(&self, _display: DisplayId) -> Option<DisplayGamma> {
+        None
+    }
+    /// Adjusts the gamma of the display, or undoes the adjustment with `None`.
+    fn set_display_gamma(&self, _display: DisplayId, _gamma: Option<DisplayGamma>) -> Result<()> {
+        Err(anyhow!(
+            "adjusting the gamma of displays is not supported on this platform"
+        ))
+    }
+    /// Returns the workspaces of the desktop, see [`App::desktop_workspaces`].
+    fn desktop_workspaces(&self) -> Vec<DesktopWorkspace> {
+        Vec::new()
+    }
+    fn desktop_workspace_groups(&self) -> Vec<DesktopWorkspaceGroup> {
+        Vec::new()
+    }
+    fn on_desktop_workspaces_change(&self, _callback: Box<dyn FnMut()>) {}
+    fn update_desktop_workspaces(&self, _requests: Vec<DesktopWorkspaceRequest>) -> Result<()> {
+        Err(anyhow!(
+            "switching workspaces is not supported on this platform"
+        ))
+    }
+    /// Sends input through a virtual pointer, see [`App::send_virtual_pointer_input`].
+    fn send_virtual_pointer_input(&self, _inputs: Vec<VirtualPointerInput>) -> Result<()> {
+        Err(anyhow!(
+            "virtual pointers are not supported on this platform"
+        ))
+    }
+    /// Sends input through a virtual keyboard, see [`App::send_virtual_keyboard_input`].
+    fn send_virtual_keyboard_input(&self, _inputs: Vec<VirtualKeyboardInput>) -> Result<()> {
+        Err(anyhow!(
+            "virtual keyboards are not supported on this platform"
+        ))
+    }
     fn active_window(&self) -> Option<AnyWindowHandle>;
     fn window_stack(&self) -> Option<Vec<AnyWindowHandle>> {
         None
@@ -160,16 +294,33 @@ pub(crate) trait Platform: 'static {
     fn screen_capture_sources(
         &self,
     ) -> oneshot::Receiver<Result<Vec<Box<dyn ScreenCaptureSource>>>>;
+    /// Captures a screenshot, see [`App::capture_screenshot`].
+    fn capture_screenshot(
+        &self,
+        _interactive: bool,
+    ) -> oneshot::Receiver<Result<Option<Arc<RenderImage>>>> {
+        let (tx, rx) = oneshot::channel();
+        tx.send(Err(anyhow!(
+            "capturing screenshots is not supported on this platform"
+        )))
+        .ok();
+        rx
+    }
 
     fn open_window(
         &self,
         handle: AnyWindowHandle,
         options: WindowParams,
-    ) -> anyhow::Result<Box<dyn PlatformWindow>>;
+    ) -> Result<Box<dyn PlatformWindow>, WindowCreationError>;
 
     /// Returns the appearance of the application's windows.
     fn window_appearance(&self) -> WindowAppearance;
 
+    /// Returns the accent color picked by the user, if the platform exposes one.
+    fn accent_color(&self) -> Option<Rgba> {
+        None
+    }
+
     fn open_url(&self, url: &str);
     fn on_open_urls(&self, callback: Box<dyn FnMut(Vec<String>)>);
     fn register_url_scheme(&self, url: &str) -> Task<Result<()>>;
@@ -179,6 +330,15 @@ pub(crate) trait Platform: 'static {
         options: PathPromptOptions,
     ) -> oneshot::Receiver<Result<Option<Vec<PathBuf>>>>;
     fn prompt_for_new_path(&self, directory: &Path) -> oneshot::Receiver<Result<Option<PathBuf>>>;
+    /// Lets the user pick the color of a pixel on the screen, see [`App::pick_color`].
+    fn pick_color(&self) -> oneshot::Receiver<Result<Option<Rgba>>> {
+        let (tx, rx) = oneshot::channel();
+        tx.send(Err(anyhow!(
+            "picking colors is not supported on this platform"
+        )))
+        .ok();
+        rx
+    }
     fn can_select_mixed_files_and_dirs(&self) -> bool;
     fn reveal_path(&self, path: &Path);
     fn open_with_system(&self, path: &Path);
@@ -186,6 +346,28 @@ pub(crate) trait Platform: 'static {
     fn on_quit(&self, callback: Box<dyn FnMut()>);
     fn on_reopen(&self, callback: Box<dyn FnMut()>);
     fn on_keyboard_layout_change(&self, callback: Box<dyn FnMut()>);
+    /// Called when the connection to the windowing system fails irrecoverably,
+    /// right before the platform's run loop returns.
+    fn on_platform_error(&self, _callback: Box<dyn FnMut(anyhow::Error)>) {}
+
+    /// Returns the display connectors known to the compositor, see [`App::output_heads`].
+    fn output_heads(&self) -> Vec<OutputHead> {
+        Vec::new()
+    }
+    fn on_output_heads_change(&self, _callback: Box<dyn FnMut()>) {}
+    /// Applies the configuration, or only checks whether it would apply with `test_only`.
+    fn configure_outputs(
+        &self,
+        _configuration: OutputConfiguration,
+        _test_only: bool,
+    ) -> oneshot::Receiver<Result<()>> {
+        let (tx, rx) = oneshot::channel();
+        tx.send(Err(anyhow!(
+            "configuring outputs is not supported on this platform"
+        )))
+        .ok();
+        rx
+    }
 
     fn set_menus(&self, menus: Vec<Menu>, keymap: &Keymap);
     fn get_menus(&self) -> Option<Vec<OwnedMenu>> {
@@ -199,6 +381,15 @@ pub(crate) trait Platform: 'static {
     fn on_will_open_app_menu(&self, callback: Box<dyn FnMut()>);
     fn on_validate_app_menu_command(&self, callback: Box<dyn FnMut(&dyn Action) -> bool>);
     fn keyboard_layout(&self) -> String;
+    fn keyboard_layout_index(&self) -> Option<usize> {
+        None
+    }
+    fn keyboard_layouts(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Selects the GPU used by windows opened afterwards, see [`crate::App::set_gpu_device`].
+    fn set_gpu_device(&self, _device_id: Option<u32>) {}
 
     fn compositor_name(&self) -> &'static str {
         ""
@@ -209,6 +400,11 @@ pub(crate) trait Platform: 'static {
     fn set_cursor_style(&self, style: CursorStyle);
     fn should_auto_hide_scrollbars(&self) -> bool;
 
+    /// Returns whether the user asked for animations to be reduced.
+    fn should_reduce_motion(&self) -> bool {
+        false
+    }
+
     #[cfg(any(target_os = "linux", target_os = "freebsd"))]
     fn write_to_primary(&self, item: ClipboardItem);
     fn write_to_clipboard(&self, item: ClipboardItem);
@@ -216,6 +412,18 @@ pub(crate) trait Platform: 'static {
     fn read_from_primary(&self) -> Option<ClipboardItem>;
     fn read_from_clipboard(&self) -> Option<ClipboardItem>;
 
+    /// Calls `callback` on the main thread whenever `fd` becomes readable.
+    #[cfg(any(target_os = "linux", target_os = "freebsd"))]
+    fn watch_fd(
+        &self,
+        _fd: std::os::fd::OwnedFd,
+        _callback: Box<dyn FnMut()>,
+    ) -> Result<crate::Subscription> {
+        Err(anyhow!(
+            "watching file descriptors is not supported on this platform"
+        ))
+    }
+
     fn write_credentials(&self, url: &str, username: &str, password: &[u8]) -> Task<Result<()>>;
     fn read_credentials(&self, url: &str) -> Task<Result<Option<(String, Vec<u8>)>>>;
     fn delete_credentials(&self, url: &str) -> Task<Result<()>>;
@@ -233,6 +441,12 @@ pub trait PlatformDisplay: Send + Sync + Debug {
     /// Get the bounds for this display
     fn bounds(&self) -> Bounds<Pixels>;
 
+    /// Returns the mode the display is driven at, with its resolution and refresh rate, if the
+    /// platform reports it. Only reported on Wayland.
+    fn mode(&self) -> Option<OutputMode> {
+        None
+    }
+
     /// Get the default bounds for this display to place a window
     fn default_bounds(&self) -> Bounds<Pixels> {
         let center = self.bounds().center();
@@ -273,6 +487,73 @@ impl Debug for DisplayId {
 
 unsafe impl Send for DisplayId {}
 
+/// An opaque identifier for an input seat, i.e. a group of input devices
+/// (keyboard, pointer) operated by a single user.
+#[derive(PartialEq, Eq, Hash, Copy, Clone)]
+pub struct SeatId(pub(crate) u32);
+
+impl SeatId {
+    /// Returns the protocol id of the `wl_seat` (Wayland)
+    pub fn as_raw(&self) -> u32 {
+        self.0
+    }
+}
+
+impl Debug for SeatId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SeatId({})", self.0)
+    }
+}
+
+/// An input event whose serial is tracked, see [`Window::latest_input_serial`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputSerialKind {
+    /// The keyboard entering a window
+    KeyboardEnter,
+    /// A key press
+    KeyPress,
+    /// The pointer entering a window
+    PointerEnter,
+    /// A mouse button press or release, which pens send too
+    PointerButton,
+    /// A finger touching the screen
+    TouchDown,
+}
+
+impl InputSerialKind {
+    /// The events that can start grabs, like interactive moves and resizes, popups and drags
+    pub const GRAB: &'static [Self] = &[Self::PointerButton, Self::TouchDown];
+
+    /// The events that can set the selection or request an activation token
+    pub const FOCUS: &'static [Self] = &[
+        Self::KeyboardEnter,
+        Self::KeyPress,
+        Self::PointerButton,
+        Self::TouchDown,
+    ];
+}
+
+/// The serial of an input event, for issuing protocol requests of your own that need one
+/// (Wayland)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputSerial {
+    /// The seat which delivered the event
+    pub seat: SeatId,
+    /// The kind of the event
+    pub kind: InputSerialKind,
+    /// The serial the event was sent with
+    pub serial: u32,
+}
+
+/// How held keys are repeated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyRepeatSettings {
+    /// The number of repeated key presses per second, zero disables key repeat
+    pub characters_per_second: u32,
+    /// How long a key has to be held before it starts repeating
+    pub delay: Duration,
+}
+
 /// Which part of the window to resize
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ResizeEdge {
@@ -294,6 +575,53 @@ pub enum ResizeEdge {
     TopLeft,
 }
 
+impl ResizeEdge {
+    /// Returns the edge of a window with client side decorations that `position` is on, when
+    /// it's within `inset` of an edge that isn't tiled. Corners reach a bit further along the
+    /// edges, to be easier to hit.
+    pub fn at_position(
+        position: Point<Pixels>,
+        inset: Pixels,
+        window_size: Size<Pixels>,
+        tiling: Tiling,
+    ) -> Option<Self> {
+        let corner_size = inset * 1.5;
+        let near_top = !tiling.top && position.y < corner_size;
+        let near_bottom = !tiling.bottom && position.y >= window_size.height - corner_size;
+        let near_left = !tiling.left && position.x < corner_size;
+        let near_right = !tiling.right && position.x >= window_size.width - corner_size;
+
+        let on_top = near_top && position.y < inset;
+        let on_bottom = near_bottom && position.y >= window_size.height - inset;
+        let on_left = near_left && position.x < inset;
+        let on_right = near_right && position.x >= window_size.width - inset;
+        if !(on_top || on_bottom || on_left || on_right) {
+            return None;
+        }
+
+        Some(match (near_top, near_bottom, near_left, near_right) {
+            (true, _, true, _) => Self::TopLeft,
+            (true, _, _, true) => Self::TopRight,
+            (_, true, true, _) => Self::BottomLeft,
+            (_, true, _, true) => Self::BottomRight,
+            _ if on_top => Self::Top,
+            _ if on_bottom => Self::Bottom,
+            _ if on_left => Self::Left,
+            _ => Self::Right,
+        })
+    }
+
+    /// The cursor shown while hovering the edge
+    pub fn cursor_style(&self) -> CursorStyle {
+        match self {
+            Self::Top | Self::Bottom => CursorStyle::ResizeUpDown,
+            Self::Left | Self::Right => CursorStyle::ResizeLeftRight,
+            Self::TopLeft | Self::BottomRight => CursorStyle::ResizeUpLeftDownRight,
+            Self::TopRight | Self::BottomLeft => CursorStyle::ResizeUpRightDownLeft,
+        }
+    }
+}
+
 /// A type to describe the appearance of a window
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
 pub enum WindowDecorations {
@@ -375,6 +703,9 @@ impl Tiling {
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
 pub(crate) struct RequestFrameOptions {
     pub(crate) require_presentation: bool,
+    /// The time the frame is expected to be shown at, when the platform knows it from the
+    /// timing of the frames it presented. Otherwise the frame is drawn for the current time.
+    pub(crate) frame_time: Option<Instant>,
 }
 
 pub(crate) trait PlatformWindow: HasWindowHandle + HasDisplayHandle {
@@ -383,6 +714,11 @@ pub(crate) trait PlatformWindow: HasWindowHandle + HasDisplayHandle {
     fn window_bounds(&self) -> WindowBounds;
     fn content_size(&self) -> Size<Pixels>;
     fn scale_factor(&self) -> f32;
+    /// The integer scale the compositor prefers for the buffers of the window, which is the
+    /// scale factor rounded up on platforms without a separate one.
+    fn buffer_scale(&self) -> u32 {
+        self.scale_factor().ceil() as u32
+    }
     fn appearance(&self) -> WindowAppearance;
     fn display(&self) -> Option<Rc<dyn PlatformDisplay>>;
     fn mouse_position(&self) -> Point<Pixels>;
@@ -401,6 +737,9 @@ pub(crate) trait PlatformWindow: HasWindowHandle + HasDisplayHandle {
     fn is_hovered(&self) -> bool;
     fn set_title(&mut self, title: &str);
     fn set_background_appearance(&self, background_appearance: WindowBackgroundAppearance);
+    fn is_background_blur_supported(&self) -> bool {
+        false
+    }
     fn minimize(&self);
     fn zoom(&self);
     fn toggle_fullscreen(&self);
@@ -417,6 +756,9 @@ pub(crate) trait PlatformWindow: HasWindowHandle + HasDisplayHandle {
     fn draw(&self, scene: &Scene);
     fn completed_frame(&self) {}
     fn sprite_atlas(&self) -> Arc<dyn PlatformAtlas>;
+    fn render_stats(&self) -> RenderStats {
+        RenderStats::default()
+    }
 
     // macOS specific methods
     fn set_edited(&mut self, _edited: bool) {}
@@ -433,6 +775,9 @@ pub(crate) trait PlatformWindow: HasWindowHandle + HasDisplayHandle {
     fn show_window_menu(&self, _position: Point<Pixels>) {}
     fn start_window_move(&self) {}
     fn start_window_resize(&self, _edge: ResizeEdge) {}
+    fn start_toplevel_drag(&self, _offset: Point<Pixels>) -> Option<oneshot::Receiver<()>> {
+        None
+    }
     fn window_decorations(&self) -> Decorations {
         Decorations::Server
     }
@@ -443,7 +788,63 @@ pub(crate) trait PlatformWindow: HasWindowHandle + HasDisplayHandle {
     fn window_controls(&self) -> WindowControls {
         WindowControls::default()
     }
+    fn recommended_bounds(&self) -> Option<Size<Pixels>> {
+        None
+    }
     fn set_client_inset(&self, _inset: Pixels) {}
+    fn set_layer_shell_margin(&self, _margin: (Pixels, Pixels, Pixels, Pixels)) {}
+    fn set_layer_shell_exclusive_zone(&self, _exclusive_zone: Pixels) {}
+    fn set_always_on_top(&self, _always_on_top: bool) {}
+    fn set_on_all_workspaces(&self, _on_all_workspaces: bool) {}
+    fn input_seat(&self) -> Option<SeatId> {
+        None
+    }
+    fn latest_input_serial(&self, _kinds: &[InputSerialKind]) -> Option<InputSerial> {
+        None
+    }
+    fn key_repeat(&self) -> Option<KeyRepeatSettings> {
+        None
+    }
+    fn set_key_repeat(&self, _settings: Option<KeyRepeatSettings>) {}
+    /// Renders the window at the given scale instead of the one picked by the compositor, or
+    /// follows the compositor again with `None`. The window isn't notified, it picks up the new
+    /// scale by itself.
+    fn set_scale_override(&self, _scale: Option<f32>) {}
+    fn pending_compose(&self) -> Option<String> {
+        None
+    }
+    fn is_throttled(&self) -> bool {
+        false
+    }
+    /// Only draw frames when [`PlatformWindow::request_frame`] is called and while the window
+    /// keeps drawing, instead of every frame of the display.
+    fn set_reactive_rendering(&self, _reactive: bool) {}
+    /// Schedules a frame of a window using reactive rendering.
+    fn request_frame(&self) {}
+    fn set_anti_aliasing(&self, _anti_aliasing: AntiAliasing) {}
+    /// Whether popups opened with [`App::open_popup_window`] can extend past this window, which
+    /// gpui uses to show tooltips that don't fit in the window.
+    fn supports_popups(&self) -> bool {
+        false
+    }
+    fn on_throttle_changed(&self, _callback: Box<dyn FnMut(bool)>) {}
+    /// Whether an assistive technology is reading this window, which then publishes its
+    /// accessibility tree after drawing frames.
+    fn is_accessibility_active(&self) -> bool {
+        false
+    }
+    fn update_accessibility_tree(&self, _tree: AccessibilityTree) {}
+    fn on_accessibility_request(&self, _callback: Box<dyn FnMut(AccessibilityRequest)>) {}
+    #[cfg(all(target_os = "linux", feature = "wayland"))]
+    fn create_foreign_surface(&self) -> Option<ForeignSurface> {
+        None
+    }
+    #[cfg(all(target_os = "linux", feature = "wayland"))]
+    fn dmabuf_feedback(&self) -> Option<DmabufFeedback> {
+        None
+    }
+    #[cfg(all(target_os = "linux", feature = "wayland"))]
+    fn on_dmabuf_feedback_changed(&self, _callback: Box<dyn FnMut()>) {}
     fn gpu_specs(&self) -> Option<GpuSpecs>;
 
     fn update_ime_position(&self, _bounds: Bounds<ScaledPixels>);
@@ -454,6 +855,45 @@ pub(crate) trait PlatformWindow: HasWindowHandle + HasDisplayHandle {
     }
 }
 
+/// Statistics about the last frame drawn by a window, see [`crate::Window::render_stats`].
+/// Platforms that don't report some of them leave them at zero.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RenderStats {
+    /// CPU time spent laying out and painting the frame.
+    pub frame_time: Duration,
+    /// The number of draw calls issued to the GPU.
+    pub draw_calls: u32,
+    /// The memory used by the sprite atlas textures, in bytes.
+    pub atlas_bytes: u64,
+    /// The number of tiles uploaded to the sprite atlas.
+    pub texture_uploads: u32,
+    /// The amount of data uploaded to the sprite atlas, in bytes.
+    pub uploaded_bytes: u64,
+}
+
+/// The memory used by the sprite atlas of a window, see [`crate::Window::sprite_atlas_usage`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AtlasUsage {
+    /// The memory used by the atlas textures, in bytes.
+    pub bytes: u64,
+    /// The number of glyphs, icons and images cached in the atlas.
+    pub tiles: usize,
+    /// The number of tiles evicted to stay within the budget since the atlas was created.
+    pub evicted_tiles: u64,
+    /// The budget set with [`crate::Window::set_sprite_atlas_budget`], in bytes.
+    pub budget: Option<u64>,
+}
+
+/// The scale of a window, passed to [`crate::Window::on_scale_factor_changed`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WindowScale {
+    /// The scale factor the window is rendered at, which can be fractional.
+    pub scale_factor: f32,
+    /// The integer scale the compositor prefers for buffers, e.g. for cursor images. It's the
+    /// scale factor rounded up, except on Wayland where the compositor reports it separately.
+    pub buffer_scale: u32,
+}
+
 /// This type is public so that our test macro can generate and use it, but it should not
 /// be considered part of our public API.
 #[doc(hidden)]
@@ -601,6 +1041,12 @@ pub(crate) trait PlatformAtlas: Send + Sync {
         build: &mut dyn FnMut() -> Result<Option<(Size<DevicePixels>, Cow<'a, [u8]>)>>,
     ) -> Result<Option<AtlasTile>>;
     fn remove(&self, key: &AtlasKey);
+    /// Limits the memory of the cached tiles to roughly `bytes`. Once reached, the tiles used
+    /// least recently are evicted, except the ones used by the frame being drawn.
+    fn set_budget(&self, _bytes: Option<u64>) {}
+    fn usage(&self) -> AtlasUsage {
+        AtlasUsage::default()
+    }
 }
 
 struct AtlasTextureList<T> {
@@ -932,6 +1378,12 @@ pub struct WindowOptions {
     /// Whether the window should be shown when created
     pub show: bool,
 
+    /// Whether to defer creating the platform window, its surface and its renderer until
+    /// [`crate::Window::realize`] is called, e.g. when it's first shown. Until then, the window can be
+    /// configured and its views updated without using any GPU memory, which helps applications
+    /// that prepare a window per display plus several popups ahead of time.
+    pub deferred: bool,
+
     /// The kind of window to create
     pub kind: WindowKind,
 
@@ -954,6 +1406,30 @@ pub struct WindowOptions {
     /// Whether to use client or server side decorations. Wayland only
     /// Note that this may be ignored.
     pub window_decorations: Option<WindowDecorations>,
+
+    /// Whether to only draw frames when the window is notified or refreshed, instead of every
+    /// frame of the display. An idle window then doesn't use the GPU at all, which suits bars
+    /// and widgets that change rarely. Use [`Window::request_frame`] to force a frame. Wayland only
+    ///
+    /// [`Window::request_frame`]: crate::Window::request_frame
+    pub reactive_rendering: bool,
+
+    /// How the edges of paths are anti-aliased, see [`AntiAliasing`].
+    pub anti_aliasing: AntiAliasing,
+}
+
+/// How the edges of shapes are smoothed. Quads, shadows and text always use analytic coverage,
+/// which is computed exactly in their shaders. Paths can additionally be multisampled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum AntiAliasing {
+    /// Only analytic coverage, the cheapest setting. On high-DPI displays paths look the same
+    /// as with multisampling.
+    Analytic,
+    /// Rasterize paths with 2 samples per pixel.
+    Msaa2,
+    /// Rasterize paths with 4 samples per pixel.
+    #[default]
+    Msaa4,
 }
 
 /// The variables that can be configured when creating a new window
@@ -990,6 +1466,14 @@ pub(crate) struct WindowParams {
     pub display_id: Option<DisplayId>,
 
     pub window_min_size: Option<Size<Pixels>>,
+
+    /// The parent window of a popup opened with [`App::open_popup_window`], and where the popup
+    /// is placed against it.
+    #[cfg_attr(
+        not(all(any(target_os = "linux", target_os = "freebsd"), feature = "wayland")),
+        allow(dead_code)
+    )]
+    pub popup_anchor: Option<(AnyWindowHandle, PopupAnchor)>,
 }
 
 /// Represents the status of how a window should be opened.
@@ -1033,6 +1517,7 @@ impl Default for WindowOptions {
             }),
             focus: true,
             show: true,
+            deferred: false,
             kind: WindowKind::Normal,
             is_movable: true,
             display_id: None,
@@ -1040,6 +1525,8 @@ impl Default for WindowOptions {
             app_id: None,
             window_min_size: None,
             window_decorations: None,
+            reactive_rendering: false,
+            anti_aliasing: AntiAliasing::default(),
         }
     }
 }
@@ -1066,14 +1553,153 @@ pub enum WindowKind {
 
     /// A window that appears above all other windows, usually used for alerts or popups
     /// use sparingly!
+    ///
+    /// On X11 this is an override-redirect window, which the window manager leaves alone: it's
+    /// shown at exactly the requested bounds. On Wayland it's an xdg popup, which needs a parent
+    /// window: open it with [`App::open_popup_window`]. On both, a pop-up opened with focus
+    /// grabs the pointer and keyboard, and is closed by a click outside of the app's windows.
     PopUp,
 
-    /// A window that is layers of the desktop, wayland only
-    #[cfg(target_os = "linux")]
-    #[cfg(feature = "wayland")]
+    /// A window that is part of the desktop shell, like a panel or a dock. A layer shell
+    /// surface on Wayland, and a dock window reserving space with struts on X11. On macOS and
+    /// Windows, a borderless window placed from its anchor and margins, kept above other windows
+    /// on the top and overlay layers.
     LayerShell(LayerShellSettings),
 }
 
+/// Why a window couldn't be opened, returned from [`App::open_window`] so apps can tell the
+/// user what's missing, or fall back to another kind of window.
+#[derive(Debug, thiserror::Error)]
+pub enum WindowCreationError {
+    /// The platform can't open windows of this kind, like popups without a parent window on
+    /// Wayland.
+    #[error("{} windows aren't supported here", kind_name(.0))]
+    UnsupportedKind(WindowKind),
+    /// The Wayland compositor doesn't implement a protocol the window needs, like
+    /// `zwlr_layer_shell_v1` for layer shell windows on GNOME.
+    #[error("the compositor doesn't support the {0} protocol")]
+    MissingProtocol(&'static str),
+    /// The renderer couldn't be set up for the window, usually because of the GPU driver.
+    #[error("failed to initialize the renderer: {0:#}")]
+    RendererInit(anyhow::Error),
+    /// Any other failure of the platform.
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+fn kind_name(kind: &WindowKind) -> &'static str {
+    match kind {
+        WindowKind::Normal => "normal",
+        WindowKind::PopUp => "popup",
+        WindowKind::LayerShell(_) => "layer shell",
+    }
+}
+
+impl From<anyhow::Error> for WindowCreationError {
+    fn from(error: anyhow::Error) -> Self {
+        // Platform code deeper down returns these wrapped in `anyhow::Error`.
+        error
+            .downcast::<Self>()
+            .unwrap_or_else(|error| Self::Other(error))
+    }
+}
+
+/// Where a popup window opened with [`App::open_popup_window`] is placed: against an area of
+/// its parent window, usually the bounds of the element that opened it.
+///
+/// On Wayland this becomes the anchor rectangle of the popup's positioner, and the compositor
+/// flips or slides the popup to keep it on screen. Elsewhere the popup is placed from the
+/// parent window's position and kept within its display.
+#[derive(Clone, Copy, PartialEq)]
+pub struct PopupAnchor {
+    /// The area the popup is placed against, relative to the parent window.
+    pub bounds: Bounds<Pixels>,
+    /// The corner of `bounds` the popup is attached to.
+    pub attach: Corner,
+    /// The corner of the popup placed at the attach point.
+    pub anchor: Corner,
+    /// An offset applied to the popup after placing it.
+    pub offset: Point<Pixels>,
+}
+
+impl PopupAnchor {
+    /// Places a popup below the given area of the parent window, aligned to its left edge.
+    pub fn new(bounds: Bounds<Pixels>) -> Self {
+        Self {
+            bounds,
+            attach: Corner::BottomLeft,
+            anchor: Corner::TopLeft,
+            offset: Point::default(),
+        }
+    }
+
+    /// Sets the corner of the area the popup is attached to.
+    pub fn attach(mut self, attach: Corner) -> Self {
+        self.attach = attach;
+        self
+    }
+
+    /// Sets the corner of the popup placed at the attach point.
+    pub fn anchor(mut self, anchor: Corner) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    /// Sets an offset applied to the popup after placing it.
+    pub fn offset(mut self, offset: Point<Pixels>) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Flips the popup to the other side of its area along the axes on which it would overflow
+    /// `area`, if it fits there, like a popover opened from a bar at the bottom of the screen
+    /// opening upwards. Both are relative to the parent window.
+    pub fn fit_within(mut self, size: Size<Pixels>, area: Bounds<Pixels>) -> Self {
+        for axis in [Axis::Horizontal, Axis::Vertical] {
+            let fits = |anchor: &Self| {
+                let bounds = anchor.popup_bounds(size);
+                match axis {
+                    Axis::Horizontal => {
+                        bounds.left() >= area.left() && bounds.right() <= area.right()
+                    }
+                    Axis::Vertical => {
+                        bounds.top() >= area.top() && bounds.bottom() <= area.bottom()
+                    }
+                }
+            };
+            let flipped = self.flipped(axis);
+            if !fits(&self) && fits(&flipped) {
+                self = flipped;
+            }
+        }
+        self
+    }
+
+    /// Places the popup on the other side of its area along the given axis, the way
+    /// compositors flip popups that don't fit.
+    fn flipped(self, axis: Axis) -> Self {
+        let offset = match axis {
+            Axis::Horizontal => point(-self.offset.x, self.offset.y),
+            Axis::Vertical => point(self.offset.x, -self.offset.y),
+        };
+        Self {
+            bounds: self.bounds,
+            attach: self.attach.other_side_corner_along(axis),
+            anchor: self.anchor.other_side_corner_along(axis),
+            offset,
+        }
+    }
+
+    /// Returns the bounds of a popup of the given size, relative to the parent window.
+    pub(crate) fn popup_bounds(&self, size: Size<Pixels>) -> Bounds<Pixels> {
+        Bounds::from_corner_and_size(
+            self.anchor,
+            self.bounds.corner(self.attach) + self.offset,
+            size,
+        )
+    }
+}
+
 /// The appearance of the window, as defined by the operating system.
 ///
 /// On macOS, this corresponds to named [`NSAppearance`](https://developer.apple.com/documentation/appkit/nsappearance)
@@ -1124,7 +1750,7 @@ pub enum WindowBackgroundAppearance {
     Transparent,
     /// Transparency, but the contents behind the window are blurred.
     ///
-    /// Not always supported.
+    /// Not always supported, see [`Window::is_background_blur_supported`].
     Blurred,
 }
 
@@ -1504,3 +2130,78 @@ impl ClipboardString {
         hasher.finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{PopupAnchor, ResizeEdge, Tiling};
+    use crate::{point, px, size, Bounds};
+
+    #[test]
+    fn test_resize_edge_at_position() {
+        let window_size = size(px(200.), px(100.));
+        let edge = |x: f32, y: f32, tiling| {
+            ResizeEdge::at_position(point(px(x), px(y)), px(10.), window_size, tiling)
+        };
+
+        assert_eq!(edge(100., 5., Tiling::default()), Some(ResizeEdge::Top));
+        assert_eq!(edge(195., 50., Tiling::default()), Some(ResizeEdge::Right));
+        assert_eq!(edge(100., 50., Tiling::default()), None);
+        // Corners reach past the inset along the edges.
+        assert_eq!(edge(12., 5., Tiling::default()), Some(ResizeEdge::TopLeft));
+        assert_eq!(
+            edge(195., 92., Tiling::default()),
+            Some(ResizeEdge::BottomRight)
+        );
+        assert_eq!(edge(12., 12., Tiling::default()), None);
+
+        // Tiled edges can't be resized, and don't make corners.
+        let tiling = Tiling {
+            top: true,
+            left: true,
+            ..Default::default()
+        };
+        assert_eq!(edge(100., 5., tiling), None);
+        assert_eq!(edge(5., 5., tiling), None);
+        assert_eq!(edge(195., 5., tiling), Some(ResizeEdge::Right));
+        assert_eq!(edge(5., 95., tiling), Some(ResizeEdge::Bottom));
+        assert_eq!(edge(5., 5., Tiling::tiled()), None);
+    }
+
+    #[test]
+    fn test_popup_flips_along_overflowing_axes() {
+        let area = Bounds::new(point(px(0.), px(0.)), size(px(1000.), px(500.)));
+        let popup_origin = |button_origin, popup_size, offset| {
+            let button = Bounds::new(button_origin, size(px(50.), px(20.)));
+            PopupAnchor::new(button)
+                .offset(offset)
+                .fit_within(popup_size, area)
+                .popup_bounds(popup_size)
+                .origin
+        };
+        let popup_size = size(px(200.), px(100.));
+
+        assert_eq!(
+            popup_origin(point(px(10.), px(10.)), popup_size, point(px(0.), px(4.))),
+            point(px(10.), px(34.))
+        );
+        // Near the bottom edge the popup opens upwards, with the offset flipped too.
+        assert_eq!(
+            popup_origin(point(px(10.), px(450.)), popup_size, point(px(0.), px(4.))),
+            point(px(10.), px(346.))
+        );
+        // Near the right edge it's aligned to the right edge of the button instead.
+        assert_eq!(
+            popup_origin(point(px(950.), px(10.)), popup_size, point(px(0.), px(0.))),
+            point(px(800.), px(30.))
+        );
+        // A popup that fits on neither side stays where it was placed.
+        assert_eq!(
+            popup_origin(
+                point(px(10.), px(450.)),
+                size(px(200.), px(600.)),
+                point(px(0.), px(0.))
+            ),
+            point(px(10.), px(470.))
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
-    platform::AtlasTextureList, AtlasKey, AtlasTextureId, AtlasTextureKind, AtlasTile, Bounds,
-    DevicePixels, PlatformAtlas, Point, Size,
+    platform::AtlasTextureList, AtlasKey, AtlasTextureId, AtlasTextureKind, AtlasTile, AtlasUsage,
+    Bounds, DevicePixels, PlatformAtlas, Point, RenderStats, Size,
 };
 use anyhow::Result;
 use blade_graphics as gpu;
@@ -12,6 +12,11 @@ use std::{borrow::Cow, ops, sync::Arc};
 
 pub(crate) const PATH_TEXTURE_FORMAT: gpu::TextureFormat = gpu::TextureFormat::R16Float;
 
+const DEFAULT_ATLAS_SIZE: Size<DevicePixels> = Size {
+    width: DevicePixels(1024),
+    height: DevicePixels(1024),
+};
+
 pub(crate) struct BladeAtlas(Mutex<BladeAtlasState>);
 
 struct PendingUpload {
@@ -20,21 +25,33 @@ struct PendingUpload {
     data: gpu::BufferPiece,
 }
 
+struct CachedTile {
+    tile: AtlasTile,
+    /// The frame the tile was last used in.
+    last_used: u64,
+}
+
 struct BladeAtlasState {
     gpu: Arc<gpu::Context>,
     upload_belt: BufferBelt,
     storage: BladeAtlasStorage,
-    tiles_by_key: FxHashMap<AtlasKey, AtlasTile>,
+    tiles_by_key: FxHashMap<AtlasKey, CachedTile>,
     initializations: Vec<AtlasTextureId>,
     uploads: Vec<PendingUpload>,
-    path_sample_count: u32,
+    /// Counts the frames drawn, tiles used in the current one are never evicted.
+    frame: u64,
+    /// The number of renderers drawing with this atlas, see [`super::BladeContext`].
+    renderers: usize,
+    budget: Option<u64>,
+    evicted_tiles: u64,
 }
 
 #[cfg(gles)]
 unsafe impl Send for BladeAtlasState {}
 
-impl BladeAtlasState {
-    fn destroy(&mut self) {
+impl Drop for BladeAtlasState {
+    // The atlas is dropped with the last window using it, after its renderer waited for the GPU.
+    fn drop(&mut self) {
         self.storage.destroy(&self.gpu);
         self.upload_belt.destroy(&self.gpu);
     }
@@ -43,11 +60,10 @@ impl BladeAtlasState {
 pub struct BladeTextureInfo {
     pub size: gpu::Extent,
     pub raw_view: gpu::TextureView,
-    pub msaa_view: Option<gpu::TextureView>,
 }
 
 impl BladeAtlas {
-    pub(crate) fn new(gpu: &Arc<gpu::Context>, path_sample_count: u32) -> Self {
+    pub(crate) fn new(gpu: &Arc<gpu::Context>) -> Self {
         BladeAtlas(Mutex::new(BladeAtlasState {
             gpu: Arc::clone(gpu),
             upload_belt: BufferBelt::new(BufferBeltDescriptor {
@@ -59,12 +75,24 @@ impl BladeAtlas {
             tiles_by_key: Default::default(),
             initializations: Vec::new(),
             uploads: Vec::new(),
-            path_sample_count,
+            frame: 0,
+            renderers: 0,
+            budget: None,
+            evicted_tiles: 0,
         }))
     }
 
-    pub(crate) fn destroy(&self) {
-        self.0.lock().destroy();
+    pub(crate) fn add_renderer(&self) {
+        self.0.lock().renderers += 1;
+    }
+
+    pub(crate) fn remove_renderer(&self) {
+        self.0.lock().renderers -= 1;
+    }
+
+    /// Whether renderers of other windows draw with this atlas.
+    pub(crate) fn is_shared(&self) -> bool {
+        self.0.lock().renderers > 1
     }
 
     pub(crate) fn clear_textures(&self, texture_kind: AtlasTextureKind) {
@@ -75,6 +103,16 @@ impl BladeAtlas {
         }
     }
 
+    /// Destroys all textures of the given kind. Only meant for kinds whose tiles aren't cached
+    /// across frames.
+    pub(crate) fn release_textures(&self, texture_kind: AtlasTextureKind) {
+        let mut lock = self.0.lock();
+        let gpu = Arc::clone(&lock.gpu);
+        for mut texture in lock.storage[texture_kind].drain().flatten() {
+            texture.destroy(&gpu);
+        }
+    }
+
     /// Allocate a rectangle and make it available for rendering immediately (without waiting for `before_frame`)
     pub fn allocate_for_rendering(
         &self,
@@ -88,9 +126,15 @@ impl BladeAtlas {
         tile
     }
 
-    pub fn before_frame(&self, gpu_encoder: &mut gpu::CommandEncoder) {
+    pub fn before_frame(&self, gpu_encoder: &mut gpu::CommandEncoder, stats: &mut RenderStats) {
         let mut lock = self.0.lock();
-        lock.flush(gpu_encoder);
+        lock.flush(gpu_encoder, stats);
+        lock.frame += 1;
+    }
+
+    /// Returns the memory used by all atlas textures, in bytes.
+    pub fn memory_usage(&self) -> u64 {
+        self.0.lock().memory_usage()
     }
 
     pub fn after_frame(&self, sync_point: &gpu::SyncPoint) {
@@ -109,7 +153,6 @@ impl BladeAtlas {
                 depth: 1,
             },
             raw_view: texture.raw_view,
-            msaa_view: texture.msaa_view,
         }
     }
 }
@@ -121,8 +164,10 @@ impl PlatformAtlas for BladeAtlas {
         build: &mut dyn FnMut() -> Result<Option<(Size<DevicePixels>, Cow<'a, [u8]>)>>,
     ) -> Result<Option<AtlasTile>> {
         let mut lock = self.0.lock();
-        if let Some(tile) = lock.tiles_by_key.get(key) {
-            Ok(Some(tile.clone()))
+        let frame = lock.frame;
+        if let Some(cached) = lock.tiles_by_key.get_mut(key) {
+            cached.last_used = frame;
+            Ok(Some(cached.tile.clone()))
         } else {
             profiling::scope!("new tile");
             let Some((size, bytes)) = build()? else {
@@ -130,41 +175,40 @@ impl PlatformAtlas for BladeAtlas {
             };
             let tile = lock.allocate(size, key.texture_kind());
             lock.upload_texture(tile.texture_id, tile.bounds, &bytes);
-            lock.tiles_by_key.insert(key.clone(), tile.clone());
+            lock.tiles_by_key.insert(
+                key.clone(),
+                CachedTile {
+                    tile: tile.clone(),
+                    last_used: frame,
+                },
+            );
             Ok(Some(tile))
         }
     }
 
     fn remove(&self, key: &AtlasKey) {
-        let mut lock = self.0.lock();
-
-        let Some(id) = lock.tiles_by_key.remove(key).map(|tile| tile.texture_id) else {
-            return;
-        };
+        self.0.lock().remove(key);
+    }
 
-        let Some(texture_slot) = lock.storage[id.kind].textures.get_mut(id.index as usize) else {
-            return;
-        };
+    fn set_budget(&self, bytes: Option<u64>) {
+        self.0.lock().budget = bytes;
+    }
 
-        if let Some(mut texture) = texture_slot.take() {
-            texture.decrement_ref_count();
-            if texture.is_unreferenced() {
-                lock.storage[id.kind]
-                    .free_list
-                    .push(texture.id.index as usize);
-                texture.destroy(&lock.gpu);
-            } else {
-                *texture_slot = Some(texture);
-            }
+    fn usage(&self) -> AtlasUsage {
+        let lock = self.0.lock();
+        AtlasUsage {
+            bytes: lock.memory_usage(),
+            tiles: lock.tiles_by_key.len(),
+            evicted_tiles: lock.evicted_tiles,
+            budget: lock.budget,
         }
     }
 }
 
 impl BladeAtlasState {
     fn allocate(&mut self, size: Size<DevicePixels>, texture_kind: AtlasTextureKind) -> AtlasTile {
-        {
+        loop {
             let textures = &mut self.storage[texture_kind];
-
             if let Some(tile) = textures
                 .iter_mut()
                 .rev()
@@ -172,22 +216,93 @@ impl BladeAtlasState {
             {
                 return tile;
             }
+
+            // Make room in the existing textures rather than growing past the budget. Path
+            // tiles only live for a frame and aren't limited.
+            let Some(budget) = self.budget else {
+                break;
+            };
+            if texture_kind == AtlasTextureKind::Path
+                || self.memory_usage() + texture_bytes(size, texture_kind) <= budget
+                || !self.evict_least_recently_used(texture_kind)
+            {
+                break;
+            }
         }
 
         let texture = self.push_texture(size, texture_kind);
         texture.allocate(size).unwrap()
     }
 
+    /// Evicts the tiles of the given kind that were used the longest time ago, returning
+    /// whether there were any not used by the current frame.
+    fn evict_least_recently_used(&mut self, kind: AtlasTextureKind) -> bool {
+        let Some(oldest) = self
+            .tiles_by_key
+            .values()
+            .filter(|cached| cached.tile.texture_id.kind == kind && cached.last_used < self.frame)
+            .map(|cached| cached.last_used)
+            .min()
+        else {
+            return false;
+        };
+        let keys = self
+            .tiles_by_key
+            .iter()
+            .filter(|(_, cached)| cached.tile.texture_id.kind == kind && cached.last_used == oldest)
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+        self.evicted_tiles += keys.len() as u64;
+        for key in keys {
+            self.remove(&key);
+        }
+        true
+    }
+
+    fn remove(&mut self, key: &AtlasKey) {
+        let Some(tile) = self.tiles_by_key.remove(key).map(|cached| cached.tile) else {
+            return;
+        };
+        let id = tile.texture_id;
+
+        let Some(texture_slot) = self.storage[id.kind].textures.get_mut(id.index as usize) else {
+            return;
+        };
+
+        if let Some(mut texture) = texture_slot.take() {
+            texture.allocator.deallocate(tile.tile_id.into());
+            texture.decrement_ref_count();
+            if texture.is_unreferenced() {
+                self.storage[id.kind]
+                    .free_list
+                    .push(texture.id.index as usize);
+                texture.destroy(&self.gpu);
+            } else {
+                *texture_slot = Some(texture);
+            }
+        }
+    }
+
+    fn memory_usage(&self) -> u64 {
+        let mut bytes = 0;
+        for kind in [
+            AtlasTextureKind::Monochrome,
+            AtlasTextureKind::Polychrome,
+            AtlasTextureKind::Path,
+        ] {
+            for texture in self.storage[kind].textures.iter().flatten() {
+                let size = texture.allocator.size();
+                bytes += size.width as u64 * size.height as u64 * texture.bytes_per_pixel() as u64;
+            }
+        }
+        bytes
+    }
+
     fn push_texture(
         &mut self,
         min_size: Size<DevicePixels>,
         kind: AtlasTextureKind,
     ) -> &mut BladeAtlasTexture {
-        const DEFAULT_ATLAS_SIZE: Size<DevicePixels> = Size {
-            width: DevicePixels(1024),
-            height: DevicePixels(1024),
-        };
-
         let size = min_size.max(&DEFAULT_ATLAS_SIZE);
         let format;
         let usage;
@@ -208,39 +323,6 @@ impl BladeAtlasState {
             }
         }
 
-        // We currently only enable MSAA for path textures.
-        let (msaa, msaa_view) = if self.path_sample_count > 1 && kind == AtlasTextureKind::Path {
-            let msaa = self.gpu.create_texture(gpu::TextureDesc {
-                name: "msaa path texture",
-                format,
-                size: gpu::Extent {
-                    width: size.width.into(),
-                    height: size.height.into(),
-                    depth: 1,
-                },
-                array_layer_count: 1,
-                mip_level_count: 1,
-                sample_count: self.path_sample_count,
-                dimension: gpu::TextureDimension::D2,
-                usage: gpu::TextureUsage::TARGET,
-            });
-
-            (
-                Some(msaa),
-                Some(self.gpu.create_texture_view(
-                    msaa,
-                    gpu::TextureViewDesc {
-                        name: "msaa texture view",
-                        format,
-                        dimension: gpu::ViewDimension::D2,
-                        subresources: &Default::default(),
-                    },
-                )),
-            )
-        } else {
-            (None, None)
-        };
-
         let raw = self.gpu.create_texture(gpu::TextureDesc {
             name: "atlas",
             format,
@@ -277,8 +359,6 @@ impl BladeAtlasState {
             format,
             raw,
             raw_view,
-            msaa,
-            msaa_view,
             live_atlas_keys: 0,
         };
 
@@ -305,12 +385,16 @@ impl BladeAtlasState {
         }
     }
 
-    fn flush(&mut self, encoder: &mut gpu::CommandEncoder) {
+    fn flush(&mut self, encoder: &mut gpu::CommandEncoder, stats: &mut RenderStats) {
         self.flush_initializations(encoder);
 
         let mut transfers = encoder.transfer("atlas");
         for upload in self.uploads.drain(..) {
             let texture = &self.storage[upload.id];
+            stats.texture_uploads += 1;
+            stats.uploaded_bytes += upload.bounds.size.width.0 as u64
+                * upload.bounds.size.height.0 as u64
+                * texture.bytes_per_pixel() as u64;
             transfers.copy_buffer_to_texture(
                 upload.data,
                 upload.bounds.size.width.to_bytes(texture.bytes_per_pixel()),
@@ -334,6 +418,17 @@ impl BladeAtlasState {
     }
 }
 
+/// Returns the memory of a new texture holding a tile of the given size.
+fn texture_bytes(min_size: Size<DevicePixels>, kind: AtlasTextureKind) -> u64 {
+    let size = min_size.max(&DEFAULT_ATLAS_SIZE);
+    let bytes_per_pixel = match kind {
+        AtlasTextureKind::Monochrome => 1,
+        AtlasTextureKind::Polychrome => 4,
+        AtlasTextureKind::Path => PATH_TEXTURE_FORMAT.block_info().size as u64,
+    };
+    size.width.0 as u64 * size.height.0 as u64 * bytes_per_pixel
+}
+
 #[derive(Default)]
 struct BladeAtlasStorage {
     monochrome_textures: AtlasTextureList<BladeAtlasTexture>,
@@ -393,8 +488,6 @@ struct BladeAtlasTexture {
     allocator: BucketedAtlasAllocator,
     raw: gpu::Texture,
     raw_view: gpu::TextureView,
-    msaa: Option<gpu::Texture>,
-    msaa_view: Option<gpu::TextureView>,
     format: gpu::TextureFormat,
     live_atlas_keys: u32,
 }
@@ -422,12 +515,6 @@ impl BladeAtlasTexture {
     fn destroy(&mut self, gpu: &gpu::Context) {
         gpu.destroy_texture(self.raw);
         gpu.destroy_texture_view(self.raw_view);
-        if let Some(msaa) = self.msaa {
-            gpu.destroy_texture(msaa);
-        }
-        if let Some(msaa_view) = self.msaa_view {
-            gpu.destroy_texture_view(msaa_view);
-        }
     }
 
     fn bytes_per_pixel(&self) -> u8 {
@@ -1,24 +1,68 @@
+use super::BladeAtlas;
 use blade_graphics as gpu;
-use std::sync::Arc;
+use parking_lot::Mutex;
+use std::sync::{Arc, Weak};
 
 #[cfg_attr(target_os = "macos", derive(Clone))]
 pub struct BladeContext {
     pub(super) gpu: Arc<gpu::Context>,
+    /// The sprite atlas shared by the renderers created from this context, so that windows
+    /// showing the same glyphs and images rasterize and upload them once. It's destroyed with
+    /// the last renderer using it.
+    atlas: Arc<Mutex<Weak<BladeAtlas>>>,
 }
 
 impl BladeContext {
-    pub fn new() -> anyhow::Result<Self> {
+    /// Creates a context on the GPU with the given PCI device ID, or on the default GPU for `None`.
+    /// The `GPUI_DEVICE` environment variable, in decimal or `0x`-prefixed hexadecimal, takes
+    /// precedence over `device_id`.
+    pub fn new(device_id: Option<u32>) -> anyhow::Result<Self> {
+        let device_id = device_id_from_env().or(device_id).unwrap_or(0);
         let gpu = Arc::new(
             unsafe {
                 gpu::Context::init(gpu::ContextDesc {
                     presentation: true,
                     validation: false,
-                    device_id: 0, //TODO: hook up to user settings
+                    device_id,
                     ..Default::default()
                 })
             }
             .map_err(|e| anyhow::anyhow!("{:?}", e))?,
         );
-        Ok(Self { gpu })
+        Ok(Self {
+            gpu,
+            atlas: Arc::default(),
+        })
+    }
+
+    /// Returns the atlas shared by the renderers of this context, creating it if there is none.
+    pub(super) fn shared_atlas(&self) -> Arc<BladeAtlas> {
+        let mut shared = self.atlas.lock();
+        let atlas = shared.upgrade().unwrap_or_else(|| {
+            let atlas = Arc::new(BladeAtlas::new(&self.gpu));
+            *shared = Arc::downgrade(&atlas);
+            atlas
+        });
+        atlas.add_renderer();
+        atlas
+    }
+}
+
+fn device_id_from_env() -> Option<u32> {
+    let value = std::env::var("GPUI_DEVICE").ok()?;
+    let value = value.trim();
+    let device_id = match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+    match device_id {
+        Ok(device_id) => Some(device_id),
+        Err(_) => {
+            log::warn!("Ignoring invalid GPUI_DEVICE value {value:?}");
+            None
+        }
     }
 }
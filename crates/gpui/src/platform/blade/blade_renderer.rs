@@ -3,8 +3,9 @@
 
 use super::{BladeAtlas, BladeContext, PATH_TEXTURE_FORMAT};
 use crate::{
-    AtlasTextureKind, AtlasTile, Background, Bounds, ContentMask, DevicePixels, GpuSpecs,
-    MonochromeSprite, Path, PathId, PathVertex, PolychromeSprite, PrimitiveBatch, Quad,
+    AntiAliasing, AtlasTextureId, AtlasTextureKind, AtlasTile, Background, BlendMode, Blur, Bounds,
+    ClipId, ContentMask, CustomShader, Damage, DevicePixels, GpuSpecs, MonochromeSprite, Path,
+    PathClip, PathId, PathVertex, Point, PolychromeSprite, PrimitiveBatch, Quad, RenderStats,
     ScaledPixels, Scene, Shadow, Size, Underline,
 };
 use blade_graphics as gpu;
@@ -16,9 +17,16 @@ use media::core_video::CVMetalTextureCache;
 use std::{mem, sync::Arc};
 
 const MAX_FRAME_TIME_MS: u32 = 10000;
-// Use 4x MSAA, all devices support it.
-// https://developer.apple.com/documentation/metal/mtldevice/1433355-supportstexturesamplecount
-const PATH_SAMPLE_COUNT: u32 = 4;
+
+/// Returns the number of samples paths are rasterized with. All devices support up to 4.
+/// https://developer.apple.com/documentation/metal/mtldevice/1433355-supportstexturesamplecount
+fn path_sample_count(anti_aliasing: AntiAliasing) -> u32 {
+    match anti_aliasing {
+        AntiAliasing::Analytic => 1,
+        AntiAliasing::Msaa2 => 2,
+        AntiAliasing::Msaa4 => 4,
+    }
+}
 
 #[repr(C)]
 #[derive(Clone, Copy, Pod, Zeroable)]
@@ -28,6 +36,47 @@ struct GlobalParams {
     pad: u32,
 }
 
+impl GlobalParams {
+    /// Blend modes other than normal are applied to premultiplied colors, whatever the alpha
+    /// mode of the surface, see [`blend_state`].
+    fn for_blend_mode(self, blend_mode: BlendMode) -> Self {
+        match blend_mode {
+            BlendMode::Normal => self,
+            BlendMode::Multiply | BlendMode::Screen | BlendMode::Additive => Self {
+                premultiplied_alpha: 1,
+                ..self
+            },
+        }
+    }
+}
+
+/// Returns how the premultiplied colors drawn with a blend mode are combined with the frame.
+/// The formulas assume the frame is opaque below them.
+fn blend_state(blend_mode: BlendMode) -> gpu::BlendState {
+    match blend_mode {
+        BlendMode::Normal => gpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING,
+        // src * dst + dst * (1 - src_alpha)
+        BlendMode::Multiply => gpu::BlendState {
+            color: gpu::BlendComponent {
+                src_factor: gpu::BlendFactor::Dst,
+                dst_factor: gpu::BlendFactor::OneMinusSrcAlpha,
+                operation: gpu::BlendOperation::Add,
+            },
+            alpha: gpu::BlendComponent::OVER,
+        },
+        // src + dst - src * dst
+        BlendMode::Screen => gpu::BlendState {
+            color: gpu::BlendComponent {
+                src_factor: gpu::BlendFactor::One,
+                dst_factor: gpu::BlendFactor::OneMinusSrc,
+                operation: gpu::BlendOperation::Add,
+            },
+            alpha: gpu::BlendComponent::OVER,
+        },
+        BlendMode::Additive => gpu::BlendState::ADDITIVE,
+    }
+}
+
 //Note: we can't use `Bounds` directly here because
 // it doesn't implement Pod + Zeroable
 #[repr(C)]
@@ -53,6 +102,23 @@ struct SurfaceParams {
     content_mask: PodBounds,
 }
 
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct BlurParams {
+    direction: [f32; 2],
+    pad: [u32; 2],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct CustomShaderParams {
+    bounds: PodBounds,
+    content_mask: PodBounds,
+    mouse_position: [f32; 2],
+    time: f32,
+    pad: u32,
+}
+
 #[derive(blade_macros::ShaderData)]
 struct ShaderQuadsData {
     globals: GlobalParams,
@@ -65,6 +131,15 @@ struct ShaderShadowsData {
     b_shadows: gpu::BufferPiece,
 }
 
+#[derive(blade_macros::ShaderData)]
+struct ShaderBlursData {
+    globals: GlobalParams,
+    blur_params: BlurParams,
+    t_blur: gpu::TextureView,
+    s_blur: gpu::Sampler,
+    b_blurs: gpu::BufferPiece,
+}
+
 #[derive(blade_macros::ShaderData)]
 struct ShaderPathRasterizationData {
     globals: GlobalParams,
@@ -79,6 +154,15 @@ struct ShaderPathsData {
     b_path_sprites: gpu::BufferPiece,
 }
 
+#[derive(blade_macros::ShaderData)]
+struct ShaderPathClipsData {
+    globals: GlobalParams,
+    t_sprite: gpu::TextureView,
+    s_sprite: gpu::Sampler,
+    t_backdrop: gpu::TextureView,
+    b_path_clip_sprites: gpu::BufferPiece,
+}
+
 #[derive(blade_macros::ShaderData)]
 struct ShaderUnderlinesData {
     globals: GlobalParams,
@@ -110,6 +194,12 @@ struct ShaderSurfacesData {
     s_surface: gpu::Sampler,
 }
 
+#[derive(blade_macros::ShaderData)]
+struct ShaderCustomData {
+    globals: GlobalParams,
+    shader_locals: CustomShaderParams,
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[repr(C)]
 struct PathSprite {
@@ -118,19 +208,50 @@ struct PathSprite {
     tile: AtlasTile,
 }
 
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[repr(C)]
+struct PathClipSprite {
+    bounds: Bounds<ScaledPixels>,
+    tile: AtlasTile,
+}
+
+/// The pipelines drawing primitives that can be painted with another [`BlendMode`] than
+/// normal, see [`BladePipelines::blended`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum BlendedPipeline {
+    Quads,
+    Shadows,
+    Paths,
+    Underlines,
+    MonoSprites,
+    PolySprites,
+}
+
 struct BladePipelines {
+    shader: gpu::Shader,
     quads: gpu::RenderPipeline,
     shadows: gpu::RenderPipeline,
+    blur_horizontal: gpu::RenderPipeline,
+    blurs: gpu::RenderPipeline,
     path_rasterization: gpu::RenderPipeline,
     paths: gpu::RenderPipeline,
+    path_clip_erase: gpu::RenderPipeline,
+    path_clip_restore: gpu::RenderPipeline,
     underlines: gpu::RenderPipeline,
     mono_sprites: gpu::RenderPipeline,
     poly_sprites: gpu::RenderPipeline,
     surfaces: gpu::RenderPipeline,
+    // Draws quads without blending, to clear the damaged area of the retained frame
+    clear: gpu::RenderPipeline,
+    color_target: gpu::ColorTargetState,
+    // Compiled lazily, for the blend modes other than normal
+    blended: HashMap<(BlendedPipeline, BlendMode), gpu::RenderPipeline>,
+    // Compiled lazily, `None` for shaders that failed to compile
+    custom_shaders: HashMap<(u64, BlendMode), Option<gpu::RenderPipeline>>,
 }
 
 impl BladePipelines {
-    fn new(gpu: &gpu::Context, surface_info: gpu::SurfaceInfo) -> Self {
+    fn new(gpu: &gpu::Context, surface_info: gpu::SurfaceInfo, path_sample_count: u32) -> Self {
         use gpu::ShaderData as _;
 
         log::info!(
@@ -144,11 +265,14 @@ impl BladePipelines {
         shader.check_struct_size::<SurfaceParams>();
         shader.check_struct_size::<Quad>();
         shader.check_struct_size::<Shadow>();
+        shader.check_struct_size::<Blur>();
+        shader.check_struct_size::<BlurParams>();
         assert_eq!(
             mem::size_of::<PathVertex<ScaledPixels>>(),
             shader.get_struct_size("PathVertex") as usize,
         );
         shader.check_struct_size::<PathSprite>();
+        shader.check_struct_size::<PathClipSprite>();
         shader.check_struct_size::<Underline>();
         shader.check_struct_size::<MonochromeSprite>();
         shader.check_struct_size::<PolychromeSprite>();
@@ -159,11 +283,12 @@ impl BladePipelines {
             gpu::AlphaMode::PreMultiplied => gpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING,
             gpu::AlphaMode::PostMultiplied => gpu::BlendState::ALPHA_BLENDING,
         };
-        let color_targets = &[gpu::ColorTargetState {
+        let color_target = gpu::ColorTargetState {
             format: surface_info.format,
             blend: Some(blend_mode),
             write_mask: gpu::ColorWrites::default(),
-        }];
+        };
+        let color_targets = &[color_target.clone()];
 
         Self {
             quads: gpu.create_render_pipeline(gpu::RenderPipelineDesc {
@@ -194,6 +319,38 @@ impl BladePipelines {
                 color_targets,
                 multisample_state: gpu::MultisampleState::default(),
             }),
+            blur_horizontal: gpu.create_render_pipeline(gpu::RenderPipelineDesc {
+                name: "blur_horizontal",
+                data_layouts: &[&ShaderBlursData::layout()],
+                vertex: shader.at("vs_blur"),
+                vertex_fetches: &[],
+                primitive: gpu::PrimitiveState {
+                    topology: gpu::PrimitiveTopology::TriangleStrip,
+                    ..Default::default()
+                },
+                depth_stencil: None,
+                fragment: Some(shader.at("fs_blur_horizontal")),
+                color_targets: &[gpu::ColorTargetState {
+                    format: surface_info.format,
+                    blend: None,
+                    write_mask: gpu::ColorWrites::default(),
+                }],
+                multisample_state: gpu::MultisampleState::default(),
+            }),
+            blurs: gpu.create_render_pipeline(gpu::RenderPipelineDesc {
+                name: "blurs",
+                data_layouts: &[&ShaderBlursData::layout()],
+                vertex: shader.at("vs_blur"),
+                vertex_fetches: &[],
+                primitive: gpu::PrimitiveState {
+                    topology: gpu::PrimitiveTopology::TriangleStrip,
+                    ..Default::default()
+                },
+                depth_stencil: None,
+                fragment: Some(shader.at("fs_blur")),
+                color_targets,
+                multisample_state: gpu::MultisampleState::default(),
+            }),
             path_rasterization: gpu.create_render_pipeline(gpu::RenderPipelineDesc {
                 name: "path_rasterization",
                 data_layouts: &[&ShaderPathRasterizationData::layout()],
@@ -211,7 +368,7 @@ impl BladePipelines {
                     write_mask: gpu::ColorWrites::default(),
                 }],
                 multisample_state: gpu::MultisampleState {
-                    sample_count: PATH_SAMPLE_COUNT,
+                    sample_count: path_sample_count,
                     ..Default::default()
                 },
             }),
@@ -229,6 +386,54 @@ impl BladePipelines {
                 color_targets,
                 multisample_state: gpu::MultisampleState::default(),
             }),
+            path_clip_erase: gpu.create_render_pipeline(gpu::RenderPipelineDesc {
+                name: "path_clip_erase",
+                data_layouts: &[&ShaderPathClipsData::layout()],
+                vertex: shader.at("vs_path_clip"),
+                vertex_fetches: &[],
+                primitive: gpu::PrimitiveState {
+                    topology: gpu::PrimitiveTopology::TriangleStrip,
+                    ..Default::default()
+                },
+                depth_stencil: None,
+                fragment: Some(shader.at("fs_path_clip_erase")),
+                // Multiplies the frame by the coverage the shader outputs.
+                color_targets: &[gpu::ColorTargetState {
+                    format: surface_info.format,
+                    blend: Some(gpu::BlendState {
+                        color: gpu::BlendComponent {
+                            src_factor: gpu::BlendFactor::Zero,
+                            dst_factor: gpu::BlendFactor::SrcAlpha,
+                            operation: gpu::BlendOperation::Add,
+                        },
+                        alpha: gpu::BlendComponent {
+                            src_factor: gpu::BlendFactor::Zero,
+                            dst_factor: gpu::BlendFactor::SrcAlpha,
+                            operation: gpu::BlendOperation::Add,
+                        },
+                    }),
+                    write_mask: gpu::ColorWrites::default(),
+                }],
+                multisample_state: gpu::MultisampleState::default(),
+            }),
+            path_clip_restore: gpu.create_render_pipeline(gpu::RenderPipelineDesc {
+                name: "path_clip_restore",
+                data_layouts: &[&ShaderPathClipsData::layout()],
+                vertex: shader.at("vs_path_clip"),
+                vertex_fetches: &[],
+                primitive: gpu::PrimitiveState {
+                    topology: gpu::PrimitiveTopology::TriangleStrip,
+                    ..Default::default()
+                },
+                depth_stencil: None,
+                fragment: Some(shader.at("fs_path_clip_restore")),
+                color_targets: &[gpu::ColorTargetState {
+                    format: surface_info.format,
+                    blend: Some(gpu::BlendState::ADDITIVE),
+                    write_mask: gpu::ColorWrites::default(),
+                }],
+                multisample_state: gpu::MultisampleState::default(),
+            }),
             underlines: gpu.create_render_pipeline(gpu::RenderPipelineDesc {
                 name: "underlines",
                 data_layouts: &[&ShaderUnderlinesData::layout()],
@@ -285,19 +490,372 @@ impl BladePipelines {
                 color_targets,
                 multisample_state: gpu::MultisampleState::default(),
             }),
+            clear: gpu.create_render_pipeline(gpu::RenderPipelineDesc {
+                name: "clear",
+                data_layouts: &[&ShaderQuadsData::layout()],
+                vertex: shader.at("vs_quad"),
+                vertex_fetches: &[],
+                primitive: gpu::PrimitiveState {
+                    topology: gpu::PrimitiveTopology::TriangleStrip,
+                    ..Default::default()
+                },
+                depth_stencil: None,
+                fragment: Some(shader.at("fs_quad")),
+                color_targets: &[gpu::ColorTargetState {
+                    blend: None,
+                    ..color_target.clone()
+                }],
+                multisample_state: gpu::MultisampleState::default(),
+            }),
+            shader,
+            color_target,
+            blended: HashMap::default(),
+            custom_shaders: HashMap::default(),
+        }
+    }
+
+    /// Returns the color target of the pipelines drawing with the given blend mode.
+    fn color_target(&self, blend_mode: BlendMode) -> gpu::ColorTargetState {
+        match blend_mode {
+            BlendMode::Normal => self.color_target.clone(),
+            BlendMode::Multiply | BlendMode::Screen | BlendMode::Additive => {
+                gpu::ColorTargetState {
+                    blend: Some(blend_state(blend_mode)),
+                    ..self.color_target.clone()
+                }
+            }
+        }
+    }
+
+    /// Returns the pipeline drawing the given kind of primitives with the given blend mode,
+    /// compiling it on first use for blend modes other than normal.
+    fn blended(
+        &mut self,
+        gpu: &gpu::Context,
+        kind: BlendedPipeline,
+        blend_mode: BlendMode,
+    ) -> &gpu::RenderPipeline {
+        use gpu::ShaderData as _;
+
+        if blend_mode == BlendMode::Normal {
+            return match kind {
+                BlendedPipeline::Quads => &self.quads,
+                BlendedPipeline::Shadows => &self.shadows,
+                BlendedPipeline::Paths => &self.paths,
+                BlendedPipeline::Underlines => &self.underlines,
+                BlendedPipeline::MonoSprites => &self.mono_sprites,
+                BlendedPipeline::PolySprites => &self.poly_sprites,
+            };
         }
+
+        let color_target = self.color_target(blend_mode);
+        let shader = &self.shader;
+        self.blended.entry((kind, blend_mode)).or_insert_with(|| {
+            let (name, data_layout, vertex, fragment) = match kind {
+                BlendedPipeline::Quads => {
+                    ("quads", ShaderQuadsData::layout(), "vs_quad", "fs_quad")
+                }
+                BlendedPipeline::Shadows => (
+                    "shadows",
+                    ShaderShadowsData::layout(),
+                    "vs_shadow",
+                    "fs_shadow",
+                ),
+                BlendedPipeline::Paths => {
+                    ("paths", ShaderPathsData::layout(), "vs_path", "fs_path")
+                }
+                BlendedPipeline::Underlines => (
+                    "underlines",
+                    ShaderUnderlinesData::layout(),
+                    "vs_underline",
+                    "fs_underline",
+                ),
+                BlendedPipeline::MonoSprites => (
+                    "mono-sprites",
+                    ShaderMonoSpritesData::layout(),
+                    "vs_mono_sprite",
+                    "fs_mono_sprite",
+                ),
+                BlendedPipeline::PolySprites => (
+                    "poly-sprites",
+                    ShaderPolySpritesData::layout(),
+                    "vs_poly_sprite",
+                    "fs_poly_sprite",
+                ),
+            };
+            gpu.create_render_pipeline(gpu::RenderPipelineDesc {
+                name,
+                data_layouts: &[&data_layout],
+                vertex: shader.at(vertex),
+                vertex_fetches: &[],
+                primitive: gpu::PrimitiveState {
+                    topology: gpu::PrimitiveTopology::TriangleStrip,
+                    ..Default::default()
+                },
+                depth_stencil: None,
+                fragment: Some(shader.at(fragment)),
+                color_targets: &[color_target],
+                multisample_state: gpu::MultisampleState::default(),
+            })
+        })
+    }
+
+    /// Returns the pipeline of a custom shader drawing with the given blend mode, compiling it
+    /// on first use.
+    fn custom_shader(
+        &mut self,
+        gpu: &gpu::Context,
+        custom_shader: &CustomShader,
+        blend_mode: BlendMode,
+    ) -> Option<&gpu::RenderPipeline> {
+        use gpu::ShaderData as _;
+
+        let color_target = self.color_target(blend_mode);
+        self.custom_shaders
+            .entry((custom_shader.id, blend_mode))
+            .or_insert_with(|| {
+                let source = format!(
+                    "{}\n{}",
+                    include_str!("custom_shader.wgsl"),
+                    custom_shader.source()
+                );
+                let shader = gpu
+                    .try_create_shader(gpu::ShaderDesc { source: &source })
+                    .map_err(|error| log::error!("Failed to compile custom shader: {error}"))
+                    .ok()?;
+                shader.check_struct_size::<CustomShaderParams>();
+                Some(gpu.create_render_pipeline(gpu::RenderPipelineDesc {
+                    name: "custom-shader",
+                    data_layouts: &[&ShaderCustomData::layout()],
+                    vertex: shader.at("vs_custom_shader"),
+                    vertex_fetches: &[],
+                    primitive: gpu::PrimitiveState {
+                        topology: gpu::PrimitiveTopology::TriangleStrip,
+                        ..Default::default()
+                    },
+                    depth_stencil: None,
+                    fragment: Some(shader.at("fs_custom_shader")),
+                    color_targets: &[color_target],
+                    multisample_state: gpu::MultisampleState::default(),
+                }))
+            })
+            .as_ref()
     }
 
     fn destroy(&mut self, gpu: &gpu::Context) {
         gpu.destroy_render_pipeline(&mut self.quads);
         gpu.destroy_render_pipeline(&mut self.shadows);
+        gpu.destroy_render_pipeline(&mut self.blur_horizontal);
+        gpu.destroy_render_pipeline(&mut self.blurs);
         gpu.destroy_render_pipeline(&mut self.path_rasterization);
         gpu.destroy_render_pipeline(&mut self.paths);
+        gpu.destroy_render_pipeline(&mut self.path_clip_erase);
+        gpu.destroy_render_pipeline(&mut self.path_clip_restore);
         gpu.destroy_render_pipeline(&mut self.underlines);
         gpu.destroy_render_pipeline(&mut self.mono_sprites);
         gpu.destroy_render_pipeline(&mut self.poly_sprites);
         gpu.destroy_render_pipeline(&mut self.surfaces);
+        gpu.destroy_render_pipeline(&mut self.clear);
+        for (_, mut pipeline) in self.blended.drain() {
+            gpu.destroy_render_pipeline(&mut pipeline);
+        }
+        for mut pipeline in self
+            .custom_shaders
+            .drain()
+            .filter_map(|(_, pipeline)| pipeline)
+        {
+            gpu.destroy_render_pipeline(&mut pipeline);
+        }
+    }
+}
+
+/// Scratch textures the size of the surface, for blurring what was drawn below a blur.
+struct BlurTextures {
+    size: gpu::Extent,
+    /// A copy of the frame drawn so far.
+    backdrop: gpu::Texture,
+    backdrop_view: gpu::TextureView,
+    /// The backdrop blurred horizontally.
+    horizontal: gpu::Texture,
+    horizontal_view: gpu::TextureView,
+}
+
+impl BlurTextures {
+    fn new(gpu: &gpu::Context, format: gpu::TextureFormat, size: gpu::Extent) -> Self {
+        let create = |name, usage| {
+            let texture = gpu.create_texture(gpu::TextureDesc {
+                name,
+                format,
+                size,
+                array_layer_count: 1,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: gpu::TextureDimension::D2,
+                usage: usage | gpu::TextureUsage::RESOURCE,
+            });
+            let view = gpu.create_texture_view(
+                texture,
+                gpu::TextureViewDesc {
+                    name,
+                    format,
+                    dimension: gpu::ViewDimension::D2,
+                    subresources: &Default::default(),
+                },
+            );
+            (texture, view)
+        };
+        let (backdrop, backdrop_view) = create("blur backdrop", gpu::TextureUsage::COPY);
+        let (horizontal, horizontal_view) = create("blur horizontal", gpu::TextureUsage::TARGET);
+        Self {
+            size,
+            backdrop,
+            backdrop_view,
+            horizontal,
+            horizontal_view,
+        }
     }
+
+    fn destroy(&mut self, gpu: &gpu::Context) {
+        gpu.destroy_texture_view(self.backdrop_view);
+        gpu.destroy_texture(self.backdrop);
+        gpu.destroy_texture_view(self.horizontal_view);
+        gpu.destroy_texture(self.horizontal);
+    }
+}
+
+/// The frame drawn into instead of the swapchain image, which keeps the previous frame outside
+/// of the damaged area. Swapchain images are handed out in turn, so they don't have it.
+struct RetainedFrame {
+    size: gpu::Extent,
+    texture: gpu::Texture,
+    view: gpu::TextureView,
+}
+
+impl RetainedFrame {
+    fn new(gpu: &gpu::Context, format: gpu::TextureFormat, size: gpu::Extent) -> Self {
+        let texture = gpu.create_texture(gpu::TextureDesc {
+            name: "retained frame",
+            format,
+            size,
+            array_layer_count: 1,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: gpu::TextureDimension::D2,
+            usage: gpu::TextureUsage::TARGET | gpu::TextureUsage::COPY,
+        });
+        let view = gpu.create_texture_view(
+            texture,
+            gpu::TextureViewDesc {
+                name: "retained frame",
+                format,
+                dimension: gpu::ViewDimension::D2,
+                subresources: &Default::default(),
+            },
+        );
+        Self {
+            size,
+            texture,
+            view,
+        }
+    }
+
+    fn destroy(&mut self, gpu: &gpu::Context) {
+        gpu.destroy_texture_view(self.view);
+        gpu.destroy_texture(self.texture);
+    }
+}
+
+/// Returns the area of a surface of `size` to repaint for `damage`, all of it unless only parts
+/// changed.
+fn damage_scissor(damage: &Damage, size: gpu::Extent) -> gpu::ScissorRect {
+    let Some(bounds) = damage.bounds() else {
+        return gpu::ScissorRect {
+            x: 0,
+            y: 0,
+            w: size.width,
+            h: size.height,
+        };
+    };
+    let left = (bounds.left().0.floor().max(0.) as u32).min(size.width);
+    let top = (bounds.top().0.floor().max(0.) as u32).min(size.height);
+    let right = (bounds.right().0.ceil().max(0.) as u32).clamp(left, size.width);
+    let bottom = (bounds.bottom().0.ceil().max(0.) as u32).clamp(top, size.height);
+    gpu::ScissorRect {
+        x: left as i32,
+        y: top as i32,
+        w: right - left,
+        h: bottom - top,
+    }
+}
+
+/// Copies of the frame taken where content clipped to a path starts, to restore what was below
+/// the content outside of the path where it ends. Clips starting together share a copy, nested
+/// ones each need their own.
+struct ClipBackdrops {
+    size: gpu::Extent,
+    textures: Vec<(gpu::Texture, gpu::TextureView)>,
+    /// The copy of every clip that started but didn't end yet.
+    clips: HashMap<ClipId, usize>,
+}
+
+impl ClipBackdrops {
+    fn new(size: gpu::Extent) -> Self {
+        Self {
+            size,
+            textures: Vec::new(),
+            clips: HashMap::default(),
+        }
+    }
+
+    /// Returns a copy no unfinished clip uses, creating one if they all are.
+    fn acquire(
+        &mut self,
+        gpu: &gpu::Context,
+        format: gpu::TextureFormat,
+        encoder: &mut gpu::CommandEncoder,
+    ) -> usize {
+        if let Some(ix) =
+            (0..self.textures.len()).find(|ix| !self.clips.values().any(|used| used == ix))
+        {
+            return ix;
+        }
+        let texture = gpu.create_texture(gpu::TextureDesc {
+            name: "clip backdrop",
+            format,
+            size: self.size,
+            array_layer_count: 1,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: gpu::TextureDimension::D2,
+            usage: gpu::TextureUsage::COPY | gpu::TextureUsage::RESOURCE,
+        });
+        let view = gpu.create_texture_view(
+            texture,
+            gpu::TextureViewDesc {
+                name: "clip backdrop",
+                format,
+                dimension: gpu::ViewDimension::D2,
+                subresources: &Default::default(),
+            },
+        );
+        encoder.init_texture(texture);
+        self.textures.push((texture, view));
+        self.textures.len() - 1
+    }
+
+    fn destroy(&mut self, gpu: &gpu::Context) {
+        for (texture, view) in self.textures.drain(..) {
+            gpu.destroy_texture_view(view);
+            gpu.destroy_texture(texture);
+        }
+        self.clips.clear();
+    }
+}
+
+/// A multisampled texture paths are rasterized into, and then resolved into the atlas.
+struct PathMsaaTexture {
+    texture: gpu::Texture,
+    view: gpu::TextureView,
 }
 
 pub struct BladeSurfaceConfig {
@@ -305,8 +863,8 @@ pub struct BladeSurfaceConfig {
     pub transparent: bool,
 }
 
-//Note: we could see some of these fields moved into `BladeContext`
-// so that they are shared between windows. E.g. `pipelines`.
+//Note: we could see more of these fields moved into `BladeContext`
+// so that they are shared between windows, like the atlas. E.g. `pipelines`.
 // But that is complicated by the fact that pipelines depend on
 // the format and alpha mode.
 pub struct BladeRenderer {
@@ -318,8 +876,26 @@ pub struct BladeRenderer {
     pipelines: BladePipelines,
     instance_belt: BufferBelt,
     path_tiles: HashMap<PathId, AtlasTile>,
+    clip_tiles: HashMap<ClipId, AtlasTile>,
+    path_sample_count: u32,
+    // One per size of the path textures of the atlas, they aren't shared with other windows
+    // because their sample count depends on the anti-aliasing of the window
+    path_msaa_textures: HashMap<gpu::Extent, PathMsaaTexture>,
     atlas: Arc<BladeAtlas>,
     atlas_sampler: gpu::Sampler,
+    // Created on the first blur and kept until the surface is resized or released
+    blur_textures: Option<BlurTextures>,
+    // Created on the first path clip and kept until the surface is resized or released
+    clip_backdrops: Option<ClipBackdrops>,
+    // Created on the first draw and kept until the surface is resized or released
+    retained_frame: Option<RetainedFrame>,
+    // Set when the surface contents can't be derived from the previous frame
+    needs_full_redraw: bool,
+    // Set while the transient buffers are released, see `release_transient_resources`
+    transient_released: bool,
+    // Set while the swapchain is shrunk, see `release_resources`
+    surface_released: bool,
+    stats: RenderStats,
     #[cfg(target_os = "macos")]
     core_video_texture_cache: CVMetalTextureCache,
 }
@@ -332,7 +908,8 @@ impl BladeRenderer {
     ) -> anyhow::Result<Self> {
         let surface_config = gpu::SurfaceConfig {
             size: config.size,
-            usage: gpu::TextureUsage::TARGET,
+            // Blurs copy the frame drawn so far.
+            usage: gpu::TextureUsage::TARGET | gpu::TextureUsage::COPY,
             display_sync: gpu::DisplaySync::Recent,
             color_space: gpu::ColorSpace::Linear,
             allow_exclusive_full_screen: false,
@@ -347,13 +924,14 @@ impl BladeRenderer {
             name: "main",
             buffer_count: 2,
         });
-        let pipelines = BladePipelines::new(&context.gpu, surface.info());
+        let path_sample_count = path_sample_count(AntiAliasing::default());
+        let pipelines = BladePipelines::new(&context.gpu, surface.info(), path_sample_count);
         let instance_belt = BufferBelt::new(BufferBeltDescriptor {
             memory: gpu::Memory::Shared,
             min_chunk_size: 0x1000,
             alignment: 0x40, // Vulkan `minStorageBufferOffsetAlignment` on Intel Xe
         });
-        let atlas = Arc::new(BladeAtlas::new(&context.gpu, PATH_SAMPLE_COUNT));
+        let atlas = context.shared_atlas();
         let atlas_sampler = context.gpu.create_sampler(gpu::SamplerDesc {
             name: "atlas",
             mag_filter: gpu::FilterMode::Linear,
@@ -378,13 +956,24 @@ impl BladeRenderer {
             pipelines,
             instance_belt,
             path_tiles: HashMap::default(),
+            clip_tiles: HashMap::default(),
+            path_sample_count,
+            path_msaa_textures: HashMap::default(),
             atlas,
             atlas_sampler,
+            blur_textures: None,
+            clip_backdrops: None,
+            retained_frame: None,
+            needs_full_redraw: true,
+            transient_released: false,
+            surface_released: false,
+            stats: RenderStats::default(),
             #[cfg(target_os = "macos")]
             core_video_texture_cache,
         })
     }
 
+    #[profiling::function]
     fn wait_for_gpu(&mut self) {
         if let Some(last_sp) = self.last_sync_point.take() {
             if !self.gpu.wait_for(&last_sp, MAX_FRAME_TIME_MS) {
@@ -416,8 +1005,8 @@ impl BladeRenderer {
         if always_resize || gpu_size != self.surface_config.size {
             self.wait_for_gpu();
             self.surface_config.size = gpu_size;
-            self.gpu
-                .reconfigure_surface(&mut self.surface, self.surface_config);
+            self.reconfigure_surface();
+            self.needs_full_redraw = true;
         }
     }
 
@@ -425,10 +1014,124 @@ impl BladeRenderer {
         if transparent != self.surface_config.transparent {
             self.wait_for_gpu();
             self.surface_config.transparent = transparent;
-            self.gpu
-                .reconfigure_surface(&mut self.surface, self.surface_config);
+            self.reconfigure_surface();
+            self.pipelines.destroy(&self.gpu);
+            self.pipelines =
+                BladePipelines::new(&self.gpu, self.surface.info(), self.path_sample_count);
+            self.needs_full_redraw = true;
+        }
+    }
+
+    pub fn update_anti_aliasing(&mut self, anti_aliasing: AntiAliasing) {
+        let path_sample_count = path_sample_count(anti_aliasing);
+        if path_sample_count != self.path_sample_count {
+            self.wait_for_gpu();
+            self.path_sample_count = path_sample_count;
+            self.destroy_path_msaa_textures();
             self.pipelines.destroy(&self.gpu);
-            self.pipelines = BladePipelines::new(&self.gpu, self.surface.info());
+            self.pipelines = BladePipelines::new(&self.gpu, self.surface.info(), path_sample_count);
+            self.needs_full_redraw = true;
+        }
+    }
+
+    /// Returns the multisampled texture to rasterize paths into a path texture of `size`.
+    fn path_msaa_view(&mut self, size: gpu::Extent) -> gpu::TextureView {
+        let gpu = &self.gpu;
+        let sample_count = self.path_sample_count;
+        self.path_msaa_textures
+            .entry(size)
+            .or_insert_with(|| {
+                let texture = gpu.create_texture(gpu::TextureDesc {
+                    name: "msaa path texture",
+                    format: PATH_TEXTURE_FORMAT,
+                    size,
+                    array_layer_count: 1,
+                    mip_level_count: 1,
+                    sample_count,
+                    dimension: gpu::TextureDimension::D2,
+                    usage: gpu::TextureUsage::TARGET,
+                });
+                let view = gpu.create_texture_view(
+                    texture,
+                    gpu::TextureViewDesc {
+                        name: "msaa texture view",
+                        format: PATH_TEXTURE_FORMAT,
+                        dimension: gpu::ViewDimension::D2,
+                        subresources: &Default::default(),
+                    },
+                );
+                PathMsaaTexture { texture, view }
+            })
+            .view
+    }
+
+    fn destroy_path_msaa_textures(&mut self) {
+        for (_, msaa) in self.path_msaa_textures.drain() {
+            self.gpu.destroy_texture_view(msaa.view);
+            self.gpu.destroy_texture(msaa.texture);
+        }
+    }
+
+    fn reconfigure_surface(&mut self) {
+        let mut config = self.surface_config;
+        if self.surface_released {
+            // Blade can't destroy a swapchain without destroying the surface, but shrinking it
+            // frees the full-size images.
+            config.size = gpu::Extent {
+                width: 1,
+                height: 1,
+                depth: 1,
+            };
+        }
+        self.gpu.reconfigure_surface(&mut self.surface, config);
+    }
+
+    /// Frees the swapchain images along with the transient buffers of a hidden window. They are
+    /// recreated on the next draw, which then repaints the whole surface.
+    #[cfg_attr(any(target_os = "macos", not(feature = "wayland")), allow(dead_code))]
+    pub fn release_resources(&mut self) {
+        self.release_transient_resources();
+        if !self.surface_released {
+            self.surface_released = true;
+            self.reconfigure_surface();
+        }
+    }
+
+    /// Frees the buffers and textures that are only needed while drawing, for idle windows that
+    /// are still shown. The swapchain is kept so the compositor can still present the window.
+    #[cfg_attr(any(target_os = "macos", not(feature = "wayland")), allow(dead_code))]
+    pub fn release_transient_resources(&mut self) {
+        if self.transient_released {
+            return;
+        }
+        self.wait_for_gpu();
+        self.transient_released = true;
+        self.instance_belt.destroy(&self.gpu);
+        if let Some(mut blur_textures) = self.blur_textures.take() {
+            blur_textures.destroy(&self.gpu);
+        }
+        if let Some(mut clip_backdrops) = self.clip_backdrops.take() {
+            clip_backdrops.destroy(&self.gpu);
+        }
+        if let Some(mut retained_frame) = self.retained_frame.take() {
+            retained_frame.destroy(&self.gpu);
+        }
+        self.destroy_path_msaa_textures();
+        self.path_tiles.clear();
+        self.clip_tiles.clear();
+        // Other windows sharing the atlas may still be rendering with the path textures.
+        if !self.atlas.is_shared() {
+            self.atlas.release_textures(AtlasTextureKind::Path);
+        }
+        self.needs_full_redraw = true;
+    }
+
+    /// Returns the area of the surface that changes when drawing `scene`.
+    pub fn damage(&self, scene: &Scene) -> Damage {
+        if self.needs_full_redraw {
+            Damage::Full
+        } else {
+            scene.damage.clone()
         }
     }
 
@@ -441,6 +1144,15 @@ impl BladeRenderer {
         &self.atlas
     }
 
+    /// Returns statistics about the last drawn frame.
+    #[cfg_attr(target_os = "macos", allow(dead_code))]
+    pub fn render_stats(&self) -> RenderStats {
+        RenderStats {
+            atlas_bytes: self.atlas.memory_usage(),
+            ..self.stats.clone()
+        }
+    }
+
     #[cfg_attr(target_os = "macos", allow(dead_code))]
     pub fn gpu_specs(&self) -> GpuSpecs {
         let info = self.gpu.device_information();
@@ -464,34 +1176,21 @@ impl BladeRenderer {
     }
 
     #[profiling::function]
-    fn rasterize_paths(&mut self, paths: &[Path<ScaledPixels>]) {
+    fn rasterize_paths(&mut self, scene: &Scene) {
         self.path_tiles.clear();
+        self.clip_tiles.clear();
         let mut vertices_by_texture_id = HashMap::default();
 
-        for path in paths {
-            let clipped_bounds = path
-                .bounds
-                .intersect(&path.content_mask.bounds)
-                .map_origin(|origin| origin.floor())
-                .map_size(|size| size.ceil());
-            let tile = self.atlas.allocate_for_rendering(
-                clipped_bounds.size.map(Into::into),
-                AtlasTextureKind::Path,
-                &mut self.command_encoder,
-            );
-            vertices_by_texture_id
-                .entry(tile.texture_id)
-                .or_insert(Vec::new())
-                .extend(path.vertices.iter().map(|vertex| PathVertex {
-                    xy_position: vertex.xy_position - clipped_bounds.origin
-                        + tile.bounds.origin.map(Into::into),
-                    st_position: vertex.st_position,
-                    content_mask: ContentMask {
-                        bounds: tile.bounds.map(Into::into),
-                    },
-                }));
+        for path in scene.paths() {
+            let tile = self.allocate_path_tile(path, &mut vertices_by_texture_id);
             self.path_tiles.insert(path.id, tile);
         }
+        for clip in &scene.path_clips {
+            if let Some(path) = &clip.path {
+                let tile = self.allocate_path_tile(path, &mut vertices_by_texture_id);
+                self.clip_tiles.insert(clip.id, tile);
+            }
+        }
 
         for (texture_id, vertices) in vertices_by_texture_id {
             let tex_info = self.atlas.get_texture_info(texture_id);
@@ -503,9 +1202,9 @@ impl BladeRenderer {
 
             let vertex_buf = unsafe { self.instance_belt.alloc_typed(&vertices, &self.gpu) };
             let frame_view = tex_info.raw_view;
-            let color_target = if let Some(msaa_view) = tex_info.msaa_view {
+            let color_target = if self.path_sample_count > 1 {
                 gpu::RenderTarget {
-                    view: msaa_view,
+                    view: self.path_msaa_view(tex_info.size),
                     init_op: gpu::InitOp::Clear(gpu::TextureColor::OpaqueBlack),
                     finish_op: gpu::FinishOp::ResolveTo(frame_view),
                 }
@@ -533,30 +1232,228 @@ impl BladeRenderer {
                     },
                 );
                 encoder.draw(0, vertices.len() as u32, 0, 1);
+                self.stats.draw_calls += 1;
             }
         }
     }
 
+    /// Allocates the tile a path is rasterized into, and adds the vertices of the path to those
+    /// rasterized into the texture of the tile.
+    fn allocate_path_tile(
+        &mut self,
+        path: &Path<ScaledPixels>,
+        vertices_by_texture_id: &mut HashMap<AtlasTextureId, Vec<PathVertex<ScaledPixels>>>,
+    ) -> AtlasTile {
+        let clipped_bounds = path
+            .bounds
+            .intersect(&path.content_mask.bounds)
+            .map_origin(|origin| origin.floor())
+            .map_size(|size| size.ceil());
+        let tile = self.atlas.allocate_for_rendering(
+            clipped_bounds.size.map(Into::into),
+            AtlasTextureKind::Path,
+            &mut self.command_encoder,
+        );
+        vertices_by_texture_id
+            .entry(tile.texture_id)
+            .or_insert(Vec::new())
+            .extend(path.vertices.iter().map(|vertex| PathVertex {
+                xy_position: vertex.xy_position - clipped_bounds.origin
+                    + tile.bounds.origin.map(Into::into),
+                st_position: vertex.st_position,
+                content_mask: ContentMask {
+                    bounds: tile.bounds.map(Into::into),
+                },
+            }));
+        tile
+    }
+
     pub fn destroy(&mut self) {
         self.wait_for_gpu();
-        self.atlas.destroy();
+        self.atlas.remove_renderer();
         self.gpu.destroy_sampler(self.atlas_sampler);
+        if let Some(mut blur_textures) = self.blur_textures.take() {
+            blur_textures.destroy(&self.gpu);
+        }
+        if let Some(mut clip_backdrops) = self.clip_backdrops.take() {
+            clip_backdrops.destroy(&self.gpu);
+        }
+        if let Some(mut retained_frame) = self.retained_frame.take() {
+            retained_frame.destroy(&self.gpu);
+        }
+        self.destroy_path_msaa_textures();
         self.instance_belt.destroy(&self.gpu);
         self.gpu.destroy_command_encoder(&mut self.command_encoder);
         self.pipelines.destroy(&self.gpu);
         self.gpu.destroy_surface(&mut self.surface);
     }
 
+    /// Copies the frame where `clips` start, see [`ClipBackdrops`].
+    fn save_clip_backdrop(&mut self, frame: gpu::Texture, clips: &[PathClip]) {
+        let size = self.surface_config.size;
+        if self
+            .clip_backdrops
+            .as_ref()
+            .map_or(true, |backdrops| backdrops.size != size)
+        {
+            if let Some(mut backdrops) = self.clip_backdrops.take() {
+                backdrops.destroy(&self.gpu);
+            }
+            self.clip_backdrops = Some(ClipBackdrops::new(size));
+        }
+        let Some(backdrops) = self.clip_backdrops.as_mut() else {
+            return;
+        };
+
+        let ix = backdrops.acquire(
+            &self.gpu,
+            self.surface.info().format,
+            &mut self.command_encoder,
+        );
+        backdrops
+            .clips
+            .extend(clips.iter().map(|clip| (clip.id, ix)));
+        if let mut transfer = self.command_encoder.transfer("clip backdrop") {
+            transfer.copy_texture_to_texture(frame.into(), backdrops.textures[ix].0.into(), size);
+        }
+    }
+
+    /// Blurs the frame below `blurs`, horizontally into a scratch texture and then vertically
+    /// back into the frame within `scissor`.
+    fn blur_backdrop(
+        &mut self,
+        frame: gpu::Texture,
+        frame_view: gpu::TextureView,
+        blurs: &[Blur],
+        globals: GlobalParams,
+        scissor: &gpu::ScissorRect,
+    ) {
+        let size = self.surface_config.size;
+        if self
+            .blur_textures
+            .as_ref()
+            .map_or(true, |textures| textures.size != size)
+        {
+            if let Some(mut textures) = self.blur_textures.take() {
+                textures.destroy(&self.gpu);
+            }
+            let textures = BlurTextures::new(&self.gpu, self.surface.info().format, size);
+            self.command_encoder.init_texture(textures.backdrop);
+            self.command_encoder.init_texture(textures.horizontal);
+            self.blur_textures = Some(textures);
+        }
+        let Some(textures) = &self.blur_textures else {
+            return;
+        };
+
+        if let mut transfer = self.command_encoder.transfer("blur backdrop") {
+            transfer.copy_texture_to_texture(frame.into(), textures.backdrop.into(), size);
+        }
+
+        let instance_buf = unsafe { self.instance_belt.alloc_typed(blurs, &self.gpu) };
+        if let mut pass = self.command_encoder.render(
+            "blur horizontal",
+            gpu::RenderTargetSet {
+                colors: &[gpu::RenderTarget {
+                    view: textures.horizontal_view,
+                    init_op: gpu::InitOp::Clear(gpu::TextureColor::TransparentBlack),
+                    finish_op: gpu::FinishOp::Store,
+                }],
+                depth_stencil: None,
+            },
+        ) {
+            let mut encoder = pass.with(&self.pipelines.blur_horizontal);
+            encoder.bind(
+                0,
+                &ShaderBlursData {
+                    globals,
+                    blur_params: BlurParams {
+                        direction: [1., 0.],
+                        pad: [0; 2],
+                    },
+                    t_blur: textures.backdrop_view,
+                    s_blur: self.atlas_sampler,
+                    b_blurs: instance_buf,
+                },
+            );
+            encoder.draw(0, 4, 0, blurs.len() as u32);
+            self.stats.draw_calls += 1;
+        }
+
+        if let mut pass = self.command_encoder.render(
+            "blur vertical",
+            gpu::RenderTargetSet {
+                colors: &[gpu::RenderTarget {
+                    view: frame_view,
+                    init_op: gpu::InitOp::Load,
+                    finish_op: gpu::FinishOp::Store,
+                }],
+                depth_stencil: None,
+            },
+        ) {
+            let mut encoder = pass.with(&self.pipelines.blurs);
+            encoder.set_scissor_rect(scissor);
+            encoder.bind(
+                0,
+                &ShaderBlursData {
+                    globals,
+                    blur_params: BlurParams {
+                        direction: [0., 1.],
+                        pad: [0; 2],
+                    },
+                    t_blur: textures.horizontal_view,
+                    s_blur: self.atlas_sampler,
+                    b_blurs: instance_buf,
+                },
+            );
+            encoder.draw(0, 4, 0, blurs.len() as u32);
+            self.stats.draw_calls += 1;
+        }
+    }
+
     pub fn draw(&mut self, scene: &Scene) {
+        self.stats = RenderStats::default();
+        let mut damage = self.damage(scene);
+        if damage == Damage::None {
+            // The last presented frame is still up to date, skip the GPU work entirely.
+            return;
+        }
+        self.needs_full_redraw = false;
+        self.transient_released = false;
+        if self.surface_released {
+            self.surface_released = false;
+            self.reconfigure_surface();
+        }
+
         self.command_encoder.start();
-        self.atlas.before_frame(&mut self.command_encoder);
-        self.rasterize_paths(scene.paths());
+        self.atlas
+            .before_frame(&mut self.command_encoder, &mut self.stats);
+        self.rasterize_paths(scene);
 
-        let frame = {
-            profiling::scope!("acquire frame");
-            self.surface.acquire_frame()
+        let size = self.surface_config.size;
+        if self
+            .retained_frame
+            .as_ref()
+            .map_or(true, |retained| retained.size != size)
+        {
+            if let Some(mut retained) = self.retained_frame.take() {
+                retained.destroy(&self.gpu);
+            }
+            let retained = RetainedFrame::new(&self.gpu, self.surface.info().format, size);
+            self.command_encoder.init_texture(retained.texture);
+            self.retained_frame = Some(retained);
+            damage = Damage::Full;
+        }
+        let Some(&RetainedFrame {
+            texture: retained_texture,
+            view: retained_view,
+            ..
+        }) = self.retained_frame.as_ref()
+        else {
+            return;
         };
-        self.command_encoder.init_texture(frame.texture());
+        // Only the damaged area is repainted, the rest of the retained frame is still up to date.
+        let scissor = damage_scissor(&damage, size);
 
         let globals = GlobalParams {
             viewport_size: [
@@ -570,220 +1467,423 @@ impl BladeRenderer {
             pad: 0,
         };
 
-        if let mut pass = self.command_encoder.render(
-            "main",
-            gpu::RenderTargetSet {
-                colors: &[gpu::RenderTarget {
-                    view: frame.texture_view(),
-                    init_op: gpu::InitOp::Clear(gpu::TextureColor::TransparentBlack),
-                    finish_op: gpu::FinishOp::Store,
-                }],
-                depth_stencil: None,
+        let mut batches = scene.batches();
+        let mut init_op = match damage {
+            Damage::Partial(_) => gpu::InitOp::Load,
+            Damage::Full | Damage::None => gpu::InitOp::Clear(gpu::TextureColor::TransparentBlack),
+        };
+        // The damaged area of a loaded frame is cleared before it's painted over.
+        let mut clear_bounds = matches!(damage, Damage::Partial(_)).then(|| Bounds {
+            origin: Point {
+                x: ScaledPixels(scissor.x as f32),
+                y: ScaledPixels(scissor.y as f32),
             },
-        ) {
-            profiling::scope!("render pass");
-            for batch in scene.batches() {
-                match batch {
-                    PrimitiveBatch::Quads(quads) => {
-                        let instance_buf =
-                            unsafe { self.instance_belt.alloc_typed(quads, &self.gpu) };
-                        let mut encoder = pass.with(&self.pipelines.quads);
-                        encoder.bind(
-                            0,
-                            &ShaderQuadsData {
-                                globals,
-                                b_quads: instance_buf,
-                            },
-                        );
-                        encoder.draw(0, 4, 0, quads.len() as u32);
-                    }
-                    PrimitiveBatch::Shadows(shadows) => {
-                        let instance_buf =
-                            unsafe { self.instance_belt.alloc_typed(shadows, &self.gpu) };
-                        let mut encoder = pass.with(&self.pipelines.shadows);
-                        encoder.bind(
-                            0,
-                            &ShaderShadowsData {
-                                globals,
-                                b_shadows: instance_buf,
-                            },
-                        );
-                        encoder.draw(0, 4, 0, shadows.len() as u32);
-                    }
-                    PrimitiveBatch::Paths(paths) => {
-                        let mut encoder = pass.with(&self.pipelines.paths);
-                        // todo(linux): group by texture ID
-                        for path in paths {
-                            let tile = &self.path_tiles[&path.id];
-                            let tex_info = self.atlas.get_texture_info(tile.texture_id);
-                            let origin = path.bounds.intersect(&path.content_mask.bounds).origin;
-                            let sprites = [PathSprite {
-                                bounds: Bounds {
-                                    origin: origin.map(|p| p.floor()),
-                                    size: tile.bounds.size.map(Into::into),
+            size: Size {
+                width: ScaledPixels(scissor.w as f32),
+                height: ScaledPixels(scissor.h as f32),
+            },
+        });
+        loop {
+            // Blurs and the starts of path clips read what was drawn before them, so they end
+            // the render pass.
+            let mut blurs = None;
+            let mut clip_starts = None;
+            if let mut pass = self.command_encoder.render(
+                "main",
+                gpu::RenderTargetSet {
+                    colors: &[gpu::RenderTarget {
+                        view: retained_view,
+                        init_op,
+                        finish_op: gpu::FinishOp::Store,
+                    }],
+                    depth_stencil: None,
+                },
+            ) {
+                profiling::scope!("render pass");
+                if let Some(bounds) = clear_bounds.take() {
+                    let clear = Quad {
+                        bounds,
+                        content_mask: ContentMask { bounds },
+                        ..Default::default()
+                    };
+                    let instance_buf =
+                        unsafe { self.instance_belt.alloc_typed(&[clear], &self.gpu) };
+                    let mut encoder = pass.with(&self.pipelines.clear);
+                    encoder.set_scissor_rect(&scissor);
+                    encoder.bind(
+                        0,
+                        &ShaderQuadsData {
+                            globals,
+                            b_quads: instance_buf,
+                        },
+                    );
+                    encoder.draw(0, 4, 0, 1);
+                    self.stats.draw_calls += 1;
+                }
+                for batch in batches.by_ref() {
+                    match batch {
+                        PrimitiveBatch::Blurs(batch_blurs) => {
+                            blurs = Some(batch_blurs);
+                            break;
+                        }
+                        PrimitiveBatch::PathClips(clips) if clips[0].path.is_none() => {
+                            clip_starts = Some(clips);
+                            break;
+                        }
+                        PrimitiveBatch::PathClips(clips) => {
+                            let Some(backdrops) = self.clip_backdrops.as_mut() else {
+                                continue;
+                            };
+                            // Clips in a batch don't overlap, so they're erased and restored
+                            // one at a time without affecting each other.
+                            for clip in clips {
+                                let (Some(tile), Some(ix)) = (
+                                    self.clip_tiles.get(&clip.id),
+                                    backdrops.clips.remove(&clip.id),
+                                ) else {
+                                    continue;
+                                };
+                                let tex_info = self.atlas.get_texture_info(tile.texture_id);
+                                let origin =
+                                    clip.bounds.intersect(&clip.content_mask.bounds).origin;
+                                let sprite = PathClipSprite {
+                                    bounds: Bounds {
+                                        origin: origin.map(|p| p.floor()),
+                                        size: tile.bounds.size.map(Into::into),
+                                    },
+                                    tile: tile.clone(),
+                                };
+                                let instance_buf =
+                                    unsafe { self.instance_belt.alloc_typed(&[sprite], &self.gpu) };
+                                let data = ShaderPathClipsData {
+                                    globals,
+                                    t_sprite: tex_info.raw_view,
+                                    s_sprite: self.atlas_sampler,
+                                    t_backdrop: backdrops.textures[ix].1,
+                                    b_path_clip_sprites: instance_buf,
+                                };
+                                for pipeline in [
+                                    &self.pipelines.path_clip_erase,
+                                    &self.pipelines.path_clip_restore,
+                                ] {
+                                    let mut encoder = pass.with(pipeline);
+                                    encoder.set_scissor_rect(&scissor);
+                                    encoder.bind(0, &data);
+                                    encoder.draw(0, 4, 0, 1);
+                                    self.stats.draw_calls += 1;
+                                }
+                            }
+                        }
+                        PrimitiveBatch::Quads(quads) => {
+                            let instance_buf =
+                                unsafe { self.instance_belt.alloc_typed(quads, &self.gpu) };
+                            let blend_mode = quads[0].blend_mode;
+                            let mut encoder = pass.with(self.pipelines.blended(
+                                &self.gpu,
+                                BlendedPipeline::Quads,
+                                blend_mode,
+                            ));
+                            encoder.set_scissor_rect(&scissor);
+                            encoder.bind(
+                                0,
+                                &ShaderQuadsData {
+                                    globals: globals.for_blend_mode(blend_mode),
+                                    b_quads: instance_buf,
+                                },
+                            );
+                            encoder.draw(0, 4, 0, quads.len() as u32);
+                            self.stats.draw_calls += 1;
+                        }
+                        PrimitiveBatch::Shadows(shadows) => {
+                            let instance_buf =
+                                unsafe { self.instance_belt.alloc_typed(shadows, &self.gpu) };
+                            let blend_mode = shadows[0].blend_mode;
+                            let mut encoder = pass.with(self.pipelines.blended(
+                                &self.gpu,
+                                BlendedPipeline::Shadows,
+                                blend_mode,
+                            ));
+                            encoder.set_scissor_rect(&scissor);
+                            encoder.bind(
+                                0,
+                                &ShaderShadowsData {
+                                    globals: globals.for_blend_mode(blend_mode),
+                                    b_shadows: instance_buf,
                                 },
-                                color: path.color,
-                                tile: (*tile).clone(),
-                            }];
+                            );
+                            encoder.draw(0, 4, 0, shadows.len() as u32);
+                            self.stats.draw_calls += 1;
+                        }
+                        PrimitiveBatch::Paths(paths) => {
+                            let blend_mode = paths[0].blend_mode;
+                            let mut encoder = pass.with(self.pipelines.blended(
+                                &self.gpu,
+                                BlendedPipeline::Paths,
+                                blend_mode,
+                            ));
+                            encoder.set_scissor_rect(&scissor);
+                            // Instances are blended in order, so consecutive paths rasterized into
+                            // the same texture can be drawn together.
+                            for paths in paths.chunk_by(|a, b| {
+                                self.path_tiles[&a.id].texture_id
+                                    == self.path_tiles[&b.id].texture_id
+                            }) {
+                                let texture_id = self.path_tiles[&paths[0].id].texture_id;
+                                let tex_info = self.atlas.get_texture_info(texture_id);
+                                let sprites = paths
+                                    .iter()
+                                    .map(|path| {
+                                        let tile = &self.path_tiles[&path.id];
+                                        let origin =
+                                            path.bounds.intersect(&path.content_mask.bounds).origin;
+                                        PathSprite {
+                                            bounds: Bounds {
+                                                origin: origin.map(|p| p.floor()),
+                                                size: tile.bounds.size.map(Into::into),
+                                            },
+                                            color: path.color,
+                                            tile: tile.clone(),
+                                        }
+                                    })
+                                    .collect::<Vec<_>>();
 
+                                let instance_buf =
+                                    unsafe { self.instance_belt.alloc_typed(&sprites, &self.gpu) };
+                                encoder.bind(
+                                    0,
+                                    &ShaderPathsData {
+                                        globals: globals.for_blend_mode(blend_mode),
+                                        t_sprite: tex_info.raw_view,
+                                        s_sprite: self.atlas_sampler,
+                                        b_path_sprites: instance_buf,
+                                    },
+                                );
+                                encoder.draw(0, 4, 0, sprites.len() as u32);
+                                self.stats.draw_calls += 1;
+                            }
+                        }
+                        PrimitiveBatch::Underlines(underlines) => {
                             let instance_buf =
-                                unsafe { self.instance_belt.alloc_typed(&sprites, &self.gpu) };
+                                unsafe { self.instance_belt.alloc_typed(underlines, &self.gpu) };
+                            let blend_mode = underlines[0].blend_mode;
+                            let mut encoder = pass.with(self.pipelines.blended(
+                                &self.gpu,
+                                BlendedPipeline::Underlines,
+                                blend_mode,
+                            ));
+                            encoder.set_scissor_rect(&scissor);
                             encoder.bind(
                                 0,
-                                &ShaderPathsData {
-                                    globals,
+                                &ShaderUnderlinesData {
+                                    globals: globals.for_blend_mode(blend_mode),
+                                    b_underlines: instance_buf,
+                                },
+                            );
+                            encoder.draw(0, 4, 0, underlines.len() as u32);
+                            self.stats.draw_calls += 1;
+                        }
+                        PrimitiveBatch::MonochromeSprites {
+                            texture_id,
+                            sprites,
+                        } => {
+                            let tex_info = self.atlas.get_texture_info(texture_id);
+                            let instance_buf =
+                                unsafe { self.instance_belt.alloc_typed(sprites, &self.gpu) };
+                            let blend_mode = sprites[0].blend_mode;
+                            let mut encoder = pass.with(self.pipelines.blended(
+                                &self.gpu,
+                                BlendedPipeline::MonoSprites,
+                                blend_mode,
+                            ));
+                            encoder.set_scissor_rect(&scissor);
+                            encoder.bind(
+                                0,
+                                &ShaderMonoSpritesData {
+                                    globals: globals.for_blend_mode(blend_mode),
                                     t_sprite: tex_info.raw_view,
                                     s_sprite: self.atlas_sampler,
-                                    b_path_sprites: instance_buf,
+                                    b_mono_sprites: instance_buf,
                                 },
                             );
                             encoder.draw(0, 4, 0, sprites.len() as u32);
+                            self.stats.draw_calls += 1;
                         }
-                    }
-                    PrimitiveBatch::Underlines(underlines) => {
-                        let instance_buf =
-                            unsafe { self.instance_belt.alloc_typed(underlines, &self.gpu) };
-                        let mut encoder = pass.with(&self.pipelines.underlines);
-                        encoder.bind(
-                            0,
-                            &ShaderUnderlinesData {
-                                globals,
-                                b_underlines: instance_buf,
-                            },
-                        );
-                        encoder.draw(0, 4, 0, underlines.len() as u32);
-                    }
-                    PrimitiveBatch::MonochromeSprites {
-                        texture_id,
-                        sprites,
-                    } => {
-                        let tex_info = self.atlas.get_texture_info(texture_id);
-                        let instance_buf =
-                            unsafe { self.instance_belt.alloc_typed(sprites, &self.gpu) };
-                        let mut encoder = pass.with(&self.pipelines.mono_sprites);
-                        encoder.bind(
-                            0,
-                            &ShaderMonoSpritesData {
-                                globals,
-                                t_sprite: tex_info.raw_view,
-                                s_sprite: self.atlas_sampler,
-                                b_mono_sprites: instance_buf,
-                            },
-                        );
-                        encoder.draw(0, 4, 0, sprites.len() as u32);
-                    }
-                    PrimitiveBatch::PolychromeSprites {
-                        texture_id,
-                        sprites,
-                    } => {
-                        let tex_info = self.atlas.get_texture_info(texture_id);
-                        let instance_buf =
-                            unsafe { self.instance_belt.alloc_typed(sprites, &self.gpu) };
-                        let mut encoder = pass.with(&self.pipelines.poly_sprites);
-                        encoder.bind(
-                            0,
-                            &ShaderPolySpritesData {
-                                globals,
-                                t_sprite: tex_info.raw_view,
-                                s_sprite: self.atlas_sampler,
-                                b_poly_sprites: instance_buf,
-                            },
-                        );
-                        encoder.draw(0, 4, 0, sprites.len() as u32);
-                    }
-                    PrimitiveBatch::Surfaces(surfaces) => {
-                        let mut _encoder = pass.with(&self.pipelines.surfaces);
+                        PrimitiveBatch::PolychromeSprites {
+                            texture_id,
+                            sprites,
+                        } => {
+                            let tex_info = self.atlas.get_texture_info(texture_id);
+                            let instance_buf =
+                                unsafe { self.instance_belt.alloc_typed(sprites, &self.gpu) };
+                            let blend_mode = sprites[0].blend_mode;
+                            let mut encoder = pass.with(self.pipelines.blended(
+                                &self.gpu,
+                                BlendedPipeline::PolySprites,
+                                blend_mode,
+                            ));
+                            encoder.set_scissor_rect(&scissor);
+                            encoder.bind(
+                                0,
+                                &ShaderPolySpritesData {
+                                    globals: globals.for_blend_mode(blend_mode),
+                                    t_sprite: tex_info.raw_view,
+                                    s_sprite: self.atlas_sampler,
+                                    b_poly_sprites: instance_buf,
+                                },
+                            );
+                            encoder.draw(0, 4, 0, sprites.len() as u32);
+                            self.stats.draw_calls += 1;
+                        }
+                        PrimitiveBatch::Surfaces(surfaces) => {
+                            let mut _encoder = pass.with(&self.pipelines.surfaces);
+                            _encoder.set_scissor_rect(&scissor);
 
-                        for surface in surfaces {
-                            #[cfg(not(target_os = "macos"))]
-                            {
-                                let _ = surface;
-                                continue;
-                            };
+                            for surface in surfaces {
+                                #[cfg(not(target_os = "macos"))]
+                                {
+                                    let _ = surface;
+                                    continue;
+                                };
 
-                            #[cfg(target_os = "macos")]
-                            {
-                                let (t_y, t_cb_cr) = unsafe {
-                                    use core_foundation::base::TCFType as _;
-                                    use std::ptr;
+                                #[cfg(target_os = "macos")]
+                                {
+                                    let (t_y, t_cb_cr) = unsafe {
+                                        use core_foundation::base::TCFType as _;
+                                        use std::ptr;
 
-                                    assert_eq!(
-                                        surface.image_buffer.pixel_format_type(),
-                                        media::core_video::kCVPixelFormatType_420YpCbCr8BiPlanarFullRange
-                                    );
+                                        assert_eq!(
+                                            surface.image_buffer.pixel_format_type(),
+                                            media::core_video::kCVPixelFormatType_420YpCbCr8BiPlanarFullRange
+                                        );
 
-                                    let y_texture = self
-                                        .core_video_texture_cache
-                                        .create_texture_from_image(
-                                            surface.image_buffer.as_concrete_TypeRef(),
-                                            ptr::null(),
-                                            metal::MTLPixelFormat::R8Unorm,
-                                            surface.image_buffer.plane_width(0),
-                                            surface.image_buffer.plane_height(0),
-                                            0,
-                                        )
-                                        .unwrap();
-                                    let cb_cr_texture = self
-                                        .core_video_texture_cache
-                                        .create_texture_from_image(
-                                            surface.image_buffer.as_concrete_TypeRef(),
-                                            ptr::null(),
-                                            metal::MTLPixelFormat::RG8Unorm,
-                                            surface.image_buffer.plane_width(1),
-                                            surface.image_buffer.plane_height(1),
-                                            1,
-                                        )
-                                        .unwrap();
-                                    (
-                                        gpu::TextureView::from_metal_texture(
-                                            &objc2::rc::Retained::retain(
-                                                foreign_types::ForeignTypeRef::as_ptr(
-                                                    y_texture.as_texture_ref(),
-                                                )
-                                                    as *mut objc2::runtime::ProtocolObject<
-                                                        dyn objc2_metal::MTLTexture,
-                                                    >,
+                                        let y_texture = self
+                                            .core_video_texture_cache
+                                            .create_texture_from_image(
+                                                surface.image_buffer.as_concrete_TypeRef(),
+                                                ptr::null(),
+                                                metal::MTLPixelFormat::R8Unorm,
+                                                surface.image_buffer.plane_width(0),
+                                                surface.image_buffer.plane_height(0),
+                                                0,
                                             )
-                                            .unwrap(),
-                                        ),
-                                        gpu::TextureView::from_metal_texture(
-                                            &objc2::rc::Retained::retain(
-                                                foreign_types::ForeignTypeRef::as_ptr(
-                                                    cb_cr_texture.as_texture_ref(),
-                                                )
-                                                    as *mut objc2::runtime::ProtocolObject<
-                                                        dyn objc2_metal::MTLTexture,
-                                                    >,
+                                            .unwrap();
+                                        let cb_cr_texture = self
+                                            .core_video_texture_cache
+                                            .create_texture_from_image(
+                                                surface.image_buffer.as_concrete_TypeRef(),
+                                                ptr::null(),
+                                                metal::MTLPixelFormat::RG8Unorm,
+                                                surface.image_buffer.plane_width(1),
+                                                surface.image_buffer.plane_height(1),
+                                                1,
                                             )
-                                            .unwrap(),
-                                        ),
-                                    )
-                                };
+                                            .unwrap();
+                                        (
+                                            gpu::TextureView::from_metal_texture(
+                                                &objc2::rc::Retained::retain(
+                                                    foreign_types::ForeignTypeRef::as_ptr(
+                                                        y_texture.as_texture_ref(),
+                                                    )
+                                                        as *mut objc2::runtime::ProtocolObject<
+                                                            dyn objc2_metal::MTLTexture,
+                                                        >,
+                                                )
+                                                .unwrap(),
+                                            ),
+                                            gpu::TextureView::from_metal_texture(
+                                                &objc2::rc::Retained::retain(
+                                                    foreign_types::ForeignTypeRef::as_ptr(
+                                                        cb_cr_texture.as_texture_ref(),
+                                                    )
+                                                        as *mut objc2::runtime::ProtocolObject<
+                                                            dyn objc2_metal::MTLTexture,
+                                                        >,
+                                                )
+                                                .unwrap(),
+                                            ),
+                                        )
+                                    };
+
+                                    _encoder.bind(
+                                        0,
+                                        &ShaderSurfacesData {
+                                            globals,
+                                            surface_locals: SurfaceParams {
+                                                bounds: surface.bounds.into(),
+                                                content_mask: surface.content_mask.bounds.into(),
+                                            },
+                                            t_y,
+                                            t_cb_cr,
+                                            s_surface: self.atlas_sampler,
+                                        },
+                                    );
+
+                                    _encoder.draw(0, 4, 0, 1);
 
-                                _encoder.bind(
+                                    self.stats.draw_calls += 1;
+                                }
+                            }
+                        }
+                        PrimitiveBatch::Shaders(shaders) => {
+                            let blend_mode = shaders[0].blend_mode;
+                            let Some(pipeline) = self.pipelines.custom_shader(
+                                &self.gpu,
+                                &shaders[0].shader,
+                                blend_mode,
+                            ) else {
+                                continue;
+                            };
+                            let mut encoder = pass.with(pipeline);
+                            encoder.set_scissor_rect(&scissor);
+                            for shader in shaders {
+                                encoder.bind(
                                     0,
-                                    &ShaderSurfacesData {
-                                        globals,
-                                        surface_locals: SurfaceParams {
-                                            bounds: surface.bounds.into(),
-                                            content_mask: surface.content_mask.bounds.into(),
+                                    &ShaderCustomData {
+                                        globals: globals.for_blend_mode(blend_mode),
+                                        shader_locals: CustomShaderParams {
+                                            bounds: shader.bounds.into(),
+                                            content_mask: shader.content_mask.bounds.into(),
+                                            mouse_position: [
+                                                shader.mouse_position.x.0,
+                                                shader.mouse_position.y.0,
+                                            ],
+                                            time: shader.time,
+                                            pad: 0,
                                         },
-                                        t_y,
-                                        t_cb_cr,
-                                        s_surface: self.atlas_sampler,
                                     },
                                 );
-
-                                _encoder.draw(0, 4, 0, 1);
+                                encoder.draw(0, 4, 0, 1);
+                                self.stats.draw_calls += 1;
                             }
                         }
                     }
                 }
             }
+
+            if let Some(blurs) = blurs {
+                self.blur_backdrop(retained_texture, retained_view, blurs, globals, &scissor);
+            } else if let Some(clips) = clip_starts {
+                self.save_clip_backdrop(retained_texture, clips);
+            } else {
+                break;
+            }
+            init_op = gpu::InitOp::Load;
+        }
+
+        let frame = {
+            profiling::scope!("acquire frame");
+            self.surface.acquire_frame()
+        };
+        self.command_encoder.init_texture(frame.texture());
+        if let mut transfer = self.command_encoder.transfer("present") {
+            transfer.copy_texture_to_texture(retained_texture.into(), frame.texture().into(), size);
         }
 
-        self.command_encoder.present(frame);
-        let sync_point = self.gpu.submit(&mut self.command_encoder);
+        let sync_point = {
+            profiling::scope!("submit");
+            self.command_encoder.present(frame);
+            self.gpu.submit(&mut self.command_encoder)
+        };
 
         profiling::scope!("finish");
         self.instance_belt.flush(&sync_point);
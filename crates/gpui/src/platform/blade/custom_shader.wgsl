@@ -0,0 +1,67 @@
+// Prelude of custom shaders, the user provided source defining `shade` is appended to it.
+
+struct GlobalParams {
+    viewport_size: vec2<f32>,
+    premultiplied_alpha: u32,
+    pad: u32,
+}
+
+struct Bounds {
+    origin: vec2<f32>,
+    size: vec2<f32>,
+}
+
+struct CustomShaderParams {
+    bounds: Bounds,
+    content_mask: Bounds,
+    mouse_position: vec2<f32>,
+    time: f32,
+    pad: u32,
+}
+
+struct ShaderInput {
+    position: vec2<f32>,
+    size: vec2<f32>,
+    mouse: vec2<f32>,
+    time: f32,
+}
+
+var<uniform> globals: GlobalParams;
+var<uniform> shader_locals: CustomShaderParams;
+
+struct CustomShaderVarying {
+    @builtin(position) position: vec4<f32>,
+    @location(3) clip_distances: vec4<f32>,
+}
+
+@vertex
+fn vs_custom_shader(@builtin(vertex_index) vertex_id: u32) -> CustomShaderVarying {
+    let unit_vertex = vec2<f32>(f32(vertex_id & 1u), 0.5 * f32(vertex_id & 2u));
+    let bounds = shader_locals.bounds;
+    let position = unit_vertex * bounds.size + bounds.origin;
+    let clip_bounds = shader_locals.content_mask;
+    let tl = position - clip_bounds.origin;
+    let br = clip_bounds.origin + clip_bounds.size - position;
+
+    var out = CustomShaderVarying();
+    out.position = vec4<f32>(position / globals.viewport_size * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+    out.clip_distances = vec4<f32>(tl.x, br.x, tl.y, br.y);
+    return out;
+}
+
+@fragment
+fn fs_custom_shader(input: CustomShaderVarying) -> @location(0) vec4<f32> {
+    if (any(input.clip_distances < vec4<f32>(0.0))) {
+        return vec4<f32>(0.0);
+    }
+
+    var shader_input: ShaderInput;
+    shader_input.position = input.position.xy - shader_locals.bounds.origin;
+    shader_input.size = shader_locals.bounds.size;
+    shader_input.mouse = shader_locals.mouse_position;
+    shader_input.time = shader_locals.time;
+
+    let color = shade(shader_input);
+    let multiplier = select(1.0, color.a, globals.premultiplied_alpha != 0u);
+    return vec4<f32>(color.rgb * multiplier, color.a);
+}
@@ -383,7 +383,7 @@ fn gradient_color(background: Background, position: vec2<f32>, bounds: Bounds,
 
 struct Quad {
     order: u32,
-    pad: u32,
+    blend_mode: u32,
     bounds: Bounds,
     content_mask: Bounds,
     background: Background,
@@ -490,6 +490,8 @@ fn fs_quad(input: QuadVarying) -> @location(0) vec4<f32> {
 struct Shadow {
     order: u32,
     blur_radius: f32,
+    blend_mode: u32,
+    pad: u32,
     bounds: Bounds,
     corner_radii: Corners,
     content_mask: Bounds,
@@ -544,11 +546,13 @@ fn fs_shadow(input: ShadowVarying) -> @location(0) vec4<f32> {
     let start = clamp(-3.0 * shadow.blur_radius, low, high);
     let end = clamp(3.0 * shadow.blur_radius, low, high);
 
-    // Accumulate samples (we can get away with surprisingly few samples)
-    let step = (end - start) / 4.0;
+    // Accumulate samples (we can get away with surprisingly few samples), wide shadows
+    // get more of them so their falloff stays smooth.
+    let sample_count = clamp(i32(ceil((end - start) / 8.0)), 4, 16);
+    let step = (end - start) / f32(sample_count);
     var y = start + step * 0.5;
     var alpha = 0.0;
-    for (var i = 0; i < 4; i += 1) {
+    for (var i = 0; i < sample_count; i += 1) {
         let blur = blur_along_x(center_to_point.x, center_to_point.y - y,
             shadow.blur_radius, corner_radius, half_size);
         alpha +=  blur * gaussian(y, shadow.blur_radius) * step;
@@ -558,6 +562,91 @@ fn fs_shadow(input: ShadowVarying) -> @location(0) vec4<f32> {
     return blend_color(input.color, alpha);
 }
 
+// --- blurs --- //
+
+struct Blur {
+    order: u32,
+    radius: f32,
+    bounds: Bounds,
+    corner_radii: Corners,
+    content_mask: Bounds,
+}
+var<storage, read> b_blurs: array<Blur>;
+
+struct BlurParams {
+    direction: vec2<f32>,
+    pad: vec2<u32>,
+}
+var<uniform> blur_params: BlurParams;
+var t_blur: texture_2d<f32>;
+var s_blur: sampler;
+
+struct BlurVarying {
+    @builtin(position) position: vec4<f32>,
+    @location(0) @interpolate(flat) blur_id: u32,
+    //TODO: use `clip_distance` once Naga supports it
+    @location(3) clip_distances: vec4<f32>,
+}
+
+@vertex
+fn vs_blur(@builtin(vertex_index) vertex_id: u32, @builtin(instance_index) instance_id: u32) -> BlurVarying {
+    let unit_vertex = vec2<f32>(f32(vertex_id & 1u), 0.5 * f32(vertex_id & 2u));
+    var blur = b_blurs[instance_id];
+
+    // The horizontal pass also covers the rows the vertical pass samples above and below
+    // the bounds.
+    let margin = vec2<f32>(0.0, 3.0 * blur.radius * blur_params.direction.x);
+    let content_mask = blur.content_mask;
+    blur.bounds.origin -= margin;
+    blur.bounds.size += 2.0 * margin;
+
+    var out = BlurVarying();
+    out.position = to_device_position(unit_vertex, blur.bounds);
+    out.blur_id = instance_id;
+    out.clip_distances = distance_from_clip_rect(unit_vertex, blur.bounds, content_mask);
+    return out;
+}
+
+// Blurs `t_blur` around `position` along the direction of the pass.
+fn gaussian_blur(position: vec2<f32>, radius: f32) -> vec4<f32> {
+    let texture_size = vec2<f32>(textureDimensions(t_blur, 0));
+    let extent = ceil(3.0 * radius);
+    // Wide blurs skip texels, the linear filtering averages the ones in between.
+    let step = max(1.0, extent / 32.0);
+    var color = vec4<f32>(0.0);
+    var total_weight = 0.0;
+    for (var offset = -extent; offset <= extent; offset += step) {
+        let weight = gaussian(offset, radius);
+        let uv = (position + offset * blur_params.direction) / texture_size;
+        color += weight * textureSampleLevel(t_blur, s_blur, uv, 0.0);
+        total_weight += weight;
+    }
+    return color / total_weight;
+}
+
+@fragment
+fn fs_blur_horizontal(input: BlurVarying) -> @location(0) vec4<f32> {
+    return gaussian_blur(input.position.xy, b_blurs[input.blur_id].radius);
+}
+
+@fragment
+fn fs_blur(input: BlurVarying) -> @location(0) vec4<f32> {
+    // Alpha clip first, since we don't have `clip_distance`.
+    if (any(input.clip_distances < vec4<f32>(0.0))) {
+        return vec4<f32>(0.0);
+    }
+
+    let blur = b_blurs[input.blur_id];
+    let color = gaussian_blur(input.position.xy, blur.radius);
+    let distance = quad_sdf(input.position.xy, blur.bounds, blur.corner_radii);
+    let coverage = saturate(0.5 - distance);
+    // The backdrop is already stored the way the surface expects it.
+    if (globals.premultiplied_alpha != 0u) {
+        return color * coverage;
+    }
+    return vec4<f32>(color.rgb, color.a * coverage);
+}
+
 // --- path rasterization --- //
 
 struct PathVertex {
@@ -651,11 +740,55 @@ fn fs_path(input: PathVarying) -> @location(0) vec4<f32> {
     return blend_color(color, mask);
 }
 
+// --- path clips --- //
+
+struct PathClipSprite {
+    bounds: Bounds,
+    tile: AtlasTile,
+}
+var<storage, read> b_path_clip_sprites: array<PathClipSprite>;
+// A copy of the frame from where the clip started.
+var t_backdrop: texture_2d<f32>;
+
+struct PathClipVarying {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tile_position: vec2<f32>,
+}
+
+@vertex
+fn vs_path_clip(@builtin(vertex_index) vertex_id: u32, @builtin(instance_index) instance_id: u32) -> PathClipVarying {
+    let unit_vertex = vec2<f32>(f32(vertex_id & 1u), 0.5 * f32(vertex_id & 2u));
+    let sprite = b_path_clip_sprites[instance_id];
+
+    var out = PathClipVarying();
+    out.position = to_device_position(unit_vertex, sprite.bounds);
+    out.tile_position = to_tile_position(unit_vertex, sprite.tile);
+    return out;
+}
+
+fn path_clip_coverage(tile_position: vec2<f32>) -> f32 {
+    let sample = textureSample(t_sprite, s_sprite, tile_position).r;
+    return 1.0 - abs(1.0 - sample % 2.0);
+}
+
+// Scales what was drawn since the clip started by the coverage of the path.
+@fragment
+fn fs_path_clip_erase(input: PathClipVarying) -> @location(0) vec4<f32> {
+    return vec4<f32>(path_clip_coverage(input.tile_position));
+}
+
+// Adds back the backdrop where the path doesn't cover it.
+@fragment
+fn fs_path_clip_restore(input: PathClipVarying) -> @location(0) vec4<f32> {
+    let backdrop = textureLoad(t_backdrop, vec2<i32>(input.position.xy), 0);
+    return backdrop * (1.0 - path_clip_coverage(input.tile_position));
+}
+
 // --- underlines --- //
 
 struct Underline {
     order: u32,
-    pad: u32,
+    blend_mode: u32,
     bounds: Bounds,
     content_mask: Bounds,
     color: Hsla,
@@ -716,7 +849,7 @@ fn fs_underline(input: UnderlineVarying) -> @location(0) vec4<f32> {
 
 struct MonochromeSprite {
     order: u32,
-    pad: u32,
+    blend_mode: u32,
     bounds: Bounds,
     content_mask: Bounds,
     color: Hsla,
@@ -760,7 +893,7 @@ fn fs_mono_sprite(input: MonoSpriteVarying) -> @location(0) vec4<f32> {
 
 struct PolychromeSprite {
     order: u32,
-    pad: u32,
+    blend_mode: u32,
     grayscale: u32,
     opacity: f32,
     bounds: Bounds,
@@ -0,0 +1,261 @@
+//! How windows follow a compositor that picks their size and scale, like a Wayland one. Shared by
+//! the Wayland backend and by test windows, which simulate the compositor.
+
+use crate::{size, Bounds, Pixels, Point, Size, Tiling};
+
+/// A configure event of an xdg toplevel or popup, see [`ToplevelState::configure`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct Configure {
+    /// The size picked by the compositor, which leaves it to the window when it's `None` or
+    /// either dimension is zero.
+    pub size: Option<Size<Pixels>>,
+    pub fullscreen: bool,
+    pub maximized: bool,
+    pub tiling: Tiling,
+}
+
+/// The state of a toplevel that configure events change.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct ToplevelState {
+    pub fullscreen: bool,
+    pub maximized: bool,
+    pub tiling: Tiling,
+    /// The bounds of the window while it's neither maximized nor fullscreen, which it gets back
+    /// when it's unmaximized.
+    pub windowed_bounds: Bounds<Pixels>,
+}
+
+impl ToplevelState {
+    pub fn new(bounds: Bounds<Pixels>) -> Self {
+        Self {
+            fullscreen: false,
+            maximized: false,
+            tiling: Tiling::default(),
+            windowed_bounds: bounds,
+        }
+    }
+
+    /// Applies a configure event, returning the size the window takes, or `None` if it keeps its
+    /// size. `inset` is the shadow of client-side decorations, which the compositor leaves out of
+    /// the size.
+    pub fn configure(
+        &mut self,
+        configure: Configure,
+        inset: Option<Pixels>,
+    ) -> Option<Size<Pixels>> {
+        let got_unmaximized = self.maximized && !configure.maximized;
+        let configured_size = configure
+            .size
+            .filter(|size| size.width > Pixels::ZERO && size.height > Pixels::ZERO);
+
+        self.fullscreen = configure.fullscreen;
+        self.maximized = configure.maximized;
+        if configure.fullscreen || configure.maximized {
+            self.tiling = Tiling::tiled();
+            return configured_size;
+        }
+        self.tiling = configure.tiling;
+
+        let size = if got_unmaximized {
+            Some(self.windowed_bounds.size)
+        } else {
+            compute_outer_size(inset, configured_size, self.tiling)
+        };
+        if let Some(size) = size {
+            self.windowed_bounds = Bounds::new(Point::default(), size);
+        }
+        size
+    }
+}
+
+/// The configuration event is in terms of the window geometry, which we are constantly
+/// updating to account for the client decorations. But that's not the area we want to render
+/// to, due to our intrusize CSD. So, here we calculate the 'actual' size, by adding back in the insets
+pub(crate) fn compute_outer_size(
+    inset: Option<Pixels>,
+    new_size: Option<Size<Pixels>>,
+    tiling: Tiling,
+) -> Option<Size<Pixels>> {
+    let Some(inset) = inset else { return new_size };
+
+    new_size.map(|mut new_size| {
+        if !tiling.top {
+            new_size.height += inset;
+        }
+        if !tiling.bottom {
+            new_size.height += inset;
+        }
+        if !tiling.left {
+            new_size.width += inset;
+        }
+        if !tiling.right {
+            new_size.width += inset;
+        }
+
+        new_size
+    })
+}
+
+/// The size a layer surface takes from a configure event. A zero width or height leaves that
+/// dimension to the window, the others come from stretching the surface between opposite
+/// anchors.
+pub(crate) fn layer_surface_size(current: Size<Pixels>, configured: Size<Pixels>) -> Size<Pixels> {
+    size(
+        if configured.width > Pixels::ZERO {
+            configured.width
+        } else {
+            current.width
+        },
+        if configured.height > Pixels::ZERO {
+            configured.height
+        } else {
+            current.height
+        },
+    )
+}
+
+/// The scales a compositor reports for a window, which its scale factor is picked from.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct CompositorScale {
+    /// The scale the compositor prefers for the window, with fractional scaling.
+    pub fractional: Option<f32>,
+    /// The integer scale the compositor prefers for the buffers of the window.
+    pub preferred_buffer: Option<i32>,
+    /// The highest scale of the outputs the window is on, for compositors that don't tell the
+    /// scale they prefer.
+    pub output: Option<i32>,
+}
+
+impl CompositorScale {
+    /// The scale factor the compositor expects the window to render at. The fractional scale
+    /// takes precedence over the preferred buffer scale, which takes precedence over the scale
+    /// of the outputs.
+    pub fn scale_factor(&self) -> f32 {
+        self.fractional
+            .or(self.preferred_buffer.map(|scale| scale as f32))
+            .or(self.output.map(|scale| scale as f32))
+            .unwrap_or(1.0)
+    }
+
+    /// The scale factor the window renders at: the overridden one if there is one, see
+    /// [`crate::Window::set_scale_override`], and the one the compositor expects otherwise.
+    pub fn resolve(&self, scale_override: Option<f32>) -> f32 {
+        scale_override.unwrap_or_else(|| self.scale_factor())
+    }
+
+    /// The integer scale the compositor expects for the buffers of the window: the one it
+    /// prefers, or the highest scale of the outputs of the window on older compositors.
+    pub fn buffer_scale(&self) -> u32 {
+        self.preferred_buffer.or(self.output).unwrap_or(1).max(1) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{point, px};
+
+    fn windowed(width: f32, height: f32) -> ToplevelState {
+        ToplevelState::new(Bounds::new(
+            point(px(0.), px(0.)),
+            size(px(width), px(height)),
+        ))
+    }
+
+    #[test]
+    fn test_configure_restores_size_when_unmaximized() {
+        let mut state = windowed(400., 300.);
+        let maximized = Configure {
+            size: Some(size(px(1920.), px(1080.))),
+            maximized: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            state.configure(maximized, None),
+            Some(size(px(1920.), px(1080.)))
+        );
+        assert!(state.maximized);
+        assert_eq!(state.tiling, Tiling::tiled());
+
+        // Compositors usually leave the size to the window when unmaximizing it.
+        assert_eq!(
+            state.configure(Configure::default(), None),
+            Some(size(px(400.), px(300.)))
+        );
+        assert!(!state.maximized);
+        assert_eq!(state.tiling, Tiling::default());
+    }
+
+    #[test]
+    fn test_configure_adds_inset_on_untiled_edges() {
+        let mut state = windowed(400., 300.);
+        let configure = Configure {
+            size: Some(size(px(800.), px(600.))),
+            tiling: Tiling {
+                left: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            state.configure(configure, Some(px(10.))),
+            Some(size(px(810.), px(620.)))
+        );
+        assert_eq!(state.windowed_bounds.size, size(px(810.), px(620.)));
+
+        // A zero dimension leaves the size to the window.
+        let configure = Configure {
+            size: Some(size(px(800.), px(0.))),
+            ..Default::default()
+        };
+        assert_eq!(state.configure(configure, Some(px(10.))), None);
+        assert_eq!(state.windowed_bounds.size, size(px(810.), px(620.)));
+    }
+
+    #[test]
+    fn test_layer_surface_keeps_unset_dimensions() {
+        assert_eq!(
+            layer_surface_size(size(px(400.), px(300.)), size(px(1920.), px(0.))),
+            size(px(1920.), px(300.))
+        );
+    }
+
+    #[test]
+    fn test_scale_factor_precedence() {
+        let mut scale = CompositorScale::default();
+        assert_eq!(scale.scale_factor(), 1.);
+        scale.output = Some(3);
+        assert_eq!(scale.scale_factor(), 3.);
+        scale.preferred_buffer = Some(2);
+        assert_eq!(scale.scale_factor(), 2.);
+        scale.fractional = Some(1.5);
+        assert_eq!(scale.scale_factor(), 1.5);
+    }
+
+    #[test]
+    fn test_scale_override_takes_precedence() {
+        let scale = CompositorScale {
+            fractional: Some(1.5),
+            output: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(scale.resolve(None), 1.5);
+        assert_eq!(scale.resolve(Some(1.)), 1.);
+        assert_eq!(CompositorScale::default().resolve(Some(3.)), 3.);
+    }
+
+    #[test]
+    fn test_buffer_scale_ignores_fractional_scale() {
+        let mut scale = CompositorScale {
+            fractional: Some(1.5),
+            ..Default::default()
+        };
+        assert_eq!(scale.buffer_scale(), 1);
+        scale.output = Some(3);
+        assert_eq!(scale.buffer_scale(), 3);
+        scale.preferred_buffer = Some(2);
+        assert_eq!(scale.buffer_scale(), 2);
+        scale.preferred_buffer = Some(0);
+        assert_eq!(scale.buffer_scale(), 1);
+    }
+}
@@ -0,0 +1,352 @@
+use std::{borrow::Cow, cell::RefCell, rc::Rc, sync::Arc};
+
+use anyhow::Result;
+use futures::channel::oneshot;
+use raw_window_handle::{
+    DisplayHandle, HandleError, HasDisplayHandle, HasWindowHandle, WindowHandle,
+};
+
+use crate::{
+    AccessibilityRequest, AntiAliasing, AnyWindowHandle, AtlasKey, AtlasTile, Bounds, DevicePixels,
+    DispatchEventResult, GpuSpecs, KeyRepeatSettings, Modifiers, Pixels, Platform, PlatformAtlas,
+    PlatformDisplay, PlatformInput, PlatformInputHandler, PlatformWindow, Point, PromptLevel,
+    RequestFrameOptions, ScaledPixels, Scene, Size, WindowAppearance, WindowBackgroundAppearance,
+    WindowBounds, WindowCreationError, WindowDecorations, WindowParams,
+};
+
+type DeferredCall = Box<dyn FnOnce(&mut dyn PlatformWindow)>;
+
+/// Creates the stand-in for the platform window of a window opened with
+/// [`crate::WindowOptions::deferred`], along with what's needed to create the platform window
+/// once the window is realized.
+pub(crate) fn deferred_window(params: WindowParams) -> (DeferredWindow, PendingWindow) {
+    let calls = Rc::new(RefCell::new(Vec::new()));
+    let window = DeferredWindow {
+        bounds: params.bounds,
+        calls: calls.clone(),
+    };
+    (window, PendingWindow { params, calls })
+}
+
+/// Stands in for the platform window of a deferred window, recording the calls that configure
+/// it or register callbacks to replay them on the platform window.
+pub(crate) struct DeferredWindow {
+    bounds: Bounds<Pixels>,
+    calls: Rc<RefCell<Vec<DeferredCall>>>,
+}
+
+/// The parameters of the platform window of a deferred window, see [`deferred_window`].
+pub(crate) struct PendingWindow {
+    params: WindowParams,
+    calls: Rc<RefCell<Vec<DeferredCall>>>,
+}
+
+impl PendingWindow {
+    /// Creates the platform window and replays the calls made to its stand-in.
+    pub(crate) fn open(
+        self,
+        handle: AnyWindowHandle,
+        platform: &dyn Platform,
+    ) -> Result<Box<dyn PlatformWindow>, WindowCreationError> {
+        let mut window = platform.open_window(handle, self.params)?;
+        replay(&self.calls, window.as_mut());
+        Ok(window)
+    }
+}
+
+/// Replays the calls made to the stand-in of a deferred window on its platform window, in the
+/// order they were made.
+fn replay(calls: &RefCell<Vec<DeferredCall>>, window: &mut dyn PlatformWindow) {
+    for call in calls.take() {
+        call(window);
+    }
+}
+
+impl DeferredWindow {
+    fn record(&self, call: impl FnOnce(&mut dyn PlatformWindow) + 'static) {
+        self.calls.borrow_mut().push(Box::new(call));
+    }
+}
+
+impl HasWindowHandle for DeferredWindow {
+    fn window_handle(&self) -> Result<WindowHandle<'_>, HandleError> {
+        Err(HandleError::Unavailable)
+    }
+}
+
+impl HasDisplayHandle for DeferredWindow {
+    fn display_handle(&self) -> Result<DisplayHandle<'_>, HandleError> {
+        Err(HandleError::Unavailable)
+    }
+}
+
+impl PlatformWindow for DeferredWindow {
+    fn bounds(&self) -> Bounds<Pixels> {
+        self.bounds
+    }
+
+    fn is_maximized(&self) -> bool {
+        false
+    }
+
+    fn window_bounds(&self) -> WindowBounds {
+        WindowBounds::Windowed(self.bounds)
+    }
+
+    fn content_size(&self) -> Size<Pixels> {
+        self.bounds.size
+    }
+
+    fn scale_factor(&self) -> f32 {
+        1.0
+    }
+
+    fn appearance(&self) -> WindowAppearance {
+        WindowAppearance::Light
+    }
+
+    fn display(&self) -> Option<Rc<dyn PlatformDisplay>> {
+        None
+    }
+
+    fn mouse_position(&self) -> Point<Pixels> {
+        Point::default()
+    }
+
+    fn modifiers(&self) -> Modifiers {
+        Modifiers::default()
+    }
+
+    fn set_input_handler(&mut self, input_handler: PlatformInputHandler) {
+        self.record(move |window| window.set_input_handler(input_handler));
+    }
+
+    fn take_input_handler(&mut self) -> Option<PlatformInputHandler> {
+        None
+    }
+
+    fn prompt(
+        &self,
+        _level: PromptLevel,
+        _msg: &str,
+        _detail: Option<&str>,
+        _answers: &[&str],
+    ) -> Option<oneshot::Receiver<usize>> {
+        None
+    }
+
+    fn activate(&self) {
+        self.record(|window| window.activate());
+    }
+
+    fn is_active(&self) -> bool {
+        false
+    }
+
+    fn is_hovered(&self) -> bool {
+        false
+    }
+
+    fn set_title(&mut self, title: &str) {
+        let title = title.to_string();
+        self.record(move |window| window.set_title(&title));
+    }
+
+    fn set_background_appearance(&self, background_appearance: WindowBackgroundAppearance) {
+        self.record(move |window| window.set_background_appearance(background_appearance));
+    }
+
+    fn minimize(&self) {
+        self.record(|window| window.minimize());
+    }
+
+    fn zoom(&self) {
+        self.record(|window| window.zoom());
+    }
+
+    fn toggle_fullscreen(&self) {
+        self.record(|window| window.toggle_fullscreen());
+    }
+
+    fn is_fullscreen(&self) -> bool {
+        false
+    }
+
+    fn on_request_frame(&self, callback: Box<dyn FnMut(RequestFrameOptions)>) {
+        self.record(move |window| window.on_request_frame(callback));
+    }
+
+    fn on_input(&self, callback: Box<dyn FnMut(PlatformInput) -> DispatchEventResult>) {
+        self.record(move |window| window.on_input(callback));
+    }
+
+    fn on_active_status_change(&self, callback: Box<dyn FnMut(bool)>) {
+        self.record(move |window| window.on_active_status_change(callback));
+    }
+
+    fn on_hover_status_change(&self, callback: Box<dyn FnMut(bool)>) {
+        self.record(move |window| window.on_hover_status_change(callback));
+    }
+
+    fn on_resize(&self, callback: Box<dyn FnMut(Size<Pixels>, f32)>) {
+        self.record(move |window| window.on_resize(callback));
+    }
+
+    fn on_moved(&self, callback: Box<dyn FnMut()>) {
+        self.record(move |window| window.on_moved(callback));
+    }
+
+    fn on_should_close(&self, callback: Box<dyn FnMut() -> bool>) {
+        self.record(move |window| window.on_should_close(callback));
+    }
+
+    fn on_close(&self, callback: Box<dyn FnOnce()>) {
+        self.record(move |window| window.on_close(callback));
+    }
+
+    fn on_appearance_changed(&self, callback: Box<dyn FnMut()>) {
+        self.record(move |window| window.on_appearance_changed(callback));
+    }
+
+    fn draw(&self, _scene: &Scene) {}
+
+    fn sprite_atlas(&self) -> Arc<dyn PlatformAtlas> {
+        Arc::new(NoAtlas)
+    }
+
+    fn set_edited(&mut self, edited: bool) {
+        self.record(move |window| window.set_edited(edited));
+    }
+
+    #[cfg(target_os = "windows")]
+    fn get_raw_handle(&self) -> windows::Win32::Foundation::HWND {
+        windows::Win32::Foundation::HWND::default()
+    }
+
+    fn request_decorations(&self, decorations: WindowDecorations) {
+        self.record(move |window| window.request_decorations(decorations));
+    }
+
+    fn set_app_id(&mut self, app_id: &str) {
+        let app_id = app_id.to_string();
+        self.record(move |window| window.set_app_id(&app_id));
+    }
+
+    fn set_client_inset(&self, inset: Pixels) {
+        self.record(move |window| window.set_client_inset(inset));
+    }
+
+    fn set_layer_shell_margin(&self, margin: (Pixels, Pixels, Pixels, Pixels)) {
+        self.record(move |window| window.set_layer_shell_margin(margin));
+    }
+
+    fn set_layer_shell_exclusive_zone(&self, exclusive_zone: Pixels) {
+        self.record(move |window| window.set_layer_shell_exclusive_zone(exclusive_zone));
+    }
+
+    fn set_always_on_top(&self, always_on_top: bool) {
+        self.record(move |window| window.set_always_on_top(always_on_top));
+    }
+
+    fn set_on_all_workspaces(&self, on_all_workspaces: bool) {
+        self.record(move |window| window.set_on_all_workspaces(on_all_workspaces));
+    }
+
+    fn set_key_repeat(&self, settings: Option<KeyRepeatSettings>) {
+        self.record(move |window| window.set_key_repeat(settings));
+    }
+
+    fn set_scale_override(&self, scale: Option<f32>) {
+        self.record(move |window| window.set_scale_override(scale));
+    }
+
+    fn set_reactive_rendering(&self, reactive: bool) {
+        self.record(move |window| window.set_reactive_rendering(reactive));
+    }
+
+    fn set_anti_aliasing(&self, anti_aliasing: AntiAliasing) {
+        self.record(move |window| window.set_anti_aliasing(anti_aliasing));
+    }
+
+    fn on_throttle_changed(&self, callback: Box<dyn FnMut(bool)>) {
+        self.record(move |window| window.on_throttle_changed(callback));
+    }
+
+    fn on_accessibility_request(&self, callback: Box<dyn FnMut(AccessibilityRequest)>) {
+        self.record(move |window| window.on_accessibility_request(callback));
+    }
+
+    #[cfg(all(target_os = "linux", feature = "wayland"))]
+    fn on_dmabuf_feedback_changed(&self, callback: Box<dyn FnMut()>) {
+        self.record(move |window| window.on_dmabuf_feedback_changed(callback));
+    }
+
+    fn gpu_specs(&self) -> Option<GpuSpecs> {
+        None
+    }
+
+    fn update_ime_position(&self, _bounds: Bounds<ScaledPixels>) {}
+}
+
+/// The atlas of deferred windows, which aren't drawn until they're realized.
+struct NoAtlas;
+
+impl PlatformAtlas for NoAtlas {
+    fn get_or_insert_with<'a>(
+        &self,
+        _key: &AtlasKey,
+        _build: &mut dyn FnMut() -> Result<Option<(Size<DevicePixels>, Cow<'a, [u8]>)>>,
+    ) -> Result<Option<AtlasTile>> {
+        Ok(None)
+    }
+
+    fn remove(&self, _key: &AtlasKey) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Weak;
+
+    use super::*;
+    use crate::{
+        point, px, size, Empty, LayerShellSettings, TestDisplay, TestWindow, WindowHandle,
+        WindowId, WindowKind,
+    };
+
+    fn params() -> WindowParams {
+        WindowParams {
+            bounds: Bounds::new(point(px(0.), px(0.)), size(px(400.), px(30.))),
+            titlebar: None,
+            kind: WindowKind::LayerShell(LayerShellSettings::default()),
+            is_movable: false,
+            focus: false,
+            show: true,
+            display_id: None,
+            window_min_size: None,
+            popup_anchor: None,
+        }
+    }
+
+    #[test]
+    fn test_replays_calls_in_order() {
+        let (mut deferred, pending) = deferred_window(params());
+        deferred.set_title("Dock");
+        deferred.set_layer_shell_exclusive_zone(px(30.));
+        deferred.set_layer_shell_exclusive_zone(px(20.));
+        let sizes = Rc::new(RefCell::new(Vec::new()));
+        deferred.on_resize({
+            let sizes = sizes.clone();
+            Box::new(move |size, _| sizes.borrow_mut().push(size))
+        });
+
+        let handle = WindowHandle::<Empty>::new(WindowId::from(1)).into();
+        let mut window =
+            TestWindow::new(handle, params(), Weak::new(), Rc::new(TestDisplay::new()));
+        replay(&pending.calls, &mut window);
+
+        assert_eq!(window.0.lock().title.as_deref(), Some("Dock"));
+        assert_eq!(window.layer_shell_exclusive_zone(), px(20.));
+        window.simulate_resize(size(px(800.), px(30.)));
+        assert_eq!(*sizes.borrow(), [size(px(800.), px(30.))]);
+    }
+}
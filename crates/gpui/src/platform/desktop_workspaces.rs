@@ -0,0 +1,72 @@
+//! The workspaces of the desktop, also called virtual desktops, for building workspace
+//! switchers. These are backed by ext-workspace on Wayland, other platforms don't list any.
+
+use crate::{DisplayId, SharedString};
+
+/// Identifies a [`DesktopWorkspace`] while it exists.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DesktopWorkspaceId(pub(crate) u32);
+
+/// Identifies a [`DesktopWorkspaceGroup`] while it exists.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DesktopWorkspaceGroupId(pub(crate) u32);
+
+/// A workspace of the desktop.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DesktopWorkspace {
+    /// Identifies the workspace in requests.
+    pub id: DesktopWorkspaceId,
+    /// An identifier of the workspace that persists across sessions, if the compositor has one.
+    pub stable_id: Option<SharedString>,
+    /// The name of the workspace.
+    pub name: SharedString,
+    /// The position of the workspace in the grid of workspaces of its group, the compositor
+    /// decides how many dimensions the grid has.
+    pub coordinates: Vec<u32>,
+    /// Whether the workspace is shown.
+    pub active: bool,
+    /// Whether the workspace requests attention.
+    pub urgent: bool,
+    /// Whether the workspace should be left out of workspace switchers.
+    pub hidden: bool,
+    /// The group the workspace belongs to.
+    pub group: Option<DesktopWorkspaceGroupId>,
+    /// Whether the workspace can be activated.
+    pub can_activate: bool,
+    /// Whether the workspace can be deactivated.
+    pub can_deactivate: bool,
+    /// Whether the workspace can be removed.
+    pub can_remove: bool,
+    /// Whether the workspace can be moved to another group.
+    pub can_assign: bool,
+}
+
+/// A group of workspaces shown on a set of displays.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DesktopWorkspaceGroup {
+    /// Identifies the group in requests.
+    pub id: DesktopWorkspaceGroupId,
+    /// The displays the workspaces of the group are shown on.
+    pub displays: Vec<DisplayId>,
+    /// The workspaces of the group.
+    pub workspaces: Vec<DesktopWorkspaceId>,
+    /// Whether workspaces can be created in the group.
+    pub can_create_workspace: bool,
+}
+
+/// A change to the workspaces, sent with [`App::update_desktop_workspaces`].
+///
+/// [`App::update_desktop_workspaces`]: crate::App::update_desktop_workspaces
+#[derive(Clone, Debug, PartialEq)]
+pub enum DesktopWorkspaceRequest {
+    /// Activates the workspace.
+    Activate(DesktopWorkspaceId),
+    /// Deactivates the workspace.
+    Deactivate(DesktopWorkspaceId),
+    /// Removes the workspace.
+    Remove(DesktopWorkspaceId),
+    /// Moves the workspace to the group.
+    Assign(DesktopWorkspaceId, DesktopWorkspaceGroupId),
+    /// Creates a workspace with the given name in the group.
+    Create(DesktopWorkspaceGroupId, SharedString),
+}
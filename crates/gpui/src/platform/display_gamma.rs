@@ -0,0 +1,104 @@
+//! Color temperature and brightness adjustments of displays, for night light widgets.
+
+/// A color temperature and brightness applied to a display with
+/// [`App::set_display_gamma`](crate::App::set_display_gamma).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DisplayGamma {
+    /// The color temperature in Kelvin. Lower temperatures look warmer,
+    /// [`DisplayGamma::NEUTRAL_TEMPERATURE`] leaves the colors unchanged.
+    pub temperature: u32,
+    /// The brightness, between 0 and 1.
+    pub brightness: f32,
+}
+
+impl Default for DisplayGamma {
+    fn default() -> Self {
+        Self {
+            temperature: Self::NEUTRAL_TEMPERATURE,
+            brightness: 1.,
+        }
+    }
+}
+
+impl DisplayGamma {
+    /// The color temperature of displays without adjustments, in Kelvin.
+    pub const NEUTRAL_TEMPERATURE: u32 = 6500;
+
+    /// Creates an adjustment to the given color temperature in Kelvin, at full brightness.
+    pub fn temperature(temperature: u32) -> Self {
+        Self {
+            temperature,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the brightness, between 0 and 1.
+    pub fn brightness(mut self, brightness: f32) -> Self {
+        self.brightness = brightness;
+        self
+    }
+
+    /// Returns the red, green and blue gamma ramps with `size` elements each, one after the
+    /// other.
+    pub fn ramps(&self, size: usize) -> Vec<u16> {
+        let white = blackbody_color(Self::NEUTRAL_TEMPERATURE);
+        let color = blackbody_color(self.temperature);
+        let brightness = self.brightness.clamp(0., 1.);
+        let mut ramps = Vec::with_capacity(size * 3);
+        for (color, white) in color.into_iter().zip(white) {
+            let factor = (color / white).min(1.) * brightness;
+            ramps.extend((0..size).map(|ix| {
+                let value = if size > 1 {
+                    ix as f32 / (size - 1) as f32
+                } else {
+                    1.
+                };
+                (value * factor * u16::MAX as f32).round() as u16
+            }));
+        }
+        ramps
+    }
+}
+
+/// Approximates the color of a black body at the given temperature, as red, green and blue
+/// between 0 and 1.
+fn blackbody_color(temperature: u32) -> [f32; 3] {
+    let temperature = temperature.clamp(1000, 40000) as f32 / 100.;
+    let red = if temperature <= 66. {
+        255.
+    } else {
+        329.698_73 * (temperature - 60.).powf(-0.133_204_76)
+    };
+    let green = if temperature <= 66. {
+        99.470_8 * temperature.ln() - 161.119_57
+    } else {
+        288.122_17 * (temperature - 60.).powf(-0.075_514_85)
+    };
+    let blue = if temperature >= 66. {
+        255.
+    } else if temperature <= 19. {
+        0.
+    } else {
+        138.517_73 * (temperature - 10.).ln() - 305.044_8
+    };
+    [red, green, blue].map(|channel| channel.clamp(0., 255.) / 255.)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gamma_ramps() {
+        let neutral = DisplayGamma::default().ramps(3);
+        assert_eq!(neutral, [0, 32768, 65535].repeat(3));
+
+        let dimmed = DisplayGamma::default().brightness(0.5).ramps(2);
+        assert_eq!(dimmed, [0, 32768].repeat(3));
+
+        let warm = DisplayGamma::temperature(3000).ramps(2);
+        let [red, green, blue] = [warm[1], warm[3], warm[5]];
+        assert_eq!(red, u16::MAX);
+        assert!(green < red && blue < green);
+    }
+}
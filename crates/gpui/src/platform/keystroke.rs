@@ -6,7 +6,7 @@ use std::{
 };
 
 /// A keystroke and associated metadata generated by the platform
-#[derive(Clone, Debug, Eq, PartialEq, Default, Deserialize, Hash)]
+#[derive(Clone, Debug, Eq, PartialEq, Default, Serialize, Deserialize, Hash)]
 pub struct Keystroke {
     /// the state of the modifier keys at the time the keystroke was generated
     pub modifiers: Modifiers,
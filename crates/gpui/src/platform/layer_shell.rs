@@ -0,0 +1,410 @@
+//! Settings for windows that are part of the desktop shell, like panels and docks. These map to
+//! layer shell surfaces on Wayland and to dock windows with struts on X11. Elsewhere they're
+//! approximated with borderless windows placed the way a layer shell compositor would.
+
+use std::time::Duration;
+
+use bitflags::bitflags;
+
+use crate::{hsla, point, px, size, Bounds, Hsla, Pixels, Point, Size};
+
+/// The z-depth of a layer
+///
+/// These values indicate which order in which layer surfaces are rendered.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Layer {
+    /// The background layer
+    Background,
+    /// The bottom layer
+    Bottom,
+    /// The top layer
+    Top,
+    /// The overlay layer
+    Overlay,
+}
+
+bitflags! {
+    /// The anchor point for a layer shell surface
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    pub struct Anchor: u32 {
+        /// The top edge of the surface
+        const TOP = 1;
+        /// The bottom edge of the surface
+        const BOTTOM = 2;
+        /// The left edge of the surface
+        const LEFT = 4;
+        /// The right edge of the surface
+        const RIGHT = 8;
+    }
+}
+
+/// Types of keyboard interaction possible for a layer shell surface
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum KeyboardInteractivity {
+    /// No keyboard focus is possible
+    None,
+    ///Request exclusive keyboard focus
+    Exclusive,
+    /// Request regular keyboard focus semantics
+    OnDemand,
+}
+
+/// Settings for a layer shell surface
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LayerShellSettings {
+    /// Layer of the surface
+    pub layer: Layer,
+    /// Anchor point of the surface
+    pub anchor: Anchor,
+    /// The exclusive edge will prevent other surfaces from being placed in the same area
+    pub exclusive_zone: Option<Pixels>,
+    /// The distance away from the anchor point
+    pub margin: Option<(Pixels, Pixels, Pixels, Pixels)>,
+    /// Types of keyboard interaction possible for layer shell surfaces
+    pub keyboard_interactivity: KeyboardInteractivity,
+    /// Whether the surface should receive pointer events
+    pub pointer_interactivity: bool,
+    /// Namespace for the layer shell surface
+    pub namespace: String,
+    /// Whether to close the window once it loses keyboard focus, like when the user clicks
+    /// elsewhere. Only applies with [`KeyboardInteractivity::OnDemand`], see
+    /// [`Window::observe_keyboard_focus_lost`](crate::Window::observe_keyboard_focus_lost).
+    pub close_on_focus_loss: bool,
+    /// Draws a drop shadow around the window, in space the surface keeps around its body. The
+    /// size, margin and exclusive zone still describe the body, and on Wayland clicks on the
+    /// shadow go to whatever is below the window.
+    ///
+    /// The root view should draw its background with the shadow's corner radius.
+    pub shadow: Option<LayerShellShadow>,
+    /// Hides the window past the edge it's anchored to, leaving a strip on screen that reveals
+    /// it when the pointer touches it, like an auto-hiding dock. The window needs to be anchored
+    /// to a single edge, or to an edge and both of its neighbours, and usually has no exclusive
+    /// zone. See [`Window::observe_layer_shell_revealed`](crate::Window::observe_layer_shell_revealed).
+    pub auto_hide: Option<AutoHide>,
+    /// Keeps the window below the menu bar on macOS, which has no way to reserve space for it
+    /// otherwise. Ignored on other platforms.
+    pub avoid_menu_bar: bool,
+}
+
+/// How a layer shell window hides and reveals itself, see [`LayerShellSettings::auto_hide`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AutoHide {
+    /// How much of the window stays on screen while it's hidden.
+    pub strip: Pixels,
+    /// How long the window takes to slide in or out.
+    pub duration: Duration,
+    /// How long the window stays revealed after the pointer leaves it.
+    pub hide_delay: Duration,
+}
+
+impl Default for AutoHide {
+    fn default() -> Self {
+        Self {
+            strip: px(1.),
+            duration: Duration::from_millis(200),
+            hide_delay: Duration::from_millis(500),
+        }
+    }
+}
+
+/// A drop shadow around a layer shell window, see [`LayerShellSettings::shadow`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LayerShellShadow {
+    /// The corner radius of the window's body, which the shadow follows.
+    pub corner_radius: Pixels,
+    /// The blur radius of the shadow.
+    pub blur_radius: Pixels,
+    /// The offset of the shadow from the body.
+    pub offset: Point<Pixels>,
+    /// The color of the shadow.
+    pub color: Hsla,
+}
+
+impl Eq for LayerShellShadow {}
+
+impl Default for LayerShellShadow {
+    fn default() -> Self {
+        Self {
+            corner_radius: px(8.),
+            blur_radius: px(12.),
+            offset: point(px(0.), px(2.)),
+            color: hsla(0., 0., 0., 0.3),
+        }
+    }
+}
+
+impl LayerShellShadow {
+    /// The space kept around the window's body for the shadow, on every side.
+    pub fn inset(&self) -> Pixels {
+        self.blur_radius + self.offset.x.abs().max(self.offset.y.abs())
+    }
+}
+
+impl Default for LayerShellSettings {
+    fn default() -> Self {
+        Self {
+            layer: Layer::Top,
+            anchor: Anchor::RIGHT | Anchor::LEFT,
+            exclusive_zone: None,
+            margin: None,
+            keyboard_interactivity: KeyboardInteractivity::Exclusive,
+            pointer_interactivity: true,
+            namespace: String::new(),
+            close_on_focus_loss: false,
+            shadow: None,
+            auto_hide: None,
+            avoid_menu_bar: false,
+        }
+    }
+}
+
+/// The margin of a surface whose body is surrounded by `inset`, which moves it outwards to keep
+/// the body at `margin`.
+pub(crate) fn inset_margin(
+    margin: (Pixels, Pixels, Pixels, Pixels),
+    inset: Pixels,
+) -> (Pixels, Pixels, Pixels, Pixels) {
+    (
+        margin.0 - inset,
+        margin.1 - inset,
+        margin.2 - inset,
+        margin.3 - inset,
+    )
+}
+
+/// The exclusive zone of a surface whose body is surrounded by `inset`. Like the margin, it's
+/// measured from the body, so a positive zone grows by the inset. Zero and negative zones have
+/// special meanings and are kept as they are.
+pub(crate) fn inset_exclusive_zone(exclusive_zone: Pixels, inset: Pixels) -> Pixels {
+    if exclusive_zone > Pixels::ZERO {
+        exclusive_zone + inset
+    } else {
+        exclusive_zone
+    }
+}
+
+/// What a layer shell window does when it loses keyboard focus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum FocusLoss {
+    /// Nothing, the window never had keyboard focus on demand.
+    Ignore,
+    /// Notify the window's keyboard focus lost observers.
+    Notify,
+    /// Notify the observers, then close the window.
+    Close,
+}
+
+impl LayerShellSettings {
+    /// What the window does when it loses keyboard focus. Only windows that take keyboard focus
+    /// on demand lose it, the others either never have it or keep it.
+    pub(crate) fn focus_loss(&self) -> FocusLoss {
+        match self.keyboard_interactivity {
+            KeyboardInteractivity::OnDemand if self.close_on_focus_loss => FocusLoss::Close,
+            KeyboardInteractivity::OnDemand => FocusLoss::Notify,
+            KeyboardInteractivity::None | KeyboardInteractivity::Exclusive => FocusLoss::Ignore,
+        }
+    }
+
+    /// The edge the window is attached to: the one it's anchored to alone or together with both
+    /// of its neighbours. This is the edge its exclusive zone applies to.
+    pub(crate) fn attached_edge(&self) -> Option<Anchor> {
+        [Anchor::TOP, Anchor::BOTTOM, Anchor::LEFT, Anchor::RIGHT]
+            .into_iter()
+            .find(|&edge| {
+                let neighbours = if edge.intersects(Anchor::TOP | Anchor::BOTTOM) {
+                    Anchor::LEFT | Anchor::RIGHT
+                } else {
+                    Anchor::TOP | Anchor::BOTTOM
+                };
+                self.anchor == edge || self.anchor == edge | neighbours
+            })
+    }
+
+    /// The space kept around the window's body for its shadow.
+    pub(crate) fn shadow_inset(&self) -> Pixels {
+        self.shadow.map_or(Pixels::ZERO, |shadow| shadow.inset())
+    }
+
+    /// The size of a surface whose body has the given size.
+    pub(crate) fn surface_size(&self, body_size: Size<Pixels>) -> Size<Pixels> {
+        let inset = self.shadow_inset() * 2.;
+        size(body_size.width + inset, body_size.height + inset)
+    }
+
+    /// The size of the body of a surface with the given size.
+    pub(crate) fn body_size(&self, surface_size: Size<Pixels>) -> Size<Pixels> {
+        let inset = self.shadow_inset() * 2.;
+        size(surface_size.width - inset, surface_size.height - inset)
+    }
+
+    /// The margin of the surface, which moves it outwards to keep the body at the given margin.
+    pub(crate) fn surface_margin(
+        &self,
+        margin: (Pixels, Pixels, Pixels, Pixels),
+    ) -> (Pixels, Pixels, Pixels, Pixels) {
+        inset_margin(margin, self.shadow_inset())
+    }
+
+    /// The exclusive zone of the surface, which covers the body but not the shadow past it.
+    pub(crate) fn surface_exclusive_zone(&self) -> Option<Pixels> {
+        self.exclusive_zone
+            .map(|zone| inset_exclusive_zone(zone, self.shadow_inset()))
+    }
+
+    /// Places a window of the given size within `area` the way a layer shell compositor would:
+    /// stretched between opposite anchored edges, against the anchored edges otherwise, and
+    /// centered along the axes it isn't anchored on.
+    pub(crate) fn bounds_within(
+        &self,
+        window_size: Size<Pixels>,
+        area: Bounds<Pixels>,
+    ) -> Bounds<Pixels> {
+        let (top, right, bottom, left) = self.surface_margin(self.margin.unwrap_or_default());
+        let window_size = self.surface_size(window_size);
+        let anchor = self.anchor;
+
+        let width = if anchor.contains(Anchor::LEFT | Anchor::RIGHT) {
+            area.size.width - left - right
+        } else {
+            window_size.width
+        };
+        let height = if anchor.contains(Anchor::TOP | Anchor::BOTTOM) {
+            area.size.height - top - bottom
+        } else {
+            window_size.height
+        };
+        let x = if anchor.contains(Anchor::LEFT) {
+            area.left() + left
+        } else if anchor.contains(Anchor::RIGHT) {
+            area.right() - width - right
+        } else {
+            area.left() + (area.size.width - width) * 0.5
+        };
+        let y = if anchor.contains(Anchor::TOP) {
+            area.top() + top
+        } else if anchor.contains(Anchor::BOTTOM) {
+            area.bottom() - height - bottom
+        } else {
+            area.top() + (area.size.height - height) * 0.5
+        };
+
+        Bounds {
+            origin: point(x, y),
+            size: size(width, height),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_shadow() -> LayerShellSettings {
+        LayerShellSettings {
+            // Keeps 12px + 2px around the body.
+            shadow: Some(LayerShellShadow::default()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_shadow_surrounds_body() {
+        let settings = with_shadow();
+        assert_eq!(settings.shadow_inset(), px(14.));
+        assert_eq!(
+            settings.surface_size(size(px(400.), px(30.))),
+            size(px(428.), px(58.))
+        );
+        assert_eq!(
+            settings.body_size(size(px(428.), px(58.))),
+            size(px(400.), px(30.))
+        );
+
+        let settings = LayerShellSettings::default();
+        assert_eq!(
+            settings.surface_size(size(px(400.), px(30.))),
+            size(px(400.), px(30.))
+        );
+    }
+
+    #[test]
+    fn test_shadow_keeps_body_at_margin() {
+        let settings = LayerShellSettings {
+            margin: Some((px(10.), px(0.), px(-30.), px(20.))),
+            ..with_shadow()
+        };
+        assert_eq!(
+            settings.surface_margin(settings.margin.unwrap()),
+            (px(-4.), px(-14.), px(-44.), px(6.))
+        );
+        assert_eq!(
+            inset_margin(settings.margin.unwrap(), Pixels::ZERO),
+            settings.margin.unwrap()
+        );
+    }
+
+    #[test]
+    fn test_shadow_only_grows_positive_exclusive_zones() {
+        let zone = |exclusive_zone| {
+            LayerShellSettings {
+                exclusive_zone,
+                ..with_shadow()
+            }
+            .surface_exclusive_zone()
+        };
+        assert_eq!(zone(Some(px(30.))), Some(px(44.)));
+        // Zero lets other surfaces overlap, -1 stretches the surface over exclusive zones.
+        assert_eq!(zone(Some(px(0.))), Some(px(0.)));
+        assert_eq!(zone(Some(px(-1.))), Some(px(-1.)));
+        assert_eq!(zone(None), None);
+    }
+
+    #[test]
+    fn test_attached_edge() {
+        let attached_edge = |anchor| {
+            LayerShellSettings {
+                anchor,
+                ..Default::default()
+            }
+            .attached_edge()
+        };
+        assert_eq!(attached_edge(Anchor::TOP), Some(Anchor::TOP));
+        assert_eq!(
+            attached_edge(Anchor::BOTTOM | Anchor::LEFT | Anchor::RIGHT),
+            Some(Anchor::BOTTOM)
+        );
+        assert_eq!(
+            attached_edge(Anchor::LEFT | Anchor::TOP | Anchor::BOTTOM),
+            Some(Anchor::LEFT)
+        );
+        // Corners and stretching between opposite edges don't attach the window to an edge.
+        assert_eq!(attached_edge(Anchor::TOP | Anchor::RIGHT), None);
+        assert_eq!(attached_edge(Anchor::LEFT | Anchor::RIGHT), None);
+        assert_eq!(attached_edge(Anchor::all()), None);
+    }
+
+    #[test]
+    fn test_focus_loss_only_applies_on_demand() {
+        let settings = |keyboard_interactivity, close_on_focus_loss| LayerShellSettings {
+            keyboard_interactivity,
+            close_on_focus_loss,
+            ..Default::default()
+        };
+        assert_eq!(
+            settings(KeyboardInteractivity::OnDemand, false).focus_loss(),
+            FocusLoss::Notify
+        );
+        assert_eq!(
+            settings(KeyboardInteractivity::OnDemand, true).focus_loss(),
+            FocusLoss::Close
+        );
+        assert_eq!(
+            settings(KeyboardInteractivity::Exclusive, true).focus_loss(),
+            FocusLoss::Ignore
+        );
+        assert_eq!(
+            settings(KeyboardInteractivity::None, true).focus_loss(),
+            FocusLoss::Ignore
+        );
+    }
+}
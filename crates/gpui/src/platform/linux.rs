@@ -1,5 +1,15 @@
+#[cfg(any(feature = "wayland", feature = "x11"))]
+mod accessibility;
+#[cfg(any(feature = "wayland", feature = "x11"))]
+mod canvas;
+#[cfg(any(feature = "wayland", feature = "x11"))]
+mod gles_renderer;
+#[cfg(any(feature = "wayland", feature = "x11"))]
+mod dbus;
 mod dispatcher;
 mod headless;
+#[cfg(target_os = "linux")]
+mod path_watcher;
 mod platform;
 #[cfg(any(feature = "wayland", feature = "x11"))]
 mod text_system;
@@ -11,8 +21,14 @@ mod x11;
 #[cfg(any(feature = "wayland", feature = "x11"))]
 mod xdg_desktop_portal;
 
+#[cfg(any(feature = "wayland", feature = "x11"))]
+pub(crate) use accessibility::*;
+#[cfg(any(feature = "wayland", feature = "x11"))]
+pub(crate) use dbus::*;
 pub(crate) use dispatcher::*;
 pub(crate) use headless::*;
+#[cfg(target_os = "linux")]
+pub(crate) use path_watcher::*;
 pub(crate) use platform::*;
 #[cfg(any(feature = "wayland", feature = "x11"))]
 pub(crate) use text_system::*;
@@ -0,0 +1,223 @@
+//! Exposes the accessibility trees of windows to assistive technologies like Orca through
+//! AccessKit, which serves them over AT-SPI.
+
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering::SeqCst},
+        Arc,
+    },
+};
+
+use accesskit::{
+    Action, ActionHandler, ActionRequest, ActivationHandler, DeactivationHandler, Node, NodeId,
+    Rect, Role, Toggled, Tree, TreeUpdate,
+};
+use collections::{HashMap, HashSet};
+use futures::{channel::mpsc, StreamExt as _};
+
+use crate::{
+    AccessibilityRequest, AccessibilityTree, AccessibleNodeId, AccessibleRole, ForegroundExecutor,
+    SharedString, Task,
+};
+
+/// What the AccessKit thread reports to the window.
+enum Event {
+    Activated,
+    Action(ActionRequest),
+}
+
+/// Handles the requests of assistive technologies on the AccessKit thread.
+#[derive(Clone)]
+struct Handler {
+    active: Arc<AtomicBool>,
+    events: mpsc::UnboundedSender<Event>,
+}
+
+impl ActivationHandler for Handler {
+    fn request_initial_tree(&mut self) -> Option<TreeUpdate> {
+        // The tree is built on the main thread, the window publishes it with its next frame.
+        self.active.store(true, SeqCst);
+        self.events.unbounded_send(Event::Activated).ok();
+        None
+    }
+}
+
+impl ActionHandler for Handler {
+    fn do_action(&mut self, request: ActionRequest) {
+        self.events.unbounded_send(Event::Action(request)).ok();
+    }
+}
+
+impl DeactivationHandler for Handler {
+    fn deactivate_accessibility(&mut self) {
+        self.active.store(false, SeqCst);
+    }
+}
+
+/// The AccessKit adapter of a window.
+pub(crate) struct AccessibilityAdapter {
+    adapter: accesskit_unix::Adapter,
+    active: Arc<AtomicBool>,
+    title: Option<SharedString>,
+    callback: Rc<RefCell<Option<Box<dyn FnMut(AccessibilityRequest)>>>>,
+    _events_task: Task<()>,
+}
+
+impl AccessibilityAdapter {
+    pub(crate) fn new(executor: &ForegroundExecutor, title: Option<SharedString>) -> Self {
+        let (events_tx, mut events_rx) = mpsc::unbounded();
+        let active = Arc::new(AtomicBool::new(false));
+        let handler = Handler {
+            active: active.clone(),
+            events: events_tx,
+        };
+        let adapter = accesskit_unix::Adapter::new(handler.clone(), handler.clone(), handler);
+
+        let callback: Rc<RefCell<Option<Box<dyn FnMut(AccessibilityRequest)>>>> = Rc::default();
+        let events_task = executor.spawn({
+            let callback = callback.clone();
+            async move {
+                while let Some(event) = events_rx.next().await {
+                    let request = match event {
+                        Event::Activated => AccessibilityRequest::Activate,
+                        Event::Action(request) => {
+                            let id = AccessibleNodeId(request.target.0);
+                            match request.action {
+                                Action::Click => AccessibilityRequest::Click(id),
+                                Action::Focus => AccessibilityRequest::Focus(id),
+                                _ => continue,
+                            }
+                        }
+                    };
+                    if let Some(callback) = callback.borrow_mut().as_mut() {
+                        callback(request);
+                    }
+                }
+            }
+        });
+
+        Self {
+            adapter,
+            active,
+            title,
+            callback,
+            _events_task: events_task,
+        }
+    }
+
+    pub(crate) fn is_active(&self) -> bool {
+        self.active.load(SeqCst)
+    }
+
+    pub(crate) fn on_request(&self, callback: Box<dyn FnMut(AccessibilityRequest)>) {
+        *self.callback.borrow_mut() = Some(callback);
+    }
+
+    pub(crate) fn set_title(&mut self, title: &str) {
+        self.title = Some(SharedString::from(title.to_string()));
+    }
+
+    pub(crate) fn set_window_focused(&mut self, focused: bool) {
+        self.adapter.update_window_focus_state(focused);
+    }
+
+    pub(crate) fn update(&mut self, tree: AccessibilityTree, scale_factor: f32) {
+        let title = self.title.clone();
+        self.adapter
+            .update_if_active(|| tree_update(tree, title, scale_factor));
+    }
+}
+
+fn tree_update(
+    tree: AccessibilityTree,
+    title: Option<SharedString>,
+    scale_factor: f32,
+) -> TreeUpdate {
+    // Nodes are listed before their children, so the parent of every node is known by the time
+    // it's reached. Nodes whose id is taken, like elements sharing an id, are left out.
+    let mut ids = HashSet::default();
+    ids.insert(AccessibleNodeId::ROOT);
+    let nodes = tree
+        .nodes
+        .into_iter()
+        .filter(|node| ids.contains(&node.parent) && ids.insert(node.id))
+        .collect::<Vec<_>>();
+    let mut children = HashMap::<AccessibleNodeId, Vec<NodeId>>::default();
+    for node in &nodes {
+        children
+            .entry(node.parent)
+            .or_default()
+            .push(NodeId(node.id.0));
+    }
+
+    let mut root = Node::new(Role::Window);
+    if let Some(title) = title {
+        root.set_label(title.to_string());
+    }
+    root.set_children(children.remove(&AccessibleNodeId::ROOT).unwrap_or_default());
+    let mut update_nodes = vec![(NodeId(AccessibleNodeId::ROOT.0), root)];
+
+    for node in nodes {
+        let mut accesskit_node = Node::new(role(node.role));
+        if let Some(label) = node.label {
+            accesskit_node.set_label(label.to_string());
+        }
+        if let Some(toggled) = node.toggled {
+            accesskit_node.set_toggled(if toggled {
+                Toggled::True
+            } else {
+                Toggled::False
+            });
+        }
+        let bounds = node.bounds.scale(scale_factor);
+        accesskit_node.set_bounds(Rect {
+            x0: bounds.left().0 as f64,
+            y0: bounds.top().0 as f64,
+            x1: bounds.right().0 as f64,
+            y1: bounds.bottom().0 as f64,
+        });
+        if node.clickable {
+            accesskit_node.add_action(Action::Click);
+        }
+        if node.focus_id.is_some() {
+            accesskit_node.add_action(Action::Focus);
+        }
+        if let Some(children) = children.remove(&node.id) {
+            accesskit_node.set_children(children);
+        }
+        update_nodes.push((NodeId(node.id.0), accesskit_node));
+    }
+
+    let mut accesskit_tree = Tree::new(NodeId(AccessibleNodeId::ROOT.0));
+    accesskit_tree.toolkit_name = Some("gpui".to_string());
+    TreeUpdate {
+        nodes: update_nodes,
+        tree: Some(accesskit_tree),
+        focus: NodeId(tree.focus.unwrap_or(AccessibleNodeId::ROOT).0),
+    }
+}
+
+fn role(role: AccessibleRole) -> Role {
+    match role {
+        AccessibleRole::Group => Role::Group,
+        AccessibleRole::Button => Role::Button,
+        AccessibleRole::CheckBox => Role::CheckBox,
+        AccessibleRole::Switch => Role::Switch,
+        AccessibleRole::Link => Role::Link,
+        AccessibleRole::Label => Role::Label,
+        AccessibleRole::Image => Role::Image,
+        AccessibleRole::List => Role::List,
+        AccessibleRole::ListItem => Role::ListItem,
+        AccessibleRole::Menu => Role::Menu,
+        AccessibleRole::MenuItem => Role::MenuItem,
+        AccessibleRole::TextInput => Role::TextInput,
+        AccessibleRole::Slider => Role::Slider,
+        AccessibleRole::ProgressIndicator => Role::ProgressIndicator,
+        AccessibleRole::TabList => Role::TabList,
+        AccessibleRole::Tab => Role::Tab,
+        AccessibleRole::Toolbar => Role::Toolbar,
+        AccessibleRole::Dialog => Role::Dialog,
+    }
+}
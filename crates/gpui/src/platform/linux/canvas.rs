@@ -0,0 +1,830 @@
+//! A CPU rasterizer for scenes, shared by the software renderers used when no usable GPU is
+//! available.
+
+use std::borrow::Cow;
+
+use anyhow::Result;
+use collections::FxHashMap;
+use parking_lot::Mutex;
+use resvg::tiny_skia as sk;
+
+use crate::{
+    color::BackgroundTag, AtlasKey, AtlasTextureId, AtlasTextureKind, AtlasTile, AtlasUsage,
+    Background, Bounds, ContentMask, Corners, DevicePixels, Hsla, MonochromeSprite, Path,
+    PlatformAtlas, PolychromeSprite, PrimitiveBatch, Quad, Rgba, ScaledPixels, Scene, Shadow, Size,
+    TileId, Underline,
+};
+
+/// Control point offset for approximating a quarter circle with a cubic bézier curve.
+const CIRCLE_CONTROL_POINT: f32 = 0.552_284_8;
+
+struct ClipMask {
+    bounds: Bounds<ScaledPixels>,
+    mask: sk::Mask,
+}
+
+pub(crate) struct Canvas {
+    pixmap: sk::Pixmap,
+    clip: Option<ClipMask>,
+}
+
+impl Canvas {
+    pub fn new(size: Size<DevicePixels>) -> Self {
+        let width = (size.width.0 as u32).max(1);
+        let height = (size.height.0 as u32).max(1);
+        Self {
+            pixmap: sk::Pixmap::new(width, height).expect("invalid canvas size"),
+            clip: None,
+        }
+    }
+
+    pub fn pixmap(&self) -> &sk::Pixmap {
+        &self.pixmap
+    }
+
+    /// Paints `scene` onto a cleared canvas, taking the sprites from `atlas`.
+    pub fn draw(&mut self, scene: &Scene, atlas: &CanvasAtlas) {
+        self.pixmap.fill(sk::Color::TRANSPARENT);
+        let atlas = atlas.0.lock();
+        for batch in scene.batches() {
+            match batch {
+                PrimitiveBatch::Shadows(shadows) => {
+                    for shadow in shadows {
+                        self.draw_shadow(shadow);
+                    }
+                }
+                PrimitiveBatch::Quads(quads) => {
+                    for quad in quads {
+                        self.draw_quad(quad);
+                    }
+                }
+                PrimitiveBatch::Paths(paths) => {
+                    for path in paths {
+                        self.draw_path(path);
+                    }
+                }
+                PrimitiveBatch::Underlines(underlines) => {
+                    for underline in underlines {
+                        self.draw_underline(underline);
+                    }
+                }
+                PrimitiveBatch::MonochromeSprites {
+                    texture_id,
+                    sprites,
+                } => {
+                    if let Some(CanvasTexture::Mask { size, data }) =
+                        atlas.textures.get(&texture_id)
+                    {
+                        for sprite in sprites {
+                            self.draw_monochrome_sprite(sprite, *size, data);
+                        }
+                    }
+                }
+                PrimitiveBatch::PolychromeSprites {
+                    texture_id,
+                    sprites,
+                } => {
+                    if let Some(CanvasTexture::Pixmap(pixmap)) = atlas.textures.get(&texture_id) {
+                        for sprite in sprites {
+                            self.draw_polychrome_sprite(sprite, pixmap);
+                        }
+                    }
+                }
+                // Surfaces are foreign Wayland surfaces here, which the compositor draws itself.
+                PrimitiveBatch::Surfaces(_) => {}
+                // Custom shaders, backdrop blurs and path clips need a GPU.
+                PrimitiveBatch::Shaders(_)
+                | PrimitiveBatch::Blurs(_)
+                | PrimitiveBatch::PathClips(_) => {}
+            }
+        }
+    }
+
+    fn fill(
+        &mut self,
+        path: &sk::Path,
+        paint: &sk::Paint,
+        fill_rule: sk::FillRule,
+        transform: sk::Transform,
+        content_mask: &ContentMask<ScaledPixels>,
+    ) {
+        let mask = clip_mask(
+            &mut self.clip,
+            self.pixmap.width(),
+            self.pixmap.height(),
+            content_mask,
+        );
+        self.pixmap
+            .fill_path(path, paint, fill_rule, transform, mask);
+    }
+
+    fn draw_quad(&mut self, quad: &Quad) {
+        let widths = &quad.border_widths;
+        let has_border =
+            widths.top.0 > 0. || widths.right.0 > 0. || widths.bottom.0 > 0. || widths.left.0 > 0.;
+        let outer = RoundedRect::new(quad.bounds, &quad.corner_radii);
+        let inner = if has_border {
+            outer.inset(widths.top.0, widths.right.0, widths.bottom.0, widths.left.0)
+        } else {
+            outer
+        };
+
+        if let Some(path) = inner.to_path() {
+            let paint = background_paint(&quad.background, &quad.bounds);
+            self.fill(
+                &path,
+                &paint,
+                sk::FillRule::Winding,
+                sk::Transform::identity(),
+                &quad.content_mask,
+            );
+        }
+
+        if has_border && quad.border_color.a > 0. {
+            let mut builder = sk::PathBuilder::new();
+            outer.push(&mut builder);
+            inner.push(&mut builder);
+            if let Some(path) = builder.finish() {
+                self.fill(
+                    &path,
+                    &solid_paint(quad.border_color),
+                    sk::FillRule::EvenOdd,
+                    sk::Transform::identity(),
+                    &quad.content_mask,
+                );
+            }
+        }
+    }
+
+    fn draw_shadow(&mut self, shadow: &Shadow) {
+        // There is no blur in tiny-skia, so approximate the falloff by stacking translucent
+        // rounded rectangles that grow from `blur_radius` inside the bounds to as far outside.
+        let blur = shadow.blur_radius.0;
+        let steps = (blur.ceil() as usize).clamp(1, 8);
+        let mut color = shadow.color;
+        color.a /= steps as f32;
+        let paint = solid_paint(color);
+        let rect = RoundedRect::new(shadow.bounds, &shadow.corner_radii);
+        for step in 0..steps {
+            let spread = blur * (1. - 2. * (step as f32 + 0.5) / steps as f32);
+            if let Some(path) = rect.outset(spread).to_path() {
+                self.fill(
+                    &path,
+                    &paint,
+                    sk::FillRule::Winding,
+                    sk::Transform::identity(),
+                    &shadow.content_mask,
+                );
+            }
+        }
+    }
+
+    fn draw_underline(&mut self, underline: &Underline) {
+        let bounds = underline.bounds;
+        let thickness = underline.thickness.0;
+        let paint = solid_paint(underline.color);
+
+        if !underline.wavy {
+            let Some(rect) = sk::Rect::from_xywh(
+                bounds.origin.x.0,
+                bounds.origin.y.0,
+                bounds.size.width.0,
+                thickness,
+            ) else {
+                return;
+            };
+            self.fill(
+                &sk::PathBuilder::from_rect(rect),
+                &paint,
+                sk::FillRule::Winding,
+                sk::Transform::identity(),
+                &underline.content_mask,
+            );
+            return;
+        }
+
+        // Matches the sine wave of the GPU renderer's underline shader.
+        let height = bounds.size.height.0;
+        if height <= 0. || thickness <= 0. {
+            return;
+        }
+        let frequency = std::f32::consts::PI * thickness;
+        let amplitude = height / (4. * thickness);
+        let middle = bounds.origin.y.0 + height / 2.;
+        let mut builder = sk::PathBuilder::new();
+        let mut x = 0.;
+        while x <= bounds.size.width.0 {
+            let y = middle + (x / height * frequency).sin() * amplitude;
+            if x == 0. {
+                builder.move_to(bounds.origin.x.0, y);
+            } else {
+                builder.line_to(bounds.origin.x.0 + x, y);
+            }
+            x += 1.;
+        }
+        let Some(path) = builder.finish() else {
+            return;
+        };
+        let stroke = sk::Stroke {
+            width: thickness,
+            ..Default::default()
+        };
+        let mask = clip_mask(
+            &mut self.clip,
+            self.pixmap.width(),
+            self.pixmap.height(),
+            &underline.content_mask,
+        );
+        self.pixmap
+            .stroke_path(&path, &paint, &stroke, sk::Transform::identity(), mask);
+    }
+
+    fn draw_path(&mut self, path: &Path<ScaledPixels>) {
+        // Paths are triangulated into a fan of straight triangles plus one triangle per quadratic
+        // segment. Filling all of them with the even-odd rule reconstructs the original shape.
+        let mut builder = sk::PathBuilder::new();
+        for triangle in path.vertices.chunks_exact(3) {
+            let [a, b, c] = triangle else {
+                continue;
+            };
+            builder.move_to(a.xy_position.x.0, a.xy_position.y.0);
+            if b.st_position.x == 0.5 {
+                builder.quad_to(
+                    b.xy_position.x.0,
+                    b.xy_position.y.0,
+                    c.xy_position.x.0,
+                    c.xy_position.y.0,
+                );
+            } else {
+                builder.line_to(b.xy_position.x.0, b.xy_position.y.0);
+                builder.line_to(c.xy_position.x.0, c.xy_position.y.0);
+            }
+            builder.close();
+        }
+        let Some(sk_path) = builder.finish() else {
+            return;
+        };
+        let paint = background_paint(&path.color, &path.bounds);
+        self.fill(
+            &sk_path,
+            &paint,
+            sk::FillRule::EvenOdd,
+            sk::Transform::identity(),
+            &path.content_mask,
+        );
+    }
+
+    fn draw_monochrome_sprite(
+        &mut self,
+        sprite: &MonochromeSprite,
+        size: Size<DevicePixels>,
+        alpha: &[u8],
+    ) {
+        let Some(mut pixmap) = sk::Pixmap::new(size.width.0 as u32, size.height.0 as u32) else {
+            return;
+        };
+        let color = Rgba::from(sprite.color);
+        for (pixel, &coverage) in pixmap.data_mut().chunks_exact_mut(4).zip(alpha) {
+            let a = coverage as f32 / 255. * color.a;
+            pixel[0] = (color.r * a * 255.).round() as u8;
+            pixel[1] = (color.g * a * 255.).round() as u8;
+            pixel[2] = (color.b * a * 255.).round() as u8;
+            pixel[3] = (a * 255.).round() as u8;
+        }
+
+        let Some(rect) = sk_rect(sprite.bounds) else {
+            return;
+        };
+        let paint = sk::Paint {
+            shader: sk::Pattern::new(
+                pixmap.as_ref(),
+                sk::SpreadMode::Pad,
+                sk::FilterQuality::Bilinear,
+                1.,
+                tile_transform(sprite.bounds, size),
+            ),
+            anti_alias: true,
+            ..Default::default()
+        };
+        let [[sx, kx], [ky, sy]] = sprite.transformation.rotation_scale;
+        let [tx, ty] = sprite.transformation.translation;
+        self.fill(
+            &sk::PathBuilder::from_rect(rect),
+            &paint,
+            sk::FillRule::Winding,
+            sk::Transform::from_row(sx, ky, kx, sy, tx, ty),
+            &sprite.content_mask,
+        );
+    }
+
+    fn draw_polychrome_sprite(&mut self, sprite: &PolychromeSprite, pixmap: &sk::Pixmap) {
+        let grayscale;
+        let pixmap = if sprite.grayscale {
+            grayscale = to_grayscale(pixmap);
+            &grayscale
+        } else {
+            pixmap
+        };
+        let Some(path) = RoundedRect::new(sprite.bounds, &sprite.corner_radii).to_path() else {
+            return;
+        };
+        let size = Size {
+            width: DevicePixels(pixmap.width() as i32),
+            height: DevicePixels(pixmap.height() as i32),
+        };
+        let paint = sk::Paint {
+            shader: sk::Pattern::new(
+                pixmap.as_ref(),
+                sk::SpreadMode::Pad,
+                sk::FilterQuality::Bilinear,
+                sprite.opacity,
+                tile_transform(sprite.bounds, size),
+            ),
+            anti_alias: true,
+            ..Default::default()
+        };
+        self.fill(
+            &path,
+            &paint,
+            sk::FillRule::Winding,
+            sk::Transform::identity(),
+            &sprite.content_mask,
+        );
+    }
+}
+
+/// Returns a mask for `content_mask`, or `None` when it doesn't clip anything. The mask is cached
+/// since consecutive primitives usually share the same content mask.
+fn clip_mask<'a>(
+    clip: &'a mut Option<ClipMask>,
+    width: u32,
+    height: u32,
+    content_mask: &ContentMask<ScaledPixels>,
+) -> Option<&'a sk::Mask> {
+    let bounds = content_mask.bounds;
+    if bounds.origin.x.0 <= 0.
+        && bounds.origin.y.0 <= 0.
+        && bounds.bottom_right().x.0 >= width as f32
+        && bounds.bottom_right().y.0 >= height as f32
+    {
+        return None;
+    }
+
+    if clip.as_ref().map_or(true, |clip| clip.bounds != bounds) {
+        let mut mask = sk::Mask::new(width, height)?;
+        if let Some(rect) = sk_rect(bounds) {
+            mask.fill_path(
+                &sk::PathBuilder::from_rect(rect),
+                sk::FillRule::Winding,
+                false,
+                sk::Transform::identity(),
+            );
+        }
+        *clip = Some(ClipMask { bounds, mask });
+    }
+    clip.as_ref().map(|clip| &clip.mask)
+}
+
+#[derive(Clone, Copy)]
+struct RoundedRect {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    /// Top left, top right, bottom right, bottom left.
+    radii: [f32; 4],
+}
+
+impl RoundedRect {
+    fn new(bounds: Bounds<ScaledPixels>, corner_radii: &Corners<ScaledPixels>) -> Self {
+        Self {
+            x: bounds.origin.x.0,
+            y: bounds.origin.y.0,
+            width: bounds.size.width.0,
+            height: bounds.size.height.0,
+            radii: [
+                corner_radii.top_left.0,
+                corner_radii.top_right.0,
+                corner_radii.bottom_right.0,
+                corner_radii.bottom_left.0,
+            ],
+        }
+    }
+
+    fn inset(&self, top: f32, right: f32, bottom: f32, left: f32) -> Self {
+        let [top_left, top_right, bottom_right, bottom_left] = self.radii;
+        Self {
+            x: self.x + left,
+            y: self.y + top,
+            width: self.width - left - right,
+            height: self.height - top - bottom,
+            radii: [
+                (top_left - top.max(left)).max(0.),
+                (top_right - top.max(right)).max(0.),
+                (bottom_right - bottom.max(right)).max(0.),
+                (bottom_left - bottom.max(left)).max(0.),
+            ],
+        }
+    }
+
+    fn outset(&self, spread: f32) -> Self {
+        Self {
+            x: self.x - spread,
+            y: self.y - spread,
+            width: self.width + spread * 2.,
+            height: self.height + spread * 2.,
+            radii: self.radii.map(|radius| (radius + spread).max(0.)),
+        }
+    }
+
+    fn to_path(self) -> Option<sk::Path> {
+        let mut builder = sk::PathBuilder::new();
+        self.push(&mut builder);
+        builder.finish()
+    }
+
+    fn push(&self, builder: &mut sk::PathBuilder) {
+        let (x, y, w, h) = (self.x, self.y, self.width, self.height);
+        if w <= 0. || h <= 0. {
+            return;
+        }
+        let max_radius = w.min(h) / 2.;
+        let [tl, tr, br, bl] = self.radii.map(|radius| radius.clamp(0., max_radius));
+        let k = 1. - CIRCLE_CONTROL_POINT;
+
+        builder.move_to(x + tl, y);
+        builder.line_to(x + w - tr, y);
+        builder.cubic_to(x + w - tr * k, y, x + w, y + tr * k, x + w, y + tr);
+        builder.line_to(x + w, y + h - br);
+        builder.cubic_to(
+            x + w,
+            y + h - br * k,
+            x + w - br * k,
+            y + h,
+            x + w - br,
+            y + h,
+        );
+        builder.line_to(x + bl, y + h);
+        builder.cubic_to(x + bl * k, y + h, x, y + h - bl * k, x, y + h - bl);
+        builder.line_to(x, y + tl);
+        builder.cubic_to(x, y + tl * k, x + tl * k, y, x + tl, y);
+        builder.close();
+    }
+}
+
+fn sk_rect(bounds: Bounds<ScaledPixels>) -> Option<sk::Rect> {
+    sk::Rect::from_xywh(
+        bounds.origin.x.0,
+        bounds.origin.y.0,
+        bounds.size.width.0,
+        bounds.size.height.0,
+    )
+}
+
+fn sk_color(color: Hsla) -> sk::Color {
+    let color = Rgba::from(color);
+    sk::Color::from_rgba(
+        color.r.clamp(0., 1.),
+        color.g.clamp(0., 1.),
+        color.b.clamp(0., 1.),
+        color.a.clamp(0., 1.),
+    )
+    .unwrap_or(sk::Color::TRANSPARENT)
+}
+
+fn solid_paint(color: Hsla) -> sk::Paint<'static> {
+    let mut paint = sk::Paint {
+        anti_alias: true,
+        ..Default::default()
+    };
+    paint.set_color(sk_color(color));
+    paint
+}
+
+fn background_paint(background: &Background, bounds: &Bounds<ScaledPixels>) -> sk::Paint<'static> {
+    if background.tag == BackgroundTag::LinearGradient {
+        if let Some(shader) = linear_gradient(background, bounds) {
+            return sk::Paint {
+                shader,
+                anti_alias: true,
+                ..Default::default()
+            };
+        }
+    }
+    solid_paint(background.solid)
+}
+
+/// Builds a gradient equivalent to the one computed by the GPU renderer's quad shader.
+fn linear_gradient(
+    background: &Background,
+    bounds: &Bounds<ScaledPixels>,
+) -> Option<sk::Shader<'static>> {
+    let (width, height) = (bounds.size.width.0, bounds.size.height.0);
+    // -90 degrees to match the CSS gradient angle.
+    let radians = (background.gradient_angle_or_pattern_height % 360. - 90.).to_radians();
+    let (mut dx, mut dy) = (radians.cos(), radians.sin());
+    if width > height {
+        dy *= height / width;
+    } else {
+        dx *= width / height;
+    }
+    let length = (dx * dx + dy * dy).sqrt();
+    if !length.is_normal() {
+        return None;
+    }
+    let (dx, dy) = (dx / length, dy / length);
+    let axis = if dx.abs() > dy.abs() { width } else { height };
+    let center_x = bounds.origin.x.0 + width / 2.;
+    let center_y = bounds.origin.y.0 + height / 2.;
+    let [start, end] = background
+        .colors
+        .map(|stop| stop.percentage * axis - axis / 2.);
+
+    sk::LinearGradient::new(
+        sk::Point::from_xy(center_x + dx * start, center_y + dy * start),
+        sk::Point::from_xy(center_x + dx * end, center_y + dy * end),
+        background
+            .colors
+            .iter()
+            .enumerate()
+            .map(|(ix, stop)| sk::GradientStop::new(ix as f32, sk_color(stop.color)))
+            .collect(),
+        sk::SpreadMode::Pad,
+        sk::Transform::identity(),
+    )
+}
+
+/// Maps a tile of the given size onto `bounds`.
+fn tile_transform(bounds: Bounds<ScaledPixels>, size: Size<DevicePixels>) -> sk::Transform {
+    sk::Transform::from_row(
+        bounds.size.width.0 / size.width.0.max(1) as f32,
+        0.,
+        0.,
+        bounds.size.height.0 / size.height.0.max(1) as f32,
+        bounds.origin.x.0,
+        bounds.origin.y.0,
+    )
+}
+
+fn to_grayscale(pixmap: &sk::Pixmap) -> sk::Pixmap {
+    let mut pixmap = pixmap.clone();
+    for pixel in pixmap.data_mut().chunks_exact_mut(4) {
+        let gray = (0.2126 * pixel[0] as f32 + 0.7152 * pixel[1] as f32 + 0.0722 * pixel[2] as f32)
+            .round() as u8;
+        pixel[0] = gray;
+        pixel[1] = gray;
+        pixel[2] = gray;
+    }
+    pixmap
+}
+
+enum CanvasTexture {
+    /// One coverage byte per pixel.
+    Mask {
+        size: Size<DevicePixels>,
+        data: Vec<u8>,
+    },
+    /// Premultiplied RGBA pixels.
+    Pixmap(sk::Pixmap),
+}
+
+impl CanvasTexture {
+    fn len(&self) -> u64 {
+        match self {
+            CanvasTexture::Mask { data, .. } => data.len() as u64,
+            CanvasTexture::Pixmap(pixmap) => pixmap.data().len() as u64,
+        }
+    }
+}
+
+struct CanvasAtlasState {
+    next_id: u32,
+    /// The tiles and the frame they were last used in.
+    tiles: FxHashMap<AtlasKey, (AtlasTile, u64)>,
+    textures: FxHashMap<AtlasTextureId, CanvasTexture>,
+    /// Counts the frames drawn, tiles used in the current one are never evicted.
+    frame: u64,
+    budget: Option<u64>,
+    evicted_tiles: u64,
+}
+
+impl CanvasAtlasState {
+    fn memory_usage(&self) -> u64 {
+        self.textures.values().map(CanvasTexture::len).sum()
+    }
+
+    fn remove(&mut self, key: &AtlasKey) {
+        if let Some((tile, _)) = self.tiles.remove(key) {
+            self.textures.remove(&tile.texture_id);
+        }
+    }
+
+    /// Evicts the tiles used least recently until the atlas fits within its budget.
+    fn enforce_budget(&mut self) {
+        let Some(budget) = self.budget else {
+            return;
+        };
+        let mut bytes = self.memory_usage();
+        if bytes <= budget {
+            return;
+        }
+        let mut candidates = self
+            .tiles
+            .iter()
+            .filter(|(_, (_, last_used))| *last_used < self.frame)
+            .map(|(key, (tile, last_used))| (*last_used, tile.texture_id, key.clone()))
+            .collect::<Vec<_>>();
+        candidates.sort_by_key(|(last_used, _, _)| *last_used);
+        for (_, texture_id, key) in candidates {
+            if bytes <= budget {
+                break;
+            }
+            bytes -= self.textures.get(&texture_id).map_or(0, CanvasTexture::len);
+            self.remove(&key);
+            self.evicted_tiles += 1;
+        }
+    }
+}
+
+/// Keeps every tile in its own CPU-side texture.
+pub(crate) struct CanvasAtlas(Mutex<CanvasAtlasState>);
+
+impl CanvasAtlas {
+    pub fn new() -> Self {
+        Self(Mutex::new(CanvasAtlasState {
+            next_id: 0,
+            tiles: FxHashMap::default(),
+            textures: FxHashMap::default(),
+            frame: 0,
+            budget: None,
+            evicted_tiles: 0,
+        }))
+    }
+
+    pub fn memory_usage(&self) -> u64 {
+        self.0.lock().memory_usage()
+    }
+
+    pub fn next_frame(&self) {
+        self.0.lock().frame += 1;
+    }
+}
+
+impl PlatformAtlas for CanvasAtlas {
+    fn get_or_insert_with<'a>(
+        &self,
+        key: &AtlasKey,
+        build: &mut dyn FnMut() -> Result<Option<(Size<DevicePixels>, Cow<'a, [u8]>)>>,
+    ) -> Result<Option<AtlasTile>> {
+        let mut state = self.0.lock();
+        let frame = state.frame;
+        if let Some((tile, last_used)) = state.tiles.get_mut(key) {
+            *last_used = frame;
+            return Ok(Some(tile.clone()));
+        }
+        drop(state);
+
+        let Some((size, bytes)) = build()? else {
+            return Ok(None);
+        };
+        let kind = key.texture_kind();
+        let texture = match kind {
+            AtlasTextureKind::Polychrome => {
+                // Polychrome tiles are straight alpha BGRA.
+                let mut data = Vec::with_capacity(bytes.len());
+                for pixel in bytes.chunks_exact(4) {
+                    let a = pixel[3] as u16;
+                    let premultiply = |channel: u8| ((channel as u16 * a + 127) / 255) as u8;
+                    data.extend([
+                        premultiply(pixel[2]),
+                        premultiply(pixel[1]),
+                        premultiply(pixel[0]),
+                        pixel[3],
+                    ]);
+                }
+                let size = sk::IntSize::from_wh(size.width.0 as u32, size.height.0 as u32)
+                    .ok_or_else(|| anyhow::anyhow!("invalid tile size {size:?}"))?;
+                CanvasTexture::Pixmap(
+                    sk::Pixmap::from_vec(data, size)
+                        .ok_or_else(|| anyhow::anyhow!("invalid tile data"))?,
+                )
+            }
+            AtlasTextureKind::Monochrome | AtlasTextureKind::Path => CanvasTexture::Mask {
+                size,
+                data: bytes.into_owned(),
+            },
+        };
+
+        let mut state = self.0.lock();
+        state.next_id += 1;
+        let texture_id = AtlasTextureId {
+            index: state.next_id,
+            kind,
+        };
+        let tile = AtlasTile {
+            texture_id,
+            tile_id: TileId(0),
+            padding: 0,
+            bounds: Bounds {
+                origin: Default::default(),
+                size,
+            },
+        };
+        state.textures.insert(texture_id, texture);
+        let frame = state.frame;
+        state.tiles.insert(key.clone(), (tile.clone(), frame));
+        state.enforce_budget();
+        Ok(Some(tile))
+    }
+
+    fn remove(&self, key: &AtlasKey) {
+        self.0.lock().remove(key);
+    }
+
+    fn set_budget(&self, bytes: Option<u64>) {
+        let mut state = self.0.lock();
+        state.budget = bytes;
+        state.enforce_budget();
+    }
+
+    fn usage(&self) -> AtlasUsage {
+        let state = self.0.lock();
+        AtlasUsage {
+            bytes: state.memory_usage(),
+            tiles: state.tiles.len(),
+            evicted_tiles: state.evicted_tiles,
+            budget: state.budget,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::CanvasAtlas;
+    use crate::{size, AtlasKey, DevicePixels, PlatformAtlas, RenderSvgParams};
+
+    fn icon(name: &str) -> AtlasKey {
+        AtlasKey::Svg(RenderSvgParams {
+            path: name.to_string().into(),
+            size: size(DevicePixels(10), DevicePixels(10)),
+        })
+    }
+
+    /// Looks up a 10x10 icon, returning whether it had to be rasterized again.
+    fn use_icon(atlas: &CanvasAtlas, name: &str) -> bool {
+        let mut built = false;
+        atlas
+            .get_or_insert_with(&icon(name), &mut || {
+                built = true;
+                Ok(Some((
+                    size(DevicePixels(10), DevicePixels(10)),
+                    Cow::Owned(vec![0; 100]),
+                )))
+            })
+            .unwrap();
+        built
+    }
+
+    #[test]
+    fn test_atlas_evicts_least_recently_used_tiles() {
+        let atlas = CanvasAtlas::new();
+        atlas.set_budget(Some(300));
+        for name in ["a", "b", "c"] {
+            atlas.next_frame();
+            assert!(use_icon(&atlas, name));
+        }
+
+        // Going over budget evicts "b", the tile left unused the longest.
+        atlas.next_frame();
+        assert!(!use_icon(&atlas, "a"));
+        assert!(use_icon(&atlas, "d"));
+        let usage = atlas.usage();
+        assert_eq!((usage.bytes, usage.tiles, usage.evicted_tiles), (300, 3, 1));
+        assert!(!use_icon(&atlas, "a"));
+        assert!(!use_icon(&atlas, "c"));
+        assert!(!use_icon(&atlas, "d"));
+        assert!(use_icon(&atlas, "b"));
+    }
+
+    #[test]
+    fn test_atlas_keeps_tiles_used_this_frame() {
+        let atlas = CanvasAtlas::new();
+        atlas.set_budget(Some(100));
+        atlas.next_frame();
+        assert!(use_icon(&atlas, "a"));
+        assert!(use_icon(&atlas, "b"));
+
+        // Both tiles are on screen, so the atlas stays over budget rather than evict them.
+        let usage = atlas.usage();
+        assert_eq!((usage.bytes, usage.evicted_tiles), (200, 0));
+
+        atlas.next_frame();
+        assert!(!use_icon(&atlas, "b"));
+        atlas.set_budget(Some(100));
+        let usage = atlas.usage();
+        assert_eq!((usage.bytes, usage.evicted_tiles), (100, 1));
+        assert!(!use_icon(&atlas, "b"));
+    }
+}
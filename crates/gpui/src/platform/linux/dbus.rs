@@ -0,0 +1,128 @@
+//! Desktop services reached over D-Bus, and the bus connections they share.
+
+pub(crate) mod app_menu;
+pub mod dbusmenu;
+pub mod global_shortcuts;
+pub(crate) mod logind;
+pub mod mpris;
+pub mod notifications;
+#[cfg(feature = "screencast")]
+pub mod screencast;
+pub mod status_notifier_host;
+pub mod status_notifier_item;
+pub mod timedate;
+pub mod upower;
+
+use std::{future::Future, sync::Arc};
+
+use futures::{
+    future::{BoxFuture, Shared},
+    FutureExt,
+};
+use zbus::{connection::Builder, Connection};
+
+use crate::{App, BackgroundExecutor, Global, Result, Task};
+
+type SharedConnection = Shared<Task<Result<Connection, Arc<zbus::Error>>>>;
+
+#[derive(Default)]
+struct DBusConnections {
+    session: Option<SharedConnection>,
+    system: Option<SharedConnection>,
+}
+
+impl Global for DBusConnections {}
+
+#[derive(Clone, Copy)]
+enum Bus {
+    Session,
+    System,
+}
+
+/// Returns the application's connection to the D-Bus session bus, connecting on first use.
+///
+/// The connection is shared by everything in the application talking to the session bus, and
+/// is driven by the [`BackgroundExecutor`] rather than a thread of its own.
+pub fn session_bus(cx: &mut App) -> Task<Result<Connection>> {
+    connection(Bus::Session, cx)
+}
+
+/// Returns the application's connection to the D-Bus system bus, connecting on first use.
+///
+/// Like [`session_bus`], the connection is shared and driven by the [`BackgroundExecutor`].
+pub fn system_bus(cx: &mut App) -> Task<Result<Connection>> {
+    connection(Bus::System, cx)
+}
+
+fn connection(bus: Bus, cx: &mut App) -> Task<Result<Connection>> {
+    let executor = cx.background_executor().clone();
+    let connections = cx.default_global::<DBusConnections>();
+    let slot = match bus {
+        Bus::Session => &mut connections.session,
+        Bus::System => &mut connections.system,
+    };
+    // A failed attempt isn't cached, the bus may have come up since.
+    if let Some(Err(_)) = slot.as_ref().and_then(|connection| connection.peek()) {
+        *slot = None;
+    }
+    let connection = slot
+        .get_or_insert_with(|| connect(bus, executor.clone()).shared())
+        .clone();
+    executor.spawn(async move { Ok(connection.await?) })
+}
+
+fn connect(bus: Bus, executor: BackgroundExecutor) -> Task<Result<Connection, Arc<zbus::Error>>> {
+    executor.clone().spawn(async move {
+        let builder = match bus {
+            Bus::Session => Builder::session(),
+            Bus::System => Builder::system(),
+        };
+        let connection = builder
+            .map_err(Arc::new)?
+            .internal_executor(false)
+            .build()
+            .await
+            .map_err(Arc::new)?;
+
+        // zbus dispatches incoming messages and method calls on its executor, which would
+        // otherwise need a thread per connection.
+        let ticker = connection.clone();
+        executor
+            .spawn(async move {
+                loop {
+                    ticker.executor().tick().await;
+                }
+            })
+            .detach();
+
+        Ok(connection)
+    })
+}
+
+/// Gives back what was claimed on a shared connection, like well-known names and served objects,
+/// once dropped. Held by the tasks serving them, since they'd otherwise outlive their owner along
+/// with the connection.
+pub(crate) struct ReleaseOnDrop {
+    executor: BackgroundExecutor,
+    release: Option<BoxFuture<'static, ()>>,
+}
+
+impl ReleaseOnDrop {
+    pub(crate) fn new(
+        executor: BackgroundExecutor,
+        release: impl Future<Output = ()> + Send + 'static,
+    ) -> Self {
+        Self {
+            executor,
+            release: Some(release.boxed()),
+        }
+    }
+}
+
+impl Drop for ReleaseOnDrop {
+    fn drop(&mut self) {
+        if let Some(release) = self.release.take() {
+            self.executor.spawn(release).detach();
+        }
+    }
+}
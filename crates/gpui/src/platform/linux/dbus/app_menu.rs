@@ -0,0 +1,478 @@
+//! Exports the application's menu bar over [dbusmenu], for desktops showing the menus of the
+//! focused window in a global menu.
+//!
+//! The menus are served on a session bus connection of their own, and windows point the desktop
+//! at them: X11 windows by registering with `com.canonical.AppMenu.Registrar`, Wayland windows
+//! through `org_kde_kwin_appmenu` on compositors that implement it.
+//!
+//! [dbusmenu]: https://github.com/AyatanaIndicators/libdbusmenu
+
+use std::{collections::HashMap, sync::Arc};
+
+use anyhow::Context as _;
+use calloop::channel::{Channel, Sender};
+use futures::{future::Shared, FutureExt};
+use parking_lot::Mutex;
+use util::ResultExt as _;
+use zbus::{
+    object_server::SignalEmitter,
+    zvariant::{ObjectPath, OwnedValue, Value},
+    Connection,
+};
+
+use crate::{Action, BackgroundExecutor, Keymap, OwnedMenu, OwnedMenuItem, Task};
+
+/// The path the menu bar is served at.
+pub(crate) const APP_MENU_PATH: &str = "/com/canonical/menu/gpui";
+
+/// Events of the exported menus, handled on the platform's event loop.
+pub(crate) enum AppMenuEvent {
+    /// The menus are being served by the connection with the given unique name.
+    Exported(String),
+    /// A menu is about to be shown.
+    WillOpen,
+    /// An item of the menus was activated.
+    Activated(Box<dyn Action>),
+}
+
+/// The layout of a menu node: its id, its properties and its children, each a variant holding
+/// another layout.
+type MenuLayout = (i32, HashMap<String, OwnedValue>, Vec<OwnedValue>);
+
+enum NodeKind {
+    Root,
+    Submenu,
+    Separator,
+    Action(Box<dyn Action>),
+}
+
+struct MenuNode {
+    kind: NodeKind,
+    label: String,
+    /// The keystrokes of the action's binding, in the format of the `shortcut` property.
+    shortcut: Vec<Vec<String>>,
+    children: Vec<i32>,
+}
+
+/// The menus as exported, where an item's id is its index.
+pub(crate) struct MenuTree {
+    revision: u32,
+    nodes: Vec<MenuNode>,
+}
+
+impl Default for MenuTree {
+    fn default() -> Self {
+        Self {
+            revision: 0,
+            nodes: vec![MenuNode {
+                kind: NodeKind::Root,
+                label: String::new(),
+                shortcut: Vec::new(),
+                children: Vec::new(),
+            }],
+        }
+    }
+}
+
+impl MenuTree {
+    /// Builds the tree of a menu bar, whose top level items are the given menus.
+    pub fn new(menus: &[OwnedMenu], keymap: &Keymap, revision: u32) -> Self {
+        let mut tree = Self {
+            revision,
+            ..Self::default()
+        };
+        for menu in menus {
+            let id = tree.push_submenu(&menu.name, &menu.items, keymap);
+            tree.nodes[0].children.push(id);
+        }
+        tree
+    }
+
+    /// Builds the tree of a single menu, like the context menu of a tray icon.
+    pub fn from_items(items: &[OwnedMenuItem], keymap: &Keymap, revision: u32) -> Self {
+        let mut tree = Self {
+            revision,
+            ..Self::default()
+        };
+        tree.push_items(0, items, keymap);
+        tree
+    }
+
+    pub fn revision(&self) -> u32 {
+        self.revision
+    }
+
+    /// Whether the tree has no items besides its root.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.len() == 1
+    }
+
+    fn push_submenu(&mut self, name: &str, items: &[OwnedMenuItem], keymap: &Keymap) -> i32 {
+        let id = self.push(NodeKind::Submenu, escape_label(name), Vec::new());
+        self.push_items(id, items, keymap);
+        id
+    }
+
+    fn push_items(&mut self, id: i32, items: &[OwnedMenuItem], keymap: &Keymap) {
+        for item in items {
+            let child = match item {
+                OwnedMenuItem::Separator => {
+                    self.push(NodeKind::Separator, String::new(), Vec::new())
+                }
+                OwnedMenuItem::Submenu(submenu) => {
+                    self.push_submenu(&submenu.name, &submenu.items, keymap)
+                }
+                OwnedMenuItem::Action { name, action, .. } => {
+                    let shortcut = Keymap::binding_to_display_from_bindings(
+                        keymap.bindings_for_action(action.as_ref()),
+                    )
+                    .map(|binding| binding.keystrokes().iter().map(shortcut_keys).collect())
+                    .unwrap_or_default();
+                    self.push(
+                        NodeKind::Action(action.boxed_clone()),
+                        escape_label(name),
+                        shortcut,
+                    )
+                }
+            };
+            self.nodes[id as usize].children.push(child);
+        }
+    }
+
+    fn push(&mut self, kind: NodeKind, label: String, shortcut: Vec<Vec<String>>) -> i32 {
+        self.nodes.push(MenuNode {
+            kind,
+            label,
+            shortcut,
+            children: Vec::new(),
+        });
+        self.nodes.len() as i32 - 1
+    }
+
+    fn node(&self, id: i32) -> Option<&MenuNode> {
+        usize::try_from(id).ok().and_then(|id| self.nodes.get(id))
+    }
+
+    fn properties(&self, id: i32, names: &[String]) -> HashMap<String, OwnedValue> {
+        let Some(node) = self.node(id) else {
+            return HashMap::new();
+        };
+        let mut properties = HashMap::new();
+        let mut insert = |name: &str, value: Value<'_>| {
+            if names.is_empty() || names.iter().any(|requested| requested == name) {
+                if let Some(value) = OwnedValue::try_from(value).log_err() {
+                    properties.insert(name.to_string(), value);
+                }
+            }
+        };
+        match &node.kind {
+            NodeKind::Root => insert("children-display", Value::from("submenu")),
+            NodeKind::Submenu => {
+                insert("label", Value::from(node.label.as_str()));
+                insert("children-display", Value::from("submenu"));
+            }
+            NodeKind::Separator => insert("type", Value::from("separator")),
+            NodeKind::Action(_) => {
+                insert("label", Value::from(node.label.as_str()));
+                if !node.shortcut.is_empty() {
+                    insert("shortcut", Value::from(node.shortcut.clone()));
+                }
+            }
+        }
+        properties
+    }
+
+    fn layout(&self, id: i32, depth: i32, names: &[String]) -> MenuLayout {
+        let children = match self.node(id) {
+            Some(node) if depth != 0 => node
+                .children
+                .iter()
+                .filter_map(|&child| {
+                    OwnedValue::try_from(Value::from(self.layout(child, depth - 1, names)))
+                        .log_err()
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        (id, self.properties(id, names), children)
+    }
+}
+
+/// Marks underscores as literal, since dbusmenu uses them to mark access keys.
+fn escape_label(label: &str) -> String {
+    label.replace('_', "__")
+}
+
+fn shortcut_keys(keystroke: &crate::Keystroke) -> Vec<String> {
+    let modifiers = &keystroke.modifiers;
+    let mut keys = Vec::new();
+    if modifiers.control {
+        keys.push("Control".to_string());
+    }
+    if modifiers.alt {
+        keys.push("Alt".to_string());
+    }
+    if modifiers.shift {
+        keys.push("Shift".to_string());
+    }
+    if modifiers.platform {
+        keys.push("Super".to_string());
+    }
+    keys.push(keystroke.key.clone());
+    keys
+}
+
+/// Serves the application's menus, see the [module documentation](self).
+pub(crate) struct AppMenuExporter {
+    executor: BackgroundExecutor,
+    tree: Arc<Mutex<MenuTree>>,
+    connection: Shared<Task<Option<Connection>>>,
+    service: Option<String>,
+}
+
+impl AppMenuExporter {
+    /// Starts serving the menus, returning the channel their events are sent to.
+    pub fn new(executor: &BackgroundExecutor) -> (Self, Channel<AppMenuEvent>) {
+        let (events, channel) = calloop::channel::channel();
+        let tree = Arc::new(Mutex::new(MenuTree::default()));
+        let connection = executor
+            .spawn({
+                let tree = tree.clone();
+                async move { serve(tree, events).await.log_err() }
+            })
+            .shared();
+        let exporter = Self {
+            executor: executor.clone(),
+            tree,
+            connection,
+            service: None,
+        };
+        (exporter, channel)
+    }
+
+    /// Returns the unique name of the connection serving the menus, once it's known.
+    pub fn service(&self) -> Option<&str> {
+        self.service.as_deref()
+    }
+
+    pub fn set_service(&mut self, service: String) {
+        self.service = Some(service);
+    }
+
+    /// Replaces the exported menus, telling the desktop to fetch them again.
+    pub fn set_menus(&self, menus: &[OwnedMenu], keymap: &Keymap) {
+        let revision = {
+            let mut tree = self.tree.lock();
+            *tree = MenuTree::new(menus, keymap, tree.revision + 1);
+            tree.revision
+        };
+        let connection = self.connection.clone();
+        self.executor
+            .spawn(async move {
+                if let Some(connection) = connection.await {
+                    emit_layout_updated(&connection, APP_MENU_PATH, revision)
+                        .await
+                        .log_err();
+                }
+            })
+            .detach();
+    }
+
+    /// Registers an X11 window's menu bar with the desktop's registrar.
+    pub fn register_x11_window(&self, window: u32) {
+        let connection = self.connection.clone();
+        self.executor
+            .spawn(async move {
+                let Some(connection) = connection.await else {
+                    return;
+                };
+                let registrar = match AppMenuRegistrarProxy::new(&connection).await {
+                    Ok(registrar) => registrar,
+                    Err(error) => {
+                        log::debug!("no application menu registrar: {error}");
+                        return;
+                    }
+                };
+                // Desktops without a global menu don't provide a registrar.
+                if let Err(error) = registrar
+                    .register_window(window, ObjectPath::from_static_str_unchecked(APP_MENU_PATH))
+                    .await
+                {
+                    log::debug!("failed to register the application menu: {error}");
+                }
+            })
+            .detach();
+    }
+}
+
+async fn serve(
+    tree: Arc<Mutex<MenuTree>>,
+    events: Sender<AppMenuEvent>,
+) -> anyhow::Result<Connection> {
+    let connection = Connection::session().await?;
+    connection
+        .object_server()
+        .at(
+            APP_MENU_PATH,
+            DbusmenuInterface::new(tree, {
+                let events = events.clone();
+                move |event| {
+                    events.send(event).log_err();
+                }
+            }),
+        )
+        .await?;
+    let service = connection
+        .unique_name()
+        .context("the session bus connection has no unique name")?
+        .to_string();
+    events.send(AppMenuEvent::Exported(service))?;
+    Ok(connection)
+}
+
+/// Tells the desktop to fetch the menu served at the given path again.
+pub(crate) async fn emit_layout_updated(
+    connection: &Connection,
+    path: &str,
+    revision: u32,
+) -> zbus::Result<()> {
+    let emitter = SignalEmitter::new(connection, path)?;
+    DbusmenuInterface::layout_updated(&emitter, revision, 0).await
+}
+
+/// Serves a [`MenuTree`] as `com.canonical.dbusmenu`, reporting the events of its items.
+/// [`AppMenuEvent::Exported`] is never reported, it's up to the owner of the connection.
+pub(crate) struct DbusmenuInterface {
+    tree: Arc<Mutex<MenuTree>>,
+    events: Box<dyn Fn(AppMenuEvent) + Send + Sync>,
+}
+
+impl DbusmenuInterface {
+    pub fn new(
+        tree: Arc<Mutex<MenuTree>>,
+        events: impl Fn(AppMenuEvent) + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            tree,
+            events: Box::new(events),
+        }
+    }
+
+    fn handle_event(&self, id: i32, event_id: &str) -> bool {
+        let tree = self.tree.lock();
+        let Some(node) = tree.node(id) else {
+            return false;
+        };
+        let event = match (&node.kind, event_id) {
+            (NodeKind::Action(action), "clicked") => AppMenuEvent::Activated(action.boxed_clone()),
+            (NodeKind::Root | NodeKind::Submenu, "opened") => AppMenuEvent::WillOpen,
+            _ => return true,
+        };
+        (self.events)(event);
+        true
+    }
+}
+
+#[zbus::interface(name = "com.canonical.dbusmenu")]
+impl DbusmenuInterface {
+    fn get_layout(
+        &self,
+        parent_id: i32,
+        recursion_depth: i32,
+        property_names: Vec<String>,
+    ) -> zbus::fdo::Result<(u32, MenuLayout)> {
+        let tree = self.tree.lock();
+        if tree.node(parent_id).is_none() {
+            return Err(zbus::fdo::Error::InvalidArgs(format!(
+                "unknown menu item {parent_id}"
+            )));
+        }
+        Ok((
+            tree.revision,
+            tree.layout(parent_id, recursion_depth, &property_names),
+        ))
+    }
+
+    fn get_group_properties(
+        &self,
+        ids: Vec<i32>,
+        property_names: Vec<String>,
+    ) -> Vec<(i32, HashMap<String, OwnedValue>)> {
+        let tree = self.tree.lock();
+        ids.into_iter()
+            .filter(|&id| tree.node(id).is_some())
+            .map(|id| (id, tree.properties(id, &property_names)))
+            .collect()
+    }
+
+    fn get_property(&self, id: i32, name: String) -> zbus::fdo::Result<OwnedValue> {
+        let mut properties = self.tree.lock().properties(id, &[name.clone()]);
+        properties
+            .remove(&name)
+            .ok_or_else(|| zbus::fdo::Error::InvalidArgs(format!("no property {name} on {id}")))
+    }
+
+    fn event(&self, id: i32, event_id: String, _data: OwnedValue, _timestamp: u32) {
+        self.handle_event(id, &event_id);
+    }
+
+    fn event_group(&self, events: Vec<(i32, String, OwnedValue, u32)>) -> Vec<i32> {
+        events
+            .into_iter()
+            .filter(|(id, event_id, _, _)| !self.handle_event(*id, event_id))
+            .map(|(id, _, _, _)| id)
+            .collect()
+    }
+
+    fn about_to_show(&self, id: i32) -> bool {
+        if id == 0 {
+            (self.events)(AppMenuEvent::WillOpen);
+        }
+        // The menus are replaced as a whole with `set_menus`, never updated lazily.
+        false
+    }
+
+    fn about_to_show_group(&self, ids: Vec<i32>) -> (Vec<i32>, Vec<i32>) {
+        if ids.contains(&0) {
+            (self.events)(AppMenuEvent::WillOpen);
+        }
+        (Vec::new(), Vec::new())
+    }
+
+    #[zbus(property)]
+    fn version(&self) -> u32 {
+        3
+    }
+
+    #[zbus(property)]
+    fn text_direction(&self) -> String {
+        "ltr".to_string()
+    }
+
+    #[zbus(property)]
+    fn status(&self) -> String {
+        "normal".to_string()
+    }
+
+    #[zbus(property)]
+    fn icon_theme_path(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    #[zbus(signal)]
+    async fn layout_updated(
+        emitter: &SignalEmitter<'_>,
+        revision: u32,
+        parent: i32,
+    ) -> zbus::Result<()>;
+}
+
+#[zbus::proxy(
+    interface = "com.canonical.AppMenu.Registrar",
+    default_service = "com.canonical.AppMenu.Registrar",
+    default_path = "/com/canonical/AppMenu/Registrar"
+)]
+trait AppMenuRegistrar {
+    fn register_window(&self, window_id: u32, menu_object_path: ObjectPath<'_>)
+        -> zbus::Result<()>;
+}
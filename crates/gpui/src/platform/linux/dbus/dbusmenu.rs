@@ -0,0 +1,508 @@
+//! A client for menus exported over [dbusmenu], like the menus of tray items and the global
+//! menus applications export for a top bar to show.
+//!
+//! A [`DBusMenu`] mirrors the menu an application exports at a path, and can turn it into
+//! [`Menu`]s whose items activate the exported ones, so it can be shown like any other menu of
+//! the app.
+//!
+//! [dbusmenu]: https://github.com/AyatanaIndicators/libdbusmenu
+
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use collections::FxHashMap;
+use futures::{stream, StreamExt};
+use util::ResultExt as _;
+use zbus::{
+    proxy::CacheProperties,
+    zvariant::{OwnedObjectPath, OwnedValue, Value},
+    Connection,
+};
+
+use crate::{
+    self as gpui, impl_internal_actions, session_bus, App, AppContext, AsyncApp, Context, Entity,
+    EntityId, Global, Menu, MenuItem, Result, SharedString, Task, WeakEntity,
+};
+
+/// The properties requested for every menu item, see the dbusmenu specification.
+const MENU_PROPERTIES: &[&str] = &[
+    "type",
+    "label",
+    "enabled",
+    "visible",
+    "icon-name",
+    "toggle-type",
+    "toggle-state",
+    "children-display",
+];
+
+/// Activates an item of a [`DBusMenu`], dispatched by the items of the menus returned by
+/// [`DBusMenu::menus`].
+#[derive(Clone, Default, PartialEq)]
+pub struct ActivateDBusMenuItem {
+    /// The entity of the menu.
+    pub menu: EntityId,
+    /// The identifier of the item.
+    pub id: i32,
+}
+
+impl_internal_actions!(dbusmenu, [ActivateDBusMenuItem]);
+
+/// How a [`DBusMenuItem`] can be toggled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DBusMenuToggle {
+    /// The item is an independent checkbox.
+    Checkmark {
+        /// Whether the item is checked.
+        checked: bool,
+    },
+    /// The item is part of a group of mutually exclusive items.
+    Radio {
+        /// Whether the item is selected.
+        checked: bool,
+    },
+}
+
+/// An entry of a [`DBusMenu`].
+#[derive(Clone, Debug)]
+pub struct DBusMenuItem {
+    /// The identifier of the item, to pass to [`DBusMenu::activate`].
+    pub id: i32,
+    /// The label of the item, with its access key markers removed.
+    pub label: SharedString,
+    /// The access key of the item, marked by an underscore in its label.
+    pub access_key: Option<char>,
+    /// Whether the item is a separator.
+    pub is_separator: bool,
+    /// Whether the item can be activated.
+    pub enabled: bool,
+    /// Whether the item should be shown.
+    pub visible: bool,
+    /// The freedesktop icon name of the item, if any.
+    pub icon_name: Option<SharedString>,
+    /// How the item can be toggled, if at all.
+    pub toggle: Option<DBusMenuToggle>,
+    /// Whether the item opens a submenu. Applications may only fill in its children once
+    /// they're told the submenu is about to be shown, see [`DBusMenu::about_to_show`].
+    pub has_submenu: bool,
+    /// The items of the submenu opened by this item.
+    pub children: Vec<DBusMenuItem>,
+}
+
+/// The layout of a menu node: its id, its properties and its children, each a variant holding
+/// another layout.
+type MenuLayout = (i32, HashMap<String, OwnedValue>, Vec<OwnedValue>);
+
+impl DBusMenuItem {
+    fn from_layout((id, properties, children): MenuLayout) -> Self {
+        let string = |name: &str| {
+            properties
+                .get(name)
+                .and_then(|value| value.downcast_ref::<&str>().ok())
+        };
+        let bool = |name: &str, default: bool| {
+            properties
+                .get(name)
+                .and_then(|value| value.downcast_ref::<bool>().ok())
+                .unwrap_or(default)
+        };
+        let checked = properties
+            .get("toggle-state")
+            .and_then(|value| value.downcast_ref::<i32>().ok())
+            == Some(1);
+        let (label, access_key) = string("label").map(parse_label).unwrap_or_default();
+        let children = children
+            .into_iter()
+            .filter_map(|child| MenuLayout::try_from(child).log_err())
+            .map(Self::from_layout)
+            .collect::<Vec<_>>();
+
+        Self {
+            id,
+            label,
+            access_key,
+            is_separator: string("type") == Some("separator"),
+            enabled: bool("enabled", true),
+            visible: bool("visible", true),
+            icon_name: string("icon-name")
+                .filter(|name| !name.is_empty())
+                .map(|name| SharedString::from(name.to_string())),
+            toggle: match string("toggle-type") {
+                Some("checkmark") => Some(DBusMenuToggle::Checkmark { checked }),
+                Some("radio") => Some(DBusMenuToggle::Radio { checked }),
+                _ => None,
+            },
+            has_submenu: string("children-display") == Some("submenu") || !children.is_empty(),
+            children,
+        }
+    }
+
+    fn to_menu_item(&self, menu: EntityId) -> Option<MenuItem> {
+        if !self.visible {
+            return None;
+        }
+        Some(if self.is_separator {
+            MenuItem::separator()
+        } else if self.has_submenu {
+            MenuItem::submenu(Menu {
+                name: self.label.clone(),
+                items: to_menu_items(&self.children, menu),
+            })
+        } else {
+            MenuItem::action(
+                self.label.clone(),
+                ActivateDBusMenuItem { menu, id: self.id },
+            )
+        })
+    }
+}
+
+fn to_menu_items(items: &[DBusMenuItem], menu: EntityId) -> Vec<MenuItem> {
+    items
+        .iter()
+        .filter_map(|item| item.to_menu_item(menu))
+        .collect()
+}
+
+/// Removes the underscores marking access keys from a dbusmenu label, keeping escaped ones, and
+/// returns the first access key.
+fn parse_label(label: &str) -> (SharedString, Option<char>) {
+    let mut result = String::with_capacity(label.len());
+    let mut access_key = None;
+    let mut chars = label.chars();
+    while let Some(char) = chars.next() {
+        if char == '_' {
+            if let Some(next) = chars.next() {
+                if next != '_' && access_key.is_none() {
+                    access_key = Some(next);
+                }
+                result.push(next);
+            }
+        } else {
+            result.push(char);
+        }
+    }
+    (result.into(), access_key)
+}
+
+/// The live menus, for [`ActivateDBusMenuItem`] to find the one its item belongs to.
+#[derive(Default)]
+struct DBusMenus(FxHashMap<EntityId, WeakEntity<DBusMenu>>);
+
+impl Global for DBusMenus {}
+
+/// A menu exported by another application over dbusmenu, kept up to date as it changes.
+pub struct DBusMenu {
+    proxy: Option<DbusmenuProxy<'static>>,
+    items: Vec<DBusMenuItem>,
+    _task: Task<()>,
+}
+
+impl DBusMenu {
+    /// Connects to the menu exported by the given D-Bus service at the given path on the session
+    /// bus. A top bar finds those of the focused application's global menu through the
+    /// application menu registrar of the desktop.
+    pub fn new(service: impl Into<String>, path: OwnedObjectPath, cx: &mut Context<Self>) -> Self {
+        let service = service.into();
+        let connection = session_bus(cx);
+        Self::spawn(
+            |this, mut cx| async move {
+                let connection = connection.await?;
+                Self::listen(this, connection, service, path, &mut cx).await
+            },
+            cx,
+        )
+    }
+
+    pub(crate) fn with_connection(
+        connection: Connection,
+        service: String,
+        path: OwnedObjectPath,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        Self::spawn(
+            |this, mut cx| async move { Self::listen(this, connection, service, path, &mut cx).await },
+            cx,
+        )
+    }
+
+    fn spawn<F>(
+        listen: impl FnOnce(WeakEntity<Self>, AsyncApp) -> F + 'static,
+        cx: &mut Context<Self>,
+    ) -> Self
+    where
+        F: std::future::Future<Output = Result<()>> + 'static,
+    {
+        if !cx.has_global::<DBusMenus>() {
+            cx.on_action(activate_item);
+        }
+        let entity_id = cx.entity_id();
+        let this = cx.weak_entity();
+        let menus = cx.default_global::<DBusMenus>();
+        menus.0.retain(|_, menu| menu.is_upgradable());
+        menus.0.insert(entity_id, this);
+
+        let task = cx.spawn(|this, cx| async move {
+            listen(this, cx).await.log_err();
+        });
+        Self {
+            proxy: None,
+            items: Vec::new(),
+            _task: task,
+        }
+    }
+
+    async fn listen(
+        this: WeakEntity<Self>,
+        connection: Connection,
+        service: String,
+        path: OwnedObjectPath,
+        cx: &mut AsyncApp,
+    ) -> Result<()> {
+        let proxy = DbusmenuProxy::builder(&connection)
+            .destination(service)?
+            .path(path)?
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await?;
+        let mut changes = stream::select_all([
+            proxy.receive_layout_updated().await?.map(|_| ()).boxed(),
+            proxy
+                .receive_items_properties_updated()
+                .await?
+                .map(|_| ())
+                .boxed(),
+        ]);
+        this.update(cx, |this, _| this.proxy = Some(proxy.clone()))?;
+
+        loop {
+            let (_revision, layout) = proxy.get_layout(0, -1, MENU_PROPERTIES).await?;
+            let items = DBusMenuItem::from_layout(layout).children;
+            this.update(cx, |this, cx| {
+                this.items = items;
+                cx.notify();
+            })?;
+
+            if changes.next().await.is_none() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Returns the top level items of the menu.
+    pub fn items(&self) -> &[DBusMenuItem] {
+        &self.items
+    }
+
+    /// Returns the menu as the menus of a menu bar, one for each top level item, whose items
+    /// dispatch [`ActivateDBusMenuItem`] to activate the exported ones. Top level items without
+    /// a submenu are left out.
+    pub fn menus(menu: &Entity<Self>, cx: &App) -> Vec<Menu> {
+        let entity_id = menu.entity_id();
+        menu.read(cx)
+            .items
+            .iter()
+            .filter(|item| item.visible && item.has_submenu)
+            .map(|item| Menu {
+                name: item.label.clone(),
+                items: to_menu_items(&item.children, entity_id),
+            })
+            .collect()
+    }
+
+    /// Tells the application that the submenu of the given item is about to be shown, giving it
+    /// a chance to update the submenu. Pass 0 for the root menu.
+    pub fn about_to_show(&self, id: i32, cx: &App) -> Task<Result<()>> {
+        let Some(proxy) = self.proxy.clone() else {
+            return Task::ready(Err(anyhow!("dbusmenu is not connected yet")));
+        };
+        cx.background_executor().spawn(async move {
+            proxy.about_to_show(id).await?;
+            Ok(())
+        })
+    }
+
+    /// Activates the menu item with the given id.
+    pub fn activate(&self, id: i32, cx: &App) -> Task<Result<()>> {
+        let Some(proxy) = self.proxy.clone() else {
+            return Task::ready(Err(anyhow!("dbusmenu is not connected yet")));
+        };
+        cx.background_executor().spawn(async move {
+            proxy.event(id, "clicked", &Value::from(0i32), 0).await?;
+            Ok(())
+        })
+    }
+}
+
+fn activate_item(action: &ActivateDBusMenuItem, cx: &mut App) {
+    let Some(menu) = cx
+        .try_global::<DBusMenus>()
+        .and_then(|menus| menus.0.get(&action.menu))
+        .and_then(|menu| menu.upgrade())
+    else {
+        return;
+    };
+    menu.read(cx).activate(action.id, cx).detach_and_log_err(cx);
+}
+
+#[zbus::proxy(interface = "com.canonical.dbusmenu")]
+trait Dbusmenu {
+    fn get_layout(
+        &self,
+        parent_id: i32,
+        recursion_depth: i32,
+        property_names: &[&str],
+    ) -> zbus::Result<(u32, MenuLayout)>;
+
+    fn event(&self, id: i32, event_id: &str, data: &Value<'_>, timestamp: u32) -> zbus::Result<()>;
+
+    fn about_to_show(&self, id: i32) -> zbus::Result<bool>;
+
+    #[zbus(signal)]
+    fn layout_updated(&self, revision: u32, parent: i32) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn items_properties_updated(
+        &self,
+        updated_props: Vec<(i32, HashMap<String, OwnedValue>)>,
+        removed_props: Vec<(i32, Vec<String>)>,
+    ) -> zbus::Result<()>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(
+        id: i32,
+        properties: Vec<(&str, Value<'static>)>,
+        children: Vec<MenuLayout>,
+    ) -> MenuLayout {
+        (
+            id,
+            properties
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value.try_to_owned().unwrap()))
+                .collect(),
+            children
+                .into_iter()
+                .map(|child| Value::from(child).try_to_owned().unwrap())
+                .collect(),
+        )
+    }
+
+    fn file_menu() -> DBusMenuItem {
+        DBusMenuItem::from_layout(layout(
+            0,
+            Vec::new(),
+            vec![
+                layout(
+                    1,
+                    vec![
+                        ("label", "_File".into()),
+                        ("children-display", "submenu".into()),
+                    ],
+                    vec![
+                        layout(2, vec![("label", "_New".into())], Vec::new()),
+                        layout(3, vec![("type", "separator".into())], Vec::new()),
+                        layout(
+                            4,
+                            vec![
+                                ("label", "Show _Hidden".into()),
+                                ("toggle-type", "checkmark".into()),
+                                ("toggle-state", 1i32.into()),
+                            ],
+                            Vec::new(),
+                        ),
+                        layout(
+                            5,
+                            vec![
+                                ("label", "Sort".into()),
+                                ("toggle-type", "radio".into()),
+                                ("toggle-state", 0i32.into()),
+                                ("enabled", false.into()),
+                            ],
+                            Vec::new(),
+                        ),
+                        layout(
+                            6,
+                            vec![("label", "Secret".into()), ("visible", false.into())],
+                            Vec::new(),
+                        ),
+                    ],
+                ),
+                // Applications may only fill in a submenu once it's about to be shown.
+                layout(
+                    7,
+                    vec![
+                        ("label", "Recent".into()),
+                        ("children-display", "submenu".into()),
+                    ],
+                    Vec::new(),
+                ),
+                layout(8, vec![("label", "Quit".into())], Vec::new()),
+            ],
+        ))
+    }
+
+    /// Describes menu items as a line of text, with the ids of the items they activate.
+    fn outline(items: &[MenuItem], menu: EntityId) -> String {
+        items
+            .iter()
+            .map(|item| match item {
+                MenuItem::Separator => "-".to_string(),
+                MenuItem::Submenu(submenu) => {
+                    format!("{} [{}]", submenu.name, outline(&submenu.items, menu))
+                }
+                MenuItem::Action { name, action, .. } => {
+                    let action = action
+                        .as_any()
+                        .downcast_ref::<ActivateDBusMenuItem>()
+                        .unwrap();
+                    assert_eq!(action.menu, menu);
+                    format!("{name} #{}", action.id)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    #[test]
+    fn test_items_from_layout() {
+        let root = file_menu();
+        let [file, recent, quit] = root.children.as_slice() else {
+            panic!("expected three items, got {:?}", root.children);
+        };
+        assert_eq!((file.label.as_ref(), file.access_key), ("File", Some('F')));
+        assert!(file.has_submenu);
+        assert!(recent.has_submenu && recent.children.is_empty());
+        assert!(!quit.has_submenu);
+
+        let [new, separator, hidden, sort, secret] = file.children.as_slice() else {
+            panic!("expected five items, got {:?}", file.children);
+        };
+        assert!(!new.is_separator && new.toggle.is_none());
+        assert!(separator.is_separator);
+        assert_eq!(
+            (hidden.label.as_ref(), hidden.access_key),
+            ("Show Hidden", Some('H'))
+        );
+        assert_eq!(
+            hidden.toggle,
+            Some(DBusMenuToggle::Checkmark { checked: true })
+        );
+        assert_eq!(sort.toggle, Some(DBusMenuToggle::Radio { checked: false }));
+        assert!(!sort.enabled && sort.visible);
+        assert!(secret.enabled && !secret.visible);
+    }
+
+    #[test]
+    fn test_items_to_menu_items() {
+        let menu = EntityId::from(1);
+        let items = to_menu_items(&file_menu().children, menu);
+        assert_eq!(
+            outline(&items, menu),
+            "File [New #2, -, Show Hidden #4, Sort #5], Recent [], Quit #8"
+        );
+    }
+}
@@ -0,0 +1,191 @@
+//! App-global keyboard shortcuts, bound through the [GlobalShortcuts portal] so they trigger
+//! even when none of the application's windows has keyboard focus.
+//!
+//! [GlobalShortcuts portal]: https://flatpak.github.io/xdg-desktop-portal/docs/doc-org.freedesktop.portal.GlobalShortcuts.html
+
+use ashpd::desktop::global_shortcuts::{GlobalShortcuts as GlobalShortcutsPortal, NewShortcut};
+use futures::{stream, StreamExt};
+
+use crate::{AsyncApp, Context, EventEmitter, Result, SharedString, Task, WeakEntity};
+
+/// A shortcut to register with [`GlobalShortcuts`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GlobalShortcut {
+    /// The identifier of the shortcut, reported when it is activated.
+    pub id: SharedString,
+    /// A description of what the shortcut does, shown to the user when binding it.
+    pub description: SharedString,
+    /// The trigger the application would like, following the [shortcuts specification], like
+    /// `LOGO+space`. The desktop may offer it to the user, who picks the actual trigger.
+    ///
+    /// [shortcuts specification]: https://specifications.freedesktop.org/shortcuts-spec/latest/
+    pub preferred_trigger: Option<SharedString>,
+}
+
+impl GlobalShortcut {
+    /// Creates a shortcut without a preferred trigger.
+    pub fn new(id: impl Into<SharedString>, description: impl Into<SharedString>) -> Self {
+        Self {
+            id: id.into(),
+            description: description.into(),
+            preferred_trigger: None,
+        }
+    }
+
+    /// Sets the trigger the application would like for this shortcut.
+    pub fn preferred_trigger(mut self, trigger: impl Into<SharedString>) -> Self {
+        self.preferred_trigger = Some(trigger.into());
+        self
+    }
+}
+
+/// A shortcut as bound by the desktop.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BoundGlobalShortcut {
+    /// The identifier of the shortcut.
+    pub id: SharedString,
+    /// The description of the shortcut.
+    pub description: SharedString,
+    /// A human readable description of the trigger the user bound the shortcut to.
+    pub trigger_description: SharedString,
+}
+
+/// An event emitted by [`GlobalShortcuts`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GlobalShortcutEvent {
+    /// The shortcut with the given id was pressed.
+    Activated(SharedString),
+    /// The shortcut with the given id was released.
+    Deactivated(SharedString),
+}
+
+/// Registers app-global shortcuts with the desktop and emits a [`GlobalShortcutEvent`] when
+/// they're triggered.
+///
+/// Create it with `cx.new(|cx| GlobalShortcuts::new(shortcuts, cx))` and subscribe to it.
+/// The desktop usually asks the user to confirm or change the triggers the first time the
+/// shortcuts are registered.
+pub struct GlobalShortcuts {
+    bound: Vec<BoundGlobalShortcut>,
+    _task: Task<()>,
+}
+
+impl EventEmitter<GlobalShortcutEvent> for GlobalShortcuts {}
+
+enum PortalEvent {
+    Activated(String, String),
+    Deactivated(String, String),
+    Changed(String, Vec<BoundGlobalShortcut>),
+}
+
+impl GlobalShortcuts {
+    /// Registers the given shortcuts with the GlobalShortcuts portal.
+    pub fn new(shortcuts: Vec<GlobalShortcut>, cx: &mut Context<Self>) -> Self {
+        let task = cx.spawn(|this, mut cx| async move {
+            if let Err(error) = Self::run(this, shortcuts, &mut cx).await {
+                log::error!("failed to register global shortcuts: {error:#}");
+            }
+        });
+        Self {
+            bound: Vec::new(),
+            _task: task,
+        }
+    }
+
+    /// Returns the shortcuts bound by the desktop, which can be fewer than the ones registered
+    /// when the user declined some of them.
+    pub fn bound_shortcuts(&self) -> &[BoundGlobalShortcut] {
+        &self.bound
+    }
+
+    async fn run(
+        this: WeakEntity<Self>,
+        shortcuts: Vec<GlobalShortcut>,
+        cx: &mut AsyncApp,
+    ) -> Result<()> {
+        let portal = GlobalShortcutsPortal::new().await?;
+        let session = portal.create_session().await?;
+        let session_path = session.path().to_string();
+
+        let mut events = stream::select_all([
+            portal
+                .receive_activated()
+                .await?
+                .map(|event| {
+                    PortalEvent::Activated(
+                        event.session_handle().to_string(),
+                        event.shortcut_id().to_string(),
+                    )
+                })
+                .boxed_local(),
+            portal
+                .receive_deactivated()
+                .await?
+                .map(|event| {
+                    PortalEvent::Deactivated(
+                        event.session_handle().to_string(),
+                        event.shortcut_id().to_string(),
+                    )
+                })
+                .boxed_local(),
+            portal
+                .receive_shortcuts_changed()
+                .await?
+                .map(|event| {
+                    PortalEvent::Changed(
+                        event.session_handle().to_string(),
+                        bound_shortcuts(event.shortcuts()),
+                    )
+                })
+                .boxed_local(),
+        ]);
+
+        let shortcuts = shortcuts
+            .iter()
+            .map(|shortcut| {
+                NewShortcut::new(shortcut.id.as_ref(), shortcut.description.as_ref())
+                    .preferred_trigger(shortcut.preferred_trigger.as_deref())
+            })
+            .collect::<Vec<_>>();
+        let response = portal
+            .bind_shortcuts(&session, &shortcuts, None)
+            .await?
+            .response()?;
+        let bound = bound_shortcuts(response.shortcuts());
+        this.update(cx, |this, cx| {
+            this.bound = bound;
+            cx.notify();
+        })?;
+
+        while let Some(event) = events.next().await {
+            this.update(cx, |this, cx| match event {
+                PortalEvent::Activated(handle, id) if handle == session_path => {
+                    cx.emit(GlobalShortcutEvent::Activated(id.into()));
+                }
+                PortalEvent::Deactivated(handle, id) if handle == session_path => {
+                    cx.emit(GlobalShortcutEvent::Deactivated(id.into()));
+                }
+                PortalEvent::Changed(handle, bound) if handle == session_path => {
+                    this.bound = bound;
+                    cx.notify();
+                }
+                _ => {}
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+fn bound_shortcuts(
+    shortcuts: &[ashpd::desktop::global_shortcuts::Shortcut],
+) -> Vec<BoundGlobalShortcut> {
+    shortcuts
+        .iter()
+        .map(|shortcut| BoundGlobalShortcut {
+            id: shortcut.id().to_string().into(),
+            description: shortcut.description().to_string().into(),
+            trigger_description: shortcut.trigger_description().to_string().into(),
+        })
+        .collect()
+}
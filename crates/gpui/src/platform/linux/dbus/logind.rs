@@ -0,0 +1,122 @@
+//! Suspend and resume notifications from [logind], see [`App::on_suspend`] and
+//! [`App::on_resume`].
+//!
+//! [logind]: https://www.freedesktop.org/software/systemd/man/latest/org.freedesktop.login1.html
+
+use futures::StreamExt as _;
+use util::ResultExt as _;
+use zbus::zvariant::OwnedFd;
+
+use crate::{system_bus, App, AsyncApp, Global, Result, SubscriberSet, Subscription, Task};
+
+type SleepHandler = Box<dyn FnMut(&mut App) -> bool + 'static>;
+
+/// Watches logind for the system going to sleep and waking up, started by the first
+/// subscription.
+struct SleepMonitor {
+    suspend_observers: SubscriberSet<(), SleepHandler>,
+    resume_observers: SubscriberSet<(), SleepHandler>,
+    _task: Task<()>,
+}
+
+impl Global for SleepMonitor {}
+
+impl App {
+    /// Invokes the callback when the system is about to suspend, e.g. to stop polling. The
+    /// system waits for the callbacks to run, up to the delay allowed by logind.
+    pub fn on_suspend(&mut self, mut callback: impl FnMut(&mut App) + 'static) -> Subscription {
+        let (subscription, activate) = self.sleep_monitor().suspend_observers.insert(
+            (),
+            Box::new(move |cx| {
+                callback(cx);
+                true
+            }),
+        );
+        activate();
+        subscription
+    }
+
+    /// Invokes the callback when the system resumes from suspend, e.g. to resync clocks and
+    /// refresh data that went stale in the meantime. Windows are redrawn on resume as well.
+    pub fn on_resume(&mut self, mut callback: impl FnMut(&mut App) + 'static) -> Subscription {
+        let (subscription, activate) = self.sleep_monitor().resume_observers.insert(
+            (),
+            Box::new(move |cx| {
+                callback(cx);
+                true
+            }),
+        );
+        activate();
+        subscription
+    }
+
+    fn sleep_monitor(&mut self) -> &SleepMonitor {
+        if !self.has_global::<SleepMonitor>() {
+            let task = self.spawn(|mut cx| async move {
+                if let Err(error) = watch_sleep(&mut cx).await {
+                    log::error!("failed to watch for suspend and resume: {error:#}");
+                }
+            });
+            self.set_global(SleepMonitor {
+                suspend_observers: SubscriberSet::new(),
+                resume_observers: SubscriberSet::new(),
+                _task: task,
+            });
+        }
+        self.global::<SleepMonitor>()
+    }
+}
+
+async fn watch_sleep(cx: &mut AsyncApp) -> Result<()> {
+    let connection = cx.update(|cx| system_bus(cx))?.await?;
+    let manager = ManagerProxy::new(&connection).await?;
+    let mut prepare_for_sleep = manager.receive_prepare_for_sleep().await?;
+    // A delay lock holds off the suspend until the suspend observers have run.
+    let mut inhibitor = inhibit(&manager).await;
+
+    while let Some(signal) = prepare_for_sleep.next().await {
+        let Some(args) = signal.args().log_err() else {
+            continue;
+        };
+        if *args.start() {
+            cx.update(|cx| {
+                let observers = cx.global::<SleepMonitor>().suspend_observers.clone();
+                observers.retain(&(), |callback| callback(cx));
+            })?;
+            inhibitor.take();
+        } else {
+            cx.update(|cx| {
+                let observers = cx.global::<SleepMonitor>().resume_observers.clone();
+                observers.retain(&(), |callback| callback(cx));
+                cx.refresh_windows();
+            })?;
+            inhibitor = inhibit(&manager).await;
+        }
+    }
+
+    Ok(())
+}
+
+async fn inhibit(manager: &ManagerProxy<'_>) -> Option<OwnedFd> {
+    manager
+        .inhibit(
+            "sleep",
+            "gpui",
+            "Notifying the application of the suspend",
+            "delay",
+        )
+        .await
+        .log_err()
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+trait Manager {
+    fn inhibit(&self, what: &str, who: &str, why: &str, mode: &str) -> zbus::Result<OwnedFd>;
+
+    #[zbus(signal)]
+    fn prepare_for_sleep(&self, start: bool) -> zbus::Result<()>;
+}
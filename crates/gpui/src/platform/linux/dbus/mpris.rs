@@ -0,0 +1,691 @@
+//! A client for media players implementing [MPRIS], for media controls like those of a bar.
+//!
+//! [`MediaPlayers`] follows the players appearing on the session bus, each exposed as a
+//! [`MediaPlayer`] entity that is kept up to date with its playback status and metadata.
+//!
+//! [MPRIS]: https://specifications.freedesktop.org/mpris-spec/latest/
+
+use std::{collections::HashMap, time::Duration};
+
+use futures::{stream, StreamExt};
+use util::ResultExt as _;
+use zbus::{
+    fdo::{DBusProxy, PropertiesProxy},
+    proxy::CacheProperties,
+    zvariant::{ObjectPath, OwnedValue},
+    Connection,
+};
+
+use crate::{
+    session_bus, App, AppContext, AsyncApp, Context, Entity, EventEmitter, Result, SharedString,
+    Task, WeakEntity,
+};
+
+const PLAYER_NAME_PREFIX: &str = "org.mpris.MediaPlayer2.";
+const PLAYER_PATH: &str = "/org/mpris/MediaPlayer2";
+const PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+
+/// Tracks the media players of the session, see the [module documentation](self).
+///
+/// Create it with `cx.new(MediaPlayers::new)` and observe it to learn about players being added
+/// and removed. Each player is an entity of its own which notifies when its state changes.
+pub struct MediaPlayers {
+    players: Vec<Entity<MediaPlayer>>,
+    _task: Task<()>,
+}
+
+impl MediaPlayers {
+    /// Connects to the session bus and starts tracking the media players.
+    pub fn new(cx: &mut Context<Self>) -> Self {
+        let task = cx.spawn(|this, mut cx| async move {
+            if let Err(error) = Self::run(this, &mut cx).await {
+                log::error!("media player tracking failed: {error:#}");
+            }
+        });
+        Self {
+            players: Vec::new(),
+            _task: task,
+        }
+    }
+
+    /// Returns the media players, in the order they appeared.
+    pub fn players(&self) -> &[Entity<MediaPlayer>] {
+        &self.players
+    }
+
+    /// Returns the player most likely to be the one the user cares about: the first one
+    /// playing, otherwise the first paused one, otherwise the first one.
+    pub fn active_player(&self, cx: &App) -> Option<&Entity<MediaPlayer>> {
+        let with_status = |status| {
+            self.players
+                .iter()
+                .find(|player| player.read(cx).state.playback_status == status)
+        };
+        with_status(PlaybackStatus::Playing)
+            .or_else(|| with_status(PlaybackStatus::Paused))
+            .or_else(|| self.players.first())
+    }
+
+    async fn run(this: WeakEntity<Self>, cx: &mut AsyncApp) -> Result<()> {
+        let connection = cx.update(|cx| session_bus(cx))?.await?;
+        let dbus = DBusProxy::new(&connection).await?;
+        let mut owner_changes = dbus.receive_name_owner_changed().await?;
+
+        for name in dbus.list_names().await? {
+            if name.starts_with(PLAYER_NAME_PREFIX) {
+                Self::add_player(&this, &connection, name.to_string(), cx).await?;
+            }
+        }
+
+        while let Some(signal) = owner_changes.next().await {
+            let Some(args) = signal.args().log_err() else {
+                continue;
+            };
+            let name = args.name().to_string();
+            if !name.starts_with(PLAYER_NAME_PREFIX) {
+                continue;
+            }
+            this.update(cx, |this, cx| {
+                this.players
+                    .retain(|player| player.read(cx).bus_name.as_ref() != name);
+                cx.notify();
+            })?;
+            if args.new_owner().is_some() {
+                Self::add_player(&this, &connection, name, cx).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn add_player(
+        this: &WeakEntity<Self>,
+        connection: &Connection,
+        bus_name: String,
+        cx: &mut AsyncApp,
+    ) -> Result<()> {
+        let proxies = async {
+            let player = PlayerProxy::builder(connection)
+                .destination(bus_name.clone())?
+                .cache_properties(CacheProperties::No)
+                .build()
+                .await?;
+            let media_player = MediaPlayer2Proxy::builder(connection)
+                .destination(bus_name.clone())?
+                .cache_properties(CacheProperties::No)
+                .build()
+                .await?;
+            anyhow::Ok((player, media_player))
+        };
+        let (proxy, media_player) = match proxies.await {
+            Ok(proxies) => proxies,
+            Err(error) => {
+                log::warn!("failed to connect to media player {bus_name}: {error}");
+                return Ok(());
+            }
+        };
+        let identity = media_player.identity().await.unwrap_or_default().into();
+        let desktop_entry = media_player
+            .desktop_entry()
+            .await
+            .ok()
+            .filter(|entry| !entry.is_empty())
+            .map(Into::into);
+        let state = PlayerState::fetch(&proxy).await;
+
+        this.update(cx, |this, cx| {
+            let player = cx.new(|cx| {
+                MediaPlayer::new(
+                    bus_name.into(),
+                    identity,
+                    desktop_entry,
+                    connection.clone(),
+                    proxy,
+                    media_player,
+                    state,
+                    cx,
+                )
+            });
+            this.players.push(player);
+            cx.notify();
+        })
+    }
+}
+
+/// The playback status of a [`MediaPlayer`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PlaybackStatus {
+    /// A track is playing.
+    Playing,
+    /// A track is paused.
+    Paused,
+    /// Nothing is playing.
+    #[default]
+    Stopped,
+}
+
+impl PlaybackStatus {
+    fn from_str(status: &str) -> Self {
+        match status {
+            "Playing" => Self::Playing,
+            "Paused" => Self::Paused,
+            _ => Self::Stopped,
+        }
+    }
+}
+
+/// What happens when a [`MediaPlayer`] reaches the end of its current track.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LoopStatus {
+    /// Playback stops once there are no more tracks to play.
+    #[default]
+    None,
+    /// The current track is played again.
+    Track,
+    /// The playlist is played again.
+    Playlist,
+}
+
+impl LoopStatus {
+    fn from_str(status: &str) -> Self {
+        match status {
+            "Track" => Self::Track,
+            "Playlist" => Self::Playlist,
+            _ => Self::None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "None",
+            Self::Track => "Track",
+            Self::Playlist => "Playlist",
+        }
+    }
+}
+
+/// The metadata of the track a [`MediaPlayer`] is playing.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TrackMetadata {
+    /// The identifier of the track within the player's playlist, used to set its position.
+    pub track_id: Option<SharedString>,
+    /// The title of the track.
+    pub title: Option<SharedString>,
+    /// The artists of the track.
+    pub artists: Vec<SharedString>,
+    /// The album of the track.
+    pub album: Option<SharedString>,
+    /// The artists of the album.
+    pub album_artists: Vec<SharedString>,
+    /// The URL of the cover art of the track, commonly a `file://` or `https://` URL.
+    pub art_url: Option<SharedString>,
+    /// The URL of the track itself.
+    pub url: Option<SharedString>,
+    /// The length of the track, if known.
+    pub length: Option<Duration>,
+}
+
+impl TrackMetadata {
+    fn from_map(metadata: HashMap<String, OwnedValue>) -> Self {
+        let string = |name: &str| {
+            metadata
+                .get(name)
+                .and_then(|value| value.downcast_ref::<&str>().ok())
+                .filter(|value| !value.is_empty())
+                .map(|value| SharedString::from(value.to_string()))
+        };
+        let strings = |name: &str| {
+            metadata
+                .get(name)
+                .and_then(|value| value.try_clone().ok())
+                .and_then(|value| Vec::<String>::try_from(value).ok())
+                .unwrap_or_default()
+                .into_iter()
+                .map(SharedString::from)
+                .collect::<Vec<_>>()
+        };
+        // The length is meant to be a signed integer, but players commonly send other kinds.
+        let length = metadata.get("mpris:length").and_then(|value| {
+            value
+                .downcast_ref::<i64>()
+                .ok()
+                .or_else(|| value.downcast_ref::<u64>().ok().map(|length| length as i64))
+                .or_else(|| value.downcast_ref::<i32>().ok().map(i64::from))
+                .or_else(|| value.downcast_ref::<f64>().ok().map(|length| length as i64))
+        });
+
+        Self {
+            track_id: metadata
+                .get("mpris:trackid")
+                .and_then(|value| value.downcast_ref::<ObjectPath>().ok())
+                .map(|path| SharedString::from(path.to_string()))
+                .or_else(|| string("mpris:trackid")),
+            title: string("xesam:title"),
+            artists: strings("xesam:artist"),
+            album: string("xesam:album"),
+            album_artists: strings("xesam:albumArtist"),
+            art_url: string("mpris:artUrl"),
+            url: string("xesam:url"),
+            length: length
+                .filter(|length| *length > 0)
+                .map(|length| Duration::from_micros(length as u64)),
+        }
+    }
+}
+
+/// An event emitted by a [`MediaPlayer`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MediaPlayerEvent {
+    /// The playback position jumped to the given position, rather than advancing with the
+    /// playback. The position isn't tracked otherwise, see [`MediaPlayer::position`].
+    Seeked(Duration),
+}
+
+enum PlayerSignal {
+    PropertiesChanged,
+    Seeked(Duration),
+}
+
+#[derive(Default)]
+struct PlayerState {
+    playback_status: PlaybackStatus,
+    loop_status: Option<LoopStatus>,
+    shuffle: Option<bool>,
+    volume: Option<f64>,
+    metadata: TrackMetadata,
+    can_go_next: bool,
+    can_go_previous: bool,
+    can_play: bool,
+    can_pause: bool,
+    can_seek: bool,
+    can_control: bool,
+}
+
+impl PlayerState {
+    /// Fetches the state of a player. The optional properties fall back to their defaults when
+    /// they're not implemented.
+    async fn fetch(proxy: &PlayerProxy<'static>) -> Self {
+        Self {
+            playback_status: proxy
+                .playback_status()
+                .await
+                .map(|status| PlaybackStatus::from_str(&status))
+                .unwrap_or_default(),
+            loop_status: proxy
+                .loop_status()
+                .await
+                .ok()
+                .map(|status| LoopStatus::from_str(&status)),
+            shuffle: proxy.shuffle().await.ok(),
+            volume: proxy.volume().await.ok(),
+            metadata: proxy
+                .metadata()
+                .await
+                .map(TrackMetadata::from_map)
+                .unwrap_or_default(),
+            can_go_next: proxy.can_go_next().await.unwrap_or(false),
+            can_go_previous: proxy.can_go_previous().await.unwrap_or(false),
+            can_play: proxy.can_play().await.unwrap_or(false),
+            can_pause: proxy.can_pause().await.unwrap_or(false),
+            can_seek: proxy.can_seek().await.unwrap_or(false),
+            can_control: proxy.can_control().await.unwrap_or(false),
+        }
+    }
+}
+
+/// A media player on the session bus, tracked by [`MediaPlayers`].
+pub struct MediaPlayer {
+    bus_name: SharedString,
+    identity: SharedString,
+    desktop_entry: Option<SharedString>,
+    proxy: PlayerProxy<'static>,
+    media_player: MediaPlayer2Proxy<'static>,
+    state: PlayerState,
+    _task: Task<()>,
+}
+
+impl EventEmitter<MediaPlayerEvent> for MediaPlayer {}
+
+impl MediaPlayer {
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        bus_name: SharedString,
+        identity: SharedString,
+        desktop_entry: Option<SharedString>,
+        connection: Connection,
+        proxy: PlayerProxy<'static>,
+        media_player: MediaPlayer2Proxy<'static>,
+        state: PlayerState,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let task = cx.spawn({
+            let proxy = proxy.clone();
+            let bus_name = bus_name.to_string();
+            |this, mut cx| async move {
+                Self::listen(this, connection, bus_name, proxy, &mut cx)
+                    .await
+                    .log_err();
+            }
+        });
+        Self {
+            bus_name,
+            identity,
+            desktop_entry,
+            proxy,
+            media_player,
+            state,
+            _task: task,
+        }
+    }
+
+    async fn listen(
+        this: WeakEntity<Self>,
+        connection: Connection,
+        bus_name: String,
+        proxy: PlayerProxy<'static>,
+        cx: &mut AsyncApp,
+    ) -> Result<()> {
+        let properties = PropertiesProxy::builder(&connection)
+            .destination(bus_name)?
+            .path(PLAYER_PATH)?
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await?;
+        let mut signals = stream::select(
+            properties
+                .receive_properties_changed()
+                .await?
+                .filter_map(|signal| async move {
+                    let args = signal.args().ok()?;
+                    (args.interface_name().as_str() == PLAYER_INTERFACE)
+                        .then_some(PlayerSignal::PropertiesChanged)
+                })
+                .boxed(),
+            proxy
+                .receive_seeked()
+                .await?
+                .filter_map(|signal| async move {
+                    let position = *signal.args().ok()?.position();
+                    Some(PlayerSignal::Seeked(Duration::from_micros(
+                        position.max(0) as u64
+                    )))
+                })
+                .boxed(),
+        );
+
+        while let Some(signal) = signals.next().await {
+            match signal {
+                PlayerSignal::PropertiesChanged => {
+                    let state = PlayerState::fetch(&proxy).await;
+                    this.update(cx, |this, cx| {
+                        this.state = state;
+                        cx.notify();
+                    })?;
+                }
+                PlayerSignal::Seeked(position) => this.update(cx, |_, cx| {
+                    cx.emit(MediaPlayerEvent::Seeked(position));
+                })?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the bus name of the player, like `org.mpris.MediaPlayer2.vlc`.
+    pub fn bus_name(&self) -> &SharedString {
+        &self.bus_name
+    }
+
+    /// Returns the human readable name of the player.
+    pub fn identity(&self) -> &SharedString {
+        &self.identity
+    }
+
+    /// Returns the desktop entry of the player, without the `.desktop` suffix, if known.
+    pub fn desktop_entry(&self) -> Option<&SharedString> {
+        self.desktop_entry.as_ref()
+    }
+
+    /// Returns the playback status of the player.
+    pub fn playback_status(&self) -> PlaybackStatus {
+        self.state.playback_status
+    }
+
+    /// Returns the loop status of the player, if it supports looping.
+    pub fn loop_status(&self) -> Option<LoopStatus> {
+        self.state.loop_status
+    }
+
+    /// Returns whether the player plays its playlist in random order, if it supports shuffling.
+    pub fn shuffle(&self) -> Option<bool> {
+        self.state.shuffle
+    }
+
+    /// Returns the volume of the player, from 0 to 1, if it reports one.
+    pub fn volume(&self) -> Option<f64> {
+        self.state.volume
+    }
+
+    /// Returns the metadata of the current track.
+    pub fn metadata(&self) -> &TrackMetadata {
+        &self.state.metadata
+    }
+
+    /// Returns whether [`MediaPlayer::next`] is expected to do anything.
+    pub fn can_go_next(&self) -> bool {
+        self.state.can_control && self.state.can_go_next
+    }
+
+    /// Returns whether [`MediaPlayer::previous`] is expected to do anything.
+    pub fn can_go_previous(&self) -> bool {
+        self.state.can_control && self.state.can_go_previous
+    }
+
+    /// Returns whether [`MediaPlayer::play`] is expected to do anything.
+    pub fn can_play(&self) -> bool {
+        self.state.can_control && self.state.can_play
+    }
+
+    /// Returns whether [`MediaPlayer::pause`] is expected to do anything.
+    pub fn can_pause(&self) -> bool {
+        self.state.can_control && self.state.can_pause
+    }
+
+    /// Returns whether [`MediaPlayer::seek`] and [`MediaPlayer::set_position`] are expected to
+    /// do anything.
+    pub fn can_seek(&self) -> bool {
+        self.state.can_control && self.state.can_seek
+    }
+
+    /// Returns whether the player can be controlled at all.
+    pub fn can_control(&self) -> bool {
+        self.state.can_control
+    }
+
+    /// Fetches the playback position in the current track. It isn't kept up to date since it
+    /// changes continuously while playing, so widgets showing it should refetch it periodically
+    /// and on [`MediaPlayerEvent::Seeked`].
+    pub fn position(&self, cx: &App) -> Task<Result<Duration>> {
+        let proxy = self.proxy.clone();
+        cx.background_executor().spawn(async move {
+            let position = proxy.position().await?;
+            Ok(Duration::from_micros(position.max(0) as u64))
+        })
+    }
+
+    /// Starts or resumes playback.
+    pub fn play(&self, cx: &App) -> Task<Result<()>> {
+        self.call(cx, |proxy| async move { proxy.play().await })
+    }
+
+    /// Pauses playback.
+    pub fn pause(&self, cx: &App) -> Task<Result<()>> {
+        self.call(cx, |proxy| async move { proxy.pause().await })
+    }
+
+    /// Pauses playback if playing, resumes it otherwise.
+    pub fn play_pause(&self, cx: &App) -> Task<Result<()>> {
+        self.call(cx, |proxy| async move { proxy.play_pause().await })
+    }
+
+    /// Stops playback.
+    pub fn stop(&self, cx: &App) -> Task<Result<()>> {
+        self.call(cx, |proxy| async move { proxy.stop().await })
+    }
+
+    /// Skips to the next track.
+    pub fn next(&self, cx: &App) -> Task<Result<()>> {
+        self.call(cx, |proxy| async move { proxy.next().await })
+    }
+
+    /// Skips to the previous track.
+    pub fn previous(&self, cx: &App) -> Task<Result<()>> {
+        self.call(cx, |proxy| async move { proxy.previous().await })
+    }
+
+    /// Moves the playback position by the given offset, backwards when negative.
+    pub fn seek(&self, offset_micros: i64, cx: &App) -> Task<Result<()>> {
+        self.call(
+            cx,
+            move |proxy| async move { proxy.seek(offset_micros).await },
+        )
+    }
+
+    /// Moves the playback position in the current track to the given position.
+    pub fn set_position(&self, position: Duration, cx: &App) -> Task<Result<()>> {
+        let Some(track_id) = self.state.metadata.track_id.clone() else {
+            return Task::ready(Err(anyhow::anyhow!("the current track has no id")));
+        };
+        let position = position.as_micros() as i64;
+        self.call(cx, move |proxy| async move {
+            let track_id = ObjectPath::try_from(track_id.as_ref())?;
+            proxy.set_position(&track_id, position).await
+        })
+    }
+
+    /// Changes the volume of the player, from 0 to 1.
+    pub fn set_volume(&self, volume: f64, cx: &App) -> Task<Result<()>> {
+        self.call(cx, move |proxy| async move {
+            proxy.set_volume(volume.max(0.)).await
+        })
+    }
+
+    /// Changes the loop status of the player.
+    pub fn set_loop_status(&self, status: LoopStatus, cx: &App) -> Task<Result<()>> {
+        self.call(cx, move |proxy| async move {
+            proxy.set_loop_status(status.as_str()).await
+        })
+    }
+
+    /// Changes whether the player plays its playlist in random order.
+    pub fn set_shuffle(&self, shuffle: bool, cx: &App) -> Task<Result<()>> {
+        self.call(
+            cx,
+            move |proxy| async move { proxy.set_shuffle(shuffle).await },
+        )
+    }
+
+    /// Asks the player to bring its user interface to the front.
+    pub fn raise(&self, cx: &App) -> Task<Result<()>> {
+        let media_player = self.media_player.clone();
+        cx.background_executor().spawn(async move {
+            media_player.raise().await?;
+            Ok(())
+        })
+    }
+
+    fn call<F>(&self, cx: &App, call: impl FnOnce(PlayerProxy<'static>) -> F) -> Task<Result<()>>
+    where
+        F: std::future::Future<Output = zbus::Result<()>> + Send + 'static,
+    {
+        let call = call(self.proxy.clone());
+        cx.background_executor().spawn(async move {
+            call.await?;
+            Ok(())
+        })
+    }
+}
+
+#[zbus::proxy(
+    interface = "org.mpris.MediaPlayer2",
+    default_path = "/org/mpris/MediaPlayer2"
+)]
+trait MediaPlayer2 {
+    fn raise(&self) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn identity(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn desktop_entry(&self) -> zbus::Result<String>;
+}
+
+#[zbus::proxy(
+    interface = "org.mpris.MediaPlayer2.Player",
+    default_path = "/org/mpris/MediaPlayer2"
+)]
+trait Player {
+    fn next(&self) -> zbus::Result<()>;
+
+    fn previous(&self) -> zbus::Result<()>;
+
+    fn pause(&self) -> zbus::Result<()>;
+
+    fn play_pause(&self) -> zbus::Result<()>;
+
+    fn stop(&self) -> zbus::Result<()>;
+
+    fn play(&self) -> zbus::Result<()>;
+
+    fn seek(&self, offset: i64) -> zbus::Result<()>;
+
+    fn set_position(&self, track_id: &ObjectPath<'_>, position: i64) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn playback_status(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn loop_status(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn set_loop_status(&self, status: &str) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn shuffle(&self) -> zbus::Result<bool>;
+
+    #[zbus(property)]
+    fn set_shuffle(&self, shuffle: bool) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn volume(&self) -> zbus::Result<f64>;
+
+    #[zbus(property)]
+    fn set_volume(&self, volume: f64) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn metadata(&self) -> zbus::Result<HashMap<String, OwnedValue>>;
+
+    #[zbus(property)]
+    fn position(&self) -> zbus::Result<i64>;
+
+    #[zbus(property)]
+    fn can_go_next(&self) -> zbus::Result<bool>;
+
+    #[zbus(property)]
+    fn can_go_previous(&self) -> zbus::Result<bool>;
+
+    #[zbus(property)]
+    fn can_play(&self) -> zbus::Result<bool>;
+
+    #[zbus(property)]
+    fn can_pause(&self) -> zbus::Result<bool>;
+
+    #[zbus(property)]
+    fn can_seek(&self) -> zbus::Result<bool>;
+
+    #[zbus(property)]
+    fn can_control(&self) -> zbus::Result<bool>;
+
+    #[zbus(signal)]
+    fn seeked(&self, position: i64) -> zbus::Result<()>;
+}
@@ -0,0 +1,510 @@
+//! Building blocks for notification daemons, implementing the server side of the
+//! [Desktop Notifications] specification.
+//!
+//! [Desktop Notifications]: https://specifications.freedesktop.org/notification-spec/latest/
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use futures::{channel::mpsc, StreamExt};
+use image::{Frame, ImageBuffer};
+use smallvec::SmallVec;
+use util::ResultExt as _;
+use zbus::{
+    fdo::{RequestNameFlags, RequestNameReply},
+    object_server::SignalEmitter,
+    zvariant::OwnedValue,
+};
+
+use crate::{
+    session_bus, AsyncApp, Context, EventEmitter, ReleaseOnDrop, RenderImage, Result, SharedString,
+    Task, WeakEntity,
+};
+
+const NOTIFICATIONS_NAME: &str = "org.freedesktop.Notifications";
+const NOTIFICATIONS_PATH: &str = "/org/freedesktop/Notifications";
+const SPEC_VERSION: &str = "1.2";
+
+/// The urgency of a [`Notification`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum NotificationUrgency {
+    /// A notification of little importance, like a song change.
+    Low,
+    /// A regular notification.
+    #[default]
+    Normal,
+    /// A notification that shouldn't go unnoticed, it doesn't expire unless it asked to.
+    Critical,
+}
+
+/// The image of a [`Notification`].
+#[derive(Clone, Debug)]
+pub enum NotificationImage {
+    /// A freedesktop icon name or a `file://` URI.
+    Path(SharedString),
+    /// Raw pixels sent along with the notification.
+    Pixels(Arc<RenderImage>),
+}
+
+/// An action the user can invoke from a [`Notification`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NotificationAction {
+    /// The key reported back to the application when the action is invoked. The `default` key
+    /// is invoked by clicking the notification itself.
+    pub key: SharedString,
+    /// The label to show for the action.
+    pub label: SharedString,
+}
+
+/// A notification received by a [`NotificationServer`].
+#[derive(Clone, Debug)]
+pub struct Notification {
+    /// The identifier of the notification, unique for the lifetime of the server.
+    pub id: u32,
+    /// The name of the application that sent the notification.
+    pub app_name: SharedString,
+    /// The icon of the application, as a freedesktop icon name or a `file://` URI.
+    pub app_icon: Option<SharedString>,
+    /// The summary of the notification.
+    pub summary: SharedString,
+    /// The body of the notification, which may contain basic markup.
+    pub body: SharedString,
+    /// The actions of the notification, in the order they should be shown.
+    pub actions: Vec<NotificationAction>,
+    /// The image of the notification, if any.
+    pub image: Option<NotificationImage>,
+    /// The urgency of the notification.
+    pub urgency: NotificationUrgency,
+    /// The category of the notification, like `email.arrived`.
+    pub category: Option<SharedString>,
+    /// The desktop entry of the application that sent the notification, without the
+    /// `.desktop` suffix.
+    pub desktop_entry: Option<SharedString>,
+    /// Whether the notification should stay around after one of its actions was invoked.
+    pub resident: bool,
+    /// Whether the notification should bypass persistence, like a notification history.
+    pub transient: bool,
+    /// How long the notification is shown before it expires, if it expires at all.
+    pub expire_timeout: Option<Duration>,
+}
+
+/// The reason a [`Notification`] was closed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NotificationCloseReason {
+    /// The notification expired.
+    Expired = 1,
+    /// The user dismissed the notification.
+    Dismissed = 2,
+    /// The application closed the notification.
+    Closed = 3,
+    /// The notification was closed for another reason.
+    Undefined = 4,
+}
+
+/// An event emitted by a [`NotificationServer`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NotificationServerEvent {
+    /// A notification was received, or replaced the notification with the same id.
+    Notified(u32),
+    /// A notification was closed.
+    Closed(u32, NotificationCloseReason),
+}
+
+/// Configures a [`NotificationServer`].
+#[derive(Clone, Debug)]
+pub struct NotificationServerOptions {
+    /// The name of the server, as reported to applications.
+    pub name: SharedString,
+    /// The vendor of the server, as reported to applications.
+    pub vendor: SharedString,
+    /// The version of the server, as reported to applications.
+    pub version: SharedString,
+    /// The optional features of the specification the server supports, like `body-markup`.
+    pub capabilities: Vec<SharedString>,
+    /// How long notifications are shown when they leave it up to the server. Critical
+    /// notifications never expire by default.
+    pub default_timeout: Option<Duration>,
+}
+
+impl Default for NotificationServerOptions {
+    fn default() -> Self {
+        Self {
+            name: "gpui".into(),
+            vendor: "gpui".into(),
+            version: env!("CARGO_PKG_VERSION").into(),
+            capabilities: vec![
+                "actions".into(),
+                "body".into(),
+                "icon-static".into(),
+                "persistence".into(),
+            ],
+            default_timeout: Some(Duration::from_secs(5)),
+        }
+    }
+}
+
+/// Serves `org.freedesktop.Notifications` on the session bus, keeping track of the open
+/// notifications and their expiry.
+///
+/// Create it with `cx.new(|cx| NotificationServer::new(options, cx))` and subscribe to it to
+/// show notifications as they come and go.
+pub struct NotificationServer {
+    notifications: Vec<Notification>,
+    expirations: HashMap<u32, Task<()>>,
+    emitter: Option<SignalEmitter<'static>>,
+    _task: Task<()>,
+}
+
+impl EventEmitter<NotificationServerEvent> for NotificationServer {}
+
+enum ServerRequest {
+    Notify(Notification),
+    Close(u32),
+}
+
+impl NotificationServer {
+    /// Claims the notifications name on the session bus and starts serving notifications.
+    /// Fails in the background, logging an error, when another notification daemon is running.
+    pub fn new(options: NotificationServerOptions, cx: &mut Context<Self>) -> Self {
+        let task = cx.spawn(|this, mut cx| async move {
+            if let Err(error) = Self::run(this, options, &mut cx).await {
+                log::error!("notification server failed: {error:#}");
+            }
+        });
+        Self {
+            notifications: Vec::new(),
+            expirations: HashMap::default(),
+            emitter: None,
+            _task: task,
+        }
+    }
+
+    async fn run(
+        this: WeakEntity<Self>,
+        options: NotificationServerOptions,
+        cx: &mut AsyncApp,
+    ) -> Result<()> {
+        let (requests_tx, mut requests_rx) = mpsc::unbounded();
+        let connection = cx.update(|cx| session_bus(cx))?.await?;
+        let served = connection
+            .object_server()
+            .at(
+                NOTIFICATIONS_PATH,
+                NotificationsInterface {
+                    options,
+                    next_id: 0,
+                    requests: requests_tx,
+                },
+            )
+            .await?;
+        anyhow::ensure!(
+            served,
+            "this application already runs a notification server"
+        );
+        let _release = ReleaseOnDrop::new(cx.background_executor().clone(), {
+            let connection = connection.clone();
+            async move {
+                connection.release_name(NOTIFICATIONS_NAME).await.log_err();
+                connection
+                    .object_server()
+                    .remove::<NotificationsInterface, _>(NOTIFICATIONS_PATH)
+                    .await
+                    .log_err();
+            }
+        });
+
+        let reply = connection
+            .request_name_with_flags(NOTIFICATIONS_NAME, RequestNameFlags::DoNotQueue.into())
+            .await?;
+        anyhow::ensure!(
+            reply == RequestNameReply::PrimaryOwner,
+            "another notification server is running"
+        );
+
+        let emitter = SignalEmitter::new(&connection, NOTIFICATIONS_PATH)?.into_owned();
+        this.update(cx, |this, _| this.emitter = Some(emitter))?;
+
+        while let Some(request) = requests_rx.next().await {
+            this.update(cx, |this, cx| match request {
+                ServerRequest::Notify(notification) => this.insert(notification, cx),
+                ServerRequest::Close(id) => this.close(id, NotificationCloseReason::Closed, cx),
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the open notifications, from the oldest to the newest.
+    pub fn notifications(&self) -> &[Notification] {
+        &self.notifications
+    }
+
+    /// Returns the open notification with the given id.
+    pub fn notification(&self, id: u32) -> Option<&Notification> {
+        self.notifications
+            .iter()
+            .find(|notification| notification.id == id)
+    }
+
+    fn insert(&mut self, notification: Notification, cx: &mut Context<Self>) {
+        let id = notification.id;
+        self.expirations.remove(&id);
+        if let Some(timeout) = notification.expire_timeout {
+            self.schedule_expiration(id, timeout, cx);
+        }
+
+        match self
+            .notifications
+            .iter_mut()
+            .find(|existing| existing.id == id)
+        {
+            Some(existing) => *existing = notification,
+            None => self.notifications.push(notification),
+        }
+        cx.emit(NotificationServerEvent::Notified(id));
+        cx.notify();
+    }
+
+    fn schedule_expiration(&mut self, id: u32, timeout: Duration, cx: &mut Context<Self>) {
+        let expiration = cx.spawn(|this, mut cx| async move {
+            cx.background_executor().timer(timeout).await;
+            this.update(&mut cx, |this, cx| {
+                this.close(id, NotificationCloseReason::Expired, cx)
+            })
+            .ok();
+        });
+        self.expirations.insert(id, expiration);
+    }
+
+    /// Closes the notification with the given id, telling the application why.
+    pub fn close(&mut self, id: u32, reason: NotificationCloseReason, cx: &mut Context<Self>) {
+        let count = self.notifications.len();
+        self.notifications
+            .retain(|notification| notification.id != id);
+        if self.notifications.len() == count {
+            return;
+        }
+        self.expirations.remove(&id);
+
+        if let Some(emitter) = self.emitter.clone() {
+            cx.background_executor()
+                .spawn(async move {
+                    NotificationsInterface::notification_closed(&emitter, id, reason as u32)
+                        .await
+                        .log_err();
+                })
+                .detach();
+        }
+        cx.emit(NotificationServerEvent::Closed(id, reason));
+        cx.notify();
+    }
+
+    /// Invokes the action with the given key on a notification, then closes the notification
+    /// unless it is resident.
+    pub fn invoke_action(&mut self, id: u32, key: &str, cx: &mut Context<Self>) {
+        let Some(notification) = self.notification(id) else {
+            return;
+        };
+        let resident = notification.resident;
+
+        if let Some(emitter) = self.emitter.clone() {
+            let key = key.to_string();
+            cx.background_executor()
+                .spawn(async move {
+                    NotificationsInterface::action_invoked(&emitter, id, &key)
+                        .await
+                        .log_err();
+                })
+                .detach();
+        }
+        if !resident {
+            self.close(id, NotificationCloseReason::Dismissed, cx);
+        }
+    }
+
+    /// Stops a notification from expiring, for example while the pointer hovers it.
+    pub fn pause_expiration(&mut self, id: u32) {
+        self.expirations.remove(&id);
+    }
+
+    /// Restarts the expiry of a notification paused with
+    /// [`NotificationServer::pause_expiration`].
+    pub fn resume_expiration(&mut self, id: u32, cx: &mut Context<Self>) {
+        if self.expirations.contains_key(&id) {
+            return;
+        }
+        if let Some(timeout) = self
+            .notification(id)
+            .and_then(|notification| notification.expire_timeout)
+        {
+            self.schedule_expiration(id, timeout, cx);
+        }
+    }
+}
+
+struct NotificationsInterface {
+    options: NotificationServerOptions,
+    next_id: u32,
+    requests: mpsc::UnboundedSender<ServerRequest>,
+}
+
+#[zbus::interface(name = "org.freedesktop.Notifications")]
+impl NotificationsInterface {
+    fn get_capabilities(&self) -> Vec<String> {
+        self.options
+            .capabilities
+            .iter()
+            .map(|capability| capability.to_string())
+            .collect()
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &mut self,
+        app_name: String,
+        replaces_id: u32,
+        app_icon: String,
+        summary: String,
+        body: String,
+        actions: Vec<String>,
+        hints: HashMap<String, OwnedValue>,
+        expire_timeout: i32,
+    ) -> u32 {
+        let id = if replaces_id != 0 {
+            replaces_id
+        } else {
+            self.next_id = self.next_id.checked_add(1).unwrap_or(1);
+            self.next_id
+        };
+
+        let string = |name: &str| {
+            hints
+                .get(name)
+                .and_then(|value| value.downcast_ref::<&str>().ok())
+                .filter(|value| !value.is_empty())
+                .map(|value| SharedString::from(value.to_string()))
+        };
+        let bool = |name: &str| {
+            hints
+                .get(name)
+                .and_then(|value| value.downcast_ref::<bool>().ok())
+                .unwrap_or(false)
+        };
+
+        let image = ["image-data", "image_data", "icon_data"]
+            .iter()
+            .find_map(|name| hints.get(*name).and_then(image_from_hint))
+            .map(NotificationImage::Pixels)
+            .or_else(|| {
+                string("image-path")
+                    .or_else(|| string("image_path"))
+                    .map(NotificationImage::Path)
+            });
+        let urgency = match hints
+            .get("urgency")
+            .and_then(|value| value.downcast_ref::<u8>().ok())
+        {
+            Some(0) => NotificationUrgency::Low,
+            Some(2) => NotificationUrgency::Critical,
+            _ => NotificationUrgency::Normal,
+        };
+
+        let notification = Notification {
+            id,
+            app_name: app_name.into(),
+            app_icon: Some(app_icon)
+                .filter(|icon| !icon.is_empty())
+                .map(Into::into),
+            summary: summary.into(),
+            body: body.into(),
+            actions: actions
+                .chunks_exact(2)
+                .map(|action| NotificationAction {
+                    key: action[0].clone().into(),
+                    label: action[1].clone().into(),
+                })
+                .collect(),
+            image,
+            urgency,
+            category: string("category"),
+            desktop_entry: string("desktop-entry"),
+            resident: bool("resident"),
+            transient: bool("transient"),
+            expire_timeout: match expire_timeout {
+                0 => None,
+                timeout if timeout < 0 => self
+                    .options
+                    .default_timeout
+                    .filter(|_| urgency != NotificationUrgency::Critical),
+                timeout => Some(Duration::from_millis(timeout as u64)),
+            },
+        };
+        self.requests
+            .unbounded_send(ServerRequest::Notify(notification))
+            .log_err();
+        id
+    }
+
+    fn close_notification(&self, id: u32) {
+        self.requests
+            .unbounded_send(ServerRequest::Close(id))
+            .log_err();
+    }
+
+    #[zbus(out_args("name", "vendor", "version", "spec_version"))]
+    fn get_server_information(&self) -> (String, String, String, String) {
+        (
+            self.options.name.to_string(),
+            self.options.vendor.to_string(),
+            self.options.version.to_string(),
+            SPEC_VERSION.to_string(),
+        )
+    }
+
+    #[zbus(signal)]
+    async fn notification_closed(
+        emitter: &SignalEmitter<'_>,
+        id: u32,
+        reason: u32,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn action_invoked(
+        emitter: &SignalEmitter<'_>,
+        id: u32,
+        action_key: &str,
+    ) -> zbus::Result<()>;
+}
+
+/// Converts an `image-data` hint, holding RGB or RGBA rows, to an image.
+fn image_from_hint(value: &OwnedValue) -> Option<Arc<RenderImage>> {
+    let (width, height, rowstride, has_alpha, bits_per_sample, channels, data) =
+        <(i32, i32, i32, bool, i32, i32, Vec<u8>)>::try_from(value.try_clone().ok()?).ok()?;
+    if width <= 0 || height <= 0 || bits_per_sample != 8 || channels != 3 + has_alpha as i32 {
+        return None;
+    }
+
+    let (width, height, rowstride, channels) = (
+        width as usize,
+        height as usize,
+        rowstride as usize,
+        channels as usize,
+    );
+    if rowstride < width * channels || data.len() < rowstride * (height - 1) + width * channels {
+        return None;
+    }
+
+    // Convert from RGB(A) to BGRA, dropping the padding at the end of each row.
+    let mut pixels = Vec::with_capacity(width * height * 4);
+    for row in data.chunks(rowstride).take(height) {
+        for pixel in row[..width * channels].chunks_exact(channels) {
+            let alpha = if has_alpha { pixel[3] } else { u8::MAX };
+            pixels.extend_from_slice(&[pixel[2], pixel[1], pixel[0], alpha]);
+        }
+    }
+
+    let buffer = ImageBuffer::from_raw(width as u32, height as u32, pixels)?;
+    Some(Arc::new(RenderImage::new(SmallVec::from_elem(
+        Frame::new(buffer),
+        1,
+    ))))
+}
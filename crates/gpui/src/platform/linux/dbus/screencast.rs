@@ -0,0 +1,369 @@
+//! Live screencasts of a monitor or window picked by the user, through the [ScreenCast portal]
+//! and PipeWire, see [`Screencast`].
+//!
+//! [ScreenCast portal]: https://flatpak.github.io/xdg-desktop-portal/docs/doc-org.freedesktop.portal.ScreenCast.html
+
+use std::{io::Cursor, os::fd::OwnedFd, sync::Arc, thread};
+
+use anyhow::Context as _;
+use ashpd::{
+    desktop::{
+        screencast::{CursorMode, Screencast as ScreencastPortal, SourceType},
+        PersistMode,
+    },
+    enumflags2::BitFlags,
+};
+use futures::{channel::mpsc, StreamExt as _};
+use image::{Frame, RgbaImage};
+use pipewire::{self as pw, properties::properties, spa};
+use spa::param::video::{VideoFormat, VideoInfoRaw};
+use util::ResultExt as _;
+
+use crate::{
+    div, img, AsyncApp, Context, IntoElement, ObjectFit, ParentElement as _, Render, RenderImage,
+    Result, Styled as _, StyledImage as _, WeakEntity, Window,
+};
+
+/// What the user can pick to cast with a [`Screencast`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScreencastOptions {
+    /// Whether monitors can be picked.
+    pub monitors: bool,
+    /// Whether windows can be picked.
+    pub windows: bool,
+    /// Whether the cursor is drawn into the frames.
+    pub cursor: bool,
+}
+
+impl Default for ScreencastOptions {
+    fn default() -> Self {
+        Self {
+            monitors: true,
+            windows: true,
+            cursor: true,
+        }
+    }
+}
+
+/// A live screencast of a monitor or window, which renders its latest frame scaled to fit its
+/// bounds. Works on any desktop with the ScreenCast portal, like GNOME and KDE.
+///
+/// Create it with `cx.new(|cx| Screencast::new(options, cx))`, which asks the user what to cast,
+/// and render it as a child of a view. The cast stops when the entity is released.
+pub struct Screencast {
+    frame: Option<Arc<RenderImage>>,
+    /// Frames replaced since the last render, whose atlas tiles are released by the next one.
+    stale_frames: Vec<Arc<RenderImage>>,
+    stop: Option<pw::channel::Sender<()>>,
+}
+
+impl Screencast {
+    /// Asks the user what to cast with the ScreenCast portal, and starts streaming it.
+    pub fn new(options: ScreencastOptions, cx: &mut Context<Self>) -> Self {
+        // The task isn't owned by the entity, so it can close the portal session once the stream
+        // has stopped after the entity is released.
+        cx.spawn(|this, mut cx| async move {
+            if let Err(error) = Self::run(this, options, &mut cx).await {
+                log::error!("failed to cast the screen: {error:#}");
+            }
+        })
+        .detach();
+        Self {
+            frame: None,
+            stale_frames: Vec::new(),
+            stop: None,
+        }
+    }
+
+    /// Returns the latest frame of the screencast, if one was received yet.
+    pub fn frame(&self) -> Option<Arc<RenderImage>> {
+        self.frame.clone()
+    }
+
+    async fn run(
+        this: WeakEntity<Self>,
+        options: ScreencastOptions,
+        cx: &mut AsyncApp,
+    ) -> Result<()> {
+        let portal = ScreencastPortal::new().await?;
+        let session = portal.create_session().await?;
+        let result = async {
+            let cursor_mode = if options.cursor {
+                CursorMode::Embedded
+            } else {
+                CursorMode::Hidden
+            };
+            let mut types = BitFlags::empty();
+            if options.monitors {
+                types |= SourceType::Monitor;
+            }
+            if options.windows {
+                types |= SourceType::Window;
+            }
+            portal
+                .select_sources(
+                    &session,
+                    cursor_mode,
+                    types,
+                    false,
+                    None,
+                    PersistMode::DoNot,
+                )
+                .await?
+                .response()?;
+            let streams = portal.start(&session, None).await?.response()?;
+            let node_id = streams
+                .streams()
+                .first()
+                .context("nothing was picked to cast")?
+                .pipe_wire_node_id();
+            let fd = portal.open_pipe_wire_remote(&session).await?;
+
+            // PipeWire runs its own loop, on a thread that sends the frames over. Only the latest
+            // frame is kept when the main thread falls behind.
+            let (frames_tx, mut frames_rx) = mpsc::channel(1);
+            let (stop_tx, stop_rx) = pw::channel::channel();
+            thread::Builder::new()
+                .name("Screencast".to_string())
+                .spawn(move || {
+                    if let Err(error) = stream_frames(fd, node_id, frames_tx, stop_rx) {
+                        log::error!("failed to stream the screencast: {error:#}");
+                    }
+                })?;
+            if this
+                .update(cx, |this, _| this.stop = Some(stop_tx.clone()))
+                .is_err()
+            {
+                stop_tx.send(()).ok();
+            }
+
+            while let Some(frame) = frames_rx.next().await {
+                let frame = Arc::new(RenderImage::new([Frame::new(frame)]));
+                let updated = this.update(cx, |this, cx| {
+                    if let Some(previous) = this.frame.replace(frame) {
+                        this.stale_frames.push(previous);
+                    }
+                    cx.notify();
+                });
+                if updated.is_err() {
+                    stop_tx.send(()).ok();
+                    break;
+                }
+            }
+            anyhow::Ok(())
+        }
+        .await;
+        session.close().await.log_err();
+        result
+    }
+}
+
+impl Drop for Screencast {
+    fn drop(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            stop.send(()).ok();
+        }
+    }
+}
+
+impl Render for Screencast {
+    fn render(&mut self, window: &mut Window, _: &mut Context<Self>) -> impl IntoElement {
+        for frame in self.stale_frames.drain(..) {
+            window.drop_image(frame).log_err();
+        }
+        div().size_full().children(
+            self.frame
+                .clone()
+                .map(|frame| img(frame).size_full().object_fit(ObjectFit::Contain)),
+        )
+    }
+}
+
+/// Connects to the PipeWire remote opened by the portal and sends the frames of the node until
+/// `stop` receives a message or the stream fails.
+fn stream_frames(
+    fd: OwnedFd,
+    node_id: u32,
+    mut frames: mpsc::Sender<RgbaImage>,
+    stop: pw::channel::Receiver<()>,
+) -> Result<()> {
+    pw::init();
+    let main_loop = pw::main_loop::MainLoop::new(None)?;
+    let context = pw::context::Context::new(&main_loop)?;
+    let core = context.connect_fd(fd, None)?;
+    let _stop = stop.attach(main_loop.loop_(), {
+        let main_loop = main_loop.clone();
+        move |()| main_loop.quit()
+    });
+
+    let stream = pw::stream::Stream::new(
+        &core,
+        "gpui-screencast",
+        properties! {
+            *pw::keys::MEDIA_TYPE => "Video",
+            *pw::keys::MEDIA_CATEGORY => "Capture",
+            *pw::keys::MEDIA_ROLE => "Screen",
+        },
+    )?;
+    let _listener = stream
+        .add_local_listener_with_user_data(VideoInfoRaw::default())
+        .state_changed({
+            let main_loop = main_loop.clone();
+            move |_, _, _, state| match state {
+                pw::stream::StreamState::Error(error) => {
+                    log::error!("screencast stream failed: {error}");
+                    main_loop.quit();
+                }
+                // The compositor stopped the cast, e.g. when the window was closed.
+                pw::stream::StreamState::Unconnected => main_loop.quit(),
+                _ => {}
+            }
+        })
+        .param_changed(|_, format, id, param| {
+            let Some(param) = param else {
+                return;
+            };
+            if id == spa::param::ParamType::Format.as_raw() {
+                format.parse(param).log_err();
+            }
+        })
+        .process(move |stream, format| {
+            let Some(mut buffer) = stream.dequeue_buffer() else {
+                return;
+            };
+            let Some(data) = buffer.datas_mut().first_mut() else {
+                return;
+            };
+            let offset = data.chunk().offset() as usize;
+            let stride = data.chunk().stride() as usize;
+            let size = format.size();
+            let Some(bytes) = data.data() else {
+                return;
+            };
+            if let Some(frame) = bytes.get(offset..).and_then(|bytes| {
+                bgra_frame(bytes, size.width, size.height, stride, format.format())
+            }) {
+                // Drop the frame if the previous one wasn't picked up yet.
+                frames.try_send(frame).ok();
+            }
+        })
+        .register()?;
+
+    let format = spa::pod::object!(
+        spa::utils::SpaTypes::ObjectParamFormat,
+        spa::param::ParamType::EnumFormat,
+        spa::pod::property!(
+            spa::param::format::FormatProperties::MediaType,
+            Id,
+            spa::param::format::MediaType::Video
+        ),
+        spa::pod::property!(
+            spa::param::format::FormatProperties::MediaSubtype,
+            Id,
+            spa::param::format::MediaSubtype::Raw
+        ),
+        spa::pod::property!(
+            spa::param::format::FormatProperties::VideoFormat,
+            Choice,
+            Enum,
+            Id,
+            VideoFormat::BGRx,
+            VideoFormat::BGRx,
+            VideoFormat::BGRA,
+            VideoFormat::RGBx,
+            VideoFormat::RGBA,
+        ),
+        spa::pod::property!(
+            spa::param::format::FormatProperties::VideoSize,
+            Choice,
+            Range,
+            Rectangle,
+            spa::utils::Rectangle {
+                width: 1920,
+                height: 1080
+            },
+            spa::utils::Rectangle {
+                width: 1,
+                height: 1
+            },
+            spa::utils::Rectangle {
+                width: 8192,
+                height: 8192
+            }
+        ),
+    );
+    let format = spa::pod::serialize::PodSerializer::serialize(
+        Cursor::new(Vec::new()),
+        &spa::pod::Value::Object(format),
+    )?
+    .0
+    .into_inner();
+    let mut params = [spa::pod::Pod::from_bytes(&format).context("invalid format pod")?];
+    stream.connect(
+        spa::utils::Direction::Input,
+        Some(node_id),
+        pw::stream::StreamFlags::AUTOCONNECT | pw::stream::StreamFlags::MAP_BUFFERS,
+        &mut params,
+    )?;
+
+    main_loop.run();
+    Ok(())
+}
+
+/// Copies a frame out of a PipeWire buffer whose rows are `stride` bytes apart, converting it
+/// to the BGRA of gpui's images.
+fn bgra_frame(
+    bytes: &[u8],
+    width: u32,
+    height: u32,
+    stride: usize,
+    format: VideoFormat,
+) -> Option<RgbaImage> {
+    let (swap_red_blue, opaque) = if format == VideoFormat::BGRA {
+        (false, false)
+    } else if format == VideoFormat::BGRx {
+        (false, true)
+    } else if format == VideoFormat::RGBA {
+        (true, false)
+    } else if format == VideoFormat::RGBx {
+        (true, true)
+    } else {
+        return None;
+    };
+
+    let row_len = width as usize * 4;
+    let height = height as usize;
+    if height == 0 || stride < row_len || bytes.len() < stride * (height - 1) + row_len {
+        return None;
+    }
+    let mut data = Vec::with_capacity(row_len * height);
+    for row in bytes.chunks(stride).take(height) {
+        data.extend_from_slice(&row[..row_len]);
+    }
+    for pixel in data.chunks_exact_mut(4) {
+        if swap_red_blue {
+            pixel.swap(0, 2);
+        }
+        if opaque {
+            pixel[3] = u8::MAX;
+        }
+    }
+    RgbaImage::from_raw(width, height as u32, data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::bgra_frame;
+    use pipewire::spa::param::video::VideoFormat;
+
+    #[test]
+    fn test_bgra_frame_skips_row_padding() {
+        // Two rows of one RGBx pixel, padded to 8 bytes.
+        let bytes = [1, 2, 3, 0, 9, 9, 9, 9, 4, 5, 6, 0];
+        let frame = bgra_frame(&bytes, 1, 2, 8, VideoFormat::RGBx).unwrap();
+        assert_eq!(frame.into_raw(), [3, 2, 1, 255, 6, 5, 4, 255]);
+
+        assert!(bgra_frame(&bytes, 2, 2, 8, VideoFormat::RGBx).is_none());
+        assert!(bgra_frame(&bytes, 1, 2, 8, VideoFormat::I420).is_none());
+    }
+}
@@ -0,0 +1,797 @@
+//! A host for [StatusNotifierItem] tray icons, as shown by panels and docks.
+//!
+//! The host registers with the `org.kde.StatusNotifierWatcher` on the session bus, becoming the
+//! watcher itself when no other process provides one, and exposes every registered item as an
+//! entity that is kept up to date with the item's icon, tooltip and [dbusmenu] menu.
+//!
+//! [StatusNotifierItem]: https://www.freedesktop.org/wiki/Specifications/StatusNotifierItem/
+//! [dbusmenu]: https://github.com/AyatanaIndicators/libdbusmenu
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering::SeqCst},
+    Arc,
+};
+
+use futures::{
+    stream::{self, BoxStream},
+    StreamExt,
+};
+use image::{Frame, ImageBuffer};
+use smallvec::SmallVec;
+use util::ResultExt as _;
+use zbus::{
+    fdo::{DBusProxy, RequestNameFlags, RequestNameReply},
+    message::Header,
+    object_server::SignalEmitter,
+    proxy::CacheProperties,
+    zvariant::OwnedObjectPath,
+    Connection,
+};
+
+use crate::{
+    session_bus, App, AppContext, AsyncApp, Context, DBusMenu, Entity, Point, ReleaseOnDrop,
+    RenderImage, Result, SharedString, Task, WeakEntity,
+};
+
+pub(crate) const WATCHER_NAME: &str = "org.kde.StatusNotifierWatcher";
+const WATCHER_PATH: &str = "/StatusNotifierWatcher";
+pub(crate) const DEFAULT_ITEM_PATH: &str = "/StatusNotifierItem";
+
+/// Hosts the tray items of the session, see the [module documentation](self).
+///
+/// Create it with `cx.new(TrayHost::new)` and observe it to learn about items being added and
+/// removed. Each item is an entity of its own which notifies when its properties change.
+pub struct TrayHost {
+    items: Vec<Entity<TrayItem>>,
+    _task: Task<()>,
+}
+
+impl TrayHost {
+    /// Connects to the session bus and starts tracking the registered tray items.
+    pub fn new(cx: &mut Context<Self>) -> Self {
+        let task = cx.spawn(|this, mut cx| async move {
+            if let Err(error) = Self::run(this, &mut cx).await {
+                log::error!("status notifier host failed: {error:#}");
+            }
+        });
+        Self {
+            items: Vec::new(),
+            _task: task,
+        }
+    }
+
+    /// Returns the tray items, in registration order.
+    pub fn items(&self) -> &[Entity<TrayItem>] {
+        &self.items
+    }
+
+    async fn run(this: WeakEntity<Self>, cx: &mut AsyncApp) -> Result<()> {
+        let connection = cx.update(|cx| session_bus(cx))?.await?;
+
+        static NEXT_HOST_ID: AtomicUsize = AtomicUsize::new(0);
+        let host_name = format!(
+            "org.kde.StatusNotifierHost-{}-{}",
+            std::process::id(),
+            NEXT_HOST_ID.fetch_add(1, SeqCst)
+        );
+
+        let is_watcher = connection
+            .request_name_with_flags(WATCHER_NAME, RequestNameFlags::DoNotQueue.into())
+            .await?
+            == RequestNameReply::PrimaryOwner;
+        let _release = ReleaseOnDrop::new(cx.background_executor().clone(), {
+            let connection = connection.clone();
+            let host_name = host_name.clone();
+            async move {
+                connection.release_name(host_name.as_str()).await.log_err();
+                if is_watcher {
+                    connection.release_name(WATCHER_NAME).await.log_err();
+                    connection
+                        .object_server()
+                        .remove::<StatusNotifierWatcher, _>(WATCHER_PATH)
+                        .await
+                        .log_err();
+                }
+            }
+        });
+        if is_watcher {
+            connection
+                .object_server()
+                .at(WATCHER_PATH, StatusNotifierWatcher::default())
+                .await?;
+        }
+
+        connection.request_name(host_name.as_str()).await?;
+
+        let watcher = StatusNotifierWatcherProxy::new(&connection).await?;
+        let mut events: Vec<BoxStream<'static, HostEvent>> = vec![
+            watcher
+                .receive_status_notifier_item_registered()
+                .await?
+                .filter_map(|signal| async move {
+                    let args = signal.args().ok()?;
+                    Some(HostEvent::ItemRegistered(args.service().to_string()))
+                })
+                .boxed(),
+            watcher
+                .receive_status_notifier_item_unregistered()
+                .await?
+                .filter_map(|signal| async move {
+                    let args = signal.args().ok()?;
+                    Some(HostEvent::ItemUnregistered(args.service().to_string()))
+                })
+                .boxed(),
+        ];
+        if is_watcher {
+            events.push(
+                DBusProxy::new(&connection)
+                    .await?
+                    .receive_name_owner_changed()
+                    .await?
+                    .filter_map(|signal| async move {
+                        let args = signal.args().ok()?;
+                        args.new_owner()
+                            .is_none()
+                            .then(|| HostEvent::NameVanished(args.name().to_string()))
+                    })
+                    .boxed(),
+            );
+        }
+        let mut events = stream::select_all(events);
+
+        watcher.register_status_notifier_host(&host_name).await?;
+        for address in watcher.registered_status_notifier_items().await? {
+            Self::add_item(&this, &connection, address, cx).await?;
+        }
+
+        while let Some(event) = events.next().await {
+            match event {
+                HostEvent::ItemRegistered(address) => {
+                    Self::add_item(&this, &connection, address, cx).await?;
+                }
+                HostEvent::ItemUnregistered(address) => {
+                    this.update(cx, |this, cx| {
+                        this.items
+                            .retain(|item| item.read(cx).address.as_ref() != address);
+                        cx.notify();
+                    })?;
+                }
+                HostEvent::NameVanished(name) => {
+                    let watcher = connection
+                        .object_server()
+                        .interface::<_, StatusNotifierWatcher>(WATCHER_PATH)
+                        .await?;
+                    watcher
+                        .get_mut()
+                        .await
+                        .remove_owner(&name, watcher.signal_emitter())
+                        .await
+                        .log_err();
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn add_item(
+        this: &WeakEntity<Self>,
+        connection: &Connection,
+        address: String,
+        cx: &mut AsyncApp,
+    ) -> Result<()> {
+        let is_known = this.update(cx, |this, cx| {
+            this.items
+                .iter()
+                .any(|item| item.read(cx).address.as_ref() == address)
+        })?;
+        if is_known {
+            return Ok(());
+        }
+
+        let (service, path) = split_item_address(&address);
+        let proxy = match StatusNotifierItemProxy::builder(connection)
+            .destination(service.to_string())?
+            .path(path.to_string())?
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await
+        {
+            Ok(proxy) => proxy,
+            Err(error) => {
+                log::warn!("failed to connect to tray item {address}: {error}");
+                return Ok(());
+            }
+        };
+        let properties = TrayItemProperties::fetch(&proxy).await;
+
+        this.update(cx, |this, cx| {
+            let item = cx
+                .new(|cx| TrayItem::new(address.into(), connection.clone(), proxy, properties, cx));
+            this.items.push(item);
+            cx.notify();
+        })
+    }
+}
+
+enum HostEvent {
+    ItemRegistered(String),
+    ItemUnregistered(String),
+    NameVanished(String),
+}
+
+/// Splits a watcher entry of the form `service/object/path` into its bus name and object path.
+fn split_item_address(address: &str) -> (&str, &str) {
+    match address.find('/') {
+        Some(index) => address.split_at(index),
+        None => (address, DEFAULT_ITEM_PATH),
+    }
+}
+
+/// The category of a [`TrayItem`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TrayItemCategory {
+    /// The item describes the status of a generic application.
+    #[default]
+    ApplicationStatus,
+    /// The item describes the status of a communication oriented application, like a chat client.
+    Communications,
+    /// The item describes services of the system not seen as a standalone application.
+    SystemServices,
+    /// The item describes the state of hardware, like the battery.
+    Hardware,
+}
+
+impl TrayItemCategory {
+    fn from_str(category: &str) -> Self {
+        match category {
+            "Communications" => Self::Communications,
+            "SystemServices" => Self::SystemServices,
+            "Hardware" => Self::Hardware,
+            _ => Self::ApplicationStatus,
+        }
+    }
+
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::ApplicationStatus => "ApplicationStatus",
+            Self::Communications => "Communications",
+            Self::SystemServices => "SystemServices",
+            Self::Hardware => "Hardware",
+        }
+    }
+}
+
+/// The status of a [`TrayItem`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TrayItemStatus {
+    /// The item doesn't convey important information and may be hidden.
+    Passive,
+    /// The item is active and should be shown.
+    #[default]
+    Active,
+    /// The item needs the user's attention, it should show its attention icon if it has one.
+    NeedsAttention,
+}
+
+impl TrayItemStatus {
+    fn from_str(status: &str) -> Self {
+        match status {
+            "Passive" => Self::Passive,
+            "NeedsAttention" => Self::NeedsAttention,
+            _ => Self::Active,
+        }
+    }
+
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Passive => "Passive",
+            Self::Active => "Active",
+            Self::NeedsAttention => "NeedsAttention",
+        }
+    }
+}
+
+/// The orientation of a scroll over a [`TrayItem`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrayScrollOrientation {
+    /// A vertical scroll.
+    Vertical,
+    /// A horizontal scroll.
+    Horizontal,
+}
+
+/// The tooltip of a [`TrayItem`].
+#[derive(Clone, Debug, Default)]
+pub struct TrayTooltip {
+    /// The freedesktop icon name of the tooltip, if any.
+    pub icon_name: Option<SharedString>,
+    /// The icon of the tooltip, if provided as pixels.
+    pub icon: Option<Arc<RenderImage>>,
+    /// The title of the tooltip.
+    pub title: SharedString,
+    /// The description of the tooltip, which may contain basic markup.
+    pub description: SharedString,
+}
+
+#[derive(Default)]
+struct TrayItemProperties {
+    id: SharedString,
+    title: SharedString,
+    category: TrayItemCategory,
+    status: TrayItemStatus,
+    icon_name: Option<SharedString>,
+    icon_theme_path: Option<SharedString>,
+    icon: Option<Arc<RenderImage>>,
+    attention_icon_name: Option<SharedString>,
+    attention_icon: Option<Arc<RenderImage>>,
+    overlay_icon_name: Option<SharedString>,
+    tooltip: Option<TrayTooltip>,
+    item_is_menu: bool,
+    menu: Option<OwnedObjectPath>,
+}
+
+impl TrayItemProperties {
+    /// Fetches the properties of an item. Items commonly don't implement all of them, so
+    /// missing properties fall back to their defaults.
+    async fn fetch(proxy: &StatusNotifierItemProxy<'static>) -> Self {
+        Self {
+            id: proxy.id().await.unwrap_or_default().into(),
+            title: proxy.title().await.unwrap_or_default().into(),
+            category: proxy
+                .category()
+                .await
+                .map(|category| TrayItemCategory::from_str(&category))
+                .unwrap_or_default(),
+            status: proxy
+                .status()
+                .await
+                .map(|status| TrayItemStatus::from_str(&status))
+                .unwrap_or_default(),
+            icon_name: non_empty(proxy.icon_name().await),
+            icon_theme_path: non_empty(proxy.icon_theme_path().await),
+            icon: proxy.icon_pixmap().await.ok().and_then(image_from_pixmaps),
+            attention_icon_name: non_empty(proxy.attention_icon_name().await),
+            attention_icon: proxy
+                .attention_icon_pixmap()
+                .await
+                .ok()
+                .and_then(image_from_pixmaps),
+            overlay_icon_name: non_empty(proxy.overlay_icon_name().await),
+            tooltip: proxy
+                .tool_tip()
+                .await
+                .ok()
+                .map(|(icon_name, icon, title, description)| TrayTooltip {
+                    icon_name: non_empty(Ok(icon_name)),
+                    icon: image_from_pixmaps(icon),
+                    title: title.into(),
+                    description: description.into(),
+                }),
+            item_is_menu: proxy.item_is_menu().await.unwrap_or(false),
+            menu: proxy.menu().await.ok().filter(|path| path.as_str() != "/"),
+        }
+    }
+}
+
+fn non_empty(value: zbus::Result<String>) -> Option<SharedString> {
+    value.ok().filter(|value| !value.is_empty()).map(Into::into)
+}
+
+/// Converts the largest of the given ARGB32 pixmaps, in network byte order, to an image.
+fn image_from_pixmaps(pixmaps: Vec<(i32, i32, Vec<u8>)>) -> Option<Arc<RenderImage>> {
+    let (width, height, mut data) = pixmaps
+        .into_iter()
+        .filter(|(width, height, data)| {
+            *width > 0 && *height > 0 && data.len() == *width as usize * *height as usize * 4
+        })
+        .max_by_key(|(width, height, _)| width * height)?;
+
+    // Convert from ARGB to BGRA.
+    for pixel in data.chunks_exact_mut(4) {
+        pixel.reverse();
+    }
+
+    let buffer = ImageBuffer::from_raw(width as u32, height as u32, data)?;
+    Some(Arc::new(RenderImage::new(SmallVec::from_elem(
+        Frame::new(buffer),
+        1,
+    ))))
+}
+
+/// An item shown in the tray, owned by a [`TrayHost`].
+pub struct TrayItem {
+    address: SharedString,
+    connection: Connection,
+    proxy: StatusNotifierItemProxy<'static>,
+    properties: TrayItemProperties,
+    menu: Option<Entity<DBusMenu>>,
+    _task: Task<()>,
+}
+
+impl TrayItem {
+    fn new(
+        address: SharedString,
+        connection: Connection,
+        proxy: StatusNotifierItemProxy<'static>,
+        properties: TrayItemProperties,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let task = cx.spawn({
+            let proxy = proxy.clone();
+            |this, mut cx| async move {
+                Self::listen(this, proxy, &mut cx).await.log_err();
+            }
+        });
+        let mut this = Self {
+            address,
+            connection,
+            proxy,
+            properties: TrayItemProperties::default(),
+            menu: None,
+            _task: task,
+        };
+        this.set_properties(properties, cx);
+        this
+    }
+
+    async fn listen(
+        this: WeakEntity<Self>,
+        proxy: StatusNotifierItemProxy<'static>,
+        cx: &mut AsyncApp,
+    ) -> Result<()> {
+        let mut changes = stream::select_all([
+            proxy.receive_new_title().await?.map(|_| ()).boxed(),
+            proxy.receive_new_icon().await?.map(|_| ()).boxed(),
+            proxy
+                .receive_new_attention_icon()
+                .await?
+                .map(|_| ())
+                .boxed(),
+            proxy.receive_new_overlay_icon().await?.map(|_| ()).boxed(),
+            proxy.receive_new_tool_tip().await?.map(|_| ()).boxed(),
+            proxy.receive_new_status().await?.map(|_| ()).boxed(),
+        ]);
+
+        while changes.next().await.is_some() {
+            let properties = TrayItemProperties::fetch(&proxy).await;
+            this.update(cx, |this, cx| this.set_properties(properties, cx))?;
+        }
+
+        Ok(())
+    }
+
+    fn set_properties(&mut self, properties: TrayItemProperties, cx: &mut Context<Self>) {
+        if properties.menu != self.properties.menu {
+            self.menu = properties.menu.clone().map(|path| {
+                let service = self.proxy.inner().destination().to_string();
+                let connection = self.connection.clone();
+                cx.new(|cx| DBusMenu::with_connection(connection, service, path, cx))
+            });
+        }
+        self.properties = properties;
+        cx.notify();
+    }
+
+    /// Returns the identifier of the item, which is stable across sessions.
+    pub fn id(&self) -> &SharedString {
+        &self.properties.id
+    }
+
+    /// Returns the human readable name of the item.
+    pub fn title(&self) -> &SharedString {
+        &self.properties.title
+    }
+
+    /// Returns the category of the item.
+    pub fn category(&self) -> TrayItemCategory {
+        self.properties.category
+    }
+
+    /// Returns the status of the item.
+    pub fn status(&self) -> TrayItemStatus {
+        self.properties.status
+    }
+
+    /// Returns the freedesktop icon name of the item, if any.
+    pub fn icon_name(&self) -> Option<&SharedString> {
+        self.properties.icon_name.as_ref()
+    }
+
+    /// Returns an additional path to look up the item's icon names in, if any.
+    pub fn icon_theme_path(&self) -> Option<&SharedString> {
+        self.properties.icon_theme_path.as_ref()
+    }
+
+    /// Returns the icon of the item, if provided as pixels.
+    pub fn icon(&self) -> Option<&Arc<RenderImage>> {
+        self.properties.icon.as_ref()
+    }
+
+    /// Returns the freedesktop icon name to show while the item needs attention, if any.
+    pub fn attention_icon_name(&self) -> Option<&SharedString> {
+        self.properties.attention_icon_name.as_ref()
+    }
+
+    /// Returns the icon to show while the item needs attention, if provided as pixels.
+    pub fn attention_icon(&self) -> Option<&Arc<RenderImage>> {
+        self.properties.attention_icon.as_ref()
+    }
+
+    /// Returns the freedesktop icon name to draw over the item's icon, if any.
+    pub fn overlay_icon_name(&self) -> Option<&SharedString> {
+        self.properties.overlay_icon_name.as_ref()
+    }
+
+    /// Returns the tooltip of the item, if any.
+    pub fn tooltip(&self) -> Option<&TrayTooltip> {
+        self.properties.tooltip.as_ref()
+    }
+
+    /// Returns whether the item only supports showing its menu, in which case activating it
+    /// should show the menu instead of calling [`TrayItem::activate`].
+    pub fn item_is_menu(&self) -> bool {
+        self.properties.item_is_menu
+    }
+
+    /// Returns the menu of the item, if it exports one.
+    pub fn menu(&self) -> Option<&Entity<DBusMenu>> {
+        self.menu.as_ref()
+    }
+
+    /// Asks the item to perform its primary action, usually on a left click at the given
+    /// screen position.
+    pub fn activate(&self, position: Point<i32>, cx: &App) -> Task<Result<()>> {
+        let proxy = self.proxy.clone();
+        cx.background_executor().spawn(async move {
+            proxy.activate(position.x, position.y).await?;
+            Ok(())
+        })
+    }
+
+    /// Asks the item to perform its secondary action, usually on a middle click at the given
+    /// screen position.
+    pub fn secondary_activate(&self, position: Point<i32>, cx: &App) -> Task<Result<()>> {
+        let proxy = self.proxy.clone();
+        cx.background_executor().spawn(async move {
+            proxy.secondary_activate(position.x, position.y).await?;
+            Ok(())
+        })
+    }
+
+    /// Asks the item to show its own context menu at the given screen position. Hosts usually
+    /// show [`TrayItem::menu`] instead when the item exports one.
+    pub fn context_menu(&self, position: Point<i32>, cx: &App) -> Task<Result<()>> {
+        let proxy = self.proxy.clone();
+        cx.background_executor().spawn(async move {
+            proxy.context_menu(position.x, position.y).await?;
+            Ok(())
+        })
+    }
+
+    /// Forwards a scroll over the item, in wheel steps.
+    pub fn scroll(
+        &self,
+        delta: i32,
+        orientation: TrayScrollOrientation,
+        cx: &App,
+    ) -> Task<Result<()>> {
+        let proxy = self.proxy.clone();
+        let orientation = match orientation {
+            TrayScrollOrientation::Vertical => "vertical",
+            TrayScrollOrientation::Horizontal => "horizontal",
+        };
+        cx.background_executor().spawn(async move {
+            proxy.scroll(delta, orientation).await?;
+            Ok(())
+        })
+    }
+}
+
+/// The watcher this process provides when no other process on the session bus does.
+#[derive(Default)]
+struct StatusNotifierWatcher {
+    items: Vec<String>,
+    hosts: Vec<String>,
+}
+
+impl StatusNotifierWatcher {
+    /// Forgets the items and hosts of a name that left the bus.
+    async fn remove_owner(&mut self, name: &str, emitter: &SignalEmitter<'_>) -> zbus::Result<()> {
+        let mut removed_items = Vec::new();
+        self.items.retain(|item| {
+            let is_owned = split_item_address(item).0 == name;
+            if is_owned {
+                removed_items.push(item.clone());
+            }
+            !is_owned
+        });
+        for item in &removed_items {
+            Self::status_notifier_item_unregistered(emitter, item).await?;
+        }
+        if !removed_items.is_empty() {
+            self.registered_status_notifier_items_changed(emitter)
+                .await?;
+        }
+
+        let host_count = self.hosts.len();
+        self.hosts.retain(|host| host != name);
+        if self.hosts.len() != host_count {
+            Self::status_notifier_host_unregistered(emitter).await?;
+            if self.hosts.is_empty() {
+                self.is_status_notifier_host_registered_changed(emitter)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[zbus::interface(name = "org.kde.StatusNotifierWatcher")]
+impl StatusNotifierWatcher {
+    async fn register_status_notifier_item(
+        &mut self,
+        service: &str,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> zbus::fdo::Result<()> {
+        // Items may register with their object path only, in which case they're identified by
+        // the sender's unique name.
+        let item = if service.starts_with('/') {
+            let sender = header
+                .sender()
+                .ok_or_else(|| zbus::fdo::Error::InvalidArgs("unknown sender".into()))?;
+            format!("{sender}{service}")
+        } else {
+            format!("{service}{DEFAULT_ITEM_PATH}")
+        };
+
+        if !self.items.contains(&item) {
+            self.items.push(item.clone());
+            Self::status_notifier_item_registered(&emitter, &item).await?;
+            self.registered_status_notifier_items_changed(&emitter)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn register_status_notifier_host(
+        &mut self,
+        service: &str,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> zbus::fdo::Result<()> {
+        if !self.hosts.iter().any(|host| host == service) {
+            self.hosts.push(service.to_string());
+            Self::status_notifier_host_registered(&emitter).await?;
+            if self.hosts.len() == 1 {
+                self.is_status_notifier_host_registered_changed(&emitter)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    #[zbus(property)]
+    fn registered_status_notifier_items(&self) -> Vec<String> {
+        self.items.clone()
+    }
+
+    #[zbus(property)]
+    fn is_status_notifier_host_registered(&self) -> bool {
+        !self.hosts.is_empty()
+    }
+
+    #[zbus(property)]
+    fn protocol_version(&self) -> i32 {
+        0
+    }
+
+    #[zbus(signal)]
+    async fn status_notifier_item_registered(
+        emitter: &SignalEmitter<'_>,
+        service: &str,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn status_notifier_item_unregistered(
+        emitter: &SignalEmitter<'_>,
+        service: &str,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn status_notifier_host_registered(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn status_notifier_host_unregistered(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+}
+
+#[zbus::proxy(
+    interface = "org.kde.StatusNotifierWatcher",
+    default_service = "org.kde.StatusNotifierWatcher",
+    default_path = "/StatusNotifierWatcher"
+)]
+pub(crate) trait StatusNotifierWatcher {
+    fn register_status_notifier_item(&self, service: &str) -> zbus::Result<()>;
+
+    fn register_status_notifier_host(&self, service: &str) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn registered_status_notifier_items(&self) -> zbus::Result<Vec<String>>;
+
+    #[zbus(signal)]
+    fn status_notifier_item_registered(&self, service: &str) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn status_notifier_item_unregistered(&self, service: &str) -> zbus::Result<()>;
+}
+
+pub(crate) type Pixmaps = Vec<(i32, i32, Vec<u8>)>;
+
+#[zbus::proxy(interface = "org.kde.StatusNotifierItem")]
+trait StatusNotifierItem {
+    fn activate(&self, x: i32, y: i32) -> zbus::Result<()>;
+
+    fn secondary_activate(&self, x: i32, y: i32) -> zbus::Result<()>;
+
+    fn context_menu(&self, x: i32, y: i32) -> zbus::Result<()>;
+
+    fn scroll(&self, delta: i32, orientation: &str) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn id(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn title(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn category(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn status(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn icon_name(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn icon_theme_path(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn icon_pixmap(&self) -> zbus::Result<Pixmaps>;
+
+    #[zbus(property)]
+    fn attention_icon_name(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn attention_icon_pixmap(&self) -> zbus::Result<Pixmaps>;
+
+    #[zbus(property)]
+    fn overlay_icon_name(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn tool_tip(&self) -> zbus::Result<(String, Pixmaps, String, String)>;
+
+    #[zbus(property)]
+    fn item_is_menu(&self) -> zbus::Result<bool>;
+
+    #[zbus(property)]
+    fn menu(&self) -> zbus::Result<OwnedObjectPath>;
+
+    #[zbus(signal)]
+    fn new_title(&self) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn new_icon(&self) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn new_attention_icon(&self) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn new_overlay_icon(&self) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn new_tool_tip(&self) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn new_status(&self, status: &str) -> zbus::Result<()>;
+}
@@ -0,0 +1,503 @@
+//! Shows the application in the tray as a [StatusNotifierItem], with an icon, a tooltip and a
+//! [dbusmenu] menu, for applications living in the background.
+//!
+//! Each icon is served on a session bus connection of its own, since watchers only forget an
+//! item once its bus name vanishes.
+//!
+//! [StatusNotifierItem]: https://www.freedesktop.org/wiki/Specifications/StatusNotifierItem/
+//! [dbusmenu]: https://github.com/AyatanaIndicators/libdbusmenu
+
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering::SeqCst},
+        Arc,
+    },
+};
+
+use futures::{channel::mpsc, future, stream, StreamExt};
+use parking_lot::Mutex;
+use util::ResultExt as _;
+use zbus::{
+    fdo::{DBusProxy, Properties},
+    names::InterfaceName,
+    object_server::SignalEmitter,
+    zvariant::{ObjectPath, OwnedObjectPath},
+    Connection,
+};
+
+use super::{
+    app_menu::{emit_layout_updated, AppMenuEvent, DbusmenuInterface, MenuTree},
+    status_notifier_host::{Pixmaps, StatusNotifierWatcherProxy, DEFAULT_ITEM_PATH, WATCHER_NAME},
+};
+use crate::{
+    Action, AsyncApp, Context, EventEmitter, MenuItem, Point, RenderImage, Result, SharedString,
+    Task, TrayItemCategory, TrayItemStatus, TrayScrollOrientation, TrayTooltip, WeakEntity,
+};
+
+const ITEM_INTERFACE: &str = "org.kde.StatusNotifierItem";
+const MENU_PATH: &str = "/StatusNotifierItem/Menu";
+
+/// Describes a [`TrayIcon`].
+#[derive(Clone, Debug, Default)]
+pub struct TrayIconOptions {
+    /// The identifier of the icon, which should be stable across sessions.
+    pub id: SharedString,
+    /// The human readable name of the icon.
+    pub title: SharedString,
+    /// The category of the icon.
+    pub category: TrayItemCategory,
+    /// The status of the icon.
+    pub status: TrayItemStatus,
+    /// The freedesktop icon name of the icon, if any.
+    pub icon_name: Option<SharedString>,
+    /// The icon as pixels, shown when there's no icon name or it can't be found.
+    pub icon: Option<Arc<RenderImage>>,
+    /// The freedesktop icon name to show while the icon needs attention, if any.
+    pub attention_icon_name: Option<SharedString>,
+    /// The icon to show while the icon needs attention, as pixels.
+    pub attention_icon: Option<Arc<RenderImage>>,
+    /// The tooltip of the icon, if any.
+    pub tooltip: Option<TrayTooltip>,
+    /// Whether the icon only shows its menu, in which case activating it shows the menu
+    /// instead of emitting [`TrayIconEvent::Activate`].
+    pub item_is_menu: bool,
+}
+
+/// An event emitted by a [`TrayIcon`], with the screen position the user interacted at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrayIconEvent {
+    /// The icon was activated, usually with a left click.
+    Activate(Point<i32>),
+    /// The icon was activated in a secondary way, usually with a middle click.
+    SecondaryActivate(Point<i32>),
+    /// The host asked for a context menu, because the icon has no menu or the host can't show
+    /// it.
+    ContextMenu(Point<i32>),
+    /// The user scrolled over the icon, by the given amount of wheel steps.
+    Scroll {
+        /// The amount of wheel steps scrolled.
+        delta: i32,
+        /// The orientation of the scroll.
+        orientation: TrayScrollOrientation,
+    },
+}
+
+enum ItemRequest {
+    Event(TrayIconEvent),
+    Activated(Box<dyn Action>),
+}
+
+enum Wakeup {
+    WatcherAppeared,
+    Request(ItemRequest),
+}
+
+/// Shows the application in the tray, see the [module documentation](self).
+///
+/// Create it with `cx.new(|cx| TrayIcon::new(options, cx))` and subscribe to it to handle the
+/// user interacting with the icon. The icon is removed from the tray once the entity is
+/// released. The actions of its menu are dispatched like those of the application menus.
+pub struct TrayIcon {
+    options: Arc<Mutex<TrayIconOptions>>,
+    menu: Arc<Mutex<MenuTree>>,
+    connection: Option<Connection>,
+    _task: Task<()>,
+}
+
+impl EventEmitter<TrayIconEvent> for TrayIcon {}
+
+impl TrayIcon {
+    /// Connects to the session bus and registers the icon with the tray.
+    pub fn new(options: TrayIconOptions, cx: &mut Context<Self>) -> Self {
+        let options = Arc::new(Mutex::new(options));
+        let menu = Arc::new(Mutex::new(MenuTree::default()));
+        let task = cx.spawn({
+            let options = options.clone();
+            let menu = menu.clone();
+            |this, mut cx| async move {
+                if let Err(error) = Self::run(this, options, menu, &mut cx).await {
+                    log::error!("tray icon failed: {error:#}");
+                }
+            }
+        });
+        Self {
+            options,
+            menu,
+            connection: None,
+            _task: task,
+        }
+    }
+
+    async fn run(
+        this: WeakEntity<Self>,
+        options: Arc<Mutex<TrayIconOptions>>,
+        menu: Arc<Mutex<MenuTree>>,
+        cx: &mut AsyncApp,
+    ) -> Result<()> {
+        let (requests_tx, requests_rx) = mpsc::unbounded();
+        let connection = Connection::session().await?;
+        connection
+            .object_server()
+            .at(
+                DEFAULT_ITEM_PATH,
+                StatusNotifierItemInterface {
+                    options,
+                    menu: menu.clone(),
+                    requests: requests_tx.clone(),
+                },
+            )
+            .await?;
+        connection
+            .object_server()
+            .at(
+                MENU_PATH,
+                DbusmenuInterface::new(menu, move |event| {
+                    if let AppMenuEvent::Activated(action) = event {
+                        requests_tx
+                            .unbounded_send(ItemRequest::Activated(action))
+                            .log_err();
+                    }
+                }),
+            )
+            .await?;
+
+        static NEXT_ITEM_ID: AtomicUsize = AtomicUsize::new(0);
+        let name = format!(
+            "org.kde.StatusNotifierItem-{}-{}",
+            std::process::id(),
+            NEXT_ITEM_ID.fetch_add(1, SeqCst)
+        );
+        connection.request_name(name.as_str()).await?;
+        this.update(cx, |this, _| this.connection = Some(connection.clone()))?;
+
+        // Register again whenever a watcher appears, like when the panel restarts.
+        let watchers = stream::once(future::ready(())).chain(
+            DBusProxy::new(&connection)
+                .await?
+                .receive_name_owner_changed_with_args(&[(0, WATCHER_NAME)])
+                .await?
+                .filter_map(|signal| async move {
+                    let args = signal.args().ok()?;
+                    args.new_owner().is_some().then_some(())
+                }),
+        );
+        let mut wakeups = stream::select(
+            watchers.map(|_| Wakeup::WatcherAppeared),
+            requests_rx.map(Wakeup::Request),
+        );
+
+        while let Some(wakeup) = wakeups.next().await {
+            match wakeup {
+                Wakeup::WatcherAppeared => {
+                    // Trays aren't always running, the icon shows up once one starts.
+                    if let Err(error) = register(&connection, &name).await {
+                        log::debug!("failed to register the tray icon: {error}");
+                    }
+                }
+                Wakeup::Request(request) => {
+                    this.update(cx, |_, cx| match request {
+                        ItemRequest::Event(event) => cx.emit(event),
+                        ItemRequest::Activated(action) => cx.dispatch_action(action.as_ref()),
+                    })?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the options the icon is shown with.
+    pub fn options(&self) -> TrayIconOptions {
+        self.options.lock().clone()
+    }
+
+    /// Changes the title of the icon.
+    pub fn set_title(&mut self, title: impl Into<SharedString>, cx: &mut Context<Self>) {
+        self.options.lock().title = title.into();
+        self.notify_tray(cx, |emitter| async move {
+            StatusNotifierItemInterface::new_title(&emitter).await
+        });
+    }
+
+    /// Changes the status of the icon.
+    pub fn set_status(&mut self, status: TrayItemStatus, cx: &mut Context<Self>) {
+        self.options.lock().status = status;
+        self.notify_tray(cx, move |emitter| async move {
+            StatusNotifierItemInterface::new_status(&emitter, status.as_str()).await
+        });
+    }
+
+    /// Changes the icon, by freedesktop icon name or as pixels.
+    pub fn set_icon(
+        &mut self,
+        icon_name: Option<SharedString>,
+        icon: Option<Arc<RenderImage>>,
+        cx: &mut Context<Self>,
+    ) {
+        {
+            let mut options = self.options.lock();
+            options.icon_name = icon_name;
+            options.icon = icon;
+        }
+        self.notify_tray(cx, |emitter| async move {
+            StatusNotifierItemInterface::new_icon(&emitter).await
+        });
+    }
+
+    /// Changes the icon shown while the icon needs attention, by freedesktop icon name or as
+    /// pixels.
+    pub fn set_attention_icon(
+        &mut self,
+        icon_name: Option<SharedString>,
+        icon: Option<Arc<RenderImage>>,
+        cx: &mut Context<Self>,
+    ) {
+        {
+            let mut options = self.options.lock();
+            options.attention_icon_name = icon_name;
+            options.attention_icon = icon;
+        }
+        self.notify_tray(cx, |emitter| async move {
+            StatusNotifierItemInterface::new_attention_icon(&emitter).await
+        });
+    }
+
+    /// Changes the tooltip of the icon.
+    pub fn set_tooltip(&mut self, tooltip: Option<TrayTooltip>, cx: &mut Context<Self>) {
+        self.options.lock().tooltip = tooltip;
+        self.notify_tray(cx, |emitter| async move {
+            StatusNotifierItemInterface::new_tool_tip(&emitter).await
+        });
+    }
+
+    /// Replaces the menu of the icon, shown by the tray on a right click. Pass no items to
+    /// remove the menu, in which case the tray asks for [`TrayIconEvent::ContextMenu`] instead.
+    pub fn set_menu(&mut self, items: Vec<MenuItem>, cx: &mut Context<Self>) {
+        let items = items
+            .into_iter()
+            .map(|item| item.owned())
+            .collect::<Vec<_>>();
+        let revision = {
+            let mut menu = self.menu.lock();
+            *menu = MenuTree::from_items(&items, &cx.keymap.borrow(), menu.revision() + 1);
+            menu.revision()
+        };
+        let connection = self.connection.clone();
+        // The menu path changes when the menu is added or removed.
+        self.notify_tray(cx, |emitter| async move {
+            Properties::properties_changed(
+                &emitter,
+                InterfaceName::from_static_str_unchecked(ITEM_INTERFACE),
+                HashMap::new(),
+                Cow::Borrowed(&["Menu"][..]),
+            )
+            .await?;
+            match connection {
+                Some(connection) => emit_layout_updated(&connection, MENU_PATH, revision).await,
+                None => Ok(()),
+            }
+        });
+    }
+
+    /// Tells the tray a property changed, once the icon is registered.
+    fn notify_tray<F>(
+        &self,
+        cx: &mut Context<Self>,
+        signal: impl FnOnce(SignalEmitter<'static>) -> F,
+    ) where
+        F: std::future::Future<Output = zbus::Result<()>> + Send + 'static,
+    {
+        let Some(emitter) = self.connection.as_ref().and_then(|connection| {
+            SignalEmitter::new(connection, DEFAULT_ITEM_PATH)
+                .log_err()
+                .map(SignalEmitter::into_owned)
+        }) else {
+            return;
+        };
+        let signal = signal(emitter);
+        cx.background_executor()
+            .spawn(async move {
+                signal.await.log_err();
+            })
+            .detach();
+    }
+}
+
+async fn register(connection: &Connection, name: &str) -> zbus::Result<()> {
+    StatusNotifierWatcherProxy::new(connection)
+        .await?
+        .register_status_notifier_item(name)
+        .await
+}
+
+/// Converts an image to ARGB32 pixmaps in network byte order, one for each frame.
+fn pixmaps(image: Option<&Arc<RenderImage>>) -> Pixmaps {
+    let Some(image) = image else {
+        return Vec::new();
+    };
+    (0..image.frame_count())
+        .filter_map(|frame| {
+            let size = image.size(frame);
+            let mut data = image.as_bytes(frame)?.to_vec();
+            // Convert from BGRA to ARGB.
+            for pixel in data.chunks_exact_mut(4) {
+                pixel.reverse();
+            }
+            Some((size.width.0, size.height.0, data))
+        })
+        .collect()
+}
+
+struct StatusNotifierItemInterface {
+    options: Arc<Mutex<TrayIconOptions>>,
+    menu: Arc<Mutex<MenuTree>>,
+    requests: mpsc::UnboundedSender<ItemRequest>,
+}
+
+impl StatusNotifierItemInterface {
+    fn send(&self, event: TrayIconEvent) {
+        self.requests
+            .unbounded_send(ItemRequest::Event(event))
+            .log_err();
+    }
+}
+
+#[zbus::interface(name = "org.kde.StatusNotifierItem")]
+impl StatusNotifierItemInterface {
+    fn activate(&self, x: i32, y: i32) {
+        self.send(TrayIconEvent::Activate(Point::new(x, y)));
+    }
+
+    fn secondary_activate(&self, x: i32, y: i32) {
+        self.send(TrayIconEvent::SecondaryActivate(Point::new(x, y)));
+    }
+
+    fn context_menu(&self, x: i32, y: i32) {
+        self.send(TrayIconEvent::ContextMenu(Point::new(x, y)));
+    }
+
+    fn scroll(&self, delta: i32, orientation: &str) {
+        let orientation = if orientation.eq_ignore_ascii_case("horizontal") {
+            TrayScrollOrientation::Horizontal
+        } else {
+            TrayScrollOrientation::Vertical
+        };
+        self.send(TrayIconEvent::Scroll { delta, orientation });
+    }
+
+    #[zbus(property)]
+    fn id(&self) -> String {
+        self.options.lock().id.to_string()
+    }
+
+    #[zbus(property)]
+    fn title(&self) -> String {
+        self.options.lock().title.to_string()
+    }
+
+    #[zbus(property)]
+    fn category(&self) -> String {
+        self.options.lock().category.as_str().to_string()
+    }
+
+    #[zbus(property)]
+    fn status(&self) -> String {
+        self.options.lock().status.as_str().to_string()
+    }
+
+    #[zbus(property)]
+    fn window_id(&self) -> i32 {
+        0
+    }
+
+    #[zbus(property)]
+    fn icon_name(&self) -> String {
+        self.options
+            .lock()
+            .icon_name
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default()
+    }
+
+    #[zbus(property)]
+    fn icon_pixmap(&self) -> Pixmaps {
+        pixmaps(self.options.lock().icon.as_ref())
+    }
+
+    #[zbus(property)]
+    fn attention_icon_name(&self) -> String {
+        self.options
+            .lock()
+            .attention_icon_name
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default()
+    }
+
+    #[zbus(property)]
+    fn attention_icon_pixmap(&self) -> Pixmaps {
+        pixmaps(self.options.lock().attention_icon.as_ref())
+    }
+
+    #[zbus(property)]
+    fn overlay_icon_name(&self) -> String {
+        String::new()
+    }
+
+    #[zbus(property)]
+    fn overlay_icon_pixmap(&self) -> Pixmaps {
+        Vec::new()
+    }
+
+    #[zbus(property)]
+    fn tool_tip(&self) -> (String, Pixmaps, String, String) {
+        let options = self.options.lock();
+        let Some(tooltip) = &options.tooltip else {
+            return Default::default();
+        };
+        (
+            tooltip
+                .icon_name
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            pixmaps(tooltip.icon.as_ref()),
+            tooltip.title.to_string(),
+            tooltip.description.to_string(),
+        )
+    }
+
+    #[zbus(property)]
+    fn item_is_menu(&self) -> bool {
+        self.options.lock().item_is_menu
+    }
+
+    #[zbus(property)]
+    fn menu(&self) -> OwnedObjectPath {
+        // Hosts take the root path as the item having no menu.
+        let path = if self.menu.lock().is_empty() {
+            "/"
+        } else {
+            MENU_PATH
+        };
+        ObjectPath::from_static_str_unchecked(path).into()
+    }
+
+    #[zbus(signal)]
+    async fn new_title(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn new_icon(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn new_attention_icon(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn new_tool_tip(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn new_status(emitter: &SignalEmitter<'_>, status: &str) -> zbus::Result<()>;
+}
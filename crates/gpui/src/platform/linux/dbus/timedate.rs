@@ -0,0 +1,173 @@
+//! Notifications of the system clock being set and of the timezone changing, see
+//! [`App::on_time_changed`].
+//!
+//! Clock jumps are noticed with a timerfd canceled whenever the realtime clock is set, and
+//! timezone changes through the properties of [timedated].
+//!
+//! [timedated]: https://www.freedesktop.org/software/systemd/man/latest/org.freedesktop.timedate1.html
+
+#[cfg(target_os = "linux")]
+use std::{
+    io, mem,
+    os::fd::{AsFd as _, AsRawFd as _, FromRawFd as _, OwnedFd},
+    ptr,
+};
+
+use futures::StreamExt as _;
+use util::ResultExt as _;
+use zbus::{fdo::PropertiesProxy, proxy::CacheProperties};
+
+use crate::{system_bus, App, AsyncApp, Global, Result, SubscriberSet, Subscription, Task};
+
+const TIMEDATE_SERVICE: &str = "org.freedesktop.timedate1";
+const TIMEDATE_PATH: &str = "/org/freedesktop/timedate1";
+const TIMEDATE_INTERFACE: &str = "org.freedesktop.timedate1";
+
+/// A change of the wall clock time, see [`App::on_time_changed`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeChange {
+    /// The system clock was set, e.g. by NTP or the user, so the time jumped.
+    Clock,
+    /// The timezone of the system changed.
+    Timezone,
+}
+
+type TimeChangeHandler = Box<dyn FnMut(TimeChange, &mut App) -> bool + 'static>;
+
+/// Watches for time changes, started by the first subscription.
+struct TimeMonitor {
+    observers: SubscriberSet<(), TimeChangeHandler>,
+    #[cfg(target_os = "linux")]
+    _clock: Option<Subscription>,
+    _timezone: Task<()>,
+}
+
+impl Global for TimeMonitor {}
+
+impl App {
+    /// Invokes the callback when the system clock is set or the timezone changes, so clocks and
+    /// calendars can update right away instead of at their next tick.
+    pub fn on_time_changed(
+        &mut self,
+        mut callback: impl FnMut(TimeChange, &mut App) + 'static,
+    ) -> Subscription {
+        if !self.has_global::<TimeMonitor>() {
+            let monitor = TimeMonitor {
+                observers: SubscriberSet::new(),
+                #[cfg(target_os = "linux")]
+                _clock: self.watch_clock().log_err(),
+                _timezone: self.spawn(|mut cx| async move {
+                    if let Err(error) = watch_timezone(&mut cx).await {
+                        log::error!("failed to watch for timezone changes: {error:#}");
+                    }
+                }),
+            };
+            self.set_global(monitor);
+        }
+
+        let (subscription, activate) = self.global::<TimeMonitor>().observers.insert(
+            (),
+            Box::new(move |change, cx| {
+                callback(change, cx);
+                true
+            }),
+        );
+        activate();
+        subscription
+    }
+
+    #[cfg(target_os = "linux")]
+    fn watch_clock(&self) -> Result<Subscription> {
+        let timer = ClockSetTimer::new()?;
+        let fd = timer.0.as_fd().try_clone_to_owned()?;
+        self.watch_fd(fd, move |cx| {
+            if timer.was_set() {
+                notify_time_changed(TimeChange::Clock, cx);
+            }
+        })
+    }
+}
+
+fn notify_time_changed(change: TimeChange, cx: &mut App) {
+    let observers = cx.global::<TimeMonitor>().observers.clone();
+    observers.retain(&(), |callback| callback(change, cx));
+}
+
+async fn watch_timezone(cx: &mut AsyncApp) -> Result<()> {
+    let connection = cx.update(|cx| system_bus(cx))?.await?;
+    // timedated only runs while it's used, listen to its signals without activating it.
+    let properties = PropertiesProxy::builder(&connection)
+        .destination(TIMEDATE_SERVICE)?
+        .path(TIMEDATE_PATH)?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+    let mut changes = properties.receive_properties_changed().await?;
+
+    while let Some(signal) = changes.next().await {
+        let Some(args) = signal.args().log_err() else {
+            continue;
+        };
+        if args.interface_name().as_str() == TIMEDATE_INTERFACE
+            && (args.changed_properties().contains_key("Timezone")
+                || args.invalidated_properties().contains(&"Timezone"))
+        {
+            cx.update(|cx| notify_time_changed(TimeChange::Timezone, cx))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// A timerfd armed far in the future, which is canceled whenever the realtime clock is set.
+#[cfg(target_os = "linux")]
+struct ClockSetTimer(OwnedFd);
+
+#[cfg(target_os = "linux")]
+impl ClockSetTimer {
+    fn new() -> io::Result<Self> {
+        let fd = unsafe {
+            libc::timerfd_create(libc::CLOCK_REALTIME, libc::TFD_NONBLOCK | libc::TFD_CLOEXEC)
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let timer = Self(unsafe { OwnedFd::from_raw_fd(fd) });
+        let spec = libc::itimerspec {
+            it_interval: libc::timespec {
+                tv_sec: 0,
+                tv_nsec: 0,
+            },
+            it_value: libc::timespec {
+                tv_sec: libc::time_t::MAX,
+                tv_nsec: 0,
+            },
+        };
+        let result = unsafe {
+            libc::timerfd_settime(
+                timer.0.as_raw_fd(),
+                libc::TFD_TIMER_ABSTIME | libc::TFD_TIMER_CANCEL_ON_SET,
+                &spec,
+                ptr::null_mut(),
+            )
+        };
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(timer)
+    }
+
+    /// Drains the timer, returning whether the clock was set since the last call. The timer
+    /// stays armed once canceled.
+    fn was_set(&self) -> bool {
+        let mut expirations = 0u64;
+        let result = unsafe {
+            libc::read(
+                self.0.as_raw_fd(),
+                &mut expirations as *mut u64 as *mut libc::c_void,
+                mem::size_of::<u64>(),
+            )
+        };
+        result < 0 && io::Error::last_os_error().raw_os_error() == Some(libc::ECANCELED)
+    }
+}
@@ -0,0 +1,224 @@
+//! The battery and power state of the system from [UPower] and power-profiles-daemon, for
+//! battery widgets, see [`PowerSupply`].
+//!
+//! [UPower]: https://upower.freedesktop.org/docs/
+
+use futures::{stream, StreamExt};
+use zbus::{fdo::PropertiesProxy, proxy::CacheProperties, Connection};
+
+use crate::{system_bus, AsyncApp, Context, Result, Task, WeakEntity};
+
+const UPOWER_SERVICE: &str = "org.freedesktop.UPower";
+const UPOWER_PATH: &str = "/org/freedesktop/UPower";
+const DISPLAY_DEVICE_PATH: &str = "/org/freedesktop/UPower/devices/DisplayDevice";
+const POWER_PROFILES_SERVICE: &str = "org.freedesktop.UPower.PowerProfiles";
+const POWER_PROFILES_PATH: &str = "/org/freedesktop/UPower/PowerProfiles";
+const POWER_SAVER_PROFILE: &str = "power-saver";
+
+/// Whether the batteries are charging, see [`PowerState::charge_state`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChargeState {
+    /// The state is unknown, e.g. without a battery.
+    #[default]
+    Unknown,
+    /// The batteries are charging.
+    Charging,
+    /// The batteries are discharging.
+    Discharging,
+    /// The batteries are empty.
+    Empty,
+    /// The batteries are fully charged.
+    FullyCharged,
+    /// The batteries are waiting to charge.
+    PendingCharge,
+    /// The batteries are waiting to discharge.
+    PendingDischarge,
+}
+
+impl ChargeState {
+    fn from_upower(state: u32) -> Self {
+        match state {
+            1 => Self::Charging,
+            2 => Self::Discharging,
+            3 => Self::Empty,
+            4 => Self::FullyCharged,
+            5 => Self::PendingCharge,
+            6 => Self::PendingDischarge,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// The power state of the system, see [`PowerSupply::state`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PowerState {
+    /// The charge of the batteries combined, from 0 to 100, or `None` without a battery.
+    pub percentage: Option<f64>,
+    /// Whether the batteries are charging.
+    pub charge_state: ChargeState,
+    /// Whether the system runs on battery.
+    pub on_battery: bool,
+    /// Whether the power saver profile is active, i.e. the low power mode.
+    pub power_saver: bool,
+}
+
+impl PowerState {
+    /// Returns whether the system should be spared work, when it runs on battery or in the
+    /// low power mode.
+    pub fn should_save_power(&self) -> bool {
+        self.on_battery || self.power_saver
+    }
+
+    async fn fetch(
+        upower: &UPowerProxy<'_>,
+        device: &DeviceProxy<'_>,
+        power_profiles: &PowerProfilesProxy<'_>,
+    ) -> Self {
+        let is_present = device.is_present().await.unwrap_or(false);
+        Self {
+            percentage: if is_present {
+                device.percentage().await.ok()
+            } else {
+                None
+            },
+            charge_state: device
+                .state()
+                .await
+                .map_or(ChargeState::Unknown, ChargeState::from_upower),
+            on_battery: upower.on_battery().await.unwrap_or(false),
+            power_saver: power_profiles
+                .active_profile()
+                .await
+                .is_ok_and(|profile| profile == POWER_SAVER_PROFILE),
+        }
+    }
+}
+
+/// Tracks the power state of the system, see the [module documentation](self).
+///
+/// Create it with `cx.new(PowerSupply::new)` and observe it to learn about changes. While it
+/// exists, windows save power on battery and in the low power mode, see
+/// [`crate::App::set_power_saving`].
+pub struct PowerSupply {
+    state: PowerState,
+    _task: Task<()>,
+}
+
+impl PowerSupply {
+    /// Connects to the system bus and starts tracking the power state.
+    pub fn new(cx: &mut Context<Self>) -> Self {
+        let task = cx.spawn(|this, mut cx| async move {
+            if let Err(error) = Self::run(this, &mut cx).await {
+                log::error!("power state tracking failed: {error:#}");
+            }
+        });
+        cx.on_release(|this, cx| {
+            if this.state.should_save_power() {
+                cx.set_power_saving(false);
+            }
+        })
+        .detach();
+        Self {
+            state: PowerState::default(),
+            _task: task,
+        }
+    }
+
+    /// Returns the current power state.
+    pub fn state(&self) -> PowerState {
+        self.state
+    }
+
+    async fn run(this: WeakEntity<Self>, cx: &mut AsyncApp) -> Result<()> {
+        let connection = cx.update(|cx| system_bus(cx))?.await?;
+        let upower = UPowerProxy::builder(&connection)
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await?;
+        let device = DeviceProxy::builder(&connection)
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await?;
+        let power_profiles = PowerProfilesProxy::builder(&connection)
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await?;
+
+        let mut changes = stream::select_all([
+            properties_changed(&connection, UPOWER_SERVICE, UPOWER_PATH).await?,
+            properties_changed(&connection, UPOWER_SERVICE, DISPLAY_DEVICE_PATH).await?,
+            properties_changed(&connection, POWER_PROFILES_SERVICE, POWER_PROFILES_PATH).await?,
+        ]);
+
+        loop {
+            let state = PowerState::fetch(&upower, &device, &power_profiles).await;
+            this.update(cx, |this, cx| {
+                if this.state != state {
+                    this.state = state;
+                    cx.set_power_saving(state.should_save_power());
+                    cx.notify();
+                }
+            })?;
+            if changes.next().await.is_none() {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns a stream of the property changes of an object, which doesn't activate its service.
+async fn properties_changed(
+    connection: &Connection,
+    service: &'static str,
+    path: &'static str,
+) -> Result<stream::BoxStream<'static, ()>> {
+    let properties = PropertiesProxy::builder(connection)
+        .destination(service)?
+        .path(path)?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+    Ok(properties
+        .receive_properties_changed()
+        .await?
+        .map(|_| ())
+        .boxed())
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.UPower",
+    default_service = "org.freedesktop.UPower",
+    default_path = "/org/freedesktop/UPower"
+)]
+trait UPower {
+    #[zbus(property)]
+    fn on_battery(&self) -> zbus::Result<bool>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.UPower.Device",
+    default_service = "org.freedesktop.UPower",
+    default_path = "/org/freedesktop/UPower/devices/DisplayDevice"
+)]
+trait Device {
+    #[zbus(property)]
+    fn is_present(&self) -> zbus::Result<bool>;
+
+    #[zbus(property)]
+    fn percentage(&self) -> zbus::Result<f64>;
+
+    #[zbus(property)]
+    fn state(&self) -> zbus::Result<u32>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.UPower.PowerProfiles",
+    default_service = "org.freedesktop.UPower.PowerProfiles",
+    default_path = "/org/freedesktop/UPower/PowerProfiles"
+)]
+trait PowerProfiles {
+    #[zbus(property)]
+    fn active_profile(&self) -> zbus::Result<String>;
+}
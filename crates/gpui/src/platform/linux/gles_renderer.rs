@@ -0,0 +1,549 @@
+//! A renderer presenting through OpenGL ES, used when Vulkan isn't available, e.g. on older GPUs
+//! and drivers that only support GL. Scenes are painted with the CPU canvas, whose damaged rows
+//! are uploaded into a texture that the GPU then draws into the window's EGL surface.
+
+use std::{ffi::c_void, ptr, sync::Arc};
+
+use anyhow::{Context as _, Result};
+use glow::HasContext as _;
+use khronos_egl as egl;
+use util::ResultExt;
+
+use crate::{
+    platform::linux::canvas::{Canvas, CanvasAtlas},
+    scene::Damage,
+    DevicePixels, GpuSpecs, RenderStats, Scene, Size,
+};
+
+#[cfg(feature = "x11")]
+const EGL_PLATFORM_XCB_EXT: egl::Enum = 0x31DC;
+#[cfg(feature = "x11")]
+const EGL_PLATFORM_XCB_SCREEN_EXT: egl::Attrib = 0x31DE;
+#[cfg(feature = "wayland")]
+const EGL_PLATFORM_WAYLAND_KHR: egl::Enum = 0x31D8;
+
+/// A triangle covering the whole viewport.
+const FULLSCREEN_TRIANGLE: [f32; 6] = [-1., -1., 3., -1., -1., 3.];
+
+const VERTEX_SHADER: &str = "
+attribute vec2 a_position;
+varying vec2 v_uv;
+
+void main() {
+    // Rows of the frame go from top to bottom, GL's from bottom to top.
+    v_uv = vec2(a_position.x + 1.0, 1.0 - a_position.y) * 0.5;
+    gl_Position = vec4(a_position, 0.0, 1.0);
+}
+";
+
+const FRAGMENT_SHADER: &str = "
+precision mediump float;
+uniform sampler2D u_frame;
+varying vec2 v_uv;
+
+void main() {
+    gl_FragColor = texture2D(u_frame, v_uv);
+}
+";
+
+/// The native window a [`GlesRenderer`] presents to.
+pub(crate) enum GlesWindow {
+    #[cfg(feature = "x11")]
+    Xcb {
+        connection: *mut c_void,
+        screen: usize,
+        window: u32,
+        visual_id: u32,
+    },
+    #[cfg(feature = "wayland")]
+    Wayland {
+        display: *mut c_void,
+        surface: *mut c_void,
+    },
+}
+
+/// `wl_egl_window`, which gives EGL the size of the buffers of a Wayland surface.
+#[cfg(feature = "wayland")]
+struct WlEglWindow {
+    library: libloading::Library,
+    window: *mut c_void,
+}
+
+#[cfg(feature = "wayland")]
+impl WlEglWindow {
+    fn new(surface: *mut c_void, size: Size<DevicePixels>) -> Result<Self> {
+        let library = unsafe {
+            libloading::Library::new("libwayland-egl.so.1")
+                .or_else(|_| libloading::Library::new("libwayland-egl.so"))
+        }
+        .context("failed to load libwayland-egl")?;
+        let window = unsafe {
+            let create: libloading::Symbol<
+                unsafe extern "C" fn(*mut c_void, i32, i32) -> *mut c_void,
+            > = library.get(b"wl_egl_window_create")?;
+            create(surface, size.width.0.max(1), size.height.0.max(1))
+        };
+        anyhow::ensure!(!window.is_null(), "wl_egl_window_create failed");
+        Ok(Self { library, window })
+    }
+
+    fn resize(&self, size: Size<DevicePixels>) {
+        unsafe {
+            let resize: Option<
+                libloading::Symbol<unsafe extern "C" fn(*mut c_void, i32, i32, i32, i32)>,
+            > = self.library.get(b"wl_egl_window_resize").log_err();
+            if let Some(resize) = resize {
+                resize(self.window, size.width.0.max(1), size.height.0.max(1), 0, 0);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "wayland")]
+impl Drop for WlEglWindow {
+    fn drop(&mut self) {
+        unsafe {
+            let destroy: Option<libloading::Symbol<unsafe extern "C" fn(*mut c_void)>> =
+                self.library.get(b"wl_egl_window_destroy").log_err();
+            if let Some(destroy) = destroy {
+                destroy(self.window);
+            }
+        }
+    }
+}
+
+pub(crate) struct GlesRenderer {
+    egl: egl::DynamicInstance<egl::EGL1_5>,
+    display: egl::Display,
+    context: egl::Context,
+    surface: egl::Surface,
+    #[cfg(feature = "wayland")]
+    wl_egl_window: Option<WlEglWindow>,
+    gl: glow::Context,
+    program: glow::Program,
+    vertex_buffer: glow::Buffer,
+    texture: glow::Texture,
+    /// The size of `texture`, which only changes when a frame of another size is drawn.
+    texture_size: Size<DevicePixels>,
+    atlas: Arc<CanvasAtlas>,
+    size: Size<DevicePixels>,
+    canvas: Canvas,
+    needs_full_redraw: bool,
+    // Set while the canvas is released, see `release_resources`
+    released: bool,
+}
+
+impl GlesRenderer {
+    pub fn new(window: GlesWindow, size: Size<DevicePixels>) -> Result<Self> {
+        let egl = unsafe { egl::DynamicInstance::<egl::EGL1_5>::load_required() }
+            .context("failed to load libEGL 1.5")?;
+
+        let (platform, native_display, display_attributes) = match &window {
+            #[cfg(feature = "x11")]
+            GlesWindow::Xcb {
+                connection, screen, ..
+            } => (
+                EGL_PLATFORM_XCB_EXT,
+                *connection,
+                vec![EGL_PLATFORM_XCB_SCREEN_EXT, *screen, egl::ATTRIB_NONE],
+            ),
+            #[cfg(feature = "wayland")]
+            GlesWindow::Wayland { display, .. } => {
+                (EGL_PLATFORM_WAYLAND_KHR, *display, vec![egl::ATTRIB_NONE])
+            }
+        };
+        let display = unsafe {
+            egl.get_platform_display(platform, native_display, &display_attributes)
+                .context("eglGetPlatformDisplay failed")?
+        };
+        egl.initialize(display).context("eglInitialize failed")?;
+        egl.bind_api(egl::OPENGL_ES_API)?;
+
+        let mut configs = Vec::with_capacity(64);
+        egl.choose_config(
+            display,
+            &[
+                egl::SURFACE_TYPE,
+                egl::WINDOW_BIT,
+                egl::RENDERABLE_TYPE,
+                egl::OPENGL_ES2_BIT,
+                egl::RED_SIZE,
+                8,
+                egl::GREEN_SIZE,
+                8,
+                egl::BLUE_SIZE,
+                8,
+                egl::ALPHA_SIZE,
+                8,
+                egl::NONE,
+            ],
+            &mut configs,
+        )
+        .context("eglChooseConfig failed")?;
+        let config = match &window {
+            // X11 windows can only show configs of their own visual.
+            #[cfg(feature = "x11")]
+            GlesWindow::Xcb { visual_id, .. } => configs.iter().copied().find(|config| {
+                egl.get_config_attrib(display, *config, egl::NATIVE_VISUAL_ID)
+                    .is_ok_and(|id| id as u32 == *visual_id)
+            }),
+            #[cfg(feature = "wayland")]
+            GlesWindow::Wayland { .. } => configs.first().copied(),
+        }
+        .context("no EGL config can render to the window")?;
+        let context = egl
+            .create_context(
+                display,
+                config,
+                None,
+                &[egl::CONTEXT_CLIENT_VERSION, 2, egl::NONE],
+            )
+            .context("eglCreateContext failed")?;
+
+        #[cfg(feature = "wayland")]
+        let mut wl_egl_window = None;
+        let surface = match window {
+            #[cfg(feature = "x11")]
+            GlesWindow::Xcb { mut window, .. } => unsafe {
+                // The XCB platform takes a pointer to the window id.
+                egl.create_platform_window_surface(
+                    display,
+                    config,
+                    ptr::addr_of_mut!(window).cast(),
+                    &[egl::ATTRIB_NONE],
+                )
+            },
+            #[cfg(feature = "wayland")]
+            GlesWindow::Wayland { surface, .. } => {
+                let window = wl_egl_window.insert(WlEglWindow::new(surface, size)?);
+                unsafe {
+                    egl.create_platform_window_surface(
+                        display,
+                        config,
+                        window.window,
+                        &[egl::ATTRIB_NONE],
+                    )
+                }
+            }
+        };
+        let surface = match surface {
+            Ok(surface) => surface,
+            Err(err) => {
+                egl.destroy_context(display, context).log_err();
+                return Err(err).context("eglCreatePlatformWindowSurface failed");
+            }
+        };
+        egl.make_current(display, Some(surface), Some(surface), Some(context))
+            .context("eglMakeCurrent failed")?;
+        // gpui paces frames itself, swapping must not wait for the display.
+        egl.swap_interval(display, 0).log_err();
+
+        let gl = unsafe {
+            glow::Context::from_loader_function(|name| {
+                egl.get_proc_address(name)
+                    .map_or(ptr::null(), |function| function as *const c_void)
+            })
+        };
+        let (program, vertex_buffer, texture) = unsafe { create_resources(&gl)? };
+
+        Ok(Self {
+            egl,
+            display,
+            context,
+            surface,
+            #[cfg(feature = "wayland")]
+            wl_egl_window,
+            gl,
+            program,
+            vertex_buffer,
+            texture,
+            texture_size: Size::default(),
+            atlas: Arc::new(CanvasAtlas::new()),
+            size,
+            canvas: Canvas::new(size),
+            needs_full_redraw: true,
+            released: false,
+        })
+    }
+
+    pub fn update_drawable_size(&mut self, size: Size<DevicePixels>) {
+        if size != self.size {
+            self.size = size;
+            if !self.released {
+                self.canvas = Canvas::new(size);
+            }
+            // X11 windows resize their EGL surface themselves.
+            #[cfg(feature = "wayland")]
+            if let Some(window) = &self.wl_egl_window {
+                window.resize(size);
+            }
+            self.needs_full_redraw = true;
+        }
+    }
+
+    pub fn update_transparency(&mut self, _transparent: bool) {
+        // The frame always has an alpha channel, opaque windows simply paint every pixel.
+        self.needs_full_redraw = true;
+    }
+
+    #[cfg_attr(not(feature = "x11"), allow(dead_code))]
+    pub fn viewport_size(&self) -> Size<DevicePixels> {
+        self.size
+    }
+
+    /// Returns the area of the window that changes when drawing `scene`.
+    pub fn damage(&self, scene: &Scene) -> Damage {
+        if self.needs_full_redraw {
+            Damage::Full
+        } else {
+            scene.damage.clone()
+        }
+    }
+
+    pub fn sprite_atlas(&self) -> &Arc<CanvasAtlas> {
+        &self.atlas
+    }
+
+    pub fn render_stats(&self) -> RenderStats {
+        RenderStats {
+            atlas_bytes: self.atlas.memory_usage(),
+            ..Default::default()
+        }
+    }
+
+    pub fn gpu_specs(&self) -> GpuSpecs {
+        let (renderer, vendor, version) = unsafe {
+            (
+                self.gl.get_parameter_string(glow::RENDERER),
+                self.gl.get_parameter_string(glow::VENDOR),
+                self.gl.get_parameter_string(glow::VERSION),
+            )
+        };
+        GpuSpecs {
+            is_software_emulated: ["llvmpipe", "softpipe", "SwiftShader"]
+                .iter()
+                .any(|name| renderer.contains(name)),
+            device_name: renderer,
+            driver_name: vendor,
+            driver_info: version,
+        }
+    }
+
+    /// Frees the canvas and the frame texture of a hidden window. They are recreated on the
+    /// next draw.
+    #[cfg_attr(not(feature = "wayland"), allow(dead_code))]
+    pub fn release_resources(&mut self) {
+        if !self.released {
+            self.released = true;
+            self.canvas = Canvas::new(Size::default());
+            if self.make_current() {
+                unsafe {
+                    self.gl.bind_texture(glow::TEXTURE_2D, Some(self.texture));
+                    self.gl.tex_image_2d(
+                        glow::TEXTURE_2D,
+                        0,
+                        glow::RGBA as i32,
+                        1,
+                        1,
+                        0,
+                        glow::RGBA,
+                        glow::UNSIGNED_BYTE,
+                        None,
+                    );
+                }
+            }
+            self.texture_size = Size::default();
+            self.needs_full_redraw = true;
+        }
+    }
+
+    pub fn destroy(&mut self) {
+        if self.make_current() {
+            unsafe {
+                self.gl.delete_texture(self.texture);
+                self.gl.delete_buffer(self.vertex_buffer);
+                self.gl.delete_program(self.program);
+            }
+        }
+        self.egl
+            .make_current(self.display, None, None, None)
+            .log_err();
+        self.egl
+            .destroy_surface(self.display, self.surface)
+            .log_err();
+        self.egl
+            .destroy_context(self.display, self.context)
+            .log_err();
+        #[cfg(feature = "wayland")]
+        self.wl_egl_window.take();
+    }
+
+    pub fn draw(&mut self, scene: &Scene) {
+        let damage = self.damage(scene);
+        if damage == Damage::None {
+            return;
+        }
+        self.atlas.next_frame();
+        self.needs_full_redraw = false;
+        if std::mem::take(&mut self.released) {
+            self.canvas = Canvas::new(self.size);
+        }
+
+        self.canvas.draw(scene, &self.atlas);
+        if self.make_current() {
+            unsafe { self.upload(&damage) };
+            self.present().log_err();
+        }
+    }
+
+    /// Presents the last frame again after the window lost its contents, returning `false` if
+    /// there's no frame to present yet.
+    #[cfg_attr(not(feature = "x11"), allow(dead_code))]
+    pub fn expose(&mut self) -> bool {
+        if self.needs_full_redraw || !self.make_current() {
+            return false;
+        }
+        self.present().log_err();
+        true
+    }
+
+    fn make_current(&self) -> bool {
+        self.egl
+            .make_current(
+                self.display,
+                Some(self.surface),
+                Some(self.surface),
+                Some(self.context),
+            )
+            .context("eglMakeCurrent failed")
+            .log_err()
+            .is_some()
+    }
+
+    /// Uploads the rows of the canvas touched by `damage` into the frame texture.
+    unsafe fn upload(&mut self, damage: &Damage) {
+        let pixmap = self.canvas.pixmap();
+        let width = pixmap.width() as i32;
+        let height = pixmap.height() as i32;
+        let gl = &self.gl;
+        gl.bind_texture(glow::TEXTURE_2D, Some(self.texture));
+        let canvas_size = Size {
+            width: DevicePixels(width),
+            height: DevicePixels(height),
+        };
+        if self.texture_size != canvas_size {
+            self.texture_size = canvas_size;
+            gl.tex_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                glow::RGBA as i32,
+                width,
+                height,
+                0,
+                glow::RGBA,
+                glow::UNSIGNED_BYTE,
+                Some(pixmap.data()),
+            );
+            return;
+        }
+
+        // GLES 2 can't upload parts of rows, so whole rows are uploaded.
+        let (top, bottom) = match damage.bounds() {
+            Some(bounds) => (
+                (bounds.top().0.floor() as i32).clamp(0, height),
+                (bounds.bottom().0.ceil() as i32).clamp(0, height),
+            ),
+            None => (0, height),
+        };
+        if top >= bottom {
+            return;
+        }
+        let row_len = width as usize * 4;
+        gl.tex_sub_image_2d(
+            glow::TEXTURE_2D,
+            0,
+            0,
+            top,
+            width,
+            bottom - top,
+            glow::RGBA,
+            glow::UNSIGNED_BYTE,
+            glow::PixelUnpackData::Slice(
+                &pixmap.data()[top as usize * row_len..bottom as usize * row_len],
+            ),
+        );
+    }
+
+    /// Draws the frame texture into the back buffer, whose contents are undefined after each
+    /// swap, and swaps it in.
+    fn present(&self) -> Result<()> {
+        unsafe {
+            let gl = &self.gl;
+            gl.viewport(0, 0, self.size.width.0, self.size.height.0);
+            gl.use_program(Some(self.program));
+            gl.active_texture(glow::TEXTURE0);
+            gl.bind_texture(glow::TEXTURE_2D, Some(self.texture));
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(self.vertex_buffer));
+            gl.enable_vertex_attrib_array(0);
+            gl.vertex_attrib_pointer_f32(0, 2, glow::FLOAT, false, 0, 0);
+            gl.draw_arrays(glow::TRIANGLES, 0, 3);
+        }
+        self.egl
+            .swap_buffers(self.display, self.surface)
+            .context("eglSwapBuffers failed")
+    }
+}
+
+unsafe fn create_resources(
+    gl: &glow::Context,
+) -> Result<(glow::Program, glow::Buffer, glow::Texture)> {
+    let program = gl.create_program().map_err(anyhow::Error::msg)?;
+    for (kind, source) in [
+        (glow::VERTEX_SHADER, VERTEX_SHADER),
+        (glow::FRAGMENT_SHADER, FRAGMENT_SHADER),
+    ] {
+        let shader = gl.create_shader(kind).map_err(anyhow::Error::msg)?;
+        gl.shader_source(shader, source);
+        gl.compile_shader(shader);
+        let compiled = gl.get_shader_compile_status(shader);
+        if compiled {
+            gl.attach_shader(program, shader);
+        }
+        let log = gl.get_shader_info_log(shader);
+        gl.delete_shader(shader);
+        anyhow::ensure!(compiled, "failed to compile shader: {log}");
+    }
+    gl.bind_attrib_location(program, 0, "a_position");
+    gl.link_program(program);
+    anyhow::ensure!(
+        gl.get_program_link_status(program),
+        "failed to link program: {}",
+        gl.get_program_info_log(program)
+    );
+    gl.use_program(Some(program));
+    gl.uniform_1_i32(gl.get_uniform_location(program, "u_frame").as_ref(), 0);
+
+    let vertex_buffer = gl.create_buffer().map_err(anyhow::Error::msg)?;
+    gl.bind_buffer(glow::ARRAY_BUFFER, Some(vertex_buffer));
+    gl.buffer_data_u8_slice(
+        glow::ARRAY_BUFFER,
+        bytemuck::cast_slice(&FULLSCREEN_TRIANGLE),
+        glow::STATIC_DRAW,
+    );
+
+    // Frames have the size of the window, so they're neither filtered nor repeated.
+    let texture = gl.create_texture().map_err(anyhow::Error::msg)?;
+    gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+    for (parameter, value) in [
+        (glow::TEXTURE_MIN_FILTER, glow::NEAREST),
+        (glow::TEXTURE_MAG_FILTER, glow::NEAREST),
+        (glow::TEXTURE_WRAP_S, glow::CLAMP_TO_EDGE),
+        (glow::TEXTURE_WRAP_T, glow::CLAMP_TO_EDGE),
+    ] {
+        gl.tex_parameter_i32(glow::TEXTURE_2D, parameter, value as i32);
+    }
+    // Rows of the canvas are packed.
+    gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
+
+    Ok((program, vertex_buffer, texture))
+}
@@ -1,16 +1,18 @@
 use std::cell::RefCell;
+use std::os::fd::OwnedFd;
 use std::rc::Rc;
 
 use calloop::{EventLoop, LoopHandle};
 
-use util::ResultExt;
-
-use crate::platform::linux::LinuxClient;
+use crate::platform::linux::{watch_fd, LinuxClient};
 use crate::platform::{LinuxCommon, PlatformWindow};
-use crate::{AnyWindowHandle, CursorStyle, DisplayId, PlatformDisplay, WindowParams};
+use crate::{
+    AnyWindowHandle, CursorStyle, DisplayId, PlatformDisplay, Subscription, WindowCreationError,
+    WindowParams,
+};
 
 pub struct HeadlessClientState {
-    pub(crate) _loop_handle: LoopHandle<'static, HeadlessClient>,
+    pub(crate) loop_handle: LoopHandle<'static, HeadlessClient>,
     pub(crate) event_loop: Option<calloop::EventLoop<'static, HeadlessClient>>,
     pub(crate) common: LinuxCommon,
 }
@@ -36,7 +38,7 @@ impl HeadlessClient {
 
         HeadlessClient(Rc::new(RefCell::new(HeadlessClientState {
             event_loop: Some(event_loop),
-            _loop_handle: handle,
+            loop_handle: handle,
             common,
         })))
     }
@@ -75,10 +77,11 @@ impl LinuxClient for HeadlessClient {
         &self,
         _handle: AnyWindowHandle,
         _params: WindowParams,
-    ) -> anyhow::Result<Box<dyn PlatformWindow>> {
+    ) -> Result<Box<dyn PlatformWindow>, WindowCreationError> {
         Err(anyhow::anyhow!(
             "neither DISPLAY nor WAYLAND_DISPLAY is set. You can run in headless mode"
-        ))
+        )
+        .into())
     }
 
     fn compositor_name(&self) -> &'static str {
@@ -103,7 +106,11 @@ impl LinuxClient for HeadlessClient {
         None
     }
 
-    fn run(&self) {
+    fn watch_fd(&self, fd: OwnedFd, callback: Box<dyn FnMut()>) -> anyhow::Result<Subscription> {
+        watch_fd(&self.0.borrow().loop_handle, fd, callback)
+    }
+
+    fn run(&self) -> anyhow::Result<()> {
         let mut event_loop = self
             .0
             .borrow_mut()
@@ -111,6 +118,7 @@ impl LinuxClient for HeadlessClient {
             .take()
             .expect("App is already running");
 
-        event_loop.run(None, &mut self.clone(), |_| {}).log_err();
+        event_loop.run(None, &mut self.clone(), |_| {})?;
+        Ok(())
     }
 }
@@ -0,0 +1,138 @@
+use std::{
+    ffi::OsString,
+    io,
+    os::fd::{AsFd as _, OwnedFd},
+    path::PathBuf,
+};
+
+use anyhow::{Context as _, Result};
+use collections::{HashMap, HashSet};
+use inotify::{Inotify, WatchDescriptor, WatchMask};
+
+/// Watches files and directories with inotify, see [`crate::App::watch_paths`].
+///
+/// Files are watched through their directory, since editors and config tools usually save them
+/// by renaming a new file over the old one, which would end a watch on the file itself.
+pub(crate) struct PathWatcher {
+    inotify: Inotify,
+    /// The watched directories, with the names of the watched files in them, or `None` when
+    /// the whole directory is watched.
+    watches: HashMap<WatchDescriptor, (PathBuf, Option<HashSet<OsString>>)>,
+}
+
+impl PathWatcher {
+    pub(crate) fn new(paths: impl IntoIterator<Item = PathBuf>) -> Result<Self> {
+        let mut inotify = Inotify::init().context("failed to initialize inotify")?;
+        let mut watches =
+            HashMap::<WatchDescriptor, (PathBuf, Option<HashSet<OsString>>)>::default();
+        let mask = WatchMask::CLOSE_WRITE
+            | WatchMask::CREATE
+            | WatchMask::DELETE
+            | WatchMask::MOVED_FROM
+            | WatchMask::MOVED_TO
+            | WatchMask::DELETE_SELF
+            | WatchMask::MOVE_SELF;
+
+        for path in paths {
+            let (directory, name) = if path.is_dir() {
+                (path, None)
+            } else {
+                let name = path
+                    .file_name()
+                    .with_context(|| format!("can't watch {path:?}"))?
+                    .to_owned();
+                let directory = match path.parent() {
+                    Some(parent) if !parent.as_os_str().is_empty() => parent.to_owned(),
+                    _ => PathBuf::from("."),
+                };
+                (directory, Some(name))
+            };
+
+            let descriptor = inotify
+                .watches()
+                .add(&directory, mask)
+                .with_context(|| format!("failed to watch {directory:?}"))?;
+            // Adding a directory twice returns the same descriptor.
+            let (_, names) = watches
+                .entry(descriptor)
+                .or_insert_with(|| (directory, Some(HashSet::default())));
+            match (names.as_mut(), name) {
+                (Some(names), Some(name)) => {
+                    names.insert(name);
+                }
+                _ => *names = None,
+            }
+        }
+
+        Ok(Self { inotify, watches })
+    }
+
+    /// Returns a file descriptor that becomes readable when watched paths change.
+    pub(crate) fn fd(&self) -> Result<OwnedFd> {
+        Ok(self.inotify.as_fd().try_clone_to_owned()?)
+    }
+
+    /// Drains the pending inotify events, returning the watched paths they are about.
+    pub(crate) fn read_changes(&mut self) -> Vec<PathBuf> {
+        let mut buffer = [0; 4096];
+        let mut changes = Vec::new();
+        loop {
+            let events = match self.inotify.read_events(&mut buffer) {
+                Ok(events) => events,
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+                Err(error) => {
+                    log::error!("failed to read inotify events: {error}");
+                    break;
+                }
+            };
+
+            let mut drained = true;
+            for event in events {
+                drained = false;
+                let Some((directory, names)) = self.watches.get(&event.wd) else {
+                    continue;
+                };
+                let path = match event.name {
+                    Some(name) if names.as_ref().map_or(true, |names| names.contains(name)) => {
+                        directory.join(name)
+                    }
+                    Some(_) => continue,
+                    None => directory.clone(),
+                };
+                if !changes.contains(&path) {
+                    changes.push(path);
+                }
+            }
+            if drained {
+                break;
+            }
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PathWatcher;
+    use std::fs;
+
+    #[test]
+    fn test_watches_files_replaced_by_renaming() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("config.toml");
+        let other = dir.path().join("other.toml");
+        fs::write(&config, "a").unwrap();
+        let mut watcher = PathWatcher::new([config.clone()]).unwrap();
+        assert!(watcher.read_changes().is_empty());
+
+        // Changes to other files of the directory are ignored.
+        fs::write(&other, "b").unwrap();
+        assert!(watcher.read_changes().is_empty());
+
+        fs::rename(&other, &config).unwrap();
+        assert_eq!(watcher.read_changes(), [config.clone()]);
+
+        fs::write(&config, "c").unwrap();
+        assert_eq!(watcher.read_changes(), [config]);
+    }
+}
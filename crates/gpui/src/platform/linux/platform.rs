@@ -1,32 +1,39 @@
-use std::{
-    env,
-    path::{Path, PathBuf},
-    process::Command,
-    rc::Rc,
-    sync::Arc,
-};
 #[cfg(any(feature = "wayland", feature = "x11"))]
 use std::{
+    cell::RefMut,
     ffi::OsString,
     fs::File,
+    future::Future,
     io::Read as _,
     os::fd::{AsFd, AsRawFd, FromRawFd},
     time::Duration,
 };
+use std::{
+    env,
+    os::fd::OwnedFd,
+    path::{Path, PathBuf},
+    process::Command,
+    rc::Rc,
+    sync::Arc,
+};
 
 use anyhow::{anyhow, Context as _};
 use async_task::Runnable;
-use calloop::{channel::Channel, LoopSignal};
+use calloop::{
+    channel::Channel, generic::Generic, Interest, LoopHandle, LoopSignal, Mode, PostAction,
+};
 use futures::channel::oneshot;
 use util::ResultExt as _;
 #[cfg(any(feature = "wayland", feature = "x11"))]
 use xkbcommon::xkb::{self, Keycode, Keysym, State};
 
 use crate::{
-    px, Action, AnyWindowHandle, BackgroundExecutor, ClipboardItem, CursorStyle, DisplayId,
-    ForegroundExecutor, Keymap, LinuxDispatcher, Menu, MenuItem, OwnedMenu, PathPromptOptions,
-    Pixels, Platform, PlatformDisplay, PlatformTextSystem, PlatformWindow, Point, Result,
-    ScreenCaptureSource, Task, WindowAppearance, WindowParams,
+    px, Action, AnyWindowHandle, BackgroundExecutor, ClipboardItem, CursorStyle, DesktopWorkspace,
+    DesktopWorkspaceGroup, DesktopWorkspaceRequest, DisplayGamma, DisplayId, ForegroundExecutor,
+    Keymap, LinuxDispatcher, Menu, MenuItem, OutputConfiguration, OutputHead, OwnedMenu,
+    PathPromptOptions, Pixels, Platform, PlatformDisplay, PlatformTextSystem, PlatformWindow,
+    Point, RenderImage, Result, Rgba, ScreenCaptureSource, Subscription, Task,
+    VirtualKeyboardInput, VirtualPointerInput, WindowAppearance, WindowCreationError, WindowParams,
 };
 #[cfg(any(feature = "wayland", feature = "x11"))]
 pub(crate) const SCROLL_LINES: f32 = 3.0;
@@ -41,11 +48,22 @@ pub(crate) const KEYRING_LABEL: &str = "zed-github-account";
 #[cfg(any(feature = "wayland", feature = "x11"))]
 const FILE_PICKER_PORTAL_MISSING: &str =
     "Couldn't open file picker due to missing xdg-desktop-portal implementation.";
+const COLOR_PICKER_PORTAL_MISSING: &str =
+    "Couldn't pick a color due to missing xdg-desktop-portal implementation.";
+const SCREENSHOT_PORTAL_MISSING: &str =
+    "Couldn't capture a screenshot due to missing xdg-desktop-portal implementation.";
 
 pub trait LinuxClient {
     fn compositor_name(&self) -> &'static str;
     fn with_common<R>(&self, f: impl FnOnce(&mut LinuxCommon) -> R) -> R;
     fn keyboard_layout(&self) -> String;
+    fn keyboard_layout_index(&self) -> Option<usize> {
+        None
+    }
+    fn keyboard_layouts(&self) -> Vec<String> {
+        Vec::new()
+    }
+    fn set_gpu_device(&self, _device_id: Option<u32>) {}
     fn displays(&self) -> Vec<Rc<dyn PlatformDisplay>>;
     #[allow(unused)]
     fn display(&self, id: DisplayId) -> Option<Rc<dyn PlatformDisplay>>;
@@ -55,7 +73,7 @@ pub trait LinuxClient {
         &self,
         handle: AnyWindowHandle,
         options: WindowParams,
-    ) -> anyhow::Result<Box<dyn PlatformWindow>>;
+    ) -> Result<Box<dyn PlatformWindow>, WindowCreationError>;
     fn set_cursor_style(&self, style: CursorStyle);
     fn open_uri(&self, uri: &str);
     fn reveal_path(&self, path: PathBuf);
@@ -65,7 +83,72 @@ pub trait LinuxClient {
     fn read_from_clipboard(&self) -> Option<ClipboardItem>;
     fn active_window(&self) -> Option<AnyWindowHandle>;
     fn window_stack(&self) -> Option<Vec<AnyWindowHandle>>;
-    fn run(&self);
+    /// Identifies the active window to the desktop portals, so their dialogs are shown on top
+    /// of it.
+    #[cfg(any(feature = "wayland", feature = "x11"))]
+    fn window_identifier(&self) -> impl Future<Output = Option<ashpd::WindowIdentifier>> + 'static {
+        std::future::ready(None)
+    }
+    fn watch_fd(&self, fd: OwnedFd, callback: Box<dyn FnMut()>) -> Result<Subscription>;
+    fn output_heads(&self) -> Vec<OutputHead> {
+        Vec::new()
+    }
+    fn is_display_powered(&self, _display: DisplayId) -> Option<bool> {
+        None
+    }
+    fn set_display_powered(&self, _display: DisplayId, _powered: bool) -> Result<()> {
+        Err(anyhow!(
+            "turning displays on and off is not supported by {}",
+            self.compositor_name()
+        ))
+    }
+    fn desktop_workspaces(&self) -> Vec<DesktopWorkspace> {
+        Vec::new()
+    }
+    fn desktop_workspace_groups(&self) -> Vec<DesktopWorkspaceGroup> {
+        Vec::new()
+    }
+    fn update_desktop_workspaces(&self, _requests: Vec<DesktopWorkspaceRequest>) -> Result<()> {
+        Err(anyhow!(
+            "switching workspaces is not supported by {}",
+            self.compositor_name()
+        ))
+    }
+    fn send_virtual_pointer_input(&self, _inputs: Vec<VirtualPointerInput>) -> Result<()> {
+        Err(anyhow!(
+            "virtual pointers are not supported by {}",
+            self.compositor_name()
+        ))
+    }
+    fn send_virtual_keyboard_input(&self, _inputs: Vec<VirtualKeyboardInput>) -> Result<()> {
+        Err(anyhow!(
+            "virtual keyboards are not supported by {}",
+            self.compositor_name()
+        ))
+    }
+    fn display_gamma(&self, _display: DisplayId) -> Option<DisplayGamma> {
+        None
+    }
+    fn set_display_gamma(&self, _display: DisplayId, _gamma: Option<DisplayGamma>) -> Result<()> {
+        Err(anyhow!(
+            "adjusting the gamma of displays is not supported by {}",
+            self.compositor_name()
+        ))
+    }
+    fn configure_outputs(
+        &self,
+        _configuration: OutputConfiguration,
+        _test_only: bool,
+    ) -> oneshot::Receiver<Result<()>> {
+        let (tx, rx) = oneshot::channel();
+        tx.send(Err(anyhow!(
+            "configuring outputs is not supported by {}",
+            self.compositor_name()
+        )))
+        .ok();
+        rx
+    }
+    fn run(&self) -> anyhow::Result<()>;
 }
 
 #[derive(Default)]
@@ -77,6 +160,9 @@ pub(crate) struct PlatformHandlers {
     pub(crate) will_open_app_menu: Option<Box<dyn FnMut()>>,
     pub(crate) validate_app_menu_command: Option<Box<dyn FnMut(&dyn Action) -> bool>>,
     pub(crate) keyboard_layout_change: Option<Box<dyn FnMut()>>,
+    pub(crate) output_heads_change: Option<Box<dyn FnMut()>>,
+    pub(crate) desktop_workspaces_change: Option<Box<dyn FnMut()>>,
+    pub(crate) platform_error: Option<Box<dyn FnMut(anyhow::Error)>>,
 }
 
 pub(crate) struct LinuxCommon {
@@ -84,10 +170,15 @@ pub(crate) struct LinuxCommon {
     pub(crate) foreground_executor: ForegroundExecutor,
     pub(crate) text_system: Arc<dyn PlatformTextSystem>,
     pub(crate) appearance: WindowAppearance,
+    pub(crate) accent_color: Option<Rgba>,
     pub(crate) auto_hide_scrollbars: bool,
+    pub(crate) reduce_motion: bool,
     pub(crate) callbacks: PlatformHandlers,
     pub(crate) signal: LoopSignal,
     pub(crate) menus: Vec<OwnedMenu>,
+    /// Exports the menus for desktops with a global menu, set up by the client.
+    #[cfg(any(feature = "wayland", feature = "x11"))]
+    pub(crate) app_menu: Option<super::app_menu::AppMenuExporter>,
 }
 
 impl LinuxCommon {
@@ -110,16 +201,74 @@ impl LinuxCommon {
             foreground_executor: ForegroundExecutor::new(dispatcher.clone()),
             text_system,
             appearance: WindowAppearance::Light,
+            accent_color: None,
             auto_hide_scrollbars: false,
+            reduce_motion: false,
             callbacks,
             signal,
             menus: Vec::new(),
+            #[cfg(any(feature = "wayland", feature = "x11"))]
+            app_menu: None,
         };
 
         (common, main_receiver)
     }
 }
 
+/// Handles an event of the menus exported with [`super::app_menu::AppMenuExporter`], borrowing
+/// the client's [`LinuxCommon`] with `common`. The app's callbacks run without it borrowed, since
+/// they may call back into the platform.
+#[cfg(any(feature = "wayland", feature = "x11"))]
+pub(crate) fn handle_app_menu_event<'a>(
+    event: super::app_menu::AppMenuEvent,
+    common: impl Fn() -> RefMut<'a, LinuxCommon>,
+) {
+    use super::app_menu::AppMenuEvent;
+
+    match event {
+        AppMenuEvent::Exported(service) => {
+            if let Some(app_menu) = common().app_menu.as_mut() {
+                app_menu.set_service(service);
+            }
+        }
+        AppMenuEvent::WillOpen => {
+            let callback = common().callbacks.will_open_app_menu.take();
+            if let Some(mut callback) = callback {
+                callback();
+                common().callbacks.will_open_app_menu = Some(callback);
+            }
+        }
+        AppMenuEvent::Activated(action) => {
+            let callback = common().callbacks.app_menu_action.take();
+            if let Some(mut callback) = callback {
+                callback(action.as_ref());
+                common().callbacks.app_menu_action = Some(callback);
+            }
+        }
+    }
+}
+
+/// Registers `fd` with the event loop behind `loop_handle`, calling `callback` whenever
+/// it becomes readable. Dropping the returned subscription unregisters and closes it.
+pub(crate) fn watch_fd<T: 'static>(
+    loop_handle: &LoopHandle<'static, T>,
+    fd: OwnedFd,
+    mut callback: Box<dyn FnMut()>,
+) -> Result<Subscription> {
+    let token = loop_handle
+        .insert_source(
+            Generic::new(fd, Interest::READ, Mode::Level),
+            move |_, _, _| {
+                callback();
+                Ok(PostAction::Continue)
+            },
+        )
+        .map_err(|err| anyhow!("failed to watch file descriptor: {}", err.error))?;
+
+    let loop_handle = loop_handle.clone();
+    Ok(Subscription::new(move || loop_handle.remove(token)))
+}
+
 impl<P: LinuxClient + 'static> Platform for P {
     fn background_executor(&self) -> BackgroundExecutor {
         self.with_common(|common| common.background_executor.clone())
@@ -137,14 +286,57 @@ impl<P: LinuxClient + 'static> Platform for P {
         self.keyboard_layout()
     }
 
+    fn keyboard_layout_index(&self) -> Option<usize> {
+        self.keyboard_layout_index()
+    }
+
+    fn keyboard_layouts(&self) -> Vec<String> {
+        self.keyboard_layouts()
+    }
+
     fn on_keyboard_layout_change(&self, callback: Box<dyn FnMut()>) {
         self.with_common(|common| common.callbacks.keyboard_layout_change = Some(callback));
     }
 
+    fn watch_fd(&self, fd: OwnedFd, callback: Box<dyn FnMut()>) -> Result<Subscription> {
+        LinuxClient::watch_fd(self, fd, callback)
+    }
+
+    fn set_gpu_device(&self, device_id: Option<u32>) {
+        LinuxClient::set_gpu_device(self, device_id)
+    }
+
+    fn on_platform_error(&self, callback: Box<dyn FnMut(anyhow::Error)>) {
+        self.with_common(|common| common.callbacks.platform_error = Some(callback));
+    }
+
+    fn output_heads(&self) -> Vec<OutputHead> {
+        LinuxClient::output_heads(self)
+    }
+
+    fn on_output_heads_change(&self, callback: Box<dyn FnMut()>) {
+        self.with_common(|common| common.callbacks.output_heads_change = Some(callback));
+    }
+
+    fn configure_outputs(
+        &self,
+        configuration: OutputConfiguration,
+        test_only: bool,
+    ) -> oneshot::Receiver<Result<()>> {
+        LinuxClient::configure_outputs(self, configuration, test_only)
+    }
+
     fn run(&self, on_finish_launching: Box<dyn FnOnce()>) {
         on_finish_launching();
 
-        LinuxClient::run(self);
+        if let Err(error) = LinuxClient::run(self) {
+            let callback = self.with_common(|common| common.callbacks.platform_error.take());
+            if let Some(mut callback) = callback {
+                callback(error);
+            } else {
+                log::error!("{error:?}");
+            }
+        }
 
         let quit = self.with_common(|common| common.callbacks.quit.take());
         if let Some(mut fun) = quit {
@@ -230,6 +422,46 @@ impl<P: LinuxClient + 'static> Platform for P {
         self.displays()
     }
 
+    fn is_display_powered(&self, display: DisplayId) -> Option<bool> {
+        LinuxClient::is_display_powered(self, display)
+    }
+
+    fn set_display_powered(&self, display: DisplayId, powered: bool) -> Result<()> {
+        LinuxClient::set_display_powered(self, display, powered)
+    }
+
+    fn display_gamma(&self, display: DisplayId) -> Option<DisplayGamma> {
+        LinuxClient::display_gamma(self, display)
+    }
+
+    fn send_virtual_pointer_input(&self, inputs: Vec<VirtualPointerInput>) -> Result<()> {
+        LinuxClient::send_virtual_pointer_input(self, inputs)
+    }
+
+    fn send_virtual_keyboard_input(&self, inputs: Vec<VirtualKeyboardInput>) -> Result<()> {
+        LinuxClient::send_virtual_keyboard_input(self, inputs)
+    }
+
+    fn desktop_workspaces(&self) -> Vec<DesktopWorkspace> {
+        LinuxClient::desktop_workspaces(self)
+    }
+
+    fn desktop_workspace_groups(&self) -> Vec<DesktopWorkspaceGroup> {
+        LinuxClient::desktop_workspace_groups(self)
+    }
+
+    fn on_desktop_workspaces_change(&self, callback: Box<dyn FnMut()>) {
+        self.with_common(|common| common.callbacks.desktop_workspaces_change = Some(callback));
+    }
+
+    fn update_desktop_workspaces(&self, requests: Vec<DesktopWorkspaceRequest>) -> Result<()> {
+        LinuxClient::update_desktop_workspaces(self, requests)
+    }
+
+    fn set_display_gamma(&self, display: DisplayId, gamma: Option<DisplayGamma>) -> Result<()> {
+        LinuxClient::set_display_gamma(self, display, gamma)
+    }
+
     fn screen_capture_sources(
         &self,
     ) -> oneshot::Receiver<Result<Vec<Box<dyn ScreenCaptureSource>>>> {
@@ -238,6 +470,69 @@ impl<P: LinuxClient + 'static> Platform for P {
         rx
     }
 
+    fn capture_screenshot(
+        &self,
+        interactive: bool,
+    ) -> oneshot::Receiver<Result<Option<Arc<RenderImage>>>> {
+        let (done_tx, done_rx) = oneshot::channel();
+
+        #[cfg(not(any(feature = "wayland", feature = "x11")))]
+        let _ = (done_tx.send(Ok(None)), interactive);
+
+        #[cfg(any(feature = "wayland", feature = "x11"))]
+        let identifier = self.window_identifier();
+
+        #[cfg(any(feature = "wayland", feature = "x11"))]
+        let background_executor = self.background_executor();
+
+        #[cfg(any(feature = "wayland", feature = "x11"))]
+        self.foreground_executor()
+            .spawn(async move {
+                let request = match ashpd::desktop::screenshot::Screenshot::request()
+                    .identifier(identifier.await)
+                    .modal(true)
+                    .interactive(interactive)
+                    .send()
+                    .await
+                {
+                    Ok(request) => request,
+                    Err(err) => {
+                        let result = match err {
+                            ashpd::Error::PortalNotFound(_) => anyhow!(SCREENSHOT_PORTAL_MISSING),
+                            err => err.into(),
+                        };
+                        let _ = done_tx.send(Err(result));
+                        return;
+                    }
+                };
+
+                let path = match request.response() {
+                    Ok(response) => response.uri().to_file_path().ok(),
+                    Err(ashpd::Error::Response(_)) => {
+                        let _ = done_tx.send(Ok(None));
+                        return;
+                    }
+                    Err(e) => {
+                        let _ = done_tx.send(Err(e.into()));
+                        return;
+                    }
+                };
+                let Some(path) = path else {
+                    let _ = done_tx.send(Err(anyhow!("screenshot wasn't saved to a file")));
+                    return;
+                };
+
+                let result = background_executor
+                    .spawn(async move { load_screenshot(&path) })
+                    .await
+                    .map(Some);
+                let _ = done_tx.send(result);
+            })
+            .detach();
+
+        done_rx
+    }
+
     fn active_window(&self) -> Option<AnyWindowHandle> {
         self.active_window()
     }
@@ -250,7 +545,7 @@ impl<P: LinuxClient + 'static> Platform for P {
         &self,
         handle: AnyWindowHandle,
         options: WindowParams,
-    ) -> anyhow::Result<Box<dyn PlatformWindow>> {
+    ) -> Result<Box<dyn PlatformWindow>, WindowCreationError> {
         self.open_window(handle, options)
     }
 
@@ -271,6 +566,9 @@ impl<P: LinuxClient + 'static> Platform for P {
         #[cfg(not(any(feature = "wayland", feature = "x11")))]
         let _ = (done_tx.send(Ok(None)), options);
 
+        #[cfg(any(feature = "wayland", feature = "x11"))]
+        let identifier = self.window_identifier();
+
         #[cfg(any(feature = "wayland", feature = "x11"))]
         self.foreground_executor()
             .spawn(async move {
@@ -281,6 +579,7 @@ impl<P: LinuxClient + 'static> Platform for P {
                 };
 
                 let request = match ashpd::desktop::file_chooser::OpenFileRequest::default()
+                    .identifier(identifier.await)
                     .modal(true)
                     .title(title)
                     .multiple(options.multiple)
@@ -322,6 +621,9 @@ impl<P: LinuxClient + 'static> Platform for P {
         #[cfg(not(any(feature = "wayland", feature = "x11")))]
         let _ = (done_tx.send(Ok(None)), directory);
 
+        #[cfg(any(feature = "wayland", feature = "x11"))]
+        let identifier = self.window_identifier();
+
         #[cfg(any(feature = "wayland", feature = "x11"))]
         self.foreground_executor()
             .spawn({
@@ -329,6 +631,7 @@ impl<P: LinuxClient + 'static> Platform for P {
 
                 async move {
                     let request = match ashpd::desktop::file_chooser::SaveFileRequest::default()
+                        .identifier(identifier.await)
                         .modal(true)
                         .title("Save File")
                         .current_folder(directory)
@@ -365,6 +668,53 @@ impl<P: LinuxClient + 'static> Platform for P {
         done_rx
     }
 
+    fn pick_color(&self) -> oneshot::Receiver<Result<Option<Rgba>>> {
+        let (done_tx, done_rx) = oneshot::channel();
+
+        #[cfg(not(any(feature = "wayland", feature = "x11")))]
+        let _ = done_tx.send(Ok(None));
+
+        #[cfg(any(feature = "wayland", feature = "x11"))]
+        let identifier = self.window_identifier();
+
+        #[cfg(any(feature = "wayland", feature = "x11"))]
+        self.foreground_executor()
+            .spawn(async move {
+                let request = match ashpd::desktop::Color::pick()
+                    .identifier(identifier.await)
+                    .send()
+                    .await
+                {
+                    Ok(request) => request,
+                    Err(err) => {
+                        let result = match err {
+                            ashpd::Error::PortalNotFound(_) => {
+                                anyhow!(COLOR_PICKER_PORTAL_MISSING)
+                            }
+                            err => err.into(),
+                        };
+                        let _ = done_tx.send(Err(result));
+                        return;
+                    }
+                };
+
+                let result = match request.response() {
+                    Ok(color) => Ok(Some(Rgba {
+                        r: color.red() as f32,
+                        g: color.green() as f32,
+                        b: color.blue() as f32,
+                        a: 1.,
+                    })),
+                    Err(ashpd::Error::Response(_)) => Ok(None),
+                    Err(e) => Err(e.into()),
+                };
+                let _ = done_tx.send(result);
+            })
+            .detach();
+
+        done_rx
+    }
+
     fn can_select_mixed_files_and_dirs(&self) -> bool {
         // org.freedesktop.portal.FileChooser only supports "pick files" and "pick directories".
         false
@@ -423,9 +773,15 @@ impl<P: LinuxClient + 'static> Platform for P {
         Ok(exe_path)
     }
 
-    fn set_menus(&self, menus: Vec<Menu>, _keymap: &Keymap) {
+    fn set_menus(&self, menus: Vec<Menu>, keymap: &Keymap) {
         self.with_common(|common| {
             common.menus = menus.into_iter().map(|menu| menu.owned()).collect();
+            #[cfg(any(feature = "wayland", feature = "x11"))]
+            if let Some(app_menu) = &common.app_menu {
+                app_menu.set_menus(&common.menus, keymap);
+            }
+            #[cfg(not(any(feature = "wayland", feature = "x11")))]
+            let _ = keymap;
         })
     }
 
@@ -449,6 +805,10 @@ impl<P: LinuxClient + 'static> Platform for P {
         self.with_common(|common| common.auto_hide_scrollbars)
     }
 
+    fn should_reduce_motion(&self) -> bool {
+        self.with_common(|common| common.reduce_motion)
+    }
+
     fn write_credentials(&self, url: &str, username: &str, password: &[u8]) -> Task<Result<()>> {
         let url = url.to_string();
         let username = username.to_string();
@@ -518,6 +878,10 @@ impl<P: LinuxClient + 'static> Platform for P {
         self.with_common(|common| common.appearance)
     }
 
+    fn accent_color(&self) -> Option<Rgba> {
+        self.with_common(|common| common.accent_color)
+    }
+
     fn register_url_scheme(&self, _: &str) -> Task<anyhow::Result<()>> {
         Task::ready(Err(anyhow!("register_url_scheme unimplemented")))
     }
@@ -602,6 +966,59 @@ pub(super) fn reveal_path_internal(
         .detach();
 }
 
+/// Decodes a screenshot saved by the Screenshot portal. The file is left in place, as the portal
+/// may have saved it to the user's pictures.
+#[cfg(any(feature = "x11", feature = "wayland"))]
+fn load_screenshot(path: &Path) -> Result<Arc<RenderImage>> {
+    let mut data = image::open(path)
+        .with_context(|| format!("failed to load screenshot {path:?}"))?
+        .into_rgba8();
+
+    // Convert from RGBA to BGRA.
+    for pixel in data.chunks_exact_mut(4) {
+        pixel.swap(0, 2);
+    }
+
+    Ok(Arc::new(RenderImage::new([image::Frame::new(data)])))
+}
+
+/// The GPU context shared by a client's windows. It's created lazily so the device can still be
+/// changed before the first window is opened, without waking up the default GPU.
+#[cfg(any(feature = "wayland", feature = "x11"))]
+#[derive(Default)]
+pub(super) enum GpuContext {
+    #[default]
+    Uninitialized,
+    /// Creating the context failed, so windows render with OpenGL ES, or on the CPU when that
+    /// fails too, until the device is changed.
+    Failed,
+    Ready(crate::platform::blade::BladeContext),
+}
+
+#[cfg(any(feature = "wayland", feature = "x11"))]
+impl GpuContext {
+    /// Creates the context on the first call, or after a device change. `fallback` names what
+    /// windows render with instead when that fails.
+    pub(super) fn init(&mut self, device_id: Option<u32>, fallback: &str) {
+        if let Self::Uninitialized = self {
+            *self = match crate::platform::blade::BladeContext::new(device_id)
+                .with_context(|| format!("Unable to init GPU context, falling back to {fallback}"))
+                .log_err()
+            {
+                Some(context) => Self::Ready(context),
+                None => Self::Failed,
+            };
+        }
+    }
+
+    pub(super) fn get(&self) -> Option<&crate::platform::blade::BladeContext> {
+        match self {
+            Self::Ready(context) => Some(context),
+            Self::Uninitialized | Self::Failed => None,
+        }
+    }
+}
+
 #[allow(unused)]
 pub(super) fn is_within_click_distance(a: Point<Pixels>, b: Point<Pixels>) -> bool {
     let diff = a - b;
@@ -1,8 +1,21 @@
 mod client;
 mod clipboard;
 mod cursor;
+mod desktop_workspaces;
 mod display;
+pub mod dmabuf;
+pub mod dmabuf_feedback;
+pub mod notification_stack;
+mod output_gamma;
+mod output_management;
+mod output_power;
+mod protocols;
+mod security_context;
 mod serial;
+mod shm_renderer;
+pub mod subsurface;
+mod trace;
+mod virtual_input;
 pub mod window;
 
 pub(crate) use client::*;
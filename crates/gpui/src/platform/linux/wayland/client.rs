@@ -1,12 +1,15 @@
 use std::{
     cell::{RefCell, RefMut},
+    future::Future,
     hash::Hash,
-    os::fd::{AsRawFd, BorrowedFd},
+    ops::Range,
+    os::fd::{AsRawFd, BorrowedFd, OwnedFd},
     path::PathBuf,
     rc::{Rc, Weak},
     time::{Duration, Instant},
 };
 
+use anyhow::Context as _;
 use calloop::{
     timer::{TimeoutAction, Timer},
     EventLoop, LoopHandle,
@@ -14,6 +17,7 @@ use calloop::{
 use calloop_wayland_source::WaylandSource;
 use collections::HashMap;
 use filedescriptor::Pipe;
+use futures::channel::oneshot;
 
 use http_client::Url;
 use smallvec::SmallVec;
@@ -33,7 +37,7 @@ use wayland_client::{
     delegate_noop,
     protocol::{
         wl_buffer, wl_compositor, wl_keyboard, wl_pointer, wl_registry, wl_seat, wl_shm,
-        wl_shm_pool, wl_surface,
+        wl_shm_pool, wl_subcompositor, wl_subsurface, wl_surface, wl_touch,
     },
     Connection, Dispatch, Proxy, QueueHandle,
 };
@@ -43,6 +47,10 @@ use wayland_protocols::wp::cursor_shape::v1::client::{
 use wayland_protocols::wp::fractional_scale::v1::client::{
     wp_fractional_scale_manager_v1, wp_fractional_scale_v1,
 };
+use wayland_protocols::wp::linux_dmabuf::zv1::client::{
+    zwp_linux_dmabuf_feedback_v1, zwp_linux_dmabuf_v1,
+};
+use wayland_protocols::wp::presentation_time::client::{wp_presentation, wp_presentation_feedback};
 use wayland_protocols::wp::primary_selection::zv1::client::zwp_primary_selection_offer_v1::{
     self, ZwpPrimarySelectionOfferV1,
 };
@@ -61,59 +69,119 @@ use wayland_protocols::xdg::activation::v1::client::{xdg_activation_token_v1, xd
 use wayland_protocols::xdg::decoration::zv1::client::{
     zxdg_decoration_manager_v1, zxdg_toplevel_decoration_v1,
 };
-use wayland_protocols::xdg::shell::client::{xdg_surface, xdg_toplevel, xdg_wm_base};
+use wayland_protocols::xdg::shell::client::{
+    xdg_popup, xdg_positioner, xdg_surface, xdg_toplevel, xdg_wm_base,
+};
+use wayland_protocols::xdg::xdg_output::zv1::client::{zxdg_output_manager_v1, zxdg_output_v1};
+use wayland_protocols_plasma::appmenu::client::{
+    org_kde_kwin_appmenu, org_kde_kwin_appmenu_manager,
+};
 use wayland_protocols_plasma::blur::client::{org_kde_kwin_blur, org_kde_kwin_blur_manager};
+use wayland_protocols_wlr::gamma_control::v1::client::{
+    zwlr_gamma_control_manager_v1, zwlr_gamma_control_v1,
+};
 use wayland_protocols_wlr::layer_shell::v1::client::{zwlr_layer_shell_v1, zwlr_layer_surface_v1};
+use wayland_protocols_wlr::output_management::v1::client::{
+    zwlr_output_configuration_head_v1, zwlr_output_configuration_v1, zwlr_output_head_v1,
+    zwlr_output_manager_v1, zwlr_output_mode_v1,
+};
+use wayland_protocols_wlr::output_power_management::v1::client::{
+    zwlr_output_power_manager_v1, zwlr_output_power_v1,
+};
+use wayland_protocols_wlr::virtual_pointer::v1::client::{
+    zwlr_virtual_pointer_manager_v1, zwlr_virtual_pointer_v1,
+};
 use xkbcommon::xkb::ffi::XKB_KEYMAP_FORMAT_TEXT_V1;
 use xkbcommon::xkb::{self, Keycode, KEYMAP_COMPILE_NO_FLAGS};
 
 use super::display::WaylandDisplay;
-use super::window::{ImeInput, WaylandWindowStatePtr};
+use super::protocols::ext_background_effect_v1::{
+    ext_background_effect_manager_v1, ext_background_effect_surface_v1,
+};
+use super::protocols::ext_workspace_v1::{
+    ext_workspace_group_handle_v1, ext_workspace_handle_v1, ext_workspace_manager_v1,
+};
+use super::protocols::virtual_keyboard_unstable_v1::{
+    zwp_virtual_keyboard_manager_v1, zwp_virtual_keyboard_v1,
+};
+use super::protocols::xdg_toplevel_drag_v1::{xdg_toplevel_drag_manager_v1, xdg_toplevel_drag_v1};
+use super::security_context::{tag_connection, SecurityContext};
+use super::window::{ImeInput, WaylandWindowStatePtr, FRAME_CALLBACK_TIMEOUT};
 
 use crate::platform::linux::{
-    get_xkb_compose_state, is_within_click_distance, open_uri_internal, read_fd,
-    reveal_path_internal,
+    app_menu::{AppMenuEvent, AppMenuExporter, APP_MENU_PATH},
+    get_xkb_compose_state, handle_app_menu_event, is_within_click_distance, open_uri_internal,
+    read_fd, reveal_path_internal, watch_fd,
     wayland::{
         clipboard::{Clipboard, DataOffer, FILE_LIST_MIME_TYPE, TEXT_MIME_TYPE},
         cursor::Cursor,
+        desktop_workspaces::DesktopWorkspaces,
+        output_gamma::OutputGammas,
+        output_management::OutputHeads,
+        output_power::OutputPowers,
         serial::{SerialKind, SerialTracker},
+        trace,
+        virtual_input::{VirtualKeyboard, VirtualPointer},
         window::WaylandWindow,
     },
     xdg_desktop_portal::{Event as XDPEvent, XDPEventSource},
-    LinuxClient,
+    GpuContext, LinuxClient,
 };
-use crate::platform::{blade::BladeContext, PlatformWindow};
+use crate::platform::PlatformWindow;
 use crate::{
-    point, px, size, AnyWindowHandle, Bounds, CursorStyle, DevicePixels, DisplayId, FileDropEvent,
-    ForegroundExecutor, KeyDownEvent, KeyUpEvent, Keystroke, LinuxCommon, Modifiers,
-    ModifiersChangedEvent, MouseButton, MouseDownEvent, MouseExitEvent, MouseMoveEvent,
-    MouseUpEvent, NavigationDirection, Pixels, PlatformDisplay, PlatformInput, Point, ScaledPixels,
-    ScrollDelta, ScrollWheelEvent, Size, TouchPhase, WindowParams, DOUBLE_CLICK_INTERVAL,
-    SCROLL_LINES,
+    point, px, size, AnyWindowHandle, Bounds, CursorStyle, DesktopWorkspace, DesktopWorkspaceGroup,
+    DesktopWorkspaceRequest, DevicePixels, DisplayGamma, DisplayId, FileDropEvent,
+    ForegroundExecutor, KeyDownEvent, KeyRepeatSettings, KeyUpEvent, Keystroke, LinuxCommon,
+    Modifiers, ModifiersChangedEvent, MouseButton, MouseDownEvent, MouseExitEvent, MouseMoveEvent,
+    MouseUpEvent, NavigationDirection, OutputConfiguration, OutputHead, OutputMode, Pixels,
+    PlatformDisplay, PlatformInput, Point, ScrollDelta, ScrollWheelEvent, SeatId, Size,
+    Subscription, TouchPhase, VirtualKeyboardInput, VirtualPointerInput, WindowCreationError,
+    WindowParams, DOUBLE_CLICK_INTERVAL, SCROLL_LINES,
 };
 
 /// Used to convert evdev scancode to xkb scancode
 const MIN_KEYCODE: u32 = 8;
 
+/// Shown as the marked text for the compose key while a compose sequence is typed.
+const COMPOSE_KEY_PRE_EDIT: char = '·';
+
+/// Offered by the drags of [`WaylandClientStatePtr::start_toplevel_drag`], which carry no data.
+const TOPLEVEL_DRAG_MIME_TYPE: &str = "application/x-gpui-toplevel-drag";
+
 #[derive(Clone)]
 pub struct Globals {
     pub qh: QueueHandle<WaylandClientStatePtr>,
     pub activation: Option<xdg_activation_v1::XdgActivationV1>,
     pub compositor: wl_compositor::WlCompositor,
+    pub subcompositor: Option<wl_subcompositor::WlSubcompositor>,
     pub cursor_shape_manager: Option<wp_cursor_shape_manager_v1::WpCursorShapeManagerV1>,
     pub data_device_manager: Option<wl_data_device_manager::WlDataDeviceManager>,
     pub primary_selection_manager:
         Option<zwp_primary_selection_device_manager_v1::ZwpPrimarySelectionDeviceManagerV1>,
     pub wm_base: xdg_wm_base::XdgWmBase,
-    pub layer_shell: zwlr_layer_shell_v1::ZwlrLayerShellV1,
+    pub layer_shell: Option<zwlr_layer_shell_v1::ZwlrLayerShellV1>,
     pub shm: wl_shm::WlShm,
-    pub seat: wl_seat::WlSeat,
     pub viewporter: Option<wp_viewporter::WpViewporter>,
+    pub linux_dmabuf: Option<zwp_linux_dmabuf_v1::ZwpLinuxDmabufV1>,
     pub fractional_scale_manager:
         Option<wp_fractional_scale_manager_v1::WpFractionalScaleManagerV1>,
     pub decoration_manager: Option<zxdg_decoration_manager_v1::ZxdgDecorationManagerV1>,
     pub blur_manager: Option<org_kde_kwin_blur_manager::OrgKdeKwinBlurManager>,
+    pub appmenu_manager: Option<org_kde_kwin_appmenu_manager::OrgKdeKwinAppmenuManager>,
+    pub background_effect_manager:
+        Option<ext_background_effect_manager_v1::ExtBackgroundEffectManagerV1>,
     pub text_input_manager: Option<zwp_text_input_manager_v3::ZwpTextInputManagerV3>,
+    pub output_manager: Option<zwlr_output_manager_v1::ZwlrOutputManagerV1>,
+    pub output_power_manager: Option<zwlr_output_power_manager_v1::ZwlrOutputPowerManagerV1>,
+    pub gamma_control_manager: Option<zwlr_gamma_control_manager_v1::ZwlrGammaControlManagerV1>,
+    pub workspace_manager: Option<ext_workspace_manager_v1::ExtWorkspaceManagerV1>,
+    pub virtual_pointer_manager:
+        Option<zwlr_virtual_pointer_manager_v1::ZwlrVirtualPointerManagerV1>,
+    pub virtual_keyboard_manager:
+        Option<zwp_virtual_keyboard_manager_v1::ZwpVirtualKeyboardManagerV1>,
+    pub xdg_output_manager: Option<zxdg_output_manager_v1::ZxdgOutputManagerV1>,
+    pub presentation: Option<wp_presentation::WpPresentation>,
+    pub toplevel_drag_manager: Option<xdg_toplevel_drag_manager_v1::XdgToplevelDragManagerV1>,
     pub executor: ForegroundExecutor,
 }
 
@@ -122,7 +190,6 @@ impl Globals {
         globals: GlobalList,
         executor: ForegroundExecutor,
         qh: QueueHandle<WaylandClientStatePtr>,
-        seat: wl_seat::WlSeat,
     ) -> Self {
         Globals {
             activation: globals.bind(&qh, 1..=1, ()).ok(),
@@ -134,6 +201,7 @@ impl Globals {
                     (),
                 )
                 .unwrap(),
+            subcompositor: globals.bind(&qh, 1..=1, ()).ok(),
             cursor_shape_manager: globals.bind(&qh, 1..=1, ()).ok(),
             data_device_manager: globals
                 .bind(
@@ -144,14 +212,31 @@ impl Globals {
                 .ok(),
             primary_selection_manager: globals.bind(&qh, 1..=1, ()).ok(),
             shm: globals.bind(&qh, 1..=1, ()).unwrap(),
-            seat,
             wm_base: globals.bind(&qh, 2..=5, ()).unwrap(),
-            layer_shell: globals.bind(&qh, 1..=1, ()).unwrap(),
+            layer_shell: globals.bind(&qh, 1..=2, ()).ok(),
             viewporter: globals.bind(&qh, 1..=1, ()).ok(),
+            linux_dmabuf: globals
+                .bind(
+                    &qh,
+                    3..=zwp_linux_dmabuf_v1::REQ_GET_SURFACE_FEEDBACK_SINCE,
+                    (),
+                )
+                .ok(),
             fractional_scale_manager: globals.bind(&qh, 1..=1, ()).ok(),
             decoration_manager: globals.bind(&qh, 1..=1, ()).ok(),
             blur_manager: globals.bind(&qh, 1..=1, ()).ok(),
+            appmenu_manager: globals.bind(&qh, 1..=1, ()).ok(),
+            background_effect_manager: globals.bind(&qh, 1..=1, ()).ok(),
             text_input_manager: globals.bind(&qh, 1..=1, ()).ok(),
+            output_manager: globals.bind(&qh, 1..=3, ()).ok(),
+            output_power_manager: globals.bind(&qh, 1..=1, ()).ok(),
+            gamma_control_manager: globals.bind(&qh, 1..=1, ()).ok(),
+            workspace_manager: globals.bind(&qh, 1..=1, ()).ok(),
+            virtual_pointer_manager: globals.bind(&qh, 1..=2, ()).ok(),
+            virtual_keyboard_manager: globals.bind(&qh, 1..=1, ()).ok(),
+            xdg_output_manager: globals.bind(&qh, 1..=3, ()).ok(),
+            presentation: globals.bind(&qh, 1..=1, ()).ok(),
+            toplevel_drag_manager: globals.bind(&qh, 1..=1, ()).ok(),
             executor,
             qh,
         }
@@ -164,6 +249,9 @@ pub struct InProgressOutput {
     scale: Option<i32>,
     position: Option<Point<DevicePixels>>,
     size: Option<Size<DevicePixels>>,
+    mode: Option<OutputMode>,
+    logical_position: Option<Point<Pixels>>,
+    logical_size: Option<Size<Pixels>>,
 }
 
 impl InProgressOutput {
@@ -174,6 +262,11 @@ impl InProgressOutput {
                 name: self.name.clone(),
                 scale,
                 bounds: Bounds::new(position, size),
+                mode: self.mode,
+                logical_bounds: self
+                    .logical_position
+                    .zip(self.logical_size)
+                    .map(|(position, size)| Bounds::new(position, size)),
             })
         } else {
             None
@@ -181,40 +274,105 @@ impl InProgressOutput {
     }
 }
 
+impl From<&Output> for InProgressOutput {
+    fn from(output: &Output) -> Self {
+        Self {
+            name: output.name.clone(),
+            scale: Some(output.scale),
+            position: Some(output.bounds.origin),
+            size: Some(output.bounds.size),
+            mode: output.mode,
+            logical_position: output.logical_bounds.map(|bounds| bounds.origin),
+            logical_size: output.logical_bounds.map(|bounds| bounds.size),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct Output {
     pub name: Option<String>,
     pub scale: i32,
     pub bounds: Bounds<DevicePixels>,
+    /// The current mode of the output.
+    pub mode: Option<OutputMode>,
+    /// The area of the global compositor space the output shows, from xdg-output.
+    pub logical_bounds: Option<Bounds<Pixels>>,
+}
+
+impl Output {
+    /// Returns the bounds of the output in the global compositor space. Without xdg-output, they
+    /// are derived from the mode and the scale, which is wrong for rotated outputs and outputs
+    /// with fractional scales.
+    pub fn logical_bounds(&self) -> Bounds<Pixels> {
+        self.logical_bounds
+            .unwrap_or_else(|| self.bounds.to_pixels(self.scale as f32))
+    }
 }
 
 pub(crate) struct WaylandClientState {
-    serial_tracker: SerialTracker,
     globals: Globals,
-    gpu_context: BladeContext,
-    wl_seat: wl_seat::WlSeat, // TODO: Multi seat support
+    _security_context: Option<SecurityContext>,
+    gpu_context: GpuContext,
+    gpu_device: Option<u32>,
+    // Seat to input state mapping
+    seats: HashMap<ObjectId, SeatState>,
+    // The seat which delivered the most recent input event
+    active_seat: Option<ObjectId>,
+    // Surface to Window mapping
+    windows: HashMap<ObjectId, WaylandWindowStatePtr>,
+    // Output to scale mapping
+    outputs: HashMap<ObjectId, Output>,
+    in_progress_outputs: HashMap<ObjectId, InProgressOutput>,
+    drag: DragState,
+    toplevel_drag: Option<ToplevelDrag>,
+    loop_handle: LoopHandle<'static, WaylandClientStatePtr>,
+    cursor_style: Option<CursorStyle>,
+    clipboard: Clipboard,
+    data_offers: Vec<DataOffer<WlDataOffer>>,
+    primary_data_offer: Option<DataOffer<ZwpPrimarySelectionOfferV1>>,
+    cursor: Cursor,
+    pending_activation: Option<PendingActivation>,
+    /// Whether the compositor advertised blur with ext-background-effect.
+    background_effect_blur: bool,
+    output_heads: OutputHeads,
+    output_powers: OutputPowers,
+    output_gammas: OutputGammas,
+    desktop_workspaces: DesktopWorkspaces,
+    /// Created on the first virtual pointer input.
+    virtual_pointer: Option<VirtualPointer>,
+    /// Created on the first virtual keyboard input.
+    virtual_keyboard: Option<VirtualKeyboard>,
+    event_loop: Option<EventLoop<'static, WaylandClientStatePtr>>,
+    common: LinuxCommon,
+}
+
+/// Input state for a single `wl_seat`.
+///
+/// Every seat has its own keyboard focus, pointer focus, keymap and serials, so that
+/// multiple users (or input-leap style setups) can interact with our windows independently.
+pub(crate) struct SeatState {
+    /// The registry name of the global, used to handle its removal
+    global_name: u32,
+    wl_seat: wl_seat::WlSeat,
     wl_pointer: Option<wl_pointer::WlPointer>,
     wl_keyboard: Option<wl_keyboard::WlKeyboard>,
+    wl_touch: Option<wl_touch::WlTouch>,
     cursor_shape_device: Option<wp_cursor_shape_device_v1::WpCursorShapeDeviceV1>,
     data_device: Option<wl_data_device::WlDataDevice>,
     primary_selection: Option<zwp_primary_selection_device_v1::ZwpPrimarySelectionDeviceV1>,
     text_input: Option<zwp_text_input_v3::ZwpTextInputV3>,
+    serial_tracker: SerialTracker,
     pre_edit_text: Option<String>,
-    ime_pre_edit: Option<String>,
+    /// The preedit text of text-input, with the UTF-16 range of its cursor.
+    ime_pre_edit: Option<(String, Option<Range<usize>>)>,
     composing: bool,
-    // Surface to Window mapping
-    windows: HashMap<ObjectId, WaylandWindowStatePtr>,
-    // Output to scale mapping
-    outputs: HashMap<ObjectId, Output>,
-    in_progress_outputs: HashMap<ObjectId, InProgressOutput>,
     keymap_state: Option<xkb::State>,
     compose_state: Option<xkb::compose::State>,
-    drag: DragState,
     click: ClickState,
     repeat: KeyRepeat,
-    pub modifiers: Modifiers,
+    modifiers: Modifiers,
     axis_source: AxisSource,
-    pub mouse_location: Option<Point<Pixels>>,
+    mouse_location: Option<Point<Pixels>>,
     continuous_scroll_delta: Option<Point<Pixels>>,
     discrete_scroll_delta: Option<Point<f32>>,
     vertical_modifier: f32,
@@ -224,15 +382,172 @@ pub(crate) struct WaylandClientState {
     button_pressed: Option<MouseButton>,
     mouse_focused_window: Option<WaylandWindowStatePtr>,
     keyboard_focused_window: Option<WaylandWindowStatePtr>,
-    loop_handle: LoopHandle<'static, WaylandClientStatePtr>,
-    cursor_style: Option<CursorStyle>,
-    clipboard: Clipboard,
-    data_offers: Vec<DataOffer<WlDataOffer>>,
-    primary_data_offer: Option<DataOffer<ZwpPrimarySelectionOfferV1>>,
-    cursor: Cursor,
-    pending_activation: Option<PendingActivation>,
-    event_loop: Option<EventLoop<'static, WaylandClientStatePtr>>,
-    common: LinuxCommon,
+    /// The first finger on the touchscreen, which is handled like the left mouse button.
+    primary_touch: Option<PrimaryTouch>,
+}
+
+struct PrimaryTouch {
+    id: i32,
+    window: WaylandWindowStatePtr,
+    position: Point<Pixels>,
+}
+
+impl SeatState {
+    fn new(global_name: u32, wl_seat: wl_seat::WlSeat, globals: &Globals) -> Self {
+        let data_device = globals
+            .data_device_manager
+            .as_ref()
+            .map(|data_device_manager| {
+                data_device_manager.get_data_device(&wl_seat, &globals.qh, wl_seat.id())
+            });
+
+        let primary_selection =
+            globals
+                .primary_selection_manager
+                .as_ref()
+                .map(|primary_selection_manager| {
+                    primary_selection_manager.get_device(&wl_seat, &globals.qh, wl_seat.id())
+                });
+
+        Self {
+            global_name,
+            wl_seat,
+            wl_pointer: None,
+            wl_keyboard: None,
+            wl_touch: None,
+            cursor_shape_device: None,
+            data_device,
+            primary_selection,
+            text_input: None,
+            serial_tracker: SerialTracker::new(),
+            pre_edit_text: None,
+            ime_pre_edit: None,
+            composing: false,
+            keymap_state: None,
+            compose_state: None,
+            click: ClickState {
+                last_click: Instant::now(),
+                last_mouse_button: None,
+                last_location: Point::default(),
+                current_count: 0,
+            },
+            repeat: KeyRepeat {
+                characters_per_second: 16,
+                delay: Duration::from_millis(500),
+                current_id: 0,
+                current_keycode: None,
+            },
+            modifiers: Modifiers {
+                shift: false,
+                control: false,
+                alt: false,
+                function: false,
+                platform: false,
+            },
+            scroll_event_received: false,
+            axis_source: AxisSource::Wheel,
+            mouse_location: None,
+            continuous_scroll_delta: None,
+            discrete_scroll_delta: None,
+            vertical_modifier: -1.0,
+            horizontal_modifier: -1.0,
+            button_pressed: None,
+            enter_token: None,
+            mouse_focused_window: None,
+            keyboard_focused_window: None,
+            primary_touch: None,
+        }
+    }
+
+    /// Lifts the first finger on the touchscreen, returning its window with the mouse up to
+    /// dispatch to it.
+    fn release_primary_touch(&mut self) -> Option<(WaylandWindowStatePtr, PlatformInput)> {
+        let touch = self.primary_touch.take()?;
+        let input = PlatformInput::MouseUp(MouseUpEvent {
+            button: MouseButton::Left,
+            position: touch.position,
+            modifiers: self.modifiers,
+            click_count: 1,
+        });
+        Some((touch.window, input))
+    }
+
+    fn release(&self) {
+        if let Some(wl_pointer) = &self.wl_pointer {
+            wl_pointer.release();
+        }
+        if let Some(wl_keyboard) = &self.wl_keyboard {
+            wl_keyboard.release();
+        }
+        if let Some(wl_touch) = &self.wl_touch {
+            wl_touch.release();
+        }
+        if let Some(cursor_shape_device) = &self.cursor_shape_device {
+            cursor_shape_device.destroy();
+        }
+        if let Some(data_device) = &self.data_device {
+            data_device.release();
+        }
+        if let Some(primary_selection) = &self.primary_selection {
+            primary_selection.destroy();
+        }
+        if let Some(text_input) = &self.text_input {
+            text_input.destroy();
+        }
+        if self.wl_seat.version() >= wl_seat::REQ_RELEASE_SINCE {
+            self.wl_seat.release();
+        }
+    }
+}
+
+impl WaylandClientState {
+    /// Returns the seat which delivered the most recent input event, falling back
+    /// to any known seat.
+    fn active_seat(&self) -> Option<&SeatState> {
+        self.active_seat
+            .as_ref()
+            .and_then(|id| self.seats.get(id))
+            .or_else(|| self.seats.values().next())
+    }
+
+    fn active_seat_mut(&mut self) -> Option<&mut SeatState> {
+        let id = self
+            .active_seat
+            .clone()
+            .filter(|id| self.seats.contains_key(id))
+            .or_else(|| self.seats.keys().next().cloned())?;
+        self.seats.get_mut(&id)
+    }
+
+    /// Returns the state the changes to the output are collected in until they're done. Changes
+    /// after the initial state are applied to the current state of the output.
+    fn in_progress_output(&mut self, id: &ObjectId) -> Option<&mut InProgressOutput> {
+        match self.outputs.get(id) {
+            Some(current) => Some(
+                self.in_progress_outputs
+                    .entry(id.clone())
+                    .or_insert_with(|| current.into()),
+            ),
+            None => self.in_progress_outputs.get_mut(id),
+        }
+    }
+
+    fn output_done(&mut self, id: &ObjectId) {
+        if let Some(complete) = self
+            .in_progress_outputs
+            .remove(id)
+            .and_then(|output| output.complete())
+        {
+            self.outputs.insert(id.clone(), complete);
+        }
+    }
+}
+
+/// Requests the logical geometry of the output, which is sent to the output's dispatcher.
+fn get_xdg_output(output: &wl_output::WlOutput, globals: &Globals) {
+    if let Some(manager) = &globals.xdg_output_manager {
+        manager.get_xdg_output(output, &globals.qh, output.id());
+    }
 }
 
 pub struct DragState {
@@ -241,6 +556,24 @@ pub struct DragState {
     position: Point<Pixels>,
 }
 
+/// A drag started with [`WaylandClientStatePtr::start_toplevel_drag`], which moves a window.
+struct ToplevelDrag {
+    data_source: wl_data_source::WlDataSource,
+    toplevel_drag: xdg_toplevel_drag_v1::XdgToplevelDragV1,
+    ended: oneshot::Sender<()>,
+}
+
+impl ToplevelDrag {
+    fn end(self) {
+        self.toplevel_drag.destroy();
+        self.data_source.destroy();
+        self.ended.send(()).ok();
+    }
+}
+
+/// The user data of the data sources of toplevel drags, which offer no data.
+struct ToplevelDragSource;
+
 pub struct ClickState {
     last_mouse_button: Option<MouseButton>,
     last_click: Instant,
@@ -276,8 +609,119 @@ impl WaylandClientStatePtr {
             .expect("The pointer should always be valid when dispatching in wayland")
     }
 
-    pub fn get_serial(&self, kind: SerialKind) -> u32 {
-        self.0.upgrade().unwrap().borrow().serial_tracker.get(kind)
+    /// Returns the active seat together with its latest serial of the given kinds of input.
+    pub fn latest_input_serial(
+        &self,
+        kinds: &[InputSerialKind],
+    ) -> Option<(wl_seat::WlSeat, InputSerial)> {
+        let client = self.get_client();
+        let state = client.borrow();
+        let seat = state.active_seat()?;
+        let (kind, serial) = seat.serial_tracker.latest(kinds)?;
+        Some((
+            seat.wl_seat.clone(),
+            InputSerial {
+                seat: SeatId(seat.wl_seat.id().protocol_id()),
+                kind,
+                serial,
+            },
+        ))
+    }
+
+    /// Whether windows can blur their background, with either the KDE blur protocol or
+    /// ext-background-effect.
+    pub fn background_blur_supported(&self) -> bool {
+        let client = self.get_client();
+        let state = client.borrow();
+        state.globals.blur_manager.is_some() || state.background_effect_blur
+    }
+
+    /// Starts a drag with the pointer button held down in a window, which moves the given toplevel
+    /// along with the pointer until the button is released, `offset` being the position of the
+    /// pointer in it. Returns a receiver that's notified when the drag ends, or `None` without
+    /// xdg-toplevel-drag or a pressed button.
+    pub fn start_toplevel_drag(
+        &self,
+        toplevel: &xdg_toplevel::XdgToplevel,
+        offset: Point<Pixels>,
+    ) -> Option<oneshot::Receiver<()>> {
+        let client = self.get_client();
+        let mut state = client.borrow_mut();
+        let manager = state.globals.toplevel_drag_manager.clone()?;
+        let data_device_manager = state.globals.data_device_manager.clone()?;
+        let seat = state.active_seat()?;
+        seat.button_pressed?;
+        let data_device = seat.data_device.clone()?;
+        let origin = seat.mouse_focused_window.clone()?;
+        let serial = seat.serial_tracker.get(InputSerialKind::PointerButton);
+
+        if let Some(previous) = state.toplevel_drag.take() {
+            previous.end();
+        }
+        let qh = &state.globals.qh;
+        let data_source = data_device_manager.create_data_source(qh, ToplevelDragSource);
+        data_source.offer(TOPLEVEL_DRAG_MIME_TYPE.to_string());
+        data_source.set_actions(DndAction::Move);
+        let toplevel_drag = manager.get_xdg_toplevel_drag(&data_source, qh, ());
+        toplevel_drag.attach(toplevel, offset.x.0 as i32, offset.y.0 as i32);
+        data_device.start_drag(Some(&data_source), &origin.surface(), None, serial);
+
+        let (ended, receiver) = oneshot::channel();
+        state.toplevel_drag = Some(ToplevelDrag {
+            data_source,
+            toplevel_drag,
+            ended,
+        });
+        Some(receiver)
+    }
+
+    /// Returns the pointer position of the seat whose pointer is over the given surface.
+    pub fn mouse_location(&self, surface_id: &ObjectId) -> Option<Point<Pixels>> {
+        let client = self.get_client();
+        let state = client.borrow();
+        state
+            .seats
+            .values()
+            .find(|seat| {
+                seat.mouse_focused_window
+                    .as_ref()
+                    .is_some_and(|window| &window.surface().id() == surface_id)
+            })
+            .and_then(|seat| seat.mouse_location)
+    }
+
+    /// Returns the compose sequence typed so far by the seat focusing the given surface.
+    pub fn pending_compose(&self, surface_id: &ObjectId) -> Option<String> {
+        let client = self.get_client();
+        let state = client.borrow();
+        state
+            .seats
+            .values()
+            .find(|seat| {
+                seat.keyboard_focused_window
+                    .as_ref()
+                    .is_some_and(|window| &window.surface().id() == surface_id)
+            })
+            .and_then(|seat| seat.pre_edit_text.clone())
+    }
+
+    /// Returns the key repeat configuration of the active seat.
+    pub fn key_repeat(&self) -> Option<KeyRepeatSettings> {
+        let client = self.get_client();
+        let state = client.borrow();
+        state.active_seat().map(|seat| KeyRepeatSettings {
+            characters_per_second: seat.repeat.characters_per_second,
+            delay: seat.repeat.delay,
+        })
+    }
+
+    pub fn modifiers(&self) -> Modifiers {
+        let client = self.get_client();
+        let state = client.borrow();
+        state
+            .active_seat()
+            .map(|seat| seat.modifiers)
+            .unwrap_or_default()
     }
 
     pub fn set_pending_activation(&self, window: ObjectId) {
@@ -285,55 +729,59 @@ impl WaylandClientStatePtr {
             Some(PendingActivation::Window(window));
     }
 
-    pub fn enable_ime(&self) {
+    pub fn enable_ime(&self, seat_id: &ObjectId) {
         let client = self.get_client();
         let mut state = client.borrow_mut();
-        let Some(mut text_input) = state.text_input.take() else {
+        let Some(seat) = state.seats.get_mut(seat_id) else {
+            return;
+        };
+        let Some(mut text_input) = seat.text_input.take() else {
             return;
         };
 
         text_input.enable();
         text_input.set_content_type(ContentHint::None, ContentPurpose::Normal);
-        if let Some(window) = state.keyboard_focused_window.clone() {
+        if let Some(window) = seat.keyboard_focused_window.clone() {
             drop(state);
             if let Some(area) = window.get_ime_area() {
-                text_input.set_cursor_rectangle(
-                    area.origin.x.0 as i32,
-                    area.origin.y.0 as i32,
-                    area.size.width.0 as i32,
-                    area.size.height.0 as i32,
-                );
+                set_cursor_rectangle(&text_input, area);
             }
             state = client.borrow_mut();
         }
         text_input.commit();
-        state.text_input = Some(text_input);
+        if let Some(seat) = state.seats.get_mut(seat_id) {
+            seat.text_input = Some(text_input);
+        }
     }
 
-    pub fn disable_ime(&self) {
+    pub fn disable_ime(&self, seat_id: &ObjectId) {
         let client = self.get_client();
         let mut state = client.borrow_mut();
-        state.composing = false;
-        if let Some(text_input) = &state.text_input {
+        let Some(seat) = state.seats.get_mut(seat_id) else {
+            return;
+        };
+        seat.composing = false;
+        if let Some(text_input) = &seat.text_input {
             text_input.disable();
             text_input.commit();
         }
     }
 
-    pub fn update_ime_position(&self, bounds: Bounds<ScaledPixels>) {
+    /// Moves the candidate window next to the caret, given in surface-local coordinates.
+    pub fn update_ime_position(&self, bounds: Bounds<Pixels>) {
         let client = self.get_client();
         let mut state = client.borrow_mut();
-        if state.composing || state.text_input.is_none() || state.pre_edit_text.is_some() {
+        let Some(seat) = state.active_seat_mut() else {
+            return;
+        };
+        if seat.composing || seat.pre_edit_text.is_some() {
             return;
         }
+        let Some(text_input) = seat.text_input.as_ref() else {
+            return;
+        };
 
-        let text_input = state.text_input.as_ref().unwrap();
-        text_input.set_cursor_rectangle(
-            bounds.origin.x.0 as i32,
-            bounds.origin.y.0 as i32,
-            bounds.size.width.0 as i32,
-            bounds.size.height.0 as i32,
-        );
+        set_cursor_rectangle(text_input, bounds);
         text_input.commit();
     }
 
@@ -341,14 +789,16 @@ impl WaylandClientStatePtr {
         let mut client = self.get_client();
         let mut state = client.borrow_mut();
         let closed_window = state.windows.remove(surface_id).unwrap();
-        if let Some(window) = state.mouse_focused_window.take() {
-            if !window.ptr_eq(&closed_window) {
-                state.mouse_focused_window = Some(window);
+        for seat in state.seats.values_mut() {
+            if let Some(window) = seat.mouse_focused_window.take() {
+                if !window.ptr_eq(&closed_window) {
+                    seat.mouse_focused_window = Some(window);
+                }
             }
-        }
-        if let Some(window) = state.keyboard_focused_window.take() {
-            if !window.ptr_eq(&closed_window) {
-                state.keyboard_focused_window = Some(window);
+            if let Some(window) = seat.keyboard_focused_window.take() {
+                if !window.ptr_eq(&closed_window) {
+                    seat.keyboard_focused_window = Some(window);
+                }
             }
         }
         if state.windows.is_empty() {
@@ -365,17 +815,8 @@ impl Drop for WaylandClient {
         let mut state = self.0.borrow_mut();
         state.windows.clear();
 
-        if let Some(wl_pointer) = &state.wl_pointer {
-            wl_pointer.release();
-        }
-        if let Some(cursor_shape_device) = &state.cursor_shape_device {
-            cursor_shape_device.destroy();
-        }
-        if let Some(data_device) = &state.data_device {
-            data_device.release();
-        }
-        if let Some(text_input) = &state.text_input {
-            text_input.destroy();
+        for seat in state.seats.values() {
+            seat.release();
         }
     }
 }
@@ -412,26 +853,29 @@ fn wl_output_version(version: u32) -> u32 {
 }
 
 impl WaylandClient {
-    pub(crate) fn new() -> Self {
-        let conn = Connection::connect_to_env().unwrap();
+    pub(crate) fn new() -> anyhow::Result<Self> {
+        let conn = Connection::connect_to_env().context("failed to connect to the compositor")?;
+        let (conn, security_context) = tag_connection(conn)?;
 
-        let (globals, mut event_queue) =
-            registry_queue_init::<WaylandClientStatePtr>(&conn).unwrap();
+        let (globals, mut event_queue) = registry_queue_init::<WaylandClientStatePtr>(&conn)
+            .context("failed to read the compositor's globals")?;
         let qh = event_queue.handle();
 
-        let mut seat: Option<wl_seat::WlSeat> = None;
+        let mut seats = Vec::new();
+        let mut wl_outputs = Vec::new();
         #[allow(clippy::mutable_key_type)]
         let mut in_progress_outputs = HashMap::default();
         globals.contents().with_list(|list| {
             for global in list {
                 match &global.interface[..] {
                     "wl_seat" => {
-                        seat = Some(globals.registry().bind::<wl_seat::WlSeat, _, _>(
+                        let seat = globals.registry().bind::<wl_seat::WlSeat, _, _>(
                             global.name,
                             wl_seat_version(global.version),
                             &qh,
                             (),
-                        ));
+                        );
+                        seats.push((global.name, seat));
                     }
                     "wl_output" => {
                         let output = globals.registry().bind::<wl_output::WlOutput, _, _>(
@@ -441,6 +885,7 @@ impl WaylandClient {
                             (),
                         );
                         in_progress_outputs.insert(output.id(), InProgressOutput::default());
+                        wl_outputs.push(output);
                     }
                     _ => {}
                 }
@@ -449,7 +894,7 @@ impl WaylandClient {
 
         let event_loop = EventLoop::<WaylandClientStatePtr>::try_new().unwrap();
 
-        let (common, main_receiver) = LinuxCommon::new(event_loop.get_signal());
+        let (mut common, main_receiver) = LinuxCommon::new(event_loop.get_signal());
 
         let handle = event_loop.handle();
         handle
@@ -465,42 +910,114 @@ impl WaylandClient {
             })
             .unwrap();
 
-        let gpu_context = BladeContext::new().expect("Unable to init GPU context");
-
-        let seat = seat.unwrap();
-        let globals = Globals::new(
-            globals,
-            common.foreground_executor.clone(),
-            qh.clone(),
-            seat.clone(),
-        );
+        handle
+            .insert_source(Timer::from_duration(FRAME_CALLBACK_TIMEOUT), {
+                move |_, _, client: &mut WaylandClientStatePtr| {
+                    if let Some(client) = client.0.upgrade() {
+                        let windows = client
+                            .borrow()
+                            .windows
+                            .values()
+                            .cloned()
+                            .collect::<Vec<_>>();
+                        for window in windows {
+                            window.check_frame_timeout();
+                        }
+                    }
+                    TimeoutAction::ToDuration(FRAME_CALLBACK_TIMEOUT)
+                }
+            })
+            .unwrap();
 
-        let data_device = globals
-            .data_device_manager
-            .as_ref()
-            .map(|data_device_manager| data_device_manager.get_data_device(&seat, &qh, ()));
+        let globals = Globals::new(globals, common.foreground_executor.clone(), qh.clone());
 
-        let primary_selection = globals
-            .primary_selection_manager
-            .as_ref()
-            .map(|primary_selection_manager| primary_selection_manager.get_device(&seat, &qh, ()));
+        #[allow(clippy::mutable_key_type)]
+        let seats = seats
+            .into_iter()
+            .map(|(name, seat)| (seat.id(), SeatState::new(name, seat, &globals)))
+            .collect::<HashMap<_, _>>();
+
+        let mut output_powers = OutputPowers::default();
+        let mut output_gammas = OutputGammas::default();
+        for output in &wl_outputs {
+            output_powers.add_output(output, &globals);
+            output_gammas.add_output(output);
+            get_xdg_output(output, &globals);
+        }
 
         let mut cursor = Cursor::new(&conn, &globals, 24);
 
+        let (app_menu, app_menu_events) = AppMenuExporter::new(&common.background_executor);
+        common.app_menu = Some(app_menu);
+        handle
+            .insert_source(
+                app_menu_events,
+                |event, _, client: &mut WaylandClientStatePtr| {
+                    let calloop::channel::Event::Msg(event) = event else {
+                        return;
+                    };
+                    let Some(client) = client.0.upgrade() else {
+                        return;
+                    };
+                    if let AppMenuEvent::Exported(service) = &event {
+                        let windows = client
+                            .borrow()
+                            .windows
+                            .values()
+                            .cloned()
+                            .collect::<Vec<_>>();
+                        for window in windows {
+                            window.set_app_menu_address(service, APP_MENU_PATH);
+                        }
+                    }
+                    handle_app_menu_event(event, || {
+                        RefMut::map(client.borrow_mut(), |state| &mut state.common)
+                    });
+                },
+            )
+            .unwrap();
+
         handle
             .insert_source(XDPEventSource::new(&common.background_executor), {
                 move |event, _, client| match event {
                     XDPEvent::WindowAppearance(appearance) => {
                         if let Some(client) = client.0.upgrade() {
                             let mut client = client.borrow_mut();
-
                             client.common.appearance = appearance;
 
-                            for (_, window) in &mut client.windows {
+                            // The windows' callbacks may query the platform, so they run after
+                            // the client is released.
+                            let windows = client.windows.values().cloned().collect::<Vec<_>>();
+                            drop(client);
+                            for mut window in windows {
                                 window.set_appearance(appearance);
                             }
                         }
                     }
+                    XDPEvent::AccentColor(accent_color) => {
+                        if let Some(client) = client.0.upgrade() {
+                            let mut client = client.borrow_mut();
+                            client.common.accent_color = accent_color;
+
+                            let windows = client.windows.values().cloned().collect::<Vec<_>>();
+                            drop(client);
+                            for window in windows {
+                                window.appearance_changed();
+                            }
+                        }
+                    }
+                    XDPEvent::ReduceMotion(reduce_motion) => {
+                        if let Some(client) = client.0.upgrade() {
+                            let mut client = client.borrow_mut();
+                            client.common.reduce_motion = reduce_motion;
+
+                            let windows = client.windows.values().cloned().collect::<Vec<_>>();
+                            drop(client);
+                            for window in windows {
+                                window.appearance_changed();
+                            }
+                        }
+                    }
                     XDPEvent::CursorTheme(theme) => {
                         if let Some(client) = client.0.upgrade() {
                             let mut client = client.borrow_mut();
@@ -518,82 +1035,57 @@ impl WaylandClient {
             .unwrap();
 
         let mut state = Rc::new(RefCell::new(WaylandClientState {
-            serial_tracker: SerialTracker::new(),
             globals,
-            gpu_context,
-            wl_seat: seat,
-            wl_pointer: None,
-            wl_keyboard: None,
-            cursor_shape_device: None,
-            data_device,
-            primary_selection,
-            text_input: None,
-            pre_edit_text: None,
-            ime_pre_edit: None,
-            composing: false,
+            _security_context: security_context,
+            gpu_context: GpuContext::default(),
+            gpu_device: None,
+            seats,
+            active_seat: None,
             outputs: HashMap::default(),
             in_progress_outputs,
             windows: HashMap::default(),
             common,
-            keymap_state: None,
-            compose_state: None,
             drag: DragState {
                 data_offer: None,
                 window: None,
                 position: Point::default(),
             },
-            click: ClickState {
-                last_click: Instant::now(),
-                last_mouse_button: None,
-                last_location: Point::default(),
-                current_count: 0,
-            },
-            repeat: KeyRepeat {
-                characters_per_second: 16,
-                delay: Duration::from_millis(500),
-                current_id: 0,
-                current_keycode: None,
-            },
-            modifiers: Modifiers {
-                shift: false,
-                control: false,
-                alt: false,
-                function: false,
-                platform: false,
-            },
-            scroll_event_received: false,
-            axis_source: AxisSource::Wheel,
-            mouse_location: None,
-            continuous_scroll_delta: None,
-            discrete_scroll_delta: None,
-            vertical_modifier: -1.0,
-            horizontal_modifier: -1.0,
-            button_pressed: None,
-            mouse_focused_window: None,
-            keyboard_focused_window: None,
+            toplevel_drag: None,
             loop_handle: handle.clone(),
-            enter_token: None,
             cursor_style: None,
             clipboard: Clipboard::new(conn.clone(), handle.clone()),
             data_offers: Vec::new(),
             primary_data_offer: None,
             cursor,
             pending_activation: None,
+            background_effect_blur: false,
+            output_heads: OutputHeads::default(),
+            output_powers,
+            output_gammas,
+            desktop_workspaces: DesktopWorkspaces::default(),
+            virtual_pointer: None,
+            virtual_keyboard: None,
             event_loop: Some(event_loop),
         }));
 
-        WaylandSource::new(conn, event_queue)
-            .insert(handle)
+        handle
+            .insert_source(WaylandSource::new(conn, event_queue), |_, queue, state| {
+                profiling::scope!("wayland dispatch");
+                queue.dispatch_pending(state)
+            })
             .unwrap();
 
-        Self(state)
+        Ok(Self(state))
     }
 }
 
 impl LinuxClient for WaylandClient {
     fn keyboard_layout(&self) -> String {
         let state = self.0.borrow();
-        if let Some(keymap_state) = &state.keymap_state {
+        if let Some(keymap_state) = state
+            .active_seat()
+            .and_then(|seat| seat.keymap_state.as_ref())
+        {
             let layout_idx = keymap_state.serialize_layout(xkbcommon::xkb::STATE_LAYOUT_EFFECTIVE);
             keymap_state
                 .get_keymap()
@@ -604,6 +1096,35 @@ impl LinuxClient for WaylandClient {
         }
     }
 
+    fn keyboard_layout_index(&self) -> Option<usize> {
+        let state = self.0.borrow();
+        let keymap_state = state.active_seat()?.keymap_state.as_ref()?;
+        Some(keymap_state.serialize_layout(xkbcommon::xkb::STATE_LAYOUT_EFFECTIVE) as usize)
+    }
+
+    fn keyboard_layouts(&self) -> Vec<String> {
+        let state = self.0.borrow();
+        let Some(keymap_state) = state
+            .active_seat()
+            .and_then(|seat| seat.keymap_state.as_ref())
+        else {
+            return Vec::new();
+        };
+        let keymap = keymap_state.get_keymap();
+        (0..keymap.num_layouts())
+            .map(|layout_idx| keymap.layout_get_name(layout_idx).to_string())
+            .collect()
+    }
+
+    fn set_gpu_device(&self, device_id: Option<u32>) {
+        let mut state = self.0.borrow_mut();
+        if state.gpu_device != device_id {
+            // Already open windows keep rendering on their current GPU.
+            state.gpu_device = device_id;
+            state.gpu_context = GpuContext::default();
+        }
+    }
+
     fn displays(&self) -> Vec<Rc<dyn PlatformDisplay>> {
         self.0
             .borrow()
@@ -613,7 +1134,8 @@ impl LinuxClient for WaylandClient {
                 Rc::new(WaylandDisplay {
                     id: id.clone(),
                     name: output.name.clone(),
-                    bounds: output.bounds.to_pixels(output.scale as f32),
+                    bounds: output.logical_bounds(),
+                    mode: output.mode,
                 }) as Rc<dyn PlatformDisplay>
             })
             .collect()
@@ -629,7 +1151,8 @@ impl LinuxClient for WaylandClient {
                     Rc::new(WaylandDisplay {
                         id: object_id.clone(),
                         name: output.name.clone(),
-                        bounds: output.bounds.to_pixels(output.scale as f32),
+                        bounds: output.logical_bounds(),
+                        mode: output.mode,
                     }) as Rc<dyn PlatformDisplay>
                 })
             })
@@ -643,17 +1166,49 @@ impl LinuxClient for WaylandClient {
         &self,
         handle: AnyWindowHandle,
         params: WindowParams,
-    ) -> anyhow::Result<Box<dyn PlatformWindow>> {
+    ) -> Result<Box<dyn PlatformWindow>, WindowCreationError> {
         let mut state = self.0.borrow_mut();
+        let gpu_device = state.gpu_device;
+        state.gpu_context.init(gpu_device, "OpenGL ES");
+
+        let parent = params.popup_anchor.as_ref().and_then(|(parent, _)| {
+            state
+                .windows
+                .values()
+                .find(|window| window.handle() == *parent)
+                .cloned()
+        });
+        // Popups grab the input with the serial of the click, touch or key press that opened them.
+        let grab = state.active_seat().map(|seat| {
+            let serial = seat
+                .serial_tracker
+                .latest(&[
+                    InputSerialKind::PointerButton,
+                    InputSerialKind::TouchDown,
+                    InputSerialKind::KeyPress,
+                ])
+                .map_or(0, |(_, serial)| serial);
+            (seat.wl_seat.clone(), serial)
+        });
 
         let (window, surface_id) = WaylandWindow::new(
             handle,
             state.globals.clone(),
-            &state.gpu_context,
+            state.gpu_context.get(),
             WaylandClientStatePtr(Rc::downgrade(&self.0)),
             params,
             state.common.appearance,
+            parent,
+            grab,
         )?;
+        if let Some(service) = state
+            .common
+            .app_menu
+            .as_ref()
+            .and_then(|app_menu| app_menu.service())
+        {
+            window.0.set_app_menu_address(service, APP_MENU_PATH);
+        }
         state.windows.insert(surface_id, window.0.clone());
 
         Ok(Box::new(window))
@@ -667,35 +1222,54 @@ impl LinuxClient for WaylandClient {
             .map_or(true, |current_style| current_style != style);
 
         if need_update {
-            let serial = state.serial_tracker.get(SerialKind::MouseEnter);
             state.cursor_style = Some(style);
 
-            if let Some(cursor_shape_device) = &state.cursor_shape_device {
-                cursor_shape_device.set_shape(serial, style.to_shape());
-            } else if let Some(focused_window) = &state.mouse_focused_window {
-                // cursor-shape-v1 isn't supported, set the cursor using a surface.
-                let wl_pointer = state
-                    .wl_pointer
-                    .clone()
-                    .expect("window is focused by pointer");
-                let scale = focused_window.primary_output_scale();
-                state
-                    .cursor
-                    .set_icon(&wl_pointer, serial, &style.to_icon_name(), scale);
+            let pointers = state
+                .seats
+                .values()
+                .filter_map(|seat| {
+                    let focused_window = seat.mouse_focused_window.clone()?;
+                    let serial = seat.serial_tracker.get(InputSerialKind::PointerEnter);
+                    Some((
+                        seat.cursor_shape_device.clone(),
+                        seat.wl_pointer.clone(),
+                        focused_window,
+                        serial,
+                    ))
+                })
+                .collect::<Vec<_>>();
+
+            for (cursor_shape_device, wl_pointer, focused_window, serial) in pointers {
+                if let Some(cursor_shape_device) = cursor_shape_device {
+                    cursor_shape_device.set_shape(serial, style.to_shape());
+                } else {
+                    // cursor-shape-v1 isn't supported, set the cursor using a surface.
+                    let wl_pointer = wl_pointer.expect("window is focused by pointer");
+                    let scale = focused_window.primary_output_scale();
+                    state
+                        .cursor
+                        .set_icon(&wl_pointer, serial, &style.to_icon_name(), scale);
+                }
             }
         }
     }
 
     fn open_uri(&self, uri: &str) {
         let mut state = self.0.borrow_mut();
-        if let (Some(activation), Some(window)) = (
-            state.globals.activation.clone(),
-            state.mouse_focused_window.clone(),
-        ) {
+        let seat = state.active_seat().and_then(|seat| {
+            let window = seat.mouse_focused_window.clone()?;
+            let serial = seat
+                .serial_tracker
+                .latest(InputSerialKind::FOCUS)
+                .map_or(0, |(_, serial)| serial);
+            Some((seat.wl_seat.clone(), serial, window))
+        });
+        if let (Some(activation), Some((wl_seat, serial, window))) =
+            (state.globals.activation.clone(), seat)
+        {
             state.pending_activation = Some(PendingActivation::Uri(uri.to_string()));
             let token = activation.get_activation_token(&state.globals.qh, ());
-            let serial = state.serial_tracker.get(SerialKind::MousePress);
-            token.set_serial(serial, &state.wl_seat);
+            token.set_serial(serial, &wl_seat);
             token.set_surface(&window.surface());
             token.commit();
         } else {
@@ -706,14 +1280,20 @@ impl LinuxClient for WaylandClient {
 
     fn reveal_path(&self, path: PathBuf) {
         let mut state = self.0.borrow_mut();
-        if let (Some(activation), Some(window)) = (
-            state.globals.activation.clone(),
-            state.mouse_focused_window.clone(),
-        ) {
+        let seat = state.active_seat().and_then(|seat| {
+            let window = seat.mouse_focused_window.clone()?;
+            let serial = seat
+                .serial_tracker
+                .latest(InputSerialKind::FOCUS)
+                .map_or(0, |(_, serial)| serial);
+            Some((seat.wl_seat.clone(), serial, window))
+        });
+        if let (Some(activation), Some((wl_seat, serial, window))) =
+            (state.globals.activation.clone(), seat)
+        {
             state.pending_activation = Some(PendingActivation::Path(path));
             let token = activation.get_activation_token(&state.globals.qh, ());
-            let serial = state.serial_tracker.get(SerialKind::MousePress);
-            token.set_serial(serial, &state.wl_seat);
+            token.set_serial(serial, &wl_seat);
             token.set_surface(&window.surface());
             token.commit();
         } else {
@@ -726,7 +1306,140 @@ impl LinuxClient for WaylandClient {
         f(&mut self.0.borrow_mut().common)
     }
 
-    fn run(&self) {
+    fn watch_fd(&self, fd: OwnedFd, callback: Box<dyn FnMut()>) -> anyhow::Result<Subscription> {
+        watch_fd(&self.0.borrow().loop_handle, fd, callback)
+    }
+
+    fn output_heads(&self) -> Vec<OutputHead> {
+        self.0.borrow().output_heads.heads()
+    }
+
+    fn is_display_powered(&self, display: DisplayId) -> Option<bool> {
+        self.0.borrow().output_powers.is_powered(display)
+    }
+
+    fn set_display_powered(&self, display: DisplayId, powered: bool) -> anyhow::Result<()> {
+        self.0.borrow().output_powers.set_powered(display, powered)
+    }
+
+    fn desktop_workspaces(&self) -> Vec<DesktopWorkspace> {
+        self.0.borrow().desktop_workspaces.workspaces()
+    }
+
+    fn desktop_workspace_groups(&self) -> Vec<DesktopWorkspaceGroup> {
+        self.0.borrow().desktop_workspaces.groups()
+    }
+
+    fn update_desktop_workspaces(
+        &self,
+        requests: Vec<DesktopWorkspaceRequest>,
+    ) -> anyhow::Result<()> {
+        let state = self.0.borrow();
+        let manager = state
+            .globals
+            .workspace_manager
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("the compositor doesn't support ext-workspace"))?;
+        state.desktop_workspaces.update(manager, requests)
+    }
+
+    fn send_virtual_pointer_input(&self, inputs: Vec<VirtualPointerInput>) -> anyhow::Result<()> {
+        let mut state = self.0.borrow_mut();
+        let state = &mut *state;
+        if state.virtual_pointer.is_none() {
+            let manager = state
+                .globals
+                .virtual_pointer_manager
+                .as_ref()
+                .ok_or_else(|| {
+                    anyhow::anyhow!("the compositor doesn't support wlr-virtual-pointer")
+                })?;
+            let seat = state.active_seat().map(|seat| &seat.wl_seat);
+            state.virtual_pointer = Some(VirtualPointer::new(manager, seat, &state.globals.qh));
+        }
+        let layout = state
+            .outputs
+            .values()
+            .map(|output| output.logical_bounds())
+            .reduce(|layout, bounds| layout.union(&bounds))
+            .unwrap_or_default();
+        if let Some(pointer) = &state.virtual_pointer {
+            for input in &inputs {
+                pointer.send(input, layout);
+            }
+        }
+        Ok(())
+    }
+
+    fn send_virtual_keyboard_input(&self, inputs: Vec<VirtualKeyboardInput>) -> anyhow::Result<()> {
+        let mut state = self.0.borrow_mut();
+        let state = &mut *state;
+        if state.virtual_keyboard.is_none() {
+            let manager = state
+                .globals
+                .virtual_keyboard_manager
+                .as_ref()
+                .ok_or_else(|| {
+                    anyhow::anyhow!("the compositor doesn't support virtual-keyboard")
+                })?;
+            let seat = state
+                .active_seat()
+                .ok_or_else(|| anyhow::anyhow!("no seat to create a virtual keyboard on"))?;
+            state.virtual_keyboard = Some(VirtualKeyboard::new(
+                manager,
+                &seat.wl_seat,
+                &state.globals.qh,
+            ));
+        }
+        if let Some(keyboard) = &mut state.virtual_keyboard {
+            for input in &inputs {
+                keyboard.send(input)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn display_gamma(&self, display: DisplayId) -> Option<DisplayGamma> {
+        self.0.borrow_mut().output_gammas.gamma(display)
+    }
+
+    fn set_display_gamma(
+        &self,
+        display: DisplayId,
+        gamma: Option<DisplayGamma>,
+    ) -> anyhow::Result<()> {
+        let mut state = self.0.borrow_mut();
+        let state = &mut *state;
+        state
+            .output_gammas
+            .set_gamma(display, gamma, &state.globals)
+    }
+
+    fn configure_outputs(
+        &self,
+        configuration: OutputConfiguration,
+        test_only: bool,
+    ) -> oneshot::Receiver<anyhow::Result<()>> {
+        let mut state = self.0.borrow_mut();
+        let state = &mut *state;
+        let result = state
+            .globals
+            .output_manager
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("the compositor doesn't support wlr-output-management"))
+            .and_then(|manager| {
+                state
+                    .output_heads
+                    .configure(manager, configuration, test_only, &state.globals.qh)
+            });
+        result.unwrap_or_else(|error| {
+            let (tx, rx) = oneshot::channel();
+            tx.send(Err(error)).ok();
+            rx
+        })
+    }
+
+    fn run(&self) -> anyhow::Result<()> {
         let mut event_loop = self
             .0
             .borrow_mut()
@@ -740,20 +1453,23 @@ impl LinuxClient for WaylandClient {
                 &mut WaylandClientStatePtr(Rc::downgrade(&self.0)),
                 |_| {},
             )
-            .log_err();
+            .context("lost connection to the Wayland compositor")
     }
 
     fn write_to_primary(&self, item: crate::ClipboardItem) {
         let mut state = self.0.borrow_mut();
+        let Some(seat) = state.active_seat() else {
+            return;
+        };
         let (Some(primary_selection_manager), Some(primary_selection)) = (
             state.globals.primary_selection_manager.clone(),
-            state.primary_selection.clone(),
+            seat.primary_selection.clone(),
         ) else {
             return;
         };
-        if state.mouse_focused_window.is_some() || state.keyboard_focused_window.is_some() {
+        if seat.mouse_focused_window.is_some() || seat.keyboard_focused_window.is_some() {
+            let serial = seat.serial_tracker.get(InputSerialKind::KeyPress);
             state.clipboard.set_primary(item);
-            let serial = state.serial_tracker.get(SerialKind::KeyPress);
             let data_source = primary_selection_manager.create_source(&state.globals.qh, ());
             data_source.offer(state.clipboard.self_mime());
             data_source.offer(TEXT_MIME_TYPE.to_string());
@@ -763,15 +1479,18 @@ impl LinuxClient for WaylandClient {
 
     fn write_to_clipboard(&self, item: crate::ClipboardItem) {
         let mut state = self.0.borrow_mut();
+        let Some(seat) = state.active_seat() else {
+            return;
+        };
         let (Some(data_device_manager), Some(data_device)) = (
             state.globals.data_device_manager.clone(),
-            state.data_device.clone(),
+            seat.data_device.clone(),
         ) else {
             return;
         };
-        if state.mouse_focused_window.is_some() || state.keyboard_focused_window.is_some() {
+        if seat.mouse_focused_window.is_some() || seat.keyboard_focused_window.is_some() {
+            let serial = seat.serial_tracker.get(InputSerialKind::KeyPress);
             state.clipboard.set(item);
-            let serial = state.serial_tracker.get(SerialKind::KeyPress);
             let data_source = data_device_manager.create_data_source(&state.globals.qh, ());
             data_source.offer(state.clipboard.self_mime());
             data_source.offer(TEXT_MIME_TYPE.to_string());
@@ -789,9 +1508,9 @@ impl LinuxClient for WaylandClient {
 
     fn active_window(&self) -> Option<AnyWindowHandle> {
         self.0
-            .borrow_mut()
-            .keyboard_focused_window
-            .as_ref()
+            .borrow()
+            .active_seat()
+            .and_then(|seat| seat.keyboard_focused_window.as_ref())
             .map(|window| window.handle())
     }
 
@@ -799,6 +1518,19 @@ impl LinuxClient for WaylandClient {
         None
     }
 
+    fn window_identifier(&self) -> impl Future<Output = Option<ashpd::WindowIdentifier>> + 'static {
+        // xdg-foreign can only export toplevels, the dialogs of layer shell windows are shown
+        // without a parent.
+        let surface = self
+            .0
+            .borrow()
+            .active_seat()
+            .and_then(|seat| seat.keyboard_focused_window.as_ref())
+            .filter(|window| window.is_toplevel())
+            .map(|window| window.surface());
+        async move { ashpd::WindowIdentifier::from_wayland(&surface?).await }
+    }
+
     fn compositor_name(&self) -> &'static str {
         "Wayland"
     }
@@ -813,6 +1545,7 @@ impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for WaylandClientStat
         _: &Connection,
         qh: &QueueHandle<Self>,
     ) {
+        trace::event(registry, &event);
         let mut client = this.get_client();
         let mut state = client.borrow_mut();
 
@@ -823,19 +1556,14 @@ impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for WaylandClientStat
                 version,
             } => match &interface[..] {
                 "wl_seat" => {
-                    if let Some(wl_pointer) = state.wl_pointer.take() {
-                        wl_pointer.release();
-                    }
-                    if let Some(wl_keyboard) = state.wl_keyboard.take() {
-                        wl_keyboard.release();
-                    }
-                    state.wl_seat.release();
-                    state.wl_seat = registry.bind::<wl_seat::WlSeat, _, _>(
+                    let wl_seat = registry.bind::<wl_seat::WlSeat, _, _>(
                         name,
                         wl_seat_version(version),
                         qh,
                         (),
                     );
+                    let seat = SeatState::new(name, wl_seat, &state.globals);
+                    state.seats.insert(seat.wl_seat.id(), seat);
                 }
                 "wl_output" => {
                     let output = registry.bind::<wl_output::WlOutput, _, _>(
@@ -848,11 +1576,33 @@ impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for WaylandClientStat
                     state
                         .in_progress_outputs
                         .insert(output.id(), InProgressOutput::default());
+                    let state = &mut *state;
+                    state.output_powers.add_output(&output, &state.globals);
+                    state.output_gammas.add_output(&output);
+                    get_xdg_output(&output, &state.globals);
                 }
                 _ => {}
             },
-            wl_registry::Event::GlobalRemove { name: _ } => {
-                // TODO: handle global removal
+            wl_registry::Event::GlobalRemove { name } => {
+                let removed_seat = state
+                    .seats
+                    .iter()
+                    .find(|(_, seat)| seat.global_name == name)
+                    .map(|(id, _)| id.clone());
+                if let Some(seat_id) = removed_seat {
+                    let hovered_window = state.seats.remove(&seat_id).and_then(|seat| {
+                        seat.release();
+                        seat.mouse_focused_window
+                    });
+                    if state.active_seat.as_ref() == Some(&seat_id) {
+                        state.active_seat = None;
+                    }
+                    if let Some(window) = hovered_window {
+                        drop(state);
+                        window.set_hovered(false);
+                    }
+                }
+                // TODO: handle removal of other globals
             }
             _ => {}
         }
@@ -862,6 +1612,17 @@ impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for WaylandClientStat
 delegate_noop!(WaylandClientStatePtr: ignore xdg_activation_v1::XdgActivationV1);
 delegate_noop!(WaylandClientStatePtr: ignore wl_compositor::WlCompositor);
 delegate_noop!(WaylandClientStatePtr: ignore zwlr_layer_shell_v1::ZwlrLayerShellV1);
+delegate_noop!(WaylandClientStatePtr: ignore zwlr_output_configuration_head_v1::ZwlrOutputConfigurationHeadV1);
+delegate_noop!(WaylandClientStatePtr: ignore zwlr_output_power_manager_v1::ZwlrOutputPowerManagerV1);
+delegate_noop!(WaylandClientStatePtr: ignore zwlr_gamma_control_manager_v1::ZwlrGammaControlManagerV1);
+delegate_noop!(WaylandClientStatePtr: ignore zwlr_virtual_pointer_manager_v1::ZwlrVirtualPointerManagerV1);
+delegate_noop!(WaylandClientStatePtr: ignore zwlr_virtual_pointer_v1::ZwlrVirtualPointerV1);
+delegate_noop!(WaylandClientStatePtr: ignore zwp_virtual_keyboard_manager_v1::ZwpVirtualKeyboardManagerV1);
+delegate_noop!(WaylandClientStatePtr: ignore zwp_virtual_keyboard_v1::ZwpVirtualKeyboardV1);
+delegate_noop!(WaylandClientStatePtr: ignore zxdg_output_manager_v1::ZxdgOutputManagerV1);
+delegate_noop!(WaylandClientStatePtr: ignore xdg_toplevel_drag_manager_v1::XdgToplevelDragManagerV1);
+delegate_noop!(WaylandClientStatePtr: ignore xdg_toplevel_drag_v1::XdgToplevelDragV1);
+delegate_noop!(WaylandClientStatePtr: ignore wp_presentation::WpPresentation);
 delegate_noop!(WaylandClientStatePtr: ignore wp_cursor_shape_device_v1::WpCursorShapeDeviceV1);
 delegate_noop!(WaylandClientStatePtr: ignore wp_cursor_shape_manager_v1::WpCursorShapeManagerV1);
 delegate_noop!(WaylandClientStatePtr: ignore wl_data_device_manager::WlDataDeviceManager);
@@ -870,66 +1631,340 @@ delegate_noop!(WaylandClientStatePtr: ignore wl_shm::WlShm);
 delegate_noop!(WaylandClientStatePtr: ignore wl_shm_pool::WlShmPool);
 delegate_noop!(WaylandClientStatePtr: ignore wl_buffer::WlBuffer);
 delegate_noop!(WaylandClientStatePtr: ignore wl_region::WlRegion);
+delegate_noop!(WaylandClientStatePtr: ignore xdg_positioner::XdgPositioner);
 delegate_noop!(WaylandClientStatePtr: ignore wp_fractional_scale_manager_v1::WpFractionalScaleManagerV1);
 delegate_noop!(WaylandClientStatePtr: ignore zxdg_decoration_manager_v1::ZxdgDecorationManagerV1);
 delegate_noop!(WaylandClientStatePtr: ignore org_kde_kwin_blur_manager::OrgKdeKwinBlurManager);
 delegate_noop!(WaylandClientStatePtr: ignore zwp_text_input_manager_v3::ZwpTextInputManagerV3);
 delegate_noop!(WaylandClientStatePtr: ignore org_kde_kwin_blur::OrgKdeKwinBlur);
+delegate_noop!(WaylandClientStatePtr: ignore org_kde_kwin_appmenu_manager::OrgKdeKwinAppmenuManager);
+delegate_noop!(WaylandClientStatePtr: ignore org_kde_kwin_appmenu::OrgKdeKwinAppmenu);
+delegate_noop!(WaylandClientStatePtr: ignore ext_background_effect_surface_v1::ExtBackgroundEffectSurfaceV1);
 delegate_noop!(WaylandClientStatePtr: ignore wp_viewporter::WpViewporter);
 delegate_noop!(WaylandClientStatePtr: ignore wp_viewport::WpViewport);
+delegate_noop!(WaylandClientStatePtr: ignore wl_subcompositor::WlSubcompositor);
+delegate_noop!(WaylandClientStatePtr: ignore wl_subsurface::WlSubsurface);
+delegate_noop!(WaylandClientStatePtr: ignore zwp_linux_dmabuf_v1::ZwpLinuxDmabufV1);
+
+impl Dispatch<WlCallback, ObjectId> for WaylandClientStatePtr {
+    fn event(
+        state: &mut WaylandClientStatePtr,
+        proxy: &wl_callback::WlCallback,
+        event: wl_callback::Event,
+        surface_id: &ObjectId,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        trace::event(proxy, &event);
+        let client = state.get_client();
+        let mut state = client.borrow_mut();
+        let Some(window) = get_window(&mut state, surface_id) else {
+            return;
+        };
+        drop(state);
+
+        match event {
+            wl_callback::Event::Done { .. } => {
+                window.frame();
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<wp_presentation_feedback::WpPresentationFeedback, ObjectId>
+    for WaylandClientStatePtr
+{
+    fn event(
+        state: &mut WaylandClientStatePtr,
+        proxy: &wp_presentation_feedback::WpPresentationFeedback,
+        event: wp_presentation_feedback::Event,
+        surface_id: &ObjectId,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        trace::event(proxy, &event);
+        let client = state.get_client();
+        let mut state = client.borrow_mut();
+        let Some(window) = get_window(&mut state, surface_id) else {
+            return;
+        };
+        drop(state);
+
+        match event {
+            wp_presentation_feedback::Event::Presented {
+                tv_sec_hi,
+                tv_sec_lo,
+                tv_nsec,
+                refresh,
+                ..
+            } => {
+                let seconds = ((tv_sec_hi as u64) << 32) | tv_sec_lo as u64;
+                window.presented(
+                    Duration::new(seconds, tv_nsec),
+                    Duration::from_nanos(refresh as u64),
+                );
+            }
+            _ => {}
+        }
+    }
+}
+
+fn get_window(
+    mut state: &mut RefMut<WaylandClientState>,
+    surface_id: &ObjectId,
+) -> Option<WaylandWindowStatePtr> {
+    state.windows.get(surface_id).cloned()
+}
+
+impl Dispatch<wl_surface::WlSurface, ()> for WaylandClientStatePtr {
+    fn event(
+        this: &mut Self,
+        surface: &wl_surface::WlSurface,
+        event: <wl_surface::WlSurface as Proxy>::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        trace::event(surface, &event);
+        let mut client = this.get_client();
+        let mut state = client.borrow_mut();
+
+        let Some(window) = get_window(&mut state, &surface.id()) else {
+            return;
+        };
+        #[allow(clippy::mutable_key_type)]
+        let outputs = state.outputs.clone();
+        drop(state);
+
+        window.handle_surface_event(event, outputs);
+    }
+}
+
+impl Dispatch<ext_background_effect_manager_v1::ExtBackgroundEffectManagerV1, ()>
+    for WaylandClientStatePtr
+{
+    fn event(
+        this: &mut Self,
+        proxy: &ext_background_effect_manager_v1::ExtBackgroundEffectManagerV1,
+        event: ext_background_effect_manager_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        trace::event(proxy, &event);
+        if let ext_background_effect_manager_v1::Event::Capabilities {
+            flags: WEnum::Value(flags),
+        } = event
+        {
+            let client = this.get_client();
+            let mut state = client.borrow_mut();
+            state.background_effect_blur =
+                flags.contains(ext_background_effect_manager_v1::Capability::Blur);
+        }
+    }
+}
+
+impl Dispatch<zwlr_output_manager_v1::ZwlrOutputManagerV1, ()> for WaylandClientStatePtr {
+    fn event(
+        this: &mut Self,
+        proxy: &zwlr_output_manager_v1::ZwlrOutputManagerV1,
+        event: zwlr_output_manager_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        trace::event(proxy, &event);
+        let client = this.get_client();
+        let mut state = client.borrow_mut();
+        match event {
+            zwlr_output_manager_v1::Event::Head { head } => state.output_heads.add_head(head),
+            zwlr_output_manager_v1::Event::Done { serial } => {
+                state.output_heads.done(serial);
+                if let Some(mut callback) = state.common.callbacks.output_heads_change.take() {
+                    drop(state);
+                    callback();
+                    client.borrow_mut().common.callbacks.output_heads_change = Some(callback);
+                }
+            }
+            zwlr_output_manager_v1::Event::Finished => {
+                state.output_heads.finished();
+                state.globals.output_manager = None;
+            }
+            _ => {}
+        }
+    }
+
+    event_created_child!(WaylandClientStatePtr, zwlr_output_manager_v1::ZwlrOutputManagerV1, [
+        zwlr_output_manager_v1::EVT_HEAD_OPCODE => (zwlr_output_head_v1::ZwlrOutputHeadV1, ()),
+    ]);
+}
+
+impl Dispatch<zwlr_output_head_v1::ZwlrOutputHeadV1, ()> for WaylandClientStatePtr {
+    fn event(
+        this: &mut Self,
+        proxy: &zwlr_output_head_v1::ZwlrOutputHeadV1,
+        event: zwlr_output_head_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        trace::event(proxy, &event);
+        let client = this.get_client();
+        let mut state = client.borrow_mut();
+        state.output_heads.handle_head_event(proxy, event);
+    }
+
+    event_created_child!(WaylandClientStatePtr, zwlr_output_head_v1::ZwlrOutputHeadV1, [
+        zwlr_output_head_v1::EVT_MODE_OPCODE => (zwlr_output_mode_v1::ZwlrOutputModeV1, ()),
+    ]);
+}
+
+impl Dispatch<zwlr_output_mode_v1::ZwlrOutputModeV1, ()> for WaylandClientStatePtr {
+    fn event(
+        this: &mut Self,
+        proxy: &zwlr_output_mode_v1::ZwlrOutputModeV1,
+        event: zwlr_output_mode_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        trace::event(proxy, &event);
+        let client = this.get_client();
+        let mut state = client.borrow_mut();
+        state.output_heads.handle_mode_event(proxy, event);
+    }
+}
+
+impl Dispatch<zwlr_output_configuration_v1::ZwlrOutputConfigurationV1, ()>
+    for WaylandClientStatePtr
+{
+    fn event(
+        this: &mut Self,
+        proxy: &zwlr_output_configuration_v1::ZwlrOutputConfigurationV1,
+        event: zwlr_output_configuration_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        trace::event(proxy, &event);
+        let client = this.get_client();
+        let mut state = client.borrow_mut();
+        state.output_heads.handle_configuration_event(proxy, event);
+    }
+}
+
+impl Dispatch<zwlr_output_power_v1::ZwlrOutputPowerV1, ObjectId> for WaylandClientStatePtr {
+    fn event(
+        this: &mut Self,
+        proxy: &zwlr_output_power_v1::ZwlrOutputPowerV1,
+        event: zwlr_output_power_v1::Event,
+        output_id: &ObjectId,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        trace::event(proxy, &event);
+        let client = this.get_client();
+        let mut state = client.borrow_mut();
+        state.output_powers.handle_event(output_id, event);
+    }
+}
 
-impl Dispatch<WlCallback, ObjectId> for WaylandClientStatePtr {
+impl Dispatch<zwlr_gamma_control_v1::ZwlrGammaControlV1, ObjectId> for WaylandClientStatePtr {
     fn event(
-        state: &mut WaylandClientStatePtr,
-        _: &wl_callback::WlCallback,
-        event: wl_callback::Event,
-        surface_id: &ObjectId,
+        this: &mut Self,
+        proxy: &zwlr_gamma_control_v1::ZwlrGammaControlV1,
+        event: zwlr_gamma_control_v1::Event,
+        output_id: &ObjectId,
         _: &Connection,
         _: &QueueHandle<Self>,
     ) {
-        let client = state.get_client();
+        trace::event(proxy, &event);
+        let client = this.get_client();
         let mut state = client.borrow_mut();
-        let Some(window) = get_window(&mut state, surface_id) else {
-            return;
-        };
-        drop(state);
+        state.output_gammas.handle_event(output_id, event);
+    }
+}
 
+impl Dispatch<ext_workspace_manager_v1::ExtWorkspaceManagerV1, ()> for WaylandClientStatePtr {
+    fn event(
+        this: &mut Self,
+        proxy: &ext_workspace_manager_v1::ExtWorkspaceManagerV1,
+        event: ext_workspace_manager_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        trace::event(proxy, &event);
+        let client = this.get_client();
+        let mut state = client.borrow_mut();
         match event {
-            wl_callback::Event::Done { .. } => {
-                window.frame();
+            ext_workspace_manager_v1::Event::WorkspaceGroup { workspace_group } => {
+                state.desktop_workspaces.add_group(workspace_group);
+            }
+            ext_workspace_manager_v1::Event::Workspace { workspace } => {
+                state.desktop_workspaces.add_workspace(workspace);
+            }
+            ext_workspace_manager_v1::Event::Done => {
+                state.desktop_workspaces.done();
+                if let Some(mut callback) = state.common.callbacks.desktop_workspaces_change.take()
+                {
+                    drop(state);
+                    callback();
+                    client
+                        .borrow_mut()
+                        .common
+                        .callbacks
+                        .desktop_workspaces_change = Some(callback);
+                }
+            }
+            ext_workspace_manager_v1::Event::Finished => {
+                state.desktop_workspaces.finished();
+                state.globals.workspace_manager = None;
             }
             _ => {}
         }
     }
-}
 
-fn get_window(
-    mut state: &mut RefMut<WaylandClientState>,
-    surface_id: &ObjectId,
-) -> Option<WaylandWindowStatePtr> {
-    state.windows.get(surface_id).cloned()
+    event_created_child!(WaylandClientStatePtr, ext_workspace_manager_v1::ExtWorkspaceManagerV1, [
+        ext_workspace_manager_v1::EVT_WORKSPACE_GROUP_OPCODE => (ext_workspace_group_handle_v1::ExtWorkspaceGroupHandleV1, ()),
+        ext_workspace_manager_v1::EVT_WORKSPACE_OPCODE => (ext_workspace_handle_v1::ExtWorkspaceHandleV1, ()),
+    ]);
 }
 
-impl Dispatch<wl_surface::WlSurface, ()> for WaylandClientStatePtr {
+impl Dispatch<ext_workspace_group_handle_v1::ExtWorkspaceGroupHandleV1, ()>
+    for WaylandClientStatePtr
+{
     fn event(
         this: &mut Self,
-        surface: &wl_surface::WlSurface,
-        event: <wl_surface::WlSurface as Proxy>::Event,
+        proxy: &ext_workspace_group_handle_v1::ExtWorkspaceGroupHandleV1,
+        event: ext_workspace_group_handle_v1::Event,
         _: &(),
         _: &Connection,
         _: &QueueHandle<Self>,
     ) {
-        let mut client = this.get_client();
+        trace::event(proxy, &event);
+        let client = this.get_client();
         let mut state = client.borrow_mut();
+        state.desktop_workspaces.handle_group_event(proxy, event);
+    }
+}
 
-        let Some(window) = get_window(&mut state, &surface.id()) else {
-            return;
-        };
-        #[allow(clippy::mutable_key_type)]
-        let outputs = state.outputs.clone();
-        drop(state);
-
-        window.handle_surface_event(event, outputs);
+impl Dispatch<ext_workspace_handle_v1::ExtWorkspaceHandleV1, ()> for WaylandClientStatePtr {
+    fn event(
+        this: &mut Self,
+        proxy: &ext_workspace_handle_v1::ExtWorkspaceHandleV1,
+        event: ext_workspace_handle_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        trace::event(proxy, &event);
+        let client = this.get_client();
+        let mut state = client.borrow_mut();
+        state
+            .desktop_workspaces
+            .handle_workspace_event(proxy, event);
     }
 }
 
@@ -942,10 +1977,11 @@ impl Dispatch<wl_output::WlOutput, ()> for WaylandClientStatePtr {
         _: &Connection,
         _: &QueueHandle<Self>,
     ) {
+        trace::event(output, &event);
         let mut client = this.get_client();
         let mut state = client.borrow_mut();
 
-        let Some(mut in_progress_output) = state.in_progress_outputs.get_mut(&output.id()) else {
+        let Some(in_progress_output) = state.in_progress_output(&output.id()) else {
             return;
         };
 
@@ -959,14 +1995,52 @@ impl Dispatch<wl_output::WlOutput, ()> for WaylandClientStatePtr {
             wl_output::Event::Geometry { x, y, .. } => {
                 in_progress_output.position = Some(point(DevicePixels(x), DevicePixels(y)))
             }
-            wl_output::Event::Mode { width, height, .. } => {
-                in_progress_output.size = Some(size(DevicePixels(width), DevicePixels(height)))
+            wl_output::Event::Mode {
+                flags: WEnum::Value(flags),
+                width,
+                height,
+                refresh,
+            } if flags.contains(wl_output::Mode::Current) => {
+                let size = size(DevicePixels(width), DevicePixels(height));
+                in_progress_output.size = Some(size);
+                in_progress_output.mode = Some(OutputMode {
+                    size,
+                    refresh_rate: u32::try_from(refresh).ok().filter(|refresh| *refresh > 0),
+                    preferred: flags.contains(wl_output::Mode::Preferred),
+                });
             }
-            wl_output::Event::Done => {
-                if let Some(complete) = in_progress_output.complete() {
-                    state.outputs.insert(output.id(), complete);
-                }
-                state.in_progress_outputs.remove(&output.id());
+            wl_output::Event::Done => state.output_done(&output.id()),
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<zxdg_output_v1::ZxdgOutputV1, ObjectId> for WaylandClientStatePtr {
+    fn event(
+        this: &mut Self,
+        xdg_output: &zxdg_output_v1::ZxdgOutputV1,
+        event: zxdg_output_v1::Event,
+        output_id: &ObjectId,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        trace::event(xdg_output, &event);
+        let client = this.get_client();
+        let mut state = client.borrow_mut();
+        let Some(in_progress_output) = state.in_progress_output(output_id) else {
+            return;
+        };
+
+        match event {
+            zxdg_output_v1::Event::LogicalPosition { x, y } => {
+                in_progress_output.logical_position = Some(point(px(x as f32), px(y as f32)));
+            }
+            zxdg_output_v1::Event::LogicalSize { width, height } => {
+                in_progress_output.logical_size = Some(size(px(width as f32), px(height as f32)));
+            }
+            // Since version 3, the changes are applied with the done event of the output.
+            zxdg_output_v1::Event::Done if xdg_output.version() < 3 => {
+                state.output_done(output_id);
             }
             _ => {}
         }
@@ -976,12 +2050,13 @@ impl Dispatch<wl_output::WlOutput, ()> for WaylandClientStatePtr {
 impl Dispatch<zwlr_layer_surface_v1::ZwlrLayerSurfaceV1, ObjectId> for WaylandClientStatePtr {
     fn event(
         state: &mut Self,
-        _: &zwlr_layer_surface_v1::ZwlrLayerSurfaceV1,
+        proxy: &zwlr_layer_surface_v1::ZwlrLayerSurfaceV1,
         event: <zwlr_layer_surface_v1::ZwlrLayerSurfaceV1 as Proxy>::Event,
         surface_id: &ObjectId,
         _: &Connection,
         _: &QueueHandle<Self>,
     ) {
+        trace::event(proxy, &event);
         let mut client = state.get_client();
         let mut state = client.borrow_mut();
         let Some(window) = get_window(&mut state, surface_id) else {
@@ -995,12 +2070,13 @@ impl Dispatch<zwlr_layer_surface_v1::ZwlrLayerSurfaceV1, ObjectId> for WaylandCl
 impl Dispatch<xdg_surface::XdgSurface, ObjectId> for WaylandClientStatePtr {
     fn event(
         state: &mut Self,
-        _: &xdg_surface::XdgSurface,
+        proxy: &xdg_surface::XdgSurface,
         event: xdg_surface::Event,
         surface_id: &ObjectId,
         _: &Connection,
         _: &QueueHandle<Self>,
     ) {
+        trace::event(proxy, &event);
         let client = state.get_client();
         let mut state = client.borrow_mut();
         let Some(window) = get_window(&mut state, surface_id) else {
@@ -1014,12 +2090,13 @@ impl Dispatch<xdg_surface::XdgSurface, ObjectId> for WaylandClientStatePtr {
 impl Dispatch<xdg_toplevel::XdgToplevel, ObjectId> for WaylandClientStatePtr {
     fn event(
         this: &mut Self,
-        _: &xdg_toplevel::XdgToplevel,
+        proxy: &xdg_toplevel::XdgToplevel,
         event: <xdg_toplevel::XdgToplevel as Proxy>::Event,
         surface_id: &ObjectId,
         _: &Connection,
         _: &QueueHandle<Self>,
     ) {
+        trace::event(proxy, &event);
         let client = this.get_client();
         let mut state = client.borrow_mut();
         let Some(window) = get_window(&mut state, surface_id) else {
@@ -1036,6 +2113,32 @@ impl Dispatch<xdg_toplevel::XdgToplevel, ObjectId> for WaylandClientStatePtr {
     }
 }
 
+impl Dispatch<xdg_popup::XdgPopup, ObjectId> for WaylandClientStatePtr {
+    fn event(
+        this: &mut Self,
+        proxy: &xdg_popup::XdgPopup,
+        event: xdg_popup::Event,
+        surface_id: &ObjectId,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        trace::event(proxy, &event);
+        let client = this.get_client();
+        let mut state = client.borrow_mut();
+        let Some(window) = get_window(&mut state, surface_id) else {
+            return;
+        };
+        drop(state);
+
+        if let xdg_popup::Event::PopupDone = event {
+            // The compositor dismissed the popup, e.g. after a click outside of it.
+            window.close();
+        } else {
+            window.handle_popup_event(event);
+        }
+    }
+}
+
 impl Dispatch<xdg_wm_base::XdgWmBase, ()> for WaylandClientStatePtr {
     fn event(
         _: &mut Self,
@@ -1045,6 +2148,7 @@ impl Dispatch<xdg_wm_base::XdgWmBase, ()> for WaylandClientStatePtr {
         _: &Connection,
         _: &QueueHandle<Self>,
     ) {
+        trace::event(wm_base, &event);
         if let xdg_wm_base::Event::Ping { serial } = event {
             wm_base.pong(serial);
         }
@@ -1060,6 +2164,7 @@ impl Dispatch<xdg_activation_token_v1::XdgActivationTokenV1, ()> for WaylandClie
         _: &Connection,
         _: &QueueHandle<Self>,
     ) {
+        trace::event(token, &event);
         let client = this.get_client();
         let mut state = client.borrow_mut();
 
@@ -1074,8 +2179,10 @@ impl Dispatch<xdg_activation_token_v1::XdgActivationTokenV1, ()> for WaylandClie
                     let Some(window) = get_window(&mut state, &window) else {
                         return;
                     };
-                    let activation = state.globals.activation.as_ref().unwrap();
-                    activation.activate(token, &window.surface());
+                    // Sandboxed connections may not get the global back.
+                    if let Some(activation) = state.globals.activation.as_ref() {
+                        activation.activate(token, &window.surface());
+                    }
                 }
                 None => log::error!("activation token received with no pending activation"),
             }
@@ -1094,60 +2201,87 @@ impl Dispatch<wl_seat::WlSeat, ()> for WaylandClientStatePtr {
         _: &Connection,
         qh: &QueueHandle<Self>,
     ) {
+        trace::event(seat, &event);
+        let client = state.get_client();
+        let mut state = client.borrow_mut();
+        let state = &mut *state;
+        let Some(seat_state) = state.seats.get_mut(&seat.id()) else {
+            return;
+        };
+
         if let wl_seat::Event::Capabilities {
             capabilities: WEnum::Value(capabilities),
         } = event
         {
-            let client = state.get_client();
-            let mut state = client.borrow_mut();
             if capabilities.contains(wl_seat::Capability::Keyboard) {
-                let keyboard = seat.get_keyboard(qh, ());
+                let keyboard = seat.get_keyboard(qh, seat.id());
 
-                state.text_input = state
-                    .globals
-                    .text_input_manager
-                    .as_ref()
-                    .map(|text_input_manager| text_input_manager.get_text_input(&seat, qh, ()));
+                if let Some(text_input) = seat_state.text_input.take() {
+                    text_input.destroy();
+                }
+                seat_state.text_input =
+                    state
+                        .globals
+                        .text_input_manager
+                        .as_ref()
+                        .map(|text_input_manager| {
+                            text_input_manager.get_text_input(&seat, qh, seat.id())
+                        });
 
-                if let Some(wl_keyboard) = &state.wl_keyboard {
+                if let Some(wl_keyboard) = &seat_state.wl_keyboard {
                     wl_keyboard.release();
                 }
 
-                state.wl_keyboard = Some(keyboard);
+                seat_state.wl_keyboard = Some(keyboard);
             }
             if capabilities.contains(wl_seat::Capability::Pointer) {
-                let pointer = seat.get_pointer(qh, ());
-                state.cursor_shape_device = state
+                let pointer = seat.get_pointer(qh, seat.id());
+                if let Some(cursor_shape_device) = seat_state.cursor_shape_device.take() {
+                    cursor_shape_device.destroy();
+                }
+                seat_state.cursor_shape_device = state
                     .globals
                     .cursor_shape_manager
                     .as_ref()
                     .map(|cursor_shape_manager| cursor_shape_manager.get_pointer(&pointer, qh, ()));
 
-                if let Some(wl_pointer) = &state.wl_pointer {
+                if let Some(wl_pointer) = &seat_state.wl_pointer {
                     wl_pointer.release();
                 }
 
-                state.wl_pointer = Some(pointer);
+                seat_state.wl_pointer = Some(pointer);
+            }
+            if capabilities.contains(wl_seat::Capability::Touch) {
+                let touch = seat.get_touch(qh, seat.id());
+                if let Some(wl_touch) = &seat_state.wl_touch {
+                    wl_touch.release();
+                }
+                seat_state.wl_touch = Some(touch);
             }
         }
     }
 }
 
-impl Dispatch<wl_keyboard::WlKeyboard, ()> for WaylandClientStatePtr {
+impl Dispatch<wl_keyboard::WlKeyboard, ObjectId> for WaylandClientStatePtr {
     fn event(
         this: &mut Self,
-        _: &wl_keyboard::WlKeyboard,
+        proxy: &wl_keyboard::WlKeyboard,
         event: wl_keyboard::Event,
-        _: &(),
+        seat_id: &ObjectId,
         _: &Connection,
         _: &QueueHandle<Self>,
     ) {
+        trace::event(proxy, &event);
         let mut client = this.get_client();
         let mut state = client.borrow_mut();
+        if !state.seats.contains_key(seat_id) {
+            return;
+        }
         match event {
             wl_keyboard::Event::RepeatInfo { rate, delay } => {
-                state.repeat.characters_per_second = rate as u32;
-                state.repeat.delay = Duration::from_millis(delay as u64);
+                let seat = state.seats.get_mut(seat_id).unwrap();
+                seat.repeat.characters_per_second = rate as u32;
+                seat.repeat.delay = Duration::from_millis(delay as u64);
             }
             wl_keyboard::Event::Keymap {
                 format: WEnum::Value(format),
@@ -1172,8 +2306,9 @@ impl Dispatch<wl_keyboard::WlKeyboard, ()> for WaylandClientStatePtr {
                     .flatten()
                     .expect("Failed to create keymap")
                 };
-                state.keymap_state = Some(xkb::State::new(&keymap));
-                state.compose_state = get_xkb_compose_state(&xkb_context);
+                let seat = state.seats.get_mut(seat_id).unwrap();
+                seat.keymap_state = Some(xkb::State::new(&keymap));
+                seat.compose_state = get_xkb_compose_state(&xkb_context);
 
                 if let Some(mut callback) = state.common.callbacks.keyboard_layout_change.take() {
                     drop(state);
@@ -1182,30 +2317,47 @@ impl Dispatch<wl_keyboard::WlKeyboard, ()> for WaylandClientStatePtr {
                     state.common.callbacks.keyboard_layout_change = Some(callback);
                 }
             }
-            wl_keyboard::Event::Enter { surface, .. } => {
-                state.keyboard_focused_window = get_window(&mut state, &surface.id());
-                state.enter_token = Some(());
-
-                if let Some(window) = state.keyboard_focused_window.clone() {
+            wl_keyboard::Event::Enter {
+                serial, surface, ..
+            } => {
+                let window = get_window(&mut state, &surface.id());
+                state.active_seat = Some(seat_id.clone());
+                let seat = state.seats.get_mut(seat_id).unwrap();
+                seat.serial_tracker
+                    .update(InputSerialKind::KeyboardEnter, serial);
+                seat.keyboard_focused_window = window.clone();
+                seat.enter_token = Some(());
+
+                if let Some(window) = window {
                     drop(state);
                     window.set_focused(true);
                 }
             }
             wl_keyboard::Event::Leave { surface, .. } => {
                 let keyboard_focused_window = get_window(&mut state, &surface.id());
-                state.keyboard_focused_window = None;
-                state.enter_token.take();
+                let seat = state.seats.get_mut(seat_id).unwrap();
+                seat.keyboard_focused_window = None;
+                seat.enter_token.take();
                 // Prevent keyboard events from repeating after opening e.g. a file chooser and closing it quickly
-                state.repeat.current_id += 1;
+                seat.repeat.current_id += 1;
 
                 if let Some(window) = keyboard_focused_window {
-                    if let Some(ref mut compose) = state.compose_state {
+                    if let Some(ref mut compose) = seat.compose_state {
                         compose.reset();
                     }
-                    state.pre_edit_text.take();
+                    seat.pre_edit_text.take();
+
+                    // Another seat may still be focusing this window
+                    let still_focused = state.seats.values().any(|seat| {
+                        seat.keyboard_focused_window
+                            .as_ref()
+                            .is_some_and(|focused| focused.ptr_eq(&window))
+                    });
                     drop(state);
                     window.handle_ime(ImeInput::DeleteText);
-                    window.set_focused(false);
+                    if !still_focused {
+                        window.set_focused(false);
+                    }
                 }
             }
             wl_keyboard::Event::Modifiers {
@@ -1215,13 +2367,21 @@ impl Dispatch<wl_keyboard::WlKeyboard, ()> for WaylandClientStatePtr {
                 group,
                 ..
             } => {
-                let focused_window = state.keyboard_focused_window.clone();
+                let seat = state.seats.get_mut(seat_id).unwrap();
+                let focused_window = seat.keyboard_focused_window.clone();
 
-                let keymap_state = state.keymap_state.as_mut().unwrap();
+                let Some(keymap_state) = seat.keymap_state.as_mut() else {
+                    return;
+                };
                 let old_layout =
                     keymap_state.serialize_layout(xkbcommon::xkb::STATE_LAYOUT_EFFECTIVE);
                 keymap_state.update_mask(mods_depressed, mods_latched, mods_locked, 0, 0, group);
-                state.modifiers = Modifiers::from_xkb(keymap_state);
+                let modifiers = Modifiers::from_xkb(keymap_state);
+                seat.modifiers = modifiers;
+
+                if focused_window.is_some() {
+                    state.active_seat = Some(seat_id.clone());
+                }
 
                 if group != old_layout {
                     if let Some(mut callback) = state.common.callbacks.keyboard_layout_change.take()
@@ -1237,12 +2397,10 @@ impl Dispatch<wl_keyboard::WlKeyboard, ()> for WaylandClientStatePtr {
                     return;
                 };
 
-                let input = PlatformInput::ModifiersChanged(ModifiersChangedEvent {
-                    modifiers: state.modifiers,
-                });
+                let input = PlatformInput::ModifiersChanged(ModifiersChangedEvent { modifiers });
 
                 drop(state);
-                focused_window.handle_input(input);
+                focused_window.handle_input(seat_id, input);
             }
             wl_keyboard::Event::Key {
                 serial,
@@ -1250,95 +2408,131 @@ impl Dispatch<wl_keyboard::WlKeyboard, ()> for WaylandClientStatePtr {
                 state: WEnum::Value(key_state),
                 ..
             } => {
-                state.serial_tracker.update(SerialKind::KeyPress, serial);
+                state.active_seat = Some(seat_id.clone());
+                let seat = state.seats.get_mut(seat_id).unwrap();
+                seat.serial_tracker
+                    .update(InputSerialKind::KeyPress, serial);
 
-                let focused_window = state.keyboard_focused_window.clone();
-                let Some(focused_window) = focused_window else {
+                let Some(focused_window) = seat.keyboard_focused_window.clone() else {
                     return;
                 };
-                let focused_window = focused_window.clone();
 
-                let keymap_state = state.keymap_state.as_ref().unwrap();
+                let Some(keymap_state) = seat.keymap_state.as_ref() else {
+                    return;
+                };
                 let keycode = Keycode::from(key + MIN_KEYCODE);
                 let keysym = keymap_state.key_get_one_sym(keycode);
 
                 match key_state {
                     wl_keyboard::KeyState::Pressed if !keysym.is_modifier_key() => {
                         let mut keystroke =
-                            Keystroke::from_xkb(&keymap_state, state.modifiers, keycode);
-                        if let Some(mut compose) = state.compose_state.take() {
+                            Keystroke::from_xkb(&keymap_state, seat.modifiers, keycode);
+                        if let Some(mut compose) = seat.compose_state.take() {
                             compose.feed(keysym);
                             match compose.status() {
                                 xkb::Status::Composing => {
-                                    keystroke.key_char = None;
-                                    state.pre_edit_text =
-                                        compose.utf8().or(Keystroke::underlying_dead_key(keysym));
-                                    let pre_edit =
-                                        state.pre_edit_text.clone().unwrap_or(String::default());
+                                    // Shows the sequence typed so far as marked text, like
+                                    // other toolkits do.
+                                    let typed = keystroke.key_char.take();
+                                    let pre_edit = seat.pre_edit_text.get_or_insert_default();
+                                    if let Some(dead_key) = Keystroke::underlying_dead_key(keysym) {
+                                        pre_edit.push_str(&dead_key);
+                                    } else if keysym == xkb::Keysym::Multi_key {
+                                        pre_edit.push(COMPOSE_KEY_PRE_EDIT);
+                                    } else if let Some(typed) = typed {
+                                        pre_edit.push_str(&typed);
+                                    }
+                                    let pre_edit = pre_edit.clone();
                                     drop(state);
-                                    focused_window.handle_ime(ImeInput::SetMarkedText(pre_edit));
+                                    focused_window
+                                        .handle_ime(ImeInput::SetMarkedText(pre_edit, None));
                                     state = client.borrow_mut();
                                 }
 
                                 xkb::Status::Composed => {
-                                    state.pre_edit_text.take();
+                                    seat.pre_edit_text.take();
                                     keystroke.key_char = compose.utf8();
                                     if let Some(keysym) = compose.keysym() {
                                         keystroke.key = xkb::keysym_get_name(keysym);
                                     }
+                                    // Otherwise the composed text replaces the marked text.
+                                    if keystroke.key_char.is_none() {
+                                        drop(state);
+                                        focused_window.handle_ime(ImeInput::DeleteText);
+                                        state = client.borrow_mut();
+                                    }
                                 }
                                 xkb::Status::Cancelled => {
-                                    let pre_edit = state.pre_edit_text.take();
+                                    // The characters typed are kept, without the compose key.
+                                    let pre_edit = seat
+                                        .pre_edit_text
+                                        .take()
+                                        .map(|pre_edit| pre_edit.replace(COMPOSE_KEY_PRE_EDIT, ""))
+                                        .filter(|pre_edit| !pre_edit.is_empty());
                                     let new_pre_edit = Keystroke::underlying_dead_key(keysym);
-                                    state.pre_edit_text = new_pre_edit.clone();
+                                    seat.pre_edit_text = new_pre_edit.clone();
                                     drop(state);
                                     if let Some(pre_edit) = pre_edit {
                                         focused_window.handle_ime(ImeInput::InsertText(pre_edit));
                                     }
                                     if let Some(current_key) = new_pre_edit {
                                         focused_window
-                                            .handle_ime(ImeInput::SetMarkedText(current_key));
+                                            .handle_ime(ImeInput::SetMarkedText(current_key, None));
                                     }
                                     compose.feed(keysym);
                                     state = client.borrow_mut();
                                 }
                                 _ => {}
                             }
-                            state.compose_state = Some(compose);
+                            let Some(seat) = state.seats.get_mut(seat_id) else {
+                                return;
+                            };
+                            seat.compose_state = Some(compose);
                         }
                         let input = PlatformInput::KeyDown(KeyDownEvent {
                             keystroke: keystroke.clone(),
                             is_held: false,
                         });
 
-                        state.repeat.current_id += 1;
-                        state.repeat.current_keycode = Some(keycode);
-
-                        let rate = state.repeat.characters_per_second;
-                        let id = state.repeat.current_id;
+                        let Some(seat) = state.seats.get_mut(seat_id) else {
+                            return;
+                        };
+                        seat.repeat.current_id += 1;
+                        seat.repeat.current_keycode = Some(keycode);
+
+                        // The window may override the repeat rate configured for this seat
+                        let (rate, delay) = match focused_window.key_repeat_override() {
+                            Some(settings) => (settings.characters_per_second, settings.delay),
+                            None => (seat.repeat.characters_per_second, seat.repeat.delay),
+                        };
+                        let id = seat.repeat.current_id;
                         state
                             .loop_handle
-                            .insert_source(Timer::from_duration(state.repeat.delay), {
+                            .insert_source(Timer::from_duration(delay), {
                                 let input = PlatformInput::KeyDown(KeyDownEvent {
                                     keystroke,
                                     is_held: true,
                                 });
+                                let seat_id = seat_id.clone();
                                 move |_event, _metadata, this| {
                                     let mut client = this.get_client();
                                     let mut state = client.borrow_mut();
-                                    let is_repeating = id == state.repeat.current_id
-                                        && state.repeat.current_keycode.is_some()
-                                        && state.keyboard_focused_window.is_some();
+                                    let Some(seat) = state.seats.get(&seat_id) else {
+                                        return TimeoutAction::Drop;
+                                    };
+                                    let is_repeating = id == seat.repeat.current_id
+                                        && seat.repeat.current_keycode.is_some()
+                                        && seat.keyboard_focused_window.is_some();
 
                                     if !is_repeating || rate == 0 {
                                         return TimeoutAction::Drop;
                                     }
 
                                     let focused_window =
-                                        state.keyboard_focused_window.as_ref().unwrap().clone();
+                                        seat.keyboard_focused_window.as_ref().unwrap().clone();
 
                                     drop(state);
-                                    focused_window.handle_input(input.clone());
+                                    focused_window.handle_input(&seat_id, input.clone());
 
                                     TimeoutAction::ToDuration(Duration::from_secs(1) / rate)
                                 }
@@ -1346,19 +2540,19 @@ impl Dispatch<wl_keyboard::WlKeyboard, ()> for WaylandClientStatePtr {
                             .unwrap();
 
                         drop(state);
-                        focused_window.handle_input(input);
+                        focused_window.handle_input(seat_id, input);
                     }
                     wl_keyboard::KeyState::Released if !keysym.is_modifier_key() => {
                         let input = PlatformInput::KeyUp(KeyUpEvent {
-                            keystroke: Keystroke::from_xkb(keymap_state, state.modifiers, keycode),
+                            keystroke: Keystroke::from_xkb(keymap_state, seat.modifiers, keycode),
                         });
 
-                        if state.repeat.current_keycode == Some(keycode) {
-                            state.repeat.current_keycode = None;
+                        if seat.repeat.current_keycode == Some(keycode) {
+                            seat.repeat.current_keycode = None;
                         }
 
                         drop(state);
-                        focused_window.handle_input(input);
+                        focused_window.handle_input(seat_id, input);
                     }
                     _ => {}
                 }
@@ -1367,29 +2561,55 @@ impl Dispatch<wl_keyboard::WlKeyboard, ()> for WaylandClientStatePtr {
         }
     }
 }
-impl Dispatch<zwp_text_input_v3::ZwpTextInputV3, ()> for WaylandClientStatePtr {
+
+/// Converts the cursor of the preedit text from byte offsets to a UTF-16 range. The input
+/// method highlights the segment being converted with it, like the selection in native
+/// applications, or hides the cursor with negative offsets.
+fn preedit_cursor_utf16(text: &str, begin: i32, end: i32) -> Option<Range<usize>> {
+    let utf16_offset = |offset: i32| {
+        let prefix = text.get(..usize::try_from(offset).ok()?)?;
+        Some(prefix.encode_utf16().count())
+    };
+    let (begin, end) = (utf16_offset(begin)?, utf16_offset(end)?);
+    Some(begin.min(end)..begin.max(end))
+}
+
+fn set_cursor_rectangle(text_input: &zwp_text_input_v3::ZwpTextInputV3, area: Bounds<Pixels>) {
+    text_input.set_cursor_rectangle(
+        area.origin.x.0 as i32,
+        area.origin.y.0 as i32,
+        area.size.width.0.ceil() as i32,
+        area.size.height.0.ceil() as i32,
+    );
+}
+
+impl Dispatch<zwp_text_input_v3::ZwpTextInputV3, ObjectId> for WaylandClientStatePtr {
     fn event(
         this: &mut Self,
         text_input: &zwp_text_input_v3::ZwpTextInputV3,
         event: <zwp_text_input_v3::ZwpTextInputV3 as Proxy>::Event,
-        _: &(),
+        seat_id: &ObjectId,
         _: &Connection,
         _: &QueueHandle<Self>,
     ) {
+        trace::event(text_input, &event);
         let client = this.get_client();
         let mut state = client.borrow_mut();
+        let Some(seat) = state.seats.get_mut(seat_id) else {
+            return;
+        };
         match event {
             zwp_text_input_v3::Event::Enter { .. } => {
                 drop(state);
-                this.enable_ime();
+                this.enable_ime(seat_id);
             }
             zwp_text_input_v3::Event::Leave { .. } => {
                 drop(state);
-                this.disable_ime();
+                this.disable_ime(seat_id);
             }
             zwp_text_input_v3::Event::CommitString { text } => {
-                state.composing = false;
-                let Some(window) = state.keyboard_focused_window.clone() else {
+                seat.composing = false;
+                let Some(window) = seat.keyboard_focused_window.clone() else {
                     return;
                 };
 
@@ -1398,46 +2618,51 @@ impl Dispatch<zwp_text_input_v3::ZwpTextInputV3, ()> for WaylandClientStatePtr {
                     // IBus Intercepts keys like `a`, `b`, but those keys are needed for vim mode.
                     // We should only send ASCII characters to Zed, otherwise a user could remap a letter like `か` or `相`.
                     if commit_text.len() == 1 {
-                        window.handle_input(PlatformInput::KeyDown(KeyDownEvent {
-                            keystroke: Keystroke {
-                                modifiers: Modifiers::default(),
-                                key: commit_text.clone(),
-                                key_char: Some(commit_text),
-                            },
-                            is_held: false,
-                        }));
+                        window.handle_input(
+                            seat_id,
+                            PlatformInput::KeyDown(KeyDownEvent {
+                                keystroke: Keystroke {
+                                    modifiers: Modifiers::default(),
+                                    key: commit_text.clone(),
+                                    key_char: Some(commit_text),
+                                },
+                                is_held: false,
+                            }),
+                        );
                     } else {
                         window.handle_ime(ImeInput::InsertText(commit_text));
                     }
                 }
             }
-            zwp_text_input_v3::Event::PreeditString { text, .. } => {
-                state.composing = true;
-                state.ime_pre_edit = text;
+            zwp_text_input_v3::Event::PreeditString {
+                text,
+                cursor_begin,
+                cursor_end,
+            } => {
+                seat.composing = true;
+                seat.ime_pre_edit = text.map(|text| {
+                    let cursor = preedit_cursor_utf16(&text, cursor_begin, cursor_end);
+                    (text, cursor)
+                });
             }
             zwp_text_input_v3::Event::Done { serial } => {
-                let last_serial = state.serial_tracker.get(SerialKind::InputMethod);
-                state.serial_tracker.update(SerialKind::InputMethod, serial);
-                let Some(window) = state.keyboard_focused_window.clone() else {
+                let last_serial = seat.serial_tracker.get(SerialKind::InputMethod);
+                seat.serial_tracker.update(SerialKind::InputMethod, serial);
+                let Some(window) = seat.keyboard_focused_window.clone() else {
                     return;
                 };
 
-                if let Some(text) = state.ime_pre_edit.take() {
+                if let Some((text, cursor)) = seat.ime_pre_edit.take() {
                     drop(state);
-                    window.handle_ime(ImeInput::SetMarkedText(text));
+                    window.handle_ime(ImeInput::SetMarkedText(text, cursor));
                     if let Some(area) = window.get_ime_area() {
-                        text_input.set_cursor_rectangle(
-                            area.origin.x.0 as i32,
-                            area.origin.y.0 as i32,
-                            area.size.width.0 as i32,
-                            area.size.height.0 as i32,
-                        );
+                        set_cursor_rectangle(text_input, area);
                         if last_serial == serial {
                             text_input.commit();
                         }
                     }
                 } else {
-                    state.composing = false;
+                    seat.composing = false;
                     drop(state);
                     window.handle_ime(ImeInput::DeleteText);
                 }
@@ -1447,16 +2672,16 @@ impl Dispatch<zwp_text_input_v3::ZwpTextInputV3, ()> for WaylandClientStatePtr {
     }
 }
 
-fn linux_button_to_gpui(button: u32) -> Option<MouseButton> {
-    // These values are coming from <linux/input-event-codes.h>.
-    const BTN_LEFT: u32 = 0x110;
-    const BTN_RIGHT: u32 = 0x111;
-    const BTN_MIDDLE: u32 = 0x112;
-    const BTN_SIDE: u32 = 0x113;
-    const BTN_EXTRA: u32 = 0x114;
-    const BTN_FORWARD: u32 = 0x115;
-    const BTN_BACK: u32 = 0x116;
+// These values are coming from <linux/input-event-codes.h>.
+const BTN_LEFT: u32 = 0x110;
+const BTN_RIGHT: u32 = 0x111;
+const BTN_MIDDLE: u32 = 0x112;
+const BTN_SIDE: u32 = 0x113;
+const BTN_EXTRA: u32 = 0x114;
+const BTN_FORWARD: u32 = 0x115;
+const BTN_BACK: u32 = 0x116;
 
+fn linux_button_to_gpui(button: u32) -> Option<MouseButton> {
     Some(match button {
         BTN_LEFT => MouseButton::Left,
         BTN_RIGHT => MouseButton::Right,
@@ -1467,17 +2692,31 @@ fn linux_button_to_gpui(button: u32) -> Option<MouseButton> {
     })
 }
 
-impl Dispatch<wl_pointer::WlPointer, ()> for WaylandClientStatePtr {
+pub(super) fn gpui_button_to_linux(button: MouseButton) -> u32 {
+    match button {
+        MouseButton::Left => BTN_LEFT,
+        MouseButton::Right => BTN_RIGHT,
+        MouseButton::Middle => BTN_MIDDLE,
+        MouseButton::Navigate(NavigationDirection::Back) => BTN_BACK,
+        MouseButton::Navigate(NavigationDirection::Forward) => BTN_FORWARD,
+    }
+}
+
+impl Dispatch<wl_pointer::WlPointer, ObjectId> for WaylandClientStatePtr {
     fn event(
         this: &mut Self,
         wl_pointer: &wl_pointer::WlPointer,
         event: wl_pointer::Event,
-        _: &(),
+        seat_id: &ObjectId,
         _: &Connection,
         _: &QueueHandle<Self>,
     ) {
+        trace::event(wl_pointer, &event);
         let mut client = this.get_client();
         let mut state = client.borrow_mut();
+        if !state.seats.contains_key(seat_id) {
+            return;
+        }
 
         match event {
             wl_pointer::Event::Enter {
@@ -1487,18 +2726,23 @@ impl Dispatch<wl_pointer::WlPointer, ()> for WaylandClientStatePtr {
                 surface_y,
                 ..
             } => {
-                state.serial_tracker.update(SerialKind::MouseEnter, serial);
-                state.mouse_location = Some(point(px(surface_x as f32), px(surface_y as f32)));
-                state.button_pressed = None;
-
-                if let Some(window) = get_window(&mut state, &surface.id()) {
-                    state.mouse_focused_window = Some(window.clone());
-
-                    if state.enter_token.is_some() {
-                        state.enter_token = None;
+                let window = get_window(&mut state, &surface.id());
+                let cursor_style = state.cursor_style;
+                state.active_seat = Some(seat_id.clone());
+                let seat = state.seats.get_mut(seat_id).unwrap();
+                seat.serial_tracker
+                    .update(InputSerialKind::PointerEnter, serial);
+                seat.mouse_location = Some(point(px(surface_x as f32), px(surface_y as f32)));
+                seat.button_pressed = None;
+
+                if let Some(window) = window {
+                    seat.mouse_focused_window = Some(window.clone());
+
+                    if seat.enter_token.is_some() {
+                        seat.enter_token = None;
                     }
-                    if let Some(style) = state.cursor_style {
-                        if let Some(cursor_shape_device) = &state.cursor_shape_device {
+                    if let Some(style) = cursor_style {
+                        if let Some(cursor_shape_device) = &seat.cursor_shape_device {
                             cursor_shape_device.set_shape(serial, style.to_shape());
                         } else {
                             let scale = window.primary_output_scale();
@@ -1515,18 +2759,19 @@ impl Dispatch<wl_pointer::WlPointer, ()> for WaylandClientStatePtr {
                 }
             }
             wl_pointer::Event::Leave { .. } => {
-                if let Some(focused_window) = state.mouse_focused_window.clone() {
+                let seat = state.seats.get_mut(seat_id).unwrap();
+                if let Some(focused_window) = seat.mouse_focused_window.clone() {
                     let input = PlatformInput::MouseExited(MouseExitEvent {
-                        position: state.mouse_location.unwrap(),
-                        pressed_button: state.button_pressed,
-                        modifiers: state.modifiers,
+                        position: seat.mouse_location.unwrap(),
+                        pressed_button: seat.button_pressed,
+                        modifiers: seat.modifiers,
                     });
-                    state.mouse_focused_window = None;
-                    state.mouse_location = None;
-                    state.button_pressed = None;
+                    seat.mouse_focused_window = None;
+                    seat.mouse_location = None;
+                    seat.button_pressed = None;
 
                     drop(state);
-                    focused_window.handle_input(input);
+                    focused_window.handle_input(seat_id, input);
                     focused_window.set_hovered(false);
                 }
             }
@@ -1535,26 +2780,28 @@ impl Dispatch<wl_pointer::WlPointer, ()> for WaylandClientStatePtr {
                 surface_y,
                 ..
             } => {
-                if state.mouse_focused_window.is_none() {
+                state.active_seat = Some(seat_id.clone());
+                let seat = state.seats.get_mut(seat_id).unwrap();
+                if seat.mouse_focused_window.is_none() {
                     return;
                 }
-                state.mouse_location = Some(point(px(surface_x as f32), px(surface_y as f32)));
+                seat.mouse_location = Some(point(px(surface_x as f32), px(surface_y as f32)));
 
-                if let Some(window) = state.mouse_focused_window.clone() {
-                    if state
+                if let Some(window) = seat.mouse_focused_window.clone() {
+                    if seat
                         .keyboard_focused_window
                         .as_ref()
                         .map_or(false, |keyboard_window| window.ptr_eq(&keyboard_window))
                     {
-                        state.enter_token = None;
+                        seat.enter_token = None;
                     }
                     let input = PlatformInput::MouseMove(MouseMoveEvent {
-                        position: state.mouse_location.unwrap(),
-                        pressed_button: state.button_pressed,
-                        modifiers: state.modifiers,
+                        position: seat.mouse_location.unwrap(),
+                        pressed_button: seat.button_pressed,
+                        modifiers: seat.modifiers,
                     });
                     drop(state);
-                    window.handle_input(input);
+                    window.handle_input(seat_id, input);
                 }
             }
             wl_pointer::Event::Button {
@@ -1563,24 +2810,27 @@ impl Dispatch<wl_pointer::WlPointer, ()> for WaylandClientStatePtr {
                 state: WEnum::Value(button_state),
                 ..
             } => {
-                state.serial_tracker.update(SerialKind::MousePress, serial);
+                state.active_seat = Some(seat_id.clone());
+                let seat = state.seats.get_mut(seat_id).unwrap();
+                seat.serial_tracker
+                    .update(InputSerialKind::PointerButton, serial);
                 let button = linux_button_to_gpui(button);
                 let Some(button) = button else { return };
-                if state.mouse_focused_window.is_none() {
+                if seat.mouse_focused_window.is_none() {
                     return;
                 }
                 match button_state {
                     wl_pointer::ButtonState::Pressed => {
-                        if let Some(window) = state.keyboard_focused_window.clone() {
-                            if state.composing && state.text_input.is_some() {
+                        if let Some(window) = seat.keyboard_focused_window.clone() {
+                            if seat.composing && seat.text_input.is_some() {
                                 drop(state);
                                 // text_input_v3 don't have something like a reset function
-                                this.disable_ime();
-                                this.enable_ime();
+                                this.disable_ime(seat_id);
+                                this.enable_ime(seat_id);
                                 window.handle_ime(ImeInput::UnmarkText);
                                 state = client.borrow_mut();
                             } else if let (Some(text), Some(compose)) =
-                                (state.pre_edit_text.take(), state.compose_state.as_mut())
+                                (seat.pre_edit_text.take(), seat.compose_state.as_mut())
                             {
                                 compose.reset();
                                 drop(state);
@@ -1588,53 +2838,56 @@ impl Dispatch<wl_pointer::WlPointer, ()> for WaylandClientStatePtr {
                                 state = client.borrow_mut();
                             }
                         }
-                        let click_elapsed = state.click.last_click.elapsed();
+                        let Some(seat) = state.seats.get_mut(seat_id) else {
+                            return;
+                        };
+                        let click_elapsed = seat.click.last_click.elapsed();
 
                         if click_elapsed < DOUBLE_CLICK_INTERVAL
-                            && state
+                            && seat
                                 .click
                                 .last_mouse_button
                                 .is_some_and(|prev_button| prev_button == button)
                             && is_within_click_distance(
-                                state.click.last_location,
-                                state.mouse_location.unwrap(),
+                                seat.click.last_location,
+                                seat.mouse_location.unwrap(),
                             )
                         {
-                            state.click.current_count += 1;
+                            seat.click.current_count += 1;
                         } else {
-                            state.click.current_count = 1;
+                            seat.click.current_count = 1;
                         }
 
-                        state.click.last_click = Instant::now();
-                        state.click.last_mouse_button = Some(button);
-                        state.click.last_location = state.mouse_location.unwrap();
+                        seat.click.last_click = Instant::now();
+                        seat.click.last_mouse_button = Some(button);
+                        seat.click.last_location = seat.mouse_location.unwrap();
 
-                        state.button_pressed = Some(button);
+                        seat.button_pressed = Some(button);
 
-                        if let Some(window) = state.mouse_focused_window.clone() {
+                        if let Some(window) = seat.mouse_focused_window.clone() {
                             let input = PlatformInput::MouseDown(MouseDownEvent {
                                 button,
-                                position: state.mouse_location.unwrap(),
-                                modifiers: state.modifiers,
-                                click_count: state.click.current_count,
-                                first_mouse: state.enter_token.take().is_some(),
+                                position: seat.mouse_location.unwrap(),
+                                modifiers: seat.modifiers,
+                                click_count: seat.click.current_count,
+                                first_mouse: seat.enter_token.take().is_some(),
                             });
                             drop(state);
-                            window.handle_input(input);
+                            window.handle_input(seat_id, input);
                         }
                     }
                     wl_pointer::ButtonState::Released => {
-                        state.button_pressed = None;
+                        seat.button_pressed = None;
 
-                        if let Some(window) = state.mouse_focused_window.clone() {
+                        if let Some(window) = seat.mouse_focused_window.clone() {
                             let input = PlatformInput::MouseUp(MouseUpEvent {
                                 button,
-                                position: state.mouse_location.unwrap(),
-                                modifiers: state.modifiers,
-                                click_count: state.click.current_count,
+                                position: seat.mouse_location.unwrap(),
+                                modifiers: seat.modifiers,
+                                click_count: seat.click.current_count,
                             });
                             drop(state);
-                            window.handle_input(input);
+                            window.handle_input(seat_id, input);
                         }
                     }
                     _ => {}
@@ -1645,28 +2898,30 @@ impl Dispatch<wl_pointer::WlPointer, ()> for WaylandClientStatePtr {
             wl_pointer::Event::AxisSource {
                 axis_source: WEnum::Value(axis_source),
             } => {
-                state.axis_source = axis_source;
+                let seat = state.seats.get_mut(seat_id).unwrap();
+                seat.axis_source = axis_source;
             }
             wl_pointer::Event::Axis {
                 axis: WEnum::Value(axis),
                 value,
                 ..
             } => {
-                if state.axis_source == AxisSource::Wheel {
+                let seat = state.seats.get_mut(seat_id).unwrap();
+                if seat.axis_source == AxisSource::Wheel {
                     return;
                 }
-                let axis = if state.modifiers.shift {
+                let axis = if seat.modifiers.shift {
                     wl_pointer::Axis::HorizontalScroll
                 } else {
                     axis
                 };
                 let axis_modifier = match axis {
-                    wl_pointer::Axis::VerticalScroll => state.vertical_modifier,
-                    wl_pointer::Axis::HorizontalScroll => state.horizontal_modifier,
+                    wl_pointer::Axis::VerticalScroll => seat.vertical_modifier,
+                    wl_pointer::Axis::HorizontalScroll => seat.horizontal_modifier,
                     _ => 1.0,
                 };
-                state.scroll_event_received = true;
-                let scroll_delta = state
+                seat.scroll_event_received = true;
+                let scroll_delta = seat
                     .continuous_scroll_delta
                     .get_or_insert(point(px(0.0), px(0.0)));
                 let modifier = 3.0;
@@ -1684,19 +2939,20 @@ impl Dispatch<wl_pointer::WlPointer, ()> for WaylandClientStatePtr {
                 axis: WEnum::Value(axis),
                 discrete,
             } => {
-                state.scroll_event_received = true;
-                let axis = if state.modifiers.shift {
+                let seat = state.seats.get_mut(seat_id).unwrap();
+                seat.scroll_event_received = true;
+                let axis = if seat.modifiers.shift {
                     wl_pointer::Axis::HorizontalScroll
                 } else {
                     axis
                 };
                 let axis_modifier = match axis {
-                    wl_pointer::Axis::VerticalScroll => state.vertical_modifier,
-                    wl_pointer::Axis::HorizontalScroll => state.horizontal_modifier,
+                    wl_pointer::Axis::VerticalScroll => seat.vertical_modifier,
+                    wl_pointer::Axis::HorizontalScroll => seat.horizontal_modifier,
                     _ => 1.0,
                 };
 
-                let scroll_delta = state.discrete_scroll_delta.get_or_insert(point(0.0, 0.0));
+                let scroll_delta = seat.discrete_scroll_delta.get_or_insert(point(0.0, 0.0));
                 match axis {
                     wl_pointer::Axis::VerticalScroll => {
                         scroll_delta.y += discrete as f32 * axis_modifier * SCROLL_LINES;
@@ -1711,19 +2967,20 @@ impl Dispatch<wl_pointer::WlPointer, ()> for WaylandClientStatePtr {
                 axis: WEnum::Value(axis),
                 value120,
             } => {
-                state.scroll_event_received = true;
-                let axis = if state.modifiers.shift {
+                let seat = state.seats.get_mut(seat_id).unwrap();
+                seat.scroll_event_received = true;
+                let axis = if seat.modifiers.shift {
                     wl_pointer::Axis::HorizontalScroll
                 } else {
                     axis
                 };
                 let axis_modifier = match axis {
-                    wl_pointer::Axis::VerticalScroll => state.vertical_modifier,
-                    wl_pointer::Axis::HorizontalScroll => state.horizontal_modifier,
+                    wl_pointer::Axis::VerticalScroll => seat.vertical_modifier,
+                    wl_pointer::Axis::HorizontalScroll => seat.horizontal_modifier,
                     _ => unreachable!(),
                 };
 
-                let scroll_delta = state.discrete_scroll_delta.get_or_insert(point(0.0, 0.0));
+                let scroll_delta = seat.discrete_scroll_delta.get_or_insert(point(0.0, 0.0));
                 let wheel_percent = value120 as f32 / 120.0;
                 match axis {
                     wl_pointer::Axis::VerticalScroll => {
@@ -1736,31 +2993,32 @@ impl Dispatch<wl_pointer::WlPointer, ()> for WaylandClientStatePtr {
                 }
             }
             wl_pointer::Event::Frame => {
-                if state.scroll_event_received {
-                    state.scroll_event_received = false;
-                    let continuous = state.continuous_scroll_delta.take();
-                    let discrete = state.discrete_scroll_delta.take();
+                let seat = state.seats.get_mut(seat_id).unwrap();
+                if seat.scroll_event_received {
+                    seat.scroll_event_received = false;
+                    let continuous = seat.continuous_scroll_delta.take();
+                    let discrete = seat.discrete_scroll_delta.take();
                     if let Some(continuous) = continuous {
-                        if let Some(window) = state.mouse_focused_window.clone() {
+                        if let Some(window) = seat.mouse_focused_window.clone() {
                             let input = PlatformInput::ScrollWheel(ScrollWheelEvent {
-                                position: state.mouse_location.unwrap(),
+                                position: seat.mouse_location.unwrap(),
                                 delta: ScrollDelta::Pixels(continuous),
-                                modifiers: state.modifiers,
+                                modifiers: seat.modifiers,
                                 touch_phase: TouchPhase::Moved,
                             });
                             drop(state);
-                            window.handle_input(input);
+                            window.handle_input(seat_id, input);
                         }
                     } else if let Some(discrete) = discrete {
-                        if let Some(window) = state.mouse_focused_window.clone() {
+                        if let Some(window) = seat.mouse_focused_window.clone() {
                             let input = PlatformInput::ScrollWheel(ScrollWheelEvent {
-                                position: state.mouse_location.unwrap(),
+                                position: seat.mouse_location.unwrap(),
                                 delta: ScrollDelta::Lines(discrete),
-                                modifiers: state.modifiers,
+                                modifiers: seat.modifiers,
                                 touch_phase: TouchPhase::Moved,
                             });
                             drop(state);
-                            window.handle_input(input);
+                            window.handle_input(seat_id, input);
                         }
                     }
                 }
@@ -1770,15 +3028,121 @@ impl Dispatch<wl_pointer::WlPointer, ()> for WaylandClientStatePtr {
     }
 }
 
+impl Dispatch<wl_touch::WlTouch, ObjectId> for WaylandClientStatePtr {
+    fn event(
+        this: &mut Self,
+        wl_touch: &wl_touch::WlTouch,
+        event: wl_touch::Event,
+        seat_id: &ObjectId,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        trace::event(wl_touch, &event);
+        let client = this.get_client();
+        let mut state = client.borrow_mut();
+        if !state.seats.contains_key(seat_id) {
+            return;
+        }
+
+        match event {
+            wl_touch::Event::Down {
+                serial,
+                surface,
+                id,
+                x,
+                y,
+                ..
+            } => {
+                let Some(window) = get_window(&mut state, &surface.id()) else {
+                    return;
+                };
+                state.active_seat = Some(seat_id.clone());
+                let seat = state.seats.get_mut(seat_id).unwrap();
+                if seat.primary_touch.is_some() {
+                    return;
+                }
+                // Interactive moves and resizes started by the touch need its serial.
+                seat.serial_tracker
+                    .update(InputSerialKind::TouchDown, serial);
+                let position = point(px(x as f32), px(y as f32));
+                seat.primary_touch = Some(PrimaryTouch {
+                    id,
+                    window: window.clone(),
+                    position,
+                });
+                let modifiers = seat.modifiers;
+                drop(state);
+                window.handle_input(
+                    seat_id,
+                    PlatformInput::MouseMove(MouseMoveEvent {
+                        position,
+                        pressed_button: None,
+                        modifiers,
+                    }),
+                );
+                window.handle_input(
+                    seat_id,
+                    PlatformInput::MouseDown(MouseDownEvent {
+                        button: MouseButton::Left,
+                        position,
+                        modifiers,
+                        click_count: 1,
+                        first_mouse: false,
+                    }),
+                );
+            }
+            wl_touch::Event::Motion { id, x, y, .. } => {
+                let seat = state.seats.get_mut(seat_id).unwrap();
+                let Some(touch) = seat.primary_touch.as_mut().filter(|touch| touch.id == id) else {
+                    return;
+                };
+                touch.position = point(px(x as f32), px(y as f32));
+                let input = PlatformInput::MouseMove(MouseMoveEvent {
+                    position: touch.position,
+                    pressed_button: Some(MouseButton::Left),
+                    modifiers: seat.modifiers,
+                });
+                let window = touch.window.clone();
+                drop(state);
+                window.handle_input(seat_id, input);
+            }
+            wl_touch::Event::Up { id, .. } => {
+                let seat = state.seats.get_mut(seat_id).unwrap();
+                if !seat
+                    .primary_touch
+                    .as_ref()
+                    .is_some_and(|touch| touch.id == id)
+                {
+                    return;
+                }
+                if let Some((window, input)) = seat.release_primary_touch() {
+                    drop(state);
+                    window.handle_input(seat_id, input);
+                }
+            }
+            // The compositor took the touches over, e.g. to move the window.
+            wl_touch::Event::Cancel => {
+                let seat = state.seats.get_mut(seat_id).unwrap();
+                if let Some((window, input)) = seat.release_primary_touch() {
+                    drop(state);
+                    window.handle_input(seat_id, input);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
 impl Dispatch<wp_fractional_scale_v1::WpFractionalScaleV1, ObjectId> for WaylandClientStatePtr {
     fn event(
         this: &mut Self,
-        _: &wp_fractional_scale_v1::WpFractionalScaleV1,
+        proxy: &wp_fractional_scale_v1::WpFractionalScaleV1,
         event: <wp_fractional_scale_v1::WpFractionalScaleV1 as Proxy>::Event,
         surface_id: &ObjectId,
         _: &Connection,
         _: &QueueHandle<Self>,
     ) {
+        trace::event(proxy, &event);
         let client = this.get_client();
         let mut state = client.borrow_mut();
 
@@ -1791,17 +3155,42 @@ impl Dispatch<wp_fractional_scale_v1::WpFractionalScaleV1, ObjectId> for Wayland
     }
 }
 
+impl Dispatch<zwp_linux_dmabuf_feedback_v1::ZwpLinuxDmabufFeedbackV1, ObjectId>
+    for WaylandClientStatePtr
+{
+    fn event(
+        this: &mut Self,
+        proxy: &zwp_linux_dmabuf_feedback_v1::ZwpLinuxDmabufFeedbackV1,
+        event: zwp_linux_dmabuf_feedback_v1::Event,
+        surface_id: &ObjectId,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        trace::event(proxy, &event);
+        let client = this.get_client();
+        let mut state = client.borrow_mut();
+
+        let Some(window) = get_window(&mut state, surface_id) else {
+            return;
+        };
+
+        drop(state);
+        window.handle_dmabuf_feedback_event(event);
+    }
+}
+
 impl Dispatch<zxdg_toplevel_decoration_v1::ZxdgToplevelDecorationV1, ObjectId>
     for WaylandClientStatePtr
 {
     fn event(
         this: &mut Self,
-        _: &zxdg_toplevel_decoration_v1::ZxdgToplevelDecorationV1,
+        proxy: &zxdg_toplevel_decoration_v1::ZxdgToplevelDecorationV1,
         event: zxdg_toplevel_decoration_v1::Event,
         surface_id: &ObjectId,
         _: &Connection,
         _: &QueueHandle<Self>,
     ) {
+        trace::event(proxy, &event);
         let client = this.get_client();
         let mut state = client.borrow_mut();
         let Some(window) = get_window(&mut state, surface_id) else {
@@ -1813,15 +3202,16 @@ impl Dispatch<zxdg_toplevel_decoration_v1::ZxdgToplevelDecorationV1, ObjectId>
     }
 }
 
-impl Dispatch<wl_data_device::WlDataDevice, ()> for WaylandClientStatePtr {
+impl Dispatch<wl_data_device::WlDataDevice, ObjectId> for WaylandClientStatePtr {
     fn event(
         this: &mut Self,
-        _: &wl_data_device::WlDataDevice,
+        proxy: &wl_data_device::WlDataDevice,
         event: wl_data_device::Event,
-        _: &(),
+        seat_id: &ObjectId,
         _: &Connection,
         _: &QueueHandle<Self>,
     ) {
+        trace::event(proxy, &event);
         let client = this.get_client();
         let mut state = client.borrow_mut();
 
@@ -1855,7 +3245,9 @@ impl Dispatch<wl_data_device::WlDataDevice, ()> for WaylandClientStatePtr {
                 y,
                 id: data_offer,
             } => {
-                state.serial_tracker.update(SerialKind::DataDevice, serial);
+                if let Some(seat) = state.seats.get_mut(seat_id) {
+                    seat.serial_tracker.update(SerialKind::DataDevice, serial);
+                }
                 if let Some(data_offer) = data_offer {
                     let Some(drag_window) = get_window(&mut state, &surface.id()) else {
                         return;
@@ -1915,7 +3307,7 @@ impl Dispatch<wl_data_device::WlDataDevice, ()> for WaylandClientStatePtr {
                             state.drag.position = position;
 
                             drop(state);
-                            drag_window.handle_input(input);
+                            drag_window.handle_input(seat_id, input);
                         })
                         .detach();
                 }
@@ -1929,7 +3321,7 @@ impl Dispatch<wl_data_device::WlDataDevice, ()> for WaylandClientStatePtr {
 
                 let input = PlatformInput::FileDrop(FileDropEvent::Pending { position });
                 drop(state);
-                drag_window.handle_input(input);
+                drag_window.handle_input(seat_id, input);
             }
             wl_data_device::Event::Leave => {
                 let Some(drag_window) = state.drag.window.clone() else {
@@ -1943,7 +3335,7 @@ impl Dispatch<wl_data_device::WlDataDevice, ()> for WaylandClientStatePtr {
 
                 let input = PlatformInput::FileDrop(FileDropEvent::Exited {});
                 drop(state);
-                drag_window.handle_input(input);
+                drag_window.handle_input(seat_id, input);
             }
             wl_data_device::Event::Drop => {
                 let Some(drag_window) = state.drag.window.clone() else {
@@ -1960,7 +3352,7 @@ impl Dispatch<wl_data_device::WlDataDevice, ()> for WaylandClientStatePtr {
                     position: state.drag.position,
                 });
                 drop(state);
-                drag_window.handle_input(input);
+                drag_window.handle_input(seat_id, input);
             }
             _ => {}
         }
@@ -1980,6 +3372,7 @@ impl Dispatch<wl_data_offer::WlDataOffer, ()> for WaylandClientStatePtr {
         _: &Connection,
         _: &QueueHandle<Self>,
     ) {
+        trace::event(data_offer, &event);
         let client = this.get_client();
         let mut state = client.borrow_mut();
 
@@ -1987,9 +3380,11 @@ impl Dispatch<wl_data_offer::WlDataOffer, ()> for WaylandClientStatePtr {
             wl_data_offer::Event::Offer { mime_type } => {
                 // Drag and drop
                 if mime_type == FILE_LIST_MIME_TYPE {
-                    let serial = state.serial_tracker.get(SerialKind::DataDevice);
-                    let mime_type = mime_type.clone();
-                    data_offer.accept(serial, Some(mime_type));
+                    if let Some(seat) = state.active_seat() {
+                        let serial = seat.serial_tracker.get(SerialKind::DataDevice);
+                        let mime_type = mime_type.clone();
+                        data_offer.accept(serial, Some(mime_type));
+                    }
                 }
 
                 // Clipboard
@@ -2015,6 +3410,7 @@ impl Dispatch<wl_data_source::WlDataSource, ()> for WaylandClientStatePtr {
         _: &Connection,
         _: &QueueHandle<Self>,
     ) {
+        trace::event(data_source, &event);
         let client = this.get_client();
         let mut state = client.borrow_mut();
 
@@ -2030,17 +3426,49 @@ impl Dispatch<wl_data_source::WlDataSource, ()> for WaylandClientStatePtr {
     }
 }
 
-impl Dispatch<zwp_primary_selection_device_v1::ZwpPrimarySelectionDeviceV1, ()>
+impl Dispatch<wl_data_source::WlDataSource, ToplevelDragSource> for WaylandClientStatePtr {
+    fn event(
+        this: &mut Self,
+        data_source: &wl_data_source::WlDataSource,
+        event: wl_data_source::Event,
+        _: &ToplevelDragSource,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        trace::event(data_source, &event);
+        let client = this.get_client();
+        let mut state = client.borrow_mut();
+
+        match event {
+            // Dropping the window somewhere that isn't a drop target cancels the drag, which
+            // leaves the window there as well.
+            wl_data_source::Event::DndFinished | wl_data_source::Event::Cancelled => {
+                if state
+                    .toplevel_drag
+                    .as_ref()
+                    .is_some_and(|drag| &drag.data_source == data_source)
+                {
+                    state.toplevel_drag.take().unwrap().end();
+                }
+            }
+            // The drag carries no data, dropping the fd of `Send` ends the transfer.
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<zwp_primary_selection_device_v1::ZwpPrimarySelectionDeviceV1, ObjectId>
     for WaylandClientStatePtr
 {
     fn event(
         this: &mut Self,
-        _: &zwp_primary_selection_device_v1::ZwpPrimarySelectionDeviceV1,
+        proxy: &zwp_primary_selection_device_v1::ZwpPrimarySelectionDeviceV1,
         event: zwp_primary_selection_device_v1::Event,
-        _: &(),
+        _: &ObjectId,
         _: &Connection,
         _: &QueueHandle<Self>,
     ) {
+        trace::event(proxy, &event);
         let client = this.get_client();
         let mut state = client.borrow_mut();
 
@@ -2073,12 +3501,13 @@ impl Dispatch<zwp_primary_selection_offer_v1::ZwpPrimarySelectionOfferV1, ()>
 {
     fn event(
         this: &mut Self,
-        _data_offer: &zwp_primary_selection_offer_v1::ZwpPrimarySelectionOfferV1,
+        data_offer: &zwp_primary_selection_offer_v1::ZwpPrimarySelectionOfferV1,
         event: zwp_primary_selection_offer_v1::Event,
         _: &(),
         _: &Connection,
         _: &QueueHandle<Self>,
     ) {
+        trace::event(data_offer, &event);
         let client = this.get_client();
         let mut state = client.borrow_mut();
 
@@ -2104,6 +3533,7 @@ impl Dispatch<zwp_primary_selection_source_v1::ZwpPrimarySelectionSourceV1, ()>
         _: &Connection,
         _: &QueueHandle<Self>,
     ) {
+        trace::event(selection_source, &event);
         let client = this.get_client();
         let mut state = client.borrow_mut();
 
@@ -0,0 +1,266 @@
+use anyhow::{anyhow, Result};
+use wayland_backend::protocol::WEnum;
+use wayland_client::Proxy;
+
+use super::protocols::ext_workspace_v1::{
+    ext_workspace_group_handle_v1, ext_workspace_handle_v1, ext_workspace_manager_v1,
+};
+use crate::{
+    DesktopWorkspace, DesktopWorkspaceGroup, DesktopWorkspaceGroupId, DesktopWorkspaceId,
+    DesktopWorkspaceRequest, DisplayId,
+};
+
+/// The workspaces and workspace groups advertised with ext-workspace.
+#[derive(Default)]
+pub(crate) struct DesktopWorkspaces {
+    /// Whether the compositor sent a complete state yet.
+    done: bool,
+    groups: Vec<(
+        ext_workspace_group_handle_v1::ExtWorkspaceGroupHandleV1,
+        DesktopWorkspaceGroup,
+    )>,
+    workspaces: Vec<(
+        ext_workspace_handle_v1::ExtWorkspaceHandleV1,
+        DesktopWorkspace,
+    )>,
+}
+
+impl DesktopWorkspaces {
+    pub(crate) fn groups(&self) -> Vec<DesktopWorkspaceGroup> {
+        if !self.done {
+            return Vec::new();
+        }
+        self.groups.iter().map(|(_, group)| group.clone()).collect()
+    }
+
+    pub(crate) fn workspaces(&self) -> Vec<DesktopWorkspace> {
+        if !self.done {
+            return Vec::new();
+        }
+        self.workspaces
+            .iter()
+            .map(|(_, workspace)| workspace.clone())
+            .collect()
+    }
+
+    pub(crate) fn done(&mut self) {
+        self.done = true;
+    }
+
+    /// Forgets the workspaces after the compositor stopped sending them.
+    pub(crate) fn finished(&mut self) {
+        self.done = false;
+        for (proxy, _) in self.groups.drain(..) {
+            proxy.destroy();
+        }
+        for (proxy, _) in self.workspaces.drain(..) {
+            proxy.destroy();
+        }
+    }
+
+    pub(crate) fn add_group(
+        &mut self,
+        proxy: ext_workspace_group_handle_v1::ExtWorkspaceGroupHandleV1,
+    ) {
+        let group = DesktopWorkspaceGroup {
+            id: DesktopWorkspaceGroupId(proxy.id().protocol_id()),
+            displays: Vec::new(),
+            workspaces: Vec::new(),
+            can_create_workspace: false,
+        };
+        self.groups.push((proxy, group));
+    }
+
+    pub(crate) fn add_workspace(&mut self, proxy: ext_workspace_handle_v1::ExtWorkspaceHandleV1) {
+        let workspace = DesktopWorkspace {
+            id: DesktopWorkspaceId(proxy.id().protocol_id()),
+            stable_id: None,
+            name: Default::default(),
+            coordinates: Vec::new(),
+            active: false,
+            urgent: false,
+            hidden: false,
+            group: None,
+            can_activate: false,
+            can_deactivate: false,
+            can_remove: false,
+            can_assign: false,
+        };
+        self.workspaces.push((proxy, workspace));
+    }
+
+    pub(crate) fn handle_group_event(
+        &mut self,
+        proxy: &ext_workspace_group_handle_v1::ExtWorkspaceGroupHandleV1,
+        event: ext_workspace_group_handle_v1::Event,
+    ) {
+        let id = DesktopWorkspaceGroupId(proxy.id().protocol_id());
+        let Some(ix) = self.groups.iter().position(|(_, group)| group.id == id) else {
+            return;
+        };
+        let group = &mut self.groups[ix].1;
+        match event {
+            ext_workspace_group_handle_v1::Event::Capabilities {
+                capabilities: WEnum::Value(capabilities),
+            } => {
+                group.can_create_workspace = capabilities
+                    .contains(ext_workspace_group_handle_v1::GroupCapabilities::CreateWorkspace);
+            }
+            ext_workspace_group_handle_v1::Event::OutputEnter { output } => {
+                group.displays.push(DisplayId(output.id().protocol_id()));
+            }
+            ext_workspace_group_handle_v1::Event::OutputLeave { output } => {
+                let display = DisplayId(output.id().protocol_id());
+                group.displays.retain(|id| *id != display);
+            }
+            ext_workspace_group_handle_v1::Event::WorkspaceEnter { workspace } => {
+                let workspace_id = DesktopWorkspaceId(workspace.id().protocol_id());
+                group.workspaces.push(workspace_id);
+                if let Some(workspace) = self.workspace_mut(workspace_id) {
+                    workspace.group = Some(id);
+                }
+            }
+            ext_workspace_group_handle_v1::Event::WorkspaceLeave { workspace } => {
+                let workspace_id = DesktopWorkspaceId(workspace.id().protocol_id());
+                group.workspaces.retain(|id| *id != workspace_id);
+                if let Some(workspace) = self.workspace_mut(workspace_id) {
+                    if workspace.group == Some(id) {
+                        workspace.group = None;
+                    }
+                }
+            }
+            ext_workspace_group_handle_v1::Event::Removed => {
+                let (proxy, _) = self.groups.remove(ix);
+                proxy.destroy();
+                for (_, workspace) in &mut self.workspaces {
+                    if workspace.group == Some(id) {
+                        workspace.group = None;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub(crate) fn handle_workspace_event(
+        &mut self,
+        proxy: &ext_workspace_handle_v1::ExtWorkspaceHandleV1,
+        event: ext_workspace_handle_v1::Event,
+    ) {
+        let id = DesktopWorkspaceId(proxy.id().protocol_id());
+        if let ext_workspace_handle_v1::Event::Removed = event {
+            if let Some(ix) = self
+                .workspaces
+                .iter()
+                .position(|(_, workspace)| workspace.id == id)
+            {
+                let (proxy, _) = self.workspaces.remove(ix);
+                proxy.destroy();
+            }
+            for (_, group) in &mut self.groups {
+                group.workspaces.retain(|workspace| *workspace != id);
+            }
+            return;
+        }
+
+        let Some(workspace) = self.workspace_mut(id) else {
+            return;
+        };
+        match event {
+            ext_workspace_handle_v1::Event::Id { id: stable_id } => {
+                workspace.stable_id = Some(stable_id.into())
+            }
+            ext_workspace_handle_v1::Event::Name { name } => workspace.name = name.into(),
+            ext_workspace_handle_v1::Event::Coordinates { coordinates } => {
+                workspace.coordinates = coordinates
+                    .chunks_exact(4)
+                    .map(|chunk| u32::from_ne_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+                    .collect();
+            }
+            ext_workspace_handle_v1::Event::State {
+                state: WEnum::Value(state),
+            } => {
+                workspace.active = state.contains(ext_workspace_handle_v1::State::Active);
+                workspace.urgent = state.contains(ext_workspace_handle_v1::State::Urgent);
+                workspace.hidden = state.contains(ext_workspace_handle_v1::State::Hidden);
+            }
+            ext_workspace_handle_v1::Event::Capabilities {
+                capabilities: WEnum::Value(capabilities),
+            } => {
+                use ext_workspace_handle_v1::WorkspaceCapabilities;
+                workspace.can_activate = capabilities.contains(WorkspaceCapabilities::Activate);
+                workspace.can_deactivate = capabilities.contains(WorkspaceCapabilities::Deactivate);
+                workspace.can_remove = capabilities.contains(WorkspaceCapabilities::Remove);
+                workspace.can_assign = capabilities.contains(WorkspaceCapabilities::Assign);
+            }
+            _ => {}
+        }
+    }
+
+    fn workspace_mut(&mut self, id: DesktopWorkspaceId) -> Option<&mut DesktopWorkspace> {
+        self.workspaces
+            .iter_mut()
+            .find_map(|(_, workspace)| (workspace.id == id).then_some(workspace))
+    }
+
+    fn workspace_proxy(
+        &self,
+        id: DesktopWorkspaceId,
+    ) -> Result<&ext_workspace_handle_v1::ExtWorkspaceHandleV1> {
+        self.workspaces
+            .iter()
+            .find_map(|(proxy, workspace)| (workspace.id == id).then_some(proxy))
+            .ok_or_else(|| anyhow!("no workspace with ID {id:?}"))
+    }
+
+    fn group_proxy(
+        &self,
+        id: DesktopWorkspaceGroupId,
+    ) -> Result<&ext_workspace_group_handle_v1::ExtWorkspaceGroupHandleV1> {
+        self.groups
+            .iter()
+            .find_map(|(proxy, group)| (group.id == id).then_some(proxy))
+            .ok_or_else(|| anyhow!("no workspace group with ID {id:?}"))
+    }
+
+    /// Sends the requests, which the compositor applies together.
+    pub(crate) fn update(
+        &self,
+        manager: &ext_workspace_manager_v1::ExtWorkspaceManagerV1,
+        requests: Vec<DesktopWorkspaceRequest>,
+    ) -> Result<()> {
+        // Checks all the IDs first, so that none of the requests are sent if one is invalid.
+        for request in &requests {
+            match request {
+                DesktopWorkspaceRequest::Activate(id)
+                | DesktopWorkspaceRequest::Deactivate(id)
+                | DesktopWorkspaceRequest::Remove(id) => {
+                    self.workspace_proxy(*id)?;
+                }
+                DesktopWorkspaceRequest::Assign(id, group) => {
+                    self.workspace_proxy(*id)?;
+                    self.group_proxy(*group)?;
+                }
+                DesktopWorkspaceRequest::Create(group, _) => {
+                    self.group_proxy(*group)?;
+                }
+            }
+        }
+
+        for request in requests {
+            match request {
+                DesktopWorkspaceRequest::Activate(id) => self.workspace_proxy(id)?.activate(),
+                DesktopWorkspaceRequest::Deactivate(id) => self.workspace_proxy(id)?.deactivate(),
+                DesktopWorkspaceRequest::Remove(id) => self.workspace_proxy(id)?.remove(),
+                DesktopWorkspaceRequest::Assign(id, group) => {
+                    self.workspace_proxy(id)?.assign(self.group_proxy(group)?)
+                }
+                DesktopWorkspaceRequest::Create(group, name) => {
+                    self.group_proxy(group)?.create_workspace(name.to_string())
+                }
+            }
+        }
+        manager.commit();
+        Ok(())
+    }
+}
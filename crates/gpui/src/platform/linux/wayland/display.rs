@@ -6,7 +6,7 @@ use std::{
 use uuid::Uuid;
 use wayland_backend::client::ObjectId;
 
-use crate::{Bounds, DisplayId, Pixels, PlatformDisplay};
+use crate::{Bounds, DisplayId, OutputMode, Pixels, PlatformDisplay};
 
 #[derive(Debug, Clone)]
 pub(crate) struct WaylandDisplay {
@@ -14,6 +14,7 @@ pub(crate) struct WaylandDisplay {
     pub id: ObjectId,
     pub name: Option<String>,
     pub bounds: Bounds<Pixels>,
+    pub mode: Option<OutputMode>,
 }
 
 impl Hash for WaylandDisplay {
@@ -38,4 +39,8 @@ impl PlatformDisplay for WaylandDisplay {
     fn bounds(&self) -> Bounds<Pixels> {
         self.bounds
     }
+
+    fn mode(&self) -> Option<OutputMode> {
+        self.mode
+    }
 }
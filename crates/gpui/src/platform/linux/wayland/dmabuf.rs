@@ -0,0 +1,263 @@
+//! Buffers shared through the Linux DMA-BUF mechanism, which windows show by copying their pixels
+//! into the sprite atlas.
+
+use std::ffi::c_void;
+use std::io;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, OwnedFd};
+use std::{ptr, slice};
+
+use anyhow::{Context as _, Result};
+
+use crate::ImageId;
+
+/// `DRM_FORMAT_MOD_LINEAR`, the layout of buffers whose rows follow each other.
+const DRM_FORMAT_MOD_LINEAR: u64 = 0;
+
+const DRM_FORMAT_ARGB8888: u32 = fourcc(b"AR24");
+const DRM_FORMAT_XRGB8888: u32 = fourcc(b"XR24");
+const DRM_FORMAT_ABGR8888: u32 = fourcc(b"AB24");
+const DRM_FORMAT_XBGR8888: u32 = fourcc(b"XB24");
+
+/// `DMA_BUF_IOCTL_SYNC`, which brackets CPU access to a buffer devices may be writing to.
+const DMA_BUF_IOCTL_SYNC: u64 = 0x4008_6200;
+const DMA_BUF_SYNC_READ: u64 = 1;
+const DMA_BUF_SYNC_END: u64 = 4;
+
+const fn fourcc(code: &[u8; 4]) -> u32 {
+    u32::from_le_bytes(*code)
+}
+
+/// A plane of a [`Dmabuf`].
+#[derive(Debug)]
+pub struct DmabufPlane {
+    /// The DMA-BUF file descriptor of this plane.
+    pub fd: OwnedFd,
+    /// The offset of this plane in the buffer, in bytes.
+    pub offset: u32,
+    /// The stride of this plane, in bytes.
+    pub stride: u32,
+}
+
+/// A buffer shared through the Linux DMA-BUF mechanism, e.g. a frame from PipeWire, VA-API or
+/// another process.
+///
+/// When painted by a [`surface`](crate::surface) element, the pixels of the buffer are copied
+/// into the sprite atlas the first time it's painted, and drawn like an image: clipped and
+/// stacked with the rest of the window's content. Wrap every new frame in a new `Dmabuf`, even
+/// when it reuses the file descriptors of a previous one, and remove the old ones from the atlas
+/// with [`Window::drop_dmabuf`](crate::Window::drop_dmabuf). Only single-plane buffers with a
+/// linear layout and a 32-bit RGB format are supported.
+#[derive(Debug)]
+pub struct Dmabuf {
+    id: ImageId,
+    /// The width of the buffer, in pixels.
+    pub width: u32,
+    /// The height of the buffer, in pixels.
+    pub height: u32,
+    /// The DRM fourcc code of the buffer's format.
+    pub format: u32,
+    /// The DRM format modifier describing the buffer's layout.
+    pub modifier: u64,
+    /// The planes of the buffer, in order.
+    pub planes: Vec<DmabufPlane>,
+}
+
+impl Dmabuf {
+    /// Wraps a buffer, giving it an identifier no other buffer or image has.
+    pub fn new(
+        width: u32,
+        height: u32,
+        format: u32,
+        modifier: u64,
+        planes: Vec<DmabufPlane>,
+    ) -> Self {
+        Self {
+            id: ImageId::next(),
+            width,
+            height,
+            format,
+            modifier,
+            planes,
+        }
+    }
+
+    /// Returns the identifier of the buffer in the sprite atlas.
+    pub fn id(&self) -> ImageId {
+        self.id
+    }
+
+    /// Copies the pixels of the buffer as BGRA with straight alpha, like the ones of a
+    /// [`RenderImage`](crate::RenderImage).
+    pub(crate) fn read_bgra(&self) -> Result<Vec<u8>> {
+        anyhow::ensure!(
+            self.modifier == DRM_FORMAT_MOD_LINEAR,
+            "unsupported dmabuf modifier {:#x}",
+            self.modifier
+        );
+        let (swap_red_blue, opaque) = match self.format {
+            DRM_FORMAT_ARGB8888 => (false, false),
+            DRM_FORMAT_XRGB8888 => (false, true),
+            DRM_FORMAT_ABGR8888 => (true, false),
+            DRM_FORMAT_XBGR8888 => (true, true),
+            format => anyhow::bail!("unsupported dmabuf format {format:#010x}"),
+        };
+        let plane = self.planes.first().context("dmabuf has no planes")?;
+        let height = self.height as usize;
+        let row_len = self.width as usize * 4;
+        let stride = plane.stride as usize;
+        anyhow::ensure!(stride >= row_len, "dmabuf stride {stride} is too small");
+        if height == 0 || row_len == 0 {
+            return Ok(Vec::new());
+        }
+
+        let offset = plane.offset as usize;
+        let mapping = Mapping::new(plane.fd.as_fd(), offset + stride * (height - 1) + row_len)?;
+        let mut pixels = Vec::with_capacity(row_len * height);
+        for row in 0..height {
+            let start = offset + row * stride;
+            pixels.extend(
+                mapping.bytes()[start..start + row_len]
+                    .chunks_exact(4)
+                    .flat_map(|pixel| {
+                        let (blue, red) = if swap_red_blue {
+                            (pixel[2], pixel[0])
+                        } else {
+                            (pixel[0], pixel[2])
+                        };
+                        let alpha = if opaque { u8::MAX } else { pixel[3] };
+                        // Wayland buffers are premultiplied, atlas images aren't.
+                        let unpremultiply =
+                            |channel: u8| match alpha {
+                                0 => 0,
+                                u8::MAX => channel,
+                                _ => ((channel as u16 * 255 + alpha as u16 / 2) / alpha as u16)
+                                    .min(255) as u8,
+                            };
+                        [
+                            unpremultiply(blue),
+                            unpremultiply(pixel[1]),
+                            unpremultiply(red),
+                            alpha,
+                        ]
+                    }),
+            );
+        }
+        Ok(pixels)
+    }
+}
+
+impl PartialEq for Dmabuf {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for Dmabuf {}
+
+/// A read-only mapping of a dmabuf, which the CPU may read while it lives.
+struct Mapping<'a> {
+    fd: BorrowedFd<'a>,
+    ptr: *mut c_void,
+    len: usize,
+}
+
+impl<'a> Mapping<'a> {
+    fn new(fd: BorrowedFd<'a>, len: usize) -> Result<Self> {
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_SHARED,
+                fd.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error()).context("failed to map dmabuf");
+        }
+        let mapping = Self { fd, ptr, len };
+        mapping.sync(DMA_BUF_SYNC_READ);
+        Ok(mapping)
+    }
+
+    fn sync(&self, flags: u64) {
+        // Fails for buffers that aren't dmabufs, which need no synchronization.
+        unsafe {
+            libc::ioctl(self.fd.as_raw_fd(), DMA_BUF_IOCTL_SYNC as _, &flags);
+        }
+    }
+
+    fn bytes(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.ptr.cast::<u8>(), self.len) }
+    }
+}
+
+impl Drop for Mapping<'_> {
+    fn drop(&mut self) {
+        self.sync(DMA_BUF_SYNC_READ | DMA_BUF_SYNC_END);
+        unsafe {
+            libc::munmap(self.ptr, self.len);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::FileExt;
+
+    fn dmabuf(format: u32, modifier: u64, stride: u32, rows: &[&[u8]]) -> Dmabuf {
+        let file = tempfile::tempfile().unwrap();
+        for (index, row) in rows.iter().enumerate() {
+            file.write_all_at(row, 8 + index as u64 * stride as u64)
+                .unwrap();
+        }
+        let width = rows[0].len() as u32 / 4;
+        let plane = DmabufPlane {
+            fd: file.into(),
+            offset: 8,
+            stride,
+        };
+        Dmabuf::new(width, rows.len() as u32, format, modifier, vec![plane])
+    }
+
+    #[test]
+    fn test_read_bgra_skips_row_padding() {
+        let buffer = dmabuf(
+            DRM_FORMAT_XRGB8888,
+            DRM_FORMAT_MOD_LINEAR,
+            12,
+            &[&[1, 2, 3, 0, 4, 5, 6, 0], &[7, 8, 9, 0, 10, 11, 12, 0]],
+        );
+        assert_eq!(
+            buffer.read_bgra().unwrap(),
+            [1, 2, 3, 255, 4, 5, 6, 255, 7, 8, 9, 255, 10, 11, 12, 255]
+        );
+    }
+
+    #[test]
+    fn test_read_bgra_swaps_and_unpremultiplies() {
+        let buffer = dmabuf(
+            DRM_FORMAT_ABGR8888,
+            DRM_FORMAT_MOD_LINEAR,
+            8,
+            &[&[100, 50, 0, 128, 10, 20, 30, 0]],
+        );
+        assert_eq!(buffer.read_bgra().unwrap(), [0, 100, 199, 128, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_read_bgra_rejects_tiled_buffers() {
+        let buffer = dmabuf(DRM_FORMAT_ARGB8888, (1 << 56) | 1, 4, &[&[0, 0, 0, 0]]);
+        assert!(buffer.read_bgra().is_err());
+    }
+
+    #[test]
+    fn test_dmabufs_are_compared_by_id() {
+        let new = || Dmabuf::new(1, 1, DRM_FORMAT_ARGB8888, DRM_FORMAT_MOD_LINEAR, Vec::new());
+        let first = new();
+        assert_ne!(first, new());
+        assert_eq!(first.id(), first.id());
+    }
+}
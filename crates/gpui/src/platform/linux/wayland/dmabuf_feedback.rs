@@ -0,0 +1,159 @@
+//! The buffer formats and modifiers the compositor prefers for a surface, advertised with
+//! `zwp_linux_dmabuf_v1` feedback.
+
+use std::{
+    fs::File,
+    os::{fd::OwnedFd, unix::fs::FileExt},
+};
+
+use util::ResultExt;
+use wayland_client::WEnum;
+use wayland_protocols::wp::linux_dmabuf::zv1::client::zwp_linux_dmabuf_feedback_v1::{
+    self, TrancheFlags,
+};
+
+/// The size of an entry of the format table: a format, 4 bytes of padding and a modifier.
+const FORMAT_TABLE_ENTRY_SIZE: usize = 16;
+
+/// A format and modifier pair buffers can be allocated with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DmabufFormat {
+    /// The DRM fourcc code of the format.
+    pub format: u32,
+    /// The DRM format modifier.
+    pub modifier: u64,
+}
+
+/// A group of formats the compositor prefers equally, for buffers allocated on a device.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DmabufTranche {
+    /// The `dev_t` of the device buffers should be allocated on.
+    pub target_device: u64,
+    /// Whether buffers in these formats can be scanned out by the display directly, skipping
+    /// composition.
+    pub scanout: bool,
+    /// The formats of the tranche.
+    pub formats: Vec<DmabufFormat>,
+}
+
+/// The buffer parameters the compositor prefers for the output a window is on. Producers of
+/// [`ForeignSurface`](crate::ForeignSurface)s in the window should allocate their buffers with the
+/// first format and modifier they support, going through the tranches in order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DmabufFeedback {
+    /// The `dev_t` of the device the compositor composites with.
+    pub main_device: u64,
+    /// The tranches, from the most preferred one.
+    pub tranches: Vec<DmabufTranche>,
+}
+
+impl DmabufFeedback {
+    /// Returns the most preferred tranche containing the format and modifier, if any.
+    pub fn tranche_for(&self, format: DmabufFormat) -> Option<&DmabufTranche> {
+        self.tranches
+            .iter()
+            .find(|tranche| tranche.formats.contains(&format))
+    }
+
+    /// Returns the modifiers the compositor accepts for a format, from the most preferred one.
+    pub fn modifiers(&self, format: u32) -> impl Iterator<Item = u64> + '_ {
+        self.tranches
+            .iter()
+            .flat_map(|tranche| &tranche.formats)
+            .filter(move |candidate| candidate.format == format)
+            .map(|candidate| candidate.modifier)
+    }
+
+    /// Whether the compositor offers to scan the surface out directly.
+    pub fn offers_scanout(&self) -> bool {
+        self.tranches.iter().any(|tranche| tranche.scanout)
+    }
+}
+
+/// Accumulates feedback events until the compositor is done sending them.
+#[derive(Default)]
+pub(crate) struct DmabufFeedbackBuilder {
+    format_table: Vec<DmabufFormat>,
+    pending: DmabufFeedback,
+    tranche: Option<DmabufTranche>,
+}
+
+impl DmabufFeedbackBuilder {
+    /// Handles an event, returning the feedback once it's complete.
+    pub fn handle_event(
+        &mut self,
+        event: zwp_linux_dmabuf_feedback_v1::Event,
+    ) -> Option<DmabufFeedback> {
+        match event {
+            zwp_linux_dmabuf_feedback_v1::Event::FormatTable { fd, size } => {
+                // The table is kept when it's not resent along with changed parameters.
+                if let Some(format_table) = read_format_table(fd, size as usize) {
+                    self.format_table = format_table;
+                }
+            }
+            zwp_linux_dmabuf_feedback_v1::Event::MainDevice { device } => {
+                self.pending.main_device = parse_device(&device);
+            }
+            zwp_linux_dmabuf_feedback_v1::Event::TrancheTargetDevice { device } => {
+                self.tranche_mut().target_device = parse_device(&device);
+            }
+            zwp_linux_dmabuf_feedback_v1::Event::TrancheFlags { flags } => {
+                self.tranche_mut().scanout = matches!(
+                    flags,
+                    WEnum::Value(flags) if flags.contains(TrancheFlags::Scanout)
+                );
+            }
+            zwp_linux_dmabuf_feedback_v1::Event::TrancheFormats { indices } => {
+                let formats = indices
+                    .chunks_exact(2)
+                    .filter_map(|index| {
+                        let index = u16::from_ne_bytes([index[0], index[1]]);
+                        self.format_table.get(index as usize).copied()
+                    })
+                    .collect::<Vec<_>>();
+                self.tranche_mut().formats.extend(formats);
+            }
+            zwp_linux_dmabuf_feedback_v1::Event::TrancheDone => {
+                if let Some(tranche) = self.tranche.take() {
+                    self.pending.tranches.push(tranche);
+                }
+            }
+            zwp_linux_dmabuf_feedback_v1::Event::Done => {
+                return Some(std::mem::take(&mut self.pending));
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn tranche_mut(&mut self) -> &mut DmabufTranche {
+        let main_device = self.pending.main_device;
+        self.tranche.get_or_insert_with(|| DmabufTranche {
+            target_device: main_device,
+            scanout: false,
+            formats: Vec::new(),
+        })
+    }
+}
+
+fn parse_device(device: &[u8]) -> u64 {
+    match device.len() {
+        4 => u32::from_ne_bytes(device.try_into().unwrap()) as u64,
+        8 => u64::from_ne_bytes(device.try_into().unwrap()),
+        _ => 0,
+    }
+}
+
+fn read_format_table(fd: OwnedFd, size: usize) -> Option<Vec<DmabufFormat>> {
+    let mut table = vec![0; size];
+    File::from(fd).read_exact_at(&mut table, 0).log_err()?;
+    Some(
+        table
+            .chunks_exact(FORMAT_TABLE_ENTRY_SIZE)
+            .map(|entry| DmabufFormat {
+                format: u32::from_ne_bytes(entry[0..4].try_into().unwrap()),
+                modifier: u64::from_ne_bytes(entry[8..16].try_into().unwrap()),
+            })
+            .collect(),
+    )
+}
@@ -0,0 +1,312 @@
+//! Lays out the popups of a [`NotificationServer`] as a stack of layer shell windows.
+
+use std::rc::Rc;
+
+use crate::{
+    point, px, size, Anchor, AnyView, App, AppContext, Bounds, Context, Corner, Entity,
+    IntoElement, KeyboardInteractivity, Layer, LayerShellSettings, Notification,
+    NotificationServer, NotificationServerEvent, Pixels, Render, Size, Subscription, Window,
+    WindowBounds, WindowHandle, WindowKind, WindowOptions,
+};
+
+/// Configures a [`NotificationStack`].
+#[derive(Clone, Debug)]
+pub struct NotificationStackSettings {
+    /// The corner of the output the popups are stacked in, the newest popup is closest to it.
+    pub corner: Corner,
+    /// The distance between the popups and the edges of the output.
+    pub margin: Pixels,
+    /// The distance between two popups.
+    pub gap: Pixels,
+    /// The size of each popup.
+    pub popup_size: Size<Pixels>,
+    /// The maximum number of popups shown at once. The older notifications are shown again
+    /// once newer ones are closed.
+    pub max_visible: usize,
+    /// The layer of the popups.
+    pub layer: Layer,
+    /// The namespace of the popups' layer surfaces.
+    pub namespace: String,
+}
+
+impl Default for NotificationStackSettings {
+    fn default() -> Self {
+        Self {
+            corner: Corner::TopRight,
+            margin: px(16.),
+            gap: px(8.),
+            popup_size: size(px(360.), px(96.)),
+            max_visible: 5,
+            layer: Layer::Overlay,
+            namespace: "notifications".to_string(),
+        }
+    }
+}
+
+/// The layer shell margins of a popup, as top, right, bottom and left margins.
+type Margin = (Pixels, Pixels, Pixels, Pixels);
+
+impl NotificationStackSettings {
+    /// Returns the notifications shown in popups and the margins placing them, from the one
+    /// closest to the corner, given the ids of the open notifications from the oldest.
+    fn stack(&self, ids: impl DoubleEndedIterator<Item = u32>) -> Vec<(u32, Margin)> {
+        ids.rev()
+            .take(self.max_visible)
+            .enumerate()
+            .map(|(index, id)| (id, self.popup_margin(index)))
+            .collect()
+    }
+
+    /// Returns the margins of the popup at the given index in the stack.
+    fn popup_margin(&self, index: usize) -> Margin {
+        let offset = self.margin + (self.popup_size.height + self.gap) * index as f32;
+        match self.corner {
+            Corner::TopLeft => (offset, px(0.), px(0.), self.margin),
+            Corner::TopRight => (offset, self.margin, px(0.), px(0.)),
+            Corner::BottomLeft => (px(0.), px(0.), offset, self.margin),
+            Corner::BottomRight => (px(0.), self.margin, offset, px(0.)),
+        }
+    }
+}
+
+type BuildPopup =
+    dyn Fn(&Notification, &Entity<NotificationServer>, &mut Window, &mut App) -> AnyView;
+
+/// Shows the open notifications of a [`NotificationServer`] in popups stacked in a corner of
+/// the output, opening, replacing, moving and closing the popups as notifications come and go.
+///
+/// The view of each popup is built by the application, which can use the server to invoke the
+/// notification's actions or dismiss it.
+pub struct NotificationStack {
+    server: Entity<NotificationServer>,
+    settings: NotificationStackSettings,
+    build_popup: Rc<BuildPopup>,
+    popups: Vec<(u32, WindowHandle<NotificationPopup>)>,
+    _subscriptions: [Subscription; 2],
+}
+
+impl NotificationStack {
+    /// Creates a stack showing the notifications of the given server.
+    pub fn new<F>(
+        server: Entity<NotificationServer>,
+        settings: NotificationStackSettings,
+        build_popup: F,
+        cx: &mut Context<Self>,
+    ) -> Self
+    where
+        F: 'static
+            + Fn(&Notification, &Entity<NotificationServer>, &mut Window, &mut App) -> AnyView,
+    {
+        let subscriptions = [
+            cx.subscribe(&server, |this, _, event, cx| {
+                if let NotificationServerEvent::Notified(id) = event {
+                    this.rebuild_popup(*id, cx);
+                }
+                this.layout(cx);
+            }),
+            cx.on_release(|this, cx| {
+                for (_, handle) in this.popups.drain(..) {
+                    handle
+                        .update(cx, |_, window, _| window.remove_window())
+                        .ok();
+                }
+            }),
+        ];
+        let mut this = Self {
+            server,
+            settings,
+            build_popup: Rc::new(build_popup),
+            popups: Vec::new(),
+            _subscriptions: subscriptions,
+        };
+        this.layout(cx);
+        this
+    }
+
+    /// Returns the ids of the notifications shown in popups, from the one closest to the corner.
+    pub fn visible_notifications(&self) -> impl Iterator<Item = u32> + '_ {
+        self.popups.iter().map(|(id, _)| *id)
+    }
+
+    fn rebuild_popup(&mut self, id: u32, cx: &mut Context<Self>) {
+        let Some((_, handle)) = self.popups.iter().find(|(popup_id, _)| *popup_id == id) else {
+            return;
+        };
+        let Some(notification) = self.server.read(cx).notification(id).cloned() else {
+            return;
+        };
+        let server = self.server.clone();
+        let build_popup = self.build_popup.clone();
+        handle
+            .update(cx, |popup, window, cx| {
+                popup.view = build_popup(&notification, &server, window, cx);
+                cx.notify();
+            })
+            .ok();
+    }
+
+    /// Opens and closes popups so the newest notifications are shown, and moves the popups to
+    /// their place in the stack.
+    fn layout(&mut self, cx: &mut Context<Self>) {
+        let notifications = self.server.read(cx).notifications().to_vec();
+        let stack = self
+            .settings
+            .stack(notifications.iter().map(|notification| notification.id));
+
+        let mut popups = Vec::with_capacity(stack.len());
+        for (id, margin) in stack {
+            let existing = self
+                .popups
+                .iter()
+                .position(|(popup_id, _)| *popup_id == id)
+                .map(|ix| self.popups.remove(ix));
+            match existing {
+                Some((id, handle)) => {
+                    handle
+                        .update(cx, |_, window, _| window.set_layer_shell_margin(margin))
+                        .ok();
+                    popups.push((id, handle));
+                }
+                None => {
+                    let Some(notification) = notifications
+                        .iter()
+                        .find(|notification| notification.id == id)
+                    else {
+                        continue;
+                    };
+                    match self.open_popup(notification, margin, cx) {
+                        Ok(handle) => popups.push((id, handle)),
+                        Err(error) => log::error!("failed to open notification popup: {error:#}"),
+                    }
+                }
+            }
+        }
+
+        for (_, handle) in self.popups.drain(..) {
+            handle
+                .update(cx, |_, window, _| window.remove_window())
+                .ok();
+        }
+        self.popups = popups;
+    }
+
+    fn open_popup(
+        &self,
+        notification: &Notification,
+        margin: Margin,
+        cx: &mut App,
+    ) -> anyhow::Result<WindowHandle<NotificationPopup>> {
+        let anchor = match self.settings.corner {
+            Corner::TopLeft => Anchor::TOP | Anchor::LEFT,
+            Corner::TopRight => Anchor::TOP | Anchor::RIGHT,
+            Corner::BottomLeft => Anchor::BOTTOM | Anchor::LEFT,
+            Corner::BottomRight => Anchor::BOTTOM | Anchor::RIGHT,
+        };
+        let options = WindowOptions {
+            window_bounds: Some(WindowBounds::Windowed(Bounds {
+                origin: point(px(0.), px(0.)),
+                size: self.settings.popup_size,
+            })),
+            titlebar: None,
+            focus: false,
+            kind: WindowKind::LayerShell(LayerShellSettings {
+                layer: self.settings.layer,
+                anchor,
+                margin: Some(margin),
+                keyboard_interactivity: KeyboardInteractivity::None,
+                namespace: self.settings.namespace.clone(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let server = self.server.clone();
+        let build_popup = self.build_popup.clone();
+        cx.open_window(options, |window, cx| {
+            let view = build_popup(notification, &server, window, cx);
+            cx.new(|_| NotificationPopup { view })
+        })
+    }
+}
+
+struct NotificationPopup {
+    view: AnyView,
+}
+
+impl Render for NotificationPopup {
+    fn render(&mut self, _: &mut Window, _: &mut Context<Self>) -> impl IntoElement {
+        self.view.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NotificationStackSettings;
+    use crate::{px, size, Corner};
+
+    fn settings(corner: Corner) -> NotificationStackSettings {
+        NotificationStackSettings {
+            corner,
+            margin: px(10.),
+            gap: px(5.),
+            popup_size: size(px(300.), px(50.)),
+            max_visible: 3,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_newest_notification_is_closest_to_the_corner() {
+        let stack = settings(Corner::TopRight).stack([1, 2, 3].into_iter());
+        assert_eq!(
+            stack,
+            [
+                (3, (px(10.), px(10.), px(0.), px(0.))),
+                (2, (px(65.), px(10.), px(0.), px(0.))),
+                (1, (px(120.), px(10.), px(0.), px(0.))),
+            ]
+        );
+
+        let stack = settings(Corner::BottomLeft).stack([1, 2].into_iter());
+        assert_eq!(
+            stack,
+            [
+                (2, (px(0.), px(0.), px(10.), px(10.))),
+                (1, (px(0.), px(0.), px(65.), px(10.))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_popups_are_a_gap_apart() {
+        let settings = NotificationStackSettings {
+            gap: px(20.),
+            ..settings(Corner::BottomRight)
+        };
+        let offsets = settings
+            .stack([1, 2, 3].into_iter())
+            .into_iter()
+            .map(|(_, (_, _, bottom, _))| bottom)
+            .collect::<Vec<_>>();
+        assert_eq!(offsets, [px(10.), px(80.), px(150.)]);
+    }
+
+    #[test]
+    fn test_stack_is_laid_out_again_when_notifications_close() {
+        let settings = settings(Corner::TopLeft);
+        let ids = |stack: Vec<(u32, _)>| stack.into_iter().map(|(id, _)| id).collect::<Vec<_>>();
+        assert_eq!(ids(settings.stack([1, 2, 3, 4].into_iter())), [4, 3, 2]);
+
+        // Closing one of the shown notifications moves the older ones up, and brings back the
+        // oldest one, which didn't fit before.
+        let stack = settings.stack([1, 2, 4].into_iter());
+        assert_eq!(
+            stack,
+            [
+                (4, (px(10.), px(0.), px(0.), px(10.))),
+                (2, (px(65.), px(0.), px(0.), px(10.))),
+                (1, (px(120.), px(0.), px(0.), px(10.))),
+            ]
+        );
+    }
+}
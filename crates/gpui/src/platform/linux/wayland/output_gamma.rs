@@ -0,0 +1,126 @@
+use std::os::{fd::AsFd, unix::fs::FileExt as _};
+
+use anyhow::{anyhow, Result};
+use collections::HashMap;
+use util::ResultExt;
+use wayland_backend::client::ObjectId;
+use wayland_client::{protocol::wl_output, Proxy};
+use wayland_protocols_wlr::gamma_control::v1::client::zwlr_gamma_control_v1;
+
+use super::client::Globals;
+use crate::{DisplayGamma, DisplayId};
+
+/// The gamma adjustments of the outputs, applied with wlr-gamma-control.
+#[derive(Default)]
+pub(crate) struct OutputGammas {
+    outputs: HashMap<ObjectId, OutputGamma>,
+}
+
+struct OutputGamma {
+    output: wl_output::WlOutput,
+    /// The gamma control, only held while the output is adjusted since a single client can
+    /// control it at a time. The adjustment is undone once it's destroyed.
+    control: Option<zwlr_gamma_control_v1::ZwlrGammaControlV1>,
+    /// The number of elements in each ramp, sent by the compositor.
+    size: Option<usize>,
+    gamma: DisplayGamma,
+}
+
+impl OutputGammas {
+    pub(crate) fn add_output(&mut self, output: &wl_output::WlOutput) {
+        self.outputs.insert(
+            output.id(),
+            OutputGamma {
+                output: output.clone(),
+                control: None,
+                size: None,
+                gamma: DisplayGamma::default(),
+            },
+        );
+    }
+
+    fn find(&mut self, display: DisplayId) -> Option<&mut OutputGamma> {
+        self.outputs
+            .iter_mut()
+            .find_map(|(id, output)| (id.protocol_id() == display.0).then_some(output))
+    }
+
+    pub(crate) fn gamma(&mut self, display: DisplayId) -> Option<DisplayGamma> {
+        let output = self.find(display)?;
+        output.control.is_some().then_some(output.gamma)
+    }
+
+    pub(crate) fn set_gamma(
+        &mut self,
+        display: DisplayId,
+        gamma: Option<DisplayGamma>,
+        globals: &Globals,
+    ) -> Result<()> {
+        let manager = globals
+            .gamma_control_manager
+            .as_ref()
+            .ok_or_else(|| anyhow!("the compositor doesn't support wlr-gamma-control"))?;
+        let output = self
+            .find(display)
+            .ok_or_else(|| anyhow!("no display with ID {display:?}"))?;
+        let Some(gamma) = gamma else {
+            if let Some(control) = output.control.take() {
+                control.destroy();
+            }
+            output.size = None;
+            return Ok(());
+        };
+
+        output.gamma = gamma;
+        match (&output.control, output.size) {
+            (Some(control), Some(size)) => apply_gamma(control, gamma, size),
+            // The gamma is applied once the compositor sent the size of the ramps.
+            (Some(_), None) => Ok(()),
+            (None, _) => {
+                let control =
+                    manager.get_gamma_control(&output.output, &globals.qh, output.output.id());
+                output.control = Some(control);
+                Ok(())
+            }
+        }
+    }
+
+    pub(crate) fn handle_event(&mut self, output: &ObjectId, event: zwlr_gamma_control_v1::Event) {
+        let Some(output) = self.outputs.get_mut(output) else {
+            return;
+        };
+        match event {
+            zwlr_gamma_control_v1::Event::GammaSize { size } => {
+                output.size = Some(size as usize);
+                if let Some(control) = &output.control {
+                    apply_gamma(control, output.gamma, size as usize).log_err();
+                }
+            }
+            zwlr_gamma_control_v1::Event::Failed => {
+                log::warn!(
+                    "failed to adjust the gamma of output {}",
+                    output.output.id()
+                );
+                if let Some(control) = output.control.take() {
+                    control.destroy();
+                }
+                output.size = None;
+            }
+            _ => {}
+        }
+    }
+}
+
+fn apply_gamma(
+    control: &zwlr_gamma_control_v1::ZwlrGammaControlV1,
+    gamma: DisplayGamma,
+    size: usize,
+) -> Result<()> {
+    let ramps = gamma.ramps(size);
+    let file = tempfile::tempfile()?;
+    // Written at an offset to keep the file position at the start, where the compositor reads
+    // the ramps from.
+    file.write_all_at(bytemuck::cast_slice(&ramps), 0)?;
+    control.set_gamma(file.as_fd());
+    Ok(())
+}
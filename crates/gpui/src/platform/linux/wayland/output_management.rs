@@ -0,0 +1,236 @@
+use anyhow::{anyhow, Result};
+use collections::HashMap;
+use futures::channel::oneshot;
+use wayland_backend::client::ObjectId;
+use wayland_client::{Proxy, QueueHandle};
+use wayland_protocols_wlr::output_management::v1::client::{
+    zwlr_output_configuration_v1, zwlr_output_head_v1, zwlr_output_manager_v1, zwlr_output_mode_v1,
+};
+
+use crate::{
+    point, px, size, DevicePixels, OutputConfiguration, OutputHead, OutputMode,
+    WaylandClientStatePtr,
+};
+
+/// The heads advertised with wlr-output-management.
+#[derive(Default)]
+pub(crate) struct OutputHeads {
+    /// The serial of the last complete configuration, which new configurations are based on.
+    serial: Option<u32>,
+    heads: Vec<HeadState>,
+    pending_configurations: HashMap<ObjectId, oneshot::Sender<Result<()>>>,
+}
+
+struct HeadState {
+    proxy: zwlr_output_head_v1::ZwlrOutputHeadV1,
+    modes: Vec<zwlr_output_mode_v1::ZwlrOutputModeV1>,
+    head: OutputHead,
+}
+
+impl OutputHeads {
+    /// Returns the heads of the last complete configuration.
+    pub(crate) fn heads(&self) -> Vec<OutputHead> {
+        if self.serial.is_none() {
+            return Vec::new();
+        }
+        self.heads.iter().map(|head| head.head.clone()).collect()
+    }
+
+    pub(crate) fn done(&mut self, serial: u32) {
+        self.serial = Some(serial);
+    }
+
+    /// Forgets the heads after the compositor stopped sending them.
+    pub(crate) fn finished(&mut self) {
+        self.serial = None;
+        self.heads.clear();
+        for (_, sender) in self.pending_configurations.drain() {
+            sender
+                .send(Err(anyhow!("the compositor stopped managing outputs")))
+                .ok();
+        }
+    }
+
+    pub(crate) fn add_head(&mut self, proxy: zwlr_output_head_v1::ZwlrOutputHeadV1) {
+        self.heads.push(HeadState {
+            proxy,
+            modes: Vec::new(),
+            head: OutputHead {
+                name: Default::default(),
+                description: Default::default(),
+                make: None,
+                model: None,
+                serial_number: None,
+                physical_size: None,
+                enabled: false,
+                modes: Vec::new(),
+                current_mode: None,
+                position: Default::default(),
+                scale: 1.,
+            },
+        });
+    }
+
+    pub(crate) fn handle_head_event(
+        &mut self,
+        proxy: &zwlr_output_head_v1::ZwlrOutputHeadV1,
+        event: zwlr_output_head_v1::Event,
+    ) {
+        let Some(ix) = self.heads.iter().position(|head| &head.proxy == proxy) else {
+            return;
+        };
+        let state = &mut self.heads[ix];
+        let head = &mut state.head;
+        match event {
+            zwlr_output_head_v1::Event::Name { name } => head.name = name.into(),
+            zwlr_output_head_v1::Event::Description { description } => {
+                head.description = description.into()
+            }
+            zwlr_output_head_v1::Event::PhysicalSize { width, height } => {
+                head.physical_size =
+                    (width > 0 && height > 0).then(|| size(width as u32, height as u32));
+            }
+            zwlr_output_head_v1::Event::Mode { mode } => {
+                state.modes.push(mode);
+                head.modes.push(OutputMode::default());
+            }
+            zwlr_output_head_v1::Event::Enabled { enabled } => head.enabled = enabled != 0,
+            zwlr_output_head_v1::Event::CurrentMode { mode } => {
+                head.current_mode = state.modes.iter().position(|proxy| proxy == &mode);
+            }
+            zwlr_output_head_v1::Event::Position { x, y } => {
+                head.position = point(px(x as f32), px(y as f32));
+            }
+            zwlr_output_head_v1::Event::Scale { scale } => head.scale = scale as f32,
+            zwlr_output_head_v1::Event::Make { make } => head.make = Some(make.into()),
+            zwlr_output_head_v1::Event::Model { model } => head.model = Some(model.into()),
+            zwlr_output_head_v1::Event::SerialNumber { serial_number } => {
+                head.serial_number = Some(serial_number.into())
+            }
+            zwlr_output_head_v1::Event::Finished => {
+                if proxy.version() >= zwlr_output_head_v1::REQ_RELEASE_SINCE {
+                    proxy.release();
+                }
+                self.heads.remove(ix);
+            }
+            _ => {}
+        }
+    }
+
+    pub(crate) fn handle_mode_event(
+        &mut self,
+        proxy: &zwlr_output_mode_v1::ZwlrOutputModeV1,
+        event: zwlr_output_mode_v1::Event,
+    ) {
+        let Some((state, ix)) = self.heads.iter_mut().find_map(|state| {
+            let ix = state.modes.iter().position(|mode| mode == proxy)?;
+            Some((state, ix))
+        }) else {
+            return;
+        };
+        let mode = &mut state.head.modes[ix];
+        match event {
+            zwlr_output_mode_v1::Event::Size { width, height } => {
+                mode.size = size(DevicePixels(width), DevicePixels(height));
+            }
+            zwlr_output_mode_v1::Event::Refresh { refresh } => {
+                mode.refresh_rate = (refresh > 0).then_some(refresh as u32);
+            }
+            zwlr_output_mode_v1::Event::Preferred => mode.preferred = true,
+            zwlr_output_mode_v1::Event::Finished => {
+                if proxy.version() >= zwlr_output_mode_v1::REQ_RELEASE_SINCE {
+                    proxy.release();
+                }
+                state.modes.remove(ix);
+                state.head.modes.remove(ix);
+                state.head.current_mode = match state.head.current_mode {
+                    Some(current) if current == ix => None,
+                    Some(current) if current > ix => Some(current - 1),
+                    current => current,
+                };
+            }
+            _ => {}
+        }
+    }
+
+    /// Sends the configuration to the compositor, heads missing from it keep their
+    /// configuration.
+    pub(crate) fn configure(
+        &mut self,
+        manager: &zwlr_output_manager_v1::ZwlrOutputManagerV1,
+        configuration: OutputConfiguration,
+        test_only: bool,
+        qh: &QueueHandle<WaylandClientStatePtr>,
+    ) -> Result<oneshot::Receiver<Result<()>>> {
+        let serial = self
+            .serial
+            .ok_or_else(|| anyhow!("the compositor hasn't sent the outputs yet"))?;
+        for config in &configuration.heads {
+            let state = self
+                .heads
+                .iter()
+                .find(|state| state.head.name == config.name)
+                .ok_or_else(|| anyhow!("no output head named {}", config.name))?;
+            if config.mode.is_some_and(|mode| mode >= state.modes.len()) {
+                return Err(anyhow!("invalid mode for output head {}", config.name));
+            }
+        }
+
+        let output_configuration = manager.create_configuration(serial, qh, ());
+        for state in &self.heads {
+            let config = configuration
+                .heads
+                .iter()
+                .find(|config| config.name == state.head.name);
+            let enabled = config.map_or(state.head.enabled, |config| config.enabled);
+            if !enabled {
+                output_configuration.disable_head(&state.proxy);
+                continue;
+            }
+            let head_configuration = output_configuration.enable_head(&state.proxy, qh, ());
+            let Some(config) = config else {
+                continue;
+            };
+            if let Some(mode) = config.mode {
+                head_configuration.set_mode(&state.modes[mode]);
+            }
+            if let Some(position) = config.position {
+                head_configuration.set_position(position.x.0 as i32, position.y.0 as i32);
+            }
+            if let Some(scale) = config.scale {
+                head_configuration.set_scale(scale as f64);
+            }
+        }
+        if test_only {
+            output_configuration.test();
+        } else {
+            output_configuration.apply();
+        }
+
+        let (tx, rx) = oneshot::channel();
+        self.pending_configurations
+            .insert(output_configuration.id(), tx);
+        Ok(rx)
+    }
+
+    pub(crate) fn handle_configuration_event(
+        &mut self,
+        proxy: &zwlr_output_configuration_v1::ZwlrOutputConfigurationV1,
+        event: zwlr_output_configuration_v1::Event,
+    ) {
+        let result = match event {
+            zwlr_output_configuration_v1::Event::Succeeded => Ok(()),
+            zwlr_output_configuration_v1::Event::Failed => {
+                Err(anyhow!("the compositor rejected the output configuration"))
+            }
+            zwlr_output_configuration_v1::Event::Cancelled => Err(anyhow!(
+                "the output configuration was cancelled because the outputs changed"
+            )),
+            _ => return,
+        };
+        proxy.destroy();
+        if let Some(sender) = self.pending_configurations.remove(&proxy.id()) {
+            sender.send(result).ok();
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use anyhow::{anyhow, Result};
+use collections::HashMap;
+use wayland_backend::client::ObjectId;
+use wayland_backend::protocol::WEnum;
+use wayland_client::{protocol::wl_output, Proxy};
+use wayland_protocols_wlr::output_power_management::v1::client::zwlr_output_power_v1;
+
+use super::client::Globals;
+use crate::DisplayId;
+
+/// The power modes of the outputs, controlled with wlr-output-power-management.
+#[derive(Default)]
+pub(crate) struct OutputPowers {
+    /// The power controls by `wl_output` ID, with the last power mode reported for them.
+    outputs: HashMap<ObjectId, (zwlr_output_power_v1::ZwlrOutputPowerV1, Option<bool>)>,
+}
+
+impl OutputPowers {
+    pub(crate) fn add_output(&mut self, output: &wl_output::WlOutput, globals: &Globals) {
+        if let Some(manager) = &globals.output_power_manager {
+            let power = manager.get_output_power(output, &globals.qh, output.id());
+            self.outputs.insert(output.id(), (power, None));
+        }
+    }
+
+    fn find(
+        &self,
+        display: DisplayId,
+    ) -> Option<&(zwlr_output_power_v1::ZwlrOutputPowerV1, Option<bool>)> {
+        self.outputs
+            .iter()
+            .find_map(|(id, output)| (id.protocol_id() == display.0).then_some(output))
+    }
+
+    pub(crate) fn is_powered(&self, display: DisplayId) -> Option<bool> {
+        self.find(display)?.1
+    }
+
+    pub(crate) fn set_powered(&self, display: DisplayId, powered: bool) -> Result<()> {
+        let (power, _) = self
+            .find(display)
+            .ok_or_else(|| anyhow!("the power mode of display {display:?} can't be controlled"))?;
+        power.set_mode(if powered {
+            zwlr_output_power_v1::Mode::On
+        } else {
+            zwlr_output_power_v1::Mode::Off
+        });
+        Ok(())
+    }
+
+    pub(crate) fn handle_event(&mut self, output: &ObjectId, event: zwlr_output_power_v1::Event) {
+        match event {
+            zwlr_output_power_v1::Event::Mode {
+                mode: WEnum::Value(mode),
+            } => {
+                if let Some((_, powered)) = self.outputs.get_mut(output) {
+                    *powered = Some(mode == zwlr_output_power_v1::Mode::On);
+                }
+            }
+            zwlr_output_power_v1::Event::Failed => {
+                // The output is gone, doesn't support power management or another client
+                // controls it.
+                if let Some((power, _)) = self.outputs.remove(output) {
+                    power.destroy();
+                }
+            }
+            _ => {}
+        }
+    }
+}
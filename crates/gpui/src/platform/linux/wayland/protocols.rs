@@ -0,0 +1,63 @@
+//! Bindings for protocols that aren't in the `wayland-protocols` releases gpui depends on,
+//! generated from the XML files in `resources/wayland`.
+
+#![allow(
+    missing_docs,
+    non_upper_case_globals,
+    non_camel_case_types,
+    clippy::all
+)]
+
+pub mod ext_background_effect_v1 {
+    use wayland_client;
+    use wayland_client::protocol::*;
+
+    pub mod __interfaces {
+        use wayland_client::protocol::__interfaces::*;
+        wayland_scanner::generate_interfaces!("resources/wayland/ext-background-effect-v1.xml");
+    }
+    use self::__interfaces::*;
+
+    wayland_scanner::generate_client_code!("resources/wayland/ext-background-effect-v1.xml");
+}
+
+pub mod ext_workspace_v1 {
+    use wayland_client;
+    use wayland_client::protocol::*;
+
+    pub mod __interfaces {
+        use wayland_client::protocol::__interfaces::*;
+        wayland_scanner::generate_interfaces!("resources/wayland/ext-workspace-v1.xml");
+    }
+    use self::__interfaces::*;
+
+    wayland_scanner::generate_client_code!("resources/wayland/ext-workspace-v1.xml");
+}
+
+pub mod virtual_keyboard_unstable_v1 {
+    use wayland_client;
+    use wayland_client::protocol::*;
+
+    pub mod __interfaces {
+        use wayland_client::protocol::__interfaces::*;
+        wayland_scanner::generate_interfaces!("resources/wayland/virtual-keyboard-unstable-v1.xml");
+    }
+    use self::__interfaces::*;
+
+    wayland_scanner::generate_client_code!("resources/wayland/virtual-keyboard-unstable-v1.xml");
+}
+
+pub mod xdg_toplevel_drag_v1 {
+    use wayland_client;
+    use wayland_client::protocol::*;
+    use wayland_protocols::xdg::shell::client::*;
+
+    pub mod __interfaces {
+        use wayland_client::protocol::__interfaces::*;
+        use wayland_protocols::xdg::shell::client::__interfaces::*;
+        wayland_scanner::generate_interfaces!("resources/wayland/xdg-toplevel-drag-v1.xml");
+    }
+    use self::__interfaces::*;
+
+    wayland_scanner::generate_client_code!("resources/wayland/xdg-toplevel-drag-v1.xml");
+}
@@ -0,0 +1,186 @@
+//! Attaches a security context to the app's Wayland connection when it runs in a sandbox, so
+//! the compositor can apply the sandbox's policies to it, with security-context-v1.
+//!
+//! Sandbox engines that support the protocol hand the app a socket that's tagged already, and
+//! compositors don't expose the manager through it, so this only kicks in when the engine
+//! didn't. The sandbox is detected from Flatpak's and Snap's environment, or configured with
+//! `GPUI_SECURITY_CONTEXT`: `0` to never tag the connection, or `engine[:app_id[:instance_id]]`
+//! to tag it as the given sandbox.
+
+use std::{
+    fs,
+    os::{fd::AsFd, unix::net::UnixListener, unix::net::UnixStream},
+    path::PathBuf,
+};
+
+use anyhow::{Context as _, Result};
+use util::ResultExt;
+use wayland_client::{
+    delegate_noop,
+    globals::{registry_queue_init, GlobalListContents},
+    protocol::wl_registry,
+    Connection, Dispatch, QueueHandle,
+};
+use wayland_protocols::wp::security_context::v1::client::{
+    wp_security_context_manager_v1::WpSecurityContextManagerV1,
+    wp_security_context_v1::WpSecurityContextV1,
+};
+
+/// The metadata attached to the connection.
+#[derive(Debug, PartialEq, Eq)]
+struct Sandbox {
+    /// The engine's name, in reverse-DNS style, like `org.flatpak`.
+    engine: String,
+    app_id: Option<String>,
+    instance_id: Option<String>,
+}
+
+impl Sandbox {
+    fn detect() -> Option<Self> {
+        if let Ok(config) = std::env::var("GPUI_SECURITY_CONTEXT") {
+            return Self::parse(&config);
+        }
+        if let Ok(info) = fs::read_to_string("/.flatpak-info") {
+            return Some(Self {
+                engine: "org.flatpak".into(),
+                app_id: std::env::var("FLATPAK_ID").ok(),
+                instance_id: flatpak_instance_id(&info),
+            });
+        }
+        if std::env::var_os("SNAP").is_some() {
+            return Some(Self {
+                engine: "io.snapcraft".into(),
+                app_id: std::env::var("SNAP_INSTANCE_NAME")
+                    .or_else(|_| std::env::var("SNAP_NAME"))
+                    .ok(),
+                instance_id: None,
+            });
+        }
+        None
+    }
+
+    fn parse(config: &str) -> Option<Self> {
+        let config = config.trim();
+        if config.is_empty() || config == "0" {
+            return None;
+        }
+        let mut parts = config.splitn(3, ':').map(str::to_string);
+        Some(Self {
+            engine: parts.next()?,
+            app_id: parts.next(),
+            instance_id: parts.next(),
+        })
+    }
+}
+
+/// Reads the instance ID from the `[Instance]` group of `/.flatpak-info`.
+fn flatpak_instance_id(info: &str) -> Option<String> {
+    let mut in_instance = false;
+    for line in info.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_instance = line == "[Instance]";
+        } else if in_instance {
+            if let Some(id) = line.strip_prefix("instance-id=") {
+                return Some(id.to_string());
+            }
+        }
+    }
+    None
+}
+
+/// A connection tagged with a security context. The compositor accepts connections on the
+/// context's socket until `_close_fd` is closed, so it's kept open alongside the connection.
+pub(crate) struct SecurityContext {
+    _close_fd: UnixStream,
+}
+
+struct State;
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for State {
+    fn event(
+        _: &mut Self,
+        _: &wl_registry::WlRegistry,
+        _: wl_registry::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+delegate_noop!(State: WpSecurityContextManagerV1);
+delegate_noop!(State: WpSecurityContextV1);
+
+/// Replaces the connection with one tagged with a security context when running in a sandbox
+/// and the compositor lets the app attach one. Otherwise, or when that fails, the connection is
+/// returned as it is. Only fails when the connection has to be replaced and reconnecting fails.
+pub(crate) fn tag_connection(
+    connection: Connection,
+) -> Result<(Connection, Option<SecurityContext>)> {
+    let Some(sandbox) = Sandbox::detect() else {
+        return Ok((connection, None));
+    };
+    match attach(&connection, &sandbox) {
+        Ok(Some((tagged, context))) => Ok((tagged, Some(context))),
+        Ok(None) => Ok((connection, None)),
+        Err(error) => {
+            log::warn!("Failed to attach a security context for {sandbox:?}: {error:#}");
+            // A protocol error kills the connection, so start over with a fresh one.
+            drop(connection);
+            let connection =
+                Connection::connect_to_env().context("failed to reconnect to the compositor")?;
+            Ok((connection, None))
+        }
+    }
+}
+
+fn attach(
+    connection: &Connection,
+    sandbox: &Sandbox,
+) -> Result<Option<(Connection, SecurityContext)>> {
+    let (globals, mut queue) = registry_queue_init::<State>(connection)?;
+    let qh = queue.handle();
+    // Not exposed to connections that have a security context already.
+    let Ok(manager) = globals.bind::<WpSecurityContextManagerV1, _, _>(&qh, 1..=1, ()) else {
+        return Ok(None);
+    };
+
+    let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .context("XDG_RUNTIME_DIR is not set")?;
+    let socket_path = runtime_dir.join(format!("gpui-security-context-{}", std::process::id()));
+    fs::remove_file(&socket_path).ok();
+    let listener = UnixListener::bind(&socket_path).context("failed to bind the socket")?;
+    let (close_fd, compositor_close_fd) = UnixStream::pair()?;
+
+    let context = manager.create_listener(listener.as_fd(), compositor_close_fd.as_fd(), &qh, ());
+    context.set_sandbox_engine(sandbox.engine.clone());
+    if let Some(app_id) = &sandbox.app_id {
+        context.set_app_id(app_id.clone());
+    }
+    if let Some(instance_id) = &sandbox.instance_id {
+        context.set_instance_id(instance_id.clone());
+    }
+    context.commit();
+    context.destroy();
+    manager.destroy();
+    let roundtrip = queue.roundtrip(&mut State);
+    // The compositor has its own copies of the descriptors now.
+    drop((listener, compositor_close_fd));
+    if let Err(error) = roundtrip {
+        fs::remove_file(&socket_path).log_err();
+        return Err(error.into());
+    }
+
+    let stream = UnixStream::connect(&socket_path);
+    // Nothing else should be able to connect through the context.
+    fs::remove_file(&socket_path).log_err();
+    let tagged = Connection::from_socket(stream.context("failed to connect to the socket")?)?;
+    log::info!("Attached a security context for {sandbox:?} to the Wayland connection");
+    Ok(Some((
+        tagged,
+        SecurityContext {
+            _close_fd: close_fd,
+        },
+    )))
+}
@@ -1,22 +1,33 @@
 use collections::HashMap;
 
-#[derive(Debug, Hash, PartialEq, Eq)]
+use crate::InputSerialKind;
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
 pub(crate) enum SerialKind {
     DataDevice,
     InputMethod,
-    MouseEnter,
-    MousePress,
-    KeyPress,
+    Input(InputSerialKind),
+}
+
+impl From<InputSerialKind> for SerialKind {
+    fn from(kind: InputSerialKind) -> Self {
+        Self::Input(kind)
+    }
 }
 
 #[derive(Debug)]
 struct SerialData {
     serial: u32,
+    /// When the serial was tracked, relative to the other kinds.
+    order: u64,
 }
 
 impl SerialData {
-    fn new(value: u32) -> Self {
-        Self { serial: value }
+    fn new(value: u32, order: u64) -> Self {
+        Self {
+            serial: value,
+            order,
+        }
     }
 }
 
@@ -24,26 +35,62 @@ impl SerialData {
 /// Helper for tracking of different serial kinds.
 pub(crate) struct SerialTracker {
     serials: HashMap<SerialKind, SerialData>,
+    updates: u64,
 }
 
 impl SerialTracker {
     pub fn new() -> Self {
         Self {
             serials: HashMap::default(),
+            updates: 0,
         }
     }
 
-    pub fn update(&mut self, kind: SerialKind, value: u32) {
-        self.serials.insert(kind, SerialData::new(value));
+    pub fn update(&mut self, kind: impl Into<SerialKind>, value: u32) {
+        self.updates += 1;
+        self.serials
+            .insert(kind.into(), SerialData::new(value, self.updates));
     }
 
     /// Returns the latest tracked serial of the provided [`SerialKind`]
     ///
     /// Will return 0 if not tracked.
-    pub fn get(&self, kind: SerialKind) -> u32 {
+    pub fn get(&self, kind: impl Into<SerialKind>) -> u32 {
         self.serials
-            .get(&kind)
+            .get(&kind.into())
             .map(|serial_data| serial_data.serial)
             .unwrap_or(0)
     }
+
+    /// Returns whichever of the provided kinds was tracked last with its serial, e.g. the press
+    /// of a mouse button or a touch that started a drag.
+    pub fn latest<K: Copy + Into<SerialKind>>(&self, kinds: &[K]) -> Option<(K, u32)> {
+        kinds
+            .iter()
+            .filter_map(|kind| Some((*kind, self.serials.get(&(*kind).into())?)))
+            .max_by_key(|(_, serial_data)| serial_data.order)
+            .map(|(kind, serial_data)| (kind, serial_data.serial))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SerialKind, SerialTracker};
+    use crate::InputSerialKind;
+
+    #[test]
+    fn test_latest_serial() {
+        let button = SerialKind::from(InputSerialKind::PointerButton);
+        let touch = SerialKind::from(InputSerialKind::TouchDown);
+        let mut tracker = SerialTracker::new();
+        assert_eq!(tracker.latest(&[button]), None);
+
+        tracker.update(button, 10);
+        tracker.update(touch, 5);
+        tracker.update(SerialKind::from(InputSerialKind::KeyPress), 20);
+        assert_eq!(tracker.latest(&[button, touch]), Some((touch, 5)));
+
+        tracker.update(button, 30);
+        assert_eq!(tracker.latest(&[button, touch]), Some((button, 30)));
+    }
 }
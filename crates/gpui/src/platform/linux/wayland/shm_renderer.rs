@@ -0,0 +1,248 @@
+//! A CPU renderer presenting through `wl_shm` buffers, used when no usable GPU is available.
+
+use std::{
+    fs::File,
+    os::{fd::AsFd, unix::fs::FileExt},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use anyhow::Result;
+use util::ResultExt;
+use wayland_client::{
+    protocol::{wl_buffer, wl_shm, wl_shm_pool, wl_surface},
+    Connection, Dispatch, Proxy, QueueHandle,
+};
+
+use crate::{
+    platform::linux::canvas::{Canvas, CanvasAtlas},
+    DevicePixels, GpuSpecs, RenderStats, Scene, Size, WaylandClientStatePtr,
+};
+use crate::{scene::Damage, Globals};
+
+pub(crate) struct ShmRenderer {
+    shm: wl_shm::WlShm,
+    qh: QueueHandle<WaylandClientStatePtr>,
+    surface: wl_surface::WlSurface,
+    atlas: Arc<CanvasAtlas>,
+    size: Size<DevicePixels>,
+    canvas: Canvas,
+    buffers: Vec<ShmBuffer>,
+    staging: Vec<u8>,
+    needs_full_redraw: bool,
+    // Set while the canvas is released, see `release_resources`
+    released: bool,
+}
+
+impl ShmRenderer {
+    pub fn new(
+        globals: &Globals,
+        surface: wl_surface::WlSurface,
+        size: Size<DevicePixels>,
+    ) -> Self {
+        Self {
+            shm: globals.shm.clone(),
+            qh: globals.qh.clone(),
+            surface,
+            atlas: Arc::new(CanvasAtlas::new()),
+            size,
+            canvas: Canvas::new(size),
+            buffers: Vec::new(),
+            staging: Vec::new(),
+            needs_full_redraw: true,
+            released: false,
+        }
+    }
+
+    pub fn update_drawable_size(&mut self, size: Size<DevicePixels>) {
+        if size != self.size {
+            self.size = size;
+            if !self.released {
+                self.canvas = Canvas::new(size);
+            }
+            self.needs_full_redraw = true;
+        }
+    }
+
+    pub fn update_transparency(&mut self, _transparent: bool) {
+        // Buffers are always ARGB, opaque windows simply paint every pixel.
+        self.needs_full_redraw = true;
+    }
+
+    /// Returns the area of the surface that changes when drawing `scene`.
+    pub fn damage(&self, scene: &Scene) -> Damage {
+        if self.needs_full_redraw {
+            Damage::Full
+        } else {
+            scene.damage.clone()
+        }
+    }
+
+    pub fn sprite_atlas(&self) -> &Arc<CanvasAtlas> {
+        &self.atlas
+    }
+
+    pub fn render_stats(&self) -> RenderStats {
+        RenderStats {
+            atlas_bytes: self.atlas.memory_usage(),
+            ..Default::default()
+        }
+    }
+
+    pub fn gpu_specs(&self) -> GpuSpecs {
+        GpuSpecs {
+            is_software_emulated: true,
+            device_name: "wl_shm".to_string(),
+            driver_name: "tiny-skia".to_string(),
+            driver_info: String::new(),
+        }
+    }
+
+    /// Frees the canvas of a hidden window along with the transient buffers. They are recreated
+    /// on the next draw.
+    pub fn release_resources(&mut self) {
+        self.release_transient_resources();
+        if !self.released {
+            self.released = true;
+            self.canvas = Canvas::new(Size::default());
+            self.needs_full_redraw = true;
+        }
+    }
+
+    /// Frees the buffers the compositor isn't holding on to, for idle windows that are still
+    /// shown. The canvas is kept, so the next draw only repaints what changed.
+    pub fn release_transient_resources(&mut self) {
+        self.buffers.retain(ShmBuffer::is_busy);
+        self.staging = Vec::new();
+    }
+
+    pub fn destroy(&mut self) {
+        self.buffers.clear();
+    }
+
+    pub fn draw(&mut self, scene: &Scene) {
+        let damage = self.damage(scene);
+        if damage == Damage::None {
+            return;
+        }
+        self.atlas.next_frame();
+        self.needs_full_redraw = false;
+        if std::mem::take(&mut self.released) {
+            self.canvas = Canvas::new(self.size);
+        }
+
+        self.canvas.draw(scene, &self.atlas);
+
+        if damage == Damage::Full {
+            if self.surface.version() >= wl_surface::REQ_DAMAGE_BUFFER_SINCE {
+                self.surface.damage_buffer(0, 0, i32::MAX, i32::MAX);
+            } else {
+                self.surface.damage(0, 0, i32::MAX, i32::MAX);
+            }
+        }
+        self.present().log_err();
+    }
+
+    fn present(&mut self) -> Result<()> {
+        let width = self.canvas.pixmap().width();
+        let height = self.canvas.pixmap().height();
+
+        // Drop buffers of a previous size once the compositor is done with them.
+        self.buffers
+            .retain(|buffer| buffer.is_busy() || (buffer.width, buffer.height) == (width, height));
+        let index = match self.buffers.iter().position(|buffer| !buffer.is_busy()) {
+            Some(index) => index,
+            None => {
+                self.buffers
+                    .push(ShmBuffer::new(&self.shm, &self.qh, width, height)?);
+                self.buffers.len() - 1
+            }
+        };
+        let buffer = &self.buffers[index];
+
+        // tiny-skia produces premultiplied RGBA, wl_shm expects premultiplied little-endian ARGB.
+        self.staging.clear();
+        self.staging.extend(
+            self.canvas
+                .pixmap()
+                .data()
+                .chunks_exact(4)
+                .flat_map(|pixel| [pixel[2], pixel[1], pixel[0], pixel[3]]),
+        );
+        buffer.file.write_all_at(&self.staging, 0)?;
+
+        buffer.busy.store(true, Ordering::Release);
+        self.surface.attach(Some(&buffer.buffer), 0, 0);
+        Ok(())
+    }
+}
+
+struct ShmBuffer {
+    file: File,
+    pool: wl_shm_pool::WlShmPool,
+    buffer: wl_buffer::WlBuffer,
+    width: u32,
+    height: u32,
+    busy: Arc<AtomicBool>,
+}
+
+impl ShmBuffer {
+    fn new(
+        shm: &wl_shm::WlShm,
+        qh: &QueueHandle<WaylandClientStatePtr>,
+        width: u32,
+        height: u32,
+    ) -> Result<Self> {
+        let stride = width * 4;
+        let len = stride * height;
+        let file = tempfile::tempfile()?;
+        file.set_len(len as u64)?;
+        let pool = shm.create_pool(file.as_fd(), len as i32, qh, ());
+        let busy = Arc::new(AtomicBool::new(false));
+        let buffer = pool.create_buffer(
+            0,
+            width as i32,
+            height as i32,
+            stride as i32,
+            wl_shm::Format::Argb8888,
+            qh,
+            busy.clone(),
+        );
+        Ok(Self {
+            file,
+            pool,
+            buffer,
+            width,
+            height,
+            busy,
+        })
+    }
+
+    fn is_busy(&self) -> bool {
+        self.busy.load(Ordering::Acquire)
+    }
+}
+
+impl Drop for ShmBuffer {
+    fn drop(&mut self) {
+        self.buffer.destroy();
+        self.pool.destroy();
+    }
+}
+
+impl Dispatch<wl_buffer::WlBuffer, Arc<AtomicBool>> for WaylandClientStatePtr {
+    fn event(
+        _: &mut Self,
+        _: &wl_buffer::WlBuffer,
+        event: wl_buffer::Event,
+        busy: &Arc<AtomicBool>,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let wl_buffer::Event::Release = event {
+            busy.store(false, Ordering::Release);
+        }
+    }
+}
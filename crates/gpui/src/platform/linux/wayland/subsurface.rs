@@ -0,0 +1,120 @@
+//! Embedding of external content into windows through `wl_subsurface`s, so it reaches the
+//! compositor without passing through the renderer.
+
+use std::cell::RefCell;
+use std::ffi::c_void;
+use std::fmt;
+use std::ptr::NonNull;
+use std::rc::Rc;
+
+use raw_window_handle as rwh;
+use wayland_client::protocol::{wl_subsurface, wl_surface};
+use wayland_client::Proxy;
+
+use crate::{Bounds, Globals, PaintSurface, Pixels};
+
+/// A `wl_surface` embedded in a window through a `wl_subsurface`, whose content is provided by an
+/// external producer such as GStreamer's `waylandsink`.
+///
+/// The producer attaches buffers to the surface exposed through [`rwh::HasWindowHandle`], using
+/// the connection exposed through [`rwh::HasDisplayHandle`], and commits them on its own
+/// schedule. Paint the surface with a [`surface`](crate::surface) element to position it. It is
+/// stacked above the window's own content, isn't clipped, and stays where it was last painted
+/// until every clone of it is dropped.
+#[derive(Clone)]
+pub struct ForeignSurface(Rc<ForeignSurfaceState>);
+
+struct ForeignSurfaceState {
+    surface: wl_surface::WlSurface,
+    subsurface: wl_subsurface::WlSubsurface,
+    bounds: RefCell<Option<Bounds<Pixels>>>,
+}
+
+impl ForeignSurface {
+    pub(crate) fn new(globals: &Globals, parent: &wl_surface::WlSurface) -> Option<Self> {
+        let subcompositor = globals.subcompositor.as_ref()?;
+        let surface = globals.compositor.create_surface(&globals.qh, ());
+        let subsurface = subcompositor.get_subsurface(&surface, parent, &globals.qh, ());
+        // Let the producer present frames without waiting for the window to commit.
+        subsurface.set_desync();
+        let region = globals.compositor.create_region(&globals.qh, ());
+        surface.set_input_region(Some(&region));
+        region.destroy();
+        Some(Self(Rc::new(ForeignSurfaceState {
+            surface,
+            subsurface,
+            bounds: RefCell::new(None),
+        })))
+    }
+
+    /// Returns the bounds of the window where the surface was last painted, in logical pixels.
+    /// Producers should size their buffers accordingly.
+    pub fn bounds(&self) -> Option<Bounds<Pixels>> {
+        self.0.bounds.borrow().clone()
+    }
+
+    /// Moves the surface to where `paint` puts it. The change is applied atomically with the next
+    /// commit of the window.
+    pub(crate) fn update(&self, paint: &PaintSurface, scale: f32) {
+        let bounds = paint.bounds.map(|pixels| Pixels(pixels.0 / scale));
+        let origin = bounds.origin;
+        if self
+            .0
+            .bounds
+            .replace(Some(bounds))
+            .map(|bounds| bounds.origin)
+            != Some(origin)
+        {
+            self.0
+                .subsurface
+                .set_position(origin.x.0.round() as i32, origin.y.0.round() as i32);
+        }
+    }
+}
+
+impl Drop for ForeignSurfaceState {
+    fn drop(&mut self) {
+        self.subsurface.destroy();
+        self.surface.destroy();
+    }
+}
+
+impl PartialEq for ForeignSurface {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for ForeignSurface {}
+
+impl fmt::Debug for ForeignSurface {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ForeignSurface")
+            .field(&self.0.surface.id())
+            .finish()
+    }
+}
+
+impl rwh::HasWindowHandle for ForeignSurface {
+    fn window_handle(&self) -> Result<rwh::WindowHandle<'_>, rwh::HandleError> {
+        let surface = NonNull::new(self.0.surface.id().as_ptr().cast::<c_void>())
+            .ok_or(rwh::HandleError::Unavailable)?;
+        let handle = rwh::WaylandWindowHandle::new(surface);
+        Ok(unsafe { rwh::WindowHandle::borrow_raw(handle.into()) })
+    }
+}
+
+impl rwh::HasDisplayHandle for ForeignSurface {
+    fn display_handle(&self) -> Result<rwh::DisplayHandle<'_>, rwh::HandleError> {
+        let backend = self
+            .0
+            .surface
+            .backend()
+            .upgrade()
+            .ok_or(rwh::HandleError::Unavailable)?;
+        let display = NonNull::new(backend.display_ptr().cast::<c_void>())
+            .ok_or(rwh::HandleError::Unavailable)?;
+        let handle = rwh::WaylandDisplayHandle::new(display);
+        Ok(unsafe { rwh::DisplayHandle::borrow_raw(handle.into()) })
+    }
+}
@@ -0,0 +1,82 @@
+//! Logs the Wayland events the client handles and the requests answering them, enabled with the
+//! `GPUI_WAYLAND_TRACE` environment variable.
+//!
+//! `GPUI_WAYLAND_TRACE=1` (or `all`) traces every interface, a comma separated list of interface
+//! names like `xdg_surface,zwlr_layer_surface_v1` only those. The messages are logged at the info
+//! level with the `gpui::wayland` target.
+
+use std::{fmt::Debug, sync::LazyLock};
+
+use wayland_client::Proxy;
+
+const TARGET: &str = "gpui::wayland";
+
+enum Filter {
+    All,
+    Interfaces(Vec<String>),
+}
+
+impl Filter {
+    fn from_env() -> Option<Self> {
+        let value = std::env::var("GPUI_WAYLAND_TRACE").ok()?;
+        match value.trim() {
+            "" | "0" => None,
+            "1" | "all" => Some(Self::All),
+            interfaces => Some(Self::Interfaces(
+                interfaces
+                    .split(',')
+                    .map(|interface| interface.trim().to_string())
+                    .filter(|interface| !interface.is_empty())
+                    .collect(),
+            )),
+        }
+    }
+
+    fn matches(&self, interface: &str) -> bool {
+        match self {
+            Self::All => true,
+            Self::Interfaces(interfaces) => interfaces.iter().any(|name| name == interface),
+        }
+    }
+}
+
+static FILTER: LazyLock<Option<Filter>> = LazyLock::new(Filter::from_env);
+
+fn enabled<I: Proxy>() -> bool {
+    FILTER
+        .as_ref()
+        .map_or(false, |filter| filter.matches(I::interface().name))
+}
+
+/// Logs an event received on the given object.
+pub(crate) fn event<I: Proxy>(proxy: &I, event: &I::Event)
+where
+    I::Event: Debug,
+{
+    if enabled::<I>() {
+        log::info!(
+            target: TARGET,
+            "{}@{} <- {:?}",
+            I::interface().name,
+            proxy.id().protocol_id(),
+            event
+        );
+    }
+}
+
+/// Sends a request on the given object, logging it first.
+pub(crate) fn request<'a, I: Proxy>(proxy: &I, request: I::Request<'a>)
+where
+    I::Request<'a>: Debug,
+{
+    if enabled::<I>() {
+        log::info!(
+            target: TARGET,
+            "{}@{} -> {:?}",
+            I::interface().name,
+            proxy.id().protocol_id(),
+            request
+        );
+    }
+    proxy.send_request(request).ok();
+}
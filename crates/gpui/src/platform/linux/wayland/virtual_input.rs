@@ -0,0 +1,275 @@
+use std::{fmt::Write as _, io::Write as _, os::fd::AsFd, time::Instant};
+
+use anyhow::{anyhow, Result};
+use wayland_client::{
+    protocol::{wl_keyboard, wl_pointer, wl_seat},
+    QueueHandle,
+};
+use wayland_protocols_wlr::virtual_pointer::v1::client::{
+    zwlr_virtual_pointer_manager_v1, zwlr_virtual_pointer_v1,
+};
+use xkbcommon::xkb::{self, Keysym};
+
+use super::{
+    client::gpui_button_to_linux,
+    protocols::virtual_keyboard_unstable_v1::{
+        zwp_virtual_keyboard_manager_v1, zwp_virtual_keyboard_v1,
+    },
+};
+use crate::{
+    px, Bounds, Pixels, ScrollDelta, VirtualKeyboardInput, VirtualPointerInput,
+    WaylandClientStatePtr, SCROLL_LINES,
+};
+
+/// The scroll distance of a wheel step, as reported by libinput.
+const WHEEL_STEP: f64 = 15.;
+
+/// A pointer created with wlr-virtual-pointer, which the compositor handles like a real one.
+pub(crate) struct VirtualPointer {
+    proxy: zwlr_virtual_pointer_v1::ZwlrVirtualPointerV1,
+    /// The time the timestamps of the events are relative to.
+    started_at: Instant,
+}
+
+impl VirtualPointer {
+    pub(crate) fn new(
+        manager: &zwlr_virtual_pointer_manager_v1::ZwlrVirtualPointerManagerV1,
+        seat: Option<&wl_seat::WlSeat>,
+        qh: &QueueHandle<WaylandClientStatePtr>,
+    ) -> Self {
+        Self {
+            proxy: manager.create_virtual_pointer(seat, qh, ()),
+            started_at: Instant::now(),
+        }
+    }
+
+    /// Sends the input as a single pointer frame. Absolute positions are mapped within the
+    /// bounds of all the displays.
+    pub(crate) fn send(&self, input: &VirtualPointerInput, layout: Bounds<Pixels>) {
+        let time = self.started_at.elapsed().as_millis() as u32;
+        match input {
+            VirtualPointerInput::MoveBy(delta) => {
+                self.proxy.motion(time, delta.x.0 as f64, delta.y.0 as f64);
+            }
+            VirtualPointerInput::MoveTo(position) => {
+                let x = (position.x - layout.origin.x).clamp(px(0.), layout.size.width);
+                let y = (position.y - layout.origin.y).clamp(px(0.), layout.size.height);
+                self.proxy.motion_absolute(
+                    time,
+                    x.0 as u32,
+                    y.0 as u32,
+                    layout.size.width.0.max(1.) as u32,
+                    layout.size.height.0.max(1.) as u32,
+                );
+            }
+            VirtualPointerInput::Press(button) => {
+                self.proxy.button(
+                    time,
+                    gpui_button_to_linux(*button),
+                    wl_pointer::ButtonState::Pressed,
+                );
+            }
+            VirtualPointerInput::Release(button) => {
+                self.proxy.button(
+                    time,
+                    gpui_button_to_linux(*button),
+                    wl_pointer::ButtonState::Released,
+                );
+            }
+            // gpui reports scrolling in the opposite direction of the axis events.
+            VirtualPointerInput::Scroll(ScrollDelta::Pixels(delta)) => {
+                self.proxy.axis_source(wl_pointer::AxisSource::Finger);
+                for (axis, value) in [
+                    (wl_pointer::Axis::HorizontalScroll, delta.x),
+                    (wl_pointer::Axis::VerticalScroll, delta.y),
+                ] {
+                    if value != px(0.) {
+                        self.proxy.axis(time, axis, -value.0 as f64);
+                    }
+                }
+            }
+            VirtualPointerInput::Scroll(ScrollDelta::Lines(delta)) => {
+                self.proxy.axis_source(wl_pointer::AxisSource::Wheel);
+                for (axis, lines) in [
+                    (wl_pointer::Axis::HorizontalScroll, delta.x),
+                    (wl_pointer::Axis::VerticalScroll, delta.y),
+                ] {
+                    let steps = (-lines / SCROLL_LINES).round() as i32;
+                    if steps != 0 {
+                        self.proxy
+                            .axis_discrete(time, axis, steps as f64 * WHEEL_STEP, steps);
+                    }
+                }
+            }
+        }
+        self.proxy.frame();
+    }
+}
+
+impl Drop for VirtualPointer {
+    fn drop(&mut self) {
+        self.proxy.destroy();
+    }
+}
+
+/// The highest keycode of an XKB keymap, keycodes start at 8.
+const MAX_XKB_KEYCODE: usize = 255;
+
+/// Real modifier masks, which are the same in every XKB keymap.
+const SHIFT_MASK: u32 = 1 << 0;
+const CONTROL_MASK: u32 = 1 << 2;
+const ALT_MASK: u32 = 1 << 3;
+const LOGO_MASK: u32 = 1 << 6;
+
+/// A keyboard created with virtual-keyboard. Its keymap is generated to contain a key for each
+/// keysym that was typed, so that any text can be typed regardless of the layout of the user.
+pub(crate) struct VirtualKeyboard {
+    proxy: zwp_virtual_keyboard_v1::ZwpVirtualKeyboardV1,
+    /// The time the timestamps of the events are relative to.
+    started_at: Instant,
+    /// The keysyms of the keys in the current keymap, the key of the keysym at index `i` has
+    /// the evdev keycode `i + 1`.
+    keysyms: Vec<Keysym>,
+}
+
+impl VirtualKeyboard {
+    pub(crate) fn new(
+        manager: &zwp_virtual_keyboard_manager_v1::ZwpVirtualKeyboardManagerV1,
+        seat: &wl_seat::WlSeat,
+        qh: &QueueHandle<WaylandClientStatePtr>,
+    ) -> Self {
+        Self {
+            proxy: manager.create_virtual_keyboard(seat, qh, ()),
+            started_at: Instant::now(),
+            keysyms: Vec::new(),
+        }
+    }
+
+    pub(crate) fn send(&mut self, input: &VirtualKeyboardInput) -> Result<()> {
+        match input {
+            VirtualKeyboardInput::Text(text) => {
+                for character in text.chars() {
+                    let keysym = match character {
+                        '\n' => Keysym::Return,
+                        '\t' => Keysym::Tab,
+                        _ => xkb::utf32_to_keysym(character as u32),
+                    };
+                    if keysym == Keysym::NoSymbol {
+                        return Err(anyhow!("can't type {character:?}"));
+                    }
+                    self.press_and_release(keysym, 0)?;
+                }
+            }
+            VirtualKeyboardInput::Keystroke(keystroke) => {
+                let keysym = keystroke_keysym(&keystroke.key)
+                    .ok_or_else(|| anyhow!("unknown key {:?}", keystroke.key))?;
+                let modifiers = &keystroke.modifiers;
+                let mut mask = 0;
+                for (active, modifier_mask) in [
+                    (modifiers.shift, SHIFT_MASK),
+                    (modifiers.control, CONTROL_MASK),
+                    (modifiers.alt, ALT_MASK),
+                    (modifiers.platform, LOGO_MASK),
+                ] {
+                    if active {
+                        mask |= modifier_mask;
+                    }
+                }
+                self.press_and_release(keysym, mask)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn press_and_release(&mut self, keysym: Keysym, modifiers: u32) -> Result<()> {
+        let keycode = self.keycode(keysym)?;
+        let time = self.started_at.elapsed().as_millis() as u32;
+        if modifiers != 0 {
+            self.proxy.modifiers(modifiers, 0, 0, 0);
+        }
+        self.proxy
+            .key(time, keycode, wl_keyboard::KeyState::Pressed.into());
+        self.proxy
+            .key(time, keycode, wl_keyboard::KeyState::Released.into());
+        if modifiers != 0 {
+            self.proxy.modifiers(0, 0, 0, 0);
+        }
+        Ok(())
+    }
+
+    /// Returns the evdev keycode of the keysym, adding it to the keymap if needed.
+    fn keycode(&mut self, keysym: Keysym) -> Result<u32> {
+        if let Some(ix) = self.keysyms.iter().position(|key| *key == keysym) {
+            return Ok(ix as u32 + 1);
+        }
+        // Starts over once the keymap is full, the keys that were sent are already handled.
+        if self.keysyms.len() == MAX_XKB_KEYCODE - 8 {
+            self.keysyms.clear();
+        }
+        self.keysyms.push(keysym);
+        self.upload_keymap()?;
+        Ok(self.keysyms.len() as u32)
+    }
+
+    fn upload_keymap(&self) -> Result<()> {
+        let mut keymap = String::from("xkb_keymap {\n");
+        writeln!(
+            keymap,
+            "xkb_keycodes \"(unnamed)\" {{ minimum = 8; maximum = {MAX_XKB_KEYCODE};"
+        )?;
+        for keycode in 9..self.keysyms.len() + 9 {
+            writeln!(keymap, "<K{keycode}> = {keycode};")?;
+        }
+        keymap.push_str("};\n");
+        keymap.push_str("xkb_types \"(unnamed)\" { include \"complete\" };\n");
+        keymap.push_str("xkb_compatibility \"(unnamed)\" { include \"complete\" };\n");
+        keymap.push_str("xkb_symbols \"(unnamed)\" {\n");
+        for (keycode, keysym) in (9..).zip(&self.keysyms) {
+            let name = xkb::keysym_get_name(*keysym);
+            writeln!(keymap, "key <K{keycode}> {{ [ {name} ] }};")?;
+        }
+        keymap.push_str("};\n};\n");
+
+        // The compositor maps the keymap, which includes the terminating nul byte.
+        let mut file = tempfile::tempfile()?;
+        file.write_all(keymap.as_bytes())?;
+        file.write_all(&[0])?;
+        self.proxy.keymap(
+            wl_keyboard::KeymapFormat::XkbV1.into(),
+            file.as_fd(),
+            keymap.len() as u32 + 1,
+        );
+        Ok(())
+    }
+}
+
+impl Drop for VirtualKeyboard {
+    fn drop(&mut self) {
+        self.proxy.destroy();
+    }
+}
+
+/// Returns the keysym of the key of a [`crate::Keystroke`], the inverse of how gpui names keysyms.
+fn keystroke_keysym(key: &str) -> Option<Keysym> {
+    let keysym = match key {
+        "enter" => Keysym::Return,
+        "pageup" => Keysym::Prior,
+        "pagedown" => Keysym::Next,
+        "back" => Keysym::XF86_Back,
+        "forward" => Keysym::XF86_Forward,
+        "cut" => Keysym::XF86_Cut,
+        "copy" => Keysym::XF86_Copy,
+        "paste" => Keysym::XF86_Paste,
+        "new" => Keysym::XF86_New,
+        "open" => Keysym::XF86_Open,
+        "save" => Keysym::XF86_Save,
+        _ => {
+            let mut chars = key.chars();
+            match (chars.next(), chars.next()) {
+                (Some(character), None) => xkb::utf32_to_keysym(character as u32),
+                _ => xkb::keysym_from_name(key, xkb::KEYSYM_CASE_INSENSITIVE),
+            }
+        }
+    };
+    (keysym != Keysym::NoSymbol).then_some(keysym)
+}
@@ -1,13 +1,13 @@
 use std::{
     cell::{Ref, RefCell, RefMut},
     ffi::c_void,
+    ops::Range,
     ptr::NonNull,
     rc::Rc,
     sync::Arc,
+    time::{Duration, Instant},
 };
 
-use bitflags::bitflags;
-
 use blade_graphics as gpu;
 use collections::HashMap;
 use futures::channel::oneshot::Receiver;
@@ -15,40 +15,59 @@ use futures::channel::oneshot::Receiver;
 use raw_window_handle as rwh;
 use wayland_backend::client::ObjectId;
 use wayland_client::WEnum;
-use wayland_client::{protocol::wl_surface, Proxy};
+use wayland_client::{
+    protocol::{wl_seat, wl_surface},
+    Proxy,
+};
 use wayland_protocols::xdg::shell::client::xdg_surface;
 use wayland_protocols::xdg::shell::client::xdg_toplevel::{self};
+use wayland_protocols::xdg::shell::client::{xdg_popup, xdg_positioner};
 use wayland_protocols::xdg::{
     decoration::zv1::client::zxdg_toplevel_decoration_v1::{self, ZxdgToplevelDecorationV1},
     shell::client::xdg_toplevel::XdgToplevel,
 };
 use wayland_protocols::{
     wp::fractional_scale::v1::client::wp_fractional_scale_v1,
+    wp::linux_dmabuf::zv1::client::{zwp_linux_dmabuf_feedback_v1, zwp_linux_dmabuf_v1},
     xdg::shell::client::xdg_surface::XdgSurface,
 };
 use wayland_protocols::{
     wp::viewporter::client::wp_viewport, xdg::shell::client::xdg_popup::XdgPopup,
 };
+use wayland_protocols_plasma::appmenu::client::org_kde_kwin_appmenu;
 use wayland_protocols_plasma::blur::client::org_kde_kwin_blur;
+
+use super::protocols::ext_background_effect_v1::ext_background_effect_surface_v1;
 use wayland_protocols_wlr::layer_shell::v1::client::{
     zwlr_layer_shell_v1,
     zwlr_layer_surface_v1::{self, ZwlrLayerSurfaceV1},
 };
 
-use crate::scene::Scene;
+use crate::scene::{Damage, Scene};
 use crate::{
     platform::{
         blade::{BladeContext, BladeRenderer, BladeSurfaceConfig},
-        linux::wayland::{display::WaylandDisplay, serial::SerialKind},
-        PlatformAtlas, PlatformInputHandler, PlatformWindow,
+        compute_outer_size, inset_exclusive_zone, inset_margin, layer_surface_size,
+        linux::gles_renderer::{GlesRenderer, GlesWindow},
+        linux::wayland::{
+            display::WaylandDisplay,
+            dmabuf_feedback::{DmabufFeedback, DmabufFeedbackBuilder},
+            shm_renderer::ShmRenderer,
+            subsurface::ForeignSurface,
+            trace,
+        },
+        AccessibilityAdapter, CompositorScale, Configure, PlatformAtlas, PlatformInputHandler,
+        PlatformWindow, ToplevelState,
     },
-    WindowKind,
+    Corner, KeyboardInteractivity, Layer, WindowKind,
 };
 use crate::{
-    px, size, AnyWindowHandle, Bounds, Decorations, Globals, GpuSpecs, Modifiers, Output, Pixels,
-    PlatformDisplay, PlatformInput, Point, PromptLevel, RequestFrameOptions, ResizeEdge,
-    ScaledPixels, Size, Tiling, WaylandClientStatePtr, WindowAppearance,
-    WindowBackgroundAppearance, WindowBounds, WindowControls, WindowDecorations, WindowParams,
+    px, size, AccessibilityRequest, AccessibilityTree, AntiAliasing, AnyWindowHandle, Bounds,
+    Decorations, DevicePixels, Globals, GpuSpecs, InputSerial, InputSerialKind, KeyRepeatSettings,
+    Modifiers, Output, Pixels, PlatformDisplay, PlatformInput, Point, PromptLevel, RenderStats,
+    RequestFrameOptions, ResizeEdge, ScaledPixels, SeatId, Size, Tiling, WaylandClientStatePtr,
+    WindowAppearance, WindowBackgroundAppearance, WindowBounds, WindowControls,
+    WindowCreationError, WindowDecorations, WindowParams,
 };
 
 #[derive(Default)]
@@ -62,6 +81,39 @@ pub(crate) struct Callbacks {
     should_close: Option<Box<dyn FnMut() -> bool>>,
     close: Option<Box<dyn FnOnce()>>,
     appearance_changed: Option<Box<dyn FnMut()>>,
+    throttle_changed: Option<Box<dyn FnMut(bool)>>,
+    dmabuf_feedback_changed: Option<Box<dyn FnMut()>>,
+}
+
+/// How long to wait for a requested frame callback before considering the window throttled.
+pub(crate) const FRAME_CALLBACK_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How long a window can go without drawing before its renderer releases the transient buffers.
+const GPU_RESOURCES_IDLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The timing of the last frame of a window the compositor presented, reported with
+/// presentation-time.
+#[derive(Clone, Copy, Debug)]
+struct PresentationTiming {
+    /// The time of the presentation on the clock of the compositor.
+    timestamp: Duration,
+    /// The time of the presentation mapped to an [`Instant`].
+    presented_at: Instant,
+    /// The refresh interval of the output, zero when it is unknown or variable.
+    refresh: Duration,
+}
+
+impl PresentationTiming {
+    /// Returns when the next frame drawn at `now` will be shown, the first refresh after it.
+    fn next_presentation(&self, now: Instant) -> Instant {
+        let elapsed = now.saturating_duration_since(self.presented_at);
+        // The timing is stale after the window wasn't drawn for a while.
+        if self.refresh.is_zero() || elapsed >= FRAME_CALLBACK_TIMEOUT {
+            return now;
+        }
+        let refreshes = elapsed.as_nanos() / self.refresh.as_nanos() + 1;
+        self.presented_at + self.refresh * refreshes as u32
+    }
 }
 
 struct RawWindow {
@@ -84,88 +136,6 @@ impl rwh::HasDisplayHandle for RawWindow {
     }
 }
 
-#[derive(Debug)]
-struct InProgressConfigure {
-    size: Option<Size<Pixels>>,
-    fullscreen: bool,
-    maximized: bool,
-    tiling: Tiling,
-}
-
-/// The z-depth of a layer
-///
-/// These values indicate which order in which layer surfaces are rendered.
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
-pub enum Layer {
-    /// The background layer
-    Background,
-    /// The bottom layer
-    Bottom,
-    /// The top layer
-    Top,
-    /// The overlay layer
-    Overlay,
-}
-
-bitflags! {
-    /// The anchor point for a layer shell surface
-    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
-    pub struct Anchor: u32 {
-        /// The top edge of the surface
-        const TOP = 1;
-        /// The bottom edge of the surface
-        const BOTTOM = 2;
-        /// The left edge of the surface
-        const LEFT = 4;
-        /// The right edge of the surface
-        const RIGHT = 8;
-    }
-}
-
-/// Types of keyboard interaction possible for a layer shell surface
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
-pub enum KeyboardInteractivity {
-    /// No keyboard focus is possible
-    None,
-    ///Request exclusive keyboard focus
-    Exclusive,
-    /// Request regular keyboard focus semantics
-    OnDemand,
-}
-
-/// Settings for a layer shell surface
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub struct LayerShellSettings {
-    /// Layer of the surface
-    pub layer: Layer,
-    /// Anchor point of the surface
-    pub anchor: Anchor,
-    /// The exclusive edge will prevent other surfaces from being placed in the same area
-    pub exclusive_zone: Option<Pixels>,
-    /// The distance away from the anchor point
-    pub margin: Option<(Pixels, Pixels, Pixels, Pixels)>,
-    /// Types of keyboard interaction possible for layer shell surfaces
-    pub keyboard_interactivity: KeyboardInteractivity,
-    /// Whether the surface should receive pointer events
-    pub pointer_interactivity: bool,
-    /// Namespace for the layer shell surface
-    pub namespace: String,
-}
-
-impl Default for LayerShellSettings {
-    fn default() -> Self {
-        Self {
-            layer: Layer::Top,
-            anchor: Anchor::RIGHT | Anchor::LEFT,
-            exclusive_zone: None,
-            margin: None,
-            keyboard_interactivity: KeyboardInteractivity::Exclusive,
-            pointer_interactivity: true,
-            namespace: String::new(),
-        }
-    }
-}
-
 impl From<Layer> for zwlr_layer_shell_v1::Layer {
     fn from(layer: Layer) -> Self {
         match layer {
@@ -187,6 +157,103 @@ impl From<KeyboardInteractivity> for zwlr_layer_surface_v1::KeyboardInteractivit
     }
 }
 
+enum Renderer {
+    Blade(BladeRenderer),
+    Gles(GlesRenderer),
+    Shm(ShmRenderer),
+}
+
+impl Renderer {
+    fn update_drawable_size(&mut self, size: Size<DevicePixels>) {
+        match self {
+            Renderer::Blade(renderer) => renderer.update_drawable_size(size),
+            Renderer::Gles(renderer) => renderer.update_drawable_size(size),
+            Renderer::Shm(renderer) => renderer.update_drawable_size(size),
+        }
+    }
+
+    fn update_transparency(&mut self, transparent: bool) {
+        match self {
+            Renderer::Blade(renderer) => renderer.update_transparency(transparent),
+            Renderer::Gles(renderer) => renderer.update_transparency(transparent),
+            Renderer::Shm(renderer) => renderer.update_transparency(transparent),
+        }
+    }
+
+    fn update_anti_aliasing(&mut self, anti_aliasing: AntiAliasing) {
+        match self {
+            Renderer::Blade(renderer) => renderer.update_anti_aliasing(anti_aliasing),
+            // The canvas only uses analytic coverage.
+            Renderer::Gles(_) | Renderer::Shm(_) => {}
+        }
+    }
+
+    fn damage(&self, scene: &Scene) -> Damage {
+        match self {
+            Renderer::Blade(renderer) => renderer.damage(scene),
+            Renderer::Gles(renderer) => renderer.damage(scene),
+            Renderer::Shm(renderer) => renderer.damage(scene),
+        }
+    }
+
+    fn draw(&mut self, scene: &Scene) {
+        match self {
+            Renderer::Blade(renderer) => renderer.draw(scene),
+            Renderer::Gles(renderer) => renderer.draw(scene),
+            Renderer::Shm(renderer) => renderer.draw(scene),
+        }
+    }
+
+    fn sprite_atlas(&self) -> Arc<dyn PlatformAtlas> {
+        match self {
+            Renderer::Blade(renderer) => renderer.sprite_atlas().clone(),
+            Renderer::Gles(renderer) => renderer.sprite_atlas().clone(),
+            Renderer::Shm(renderer) => renderer.sprite_atlas().clone(),
+        }
+    }
+
+    fn gpu_specs(&self) -> GpuSpecs {
+        match self {
+            Renderer::Blade(renderer) => renderer.gpu_specs(),
+            Renderer::Gles(renderer) => renderer.gpu_specs(),
+            Renderer::Shm(renderer) => renderer.gpu_specs(),
+        }
+    }
+
+    fn render_stats(&self) -> RenderStats {
+        match self {
+            Renderer::Blade(renderer) => renderer.render_stats(),
+            Renderer::Gles(renderer) => renderer.render_stats(),
+            Renderer::Shm(renderer) => renderer.render_stats(),
+        }
+    }
+
+    fn release_resources(&mut self) {
+        match self {
+            Renderer::Blade(renderer) => renderer.release_resources(),
+            Renderer::Gles(renderer) => renderer.release_resources(),
+            Renderer::Shm(renderer) => renderer.release_resources(),
+        }
+    }
+
+    fn release_transient_resources(&mut self) {
+        match self {
+            Renderer::Blade(renderer) => renderer.release_transient_resources(),
+            // The frame texture is kept for the next partial upload.
+            Renderer::Gles(_) => {}
+            Renderer::Shm(renderer) => renderer.release_transient_resources(),
+        }
+    }
+
+    fn destroy(&mut self) {
+        match self {
+            Renderer::Blade(renderer) => renderer.destroy(),
+            Renderer::Gles(renderer) => renderer.destroy(),
+            Renderer::Shm(renderer) => renderer.destroy(),
+        }
+    }
+}
+
 enum Surface {
     Xdg((XdgSurface, XdgToplevel, Option<ZxdgToplevelDecorationV1>)),
     Layer(ZwlrLayerSurfaceV1),
@@ -196,8 +263,8 @@ enum Surface {
 impl Surface {
     fn xdg(&self) -> Option<&XdgSurface> {
         match self {
-            Surface::Xdg((surface, _, _)) => Some(surface),
-            _ => None,
+            Surface::Xdg((surface, _, _)) | Surface::Popup((_, surface)) => Some(surface),
+            Surface::Layer(_) => None,
         }
     }
 
@@ -222,10 +289,6 @@ impl Surface {
         }
     }
 
-    fn popop(&self) {
-        unimplemented!()
-    }
-
     fn destory(&self) {
         match self {
             Surface::Xdg((surface, toplevel, decoration)) => {
@@ -236,8 +299,9 @@ impl Surface {
                 }
             }
             Surface::Layer(layer_shell) => layer_shell.destroy(),
-            Surface::Popup(_) => {
-                unimplemented!()
+            Surface::Popup((popup, surface)) => {
+                popup.destroy();
+                surface.destroy();
             }
         }
     }
@@ -247,31 +311,61 @@ struct WaylandWindowState {
     acknowledged_first_configure: bool,
     pub wl_surface: wl_surface::WlSurface,
     surface: Surface,
+    /// The layer a layer shell window was opened on.
+    layer: Option<Layer>,
     app_id: Option<String>,
     appearance: WindowAppearance,
     blur: Option<org_kde_kwin_blur::OrgKdeKwinBlur>,
+    app_menu: Option<org_kde_kwin_appmenu::OrgKdeKwinAppmenu>,
+    background_effect: Option<ext_background_effect_surface_v1::ExtBackgroundEffectSurfaceV1>,
     viewport: Option<wp_viewport::WpViewport>,
+    dmabuf_feedback_object: Option<zwp_linux_dmabuf_feedback_v1::ZwpLinuxDmabufFeedbackV1>,
+    dmabuf_feedback_builder: DmabufFeedbackBuilder,
+    /// The buffer parameters the compositor last reported for the surface.
+    dmabuf_feedback: Option<DmabufFeedback>,
     outputs: HashMap<ObjectId, Output>,
     display: Option<(ObjectId, Output)>,
     globals: Globals,
-    renderer: BladeRenderer,
+    renderer: Renderer,
     bounds: Bounds<Pixels>,
     scale: f32,
+    /// The scales reported by the compositor, which the window renders at unless its scale is
+    /// overridden.
+    compositor_scale: CompositorScale,
+    scale_override: Option<f32>,
     input_handler: Option<PlatformInputHandler>,
     decorations: WindowDecorations,
     background_appearance: WindowBackgroundAppearance,
-    fullscreen: bool,
-    maximized: bool,
-    tiling: Tiling,
-    window_bounds: Bounds<Pixels>,
+    toplevel: ToplevelState,
     client: WaylandClientStatePtr,
     handle: AnyWindowHandle,
     active: bool,
-    hovered: bool,
-    in_progress_configure: Option<InProgressConfigure>,
+    /// The number of seats whose pointer is over the window.
+    hovering_seats: usize,
+    /// The seat that delivered the latest input event to the window.
+    input_seat: Option<SeatId>,
+    in_progress_configure: Option<Configure>,
     in_progress_window_controls: Option<WindowControls>,
     window_controls: WindowControls,
+    /// The largest size the compositor recommends, e.g. the work area of the output.
+    recommended_bounds: Option<Size<Pixels>>,
     inset: Option<Pixels>,
+    /// The inset of the input region of layer shell windows drawing a shadow, which leaves
+    /// clicks on the shadow to the surfaces below.
+    input_inset: Option<Pixels>,
+    key_repeat: Option<KeyRepeatSettings>,
+    /// When the pending frame callback was requested.
+    frame_requested_at: Option<Instant>,
+    /// Whether a frame is requested only when the window draws, see
+    /// [`crate::WindowOptions::reactive_rendering`].
+    reactive_rendering: bool,
+    /// Whether a frame of a window using reactive rendering is scheduled outside of a frame
+    /// callback.
+    frame_scheduled: bool,
+    throttled: bool,
+    last_drawn_at: Instant,
+    last_presentation: Option<PresentationTiming>,
+    accessibility: AccessibilityAdapter,
 }
 
 #[derive(Clone)]
@@ -290,19 +384,19 @@ impl WaylandWindowState {
         viewport: Option<wp_viewport::WpViewport>,
         client: WaylandClientStatePtr,
         globals: Globals,
-        gpu_context: &BladeContext,
+        gpu_context: Option<&BladeContext>,
         options: WindowParams,
     ) -> anyhow::Result<Self> {
-        let renderer = {
-            let raw_window = RawWindow {
-                window: wl_surface.id().as_ptr().cast::<c_void>(),
-                display: wl_surface
-                    .backend()
-                    .upgrade()
-                    .unwrap()
-                    .display_ptr()
-                    .cast::<c_void>(),
-            };
+        let raw_window = RawWindow {
+            window: wl_surface.id().as_ptr().cast::<c_void>(),
+            display: wl_surface
+                .backend()
+                .upgrade()
+                .unwrap()
+                .display_ptr()
+                .cast::<c_void>(),
+        };
+        let blade_renderer = gpu_context.map(|gpu_context| {
             let config = BladeSurfaceConfig {
                 size: gpu::Extent {
                     width: options.bounds.size.width.0 as u32,
@@ -311,44 +405,131 @@ impl WaylandWindowState {
                 },
                 transparent: true,
             };
-            BladeRenderer::new(gpu_context, &raw_window, config)?
+            BladeRenderer::new(gpu_context, &raw_window, config)
+        });
+        let renderer = match blade_renderer {
+            Some(Ok(renderer)) => Renderer::Blade(renderer),
+            blade_renderer => {
+                if let Some(Err(err)) = blade_renderer {
+                    log::warn!("Failed to create GPU renderer, falling back to OpenGL ES: {err:?}");
+                }
+                let size = options.bounds.size.map(|size| DevicePixels(size.0 as i32));
+                let gles_window = GlesWindow::Wayland {
+                    display: raw_window.display,
+                    surface: raw_window.window,
+                };
+                match GlesRenderer::new(gles_window, size) {
+                    Ok(renderer) => Renderer::Gles(renderer),
+                    Err(err) => {
+                        log::warn!(
+                            "Failed to create OpenGL ES renderer, falling back to wl_shm: {err:?}"
+                        );
+                        Renderer::Shm(ShmRenderer::new(&globals, wl_surface.clone(), size))
+                    }
+                }
+            }
+        };
+
+        let dmabuf_feedback_object = globals
+            .linux_dmabuf
+            .as_ref()
+            .filter(|linux_dmabuf| {
+                linux_dmabuf.version() >= zwp_linux_dmabuf_v1::REQ_GET_SURFACE_FEEDBACK_SINCE
+            })
+            .map(|linux_dmabuf| {
+                linux_dmabuf.get_surface_feedback(&wl_surface, &globals.qh, wl_surface.id())
+            });
+
+        let layer = match &options.kind {
+            WindowKind::LayerShell(settings) => Some(settings.layer),
+            _ => None,
+        };
+        let (inset, input_inset) = match &options.kind {
+            WindowKind::LayerShell(settings) if settings.shadow.is_some() => {
+                let inset = settings.shadow_inset();
+                (Some(inset), settings.pointer_interactivity.then_some(inset))
+            }
+            _ => (None, None),
         };
+        let accessibility = AccessibilityAdapter::new(
+            &globals.executor,
+            options
+                .titlebar
+                .as_ref()
+                .and_then(|titlebar| titlebar.title.clone()),
+        );
 
         Ok(Self {
             acknowledged_first_configure: false,
             wl_surface,
             surface,
+            layer,
             app_id: None,
             blur: None,
+            app_menu: None,
+            background_effect: None,
             viewport,
+            dmabuf_feedback_object,
+            dmabuf_feedback_builder: DmabufFeedbackBuilder::default(),
+            dmabuf_feedback: None,
             globals,
             outputs: HashMap::default(),
             display: None,
             renderer,
             bounds: options.bounds,
             scale: 1.0,
+            compositor_scale: CompositorScale::default(),
+            scale_override: None,
             input_handler: None,
             decorations: WindowDecorations::Client,
             background_appearance: WindowBackgroundAppearance::Opaque,
-            fullscreen: false,
-            maximized: false,
-            tiling: Tiling::default(),
-            window_bounds: options.bounds,
+            toplevel: ToplevelState::new(options.bounds),
             in_progress_configure: None,
             client,
             appearance,
             handle,
             active: false,
-            hovered: false,
+            hovering_seats: 0,
+            input_seat: None,
             in_progress_window_controls: None,
             window_controls: WindowControls::default(),
-            inset: None,
+            recommended_bounds: None,
+            inset,
+            input_inset,
+            key_repeat: None,
+            frame_requested_at: None,
+            reactive_rendering: false,
+            frame_scheduled: false,
+            throttled: false,
+            last_drawn_at: Instant::now(),
+            last_presentation: None,
+            accessibility,
         })
     }
 
     pub fn is_transparent(&self) -> bool {
-        self.decorations == WindowDecorations::Client
-            || self.background_appearance != WindowBackgroundAppearance::Opaque
+        if self.background_appearance != WindowBackgroundAppearance::Opaque {
+            return true;
+        }
+        // Client side decorations round the corners and draw shadows, except on fullscreen
+        // windows, and on layer surfaces that don't draw a shadow. Keeping those opaque lets the
+        // compositor scan them out directly.
+        if self.surface.layer().is_some() {
+            self.inset.is_some()
+        } else {
+            self.decorations == WindowDecorations::Client && !self.toplevel.fullscreen
+        }
+    }
+
+    /// Sets the scale of the buffers attached to the surface, unless the scale of the window is
+    /// overridden, in which case the viewport scales them.
+    fn set_surface_buffer_scale(&self, scale: i32) {
+        if self.scale_override.is_none() {
+            trace::request(
+                &self.wl_surface,
+                wl_surface::Request::SetBufferScale { scale },
+            );
+        }
     }
 
     pub fn primary_output_scale(&mut self) -> i32 {
@@ -372,7 +553,8 @@ impl WaylandWindowState {
 pub(crate) struct WaylandWindow(pub WaylandWindowStatePtr);
 pub(crate) enum ImeInput {
     InsertText(String),
-    SetMarkedText(String),
+    /// Marks the text, with the range of the cursor or the selected segment within it in UTF-16.
+    SetMarkedText(String, Option<Range<usize>>),
     UnmarkText,
     DeleteText,
 }
@@ -387,9 +569,18 @@ impl Drop for WaylandWindow {
         if let Some(blur) = &state.blur {
             blur.release();
         }
+        if let Some(app_menu) = &state.app_menu {
+            app_menu.release();
+        }
+        if let Some(background_effect) = &state.background_effect {
+            background_effect.destroy();
+        }
         if let Some(viewport) = &state.viewport {
             viewport.destroy();
         }
+        if let Some(dmabuf_feedback) = &state.dmabuf_feedback_object {
+            dmabuf_feedback.destroy();
+        }
         state.wl_surface.destroy();
         state.surface.destory();
 
@@ -415,13 +606,19 @@ impl WaylandWindow {
         self.0.state.borrow_mut()
     }
 
+    /// Creates the window's surface. Popups are attached to `parent`, and grab the pointer and
+    /// keyboard with the seat and serial in `grab`, so the compositor dismisses them when the
+    /// user clicks elsewhere.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         handle: AnyWindowHandle,
         globals: Globals,
-        gpu_context: &BladeContext,
+        gpu_context: Option<&BladeContext>,
         client: WaylandClientStatePtr,
-        params: WindowParams,
+        mut params: WindowParams,
         appearance: WindowAppearance,
+        parent: Option<WaylandWindowStatePtr>,
+        grab: Option<(wl_seat::WlSeat, u32)>,
     ) -> anyhow::Result<(Self, ObjectId)> {
         let wl_surface = globals.compositor.create_surface(&globals.qh, ());
 
@@ -452,7 +649,12 @@ impl WaylandWindow {
                 Surface::Xdg((xdg_surface, toplevel, decoration))
             }
             WindowKind::LayerShell(ref layer_shell_settings) => {
-                let layer_surface = globals.layer_shell.get_layer_surface(
+                // Not implemented by GNOME, among others.
+                let Some(layer_shell) = globals.layer_shell.as_ref() else {
+                    wl_surface.destroy();
+                    return Err(WindowCreationError::MissingProtocol("zwlr_layer_shell_v1").into());
+                };
+                let layer_surface = layer_shell.get_layer_surface(
                     &wl_surface,
                     None,
                     layer_shell_settings.layer.into(),
@@ -463,10 +665,9 @@ impl WaylandWindow {
                 layer_surface.set_anchor(zwlr_layer_surface_v1::Anchor::from_bits_truncate(
                     layer_shell_settings.anchor.bits(),
                 ));
-                layer_surface.set_size(
-                    params.bounds.size.width.0 as u32,
-                    params.bounds.size.height.0 as u32,
-                );
+                // The surface keeps space around the body for its shadow.
+                let surface_size = layer_shell_settings.surface_size(params.bounds.size);
+                layer_surface.set_size(surface_size.width.0 as u32, surface_size.height.0 as u32);
                 layer_surface
                     .set_keyboard_interactivity(layer_shell_settings.keyboard_interactivity.into());
                 if !layer_shell_settings.pointer_interactivity {
@@ -474,7 +675,9 @@ impl WaylandWindow {
                     wl_surface.set_input_region(Some(&region));
                     region.destroy();
                 }
-                if let Some(margin) = layer_shell_settings.margin {
+                if layer_shell_settings.margin.is_some() || layer_shell_settings.shadow.is_some() {
+                    let margin = layer_shell_settings
+                        .surface_margin(layer_shell_settings.margin.unwrap_or_default());
                     layer_surface.set_margin(
                         margin.0 .0 as i32,
                         margin.1 .0 as i32,
@@ -482,17 +685,90 @@ impl WaylandWindow {
                         margin.3 .0 as i32,
                     );
                 }
-                if let Some(exclusive_zone) = layer_shell_settings.exclusive_zone {
+                if let Some(exclusive_zone) = layer_shell_settings.surface_exclusive_zone() {
                     layer_surface.set_exclusive_zone(exclusive_zone.0 as i32);
                 }
 
                 Surface::Layer(layer_surface)
             }
             WindowKind::PopUp => {
-                unimplemented!()
+                let (Some((_, anchor)), Some(parent)) = (params.popup_anchor, parent) else {
+                    // Popups need a parent window, see `App::open_popup_window`.
+                    wl_surface.destroy();
+                    return Err(WindowCreationError::UnsupportedKind(WindowKind::PopUp).into());
+                };
+                let xdg_surface =
+                    globals
+                        .wm_base
+                        .get_xdg_surface(&wl_surface, &globals.qh, wl_surface.id());
+
+                // The anchor rectangle is relative to the parent's window geometry, which
+                // excludes the client side shadows.
+                let parent_geometry = parent.window_geometry();
+                let anchor_origin = anchor.bounds.origin - parent_geometry.origin;
+                let anchor_bounds = Bounds {
+                    origin: anchor_origin,
+                    size: anchor.bounds.size,
+                }
+                .map(|v| v.0 as i32);
+                let positioner = globals.wm_base.create_positioner(&globals.qh, ());
+                positioner.set_size(
+                    (params.bounds.size.width.0 as i32).max(1),
+                    (params.bounds.size.height.0 as i32).max(1),
+                );
+                positioner.set_anchor_rect(
+                    anchor_bounds.origin.x,
+                    anchor_bounds.origin.y,
+                    anchor_bounds.size.width.max(1),
+                    anchor_bounds.size.height.max(1),
+                );
+                positioner.set_anchor(positioner_anchor(anchor.attach));
+                positioner.set_gravity(positioner_gravity(anchor.anchor));
+                positioner.set_offset(anchor.offset.x.0 as i32, anchor.offset.y.0 as i32);
+                positioner.set_constraint_adjustment(
+                    xdg_positioner::ConstraintAdjustment::FlipX
+                        | xdg_positioner::ConstraintAdjustment::FlipY
+                        | xdg_positioner::ConstraintAdjustment::SlideX
+                        | xdg_positioner::ConstraintAdjustment::SlideY,
+                );
+
+                let popup = {
+                    let parent = parent.state.borrow();
+                    match (&parent.surface, parent.surface.xdg()) {
+                        (Surface::Layer(layer_surface), _) => {
+                            let popup = xdg_surface.get_popup(
+                                None,
+                                &positioner,
+                                &globals.qh,
+                                wl_surface.id(),
+                            );
+                            layer_surface.get_popup(&popup);
+                            popup
+                        }
+                        (_, parent_surface) => xdg_surface.get_popup(
+                            parent_surface,
+                            &positioner,
+                            &globals.qh,
+                            wl_surface.id(),
+                        ),
+                    }
+                };
+                positioner.destroy();
+
+                if params.focus {
+                    if let Some((seat, serial)) = grab {
+                        popup.grab(&seat, serial);
+                    }
+                }
+
+                Surface::Popup((popup, xdg_surface))
             }
         };
 
+        if let WindowKind::LayerShell(settings) = &params.kind {
+            params.bounds.size = settings.surface_size(params.bounds.size);
+        }
+
         if let Some(fractional_scale_manager) = globals.fractional_scale_manager.as_ref() {
             fractional_scale_manager.get_fractional_scale(
                 &wl_surface,
@@ -537,20 +813,109 @@ impl WaylandWindowStatePtr {
         self.state.borrow().wl_surface.clone()
     }
 
+    pub fn is_toplevel(&self) -> bool {
+        self.state.borrow().surface.toplevel().is_some()
+    }
+
+    /// The window geometry last set on the xdg surface, relative to the surface. It excludes
+    /// the client side shadows.
+    pub fn window_geometry(&self) -> Bounds<Pixels> {
+        let state = self.state.borrow();
+        inset_by_tiling(
+            state.bounds.map_origin(|_| px(0.0)),
+            state.inset.unwrap_or(px(0.0)),
+            state.toplevel.tiling,
+        )
+    }
+
     pub fn ptr_eq(&self, other: &Self) -> bool {
         Rc::ptr_eq(&self.state, &other.state)
     }
 
     pub fn frame(&self) {
         let mut state = self.state.borrow_mut();
-        state
-            .wl_surface
-            .frame(&state.globals.qh, state.wl_surface.id());
+        let now = Instant::now();
+        state.frame_scheduled = false;
+        // Windows using reactive rendering request the next frame when they draw this one.
+        if state.reactive_rendering {
+            state.frame_requested_at = None;
+        } else {
+            state
+                .wl_surface
+                .frame(&state.globals.qh, state.wl_surface.id());
+            state.frame_requested_at = Some(now);
+        }
+        let was_throttled = std::mem::replace(&mut state.throttled, false);
+        let frame_time = state
+            .last_presentation
+            .map_or(now, |timing| timing.next_presentation(now));
         drop(state);
 
+        if was_throttled {
+            if let Some(fun) = self.callbacks.borrow_mut().throttle_changed.as_mut() {
+                fun(false);
+            }
+        }
+
         let mut cb = self.callbacks.borrow_mut();
         if let Some(fun) = cb.request_frame.as_mut() {
-            fun(Default::default());
+            fun(RequestFrameOptions {
+                frame_time: Some(frame_time),
+                ..Default::default()
+            });
+        }
+    }
+
+    /// Records the presentation of a frame, `timestamp` being the time on the clock of the
+    /// compositor.
+    pub fn presented(&self, timestamp: Duration, refresh: Duration) {
+        let mut state = self.state.borrow_mut();
+        let now = Instant::now();
+        // The feedback arrives a bit after the presentation, consecutive frames are spaced by
+        // the clock of the compositor so the delivery of the events doesn't add jitter.
+        let presented_at = match state.last_presentation {
+            Some(last)
+                if timestamp > last.timestamp
+                    && timestamp - last.timestamp < FRAME_CALLBACK_TIMEOUT =>
+            {
+                (last.presented_at + (timestamp - last.timestamp)).min(now)
+            }
+            _ => now,
+        };
+        state.last_presentation = Some(PresentationTiming {
+            timestamp,
+            presented_at,
+            refresh,
+        });
+    }
+
+    /// Marks the window as throttled when the compositor hasn't delivered the last requested
+    /// frame callback in time, which happens when the surface is occluded or its output is off.
+    /// Rendering resumes with the next frame callback.
+    ///
+    /// Hidden windows release their GPU resources, while windows that are shown but haven't
+    /// drawn anything for a while only release the transient ones. The renderer recreates them
+    /// on the next draw.
+    pub fn check_frame_timeout(&self) {
+        let mut state = self.state.borrow_mut();
+        if state.throttled {
+            return;
+        }
+        if state.last_drawn_at.elapsed() >= GPU_RESOURCES_IDLE_TIMEOUT {
+            state.renderer.release_transient_resources();
+        }
+        let Some(requested_at) = state.frame_requested_at else {
+            return;
+        };
+        if requested_at.elapsed() < FRAME_CALLBACK_TIMEOUT {
+            return;
+        }
+        state.throttled = true;
+        state.renderer.release_resources();
+        drop(state);
+
+        if let Some(fun) = self.callbacks.borrow_mut().throttle_changed.as_mut() {
+            fun(true);
         }
     }
 
@@ -578,48 +943,39 @@ impl WaylandWindowStatePtr {
                 {
                     let mut state = self.state.borrow_mut();
 
-                    if let Some(mut configure) = state.in_progress_configure.take() {
-                        let got_unmaximized = state.maximized && !configure.maximized;
-
-                        state.fullscreen = configure.fullscreen;
-                        state.maximized = configure.maximized;
-                        state.tiling = configure.tiling;
-                        if !configure.fullscreen && !configure.maximized {
-                            configure.size = if got_unmaximized {
-                                Some(state.window_bounds.size)
-                            } else {
-                                compute_outer_size(state.inset, configure.size, state.tiling)
-                            };
-                            if let Some(size) = configure.size {
-                                state.window_bounds = Bounds {
-                                    origin: Point::default(),
-                                    size,
-                                };
-                            }
-                        }
+                    if let Some(configure) = state.in_progress_configure.take() {
+                        let fullscreen_changed = state.toplevel.fullscreen != configure.fullscreen;
+                        let inset = state.inset;
+                        let size = state.toplevel.configure(configure, inset);
                         drop(state);
-                        if let Some(size) = configure.size {
+                        if let Some(size) = size {
                             self.resize(size);
                         }
+                        if fullscreen_changed {
+                            update_window(self.state.borrow_mut());
+                        }
                     }
                 }
                 let mut state = self.state.borrow_mut();
                 let xdg_surface = state.surface.xdg().unwrap();
-                xdg_surface.ack_configure(serial);
+                trace::request(xdg_surface, xdg_surface::Request::AckConfigure { serial });
 
                 let window_geometry = inset_by_tiling(
                     state.bounds.map_origin(|_| px(0.0)),
                     state.inset.unwrap_or(px(0.0)),
-                    state.tiling,
+                    state.toplevel.tiling,
                 )
                 .map(|v| v.0 as i32)
                 .map_size(|v| if v <= 0 { 1 } else { v });
 
-                xdg_surface.set_window_geometry(
-                    window_geometry.origin.x,
-                    window_geometry.origin.y,
-                    window_geometry.size.width,
-                    window_geometry.size.height,
+                trace::request(
+                    xdg_surface,
+                    xdg_surface::Request::SetWindowGeometry {
+                        x: window_geometry.origin.x,
+                        y: window_geometry.origin.y,
+                        width: window_geometry.size.width,
+                        height: window_geometry.size.height,
+                    },
                 );
 
                 let request_frame_callback = !state.acknowledged_first_configure;
@@ -633,6 +989,16 @@ impl WaylandWindowStatePtr {
         }
     }
 
+    pub fn handle_popup_event(&self, event: xdg_popup::Event) {
+        if let xdg_popup::Event::Configure { width, height, .. } = event {
+            let mut state = self.state.borrow_mut();
+            state.in_progress_configure = Some(Configure {
+                size: Some(size(px(width as f32), px(height as f32))),
+                ..Default::default()
+            });
+        }
+    }
+
     pub fn handle_layer_surface(&self, event: zwlr_layer_surface_v1::Event) {
         let mut state = self.state.borrow_mut();
         if state.surface.layer().is_none() {
@@ -646,16 +1012,49 @@ impl WaylandWindowStatePtr {
                 height,
             } => {
                 let layer_surface = state.surface.layer().unwrap();
-                layer_surface.ack_configure(serial);
-                layer_surface.set_size(width, height);
+                trace::request(
+                    layer_surface,
+                    zwlr_layer_surface_v1::Request::AckConfigure { serial },
+                );
+                trace::request(
+                    layer_surface,
+                    zwlr_layer_surface_v1::Request::SetSize { width, height },
+                );
+
+                let size = layer_surface_size(
+                    state.bounds.size,
+                    size(px(width as f32), px(height as f32)),
+                );
+
+                if let Some(inset) = state.input_inset {
+                    let inset = inset.0 as i32;
+                    let region = state
+                        .globals
+                        .compositor
+                        .create_region(&state.globals.qh, ());
+                    region.add(
+                        inset,
+                        inset,
+                        size.width.0 as i32 - inset * 2,
+                        size.height.0 as i32 - inset * 2,
+                    );
+                    state.wl_surface.set_input_region(Some(&region));
+                    region.destroy();
+                }
 
                 let request_frame_callback = !state.acknowledged_first_configure;
+                state.acknowledged_first_configure = true;
+                drop(state);
+                self.resize(size);
                 if request_frame_callback {
-                    state.acknowledged_first_configure = true;
-                    drop(state);
                     self.frame();
                 }
             }
+            // The surface is gone, like when its output was unplugged, so there's no asking.
+            zwlr_layer_surface_v1::Event::Closed => {
+                drop(state);
+                self.close();
+            }
             _ => {}
         }
     }
@@ -693,12 +1092,54 @@ impl WaylandWindowStatePtr {
     pub fn handle_fractional_scale_event(&self, event: wp_fractional_scale_v1::Event) {
         match event {
             wp_fractional_scale_v1::Event::PreferredScale { scale } => {
-                self.rescale(scale as f32 / 120.0);
+                self.state.borrow_mut().compositor_scale.fractional = Some(scale as f32 / 120.0);
+                self.update_scale();
             }
             _ => {}
         }
     }
 
+    /// Points the compositor at the menus exported over dbusmenu by the given service, for it to
+    /// show in a global menu while the window is focused.
+    pub fn set_app_menu_address(&self, service: &str, path: &str) {
+        let mut state = self.state.borrow_mut();
+        if state.surface.toplevel().is_none() {
+            return;
+        }
+        if state.app_menu.is_none() {
+            let Some(manager) = state.globals.appmenu_manager.as_ref() else {
+                return;
+            };
+            state.app_menu = Some(manager.create(&state.wl_surface, &state.globals.qh, ()));
+        }
+        if let Some(app_menu) = &state.app_menu {
+            app_menu.set_address(service.to_string(), path.to_string());
+        }
+    }
+
+    pub fn handle_dmabuf_feedback_event(&self, event: zwp_linux_dmabuf_feedback_v1::Event) {
+        let mut state = self.state.borrow_mut();
+        let Some(feedback) = state.dmabuf_feedback_builder.handle_event(event) else {
+            return;
+        };
+        if state.dmabuf_feedback.as_ref() == Some(&feedback) {
+            return;
+        }
+        log::debug!(
+            "dmabuf feedback: main device {}, {} tranches, scanout offered: {}",
+            feedback.main_device,
+            feedback.tranches.len(),
+            feedback.offers_scanout()
+        );
+        state.dmabuf_feedback = Some(feedback);
+        drop(state);
+
+        let mut callbacks = self.callbacks.borrow_mut();
+        if let Some(ref mut fun) = callbacks.dmabuf_feedback_changed {
+            (fun)()
+        }
+    }
+
     pub fn handle_toplevel_event(&self, event: xdg_toplevel::Event) -> bool {
         match event {
             xdg_toplevel::Event::Configure {
@@ -706,12 +1147,7 @@ impl WaylandWindowStatePtr {
                 height,
                 states,
             } => {
-                let mut size = if width == 0 || height == 0 {
-                    None
-                } else {
-                    Some(size(px(width as f32), px(height as f32)))
-                };
-
+                let size = size(px(width as f32), px(height as f32));
                 let states = extract_states::<xdg_toplevel::State>(&states);
 
                 let mut tiling = Tiling::default();
@@ -744,13 +1180,9 @@ impl WaylandWindowStatePtr {
                     }
                 }
 
-                if fullscreen || maximized {
-                    tiling = Tiling::tiled();
-                }
-
                 let mut state = self.state.borrow_mut();
-                state.in_progress_configure = Some(InProgressConfigure {
-                    size,
+                state.in_progress_configure = Some(Configure {
+                    size: Some(size),
                     fullscreen,
                     maximized,
                     tiling,
@@ -799,6 +1231,15 @@ impl WaylandWindowStatePtr {
                 state.in_progress_window_controls = Some(window_controls);
                 false
             }
+            xdg_toplevel::Event::ConfigureBounds { width, height } => {
+                let mut state = self.state.borrow_mut();
+                state.recommended_bounds = if width == 0 || height == 0 {
+                    None
+                } else {
+                    Some(size(px(width as f32), px(height as f32)))
+                };
+                false
+            }
             _ => false,
         }
     }
@@ -822,32 +1263,45 @@ impl WaylandWindowStatePtr {
                 state.outputs.insert(id, output.clone());
 
                 let scale = state.primary_output_scale();
+                state.compositor_scale.output = Some(scale);
 
                 // We use `PreferredBufferScale` instead to set the scale if it's available
                 if state.wl_surface.version() < wl_surface::EVT_PREFERRED_BUFFER_SCALE_SINCE {
-                    state.wl_surface.set_buffer_scale(scale);
+                    state.set_surface_buffer_scale(scale);
                     drop(state);
-                    self.rescale(scale as f32);
+                    self.update_scale();
                 }
             }
             wl_surface::Event::Leave { output } => {
                 state.outputs.remove(&output.id());
 
                 let scale = state.primary_output_scale();
+                state.compositor_scale.output = Some(scale);
 
                 // We use `PreferredBufferScale` instead to set the scale if it's available
                 if state.wl_surface.version() < wl_surface::EVT_PREFERRED_BUFFER_SCALE_SINCE {
-                    state.wl_surface.set_buffer_scale(scale);
+                    state.set_surface_buffer_scale(scale);
                     drop(state);
-                    self.rescale(scale as f32);
+                    self.update_scale();
                 }
             }
             wl_surface::Event::PreferredBufferScale { factor } => {
+                let buffer_scale = state.compositor_scale.buffer_scale();
+                state.compositor_scale.preferred_buffer = Some(factor);
+                let buffer_scale_changed = state.compositor_scale.buffer_scale() != buffer_scale;
                 // We use `WpFractionalScale` instead to set the scale if it's available
                 if state.globals.fractional_scale_manager.is_none() {
-                    state.wl_surface.set_buffer_scale(factor);
+                    state.set_surface_buffer_scale(factor);
+                    drop(state);
+                    self.update_scale();
+                } else if buffer_scale_changed {
+                    // The scale factor may stay the same, but the window still needs to learn
+                    // about the new buffer scale.
+                    let (size, scale) = (state.bounds.size, state.scale);
                     drop(state);
-                    self.rescale(factor as f32);
+                    if let Some(ref mut fun) = self.callbacks.borrow_mut().resize {
+                        fun(size, scale);
+                    }
                 }
             }
             _ => {}
@@ -862,8 +1316,8 @@ impl WaylandWindowStatePtr {
                 ImeInput::InsertText(text) => {
                     input_handler.replace_text_in_range(None, &text);
                 }
-                ImeInput::SetMarkedText(text) => {
-                    input_handler.replace_and_mark_text_in_range(None, &text, None);
+                ImeInput::SetMarkedText(text, selected_range) => {
+                    input_handler.replace_and_mark_text_in_range(None, &text, selected_range);
                 }
                 ImeInput::UnmarkText => {
                     input_handler.unmark_text();
@@ -892,45 +1346,79 @@ impl WaylandWindowStatePtr {
             }
             self.state.borrow_mut().input_handler = Some(input_handler);
         }
-        bounds
+        bounds.map(|bounds| self.ime_cursor_area(bounds))
+    }
+
+    /// Returns the rectangle of the caret for text-input, which the compositor places the
+    /// candidate window next to. The rectangle is surface-local, but the surface of layer shell
+    /// and client-decorated windows extends past their body by the shadow inset, so the caret is
+    /// kept within the body. Otherwise the candidate window of a caret at the edge of a launcher
+    /// anchored with margins would open over the invisible shadow.
+    pub fn ime_cursor_area(&self, area: Bounds<Pixels>) -> Bounds<Pixels> {
+        let state = self.state.borrow();
+        // Layer shell windows aren't tiled, so their body is inset on all sides.
+        let body = inset_by_tiling(
+            Bounds::new(Point::default(), state.bounds.size),
+            state.inset.unwrap_or_default(),
+            state.toplevel.tiling,
+        );
+        let origin = area.origin.clamp(&body.origin, &body.bottom_right());
+        Bounds::new(
+            origin,
+            size(
+                area.size.width.min(body.right() - origin.x),
+                area.size.height.min(body.bottom() - origin.y),
+            ),
+        )
     }
 
     pub fn set_size_and_scale(&self, size: Option<Size<Pixels>>, scale: Option<f32>) {
-        let (size, scale) = {
-            let mut state = self.state.borrow_mut();
-            if size.map_or(true, |size| size == state.bounds.size)
-                && scale.map_or(true, |scale| scale == state.scale)
-            {
-                return;
-            }
-            if let Some(size) = size {
-                state.bounds.size = size;
-            }
-            if let Some(scale) = scale {
-                state.scale = scale;
-            }
-            let device_bounds = state.bounds.to_device_pixels(state.scale);
-            state.renderer.update_drawable_size(device_bounds.size);
-            (state.bounds.size, state.scale)
+        let Some((size, scale)) = self.update_size_and_scale(size, scale) else {
+            return;
         };
-
         if let Some(ref mut fun) = self.callbacks.borrow_mut().resize {
             fun(size, scale);
         }
+    }
 
+    /// Applies a new size or scale to the surface without notifying the window, returning the
+    /// resulting size and scale if either changed.
+    fn update_size_and_scale(
+        &self,
+        size: Option<Size<Pixels>>,
+        scale: Option<f32>,
+    ) -> Option<(Size<Pixels>, f32)> {
+        let mut state = self.state.borrow_mut();
+        if size.map_or(true, |size| size == state.bounds.size)
+            && scale.map_or(true, |scale| scale == state.scale)
         {
-            let state = self.state.borrow();
-            if let Some(viewport) = &state.viewport {
-                viewport.set_destination(size.width.0 as i32, size.height.0 as i32);
-            }
+            return None;
+        }
+        if let Some(size) = size {
+            state.bounds.size = size;
+        }
+        if let Some(scale) = scale {
+            state.scale = scale;
+        }
+        let device_bounds = state.bounds.to_device_pixels(state.scale);
+        state.renderer.update_drawable_size(device_bounds.size);
+        let size = state.bounds.size;
+        if let Some(viewport) = &state.viewport {
+            viewport.set_destination(size.width.0 as i32, size.height.0 as i32);
         }
+        Some((size, state.scale))
     }
 
     pub fn resize(&self, size: Size<Pixels>) {
         self.set_size_and_scale(Some(size), None);
     }
 
-    pub fn rescale(&self, scale: f32) {
+    /// Picks the scale of the window again after the compositor reported a new one.
+    fn update_scale(&self) {
+        let scale = {
+            let state = self.state.borrow();
+            state.compositor_scale.resolve(state.scale_override)
+        };
         self.set_size_and_scale(None, Some(scale));
     }
 
@@ -941,7 +1429,8 @@ impl WaylandWindowStatePtr {
         }
     }
 
-    pub fn handle_input(&self, input: PlatformInput) {
+    pub fn handle_input(&self, seat_id: &ObjectId, input: PlatformInput) {
+        self.state.borrow_mut().input_seat = Some(SeatId(seat_id.protocol_id()));
         if let Some(ref mut fun) = self.callbacks.borrow_mut().input {
             if !fun(input.clone()).propagate {
                 return;
@@ -960,21 +1449,40 @@ impl WaylandWindowStatePtr {
     }
 
     pub fn set_focused(&self, focus: bool) {
-        self.state.borrow_mut().active = focus;
+        let mut state = self.state.borrow_mut();
+        state.active = focus;
+        state.accessibility.set_window_focused(focus);
+        drop(state);
         if let Some(ref mut fun) = self.callbacks.borrow_mut().active_status_change {
             fun(focus);
         }
     }
 
-    pub fn set_hovered(&self, focus: bool) {
-        if let Some(ref mut fun) = self.callbacks.borrow_mut().hover_status_change {
-            fun(focus);
+    /// Records the pointer of a seat entering or leaving the window, which is hovered as long
+    /// as any pointer is over it.
+    pub fn set_hovered(&self, entered: bool) {
+        let mut state = self.state.borrow_mut();
+        let was_hovered = state.hovering_seats > 0;
+        if entered {
+            state.hovering_seats += 1;
+        } else {
+            state.hovering_seats = state.hovering_seats.saturating_sub(1);
+        }
+        let hovered = state.hovering_seats > 0;
+        drop(state);
+        if hovered != was_hovered {
+            if let Some(ref mut fun) = self.callbacks.borrow_mut().hover_status_change {
+                fun(hovered);
+            }
         }
     }
 
     pub fn set_appearance(&mut self, appearance: WindowAppearance) {
         self.state.borrow_mut().appearance = appearance;
+        self.appearance_changed();
+    }
 
+    pub fn appearance_changed(&self) {
         let mut callbacks = self.callbacks.borrow_mut();
         if let Some(ref mut fun) = callbacks.appearance_changed {
             (fun)()
@@ -984,6 +1492,10 @@ impl WaylandWindowStatePtr {
     pub fn primary_output_scale(&self) -> i32 {
         self.state.borrow_mut().primary_output_scale()
     }
+
+    pub fn key_repeat_override(&self) -> Option<KeyRepeatSettings> {
+        self.state.borrow().key_repeat
+    }
 }
 
 fn extract_states<'a, S: TryFrom<u32> + 'a>(states: &'a [u8]) -> impl Iterator<Item = S> + 'a
@@ -1014,15 +1526,15 @@ impl PlatformWindow for WaylandWindow {
     }
 
     fn is_maximized(&self) -> bool {
-        self.borrow().maximized
+        self.borrow().toplevel.maximized
     }
 
     fn window_bounds(&self) -> WindowBounds {
         let state = self.borrow();
-        if state.fullscreen {
-            WindowBounds::Fullscreen(state.window_bounds)
-        } else if state.maximized {
-            WindowBounds::Maximized(state.window_bounds)
+        if state.toplevel.fullscreen {
+            WindowBounds::Fullscreen(state.toplevel.windowed_bounds)
+        } else if state.toplevel.maximized {
+            WindowBounds::Maximized(state.toplevel.windowed_bounds)
         } else {
             drop(state);
             WindowBounds::Windowed(self.bounds())
@@ -1031,10 +1543,10 @@ impl PlatformWindow for WaylandWindow {
 
     fn inner_window_bounds(&self) -> WindowBounds {
         let state = self.borrow();
-        if state.fullscreen {
-            WindowBounds::Fullscreen(state.window_bounds)
-        } else if state.maximized {
-            WindowBounds::Maximized(state.window_bounds)
+        if state.toplevel.fullscreen {
+            WindowBounds::Fullscreen(state.toplevel.windowed_bounds)
+        } else if state.toplevel.maximized {
+            WindowBounds::Maximized(state.toplevel.windowed_bounds)
         } else {
             let inset = state.inset.unwrap_or(px(0.));
             drop(state);
@@ -1050,6 +1562,10 @@ impl PlatformWindow for WaylandWindow {
         self.borrow().scale
     }
 
+    fn buffer_scale(&self) -> u32 {
+        self.borrow().compositor_scale.buffer_scale()
+    }
+
     fn appearance(&self) -> WindowAppearance {
         self.borrow().appearance
     }
@@ -1060,22 +1576,75 @@ impl PlatformWindow for WaylandWindow {
             Rc::new(WaylandDisplay {
                 id: id.clone(),
                 name: display.name.clone(),
-                bounds: display.bounds.to_pixels(state.scale),
+                bounds: display.logical_bounds(),
+                mode: display.mode,
             }) as Rc<dyn PlatformDisplay>
         })
     }
 
     fn mouse_position(&self) -> Point<Pixels> {
-        self.borrow()
+        let state = self.borrow();
+        state
             .client
-            .get_client()
-            .borrow()
-            .mouse_location
+            .mouse_location(&state.wl_surface.id())
             .unwrap_or_default()
     }
 
     fn modifiers(&self) -> Modifiers {
-        self.borrow().client.get_client().borrow().modifiers
+        self.borrow().client.modifiers()
+    }
+
+    fn input_seat(&self) -> Option<SeatId> {
+        self.borrow().input_seat
+    }
+
+    fn latest_input_serial(&self, kinds: &[InputSerialKind]) -> Option<InputSerial> {
+        self.borrow()
+            .client
+            .latest_input_serial(kinds)
+            .map(|(_, serial)| serial)
+    }
+
+    fn key_repeat(&self) -> Option<KeyRepeatSettings> {
+        let state = self.borrow();
+        state.key_repeat.or_else(|| state.client.key_repeat())
+    }
+
+    fn set_key_repeat(&self, settings: Option<KeyRepeatSettings>) {
+        self.borrow_mut().key_repeat = settings;
+    }
+
+    fn set_scale_override(&self, scale: Option<f32>) {
+        let mut state = self.borrow_mut();
+        if scale.is_some() && state.viewport.is_none() {
+            log::warn!("the compositor doesn't support wp_viewporter, ignoring the scale override");
+            return;
+        }
+        state.scale_override = scale;
+        // The viewport scales the buffers to the window size while the scale is overridden,
+        // otherwise they go back to the buffer scale the compositor expects.
+        let buffer_scale = if scale.is_none()
+            && (state.wl_surface.version() < wl_surface::EVT_PREFERRED_BUFFER_SCALE_SINCE
+                || state.globals.fractional_scale_manager.is_none())
+        {
+            state.compositor_scale.buffer_scale() as i32
+        } else {
+            1
+        };
+        trace::request(
+            &state.wl_surface,
+            wl_surface::Request::SetBufferScale {
+                scale: buffer_scale,
+            },
+        );
+        let scale = state.compositor_scale.resolve(scale);
+        drop(state);
+        self.0.update_size_and_scale(None, Some(scale));
+    }
+
+    fn pending_compose(&self) -> Option<String> {
+        let state = self.borrow();
+        state.client.pending_compose(&state.wl_surface.id())
     }
 
     fn set_input_handler(&mut self, input_handler: PlatformInputHandler) {
@@ -1105,9 +1674,10 @@ impl PlatformWindow for WaylandWindow {
             state.client.set_pending_activation(state.wl_surface.id());
             let token = activation.get_activation_token(&state.globals.qh, ());
             // The serial isn't exactly important here, since the activation is probably going to be rejected anyway.
-            let serial = state.client.get_serial(SerialKind::MousePress);
             token.set_app_id(app_id);
-            token.set_serial(serial, &state.globals.seat);
+            if let Some((seat, serial)) = state.client.latest_input_serial(InputSerialKind::FOCUS) {
+                token.set_serial(serial.serial, &seat);
+            }
             token.set_surface(&state.wl_surface);
             token.commit();
         }
@@ -1118,7 +1688,19 @@ impl PlatformWindow for WaylandWindow {
     }
 
     fn is_hovered(&self) -> bool {
-        self.borrow().hovered
+        self.borrow().hovering_seats > 0
+    }
+
+    fn is_throttled(&self) -> bool {
+        self.borrow().throttled
+    }
+
+    fn supports_popups(&self) -> bool {
+        true
+    }
+
+    fn is_background_blur_supported(&self) -> bool {
+        self.borrow().client.background_blur_supported()
     }
 
     fn set_title(&mut self, title: &str) {
@@ -1126,6 +1708,7 @@ impl PlatformWindow for WaylandWindow {
             Some(toplevel) => toplevel.set_title(title.to_string()),
             None => log::error!("not a xdg wl_surface"),
         }
+        self.borrow_mut().accessibility.set_title(title);
     }
 
     fn set_app_id(&mut self, app_id: &str) {
@@ -1156,7 +1739,7 @@ impl PlatformWindow for WaylandWindow {
         let state = self.borrow();
         match state.surface.toplevel() {
             Some(toplevel) => {
-                if !state.maximized {
+                if !state.toplevel.maximized {
                     toplevel.set_maximized();
                 } else {
                     toplevel.unset_maximized();
@@ -1170,7 +1753,7 @@ impl PlatformWindow for WaylandWindow {
         let mut state = self.borrow_mut();
         match state.surface.toplevel() {
             Some(toplevel) => {
-                if !state.fullscreen {
+                if !state.toplevel.fullscreen {
                     toplevel.set_fullscreen(None);
                 } else {
                     toplevel.unset_fullscreen();
@@ -1181,7 +1764,7 @@ impl PlatformWindow for WaylandWindow {
     }
 
     fn is_fullscreen(&self) -> bool {
-        self.borrow().fullscreen
+        self.borrow().toplevel.fullscreen
     }
 
     fn on_request_frame(&self, callback: Box<dyn FnMut(RequestFrameOptions)>) {
@@ -1220,9 +1803,102 @@ impl PlatformWindow for WaylandWindow {
         self.0.callbacks.borrow_mut().appearance_changed = Some(callback);
     }
 
+    fn set_reactive_rendering(&self, reactive: bool) {
+        self.borrow_mut().reactive_rendering = reactive;
+    }
+
+    fn set_anti_aliasing(&self, anti_aliasing: AntiAliasing) {
+        self.borrow_mut()
+            .renderer
+            .update_anti_aliasing(anti_aliasing);
+    }
+
+    fn request_frame(&self) {
+        let mut state = self.borrow_mut();
+        // A pending frame callback or the first configure will draw the window.
+        if !state.reactive_rendering
+            || !state.acknowledged_first_configure
+            || state.frame_requested_at.is_some()
+            || state.frame_scheduled
+        {
+            return;
+        }
+        state.frame_scheduled = true;
+        let state_ptr = self.0.clone();
+        state
+            .globals
+            .executor
+            .spawn(async move { state_ptr.frame() })
+            .detach();
+    }
+
+    fn on_throttle_changed(&self, callback: Box<dyn FnMut(bool)>) {
+        self.0.callbacks.borrow_mut().throttle_changed = Some(callback);
+    }
+
+    fn is_accessibility_active(&self) -> bool {
+        self.borrow().accessibility.is_active()
+    }
+
+    fn update_accessibility_tree(&self, tree: AccessibilityTree) {
+        let mut state = self.borrow_mut();
+        let scale = state.scale;
+        state.accessibility.update(tree, scale);
+    }
+
+    fn on_accessibility_request(&self, callback: Box<dyn FnMut(AccessibilityRequest)>) {
+        self.borrow().accessibility.on_request(callback);
+    }
+
+    fn create_foreign_surface(&self) -> Option<ForeignSurface> {
+        let state = self.borrow();
+        ForeignSurface::new(&state.globals, &state.wl_surface)
+    }
+
+    fn dmabuf_feedback(&self) -> Option<DmabufFeedback> {
+        self.borrow().dmabuf_feedback.clone()
+    }
+
+    fn on_dmabuf_feedback_changed(&self, callback: Box<dyn FnMut()>) {
+        self.0.callbacks.borrow_mut().dmabuf_feedback_changed = Some(callback);
+    }
+
     fn draw(&self, scene: &Scene) {
         let mut state = self.borrow_mut();
+        let damage = state.renderer.damage(scene);
+        if damage != Damage::None {
+            state.last_drawn_at = Instant::now();
+            if state.reactive_rendering && state.frame_requested_at.is_none() {
+                state
+                    .wl_surface
+                    .frame(&state.globals.qh, state.wl_surface.id());
+                state.frame_requested_at = Some(state.last_drawn_at);
+            }
+            if let Some(presentation) = &state.globals.presentation {
+                presentation.feedback(&state.wl_surface, &state.globals.qh, state.wl_surface.id());
+            }
+        }
+        if let Damage::Partial(rects) = &damage {
+            // Let the compositor re-composite only the changed areas. Vulkan WSI implementations
+            // without incremental present support will still damage the whole buffer.
+            if state.wl_surface.version() >= wl_surface::REQ_DAMAGE_BUFFER_SINCE {
+                for bounds in rects {
+                    // Cover the pixels the edges of the area fall into.
+                    let origin = bounds.origin.map(|pixels| pixels.0.floor() as i32);
+                    let bottom_right = bounds.bottom_right().map(|pixels| pixels.0.ceil() as i32);
+                    state.wl_surface.damage_buffer(
+                        origin.x,
+                        origin.y,
+                        bottom_right.x - origin.x,
+                        bottom_right.y - origin.y,
+                    );
+                }
+            }
+        }
         state.renderer.draw(scene);
+        for paint in &scene.surfaces {
+            paint.foreign_surface.update(paint, state.scale);
+        }
     }
 
     fn completed_frame(&self) {
@@ -1232,17 +1908,23 @@ impl PlatformWindow for WaylandWindow {
 
     fn sprite_atlas(&self) -> Arc<dyn PlatformAtlas> {
         let state = self.borrow();
-        state.renderer.sprite_atlas().clone()
+        state.renderer.sprite_atlas()
+    }
+
+    fn render_stats(&self) -> RenderStats {
+        self.borrow().renderer.render_stats()
     }
 
     fn show_window_menu(&self, position: Point<Pixels>) {
         let state = self.borrow();
-        let serial = state.client.get_serial(SerialKind::MousePress);
+        let Some((seat, serial)) = state.client.latest_input_serial(InputSerialKind::GRAB) else {
+            return;
+        };
         match state.surface.toplevel() {
             Some(toplevel) => {
                 toplevel.show_window_menu(
-                    &state.globals.seat,
-                    serial,
+                    &seat,
+                    serial.serial,
                     position.x.0 as i32,
                     position.y.0 as i32,
                 );
@@ -1253,25 +1935,34 @@ impl PlatformWindow for WaylandWindow {
 
     fn start_window_move(&self) {
         let state = self.borrow();
-        let serial = state.client.get_serial(SerialKind::MousePress);
+        let Some((seat, serial)) = state.client.latest_input_serial(InputSerialKind::GRAB) else {
+            return;
+        };
 
         match state.surface.toplevel() {
             Some(toplevel) => {
-                toplevel._move(&state.globals.seat, serial);
+                toplevel._move(&seat, serial.serial);
             }
             None => log::error!("not a xdg wl_surface"),
         }
     }
 
+    fn start_toplevel_drag(&self, offset: Point<Pixels>) -> Option<Receiver<()>> {
+        let state = self.borrow();
+        let toplevel = state.surface.toplevel()?.clone();
+        let client = state.client.clone();
+        drop(state);
+        client.start_toplevel_drag(&toplevel, offset)
+    }
+
     fn start_window_resize(&self, edge: crate::ResizeEdge) {
         let state = self.borrow();
+        let Some((seat, serial)) = state.client.latest_input_serial(InputSerialKind::GRAB) else {
+            return;
+        };
         match state.surface.toplevel() {
             Some(toplevel) => {
-                toplevel.resize(
-                    &state.globals.seat,
-                    state.client.get_serial(SerialKind::MousePress),
-                    edge.to_xdg(),
-                );
+                toplevel.resize(&seat, serial.serial, edge.to_xdg());
             }
             None => log::error!("not a xdg wl_surface"),
         }
@@ -1282,7 +1973,7 @@ impl PlatformWindow for WaylandWindow {
         match state.decorations {
             WindowDecorations::Server => Decorations::Server,
             WindowDecorations::Client => Decorations::Client {
-                tiling: state.tiling,
+                tiling: state.toplevel.tiling,
             },
         }
     }
@@ -1303,6 +1994,12 @@ impl PlatformWindow for WaylandWindow {
         self.borrow().window_controls
     }
 
+    fn recommended_bounds(&self) -> Option<Size<Pixels>> {
+        let state = self.borrow();
+        // The bounds are of the window geometry, which the invisible decorations surround.
+        compute_outer_size(state.inset, state.recommended_bounds, state.toplevel.tiling)
+    }
+
     fn set_client_inset(&self, inset: Pixels) {
         let mut state = self.borrow_mut();
         if Some(inset) != state.inset {
@@ -1311,9 +2008,59 @@ impl PlatformWindow for WaylandWindow {
         }
     }
 
+    fn set_layer_shell_margin(&self, margin: (Pixels, Pixels, Pixels, Pixels)) {
+        let state = self.borrow();
+        match state.surface.layer() {
+            Some(layer_surface) => {
+                // Keep the body at the margin, with the shadow past it.
+                let margin = inset_margin(margin, state.inset.unwrap_or_default());
+                layer_surface.set_margin(
+                    margin.0 .0 as i32,
+                    margin.1 .0 as i32,
+                    margin.2 .0 as i32,
+                    margin.3 .0 as i32,
+                );
+                state.wl_surface.commit();
+            }
+            None => log::error!("not a layer shell surface"),
+        }
+    }
+
+    fn set_layer_shell_exclusive_zone(&self, exclusive_zone: Pixels) {
+        let state = self.borrow();
+        match state.surface.layer() {
+            Some(layer_surface) => {
+                let exclusive_zone =
+                    inset_exclusive_zone(exclusive_zone, state.inset.unwrap_or_default());
+                layer_surface.set_exclusive_zone(exclusive_zone.0 as i32);
+                state.wl_surface.commit();
+            }
+            None => log::error!("not a layer shell surface"),
+        }
+    }
+
+    fn set_always_on_top(&self, always_on_top: bool) {
+        let state = self.borrow();
+        let (Some(layer_surface), Some(layer)) = (state.surface.layer(), state.layer) else {
+            log::info!("only layer shell windows can be kept on top on Wayland");
+            return;
+        };
+        // Changing the layer of an existing surface was added in version 2.
+        if layer_surface.version() < 2 {
+            log::info!("the compositor can't move layer shell surfaces to another layer");
+            return;
+        }
+        let layer = if always_on_top { Layer::Overlay } else { layer };
+        layer_surface.set_layer(layer.into());
+        state.wl_surface.commit();
+    }
+
     fn update_ime_position(&self, bounds: Bounds<ScaledPixels>) {
         let state = self.borrow();
-        state.client.update_ime_position(bounds);
+        let client = state.client.clone();
+        let bounds = bounds.map(|pixels| px(pixels.0 / state.scale));
+        drop(state);
+        client.update_ime_position(self.0.ime_cursor_area(bounds));
     }
 
     fn gpu_specs(&self) -> Option<GpuSpecs> {
@@ -1325,8 +2072,8 @@ fn update_window(mut state: RefMut<WaylandWindowState>) {
     let opaque = !state.is_transparent();
 
     state.renderer.update_transparency(!opaque);
-    let mut opaque_area = state.window_bounds.map(|v| v.0 as i32);
-    if let Some(inset) = state.inset {
+    let mut opaque_area = state.toplevel.windowed_bounds.map(|v| v.0 as i32);
+    if let Some(inset) = state.inset.filter(|_| !state.toplevel.fullscreen) {
         opaque_area.inset(inset.0 as i32);
     }
 
@@ -1343,9 +2090,7 @@ fn update_window(mut state: RefMut<WaylandWindowState>) {
 
     // Note that rounded corners make this rectangle API hard to work with.
     // As this is common when using CSD, let's just disable this API.
-    if state.background_appearance == WindowBackgroundAppearance::Opaque
-        && state.decorations == WindowDecorations::Server
-    {
+    if opaque {
         // Promise the compositor that this region of the window surface
         // contains no transparent pixels. This allows the compositor to skip
         // updating whatever is behind the surface for better performance.
@@ -1368,6 +2113,22 @@ fn update_window(mut state: RefMut<WaylandWindowState>) {
                 b.release()
             }
         }
+    } else if let Some(manager) = state.globals.background_effect_manager.clone() {
+        if state.background_appearance == WindowBackgroundAppearance::Blurred {
+            if state.background_effect.is_none() {
+                let background_effect =
+                    manager.get_background_effect(&state.wl_surface, &state.globals.qh, ());
+                state.background_effect = Some(background_effect);
+            }
+            // Blur behind the window body, not behind the client side shadows.
+            state
+                .background_effect
+                .as_ref()
+                .unwrap()
+                .set_blur_region(Some(&region));
+        } else if let Some(background_effect) = &state.background_effect {
+            background_effect.set_blur_region(None);
+        }
     }
 
     region.destroy();
@@ -1397,32 +2158,24 @@ impl ResizeEdge {
     }
 }
 
-/// The configuration event is in terms of the window geometry, which we are constantly
-/// updating to account for the client decorations. But that's not the area we want to render
-/// to, due to our intrusize CSD. So, here we calculate the 'actual' size, by adding back in the insets
-fn compute_outer_size(
-    inset: Option<Pixels>,
-    new_size: Option<Size<Pixels>>,
-    tiling: Tiling,
-) -> Option<Size<Pixels>> {
-    let Some(inset) = inset else { return new_size };
-
-    new_size.map(|mut new_size| {
-        if !tiling.top {
-            new_size.height += inset;
-        }
-        if !tiling.bottom {
-            new_size.height += inset;
-        }
-        if !tiling.left {
-            new_size.width += inset;
-        }
-        if !tiling.right {
-            new_size.width += inset;
-        }
+/// The edge or corner of the anchor rectangle a popup is attached to.
+fn positioner_anchor(corner: Corner) -> xdg_positioner::Anchor {
+    match corner {
+        Corner::TopLeft => xdg_positioner::Anchor::TopLeft,
+        Corner::TopRight => xdg_positioner::Anchor::TopRight,
+        Corner::BottomLeft => xdg_positioner::Anchor::BottomLeft,
+        Corner::BottomRight => xdg_positioner::Anchor::BottomRight,
+    }
+}
 
-        new_size
-    })
+/// The direction a popup extends in from the anchor point, away from its own anchored corner.
+fn positioner_gravity(corner: Corner) -> xdg_positioner::Gravity {
+    match corner {
+        Corner::TopLeft => xdg_positioner::Gravity::BottomRight,
+        Corner::TopRight => xdg_positioner::Gravity::BottomLeft,
+        Corner::BottomLeft => xdg_positioner::Gravity::TopRight,
+        Corner::BottomRight => xdg_positioner::Gravity::TopLeft,
+    }
 }
 
 fn inset_by_tiling(mut bounds: Bounds<Pixels>, inset: Pixels, tiling: Tiling) -> Bounds<Pixels> {
@@ -1,6 +1,7 @@
 mod client;
 mod display;
 mod event;
+mod image_renderer;
 mod window;
 mod xim_handler;
 
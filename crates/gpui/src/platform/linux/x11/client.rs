@@ -1,8 +1,10 @@
 use core::str;
 use std::{
-    cell::RefCell,
-    collections::{BTreeMap, HashSet},
-    ops::Deref,
+    cell::{RefCell, RefMut},
+    collections::BTreeMap,
+    future::Future,
+    ops::{Deref, Range},
+    os::fd::OwnedFd,
     path::PathBuf,
     rc::{Rc, Weak},
     time::{Duration, Instant},
@@ -47,21 +49,21 @@ use super::{X11Display, X11WindowStatePtr, XcbAtoms};
 use super::{XimCallbackEvent, XimHandler};
 
 use crate::platform::{
-    blade::BladeContext,
     linux::{
-        get_xkb_compose_state, is_within_click_distance, open_uri_internal,
+        app_menu::AppMenuExporter,
+        get_xkb_compose_state, handle_app_menu_event, is_within_click_distance, open_uri_internal,
         platform::{DOUBLE_CLICK_INTERVAL, SCROLL_LINES},
-        reveal_path_internal,
+        reveal_path_internal, watch_fd,
         xdg_desktop_portal::{Event as XDPEvent, XDPEventSource},
-        LinuxClient,
+        GpuContext, LinuxClient,
     },
     LinuxCommon, PlatformWindow,
 };
 use crate::{
     modifiers_from_xinput_info, point, px, AnyWindowHandle, Bounds, ClipboardItem, CursorStyle,
-    DisplayId, FileDropEvent, Keystroke, Modifiers, ModifiersChangedEvent, MouseButton, Pixels,
-    Platform, PlatformDisplay, PlatformInput, Point, RequestFrameOptions, ScaledPixels,
-    ScrollDelta, Size, TouchPhase, WindowParams, X11Window,
+    DevicePixels, DisplayId, FileDropEvent, Keystroke, Modifiers, ModifiersChangedEvent,
+    MouseButton, Pixels, Platform, PlatformDisplay, PlatformInput, Point, ScaledPixels,
+    ScrollDelta, Size, Subscription, TouchPhase, WindowCreationError, WindowParams, X11Window,
 };
 
 /// Value for DeviceId parameters which selects all devices.
@@ -167,7 +169,8 @@ pub struct X11ClientState {
     pub(crate) last_location: Point<Pixels>,
     pub(crate) current_count: usize,
 
-    gpu_context: BladeContext,
+    gpu_context: GpuContext,
+    gpu_device: Option<u32>,
 
     pub(crate) scale_factor: f32,
 
@@ -275,7 +278,7 @@ impl X11Client {
     pub(crate) fn new() -> Self {
         let event_loop = EventLoop::try_new().unwrap();
 
-        let (common, main_receiver) = LinuxCommon::new(event_loop.get_signal());
+        let (mut common, main_receiver) = LinuxCommon::new(event_loop.get_signal());
 
         let handle = event_loop.handle();
 
@@ -373,8 +376,6 @@ impl X11Client {
         let compose_state = get_xkb_compose_state(&xkb_context);
         let resource_database = x11rb::resource_manager::new_from_default(&xcb_connection).unwrap();
 
-        let gpu_context = BladeContext::new().expect("Unable to init GPU context");
-
         let scale_factor = resource_database
             .get_value("Xft.dpi", "Xft.dpi")
             .ok()
@@ -423,8 +424,41 @@ impl X11Client {
                 move |event, _, client| match event {
                     XDPEvent::WindowAppearance(appearance) => {
                         client.with_common(|common| common.appearance = appearance);
-                        for (_, window) in &mut client.0.borrow_mut().windows {
-                            window.window.set_appearance(appearance);
+                        let windows = client
+                            .0
+                            .borrow()
+                            .windows
+                            .values()
+                            .map(|window| window.window.clone())
+                            .collect::<Vec<_>>();
+                        for mut window in windows {
+                            window.set_appearance(appearance);
+                        }
+                    }
+                    XDPEvent::AccentColor(accent_color) => {
+                        client.with_common(|common| common.accent_color = accent_color);
+                        let windows = client
+                            .0
+                            .borrow()
+                            .windows
+                            .values()
+                            .map(|window| window.window.clone())
+                            .collect::<Vec<_>>();
+                        for window in windows {
+                            window.appearance_changed();
+                        }
+                    }
+                    XDPEvent::ReduceMotion(reduce_motion) => {
+                        client.with_common(|common| common.reduce_motion = reduce_motion);
+                        let windows = client
+                            .0
+                            .borrow()
+                            .windows
+                            .values()
+                            .map(|window| window.window.clone())
+                            .collect::<Vec<_>>();
+                        for window in windows {
+                            window.appearance_changed();
                         }
                     }
                     XDPEvent::CursorTheme(_) | XDPEvent::CursorSize(_) => {
@@ -434,6 +468,18 @@ impl X11Client {
             })
             .unwrap();
 
+        let (app_menu, app_menu_events) = AppMenuExporter::new(&common.background_executor);
+        common.app_menu = Some(app_menu);
+        handle
+            .insert_source(app_menu_events, |event, _, client| {
+                if let calloop::channel::Event::Msg(event) = event {
+                    handle_app_menu_event(event, || {
+                        RefMut::map(client.0.borrow_mut(), |state| &mut state.common)
+                    });
+                }
+            })
+            .unwrap();
+
         X11Client(Rc::new(RefCell::new(X11ClientState {
             modifiers: Modifiers::default(),
             last_modifiers_changed_event: Modifiers::default(),
@@ -444,7 +490,8 @@ impl X11Client {
             last_mouse_button: None,
             last_location: Point::new(px(0.0), px(0.0)),
             current_count: 0,
-            gpu_context,
+            gpu_context: GpuContext::default(),
+            gpu_device: None,
             scale_factor,
 
             xkb_context,
@@ -485,7 +532,7 @@ impl X11Client {
     ) -> Result<(), EventHandlerError> {
         loop {
             let mut events = Vec::new();
-            let mut windows_to_refresh = HashSet::new();
+            let mut exposed_areas = HashMap::<xproto::Window, Vec<Bounds<DevicePixels>>>::default();
 
             let mut last_key_release = None;
             let mut last_key_press: Option<KeyPressEvent> = None;
@@ -495,7 +542,20 @@ impl X11Client {
                     Ok(Some(event)) => {
                         match event {
                             Event::Expose(expose_event) => {
-                                windows_to_refresh.insert(expose_event.window);
+                                let area = Bounds {
+                                    origin: Point {
+                                        x: DevicePixels(expose_event.x.into()),
+                                        y: DevicePixels(expose_event.y.into()),
+                                    },
+                                    size: Size {
+                                        width: DevicePixels(expose_event.width.into()),
+                                        height: DevicePixels(expose_event.height.into()),
+                                    },
+                                };
+                                exposed_areas
+                                    .entry(expose_event.window)
+                                    .or_default()
+                                    .push(area);
                             }
                             Event::KeyRelease(_) => {
                                 last_key_release = Some(event);
@@ -543,15 +603,13 @@ impl X11Client {
                 }
             }
 
-            if events.is_empty() && windows_to_refresh.is_empty() {
+            if events.is_empty() && exposed_areas.is_empty() {
                 break;
             }
 
-            for window in windows_to_refresh.into_iter() {
+            for (window, areas) in exposed_areas.into_iter() {
                 if let Some(window) = self.get_window(window) {
-                    window.refresh(RequestFrameOptions {
-                        require_presentation: true,
-                    });
+                    window.handle_expose(&areas);
                 }
             }
 
@@ -813,6 +871,9 @@ impl X11Client {
             }
             Event::FocusOut(event) => {
                 let window = self.get_window(event.event)?;
+                if event.mode == xproto::NotifyMode::UNGRAB {
+                    window.grab_lost();
+                }
                 window.set_active(false);
                 let mut state = self.0.borrow_mut();
                 state.keyboard_focused_window = None;
@@ -925,7 +986,7 @@ impl X11Client {
                                 let pre_edit =
                                     state.pre_edit_text.clone().unwrap_or(String::default());
                                 drop(state);
-                                window.handle_ime_preedit(pre_edit);
+                                window.handle_ime_preedit(pre_edit, None);
                                 state = self.0.borrow_mut();
                             }
                             xkbc::Status::Cancelled => {
@@ -935,7 +996,7 @@ impl X11Client {
                                     window.handle_ime_commit(pre_edit);
                                 }
                                 if let Some(current_key) = Keystroke::underlying_dead_key(keysym) {
-                                    window.handle_ime_preedit(current_key);
+                                    window.handle_ime_preedit(current_key, None);
                                 }
                                 state = self.0.borrow_mut();
                                 compose_state.feed(keysym);
@@ -992,6 +1053,16 @@ impl X11Client {
                     px(event.event_y as f32 / u16::MAX as f32 / state.scale_factor),
                 );
 
+                let is_button = matches!(
+                    button_or_scroll_from_event_detail(event.detail),
+                    Some(ButtonOrScroll::Button(_))
+                );
+                if is_button && window.is_press_outside_grab(position) {
+                    drop(state);
+                    window.close();
+                    return Some(());
+                }
+
                 if state.composing && state.ximc.is_some() {
                     drop(state);
                     self.reset_ime();
@@ -1121,6 +1192,9 @@ impl X11Client {
                 let mut state = self.0.borrow_mut();
                 state.mouse_focused_window = Some(event.event);
             }
+            Event::XinputLeave(event) if event.mode == xinput::NotifyMode::UNGRAB => {
+                self.get_window(event.event)?.grab_lost();
+            }
             Event::XinputLeave(event) if event.mode == xinput::NotifyMode::NORMAL => {
                 let mut state = self.0.borrow_mut();
 
@@ -1178,8 +1252,8 @@ impl X11Client {
             XimCallbackEvent::XimCommitEvent(window, text) => {
                 self.xim_handle_commit(window, text);
             }
-            XimCallbackEvent::XimPreeditEvent(window, text) => {
-                self.xim_handle_preedit(window, text);
+            XimCallbackEvent::XimPreeditEvent(window, text, caret) => {
+                self.xim_handle_preedit(window, text, caret);
             }
         };
     }
@@ -1233,7 +1307,12 @@ impl X11Client {
         Some(())
     }
 
-    fn xim_handle_preedit(&self, window: xproto::Window, text: String) -> Option<()> {
+    fn xim_handle_preedit(
+        &self,
+        window: xproto::Window,
+        text: String,
+        caret: Option<Range<usize>>,
+    ) -> Option<()> {
         let window = self.get_window(window).unwrap();
 
         let mut state = self.0.borrow_mut();
@@ -1241,7 +1320,7 @@ impl X11Client {
         let mut xim_handler = state.xim_handler.take().unwrap();
         state.composing = !text.is_empty();
         drop(state);
-        window.handle_ime_preedit(text);
+        window.handle_ime_preedit(text, caret);
 
         if let Some(area) = window.get_ime_area() {
             let ic_attributes = ximc
@@ -1292,6 +1371,28 @@ impl LinuxClient for X11Client {
             .to_string()
     }
 
+    fn keyboard_layout_index(&self) -> Option<usize> {
+        let state = self.0.borrow();
+        Some(state.xkb.serialize_layout(STATE_LAYOUT_EFFECTIVE) as usize)
+    }
+
+    fn keyboard_layouts(&self) -> Vec<String> {
+        let state = self.0.borrow();
+        let keymap = state.xkb.get_keymap();
+        (0..keymap.num_layouts())
+            .map(|layout_idx| keymap.layout_get_name(layout_idx).to_string())
+            .collect()
+    }
+
+    fn set_gpu_device(&self, device_id: Option<u32>) {
+        let mut state = self.0.borrow_mut();
+        if state.gpu_device != device_id {
+            // Already open windows keep rendering on their current GPU.
+            state.gpu_device = device_id;
+            state.gpu_context = GpuContext::default();
+        }
+    }
+
     fn displays(&self) -> Vec<Rc<dyn PlatformDisplay>> {
         let state = self.0.borrow();
         let setup = state.xcb_connection.setup();
@@ -1332,15 +1433,20 @@ impl LinuxClient for X11Client {
         &self,
         handle: AnyWindowHandle,
         params: WindowParams,
-    ) -> anyhow::Result<Box<dyn PlatformWindow>> {
+    ) -> Result<Box<dyn PlatformWindow>, WindowCreationError> {
         let mut state = self.0.borrow_mut();
-        let x_window = state.xcb_connection.generate_id().unwrap();
+        let gpu_device = state.gpu_device;
+        state.gpu_context.init(gpu_device, "OpenGL ES");
+        let x_window = state
+            .xcb_connection
+            .generate_id()
+            .context("X11 ran out of ids for the window")?;
 
         let window = X11Window::new(
             handle,
             X11ClientStatePtr(Rc::downgrade(&self.0)),
             state.common.foreground_executor.clone(),
-            &state.gpu_context,
+            state.gpu_context.get(),
             params,
             &state.xcb_connection,
             state.client_side_decorations_supported,
@@ -1359,14 +1465,14 @@ impl LinuxClient for X11Client {
                 state.atoms.XA_ATOM,
                 &[5],
             )
-            .unwrap();
+            .context("X11 ChangeProperty for XdndAware failed")?;
 
         let screen_resources = state
             .xcb_connection
             .randr_get_screen_resources(x_window)
-            .unwrap()
+            .context("X11 RandR GetScreenResources failed")?
             .reply()
-            .expect("Could not find available screens");
+            .context("Could not find available screens")?;
 
         let mode = screen_resources
             .crtcs
@@ -1384,7 +1490,7 @@ impl LinuxClient for X11Client {
                     .iter()
                     .find(|m| m.id == crtc_info.mode)
             })
-            .expect("Unable to find screen refresh rate");
+            .context("Unable to find screen refresh rate")?;
 
         let refresh_event_token = state
             .loop_handle
@@ -1411,7 +1517,9 @@ impl LinuxClient for X11Client {
                     calloop::timer::TimeoutAction::ToInstant(instant)
                 }
             })
-            .expect("Failed to initialize refresh timer");
+            .map_err(|error| {
+                anyhow::anyhow!("Failed to initialize refresh timer: {}", error.error)
+            })?;
 
         let window_ref = WindowRef {
             window: window.0.clone(),
@@ -1419,6 +1527,9 @@ impl LinuxClient for X11Client {
         };
 
         state.windows.insert(x_window, window_ref);
+        if let Some(app_menu) = &state.common.app_menu {
+            app_menu.register_x11_window(x_window);
+        }
         Ok(Box::new(window))
     }
 
@@ -1543,7 +1654,11 @@ impl LinuxClient for X11Client {
             .ok()
     }
 
-    fn run(&self) {
+    fn watch_fd(&self, fd: OwnedFd, callback: Box<dyn FnMut()>) -> anyhow::Result<Subscription> {
+        watch_fd(&self.0.borrow().loop_handle, fd, callback)
+    }
+
+    fn run(&self) -> anyhow::Result<()> {
         let mut event_loop = self
             .0
             .borrow_mut()
@@ -1551,7 +1666,9 @@ impl LinuxClient for X11Client {
             .take()
             .expect("App is already running");
 
-        event_loop.run(None, &mut self.clone(), |_| {}).log_err();
+        event_loop
+            .run(None, &mut self.clone(), |_| {})
+            .context("lost connection to the X server")
     }
 
     fn active_window(&self) -> Option<AnyWindowHandle> {
@@ -1564,6 +1681,11 @@ impl LinuxClient for X11Client {
         })
     }
 
+    fn window_identifier(&self) -> impl Future<Output = Option<ashpd::WindowIdentifier>> + 'static {
+        let focused_window = self.0.borrow().keyboard_focused_window;
+        std::future::ready(focused_window.map(|xid| ashpd::WindowIdentifier::from_xid(xid.into())))
+    }
+
     fn window_stack(&self) -> Option<Vec<AnyWindowHandle>> {
         let state = self.0.borrow();
         let root = state.xcb_connection.setup().roots[state.x_root_index].root;
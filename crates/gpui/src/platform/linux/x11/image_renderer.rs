@@ -0,0 +1,223 @@
+//! A CPU renderer presenting through core `PutImage` requests, used when no usable GPU is
+//! available.
+
+use std::{rc::Rc, sync::Arc};
+
+use anyhow::{Context as _, Result};
+use util::ResultExt;
+use x11rb::{
+    connection::{Connection, RequestConnection},
+    protocol::xproto::{self, ConnectionExt as _},
+    xcb_ffi::XCBConnection,
+};
+
+use crate::{
+    platform::linux::canvas::{Canvas, CanvasAtlas},
+    point,
+    scene::Damage,
+    Bounds, DevicePixels, GpuSpecs, Point, RenderStats, ScaledPixels, Scene, Size,
+};
+
+/// Length of a `PutImage` request without its pixels.
+const PUT_IMAGE_HEADER_LEN: usize = 24;
+
+pub(crate) struct ImageRenderer {
+    xcb: Rc<XCBConnection>,
+    x_window: xproto::Window,
+    gc: xproto::Gcontext,
+    depth: u8,
+    /// Whether the server wants the most significant byte of each pixel first.
+    msb_first: bool,
+    atlas: Arc<CanvasAtlas>,
+    size: Size<DevicePixels>,
+    canvas: Canvas,
+    staging: Vec<u8>,
+    needs_full_redraw: bool,
+}
+
+impl ImageRenderer {
+    pub fn new(
+        xcb: &Rc<XCBConnection>,
+        x_window: xproto::Window,
+        depth: u8,
+        size: Size<DevicePixels>,
+    ) -> Result<Self> {
+        let setup = xcb.setup();
+        let bits_per_pixel = setup
+            .pixmap_formats
+            .iter()
+            .find(|format| format.depth == depth)
+            .map(|format| format.bits_per_pixel)
+            .with_context(|| format!("X11 has no pixmap format for depth {depth}"))?;
+        anyhow::ensure!(
+            bits_per_pixel == 32,
+            "unsupported X11 pixmap format with {bits_per_pixel} bits per pixel"
+        );
+        let msb_first = setup.image_byte_order == xproto::ImageOrder::MSB_FIRST;
+
+        let gc = xcb.generate_id()?;
+        xcb.create_gc(gc, x_window, &xproto::CreateGCAux::new())?
+            .check()
+            .context("X11 CreateGC failed.")?;
+
+        Ok(Self {
+            xcb: xcb.clone(),
+            x_window,
+            gc,
+            depth,
+            msb_first,
+            atlas: Arc::new(CanvasAtlas::new()),
+            size,
+            canvas: Canvas::new(size),
+            staging: Vec::new(),
+            needs_full_redraw: true,
+        })
+    }
+
+    pub fn update_drawable_size(&mut self, size: Size<DevicePixels>) {
+        if size != self.size {
+            self.size = size;
+            self.canvas = Canvas::new(size);
+            self.needs_full_redraw = true;
+        }
+    }
+
+    pub fn update_transparency(&mut self, _transparent: bool) {
+        // Pixels are always ARGB, opaque windows simply paint every pixel.
+        self.needs_full_redraw = true;
+    }
+
+    pub fn viewport_size(&self) -> Size<DevicePixels> {
+        self.size
+    }
+
+    pub fn sprite_atlas(&self) -> &Arc<CanvasAtlas> {
+        &self.atlas
+    }
+
+    pub fn render_stats(&self) -> RenderStats {
+        RenderStats {
+            atlas_bytes: self.atlas.memory_usage(),
+            ..Default::default()
+        }
+    }
+
+    pub fn gpu_specs(&self) -> GpuSpecs {
+        GpuSpecs {
+            is_software_emulated: true,
+            device_name: "PutImage".to_string(),
+            driver_name: "tiny-skia".to_string(),
+            driver_info: String::new(),
+        }
+    }
+
+    pub fn destroy(&mut self) {
+        self.xcb.free_gc(self.gc).log_err();
+    }
+
+    pub fn draw(&mut self, scene: &Scene) {
+        let damage = if self.needs_full_redraw {
+            Damage::Full
+        } else {
+            scene.damage.clone()
+        };
+        if damage == Damage::None {
+            // The window still shows the last frame, and areas it loses are uploaded again when
+            // they're exposed.
+            return;
+        }
+        self.atlas.next_frame();
+        self.needs_full_redraw = false;
+        self.canvas.draw(scene, &self.atlas);
+
+        let areas = match damage {
+            Damage::Partial(areas) => areas.iter().map(covering_pixels).collect(),
+            Damage::Full | Damage::None => vec![Bounds::new(Point::default(), self.size)],
+        };
+        self.present(&areas).log_err();
+    }
+
+    /// Uploads areas of the last frame again after the server lost them, returning `false` if
+    /// there's no frame to upload yet.
+    pub fn expose(&mut self, areas: &[Bounds<DevicePixels>]) -> bool {
+        if self.needs_full_redraw {
+            return false;
+        }
+        self.present(areas).log_err();
+        true
+    }
+
+    fn present(&mut self, areas: &[Bounds<DevicePixels>]) -> Result<()> {
+        for area in areas {
+            self.upload(*area)?;
+        }
+        self.xcb.flush()?;
+        Ok(())
+    }
+
+    fn upload(&mut self, area: Bounds<DevicePixels>) -> Result<()> {
+        let pixmap = self.canvas.pixmap();
+        let width = pixmap.width() as i32;
+        let height = pixmap.height() as i32;
+        let left = area.left().0.clamp(0, width) as usize;
+        let right = area.right().0.clamp(0, width) as usize;
+        let top = area.top().0.clamp(0, height) as usize;
+        let bottom = area.bottom().0.clamp(0, height) as usize;
+        if left >= right || top >= bottom {
+            return Ok(());
+        }
+        let stride = (right - left) * 4;
+
+        // tiny-skia produces premultiplied RGBA, X11 expects premultiplied ARGB words.
+        let msb_first = self.msb_first;
+        self.staging.clear();
+        for row in pixmap
+            .data()
+            .chunks_exact(width as usize * 4)
+            .take(bottom)
+            .skip(top)
+        {
+            self.staging
+                .extend(row[left * 4..right * 4].chunks_exact(4).flat_map(|pixel| {
+                    if msb_first {
+                        [pixel[3], pixel[0], pixel[1], pixel[2]]
+                    } else {
+                        [pixel[2], pixel[1], pixel[0], pixel[3]]
+                    }
+                }));
+        }
+
+        // Large areas don't fit into a single request, send them in strips of rows.
+        let rows_per_request =
+            ((self.xcb.maximum_request_bytes() - PUT_IMAGE_HEADER_LEN) / stride).max(1);
+        for (index, rows) in self.staging.chunks(rows_per_request * stride).enumerate() {
+            self.xcb.put_image(
+                xproto::ImageFormat::Z_PIXMAP,
+                self.x_window,
+                self.gc,
+                (right - left) as u16,
+                (rows.len() / stride) as u16,
+                left as i16,
+                (top + index * rows_per_request) as i16,
+                0,
+                self.depth,
+                rows,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the device pixels an area of the scene touches.
+fn covering_pixels(area: &Bounds<ScaledPixels>) -> Bounds<DevicePixels> {
+    Bounds::from_corners(
+        point(
+            DevicePixels(area.left().0.floor() as i32),
+            DevicePixels(area.top().0.floor() as i32),
+        ),
+        point(
+            DevicePixels(area.right().0.ceil() as i32),
+            DevicePixels(area.bottom().0.ceil() as i32),
+        ),
+    )
+}
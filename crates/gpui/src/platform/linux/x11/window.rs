@@ -1,12 +1,15 @@
 use anyhow::{anyhow, Context as _};
 
 use crate::platform::blade::{BladeContext, BladeRenderer, BladeSurfaceConfig};
+use crate::platform::linux::gles_renderer::{GlesRenderer, GlesWindow};
 use crate::{
-    px, size, AnyWindowHandle, Bounds, Decorations, DevicePixels, ForegroundExecutor, GpuSpecs,
-    Modifiers, Pixels, PlatformAtlas, PlatformDisplay, PlatformInput, PlatformInputHandler,
-    PlatformWindow, Point, PromptLevel, RequestFrameOptions, ResizeEdge, ScaledPixels, Scene, Size,
-    Tiling, WindowAppearance, WindowBackgroundAppearance, WindowBounds, WindowDecorations,
-    WindowKind, WindowParams, X11ClientStatePtr,
+    px, size, AccessibilityAdapter, AccessibilityRequest, AccessibilityTree, Anchor, AntiAliasing,
+    AnyWindowHandle, Bounds, Decorations, DevicePixels, ForegroundExecutor, GpuSpecs,
+    KeyboardInteractivity, Layer, LayerShellSettings, Modifiers, Pixels, PlatformAtlas,
+    PlatformDisplay, PlatformInput, PlatformInputHandler, PlatformWindow, Point, PromptLevel,
+    RenderStats, RequestFrameOptions, ResizeEdge, ScaledPixels, Scene, Size, Tiling,
+    WindowAppearance, WindowBackgroundAppearance, WindowBounds, WindowCreationError,
+    WindowDecorations, WindowKind, WindowParams, X11ClientStatePtr,
 };
 
 use blade_graphics as gpu;
@@ -16,8 +19,9 @@ use x11rb::{
     connection::Connection,
     cookie::{Cookie, VoidCookie},
     errors::ConnectionError,
-    properties::WmSizeHints,
+    properties::{WmHints, WmSizeHints},
     protocol::{
+        shape::{self, ConnectionExt as _},
         sync,
         xinput::{self, ConnectionExt as _},
         xproto::{self, ClientMessageEvent, ConnectionExt, EventMask, TranslateCoordinatesReply},
@@ -27,11 +31,19 @@ use x11rb::{
 };
 
 use std::{
-    cell::RefCell, ffi::c_void, fmt::Display, num::NonZeroU32, ops::Div, ptr::NonNull, rc::Rc,
+    cell::RefCell,
+    ffi::c_void,
+    fmt::Display,
+    num::NonZeroU32,
+    ops::{Div, Range},
+    ptr::NonNull,
+    rc::Rc,
     sync::Arc,
 };
 
-use super::{X11Display, XINPUT_ALL_DEVICES, XINPUT_ALL_DEVICE_GROUPS};
+use super::{
+    image_renderer::ImageRenderer, X11Display, XINPUT_ALL_DEVICES, XINPUT_ALL_DEVICE_GROUPS,
+};
 x11rb::atom_manager! {
     pub XcbAtoms: AtomsCookie {
         XA_ATOM,
@@ -70,6 +82,15 @@ x11rb::atom_manager! {
         _NET_WM_MOVERESIZE,
         _NET_WM_WINDOW_TYPE,
         _NET_WM_WINDOW_TYPE_NOTIFICATION,
+        _NET_WM_WINDOW_TYPE_DOCK,
+        _NET_WM_WINDOW_TYPE_DESKTOP,
+        _NET_WM_STATE_ABOVE,
+        _NET_WM_STATE_BELOW,
+        _NET_WM_STATE_STICKY,
+        _NET_WM_DESKTOP,
+        _NET_CURRENT_DESKTOP,
+        _NET_WM_STRUT,
+        _NET_WM_STRUT_PARTIAL,
         _NET_WM_SYNC,
         _NET_SUPPORTED,
         _MOTIF_WM_HINTS,
@@ -227,6 +248,104 @@ struct RawWindow {
     visual_id: u32,
 }
 
+enum Renderer {
+    Blade(BladeRenderer),
+    Gles(GlesRenderer),
+    Image(ImageRenderer),
+}
+
+impl Renderer {
+    fn viewport_size(&self) -> Size<DevicePixels> {
+        match self {
+            Renderer::Blade(renderer) => {
+                let extent = renderer.viewport_size();
+                size(
+                    DevicePixels(extent.width as i32),
+                    DevicePixels(extent.height as i32),
+                )
+            }
+            Renderer::Gles(renderer) => renderer.viewport_size(),
+            Renderer::Image(renderer) => renderer.viewport_size(),
+        }
+    }
+
+    fn update_drawable_size(&mut self, size: Size<DevicePixels>) {
+        match self {
+            Renderer::Blade(renderer) => renderer.update_drawable_size(size),
+            Renderer::Gles(renderer) => renderer.update_drawable_size(size),
+            Renderer::Image(renderer) => renderer.update_drawable_size(size),
+        }
+    }
+
+    fn update_transparency(&mut self, transparent: bool) {
+        match self {
+            Renderer::Blade(renderer) => renderer.update_transparency(transparent),
+            Renderer::Gles(renderer) => renderer.update_transparency(transparent),
+            Renderer::Image(renderer) => renderer.update_transparency(transparent),
+        }
+    }
+
+    fn update_anti_aliasing(&mut self, anti_aliasing: AntiAliasing) {
+        match self {
+            Renderer::Blade(renderer) => renderer.update_anti_aliasing(anti_aliasing),
+            // The canvas only uses analytic coverage.
+            Renderer::Gles(_) | Renderer::Image(_) => {}
+        }
+    }
+
+    fn draw(&mut self, scene: &Scene) {
+        match self {
+            Renderer::Blade(renderer) => renderer.draw(scene),
+            Renderer::Gles(renderer) => renderer.draw(scene),
+            Renderer::Image(renderer) => renderer.draw(scene),
+        }
+    }
+
+    /// Repaints areas of the window the server lost the contents of, returning whether it did
+    /// or a new frame is needed.
+    fn expose(&mut self, areas: &[Bounds<DevicePixels>]) -> bool {
+        match self {
+            // Swapchain images can't be presented again.
+            Renderer::Blade(_) => false,
+            // The frame texture covers every area at once.
+            Renderer::Gles(renderer) => renderer.expose(),
+            Renderer::Image(renderer) => renderer.expose(areas),
+        }
+    }
+
+    fn sprite_atlas(&self) -> Arc<dyn PlatformAtlas> {
+        match self {
+            Renderer::Blade(renderer) => renderer.sprite_atlas().clone(),
+            Renderer::Gles(renderer) => renderer.sprite_atlas().clone(),
+            Renderer::Image(renderer) => renderer.sprite_atlas().clone(),
+        }
+    }
+
+    fn gpu_specs(&self) -> GpuSpecs {
+        match self {
+            Renderer::Blade(renderer) => renderer.gpu_specs(),
+            Renderer::Gles(renderer) => renderer.gpu_specs(),
+            Renderer::Image(renderer) => renderer.gpu_specs(),
+        }
+    }
+
+    fn render_stats(&self) -> RenderStats {
+        match self {
+            Renderer::Blade(renderer) => renderer.render_stats(),
+            Renderer::Gles(renderer) => renderer.render_stats(),
+            Renderer::Image(renderer) => renderer.render_stats(),
+        }
+    }
+
+    fn destroy(&mut self) {
+        match self {
+            Renderer::Blade(renderer) => renderer.destroy(),
+            Renderer::Gles(renderer) => renderer.destroy(),
+            Renderer::Image(renderer) => renderer.destroy(),
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct Callbacks {
     request_frame: Option<Box<dyn FnMut(RequestFrameOptions)>>,
@@ -250,7 +369,7 @@ pub struct X11WindowState {
     pub(crate) last_sync_counter: Option<sync::Int64>,
     bounds: Bounds<Pixels>,
     scale_factor: f32,
-    renderer: BladeRenderer,
+    renderer: Renderer,
     display: Rc<dyn PlatformDisplay>,
     input_handler: Option<PlatformInputHandler>,
     appearance: WindowAppearance,
@@ -266,6 +385,13 @@ pub struct X11WindowState {
     edge_constraints: Option<EdgeConstraints>,
     pub handle: AnyWindowHandle,
     last_insets: [u32; 4],
+    layer_shell: Option<LayerShellSettings>,
+    /// Whether the window is a pop-up that grabs the pointer and keyboard once it's mapped.
+    grabs_input: bool,
+    /// The master pointer grabbed by the pop-up, while its grab is active.
+    grabbed_pointer: Option<xinput::DeviceId>,
+    grabbed_keyboard: bool,
+    accessibility: AccessibilityAdapter,
 }
 
 impl X11WindowState {
@@ -352,13 +478,116 @@ where
         .with_context(failure_context)
 }
 
+/// Places a layer shell window on the screen the way a layer shell compositor would, returning
+/// its bounds along with the `_NET_WM_STRUT_PARTIAL` reserving its exclusive zone.
+fn layer_shell_geometry(
+    settings: &LayerShellSettings,
+    window_size: Size<DevicePixels>,
+    screen_size: Size<DevicePixels>,
+    scale_factor: f32,
+) -> (Bounds<DevicePixels>, [u32; 12]) {
+    let device = |pixels: Pixels| (pixels.0 * scale_factor).round() as i32;
+    let (top, right, bottom, left) = settings.margin.map_or((0, 0, 0, 0), |margin| {
+        (
+            device(margin.0),
+            device(margin.1),
+            device(margin.2),
+            device(margin.3),
+        )
+    });
+
+    let bounds = settings
+        .bounds_within(
+            window_size.to_pixels(scale_factor),
+            Bounds {
+                origin: Point::default(),
+                size: screen_size.to_pixels(scale_factor),
+            },
+        )
+        .to_device_pixels(scale_factor);
+    let (x, y) = (bounds.origin.x.0, bounds.origin.y.0);
+    let (width, height) = (bounds.size.width.0, bounds.size.height.0);
+
+    // As with layer shell, the exclusive zone only applies to windows anchored to a single
+    // edge, or to an edge and both of its neighbours, and it's measured from the margin.
+    let mut strut = [0u32; 12];
+    let Some(zone) = settings.exclusive_zone.filter(|zone| zone.0 > 0.) else {
+        return (bounds, strut);
+    };
+    let zone = device(zone);
+    let edge = settings.attached_edge();
+    let (start_x, end_x) = (x.max(0) as u32, (x + width - 1).max(0) as u32);
+    let (start_y, end_y) = (y.max(0) as u32, (y + height - 1).max(0) as u32);
+    match edge {
+        Some(Anchor::TOP) => {
+            strut[2] = (zone + top) as u32;
+            strut[8] = start_x;
+            strut[9] = end_x;
+        }
+        Some(Anchor::BOTTOM) => {
+            strut[3] = (zone + bottom) as u32;
+            strut[10] = start_x;
+            strut[11] = end_x;
+        }
+        Some(Anchor::LEFT) => {
+            strut[0] = (zone + left) as u32;
+            strut[4] = start_y;
+            strut[5] = end_y;
+        }
+        Some(Anchor::RIGHT) => {
+            strut[1] = (zone + right) as u32;
+            strut[6] = start_y;
+            strut[7] = end_y;
+        }
+        _ => {}
+    }
+    (bounds, strut)
+}
+
+fn set_struts(
+    xcb: &Rc<XCBConnection>,
+    x_window: xproto::Window,
+    atoms: &XcbAtoms,
+    strut: [u32; 12],
+) -> anyhow::Result<()> {
+    check_reply(
+        || "X11 ChangeProperty32 for _NET_WM_STRUT_PARTIAL failed.",
+        xcb.change_property32(
+            xproto::PropMode::REPLACE,
+            x_window,
+            atoms._NET_WM_STRUT_PARTIAL,
+            xproto::AtomEnum::CARDINAL,
+            &strut,
+        ),
+    )?;
+    // For window managers predating _NET_WM_STRUT_PARTIAL.
+    check_reply(
+        || "X11 ChangeProperty32 for _NET_WM_STRUT failed.",
+        xcb.change_property32(
+            xproto::PropMode::REPLACE,
+            x_window,
+            atoms._NET_WM_STRUT,
+            xproto::AtomEnum::CARDINAL,
+            &strut[..4],
+        ),
+    )
+}
+
+fn screen_size(xcb: &Rc<XCBConnection>, x_screen_index: usize) -> Size<DevicePixels> {
+    let screen = &xcb.setup().roots[x_screen_index];
+    size(
+        DevicePixels(screen.width_in_pixels as i32),
+        DevicePixels(screen.height_in_pixels as i32),
+    )
+}
+
 impl X11WindowState {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         handle: AnyWindowHandle,
         client: X11ClientStatePtr,
         executor: ForegroundExecutor,
-        gpu_context: &BladeContext,
+        gpu_context: Option<&BladeContext>,
         params: WindowParams,
         xcb: &Rc<XCBConnection>,
         client_side_decorations_supported: bool,
@@ -395,6 +624,9 @@ impl X11WindowState {
             id
         };
 
+        // Pop-ups bypass the window manager, so they're shown exactly where they're asked to be
+        // and aren't decorated, focused or moved to another workspace by it.
+        let is_popup = params.kind == WindowKind::PopUp;
         let win_aux = xproto::CreateWindowAux::new()
             // https://stackoverflow.com/questions/43218127/x11-xlib-xcb-creating-a-window-requires-border-pixel-if-specifying-colormap-wh
             .border_pixel(visual_set.black_pixel)
@@ -406,9 +638,27 @@ impl X11WindowState {
                     | xproto::EventMask::KEY_PRESS
                     | xproto::EventMask::KEY_RELEASE
                     | EventMask::PROPERTY_CHANGE,
-            );
+            )
+            .override_redirect(u32::from(is_popup));
+
+        let layer_shell = match &params.kind {
+            WindowKind::LayerShell(settings) => Some(settings.clone()),
+            _ => None,
+        };
 
         let mut bounds = params.bounds.to_device_pixels(scale_factor);
+        let mut strut = [0; 12];
+        if let Some(settings) = &layer_shell {
+            (bounds, strut) = layer_shell_geometry(
+                settings,
+                bounds.size,
+                screen_size(xcb, x_screen_index),
+                scale_factor,
+            );
+        }
+        // Layer shell windows and pop-ups are placed exactly where they're asked to be.
+        let is_placed = layer_shell.is_some() || is_popup;
+        let x_offset = if is_placed { 0 } else { 2 };
         if bounds.size.width.0 == 0 || bounds.size.height.0 == 0 {
             log::warn!("Window bounds contain a zero value. height={}, width={}. Falling back to defaults.", bounds.size.height.0, bounds.size.width.0);
             bounds.size.width = 800.into();
@@ -418,13 +668,13 @@ impl X11WindowState {
         check_reply(
             || {
                 format!("X11 CreateWindow failed. depth: {}, x_window: {}, visual_set.root: {}, bounds.origin.x.0: {}, bounds.origin.y.0: {}, bounds.size.width.0: {}, bounds.size.height.0: {}",
-                                visual.depth, x_window, visual_set.root, bounds.origin.x.0 + 2, bounds.origin.y.0, bounds.size.width.0, bounds.size.height.0)
+                                visual.depth, x_window, visual_set.root, bounds.origin.x.0 + x_offset, bounds.origin.y.0, bounds.size.width.0, bounds.size.height.0)
             },
             xcb.create_window(
                 visual.depth,
                 x_window,
                 visual_set.root,
-                (bounds.origin.x.0 + 2) as i16,
+                (bounds.origin.x.0 + x_offset) as i16,
                 bounds.origin.y.0 as i16,
                 bounds.size.width.0 as u16,
                 bounds.size.height.0 as u16,
@@ -465,7 +715,7 @@ impl X11WindowState {
             }
 
             let reply = get_reply(|| "X11 GetGeometry failed.", xcb.get_geometry(x_window))?;
-            if reply.x == 0 && reply.y == 0 {
+            if reply.x == 0 && reply.y == 0 && !is_placed {
                 bounds.origin.x.0 += 2;
                 // Work around a bug where our rendered content appears
                 // outside the window bounds when opened at the default position
@@ -477,8 +727,8 @@ impl X11WindowState {
                     xcb.configure_window(x_window, &xproto::ConfigureWindowAux::new().x(x).y(y)),
                 )?;
             }
-            if let Some(titlebar) = params.titlebar {
-                if let Some(title) = titlebar.title {
+            if let Some(titlebar) = &params.titlebar {
+                if let Some(title) = &titlebar.title {
                     check_reply(
                         || "X11 ChangeProperty8 on window title failed.",
                         xcb.change_property8(
@@ -491,7 +741,7 @@ impl X11WindowState {
                     )?;
                 }
             }
-            if params.kind == WindowKind::PopUp {
+            if is_popup {
                 check_reply(
                     || "X11 ChangeProperty32 setting window type for pop-up failed.",
                     xcb.change_property32(
@@ -503,6 +753,61 @@ impl X11WindowState {
                     ),
                 )?;
             }
+            if let Some(settings) = &layer_shell {
+                let (window_type, stacking) = match settings.layer {
+                    Layer::Background => {
+                        (atoms._NET_WM_WINDOW_TYPE_DESKTOP, atoms._NET_WM_STATE_BELOW)
+                    }
+                    Layer::Bottom => (atoms._NET_WM_WINDOW_TYPE_DOCK, atoms._NET_WM_STATE_BELOW),
+                    Layer::Top | Layer::Overlay => {
+                        (atoms._NET_WM_WINDOW_TYPE_DOCK, atoms._NET_WM_STATE_ABOVE)
+                    }
+                };
+                check_reply(
+                    || "X11 ChangeProperty32 setting window type for layer shell failed.",
+                    xcb.change_property32(
+                        xproto::PropMode::REPLACE,
+                        x_window,
+                        atoms._NET_WM_WINDOW_TYPE,
+                        xproto::AtomEnum::ATOM,
+                        &[window_type],
+                    ),
+                )?;
+                check_reply(
+                    || "X11 ChangeProperty32 setting window state for layer shell failed.",
+                    xcb.change_property32(
+                        xproto::PropMode::REPLACE,
+                        x_window,
+                        atoms._NET_WM_STATE,
+                        xproto::AtomEnum::ATOM,
+                        &[stacking, atoms._NET_WM_STATE_STICKY],
+                    ),
+                )?;
+                set_struts(xcb, x_window, atoms, strut)?;
+
+                if settings.keyboard_interactivity == KeyboardInteractivity::None {
+                    let mut hints = WmHints::new();
+                    hints.input = Some(false);
+                    check_reply(
+                        || "X11 change of WM_HINTS failed.",
+                        hints.set(xcb, x_window),
+                    )?;
+                }
+                if !settings.pointer_interactivity {
+                    check_reply(
+                        || "X11 ShapeRectangles clearing the input shape failed.",
+                        xcb.shape_rectangles(
+                            shape::SO::SET,
+                            shape::SK::INPUT,
+                            xproto::ClipOrdering::UNSORTED,
+                            x_window,
+                            0,
+                            0,
+                            &[],
+                        ),
+                    )?;
+                }
+            }
 
             check_reply(
                 || "X11 ChangeProperty32 setting protocols failed.",
@@ -568,11 +873,17 @@ impl X11WindowState {
 
             xcb.flush().with_context(|| "X11 Flush failed.")?;
 
-            let renderer = {
+            let render_extent = query_render_extent(xcb, x_window)?;
+            let render_size = size(
+                DevicePixels(render_extent.width as i32),
+                DevicePixels(render_extent.height as i32),
+            );
+            let raw_connection =
+                as_raw_xcb_connection::AsRawXcbConnection::as_raw_xcb_connection(xcb)
+                    as *mut c_void;
+            let blade_renderer = gpu_context.map(|gpu_context| {
                 let raw_window = RawWindow {
-                    connection: as_raw_xcb_connection::AsRawXcbConnection::as_raw_xcb_connection(
-                        xcb,
-                    ) as *mut _,
+                    connection: raw_connection,
                     screen_id: x_screen_index,
                     window_id: x_window,
                     visual_id: visual.id,
@@ -580,17 +891,49 @@ impl X11WindowState {
                 let config = BladeSurfaceConfig {
                     // Note: this has to be done after the GPU init, or otherwise
                     // the sizes are immediately invalidated.
-                    size: query_render_extent(xcb, x_window)?,
+                    size: render_extent,
                     // We set it to transparent by default, even if we have client-side
                     // decorations, since those seem to work on X11 even without `true` here.
                     // If the window appearance changes, then the renderer will get updated
                     // too
                     transparent: false,
                 };
-                BladeRenderer::new(gpu_context, &raw_window, config)?
+                BladeRenderer::new(gpu_context, &raw_window, config)
+            });
+            let renderer = match blade_renderer {
+                Some(Ok(renderer)) => Renderer::Blade(renderer),
+                blade_renderer => {
+                    if let Some(Err(err)) = blade_renderer {
+                        log::warn!(
+                            "Failed to create GPU renderer, falling back to OpenGL ES: {err:?}"
+                        );
+                    }
+                    let gles_window = GlesWindow::Xcb {
+                        connection: raw_connection,
+                        screen: x_screen_index,
+                        window: x_window,
+                        visual_id: visual.id,
+                    };
+                    match GlesRenderer::new(gles_window, render_size) {
+                        Ok(renderer) => Renderer::Gles(renderer),
+                        Err(err) => {
+                            log::warn!(
+                                "Failed to create OpenGL ES renderer, falling back to PutImage: {err:?}"
+                            );
+                            Renderer::Image(
+                                ImageRenderer::new(xcb, x_window, visual.depth, render_size)
+                                    .map_err(WindowCreationError::RendererInit)?,
+                            )
+                        }
+                    }
+                }
             };
 
             let display = Rc::new(X11Display::new(xcb, scale_factor, x_screen_index)?);
+            let accessibility = AccessibilityAdapter::new(
+                &executor,
+                params.titlebar.and_then(|titlebar| titlebar.title),
+            );
 
             Ok(Self {
                 client,
@@ -615,9 +958,14 @@ impl X11WindowState {
                 client_side_decorations_supported,
                 decorations: WindowDecorations::Server,
                 last_insets: [0, 0, 0, 0],
+                layer_shell,
+                grabs_input: is_popup && params.focus,
+                grabbed_pointer: None,
+                grabbed_keyboard: false,
                 edge_constraints: None,
                 counter_id: sync_request_counter,
                 last_sync_counter: None,
+                accessibility,
             })
         });
 
@@ -636,8 +984,8 @@ impl X11WindowState {
     fn content_size(&self) -> Size<Pixels> {
         let size = self.renderer.viewport_size();
         Size {
-            width: size.width.into(),
-            height: size.height.into(),
+            width: px(size.width.0 as f32),
+            height: px(size.height.0 as f32),
         }
     }
 }
@@ -668,6 +1016,7 @@ pub(crate) struct X11Window(pub X11WindowStatePtr);
 
 impl Drop for X11Window {
     fn drop(&mut self) {
+        self.0.ungrab_input().log_err();
         let mut state = self.0.state.borrow_mut();
         state.renderer.destroy();
 
@@ -706,8 +1055,8 @@ impl Drop for X11Window {
 }
 
 enum WmHintPropertyState {
-    // Remove = 0,
-    // Add = 1,
+    Remove = 0,
+    Add = 1,
     Toggle = 2,
 }
 
@@ -717,7 +1066,7 @@ impl X11Window {
         handle: AnyWindowHandle,
         client: X11ClientStatePtr,
         executor: ForegroundExecutor,
-        gpu_context: &BladeContext,
+        gpu_context: Option<&BladeContext>,
         params: WindowParams,
         xcb: &Rc<XCBConnection>,
         client_side_decorations_supported: bool,
@@ -834,6 +1183,43 @@ impl X11Window {
     fn flush(&self) -> anyhow::Result<()> {
         self.0.xcb.flush().with_context(|| "X11 Flush failed.")
     }
+
+    /// Changes the settings of a layer shell window, and moves it and its struts accordingly.
+    fn update_layer_shell_geometry(&self, update: impl FnOnce(&mut LayerShellSettings)) {
+        let mut state = self.0.state.borrow_mut();
+        let scale_factor = state.scale_factor;
+        let window_size = state.bounds.size.to_device_pixels(scale_factor);
+        let screen_size = screen_size(&self.0.xcb, state.display.id().0 as usize);
+        let Some(settings) = state.layer_shell.as_mut() else {
+            log::error!("not a layer shell window");
+            return;
+        };
+        update(settings);
+        // The window's size includes the space kept for its shadow, which is added back when
+        // placing it.
+        let window_size = settings
+            .body_size(window_size.to_pixels(scale_factor))
+            .to_device_pixels(scale_factor);
+        let (bounds, strut) =
+            layer_shell_geometry(settings, window_size, screen_size, scale_factor);
+
+        maybe!({
+            check_reply(
+                || "X11 ConfigureWindow for layer shell geometry failed.",
+                self.0.xcb.configure_window(
+                    self.0.x_window,
+                    &xproto::ConfigureWindowAux::new()
+                        .x(bounds.origin.x.0)
+                        .y(bounds.origin.y.0)
+                        .width(bounds.size.width.0 as u32)
+                        .height(bounds.size.height.0 as u32),
+                ),
+            )?;
+            set_struts(&self.0.xcb, self.0.x_window, &state.atoms, strut)?;
+            self.flush()
+        })
+        .log_err();
+    }
 }
 
 impl X11WindowStatePtr {
@@ -934,6 +1320,105 @@ impl X11WindowStatePtr {
         }
     }
 
+    /// Grabs the pointer and keyboard for a pop-up, so a click outside of the app's windows
+    /// dismisses it, like the grab of a pop-up on Wayland. The pop-up is dismissed right away
+    /// when either can't be grabbed.
+    fn grab_input(&self) -> anyhow::Result<()> {
+        let pointer = get_reply(
+            || "X11 XIGetClientPointer failed.",
+            self.xcb.xinput_xi_get_client_pointer(x11rb::NONE),
+        )?
+        .deviceid;
+        let pointer_grab = get_reply(
+            || "X11 XIGrabDevice failed.",
+            self.xcb.xinput_xi_grab_device(
+                self.x_window,
+                x11rb::CURRENT_TIME,
+                x11rb::NONE,
+                pointer,
+                xproto::GrabMode::ASYNC,
+                xproto::GrabMode::ASYNC,
+                xinput::GrabOwner::OWNER,
+                &[u32::from(
+                    xinput::XIEventMask::MOTION
+                        | xinput::XIEventMask::BUTTON_PRESS
+                        | xinput::XIEventMask::BUTTON_RELEASE,
+                )],
+            ),
+        )?;
+        let keyboard_grab = get_reply(
+            || "X11 GrabKeyboard failed.",
+            self.xcb.grab_keyboard(
+                true,
+                self.x_window,
+                x11rb::CURRENT_TIME,
+                xproto::GrabMode::ASYNC,
+                xproto::GrabMode::ASYNC,
+            ),
+        )?;
+
+        let mut state = self.state.borrow_mut();
+        if pointer_grab.status == xproto::GrabStatus::SUCCESS {
+            state.grabbed_pointer = Some(pointer);
+        }
+        state.grabbed_keyboard = keyboard_grab.status == xproto::GrabStatus::SUCCESS;
+        if state.grabbed_pointer.is_none() || !state.grabbed_keyboard {
+            log::warn!(
+                "failed to grab input for pop-up, pointer: {:?}, keyboard: {:?}",
+                pointer_grab.status,
+                keyboard_grab.status
+            );
+            drop(state);
+            self.ungrab_input()?;
+            let this = self.clone();
+            self.state
+                .borrow()
+                .executor
+                .spawn(async move { this.close() })
+                .detach();
+        }
+        Ok(())
+    }
+
+    fn ungrab_input(&self) -> anyhow::Result<()> {
+        let mut state = self.state.borrow_mut();
+        if let Some(pointer) = state.grabbed_pointer.take() {
+            check_reply(
+                || "X11 XIUngrabDevice failed.",
+                self.xcb
+                    .xinput_xi_ungrab_device(x11rb::CURRENT_TIME, pointer),
+            )?;
+        }
+        if std::mem::take(&mut state.grabbed_keyboard) {
+            check_reply(
+                || "X11 UngrabKeyboard failed.",
+                self.xcb.ungrab_keyboard(x11rb::CURRENT_TIME),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Returns whether a button pressed at the given position dismisses the window: with a
+    /// grab, presses outside of the app's windows are reported to the pop-up, outside of it.
+    pub fn is_press_outside_grab(&self, position: Point<Pixels>) -> bool {
+        let state = self.state.borrow();
+        state.grabbed_pointer.is_some()
+            && !Bounds::new(Point::default(), state.bounds.size).contains(&position)
+    }
+
+    /// Dismisses a pop-up whose grab was broken from outside of the app.
+    pub fn grab_lost(&self) {
+        let mut state = self.state.borrow_mut();
+        if state.grabbed_pointer.is_none() && !state.grabbed_keyboard {
+            return;
+        }
+        // The server already released the grab.
+        state.grabbed_pointer = None;
+        state.grabbed_keyboard = false;
+        drop(state);
+        self.close();
+    }
+
     pub fn refresh(&self, request_frame_options: RequestFrameOptions) {
         let mut cb = self.callbacks.borrow_mut();
         if let Some(ref mut fun) = cb.request_frame {
@@ -941,6 +1426,18 @@ impl X11WindowStatePtr {
         }
     }
 
+    pub fn handle_expose(&self, areas: &[Bounds<DevicePixels>]) {
+        let mut state = self.state.borrow_mut();
+        let repainted = state.renderer.expose(areas);
+        drop(state);
+        if !repainted {
+            self.refresh(RequestFrameOptions {
+                require_presentation: true,
+                ..Default::default()
+            });
+        }
+    }
+
     pub fn handle_input(&self, input: PlatformInput) {
         if let Some(ref mut fun) = self.callbacks.borrow_mut().input {
             if !fun(input.clone()).propagate {
@@ -970,11 +1467,11 @@ impl X11WindowStatePtr {
         }
     }
 
-    pub fn handle_ime_preedit(&self, text: String) {
+    pub fn handle_ime_preedit(&self, text: String, selected_range: Option<Range<usize>>) {
         let mut state = self.state.borrow_mut();
         if let Some(mut input_handler) = state.input_handler.take() {
             drop(state);
-            input_handler.replace_and_mark_text_in_range(None, &text, None);
+            input_handler.replace_and_mark_text_in_range(None, &text, selected_range);
             let mut state = self.state.borrow_mut();
             state.input_handler = Some(input_handler);
         }
@@ -1066,14 +1563,19 @@ impl X11WindowStatePtr {
     }
 
     pub fn set_active(&self, focus: bool) {
+        self.state
+            .borrow_mut()
+            .accessibility
+            .set_window_focused(focus);
         if let Some(ref mut fun) = self.callbacks.borrow_mut().active_status_change {
             fun(focus);
         }
     }
 
-    pub fn set_hovered(&self, focus: bool) {
+    pub fn set_hovered(&self, hovered: bool) {
+        self.state.borrow_mut().hovered = hovered;
         if let Some(ref mut fun) = self.callbacks.borrow_mut().hovered_status_change {
-            fun(focus);
+            fun(hovered);
         }
     }
 
@@ -1084,6 +1586,10 @@ impl X11WindowStatePtr {
         state.renderer.update_transparency(is_transparent);
         state.appearance = appearance;
         drop(state);
+        self.appearance_changed();
+    }
+
+    pub fn appearance_changed(&self) {
         let mut callbacks = self.callbacks.borrow_mut();
         if let Some(ref mut fun) = callbacks.appearance_changed {
             (fun)()
@@ -1256,6 +1762,7 @@ impl PlatformWindow for X11Window {
         )
         .log_err();
         self.flush().log_err();
+        self.0.state.borrow_mut().accessibility.set_title(title);
     }
 
     fn set_app_id(&mut self, app_id: &str) {
@@ -1282,6 +1789,11 @@ impl PlatformWindow for X11Window {
             || "X11 MapWindow failed.",
             self.0.xcb.map_window(self.0.x_window),
         )?;
+        // Pop-ups are override-redirect, so they're viewable, and can be grabbed for, as soon
+        // as they're mapped.
+        if self.0.state.borrow().grabs_input {
+            self.0.grab_input()?;
+        }
         Ok(())
     }
 
@@ -1296,6 +1808,11 @@ impl PlatformWindow for X11Window {
         state.renderer.update_transparency(transparent);
     }
 
+    fn set_anti_aliasing(&self, anti_aliasing: AntiAliasing) {
+        let mut state = self.0.state.borrow_mut();
+        state.renderer.update_anti_aliasing(anti_aliasing);
+    }
+
     fn show_character_palette(&self) {
         log::info!("ignoring macOS specific show_character_palette");
     }
@@ -1390,7 +1907,11 @@ impl PlatformWindow for X11Window {
 
     fn sprite_atlas(&self) -> Arc<dyn PlatformAtlas> {
         let inner = self.0.state.borrow();
-        inner.renderer.sprite_atlas().clone()
+        inner.renderer.sprite_atlas()
+    }
+
+    fn render_stats(&self) -> RenderStats {
+        self.0.state.borrow().renderer.render_stats()
     }
 
     fn show_window_menu(&self, position: Point<Pixels>) {
@@ -1512,6 +2033,86 @@ impl PlatformWindow for X11Window {
         }
     }
 
+    fn set_layer_shell_margin(&self, margin: (Pixels, Pixels, Pixels, Pixels)) {
+        self.update_layer_shell_geometry(|settings| settings.margin = Some(margin));
+    }
+
+    fn set_layer_shell_exclusive_zone(&self, exclusive_zone: Pixels) {
+        self.update_layer_shell_geometry(|settings| settings.exclusive_zone = Some(exclusive_zone));
+    }
+
+    fn set_always_on_top(&self, always_on_top: bool) {
+        let state = self.0.state.borrow();
+        maybe!({
+            self.set_wm_hints(
+                || "X11 SendEvent to keep a window on top failed.",
+                if always_on_top {
+                    WmHintPropertyState::Add
+                } else {
+                    WmHintPropertyState::Remove
+                },
+                state.atoms._NET_WM_STATE_ABOVE,
+                xproto::AtomEnum::NONE.into(),
+            )?;
+            self.flush()
+        })
+        .log_err();
+    }
+
+    fn set_on_all_workspaces(&self, on_all_workspaces: bool) {
+        let state = self.0.state.borrow();
+        maybe!({
+            self.set_wm_hints(
+                || "X11 SendEvent to make a window sticky failed.",
+                if on_all_workspaces {
+                    WmHintPropertyState::Add
+                } else {
+                    WmHintPropertyState::Remove
+                },
+                state.atoms._NET_WM_STATE_STICKY,
+                xproto::AtomEnum::NONE.into(),
+            )?;
+
+            // Most window managers put windows on every workspace through _NET_WM_DESKTOP rather
+            // than the sticky state, which is about the viewport.
+            let desktop = if on_all_workspaces {
+                u32::MAX
+            } else {
+                get_reply(
+                    || "X11 GetProperty for _NET_CURRENT_DESKTOP failed.",
+                    self.0.xcb.get_property(
+                        false,
+                        state.x_root_window,
+                        state.atoms._NET_CURRENT_DESKTOP,
+                        xproto::AtomEnum::CARDINAL,
+                        0,
+                        1,
+                    ),
+                )?
+                .value32()
+                .and_then(|mut value| value.next())
+                .unwrap_or(0)
+            };
+            let message = ClientMessageEvent::new(
+                32,
+                self.0.x_window,
+                state.atoms._NET_WM_DESKTOP,
+                [desktop, 1, 0, 0, 0],
+            );
+            check_reply(
+                || "X11 SendEvent for _NET_WM_DESKTOP failed.",
+                self.0.xcb.send_event(
+                    false,
+                    state.x_root_window,
+                    EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+                    message,
+                ),
+            )?;
+            self.flush()
+        })
+        .log_err();
+    }
+
     fn request_decorations(&self, mut decorations: crate::WindowDecorations) {
         let mut state = self.0.state.borrow_mut();
 
@@ -1571,6 +2172,20 @@ impl PlatformWindow for X11Window {
         client.update_ime_position(bounds);
     }
 
+    fn is_accessibility_active(&self) -> bool {
+        self.0.state.borrow().accessibility.is_active()
+    }
+
+    fn update_accessibility_tree(&self, tree: AccessibilityTree) {
+        let mut state = self.0.state.borrow_mut();
+        let scale_factor = state.scale_factor;
+        state.accessibility.update(tree, scale_factor);
+    }
+
+    fn on_accessibility_request(&self, callback: Box<dyn FnMut(AccessibilityRequest)>) {
+        self.0.state.borrow().accessibility.on_request(callback);
+    }
+
     fn gpu_specs(&self) -> Option<GpuSpecs> {
         self.0.state.borrow().renderer.gpu_specs().into()
     }
@@ -1,11 +1,12 @@
-use std::default::Default;
+use std::{default::Default, ops::Range};
 
 use x11rb::protocol::{xproto, Event};
 use xim::{AHashMap, AttributeName, Client, ClientError, ClientHandler, InputStyle};
 
 pub enum XimCallbackEvent {
     XimXEvent(x11rb::protocol::Event),
-    XimPreeditEvent(xproto::Window, String),
+    /// The preedit text, with the UTF-16 range of its caret.
+    XimPreeditEvent(xproto::Window, String, Option<Range<usize>>),
     XimCommitEvent(xproto::Window, String),
 }
 
@@ -110,7 +111,7 @@ impl<C: Client<XEvent = xproto::KeyPressEvent>> ClientHandler<C> for XimHandler
         _client: &mut C,
         _input_method_id: u16,
         _input_context_id: u16,
-        _caret: i32,
+        caret: i32,
         _chg_first: i32,
         _chg_len: i32,
         _status: xim::PreeditDrawStatus,
@@ -123,10 +124,19 @@ impl<C: Client<XEvent = xproto::KeyPressEvent>> ClientHandler<C> for XimHandler
         // XIMVisibleToForward: 64, XIMVisibleToBackward: 128, XIMVisibleCenter: 256: text align position
         // XIMPrimary, XIMHighlight, XIMSecondary, XIMTertiary are not specified,
         // but interchangeable as above
-        // Currently there's no way to support these.
+        // Currently there's no way to support these, only the caret is passed on.
+        let caret = usize::try_from(caret).ok().map(|caret| {
+            let offset = preedit_string
+                .chars()
+                .take(caret)
+                .map(char::len_utf16)
+                .sum::<usize>();
+            offset..offset
+        });
         self.last_callback_event = Some(XimCallbackEvent::XimPreeditEvent(
             self.window,
             String::from(preedit_string),
+            caret,
         ));
         Ok(())
     }
@@ -2,15 +2,26 @@
 //!
 //! This module uses the [ashpd] crate
 
-use ashpd::desktop::settings::{ColorScheme, Settings};
+use ashpd::desktop::{
+    settings::{ColorScheme, Settings},
+    Color,
+};
 use calloop::channel::Channel;
 use calloop::{EventSource, Poll, PostAction, Readiness, Token, TokenFactory};
 use smol::stream::StreamExt;
 
-use crate::{BackgroundExecutor, WindowAppearance};
+use crate::{BackgroundExecutor, Rgba, WindowAppearance};
+
+const APPEARANCE_NAMESPACE: &str = "org.freedesktop.appearance";
+const REDUCED_MOTION_KEY: &str = "reduced-motion";
+/// The value of the `reduced-motion` key when the user asked for less motion, as opposed to `0`
+/// for no preference.
+const REDUCED_MOTION_REDUCE: u32 = 1;
 
 pub enum Event {
     WindowAppearance(WindowAppearance),
+    AccentColor(Option<Rgba>),
+    ReduceMotion(bool),
     #[cfg_attr(feature = "x11", allow(dead_code))]
     CursorTheme(String),
     #[cfg_attr(feature = "x11", allow(dead_code))]
@@ -36,6 +47,23 @@ impl XDPEventSource {
                         initial_appearance,
                     )))?;
                 }
+                if let Ok(initial_accent_color) = settings.accent_color().await {
+                    sender.send(Event::AccentColor(accent_color_from_native(
+                        initial_accent_color,
+                    )))?;
+                }
+                // Portals that predate the `reduced-motion` key expose GNOME's setting instead.
+                if let Ok(reduced_motion) = settings
+                    .read::<u32>(APPEARANCE_NAMESPACE, REDUCED_MOTION_KEY)
+                    .await
+                {
+                    sender.send(Event::ReduceMotion(reduced_motion == REDUCED_MOTION_REDUCE))?;
+                } else if let Ok(enable_animations) = settings
+                    .read::<bool>("org.gnome.desktop.interface", "enable-animations")
+                    .await
+                {
+                    sender.send(Event::ReduceMotion(!enable_animations))?;
+                }
                 if let Ok(initial_theme) = settings
                     .read::<String>("org.gnome.desktop.interface", "cursor-theme")
                     .await
@@ -89,6 +117,61 @@ impl XDPEventSource {
                         .detach();
                 }
 
+                if let Ok(mut reduced_motion_changed) = settings
+                    .receive_setting_changed_with_args::<u32>(
+                        APPEARANCE_NAMESPACE,
+                        REDUCED_MOTION_KEY,
+                    )
+                    .await
+                {
+                    let sender = sender.clone();
+                    background
+                        .spawn(async move {
+                            while let Some(reduced_motion) = reduced_motion_changed.next().await {
+                                let reduced_motion = reduced_motion?;
+                                sender.send(Event::ReduceMotion(
+                                    reduced_motion == REDUCED_MOTION_REDUCE,
+                                ))?;
+                            }
+                            anyhow::Ok(())
+                        })
+                        .detach();
+                }
+
+                if let Ok(mut enable_animations_changed) = settings
+                    .receive_setting_changed_with_args::<bool>(
+                        "org.gnome.desktop.interface",
+                        "enable-animations",
+                    )
+                    .await
+                {
+                    let sender = sender.clone();
+                    background
+                        .spawn(async move {
+                            while let Some(enable_animations) =
+                                enable_animations_changed.next().await
+                            {
+                                let enable_animations = enable_animations?;
+                                sender.send(Event::ReduceMotion(!enable_animations))?;
+                            }
+                            anyhow::Ok(())
+                        })
+                        .detach();
+                }
+
+                if let Ok(mut accent_color_changed) = settings.receive_accent_color_changed().await
+                {
+                    let sender = sender.clone();
+                    background
+                        .spawn(async move {
+                            while let Some(color) = accent_color_changed.next().await {
+                                sender.send(Event::AccentColor(accent_color_from_native(color)))?;
+                            }
+                            anyhow::Ok(())
+                        })
+                        .detach();
+                }
+
                 let mut appearance_changed = settings.receive_color_scheme_changed().await?;
                 while let Some(scheme) = appearance_changed.next().await {
                     sender.send(Event::WindowAppearance(WindowAppearance::from_native(
@@ -169,3 +252,18 @@ impl WindowAppearance {
         *self = Self::from_native(cs);
     }
 }
+
+/// The portal reports components out of the 0..=1 range when the user didn't pick an accent
+/// color.
+fn accent_color_from_native(color: Color) -> Option<Rgba> {
+    let (r, g, b) = (color.red(), color.green(), color.blue());
+    [r, g, b]
+        .iter()
+        .all(|component| (0.0..=1.0).contains(component))
+        .then(|| Rgba {
+            r: r as f32,
+            g: g as f32,
+            b: b as f32,
+            a: 1.,
+        })
+}
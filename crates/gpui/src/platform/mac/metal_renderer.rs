@@ -464,6 +464,10 @@ impl MetalRenderer {
                     viewport_size,
                     command_encoder,
                 ),
+                // Custom shaders are written in WGSL, which only the Blade renderer can compile.
+                PrimitiveBatch::Shaders(_) => true,
+                // Backdrop blurs and path clips are only implemented by the Blade renderer.
+                PrimitiveBatch::Blurs(_) | PrimitiveBatch::PathClips(_) => true,
             };
 
             if !ok {
@@ -8,7 +8,7 @@ use crate::{
     ClipboardString, CursorStyle, ForegroundExecutor, Image, ImageFormat, Keymap, MacDispatcher,
     MacDisplay, MacWindow, Menu, MenuItem, PathPromptOptions, Platform, PlatformDisplay,
     PlatformTextSystem, PlatformWindow, Result, ScreenCaptureSource, SemanticVersion, Task,
-    WindowAppearance, WindowParams,
+    WindowAppearance, WindowCreationError, WindowParams,
 };
 use anyhow::{anyhow, Context as _};
 use block::ConcreteBlock;
@@ -571,7 +571,7 @@ impl Platform for MacPlatform {
         &self,
         handle: AnyWindowHandle,
         options: WindowParams,
-    ) -> Result<Box<dyn PlatformWindow>> {
+    ) -> Result<Box<dyn PlatformWindow>, WindowCreationError> {
         let renderer_context = self.0.lock().renderer_context.clone();
         Ok(Box::new(MacWindow::open(
             handle,
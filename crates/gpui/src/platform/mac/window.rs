@@ -1,11 +1,11 @@
 use super::{ns_string, renderer, MacDisplay, NSRange, NSStringExt};
 use crate::{
     platform::PlatformInputHandler, point, px, size, AnyWindowHandle, Bounds, DisplayLink,
-    ExternalPaths, FileDropEvent, ForegroundExecutor, KeyDownEvent, Keystroke, Modifiers,
-    ModifiersChangedEvent, MouseButton, MouseDownEvent, MouseMoveEvent, MouseUpEvent, Pixels,
-    PlatformAtlas, PlatformDisplay, PlatformInput, PlatformWindow, Point, PromptLevel,
-    RequestFrameOptions, ScaledPixels, Size, Timer, WindowAppearance, WindowBackgroundAppearance,
-    WindowBounds, WindowKind, WindowParams,
+    ExternalPaths, FileDropEvent, ForegroundExecutor, KeyDownEvent, KeyboardInteractivity,
+    Keystroke, Layer, Modifiers, ModifiersChangedEvent, MouseButton, MouseDownEvent,
+    MouseMoveEvent, MouseUpEvent, Pixels, PlatformAtlas, PlatformDisplay, PlatformInput,
+    PlatformWindow, Point, PromptLevel, RequestFrameOptions, ScaledPixels, Size, Timer,
+    WindowAppearance, WindowBackgroundAppearance, WindowBounds, WindowKind, WindowParams,
 };
 use block::ConcreteBlock;
 use cocoa::{
@@ -59,8 +59,12 @@ const NSWindowStyleMaskNonactivatingPanel: NSWindowStyleMask =
 #[allow(non_upper_case_globals)]
 const NSNormalWindowLevel: NSInteger = 0;
 #[allow(non_upper_case_globals)]
+const NSStatusWindowLevel: NSInteger = 25;
+#[allow(non_upper_case_globals)]
 const NSPopUpWindowLevel: NSInteger = 101;
 #[allow(non_upper_case_globals)]
+const kCGDesktopWindowLevel: NSInteger = i32::MIN as NSInteger + 21;
+#[allow(non_upper_case_globals)]
 const NSTrackingMouseEnteredAndExited: NSUInteger = 0x01;
 #[allow(non_upper_case_globals)]
 const NSTrackingMouseMoved: NSUInteger = 0x02;
@@ -500,6 +504,7 @@ impl MacWindow {
             show,
             display_id,
             window_min_size,
+            popup_anchor: _,
         }: WindowParams,
         executor: ForegroundExecutor,
         renderer_context: renderer::Context,
@@ -528,6 +533,11 @@ impl MacWindow {
                     style_mask |= NSWindowStyleMaskNonactivatingPanel;
                     msg_send![PANEL_CLASS, alloc]
                 }
+                WindowKind::LayerShell(_) => {
+                    style_mask = NSWindowStyleMask::NSBorderlessWindowMask
+                        | NSWindowStyleMaskNonactivatingPanel;
+                    msg_send![PANEL_CLASS, alloc]
+                }
             };
 
             let display = display_id
@@ -555,6 +565,30 @@ impl MacWindow {
                 NSScreen::frame(screen)
             });
 
+            // There's no layer shell, so place the window where the compositor would have.
+            let bounds = match &kind {
+                WindowKind::LayerShell(settings) => {
+                    // The menu bar takes up the space between the top of the screen and its
+                    // visible frame, which is empty while it's hidden.
+                    let menu_bar_height = if settings.avoid_menu_bar {
+                        let visible_frame = NSScreen::visibleFrame(target_screen);
+                        let height = (screen_frame.origin.y + screen_frame.size.height)
+                            - (visible_frame.origin.y + visible_frame.size.height);
+                        px(height.max(0.) as f32)
+                    } else {
+                        Pixels::ZERO
+                    };
+                    let display_size = display.bounds().size;
+                    settings.bounds_within(
+                        bounds.size,
+                        Bounds {
+                            origin: point(Pixels::ZERO, menu_bar_height),
+                            size: size(display_size.width, display_size.height - menu_bar_height),
+                        },
+                    )
+                }
+                _ => bounds,
+            };
             let window_rect = NSRect::new(
                 NSPoint::new(
                     screen_frame.origin.x + bounds.origin.x.0 as f64,
@@ -699,8 +733,39 @@ impl MacWindow {
                         NSWindowCollectionBehavior::NSWindowCollectionBehaviorFullScreenAuxiliary
                     );
                 }
+                WindowKind::LayerShell(ref settings) => {
+                    let level = match settings.layer {
+                        Layer::Background => kCGDesktopWindowLevel,
+                        Layer::Bottom => NSNormalWindowLevel - 1,
+                        Layer::Top => NSStatusWindowLevel,
+                        Layer::Overlay => NSPopUpWindowLevel,
+                    };
+                    native_window.setLevel_(level);
+                    native_window.setAcceptsMouseMovedEvents_(YES);
+                    let _: () = msg_send![
+                        native_window,
+                        setIgnoresMouseEvents: !settings.pointer_interactivity as BOOL
+                    ];
+                    // Like the overlay layer, which stays above fullscreen windows, let overlays
+                    // show up in fullscreen spaces too.
+                    let mut collection_behavior =
+                        NSWindowCollectionBehavior::NSWindowCollectionBehaviorCanJoinAllSpaces
+                            | NSWindowCollectionBehavior::NSWindowCollectionBehaviorStationary
+                            | NSWindowCollectionBehavior::NSWindowCollectionBehaviorIgnoresCycle;
+                    if settings.layer == Layer::Overlay {
+                        collection_behavior |=
+                            NSWindowCollectionBehavior::NSWindowCollectionBehaviorFullScreenAuxiliary;
+                    }
+                    native_window.setCollectionBehavior_(collection_behavior);
+                }
             }
 
+            let focus = focus
+                && !matches!(
+                    &kind,
+                    WindowKind::LayerShell(settings)
+                        if settings.keyboard_interactivity == KeyboardInteractivity::None
+                );
             if focus && show {
                 native_window.makeKeyAndOrderFront_(nil);
             } else if show {
@@ -972,6 +1037,10 @@ impl PlatformWindow for MacWindow {
 
     fn set_app_id(&mut self, _app_id: &str) {}
 
+    fn is_background_blur_supported(&self) -> bool {
+        true
+    }
+
     fn set_background_appearance(&self, background_appearance: WindowBackgroundAppearance) {
         let mut this = self.0.as_ref().lock();
         this.renderer
@@ -1033,6 +1102,24 @@ impl PlatformWindow for MacWindow {
         }
     }
 
+    fn set_on_all_workspaces(&self, on_all_workspaces: bool) {
+        let window = self.0.lock().native_window;
+        unsafe {
+            let mut collection_behavior = window.collectionBehavior();
+            collection_behavior.set(
+                NSWindowCollectionBehavior::NSWindowCollectionBehaviorCanJoinAllSpaces,
+                on_all_workspaces,
+            );
+            // Spaces reject windows that both join all spaces and move to the active one.
+            if on_all_workspaces {
+                collection_behavior.remove(
+                    NSWindowCollectionBehavior::NSWindowCollectionBehaviorMoveToActiveSpace,
+                );
+            }
+            window.setCollectionBehavior_(collection_behavior);
+        }
+    }
+
     fn zoom(&self) {
         let this = self.0.lock();
         let window = this.native_window;
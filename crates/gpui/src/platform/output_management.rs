@@ -0,0 +1,117 @@
+//! The outputs known to the compositor and their configuration, for building display settings.
+//! These are backed by wlr-output-management on Wayland, other platforms don't list any.
+
+use crate::{DevicePixels, Pixels, Point, SharedString, Size};
+
+/// A display connector known to the compositor, whether an output is enabled on it or not.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutputHead {
+    /// The name of the connector, like `DP-1`. It's unique among the heads and stays the same
+    /// when the display is reconnected.
+    pub name: SharedString,
+    /// A human readable description of the display.
+    pub description: SharedString,
+    /// The manufacturer of the display, if known.
+    pub make: Option<SharedString>,
+    /// The model of the display, if known.
+    pub model: Option<SharedString>,
+    /// The serial number of the display, if known.
+    pub serial_number: Option<SharedString>,
+    /// The physical size of the display in millimeters, if known.
+    pub physical_size: Option<Size<u32>>,
+    /// Whether an output is enabled on the head. The remaining fields only describe the
+    /// current configuration when it is.
+    pub enabled: bool,
+    /// The modes supported by the display.
+    pub modes: Vec<OutputMode>,
+    /// The index of the current mode in [`OutputHead::modes`], `None` for custom modes.
+    pub current_mode: Option<usize>,
+    /// The position of the output in the global compositor space.
+    pub position: Point<Pixels>,
+    /// The scale of the output.
+    pub scale: f32,
+}
+
+/// A mode of an [`OutputHead`], or the current mode of a display, see [`PlatformDisplay::mode`].
+///
+/// [`PlatformDisplay::mode`]: crate::PlatformDisplay::mode
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct OutputMode {
+    /// The resolution of the mode.
+    pub size: Size<DevicePixels>,
+    /// The refresh rate in mHz, if known.
+    pub refresh_rate: Option<u32>,
+    /// Whether the display prefers this mode.
+    pub preferred: bool,
+}
+
+/// Changes to the outputs, applied with [`App::apply_output_configuration`].
+///
+/// [`App::apply_output_configuration`]: crate::App::apply_output_configuration
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OutputConfiguration {
+    /// The changes to each head. Heads missing from the list keep their configuration.
+    pub heads: Vec<OutputHeadConfiguration>,
+}
+
+impl OutputConfiguration {
+    /// Adds the changes to a head.
+    pub fn head(mut self, head: OutputHeadConfiguration) -> Self {
+        self.heads.push(head);
+        self
+    }
+}
+
+/// Changes to an [`OutputHead`]. Settings left unset keep their current value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutputHeadConfiguration {
+    /// The [`OutputHead::name`] of the head.
+    pub name: SharedString,
+    /// Whether an output is enabled on the head.
+    pub enabled: bool,
+    /// The index of the mode in [`OutputHead::modes`].
+    pub mode: Option<usize>,
+    /// The position of the output in the global compositor space.
+    pub position: Option<Point<Pixels>>,
+    /// The scale of the output.
+    pub scale: Option<f32>,
+}
+
+impl OutputHeadConfiguration {
+    /// Enables the head with the given name, keeping its settings.
+    pub fn enable(name: impl Into<SharedString>) -> Self {
+        Self {
+            name: name.into(),
+            enabled: true,
+            mode: None,
+            position: None,
+            scale: None,
+        }
+    }
+
+    /// Disables the head with the given name.
+    pub fn disable(name: impl Into<SharedString>) -> Self {
+        Self {
+            enabled: false,
+            ..Self::enable(name)
+        }
+    }
+
+    /// Switches the head to the mode at the given index in [`OutputHead::modes`].
+    pub fn mode(mut self, mode: usize) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Moves the output to the given position.
+    pub fn position(mut self, position: Point<Pixels>) -> Self {
+        self.position = Some(position);
+        self
+    }
+
+    /// Changes the scale of the output.
+    pub fn scale(mut self, scale: f32) -> Self {
+        self.scale = Some(scale);
+        self
+    }
+}
@@ -1,7 +1,8 @@
 use crate::{
     px, size, AnyWindowHandle, BackgroundExecutor, ClipboardItem, CursorStyle, ForegroundExecutor,
     Keymap, Platform, PlatformDisplay, PlatformTextSystem, ScreenCaptureFrame, ScreenCaptureSource,
-    ScreenCaptureStream, Task, TestDisplay, TestWindow, WindowAppearance, WindowParams,
+    ScreenCaptureStream, Task, TestDisplay, TestWindow, WindowAppearance, WindowCreationError,
+    WindowParams,
 };
 use anyhow::Result;
 use collections::VecDeque;
@@ -296,7 +297,7 @@ impl Platform for TestPlatform {
         &self,
         handle: AnyWindowHandle,
         params: WindowParams,
-    ) -> anyhow::Result<Box<dyn crate::PlatformWindow>> {
+    ) -> Result<Box<dyn crate::PlatformWindow>, WindowCreationError> {
         let window = TestWindow::new(
             handle,
             params,
@@ -1,8 +1,10 @@
 use crate::{
-    AnyWindowHandle, AtlasKey, AtlasTextureId, AtlasTile, Bounds, DispatchEventResult, GpuSpecs,
-    Pixels, PlatformAtlas, PlatformDisplay, PlatformInput, PlatformInputHandler, PlatformWindow,
-    Point, RequestFrameOptions, ScaledPixels, Size, TestPlatform, TileId, WindowAppearance,
-    WindowBackgroundAppearance, WindowBounds, WindowParams,
+    inset_exclusive_zone, inset_margin, layer_surface_size, AnyWindowHandle, AtlasKey,
+    AtlasTextureId, AtlasTile, Bounds, CompositorScale, Configure, Decorations,
+    DispatchEventResult, GpuSpecs, Pixels, PlatformAtlas, PlatformDisplay, PlatformInput,
+    PlatformInputHandler, PlatformWindow, Point, RequestFrameOptions, ScaledPixels, Size,
+    TestPlatform, TileId, Tiling, ToplevelState, WindowAppearance, WindowBackgroundAppearance,
+    WindowBounds, WindowControls, WindowDecorations, WindowKind, WindowParams,
 };
 use collections::HashMap;
 use parking_lot::Mutex;
@@ -21,13 +23,43 @@ pub(crate) struct TestWindowState {
     platform: Weak<TestPlatform>,
     sprite_atlas: Arc<dyn PlatformAtlas>,
     pub(crate) should_close_handler: Option<Box<dyn FnMut() -> bool>>,
+    close_callback: Option<Box<dyn FnOnce()>>,
     input_callback: Option<Box<dyn FnMut(PlatformInput) -> DispatchEventResult>>,
     active_status_change_callback: Option<Box<dyn FnMut(bool)>>,
     hover_status_change_callback: Option<Box<dyn FnMut(bool)>>,
     resize_callback: Option<Box<dyn FnMut(Size<Pixels>, f32)>>,
     moved_callback: Option<Box<dyn FnMut()>>,
+    appearance_changed_callback: Option<Box<dyn FnMut()>>,
     input_handler: Option<PlatformInputHandler>,
-    is_fullscreen: bool,
+    pub(crate) kind: WindowKind,
+    toplevel: ToplevelState,
+    scale_factor: f32,
+    /// The scales reported by the simulated compositor, which the window renders at unless its
+    /// scale is overridden.
+    compositor_scale: CompositorScale,
+    scale_override: Option<f32>,
+    outputs: Vec<(u32, i32)>,
+    decorations: WindowDecorations,
+    window_controls: WindowControls,
+    /// The space kept around the body of a layer shell window for its shadow.
+    layer_shell_inset: Pixels,
+    layer_shell_margin: (Pixels, Pixels, Pixels, Pixels),
+    layer_shell_exclusive_zone: Pixels,
+}
+
+/// A configure event, as a Wayland compositor sends it to an xdg toplevel or a layer surface.
+/// Simulated on a test window with `simulate_window_configure`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SimulatedConfigure {
+    /// The size picked by the compositor. `None`, or a zero width or height, leaves that
+    /// dimension to the window.
+    pub size: Option<Size<Pixels>>,
+    /// Whether the window is maximized.
+    pub maximized: bool,
+    /// Whether the window is fullscreen.
+    pub fullscreen: bool,
+    /// The edges the window is tiled on, all of them when maximized or fullscreen.
+    pub tiling: Tiling,
 }
 
 #[derive(Clone)]
@@ -56,6 +88,15 @@ impl TestWindow {
         platform: Weak<TestPlatform>,
         display: Rc<dyn PlatformDisplay>,
     ) -> Self {
+        let (layer_shell_inset, layer_shell_margin, layer_shell_exclusive_zone) = match &params.kind
+        {
+            WindowKind::LayerShell(settings) => (
+                settings.shadow_inset(),
+                settings.surface_margin(settings.margin.unwrap_or_default()),
+                settings.surface_exclusive_zone().unwrap_or_default(),
+            ),
+            _ => Default::default(),
+        };
         Self(Rc::new(Mutex::new(TestWindowState {
             bounds: params.bounds,
             display,
@@ -65,13 +106,28 @@ impl TestWindow {
             title: Default::default(),
             edited: false,
             should_close_handler: None,
+            close_callback: None,
             input_callback: None,
             active_status_change_callback: None,
             hover_status_change_callback: None,
             resize_callback: None,
             moved_callback: None,
+            appearance_changed_callback: None,
             input_handler: None,
-            is_fullscreen: false,
+            kind: params.kind,
+            toplevel: ToplevelState::new(params.bounds),
+            scale_factor: 2.0,
+            compositor_scale: CompositorScale {
+                output: Some(2),
+                ..Default::default()
+            },
+            scale_override: None,
+            outputs: Vec::new(),
+            decorations: WindowDecorations::Server,
+            window_controls: WindowControls::default(),
+            layer_shell_inset,
+            layer_shell_margin,
+            layer_shell_exclusive_zone,
         })))
     }
 
@@ -87,6 +143,140 @@ impl TestWindow {
         self.0.lock().resize_callback = Some(callback);
     }
 
+    /// Applies a configure event the way the Wayland window does: the window takes the
+    /// configured size, keeps its own size for the dimensions left to it, and gets back its
+    /// previous size when unmaximized.
+    pub fn simulate_configure(&mut self, configure: SimulatedConfigure) {
+        let mut lock = self.0.lock();
+        let size = if matches!(lock.kind, WindowKind::LayerShell(_)) {
+            Some(layer_surface_size(
+                lock.bounds.size,
+                configure.size.unwrap_or_default(),
+            ))
+        } else {
+            lock.toplevel.configure(
+                Configure {
+                    size: configure.size,
+                    fullscreen: configure.fullscreen,
+                    maximized: configure.maximized,
+                    tiling: configure.tiling,
+                },
+                None,
+            )
+        };
+        drop(lock);
+        self.set_size_and_scale(size, None);
+    }
+
+    /// Simulates the compositor picking a preferred scale for the window, like with
+    /// fractional scaling. It takes precedence over the scale of the outputs the window is on.
+    pub fn simulate_scale_factor(&mut self, scale_factor: f32) {
+        self.0.lock().compositor_scale.fractional = Some(scale_factor);
+        self.update_scale();
+    }
+
+    /// Simulates the window entering an output with the given scale. Without a preferred scale,
+    /// the window takes the largest scale of the outputs it's on.
+    pub fn simulate_output_enter(&mut self, output_id: u32, scale: i32) {
+        let mut lock = self.0.lock();
+        lock.outputs.retain(|(id, _)| *id != output_id);
+        lock.outputs.push((output_id, scale));
+        drop(lock);
+        self.update_output_scale();
+    }
+
+    /// Simulates the window leaving the given output.
+    pub fn simulate_output_leave(&mut self, output_id: u32) {
+        self.0.lock().outputs.retain(|(id, _)| *id != output_id);
+        self.update_output_scale();
+    }
+
+    /// Simulates the compositor switching the window between server and client side
+    /// decorations.
+    pub fn simulate_decorations(&mut self, decorations: WindowDecorations) {
+        self.0.lock().decorations = decorations;
+        self.simulate_appearance_changed();
+    }
+
+    /// Simulates the compositor announcing which window controls it supports.
+    pub fn simulate_window_controls(&mut self, window_controls: WindowControls) {
+        self.0.lock().window_controls = window_controls;
+        self.simulate_appearance_changed();
+    }
+
+    /// Simulates the compositor closing the window, like it does with a layer surface when its
+    /// output goes away. Unlike the user closing the window, the app can't refuse.
+    pub fn simulate_closed(&mut self) {
+        let callback = self.0.lock().close_callback.take();
+        if let Some(callback) = callback {
+            callback();
+        }
+    }
+
+    /// The margin last requested for the window's layer surface, which moves the surface
+    /// outwards by its shadow like on Wayland.
+    pub fn layer_shell_margin(&self) -> (Pixels, Pixels, Pixels, Pixels) {
+        self.0.lock().layer_shell_margin
+    }
+
+    /// The exclusive zone last requested for the window's layer surface, which covers its
+    /// shadow like on Wayland.
+    pub fn layer_shell_exclusive_zone(&self) -> Pixels {
+        self.0.lock().layer_shell_exclusive_zone
+    }
+
+    fn simulate_appearance_changed(&self) {
+        let mut lock = self.0.lock();
+        let Some(mut callback) = lock.appearance_changed_callback.take() else {
+            return;
+        };
+        drop(lock);
+        callback();
+        self.0.lock().appearance_changed_callback = Some(callback);
+    }
+
+    fn update_output_scale(&mut self) {
+        let mut lock = self.0.lock();
+        // Like the Wayland window, which takes the largest scale of its outputs.
+        let scale = lock
+            .outputs
+            .iter()
+            .map(|(_, scale)| *scale)
+            .fold(1, i32::max);
+        lock.compositor_scale.output = Some(scale);
+        drop(lock);
+        self.update_scale();
+    }
+
+    fn update_scale(&self) {
+        let lock = self.0.lock();
+        let scale_factor = lock.compositor_scale.resolve(lock.scale_override);
+        drop(lock);
+        self.set_size_and_scale(None, Some(scale_factor));
+    }
+
+    fn set_size_and_scale(&self, size: Option<Size<Pixels>>, scale_factor: Option<f32>) {
+        let mut lock = self.0.lock();
+        if size.map_or(true, |size| size == lock.bounds.size)
+            && scale_factor.map_or(true, |scale_factor| scale_factor == lock.scale_factor)
+        {
+            return;
+        }
+        if let Some(size) = size {
+            lock.bounds.size = size;
+        }
+        if let Some(scale_factor) = scale_factor {
+            lock.scale_factor = scale_factor;
+        }
+        let (size, scale_factor) = (lock.bounds.size, lock.scale_factor);
+        let Some(mut callback) = lock.resize_callback.take() else {
+            return;
+        };
+        drop(lock);
+        callback(size, scale_factor);
+        self.0.lock().resize_callback = Some(callback);
+    }
+
     pub(crate) fn simulate_active_status_change(&self, active: bool) {
         let mut lock = self.0.lock();
         let Some(mut callback) = lock.active_status_change_callback.take() else {
@@ -97,6 +287,16 @@ impl TestWindow {
         self.0.lock().active_status_change_callback = Some(callback);
     }
 
+    pub(crate) fn simulate_hover_status_change(&self, hovered: bool) {
+        let mut lock = self.0.lock();
+        let Some(mut callback) = lock.hover_status_change_callback.take() else {
+            return;
+        };
+        drop(lock);
+        callback(hovered);
+        self.0.lock().hover_status_change_callback = Some(callback);
+    }
+
     pub fn simulate_input(&mut self, event: PlatformInput) -> bool {
         let mut lock = self.0.lock();
         let Some(mut callback) = lock.input_callback.take() else {
@@ -115,11 +315,18 @@ impl PlatformWindow for TestWindow {
     }
 
     fn window_bounds(&self) -> WindowBounds {
-        WindowBounds::Windowed(self.bounds())
+        let lock = self.0.lock();
+        if lock.toplevel.fullscreen {
+            WindowBounds::Fullscreen(lock.toplevel.windowed_bounds)
+        } else if lock.toplevel.maximized {
+            WindowBounds::Maximized(lock.toplevel.windowed_bounds)
+        } else {
+            WindowBounds::Windowed(lock.bounds)
+        }
     }
 
     fn is_maximized(&self) -> bool {
-        false
+        self.0.lock().toplevel.maximized
     }
 
     fn content_size(&self) -> Size<Pixels> {
@@ -127,7 +334,11 @@ impl PlatformWindow for TestWindow {
     }
 
     fn scale_factor(&self) -> f32 {
-        2.0
+        self.0.lock().scale_factor
+    }
+
+    fn buffer_scale(&self) -> u32 {
+        self.0.lock().compositor_scale.buffer_scale()
     }
 
     fn appearance(&self) -> WindowAppearance {
@@ -214,11 +425,11 @@ impl PlatformWindow for TestWindow {
 
     fn toggle_fullscreen(&self) {
         let mut lock = self.0.lock();
-        lock.is_fullscreen = !lock.is_fullscreen;
+        lock.toplevel.fullscreen = !lock.toplevel.fullscreen;
     }
 
     fn is_fullscreen(&self) -> bool {
-        self.0.lock().is_fullscreen
+        self.0.lock().toplevel.fullscreen
     }
 
     fn on_request_frame(&self, _callback: Box<dyn FnMut(RequestFrameOptions)>) {}
@@ -247,9 +458,13 @@ impl PlatformWindow for TestWindow {
         self.0.lock().should_close_handler = Some(callback);
     }
 
-    fn on_close(&self, _callback: Box<dyn FnOnce()>) {}
+    fn on_close(&self, callback: Box<dyn FnOnce()>) {
+        self.0.lock().close_callback = Some(callback);
+    }
 
-    fn on_appearance_changed(&self, _callback: Box<dyn FnMut()>) {}
+    fn on_appearance_changed(&self, callback: Box<dyn FnMut()>) {
+        self.0.lock().appearance_changed_callback = Some(callback);
+    }
 
     fn draw(&self, _scene: &crate::Scene) {}
 
@@ -276,6 +491,37 @@ impl PlatformWindow for TestWindow {
 
     fn update_ime_position(&self, _bounds: Bounds<ScaledPixels>) {}
 
+    fn window_decorations(&self) -> Decorations {
+        let lock = self.0.lock();
+        match lock.decorations {
+            WindowDecorations::Server => Decorations::Server,
+            WindowDecorations::Client => Decorations::Client {
+                tiling: lock.toplevel.tiling,
+            },
+        }
+    }
+
+    fn window_controls(&self) -> WindowControls {
+        self.0.lock().window_controls
+    }
+
+    fn set_layer_shell_margin(&self, margin: (Pixels, Pixels, Pixels, Pixels)) {
+        let mut lock = self.0.lock();
+        lock.layer_shell_margin = inset_margin(margin, lock.layer_shell_inset);
+    }
+
+    fn set_scale_override(&self, scale: Option<f32>) {
+        let mut lock = self.0.lock();
+        lock.scale_override = scale;
+        lock.scale_factor = lock.compositor_scale.resolve(scale);
+    }
+
+    fn set_layer_shell_exclusive_zone(&self, exclusive_zone: Pixels) {
+        let mut lock = self.0.lock();
+        lock.layer_shell_exclusive_zone =
+            inset_exclusive_zone(exclusive_zone, lock.layer_shell_inset);
+    }
+
     fn gpu_specs(&self) -> Option<GpuSpecs> {
         None
     }
@@ -344,4 +590,469 @@ impl PlatformAtlas for TestAtlas {
         let mut state = self.0.lock();
         state.tiles.remove(key);
     }
+
+    fn usage(&self) -> crate::AtlasUsage {
+        crate::AtlasUsage {
+            tiles: self.0.lock().tiles.len(),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        self as gpui, canvas, point, px, size, Anchor, AnyWindowHandle, AppContext as _, AutoHide,
+        Bounds, Context, Corner, Decorations, Empty, IntoElement, KeyboardInteractivity,
+        LayerShellSettings, LayerShellShadow, Pixels, PopupAnchor, Render, SimulatedConfigure,
+        Styled as _, TestAppContext, Tiling, Window, WindowBounds, WindowDecorations, WindowKind,
+        WindowOptions, WindowScale,
+    };
+    use std::{
+        cell::{Cell, RefCell},
+        rc::Rc,
+        time::Duration,
+    };
+
+    fn open_window(kind: WindowKind, cx: &mut TestAppContext) -> AnyWindowHandle {
+        cx.update(|cx| {
+            cx.open_window(
+                WindowOptions {
+                    window_bounds: Some(WindowBounds::Windowed(Bounds {
+                        origin: point(px(0.), px(0.)),
+                        size: size(px(400.), px(300.)),
+                    })),
+                    kind,
+                    ..Default::default()
+                },
+                |_, cx| cx.new(|_| Empty),
+            )
+            .unwrap()
+            .into()
+        })
+    }
+
+    #[gpui::test]
+    fn test_configure_restores_size_when_unmaximized(cx: &mut TestAppContext) {
+        let window = open_window(WindowKind::Normal, cx);
+
+        cx.simulate_window_configure(
+            window,
+            SimulatedConfigure {
+                size: Some(size(px(1920.), px(1080.))),
+                maximized: true,
+                ..Default::default()
+            },
+        );
+        window
+            .update(cx, |_, window, _| {
+                assert!(window.is_maximized());
+                assert_eq!(window.viewport_size(), size(px(1920.), px(1080.)));
+            })
+            .unwrap();
+
+        cx.simulate_window_configure(window, SimulatedConfigure::default());
+        window
+            .update(cx, |_, window, _| {
+                assert!(!window.is_maximized());
+                assert_eq!(window.viewport_size(), size(px(400.), px(300.)));
+            })
+            .unwrap();
+    }
+
+    #[gpui::test]
+    fn test_configure_layer_shell_keeps_unset_dimensions(cx: &mut TestAppContext) {
+        let window = open_window(WindowKind::LayerShell(LayerShellSettings::default()), cx);
+
+        cx.simulate_window_configure(
+            window,
+            SimulatedConfigure {
+                size: Some(size(px(1920.), px(0.))),
+                ..Default::default()
+            },
+        );
+        window
+            .update(cx, |_, window, _| {
+                assert_eq!(window.viewport_size(), size(px(1920.), px(300.)));
+            })
+            .unwrap();
+    }
+
+    #[gpui::test]
+    fn test_closed_layer_shell_is_removed(cx: &mut TestAppContext) {
+        let window = open_window(WindowKind::LayerShell(LayerShellSettings::default()), cx);
+        window
+            .update(cx, |_, window, cx| {
+                // The compositor doesn't ask, so the window can't refuse.
+                window.on_window_should_close(cx, |_, _| false);
+            })
+            .unwrap();
+
+        cx.simulate_window_closed(window);
+        cx.run_until_parked();
+        assert!(cx.windows().is_empty());
+    }
+
+    #[gpui::test]
+    fn test_layer_shell_requests_reach_compositor(cx: &mut TestAppContext) {
+        let window = open_window(
+            WindowKind::LayerShell(LayerShellSettings {
+                anchor: Anchor::TOP | Anchor::LEFT | Anchor::RIGHT,
+                exclusive_zone: Some(px(30.)),
+                ..Default::default()
+            }),
+            cx,
+        );
+        assert_eq!(cx.window_layer_shell_exclusive_zone(window), px(30.));
+
+        window
+            .update(cx, |_, window, _| {
+                window.set_layer_shell_margin((px(-30.), px(0.), px(0.), px(0.)));
+                window.set_layer_shell_exclusive_zone(px(0.));
+            })
+            .unwrap();
+        assert_eq!(
+            cx.window_layer_shell_margin(window),
+            (px(-30.), px(0.), px(0.), px(0.))
+        );
+        assert_eq!(cx.window_layer_shell_exclusive_zone(window), px(0.));
+    }
+
+    #[gpui::test]
+    fn test_layer_shell_requests_include_shadow(cx: &mut TestAppContext) {
+        let window = open_window(
+            WindowKind::LayerShell(LayerShellSettings {
+                anchor: Anchor::TOP | Anchor::LEFT | Anchor::RIGHT,
+                exclusive_zone: Some(px(30.)),
+                margin: Some((px(4.), px(4.), px(4.), px(4.))),
+                // Keeps 12px + 2px around the body.
+                shadow: Some(LayerShellShadow::default()),
+                ..Default::default()
+            }),
+            cx,
+        );
+        assert_eq!(
+            cx.window_layer_shell_margin(window),
+            (px(-10.), px(-10.), px(-10.), px(-10.))
+        );
+        assert_eq!(cx.window_layer_shell_exclusive_zone(window), px(44.));
+
+        window
+            .update(cx, |_, window, _| {
+                window.set_layer_shell_margin((px(-30.), px(0.), px(0.), px(0.)));
+                window.set_layer_shell_exclusive_zone(px(-1.));
+            })
+            .unwrap();
+        assert_eq!(
+            cx.window_layer_shell_margin(window),
+            (px(-44.), px(-14.), px(-14.), px(-14.))
+        );
+        assert_eq!(cx.window_layer_shell_exclusive_zone(window), px(-1.));
+    }
+
+    #[gpui::test]
+    fn test_deferred_window_replays_calls_when_realized(cx: &mut TestAppContext) {
+        let window: AnyWindowHandle = cx
+            .update(|cx| {
+                cx.open_window(
+                    WindowOptions {
+                        kind: WindowKind::LayerShell(LayerShellSettings {
+                            exclusive_zone: Some(px(30.)),
+                            ..Default::default()
+                        }),
+                        deferred: true,
+                        ..Default::default()
+                    },
+                    |_, cx| cx.new(|_| Empty),
+                )
+            })
+            .unwrap()
+            .into();
+        window
+            .update(cx, |_, window, _| {
+                assert!(!window.is_realized());
+                window.set_window_title("Dock");
+            })
+            .unwrap();
+
+        window
+            .update(cx, |_, window, cx| {
+                window.realize(cx).unwrap();
+                assert!(window.is_realized());
+            })
+            .unwrap();
+        assert_eq!(cx.window_layer_shell_exclusive_zone(window), px(30.));
+        assert_eq!(
+            cx.test_window(window).0.lock().title.as_deref(),
+            Some("Dock")
+        );
+    }
+
+    #[gpui::test]
+    fn test_popups_of_bottom_bar_open_upwards(cx: &mut TestAppContext) {
+        let bar = open_window(
+            WindowKind::LayerShell(LayerShellSettings {
+                anchor: Anchor::BOTTOM | Anchor::LEFT | Anchor::RIGHT,
+                exclusive_zone: Some(px(300.)),
+                ..Default::default()
+            }),
+            cx,
+        );
+        let open_popup = |anchor: PopupAnchor, cx: &mut TestAppContext| {
+            bar.update(cx, |_, window, cx| {
+                let options = WindowOptions {
+                    window_bounds: Some(WindowBounds::Windowed(Bounds {
+                        origin: point(px(0.), px(0.)),
+                        size: size(px(200.), px(400.)),
+                    })),
+                    ..Default::default()
+                };
+                let popup = cx
+                    .open_popup_window(window, anchor, options, |_, cx| cx.new(|_| Empty))
+                    .unwrap();
+                let parent_origin = window.bounds().origin;
+                popup
+                    .update(cx, |_, window, _| window.bounds().origin - parent_origin)
+                    .unwrap()
+            })
+            .unwrap()
+        };
+        let button = Bounds {
+            origin: point(px(10.), px(10.)),
+            size: size(px(50.), px(20.)),
+        };
+
+        // Below the button, the popup would leave the display.
+        assert_eq!(
+            open_popup(PopupAnchor::new(button), cx),
+            point(px(10.), px(-390.))
+        );
+        // It can't open above the button at the left edge of the display either.
+        assert_eq!(
+            open_popup(
+                PopupAnchor::new(button)
+                    .attach(Corner::BottomRight)
+                    .anchor(Corner::TopRight),
+                cx
+            ),
+            point(px(10.), px(-390.))
+        );
+    }
+
+    #[gpui::test]
+    fn test_scale_follows_outputs_until_preferred(cx: &mut TestAppContext) {
+        let window = open_window(WindowKind::Normal, cx);
+        let scale_factor = |cx: &mut TestAppContext| {
+            window
+                .update(cx, |_, window, _| window.scale_factor())
+                .unwrap()
+        };
+
+        cx.simulate_window_output_enter(window, 1, 1);
+        assert_eq!(scale_factor(cx), 1.);
+        cx.simulate_window_output_enter(window, 2, 3);
+        assert_eq!(scale_factor(cx), 3.);
+        cx.simulate_window_output_leave(window, 2);
+        assert_eq!(scale_factor(cx), 1.);
+
+        cx.simulate_window_scale_factor(window, 1.5);
+        cx.simulate_window_output_enter(window, 2, 3);
+        assert_eq!(scale_factor(cx), 1.5);
+    }
+
+    #[gpui::test]
+    fn test_scale_override_ignores_compositor(cx: &mut TestAppContext) {
+        let window = open_window(WindowKind::Normal, cx);
+        let scale_factor = |cx: &mut TestAppContext| {
+            window
+                .update(cx, |_, window, _| window.scale_factor())
+                .unwrap()
+        };
+
+        window
+            .update(cx, |_, window, cx| window.set_scale_override(Some(1.), cx))
+            .unwrap();
+        assert_eq!(scale_factor(cx), 1.);
+        cx.simulate_window_scale_factor(window, 1.5);
+        assert_eq!(scale_factor(cx), 1.);
+
+        window
+            .update(cx, |_, window, cx| window.set_scale_override(None, cx))
+            .unwrap();
+        assert_eq!(scale_factor(cx), 1.5);
+    }
+
+    #[gpui::test]
+    fn test_scale_factor_changes_notify_observers(cx: &mut TestAppContext) {
+        let window = open_window(WindowKind::Normal, cx);
+        let scales = Rc::new(RefCell::new(Vec::new()));
+        window
+            .update(cx, |_, window, _| {
+                let scales = scales.clone();
+                window
+                    .on_scale_factor_changed(move |scale, _, _| scales.borrow_mut().push(scale))
+                    .detach();
+            })
+            .unwrap();
+
+        cx.simulate_window_resize(window, size(px(800.), px(600.)));
+        assert!(scales.borrow().is_empty());
+
+        cx.simulate_window_scale_factor(window, 1.5);
+        assert_eq!(
+            *scales.borrow(),
+            [WindowScale {
+                scale_factor: 1.5,
+                buffer_scale: 2,
+            }]
+        );
+    }
+
+    #[gpui::test]
+    fn test_client_decorations_report_tiling(cx: &mut TestAppContext) {
+        let window = open_window(WindowKind::Normal, cx);
+
+        cx.simulate_window_decorations(window, WindowDecorations::Client);
+        cx.simulate_window_configure(
+            window,
+            SimulatedConfigure {
+                tiling: Tiling {
+                    left: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+        window
+            .update(cx, |_, window, _| {
+                assert_eq!(
+                    window.window_decorations(),
+                    Decorations::Client {
+                        tiling: Tiling {
+                            left: true,
+                            ..Default::default()
+                        }
+                    }
+                );
+            })
+            .unwrap();
+    }
+
+    #[gpui::test]
+    fn test_on_demand_layer_shell_closes_on_focus_loss(cx: &mut TestAppContext) {
+        let window = open_window(
+            WindowKind::LayerShell(LayerShellSettings {
+                keyboard_interactivity: KeyboardInteractivity::OnDemand,
+                close_on_focus_loss: true,
+                ..Default::default()
+            }),
+            cx,
+        );
+        let focus_lost = Rc::new(Cell::new(false));
+        window
+            .update(cx, |_, window, _| {
+                let focus_lost = focus_lost.clone();
+                window
+                    .observe_keyboard_focus_lost(move |_, _| focus_lost.set(true))
+                    .detach();
+            })
+            .unwrap();
+
+        cx.test_window(window).simulate_active_status_change(true);
+        assert!(!focus_lost.get());
+        cx.test_window(window).simulate_active_status_change(false);
+        cx.run_until_parked();
+        assert!(focus_lost.get());
+        assert!(cx.windows().is_empty());
+    }
+
+    #[gpui::test]
+    fn test_auto_hide_reveals_on_hover(cx: &mut TestAppContext) {
+        let window = open_window(
+            WindowKind::LayerShell(LayerShellSettings {
+                anchor: Anchor::TOP | Anchor::LEFT | Anchor::RIGHT,
+                auto_hide: Some(AutoHide::default()),
+                ..Default::default()
+            }),
+            cx,
+        );
+        let changes = Rc::new(Cell::new(0));
+        window
+            .update(cx, |_, window, _| {
+                let changes = changes.clone();
+                window
+                    .observe_layer_shell_revealed(move |_, _| changes.set(changes.get() + 1))
+                    .detach();
+                assert!(!window.is_layer_shell_revealed());
+            })
+            .unwrap();
+
+        cx.test_window(window).simulate_hover_status_change(true);
+        assert!(window
+            .update(cx, |_, window, _| window.is_layer_shell_revealed())
+            .unwrap());
+        assert_eq!(changes.get(), 1);
+
+        // The window stays revealed for the hide delay after the pointer leaves it.
+        cx.test_window(window).simulate_hover_status_change(false);
+        cx.executor().advance_clock(Duration::from_millis(100));
+        assert!(window
+            .update(cx, |_, window, _| window.is_layer_shell_revealed())
+            .unwrap());
+        cx.executor().advance_clock(AutoHide::default().hide_delay);
+        assert!(!window
+            .update(cx, |_, window, _| window.is_layer_shell_revealed())
+            .unwrap());
+        assert_eq!(changes.get(), 2);
+    }
+
+    struct BoundsProbe(Rc<Cell<Bounds<Pixels>>>);
+
+    impl Render for BoundsProbe {
+        fn render(&mut self, _: &mut Window, _: &mut Context<Self>) -> impl IntoElement {
+            let bounds = self.0.clone();
+            canvas(
+                move |root_bounds, _, _| bounds.set(root_bounds),
+                |_, _, _, _| {},
+            )
+            .size_full()
+        }
+    }
+
+    #[gpui::test]
+    fn test_layer_shell_shadow_insets_root_view(cx: &mut TestAppContext) {
+        let shadow = LayerShellShadow {
+            blur_radius: px(10.),
+            offset: point(px(0.), px(4.)),
+            ..Default::default()
+        };
+        let root_bounds = Rc::new(Cell::new(Bounds::default()));
+        cx.update(|cx| {
+            let root_bounds = root_bounds.clone();
+            cx.open_window(
+                WindowOptions {
+                    window_bounds: Some(WindowBounds::Windowed(Bounds {
+                        origin: point(px(0.), px(0.)),
+                        size: size(px(400.), px(300.)),
+                    })),
+                    kind: WindowKind::LayerShell(LayerShellSettings {
+                        shadow: Some(shadow),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+                |_, cx| cx.new(|_| BoundsProbe(root_bounds)),
+            )
+            .unwrap();
+        });
+        cx.run_until_parked();
+
+        assert_eq!(
+            root_bounds.get(),
+            Bounds {
+                origin: point(px(14.), px(14.)),
+                size: size(px(372.), px(272.)),
+            }
+        );
+    }
 }
@@ -0,0 +1,49 @@
+//! Input synthesized by the application and delivered to whatever is under the pointer or
+//! focused, like input from a real device. These are backed by wlr-virtual-pointer and
+//! virtual-keyboard on Wayland, other platforms don't support them.
+
+use crate::{Keystroke, MouseButton, Pixels, Point, ScrollDelta, SharedString};
+
+/// Input sent through a virtual pointer with [`App::send_virtual_pointer_input`].
+///
+/// [`App::send_virtual_pointer_input`]: crate::App::send_virtual_pointer_input
+#[derive(Clone, Debug, PartialEq)]
+pub enum VirtualPointerInput {
+    /// Moves the pointer by the given distance.
+    MoveBy(Point<Pixels>),
+    /// Moves the pointer to the given position in the space the bounds of the displays are in.
+    MoveTo(Point<Pixels>),
+    /// Presses the button.
+    Press(MouseButton),
+    /// Releases the button.
+    Release(MouseButton),
+    /// Scrolls by the given delta, in the direction gpui reports it in a [`ScrollWheelEvent`].
+    ///
+    /// [`ScrollWheelEvent`]: crate::ScrollWheelEvent
+    Scroll(ScrollDelta),
+}
+
+impl VirtualPointerInput {
+    /// Returns the inputs clicking the button.
+    pub fn click(button: MouseButton) -> [Self; 2] {
+        [Self::Press(button), Self::Release(button)]
+    }
+}
+
+/// Input sent through a virtual keyboard with [`App::send_virtual_keyboard_input`].
+///
+/// [`App::send_virtual_keyboard_input`]: crate::App::send_virtual_keyboard_input
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VirtualKeyboardInput {
+    /// Types the text, pressing and releasing a key for each character.
+    Text(SharedString),
+    /// Presses and releases the key of the keystroke while holding its modifiers, e.g. for
+    /// `ctrl-v`.
+    Keystroke(Keystroke),
+}
+
+impl From<Keystroke> for VirtualKeyboardInput {
+    fn from(keystroke: Keystroke) -> Self {
+        Self::Keystroke(keystroke)
+    }
+}
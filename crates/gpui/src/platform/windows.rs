@@ -1,3 +1,4 @@
+mod app_bar;
 mod clipboard;
 mod direct_write;
 mod dispatcher;
@@ -9,6 +10,7 @@ mod util;
 mod window;
 mod wrapper;
 
+pub(crate) use app_bar::*;
 pub(crate) use clipboard::*;
 pub(crate) use direct_write::*;
 pub(crate) use dispatcher::*;
@@ -0,0 +1,120 @@
+use windows::Win32::{
+    Foundation::{HWND, RECT},
+    UI::Shell::{
+        SHAppBarMessage, ABE_BOTTOM, ABE_LEFT, ABE_RIGHT, ABE_TOP, ABM_ACTIVATE, ABM_NEW,
+        ABM_QUERYPOS, ABM_REMOVE, ABM_SETPOS, ABM_WINDOWPOSCHANGED, APPBARDATA,
+    },
+};
+
+use crate::*;
+
+/// A layer shell window registered with the shell as an application desktop toolbar, which
+/// keeps other windows out of its exclusive zone the way a layer shell compositor would.
+pub(crate) struct AppBar {
+    hwnd: HWND,
+    edge: u32,
+    /// The space reserved along the edge, in device pixels.
+    thickness: i32,
+}
+
+impl AppBar {
+    /// Registers a window with the given settings as an app bar. As with layer shell, this only
+    /// applies to windows with a positive exclusive zone that are anchored to a single edge, or
+    /// to an edge and both of its neighbours.
+    pub(crate) fn register(
+        hwnd: HWND,
+        settings: &LayerShellSettings,
+        scale_factor: f32,
+    ) -> Option<Self> {
+        let zone = settings.exclusive_zone.filter(|zone| zone.0 > 0.)?;
+        let (top, right, bottom, left) = settings.margin.unwrap_or_default();
+        // The exclusive zone is measured from the margin.
+        let (edge, margin) = match settings.attached_edge()? {
+            Anchor::TOP => (ABE_TOP, top),
+            Anchor::BOTTOM => (ABE_BOTTOM, bottom),
+            Anchor::LEFT => (ABE_LEFT, left),
+            _ => (ABE_RIGHT, right),
+        };
+        let mut data = app_bar_data(hwnd);
+        data.uCallbackMessage = WM_GPUI_APP_BAR_NOTIFY;
+        if unsafe { SHAppBarMessage(ABM_NEW, &mut data) } == 0 {
+            log::error!(
+                "Unable to register app bar: {}",
+                std::io::Error::last_os_error()
+            );
+            return None;
+        }
+        Some(Self {
+            hwnd,
+            edge,
+            thickness: ((zone + margin).0 * scale_factor).round() as i32,
+        })
+    }
+
+    /// Reserves the app bar's space along the edge of the given display, returning the area to
+    /// place the window in. The shell moves the reserved space inwards past other app bars on
+    /// the same edge, such as the taskbar, and the window follows it.
+    pub(crate) fn reserve(&self, display_bounds: Bounds<DevicePixels>) -> Bounds<DevicePixels> {
+        let mut data = app_bar_data(self.hwnd);
+        data.uEdge = self.edge;
+        data.rc = RECT {
+            left: display_bounds.left().0,
+            top: display_bounds.top().0,
+            right: display_bounds.right().0,
+            bottom: display_bounds.bottom().0,
+        };
+        self.fit_to_edge(&mut data.rc);
+        unsafe { SHAppBarMessage(ABM_QUERYPOS, &mut data) };
+        // The shell only moves the edge it's attached to, so restore the thickness.
+        self.fit_to_edge(&mut data.rc);
+        unsafe { SHAppBarMessage(ABM_SETPOS, &mut data) };
+
+        let mut area = data.rc;
+        match self.edge {
+            ABE_TOP => area.bottom = display_bounds.bottom().0,
+            ABE_BOTTOM => area.top = display_bounds.top().0,
+            ABE_LEFT => area.right = display_bounds.right().0,
+            _ => area.left = display_bounds.left().0,
+        }
+        Bounds::from_corners(
+            point(area.left.into(), area.top.into()),
+            point(area.right.into(), area.bottom.into()),
+        )
+    }
+
+    /// Lets the shell know the window was activated, so it can raise the app bar.
+    pub(crate) fn activated(&self) {
+        let mut data = app_bar_data(self.hwnd);
+        unsafe { SHAppBarMessage(ABM_ACTIVATE, &mut data) };
+    }
+
+    /// Lets the shell know the window was moved or resized.
+    pub(crate) fn position_changed(&self) {
+        let mut data = app_bar_data(self.hwnd);
+        unsafe { SHAppBarMessage(ABM_WINDOWPOSCHANGED, &mut data) };
+    }
+
+    fn fit_to_edge(&self, rect: &mut RECT) {
+        match self.edge {
+            ABE_TOP => rect.bottom = rect.top + self.thickness,
+            ABE_BOTTOM => rect.top = rect.bottom - self.thickness,
+            ABE_LEFT => rect.right = rect.left + self.thickness,
+            _ => rect.left = rect.right - self.thickness,
+        }
+    }
+}
+
+impl Drop for AppBar {
+    fn drop(&mut self) {
+        let mut data = app_bar_data(self.hwnd);
+        unsafe { SHAppBarMessage(ABM_REMOVE, &mut data) };
+    }
+}
+
+fn app_bar_data(hwnd: HWND) -> APPBARDATA {
+    APPBARDATA {
+        cbSize: std::mem::size_of::<APPBARDATA>() as u32,
+        hWnd: hwnd,
+        ..Default::default()
+    }
+}
@@ -12,6 +12,7 @@ use windows::{
             Controls::*,
             HiDpi::*,
             Input::{Ime::*, KeyboardAndMouse::*},
+            Shell::{ABN_FULLSCREENAPP, ABN_POSCHANGED},
             WindowsAndMessaging::*,
         },
     },
@@ -23,6 +24,7 @@ pub(crate) const WM_GPUI_CURSOR_STYLE_CHANGED: u32 = WM_USER + 1;
 pub(crate) const WM_GPUI_CLOSE_ONE_WINDOW: u32 = WM_USER + 2;
 pub(crate) const WM_GPUI_TASK_DISPATCHED_ON_MAIN_THREAD: u32 = WM_USER + 3;
 pub(crate) const WM_GPUI_DOCK_MENU_ACTION: u32 = WM_USER + 4;
+pub(crate) const WM_GPUI_APP_BAR_NOTIFY: u32 = WM_USER + 5;
 
 const SIZE_MOVE_LOOP_TIMER_ID: usize = 1;
 const AUTO_HIDE_TASKBAR_THICKNESS_PX: i32 = 1;
@@ -39,6 +41,7 @@ pub(crate) fn handle_msg(
         WM_CREATE => handle_create_msg(handle, state_ptr),
         WM_MOVE => handle_move_msg(handle, lparam, state_ptr),
         WM_SIZE => handle_size_msg(wparam, lparam, state_ptr),
+        WM_WINDOWPOSCHANGED => handle_window_pos_changed_msg(state_ptr),
         WM_ENTERSIZEMOVE | WM_ENTERMENULOOP => handle_size_move_loop(handle),
         WM_EXITSIZEMOVE | WM_EXITMENULOOP => handle_size_move_loop_exit(handle),
         WM_TIMER => handle_timer_msg(handle, wparam, state_ptr),
@@ -93,6 +96,7 @@ pub(crate) fn handle_msg(
         WM_SETCURSOR => handle_set_cursor(lparam, state_ptr),
         WM_SETTINGCHANGE => handle_system_settings_changed(handle, lparam, state_ptr),
         WM_GPUI_CURSOR_STYLE_CHANGED => handle_cursor_changed(lparam, state_ptr),
+        WM_GPUI_APP_BAR_NOTIFY => handle_app_bar_notify(handle, wparam, lparam, state_ptr),
         _ => None,
     };
     if let Some(n) = handled {
@@ -773,6 +777,9 @@ fn handle_activate_msg(
     state_ptr: Rc<WindowsWindowStatePtr>,
 ) -> Option<isize> {
     let activated = wparam.loword() > 0;
+    if activated {
+        state_ptr.with_app_bar(|app_bar| app_bar.activated());
+    }
     if state_ptr.hide_title_bar {
         if let Some(titlebar_rect) = state_ptr.state.borrow().get_titlebar_rect().log_err() {
             unsafe {
@@ -851,6 +858,54 @@ fn handle_dpi_changed_msg(
 ///
 /// For example, in the case of condition 2, where the monitor on which the window is
 /// located has actually changed nothing, it will still receive this event.
+fn handle_window_pos_changed_msg(state_ptr: Rc<WindowsWindowStatePtr>) -> Option<isize> {
+    state_ptr.with_app_bar(|app_bar| app_bar.position_changed());
+    // Let `DefWindowProcW` send `WM_SIZE` and `WM_MOVE`.
+    None
+}
+
+fn handle_app_bar_notify(
+    handle: HWND,
+    wparam: WPARAM,
+    lparam: LPARAM,
+    state_ptr: Rc<WindowsWindowStatePtr>,
+) -> Option<isize> {
+    match wparam.0 as u32 {
+        ABN_POSCHANGED => state_ptr.reposition_app_bar(),
+        ABN_FULLSCREENAPP => {
+            // Like on layer shell compositors, the top layer goes below fullscreen windows
+            // while the overlay layer stays above them.
+            let layer = state_ptr
+                .state
+                .borrow()
+                .layer_shell
+                .as_ref()
+                .map(|settings| settings.layer);
+            if layer == Some(Layer::Top) {
+                let insert_after = if lparam.0 != 0 {
+                    HWND_BOTTOM
+                } else {
+                    HWND_TOPMOST
+                };
+                unsafe {
+                    SetWindowPos(
+                        handle,
+                        insert_after,
+                        0,
+                        0,
+                        0,
+                        0,
+                        SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE,
+                    )
+                }
+                .log_err();
+            }
+        }
+        _ => {}
+    }
+    Some(0)
+}
+
 fn handle_display_change_msg(handle: HWND, state_ptr: Rc<WindowsWindowStatePtr>) -> Option<isize> {
     // NOTE:
     // Even the `lParam` holds the resolution of the screen, we just ignore it.
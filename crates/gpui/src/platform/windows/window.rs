@@ -52,6 +52,8 @@ pub struct WindowsWindowState {
     pub nc_button_pressed: Option<u32>,
 
     pub display: WindowsDisplay,
+    pub layer_shell: Option<LayerShellSettings>,
+    pub app_bar: Option<AppBar>,
     fullscreen: Option<StyleAndBounds>,
     initial_placement: Option<WindowOpenStatus>,
     hwnd: HWND,
@@ -95,7 +97,8 @@ impl WindowsWindowState {
         };
         let border_offset = WindowBorderOffset::default();
         let restore_from_minimized = None;
-        let renderer = windows_renderer::init(gpu_context, hwnd, transparent)?;
+        let renderer = windows_renderer::init(gpu_context, hwnd, transparent)
+            .map_err(WindowCreationError::RendererInit)?;
         let callbacks = Callbacks::default();
         let input_handler = None;
         let system_key_handled = false;
@@ -103,6 +106,8 @@ impl WindowsWindowState {
         let click_state = ClickState::new();
         let system_settings = WindowsSystemSettings::new(display);
         let nc_button_pressed = None;
+        let layer_shell = None;
+        let app_bar = None;
         let fullscreen = None;
         let initial_placement = None;
 
@@ -123,6 +128,8 @@ impl WindowsWindowState {
             current_cursor,
             nc_button_pressed,
             display,
+            layer_shell,
+            app_bar,
             fullscreen,
             initial_placement,
             hwnd,
@@ -309,6 +316,50 @@ impl WindowsWindowStatePtr {
             .detach();
     }
 
+    /// Runs `f` with the window's app bar, if it has one. The app bar is taken out of the state
+    /// meanwhile, as the shell may send the window messages while handling app bar requests.
+    pub(crate) fn with_app_bar<R>(&self, f: impl FnOnce(&AppBar) -> R) -> Option<R> {
+        let app_bar = self.state.borrow_mut().app_bar.take()?;
+        let result = f(&app_bar);
+        self.state.borrow_mut().app_bar = Some(app_bar);
+        Some(result)
+    }
+
+    /// Moves a layer shell window registered as an app bar back into place, after the shell
+    /// moved the app bars on its display.
+    pub(crate) fn reposition_app_bar(&self) {
+        let lock = self.state.borrow();
+        let Some(settings) = lock.layer_shell.clone() else {
+            return;
+        };
+        let display_bounds = lock.display.physical_bounds();
+        let scale_factor = lock.scale_factor;
+        let border_offset = lock.border_offset;
+        // The window's size includes the space kept for its shadow, which is added back when
+        // placing it.
+        let body_size = settings.body_size(lock.logical_size);
+        drop(lock);
+        let Some(area) = self.with_app_bar(|app_bar| app_bar.reserve(display_bounds)) else {
+            return;
+        };
+        let bounds = settings
+            .bounds_within(body_size, area.to_pixels(scale_factor))
+            .to_device_pixels(scale_factor);
+        let rect = calculate_window_rect(bounds, border_offset);
+        unsafe {
+            SetWindowPos(
+                self.hwnd,
+                None,
+                rect.left,
+                rect.top,
+                rect.right - rect.left,
+                rect.bottom - rect.top,
+                SWP_NOZORDER | SWP_NOACTIVATE,
+            )
+        }
+        .log_err();
+    }
+
     fn set_window_placement(&self) -> Result<()> {
         let Some(open_status) = self.state.borrow_mut().initial_placement.take() else {
             return Ok(());
@@ -389,13 +440,22 @@ impl WindowsWindow {
                 .map(|title| title.as_ref())
                 .unwrap_or(""),
         );
-        let (dwexstyle, mut dwstyle) = if params.kind == WindowKind::PopUp {
-            (WS_EX_TOOLWINDOW, WINDOW_STYLE(0x0))
-        } else {
-            (
+        let (dwexstyle, mut dwstyle) = match &params.kind {
+            WindowKind::PopUp => (WS_EX_TOOLWINDOW, WINDOW_STYLE(0x0)),
+            WindowKind::LayerShell(settings) => {
+                let mut dwexstyle = WS_EX_TOOLWINDOW;
+                if matches!(settings.layer, Layer::Top | Layer::Overlay) {
+                    dwexstyle |= WS_EX_TOPMOST;
+                }
+                if settings.keyboard_interactivity == KeyboardInteractivity::None {
+                    dwexstyle |= WS_EX_NOACTIVATE;
+                }
+                (dwexstyle, WINDOW_STYLE(0x0))
+            }
+            WindowKind::Normal => (
                 WS_EX_APPWINDOW,
                 WS_THICKFRAME | WS_SYSMENU | WS_MAXIMIZEBOX | WS_MINIMIZEBOX,
-            )
+            ),
         };
 
         let hinstance = get_module_handle();
@@ -439,15 +499,52 @@ impl WindowsWindow {
         };
         // We should call `?` on state_ptr first, then call `?` on hwnd.
         // Or, we will lose the error info reported by `WindowsWindowState::new`
-        let state_ptr = context.inner.take().unwrap()?;
+        // The state is only missing when creation failed before `WM_NCCREATE`.
+        let Some(state_ptr) = context.inner.take() else {
+            creation_result?;
+            anyhow::bail!("window creation didn't reach WM_NCCREATE");
+        };
+        let state_ptr = state_ptr?;
         let hwnd = creation_result?;
         register_drag_drop(state_ptr.clone())?;
         configure_dwm_dark_mode(hwnd);
         state_ptr.state.borrow_mut().border_offset.update(hwnd)?;
+        // There's no layer shell, so place the window where the compositor would have, and
+        // register it as an app bar to reserve its exclusive zone.
+        let bounds = match &params.kind {
+            WindowKind::LayerShell(settings) => {
+                if matches!(settings.layer, Layer::Background | Layer::Bottom) {
+                    unsafe {
+                        SetWindowPos(
+                            hwnd,
+                            HWND_BOTTOM,
+                            0,
+                            0,
+                            0,
+                            0,
+                            SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE,
+                        )
+                    }
+                    .log_err();
+                }
+                let scale_factor = state_ptr.state.borrow().scale_factor;
+                let area = match AppBar::register(hwnd, settings, scale_factor) {
+                    Some(app_bar) => {
+                        let area = app_bar.reserve(display.physical_bounds());
+                        state_ptr.state.borrow_mut().app_bar = Some(app_bar);
+                        area.to_pixels(scale_factor)
+                    }
+                    None => display.bounds(),
+                };
+                state_ptr.state.borrow_mut().layer_shell = Some(settings.clone());
+                settings.bounds_within(params.bounds.size, area)
+            }
+            _ => params.bounds,
+        };
         let placement = retrieve_window_placement(
             hwnd,
             display,
-            params.bounds,
+            bounds,
             state_ptr.state.borrow().scale_factor,
             state_ptr.state.borrow().border_offset,
         )?;
@@ -484,6 +581,9 @@ impl rwh::HasDisplayHandle for WindowsWindow {
 impl Drop for WindowsWindow {
     fn drop(&mut self) {
         self.0.state.borrow_mut().renderer.destroy();
+        // Give the reserved space back before the window goes away.
+        let app_bar = self.0.state.borrow_mut().app_bar.take();
+        drop(app_bar);
         // clone this `Rc` to prevent early release of the pointer
         let this = self.0.clone();
         self.0
@@ -672,6 +772,10 @@ impl PlatformWindow for WindowsWindow {
             .ok();
     }
 
+    fn is_background_blur_supported(&self) -> bool {
+        true
+    }
+
     fn set_background_appearance(&self, background_appearance: WindowBackgroundAppearance) {
         let mut window_state = self.0.state.borrow_mut();
         window_state
@@ -794,6 +898,14 @@ impl PlatformWindow for WindowsWindow {
         Some(self.0.state.borrow().renderer.gpu_specs())
     }
 
+    fn set_anti_aliasing(&self, anti_aliasing: AntiAliasing) {
+        self.0
+            .state
+            .borrow_mut()
+            .renderer
+            .update_anti_aliasing(anti_aliasing);
+    }
+
     fn update_ime_position(&self, _bounds: Bounds<ScaledPixels>) {
         // todo(windows)
     }
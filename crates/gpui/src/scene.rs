@@ -3,7 +3,7 @@
 
 use crate::{
     bounds_tree::BoundsTree, point, AtlasTextureId, AtlasTile, Background, Bounds, ContentMask,
-    Corners, Edges, Hsla, Pixels, Point, Radians, ScaledPixels, Size,
+    Corners, CustomShader, Edges, Hsla, Pixels, Point, Radians, ScaledPixels, Size,
 };
 use std::{fmt::Debug, iter::Peekable, ops::Range, slice};
 
@@ -17,27 +17,40 @@ pub(crate) struct Scene {
     pub(crate) paint_operations: Vec<PaintOperation>,
     primitive_bounds: BoundsTree<ScaledPixels>,
     layer_stack: Vec<DrawOrder>,
+    /// The clips the primitives being inserted are within, and whether each clip started a
+    /// layer for them.
+    path_clip_stack: Vec<(ClipId, bool)>,
     pub(crate) shadows: Vec<Shadow>,
+    pub(crate) blurs: Vec<Blur>,
     pub(crate) quads: Vec<Quad>,
     pub(crate) paths: Vec<Path<ScaledPixels>>,
     pub(crate) underlines: Vec<Underline>,
     pub(crate) monochrome_sprites: Vec<MonochromeSprite>,
     pub(crate) polychrome_sprites: Vec<PolychromeSprite>,
     pub(crate) surfaces: Vec<PaintSurface>,
+    pub(crate) shaders: Vec<PaintShader>,
+    pub(crate) path_clips: Vec<PathClip>,
+    /// The area that changed since the previously presented scene.
+    pub(crate) damage: Damage,
 }
 
 impl Scene {
     pub fn clear(&mut self) {
+        self.damage = Damage::Full;
         self.paint_operations.clear();
         self.primitive_bounds.clear();
         self.layer_stack.clear();
+        self.path_clip_stack.clear();
         self.paths.clear();
         self.shadows.clear();
+        self.blurs.clear();
         self.quads.clear();
         self.underlines.clear();
         self.monochrome_sprites.clear();
         self.polychrome_sprites.clear();
         self.surfaces.clear();
+        self.shaders.clear();
+        self.path_clips.clear();
     }
 
     #[cfg_attr(
@@ -77,16 +90,23 @@ impl Scene {
             return;
         }
 
-        let order = self
-            .layer_stack
-            .last()
-            .copied()
-            .unwrap_or_else(|| self.primitive_bounds.insert(clipped_bounds));
+        let order = if let Primitive::PathClip(clip) = &mut primitive {
+            self.path_clip_order(clip, clipped_bounds)
+        } else {
+            self.layer_stack
+                .last()
+                .copied()
+                .unwrap_or_else(|| self.primitive_bounds.insert(clipped_bounds))
+        };
         match &mut primitive {
             Primitive::Shadow(shadow) => {
                 shadow.order = order;
                 self.shadows.push(shadow.clone());
             }
+            Primitive::Blur(blur) => {
+                blur.order = order;
+                self.blurs.push(blur.clone());
+            }
             Primitive::Quad(quad) => {
                 quad.order = order;
                 self.quads.push(quad.clone());
@@ -112,11 +132,50 @@ impl Scene {
                 surface.order = order;
                 self.surfaces.push(surface.clone());
             }
+            Primitive::Shader(shader) => {
+                shader.order = order;
+                self.shaders.push(shader.clone());
+            }
+            Primitive::PathClip(clip) => {
+                clip.order = order;
+                self.path_clips.push(clip.clone());
+            }
         }
         self.paint_operations
             .push(PaintOperation::Primitive(primitive));
     }
 
+    /// Assigns the id of a clip and returns its order, which is above everything it overlaps
+    /// for its start, and above its content for its end. Within a layer, the content of the
+    /// clip gets a layer of its own so that it's ordered between the two.
+    fn path_clip_order(
+        &mut self,
+        clip: &mut PathClip,
+        clipped_bounds: Bounds<ScaledPixels>,
+    ) -> DrawOrder {
+        if clip.path.is_none() {
+            clip.id = ClipId(self.path_clips.len());
+            let order = self.primitive_bounds.insert(clipped_bounds);
+            let in_layer = !self.layer_stack.is_empty();
+            if in_layer {
+                let content_order = self.primitive_bounds.insert(clipped_bounds);
+                self.layer_stack.push(content_order);
+            }
+            self.path_clip_stack.push((clip.id, in_layer));
+            order
+        } else {
+            let (id, in_layer) = self
+                .path_clip_stack
+                .pop()
+                .expect("path clips must be ended in the order they were started");
+            if in_layer {
+                self.layer_stack.pop();
+            }
+            clip.id = id;
+            self.primitive_bounds.insert(clipped_bounds)
+        }
+    }
+
     pub fn replay(&mut self, range: Range<usize>, prev_scene: &Scene) {
         for operation in &prev_scene.paint_operations[range] {
             match operation {
@@ -129,14 +188,92 @@ impl Scene {
 
     pub fn finish(&mut self) {
         self.shadows.sort_by_key(|shadow| shadow.order);
+        self.blurs.sort_by_key(|blur| blur.order);
         self.quads.sort_by_key(|quad| quad.order);
         self.paths.sort_by_key(|path| path.order);
         self.underlines.sort_by_key(|underline| underline.order);
-        self.monochrome_sprites
-            .sort_by_key(|sprite| (sprite.order, sprite.tile.tile_id));
-        self.polychrome_sprites
-            .sort_by_key(|sprite| (sprite.order, sprite.tile.tile_id));
+        // Sprites with the same order don't overlap, grouping them by blend mode and texture lets
+        // each texture be drawn with a single batch.
+        self.monochrome_sprites.sort_by_key(|sprite| {
+            (
+                sprite.order,
+                sprite.blend_mode as u32,
+                sprite.tile.texture_id.index,
+                sprite.tile.tile_id,
+            )
+        });
+        self.polychrome_sprites.sort_by_key(|sprite| {
+            (
+                sprite.order,
+                sprite.blend_mode as u32,
+                sprite.tile.texture_id.index,
+                sprite.tile.tile_id,
+            )
+        });
         self.surfaces.sort_by_key(|surface| surface.order);
+        self.shaders.sort_by_key(|shader| shader.order);
+        self.path_clips.sort_by_key(|clip| clip.order);
+    }
+
+    /// Computes the area that needs to be repainted to turn `previous` into this scene.
+    /// Primitives are compared pairwise in paint order, so inserting or removing a
+    /// primitive conservatively damages all following primitives of the same kind.
+    pub(crate) fn diff(&self, previous: &Scene) -> Damage {
+        let mut damage = Damage::None;
+        diff_primitives(&self.shadows, &previous.shadows, &mut damage, |shadow| {
+            // Shadows are drawn outside of their bounds
+            shadow
+                .bounds
+                .dilate(ScaledPixels(shadow.blur_radius.0 * 3.))
+                .intersect(&shadow.content_mask.bounds)
+        });
+        diff_primitives(&self.quads, &previous.quads, &mut damage, |quad| {
+            quad.bounds.intersect(&quad.content_mask.bounds)
+        });
+        diff_primitives(&self.paths, &previous.paths, &mut damage, |path| {
+            path.bounds.intersect(&path.content_mask.bounds)
+        });
+        diff_primitives(
+            &self.underlines,
+            &previous.underlines,
+            &mut damage,
+            |underline| underline.bounds.intersect(&underline.content_mask.bounds),
+        );
+        diff_primitives(
+            &self.monochrome_sprites,
+            &previous.monochrome_sprites,
+            &mut damage,
+            |sprite| sprite.bounds.intersect(&sprite.content_mask.bounds),
+        );
+        diff_primitives(
+            &self.polychrome_sprites,
+            &previous.polychrome_sprites,
+            &mut damage,
+            |sprite| sprite.bounds.intersect(&sprite.content_mask.bounds),
+        );
+        // Surfaces show external content which may change on every frame
+        for surface in self.surfaces.iter().chain(&previous.surfaces) {
+            damage.add(surface.bounds.intersect(&surface.content_mask.bounds));
+        }
+        diff_primitives(&self.shaders, &previous.shaders, &mut damage, |shader| {
+            shader.bounds.intersect(&shader.content_mask.bounds)
+        });
+        diff_primitives(
+            &self.path_clips,
+            &previous.path_clips,
+            &mut damage,
+            |clip| clip.bounds.intersect(&clip.content_mask.bounds),
+        );
+        diff_primitives(&self.blurs, &previous.blurs, &mut damage, |blur| {
+            blur.bounds.intersect(&blur.content_mask.bounds)
+        });
+        // Blurs sample the content around them, so they change with it.
+        for blur in &self.blurs {
+            if damage.intersects(&blur.bounds.dilate(ScaledPixels(blur.radius.0 * 3.))) {
+                damage.add(blur.bounds.intersect(&blur.content_mask.bounds));
+            }
+        }
+        damage
     }
 
     #[cfg_attr(
@@ -151,6 +288,9 @@ impl Scene {
             shadows: &self.shadows,
             shadows_start: 0,
             shadows_iter: self.shadows.iter().peekable(),
+            blurs: &self.blurs,
+            blurs_start: 0,
+            blurs_iter: self.blurs.iter().peekable(),
             quads: &self.quads,
             quads_start: 0,
             quads_iter: self.quads.iter().peekable(),
@@ -169,10 +309,123 @@ impl Scene {
             surfaces: &self.surfaces,
             surfaces_start: 0,
             surfaces_iter: self.surfaces.iter().peekable(),
+            shaders: &self.shaders,
+            shaders_start: 0,
+            shaders_iter: self.shaders.iter().peekable(),
+            path_clips: &self.path_clips,
+            path_clips_start: 0,
+            path_clips_iter: self.path_clips.iter().peekable(),
+        }
+    }
+}
+
+fn diff_primitives<T: PartialEq>(
+    current: &[T],
+    previous: &[T],
+    damage: &mut Damage,
+    bounds: impl Fn(&T) -> Bounds<ScaledPixels>,
+) {
+    for ix in 0..current.len().max(previous.len()) {
+        match (current.get(ix), previous.get(ix)) {
+            (Some(current), Some(previous)) if current == previous => {}
+            (current, previous) => {
+                if let Some(current) = current {
+                    damage.add(bounds(current));
+                }
+                if let Some(previous) = previous {
+                    damage.add(bounds(previous));
+                }
+            }
         }
     }
 }
 
+/// The most areas [`Damage::Partial`] keeps apart, further ones are merged into the areas
+/// they grow the least. Compositors handle a few rectangles of damage well, but each one
+/// costs them a little.
+const MAX_DAMAGE_RECTS: usize = 16;
+
+/// The area of a [`Scene`] that changed since the previously presented one.
+///
+/// Renderers repaint only the area around the damage and skip frames without changes, and the
+/// damage narrows what the compositor has to recomposite.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) enum Damage {
+    /// The whole scene needs to be repainted.
+    #[default]
+    Full,
+    /// Only the given areas changed, they don't overlap.
+    Partial(Vec<Bounds<ScaledPixels>>),
+    /// The scene is identical to the previous one.
+    None,
+}
+
+impl Damage {
+    fn add(&mut self, bounds: Bounds<ScaledPixels>) {
+        if bounds.is_empty() {
+            return;
+        }
+        match self {
+            Damage::Full => {}
+            Damage::Partial(rects) => add_damage_rect(rects, bounds),
+            Damage::None => *self = Damage::Partial(vec![bounds]),
+        }
+    }
+
+    /// Returns whether any of the changed area lies within `bounds`.
+    fn intersects(&self, bounds: &Bounds<ScaledPixels>) -> bool {
+        match self {
+            Damage::Full => true,
+            Damage::Partial(rects) => rects.iter().any(|rect| rect.intersects(bounds)),
+            Damage::None => false,
+        }
+    }
+
+    /// Returns the smallest area containing all of the changed ones, `None` if nothing or
+    /// everything changed.
+    pub(crate) fn bounds(&self) -> Option<Bounds<ScaledPixels>> {
+        match self {
+            Damage::Partial(rects) => rects.iter().copied().reduce(|a, b| a.union(&b)),
+            Damage::Full | Damage::None => None,
+        }
+    }
+
+    /// Combines the damage of two consecutive frames.
+    pub(crate) fn union(&self, other: &Damage) -> Damage {
+        match (self, other) {
+            (Damage::Full, _) | (_, Damage::Full) => Damage::Full,
+            (Damage::None, damage) | (damage, Damage::None) => damage.clone(),
+            (Damage::Partial(a), Damage::Partial(b)) => {
+                let mut rects = a.clone();
+                for bounds in b {
+                    add_damage_rect(&mut rects, *bounds);
+                }
+                Damage::Partial(rects)
+            }
+        }
+    }
+}
+
+/// Adds a changed area, merging it with the areas it overlaps so no pixel is re-composited
+/// twice.
+fn add_damage_rect(rects: &mut Vec<Bounds<ScaledPixels>>, mut bounds: Bounds<ScaledPixels>) {
+    // A merged area can overlap areas the added one didn't.
+    while let Some(ix) = rects.iter().position(|rect| rect.intersects(&bounds)) {
+        bounds = bounds.union(&rects.swap_remove(ix));
+    }
+    if rects.len() < MAX_DAMAGE_RECTS {
+        rects.push(bounds);
+        return;
+    }
+    let area = |bounds: &Bounds<ScaledPixels>| bounds.size.width.0 * bounds.size.height.0;
+    let growth = |rect: &Bounds<ScaledPixels>| area(&rect.union(&bounds)) - area(rect);
+    let ix = (0..rects.len())
+        .min_by(|a, b| growth(&rects[*a]).total_cmp(&growth(&rects[*b])))
+        .unwrap_or(0);
+    let rect = rects.swap_remove(ix);
+    add_damage_rect(rects, bounds.union(&rect));
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Default)]
 #[cfg_attr(
     all(
@@ -183,6 +436,7 @@ impl Scene {
 )]
 pub(crate) enum PrimitiveKind {
     Shadow,
+    Blur,
     #[default]
     Quad,
     Path,
@@ -190,6 +444,8 @@ pub(crate) enum PrimitiveKind {
     MonochromeSprite,
     PolychromeSprite,
     Surface,
+    Shader,
+    PathClip,
 }
 
 pub(crate) enum PaintOperation {
@@ -201,36 +457,45 @@ pub(crate) enum PaintOperation {
 #[derive(Clone)]
 pub(crate) enum Primitive {
     Shadow(Shadow),
+    Blur(Blur),
     Quad(Quad),
     Path(Path<ScaledPixels>),
     Underline(Underline),
     MonochromeSprite(MonochromeSprite),
     PolychromeSprite(PolychromeSprite),
     Surface(PaintSurface),
+    Shader(PaintShader),
+    PathClip(PathClip),
 }
 
 impl Primitive {
     pub fn bounds(&self) -> &Bounds<ScaledPixels> {
         match self {
             Primitive::Shadow(shadow) => &shadow.bounds,
+            Primitive::Blur(blur) => &blur.bounds,
             Primitive::Quad(quad) => &quad.bounds,
             Primitive::Path(path) => &path.bounds,
             Primitive::Underline(underline) => &underline.bounds,
             Primitive::MonochromeSprite(sprite) => &sprite.bounds,
             Primitive::PolychromeSprite(sprite) => &sprite.bounds,
             Primitive::Surface(surface) => &surface.bounds,
+            Primitive::Shader(shader) => &shader.bounds,
+            Primitive::PathClip(clip) => &clip.bounds,
         }
     }
 
     pub fn content_mask(&self) -> &ContentMask<ScaledPixels> {
         match self {
             Primitive::Shadow(shadow) => &shadow.content_mask,
+            Primitive::Blur(blur) => &blur.content_mask,
             Primitive::Quad(quad) => &quad.content_mask,
             Primitive::Path(path) => &path.content_mask,
             Primitive::Underline(underline) => &underline.content_mask,
             Primitive::MonochromeSprite(sprite) => &sprite.content_mask,
             Primitive::PolychromeSprite(sprite) => &sprite.content_mask,
             Primitive::Surface(surface) => &surface.content_mask,
+            Primitive::Shader(shader) => &shader.content_mask,
+            Primitive::PathClip(clip) => &clip.content_mask,
         }
     }
 }
@@ -246,6 +511,9 @@ struct BatchIterator<'a> {
     shadows: &'a [Shadow],
     shadows_start: usize,
     shadows_iter: Peekable<slice::Iter<'a, Shadow>>,
+    blurs: &'a [Blur],
+    blurs_start: usize,
+    blurs_iter: Peekable<slice::Iter<'a, Blur>>,
     quads: &'a [Quad],
     quads_start: usize,
     quads_iter: Peekable<slice::Iter<'a, Quad>>,
@@ -264,6 +532,12 @@ struct BatchIterator<'a> {
     surfaces: &'a [PaintSurface],
     surfaces_start: usize,
     surfaces_iter: Peekable<slice::Iter<'a, PaintSurface>>,
+    shaders: &'a [PaintShader],
+    shaders_start: usize,
+    shaders_iter: Peekable<slice::Iter<'a, PaintShader>>,
+    path_clips: &'a [PathClip],
+    path_clips_start: usize,
+    path_clips_iter: Peekable<slice::Iter<'a, PathClip>>,
 }
 
 impl<'a> Iterator for BatchIterator<'a> {
@@ -275,6 +549,7 @@ impl<'a> Iterator for BatchIterator<'a> {
                 self.shadows_iter.peek().map(|s| s.order),
                 PrimitiveKind::Shadow,
             ),
+            (self.blurs_iter.peek().map(|b| b.order), PrimitiveKind::Blur),
             (self.quads_iter.peek().map(|q| q.order), PrimitiveKind::Quad),
             (self.paths_iter.peek().map(|q| q.order), PrimitiveKind::Path),
             (
@@ -293,6 +568,14 @@ impl<'a> Iterator for BatchIterator<'a> {
                 self.surfaces_iter.peek().map(|s| s.order),
                 PrimitiveKind::Surface,
             ),
+            (
+                self.shaders_iter.peek().map(|s| s.order),
+                PrimitiveKind::Shader,
+            ),
+            (
+                self.path_clips_iter.peek().map(|c| c.order),
+                PrimitiveKind::PathClip,
+            ),
         ];
         orders_and_kinds.sort_by_key(|(order, kind)| (order.unwrap_or(u32::MAX), *kind));
 
@@ -306,12 +589,16 @@ impl<'a> Iterator for BatchIterator<'a> {
 
         match batch_kind {
             PrimitiveKind::Shadow => {
+                let blend_mode = self.shadows_iter.peek().unwrap().blend_mode;
                 let shadows_start = self.shadows_start;
                 let mut shadows_end = shadows_start + 1;
                 self.shadows_iter.next();
                 while self
                     .shadows_iter
-                    .next_if(|shadow| (shadow.order, batch_kind) < max_order_and_kind)
+                    .next_if(|shadow| {
+                        (shadow.order, batch_kind) < max_order_and_kind
+                            && shadow.blend_mode == blend_mode
+                    })
                     .is_some()
                 {
                     shadows_end += 1;
@@ -321,13 +608,31 @@ impl<'a> Iterator for BatchIterator<'a> {
                     &self.shadows[shadows_start..shadows_end],
                 ))
             }
+            PrimitiveKind::Blur => {
+                let blurs_start = self.blurs_start;
+                let mut blurs_end = blurs_start + 1;
+                self.blurs_iter.next();
+                while self
+                    .blurs_iter
+                    .next_if(|blur| (blur.order, batch_kind) < max_order_and_kind)
+                    .is_some()
+                {
+                    blurs_end += 1;
+                }
+                self.blurs_start = blurs_end;
+                Some(PrimitiveBatch::Blurs(&self.blurs[blurs_start..blurs_end]))
+            }
             PrimitiveKind::Quad => {
+                let blend_mode = self.quads_iter.peek().unwrap().blend_mode;
                 let quads_start = self.quads_start;
                 let mut quads_end = quads_start + 1;
                 self.quads_iter.next();
                 while self
                     .quads_iter
-                    .next_if(|quad| (quad.order, batch_kind) < max_order_and_kind)
+                    .next_if(|quad| {
+                        (quad.order, batch_kind) < max_order_and_kind
+                            && quad.blend_mode == blend_mode
+                    })
                     .is_some()
                 {
                     quads_end += 1;
@@ -336,12 +641,16 @@ impl<'a> Iterator for BatchIterator<'a> {
                 Some(PrimitiveBatch::Quads(&self.quads[quads_start..quads_end]))
             }
             PrimitiveKind::Path => {
+                let blend_mode = self.paths_iter.peek().unwrap().blend_mode;
                 let paths_start = self.paths_start;
                 let mut paths_end = paths_start + 1;
                 self.paths_iter.next();
                 while self
                     .paths_iter
-                    .next_if(|path| (path.order, batch_kind) < max_order_and_kind)
+                    .next_if(|path| {
+                        (path.order, batch_kind) < max_order_and_kind
+                            && path.blend_mode == blend_mode
+                    })
                     .is_some()
                 {
                     paths_end += 1;
@@ -350,12 +659,16 @@ impl<'a> Iterator for BatchIterator<'a> {
                 Some(PrimitiveBatch::Paths(&self.paths[paths_start..paths_end]))
             }
             PrimitiveKind::Underline => {
+                let blend_mode = self.underlines_iter.peek().unwrap().blend_mode;
                 let underlines_start = self.underlines_start;
                 let mut underlines_end = underlines_start + 1;
                 self.underlines_iter.next();
                 while self
                     .underlines_iter
-                    .next_if(|underline| (underline.order, batch_kind) < max_order_and_kind)
+                    .next_if(|underline| {
+                        (underline.order, batch_kind) < max_order_and_kind
+                            && underline.blend_mode == blend_mode
+                    })
                     .is_some()
                 {
                     underlines_end += 1;
@@ -366,7 +679,8 @@ impl<'a> Iterator for BatchIterator<'a> {
                 ))
             }
             PrimitiveKind::MonochromeSprite => {
-                let texture_id = self.monochrome_sprites_iter.peek().unwrap().tile.texture_id;
+                let first = self.monochrome_sprites_iter.peek().unwrap();
+                let (texture_id, blend_mode) = (first.tile.texture_id, first.blend_mode);
                 let sprites_start = self.monochrome_sprites_start;
                 let mut sprites_end = sprites_start + 1;
                 self.monochrome_sprites_iter.next();
@@ -375,6 +689,7 @@ impl<'a> Iterator for BatchIterator<'a> {
                     .next_if(|sprite| {
                         (sprite.order, batch_kind) < max_order_and_kind
                             && sprite.tile.texture_id == texture_id
+                            && sprite.blend_mode == blend_mode
                     })
                     .is_some()
                 {
@@ -387,7 +702,8 @@ impl<'a> Iterator for BatchIterator<'a> {
                 })
             }
             PrimitiveKind::PolychromeSprite => {
-                let texture_id = self.polychrome_sprites_iter.peek().unwrap().tile.texture_id;
+                let first = self.polychrome_sprites_iter.peek().unwrap();
+                let (texture_id, blend_mode) = (first.tile.texture_id, first.blend_mode);
                 let sprites_start = self.polychrome_sprites_start;
                 let mut sprites_end = self.polychrome_sprites_start + 1;
                 self.polychrome_sprites_iter.next();
@@ -396,6 +712,7 @@ impl<'a> Iterator for BatchIterator<'a> {
                     .next_if(|sprite| {
                         (sprite.order, batch_kind) < max_order_and_kind
                             && sprite.tile.texture_id == texture_id
+                            && sprite.blend_mode == blend_mode
                     })
                     .is_some()
                 {
@@ -423,6 +740,48 @@ impl<'a> Iterator for BatchIterator<'a> {
                     &self.surfaces[surfaces_start..surfaces_end],
                 ))
             }
+            PrimitiveKind::Shader => {
+                let first = self.shaders_iter.peek().unwrap();
+                let (shader_id, blend_mode) = (first.shader.id, first.blend_mode);
+                let shaders_start = self.shaders_start;
+                let mut shaders_end = shaders_start + 1;
+                self.shaders_iter.next();
+                while self
+                    .shaders_iter
+                    .next_if(|shader| {
+                        (shader.order, batch_kind) < max_order_and_kind
+                            && shader.shader.id == shader_id
+                            && shader.blend_mode == blend_mode
+                    })
+                    .is_some()
+                {
+                    shaders_end += 1;
+                }
+                self.shaders_start = shaders_end;
+                Some(PrimitiveBatch::Shaders(
+                    &self.shaders[shaders_start..shaders_end],
+                ))
+            }
+            PrimitiveKind::PathClip => {
+                let is_end = self.path_clips_iter.peek().unwrap().path.is_some();
+                let path_clips_start = self.path_clips_start;
+                let mut path_clips_end = path_clips_start + 1;
+                self.path_clips_iter.next();
+                while self
+                    .path_clips_iter
+                    .next_if(|clip| {
+                        (clip.order, batch_kind) < max_order_and_kind
+                            && clip.path.is_some() == is_end
+                    })
+                    .is_some()
+                {
+                    path_clips_end += 1;
+                }
+                self.path_clips_start = path_clips_end;
+                Some(PrimitiveBatch::PathClips(
+                    &self.path_clips[path_clips_start..path_clips_end],
+                ))
+            }
         }
     }
 }
@@ -435,8 +794,11 @@ impl<'a> Iterator for BatchIterator<'a> {
     ),
     allow(dead_code)
 )]
+/// Primitives in a batch share their [`BlendMode`], so each batch is drawn with one pipeline.
 pub(crate) enum PrimitiveBatch<'a> {
     Shadows(&'a [Shadow]),
+    /// Blurs in a batch don't overlap, they are all applied to the same backdrop.
+    Blurs(&'a [Blur]),
     Quads(&'a [Quad]),
     Paths(&'a [Path<ScaledPixels>]),
     Underlines(&'a [Underline]),
@@ -449,13 +811,38 @@ pub(crate) enum PrimitiveBatch<'a> {
         sprites: &'a [PolychromeSprite],
     },
     Surfaces(&'a [PaintSurface]),
+    /// Shaders in a batch all share the same [`CustomShader`].
+    Shaders(&'a [PaintShader]),
+    /// Clips in a batch don't overlap, and either all start or all end.
+    PathClips(&'a [PathClip]),
+}
+
+/// How the colors painted by an element are combined with what was painted below it, similar
+/// to the CSS `mix-blend-mode` property.
+///
+/// Blend modes are applied by the GPU as primitives are drawn, so they don't need the element
+/// to be rendered offscreen first. They assume the content below is opaque. Renderers that
+/// don't support them paint every element with [`BlendMode::Normal`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[repr(u32)]
+pub enum BlendMode {
+    /// Paint over the content below.
+    #[default]
+    Normal,
+    /// Multiply the content below by the painted colors, which darkens it, e.g. for dimming.
+    Multiply,
+    /// Multiply the inverse of the colors, which lightens the content below.
+    Screen,
+    /// Add the painted colors to the content below, e.g. for glows. Same as `plus-lighter`
+    /// in CSS.
+    Additive,
 }
 
-#[derive(Default, Debug, Clone)]
+#[derive(Default, Debug, Clone, PartialEq)]
 #[repr(C)]
 pub(crate) struct Quad {
     pub order: DrawOrder,
-    pub pad: u32, // align to 8 bytes
+    pub blend_mode: BlendMode, // also aligns to 8 bytes
     pub bounds: Bounds<ScaledPixels>,
     pub content_mask: ContentMask<ScaledPixels>,
     pub background: Background,
@@ -470,11 +857,11 @@ impl From<Quad> for Primitive {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 #[repr(C)]
 pub(crate) struct Underline {
     pub order: DrawOrder,
-    pub pad: u32, // align to 8 bytes
+    pub blend_mode: BlendMode, // also aligns to 8 bytes
     pub bounds: Bounds<ScaledPixels>,
     pub content_mask: ContentMask<ScaledPixels>,
     pub color: Hsla,
@@ -488,11 +875,13 @@ impl From<Underline> for Primitive {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 #[repr(C)]
 pub(crate) struct Shadow {
     pub order: DrawOrder,
     pub blur_radius: ScaledPixels,
+    pub blend_mode: BlendMode,
+    pub pad: u32, // align to 8 bytes
     pub bounds: Bounds<ScaledPixels>,
     pub corner_radii: Corners<ScaledPixels>,
     pub content_mask: ContentMask<ScaledPixels>,
@@ -505,6 +894,23 @@ impl From<Shadow> for Primitive {
     }
 }
 
+/// A Gaussian blur of what was painted below `bounds`, `radius` is its standard deviation.
+#[derive(Debug, Clone, PartialEq)]
+#[repr(C)]
+pub(crate) struct Blur {
+    pub order: DrawOrder,
+    pub radius: ScaledPixels,
+    pub bounds: Bounds<ScaledPixels>,
+    pub corner_radii: Corners<ScaledPixels>,
+    pub content_mask: ContentMask<ScaledPixels>,
+}
+
+impl From<Blur> for Primitive {
+    fn from(blur: Blur) -> Self {
+        Primitive::Blur(blur)
+    }
+}
+
 /// A data type representing a 2 dimensional transformation that can be applied to an element.
 #[derive(Debug, Clone, Copy, PartialEq)]
 #[repr(C)]
@@ -608,11 +1014,11 @@ impl Default for TransformationMatrix {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 #[repr(C)]
 pub(crate) struct MonochromeSprite {
     pub order: DrawOrder,
-    pub pad: u32, // align to 8 bytes
+    pub blend_mode: BlendMode, // also aligns to 8 bytes
     pub bounds: Bounds<ScaledPixels>,
     pub content_mask: ContentMask<ScaledPixels>,
     pub color: Hsla,
@@ -626,11 +1032,11 @@ impl From<MonochromeSprite> for Primitive {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 #[repr(C)]
 pub(crate) struct PolychromeSprite {
     pub order: DrawOrder,
-    pub pad: u32, // align to 8 bytes
+    pub blend_mode: BlendMode, // also aligns to 8 bytes
     pub grayscale: bool,
     pub opacity: f32,
     pub bounds: Bounds<ScaledPixels>,
@@ -652,6 +1058,8 @@ pub(crate) struct PaintSurface {
     pub content_mask: ContentMask<ScaledPixels>,
     #[cfg(target_os = "macos")]
     pub image_buffer: media::core_video::CVImageBuffer,
+    #[cfg(all(target_os = "linux", feature = "wayland"))]
+    pub foreign_surface: crate::ForeignSurface,
 }
 
 impl From<PaintSurface> for Primitive {
@@ -660,11 +1068,50 @@ impl From<PaintSurface> for Primitive {
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct PaintShader {
+    pub order: DrawOrder,
+    pub bounds: Bounds<ScaledPixels>,
+    pub content_mask: ContentMask<ScaledPixels>,
+    pub shader: CustomShader,
+    pub mouse_position: Point<ScaledPixels>,
+    pub time: f32,
+    pub blend_mode: BlendMode,
+}
+
+impl From<PaintShader> for Primitive {
+    fn from(shader: PaintShader) -> Self {
+        Primitive::Shader(shader)
+    }
+}
+
+/// Identifies content clipped to a path, the start and the end of the clip share it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct ClipId(pub(crate) usize);
+
+/// The start or the end of content clipped to a path. Renderers copy what was painted below
+/// the content where it starts, and restore the copy outside of the path where it ends.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct PathClip {
+    pub order: DrawOrder,
+    pub id: ClipId,
+    pub bounds: Bounds<ScaledPixels>,
+    pub content_mask: ContentMask<ScaledPixels>,
+    /// `None` for the start of the clip, the path to clip the content to for its end.
+    pub path: Option<Path<ScaledPixels>>,
+}
+
+impl From<PathClip> for Primitive {
+    fn from(clip: PathClip) -> Self {
+        Primitive::PathClip(clip)
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub(crate) struct PathId(pub(crate) usize);
 
 /// A line made up of a series of vertices and control points.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Path<P: Clone + Default + Debug> {
     pub(crate) id: PathId,
     order: DrawOrder,
@@ -672,6 +1119,7 @@ pub struct Path<P: Clone + Default + Debug> {
     pub(crate) content_mask: ContentMask<P>,
     pub(crate) vertices: Vec<PathVertex<P>>,
     pub(crate) color: Background,
+    pub(crate) blend_mode: BlendMode,
     start: Point<P>,
     current: Point<P>,
     contour_count: usize,
@@ -692,6 +1140,7 @@ impl Path<Pixels> {
             },
             content_mask: Default::default(),
             color: Default::default(),
+            blend_mode: BlendMode::Normal,
             contour_count: 0,
         }
     }
@@ -712,6 +1161,7 @@ impl Path<Pixels> {
             current: self.current.scale(factor),
             contour_count: self.contour_count,
             color: self.color,
+            blend_mode: self.blend_mode,
         }
     }
 
@@ -796,7 +1246,7 @@ impl From<Path<ScaledPixels>> for Primitive {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 #[repr(C)]
 pub(crate) struct PathVertex<P: Clone + Default + Debug> {
     pub(crate) xy_position: Point<P>,
@@ -813,3 +1263,261 @@ impl PathVertex<Pixels> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{px, size};
+
+    fn rect(x: f32, y: f32, width: f32, height: f32) -> Bounds<ScaledPixels> {
+        Bounds::new(
+            point(ScaledPixels(x), ScaledPixels(y)),
+            size(ScaledPixels(width), ScaledPixels(height)),
+        )
+    }
+
+    fn monochrome_sprite(x: f32, texture_index: u32, tile_id: u32) -> MonochromeSprite {
+        let bounds = rect(x, 0., 10., 10.);
+        MonochromeSprite {
+            order: 0,
+            blend_mode: BlendMode::Normal,
+            bounds,
+            content_mask: ContentMask { bounds },
+            color: Hsla::default(),
+            tile: AtlasTile {
+                texture_id: AtlasTextureId {
+                    index: texture_index,
+                    kind: crate::AtlasTextureKind::Monochrome,
+                },
+                tile_id: crate::TileId(tile_id),
+                padding: 0,
+                bounds: Bounds::default(),
+            },
+            transformation: TransformationMatrix::unit(),
+        }
+    }
+
+    #[test]
+    fn test_sprites_are_batched_by_texture() {
+        let mut scene = Scene::default();
+        scene.insert_primitive(monochrome_sprite(0., 0, 1));
+        scene.insert_primitive(monochrome_sprite(20., 1, 2));
+        scene.insert_primitive(monochrome_sprite(40., 0, 3));
+        scene.finish();
+
+        let batch_lengths = scene
+            .batches()
+            .map(|batch| match batch {
+                PrimitiveBatch::MonochromeSprites { sprites, .. } => sprites.len(),
+                _ => panic!("expected monochrome sprites"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(batch_lengths, [2, 1]);
+    }
+
+    #[test]
+    fn test_batches_are_split_by_blend_mode() {
+        let mut scene = Scene::default();
+        scene.insert_primitive(monochrome_sprite(0., 0, 1));
+        scene.insert_primitive(MonochromeSprite {
+            blend_mode: BlendMode::Additive,
+            ..monochrome_sprite(20., 0, 2)
+        });
+        scene.insert_primitive(monochrome_sprite(40., 0, 3));
+        scene.finish();
+
+        let batches = scene
+            .batches()
+            .map(|batch| match batch {
+                PrimitiveBatch::MonochromeSprites { sprites, .. } => {
+                    (sprites[0].blend_mode, sprites.len())
+                }
+                _ => panic!("expected monochrome sprites"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(batches, [(BlendMode::Normal, 2), (BlendMode::Additive, 1)]);
+    }
+
+    #[test]
+    fn test_path_clips_surround_their_content_within_layers() {
+        let bounds = rect(0., 0., 100., 100.);
+        let clip = |path| PathClip {
+            order: 0,
+            id: ClipId(0),
+            bounds,
+            content_mask: ContentMask { bounds },
+            path,
+        };
+        let mut scene = Scene::default();
+        scene.push_layer(bounds);
+        scene.insert_primitive(clip(None));
+        scene.insert_primitive(monochrome_sprite(0., 0, 1));
+        scene.insert_primitive(clip(Some(Path::new(point(px(0.), px(0.))).scale(1.))));
+        scene.pop_layer();
+        scene.finish();
+
+        let batches = scene
+            .batches()
+            .map(|batch| match batch {
+                PrimitiveBatch::PathClips(clips) if clips[0].path.is_none() => "start",
+                PrimitiveBatch::PathClips(_) => "end",
+                PrimitiveBatch::MonochromeSprites { .. } => "content",
+                _ => panic!("unexpected batch"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(batches, ["start", "content", "end"]);
+        assert_eq!(scene.path_clips[0].id, scene.path_clips[1].id);
+    }
+
+    fn scene_of(sprites: impl IntoIterator<Item = MonochromeSprite>) -> Scene {
+        let mut scene = Scene::default();
+        for sprite in sprites {
+            scene.insert_primitive(sprite);
+        }
+        scene.finish();
+        scene
+    }
+
+    #[test]
+    fn test_unchanged_scene_has_no_damage() {
+        let sprites = || [monochrome_sprite(0., 0, 1), monochrome_sprite(20., 0, 2)];
+        assert_eq!(scene_of(sprites()).diff(&scene_of(sprites())), Damage::None);
+    }
+
+    #[test]
+    fn test_changed_primitive_damages_its_bounds() {
+        let previous = scene_of([
+            monochrome_sprite(0., 0, 1),
+            monochrome_sprite(20., 0, 2),
+            monochrome_sprite(40., 0, 3),
+        ]);
+        let scene = scene_of([
+            monochrome_sprite(0., 0, 1),
+            MonochromeSprite {
+                color: crate::black(),
+                ..monochrome_sprite(20., 0, 2)
+            },
+            monochrome_sprite(40., 0, 3),
+        ]);
+        assert_eq!(
+            scene.diff(&previous),
+            Damage::Partial(vec![rect(20., 0., 10., 10.)])
+        );
+    }
+
+    #[test]
+    fn test_added_and_removed_primitives_are_damaged() {
+        let two = scene_of([monochrome_sprite(0., 0, 1), monochrome_sprite(20., 0, 2)]);
+        let three = scene_of([
+            monochrome_sprite(0., 0, 1),
+            monochrome_sprite(20., 0, 2),
+            monochrome_sprite(40., 0, 3),
+        ]);
+        assert_eq!(
+            three.diff(&two),
+            Damage::Partial(vec![rect(40., 0., 10., 10.)])
+        );
+        assert_eq!(
+            two.diff(&three),
+            Damage::Partial(vec![rect(40., 0., 10., 10.)])
+        );
+
+        // Removing a primitive shifts the ones painted after it, which are damaged too.
+        let without_middle = scene_of([monochrome_sprite(0., 0, 1), monochrome_sprite(40., 0, 3)]);
+        assert_eq!(
+            without_middle.diff(&three),
+            Damage::Partial(vec![rect(20., 0., 10., 10.), rect(40., 0., 10., 10.)])
+        );
+    }
+
+    #[test]
+    fn test_damage_union_merges_overlapping_areas() {
+        let previous = Damage::Partial(vec![rect(0., 0., 10., 10.), rect(100., 0., 10., 10.)]);
+        let current = Damage::Partial(vec![rect(5., 5., 10., 10.), rect(200., 0., 10., 10.)]);
+        assert_eq!(
+            previous.union(&current),
+            Damage::Partial(vec![
+                rect(100., 0., 10., 10.),
+                rect(0., 0., 15., 15.),
+                rect(200., 0., 10., 10.),
+            ])
+        );
+        assert_eq!(Damage::None.union(&current), current);
+        assert_eq!(current.union(&Damage::None), current);
+        assert_eq!(current.union(&Damage::Full), Damage::Full);
+        assert_eq!(Damage::None.union(&Damage::None), Damage::None);
+    }
+
+    #[test]
+    fn test_damage_bounds_contain_all_areas() {
+        let damage = Damage::Partial(vec![rect(0., 10., 10., 10.), rect(30., 0., 10., 5.)]);
+        assert_eq!(damage.bounds(), Some(rect(0., 0., 40., 20.)));
+        assert_eq!(Damage::Full.bounds(), None);
+        assert_eq!(Damage::None.bounds(), None);
+    }
+
+    #[test]
+    fn test_blurs_are_damaged_by_content_below() {
+        let blur = Blur {
+            order: 0,
+            radius: ScaledPixels(2.),
+            bounds: rect(0., 0., 10., 10.),
+            corner_radii: Corners::default(),
+            content_mask: ContentMask {
+                bounds: rect(0., 0., 10., 10.),
+            },
+        };
+        let mut previous = Scene::default();
+        previous.insert_primitive(monochrome_sprite(100., 0, 1));
+        previous.insert_primitive(blur.clone());
+        previous.finish();
+
+        // A sprite within the reach of the blur changes what the blur shows.
+        let mut scene = Scene::default();
+        scene.insert_primitive(monochrome_sprite(12., 0, 1));
+        scene.insert_primitive(blur);
+        scene.finish();
+        assert_eq!(
+            scene.diff(&previous),
+            Damage::Partial(vec![
+                rect(12., 0., 10., 10.),
+                rect(100., 0., 10., 10.),
+                rect(0., 0., 10., 10.),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_damage_keeps_separate_areas() {
+        let mut damage = Damage::None;
+        damage.add(rect(0., 0., 10., 10.));
+        damage.add(rect(100., 0., 10., 10.));
+        assert_eq!(
+            damage,
+            Damage::Partial(vec![rect(0., 0., 10., 10.), rect(100., 0., 10., 10.)])
+        );
+
+        // Overlapping both areas merges them into one.
+        damage.add(rect(5., 5., 100., 1.));
+        assert_eq!(damage, Damage::Partial(vec![rect(0., 0., 110., 10.)]));
+    }
+
+    #[test]
+    fn test_damage_merges_areas_past_the_limit() {
+        let mut damage = Damage::None;
+        for ix in 0..MAX_DAMAGE_RECTS + 4 {
+            damage.add(rect(ix as f32 * 20., 0., 10., 10.));
+        }
+        let Damage::Partial(rects) = damage else {
+            panic!("expected partial damage");
+        };
+        assert_eq!(rects.len(), MAX_DAMAGE_RECTS);
+        let covered = rects
+            .iter()
+            .fold(rects[0], |covered, rect| covered.union(rect));
+        assert_eq!(
+            covered,
+            rect(0., 0., (MAX_DAMAGE_RECTS + 3) as f32 * 20. + 10., 10.)
+        );
+    }
+}
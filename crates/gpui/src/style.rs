@@ -5,9 +5,9 @@ use std::{
 };
 
 use crate::{
-    black, phi, point, quad, rems, size, AbsoluteLength, App, Background, BackgroundTag, Bounds,
-    ContentMask, Corners, CornersRefinement, CursorStyle, DefiniteLength, DevicePixels, Edges,
-    EdgesRefinement, Font, FontFallbacks, FontFeatures, FontStyle, FontWeight, Hsla, Length,
+    black, phi, point, quad, rems, size, AbsoluteLength, App, Background, BackgroundTag, BlendMode,
+    Bounds, ContentMask, Corners, CornersRefinement, CursorStyle, DefiniteLength, DevicePixels,
+    Edges, EdgesRefinement, Font, FontFallbacks, FontFeatures, FontStyle, FontWeight, Hsla, Length,
     Pixels, Point, PointRefinement, Rgba, SharedString, Size, SizeRefinement, Styled, TextRun,
     Window,
 };
@@ -15,8 +15,8 @@ use collections::HashSet;
 use refineable::Refineable;
 use smallvec::SmallVec;
 pub use taffy::style::{
-    AlignContent, AlignItems, AlignSelf, Display, FlexDirection, FlexWrap, JustifyContent,
-    Overflow, Position,
+    AlignContent, AlignItems, AlignSelf, Display, FlexDirection, FlexWrap, GridAutoFlow,
+    JustifyContent, Overflow, Position,
 };
 
 /// Use this struct for interfacing with the 'debug_below' styling from your own elements.
@@ -222,7 +222,7 @@ pub struct Style {
     pub align_content: Option<AlignContent>,
     /// How should contained within this item be aligned in the main/inline axis
     pub justify_content: Option<JustifyContent>,
-    /// How large should the gaps between items in a flex container be?
+    /// How large should the gaps between items in a flex or grid container be?
     #[refineable]
     pub gap: Size<DefiniteLength>,
 
@@ -238,6 +238,22 @@ pub struct Style {
     /// The relative rate at which this item shrinks when it is contracting to fit into space, 1.0 is the default value, and this value must be positive.
     pub flex_shrink: f32,
 
+    // Grid properties
+    /// The sizes of the columns of a grid container
+    pub grid_template_columns: Vec<GridTemplateTrack>,
+    /// The sizes of the rows of a grid container
+    pub grid_template_rows: Vec<GridTemplateTrack>,
+    /// The size of the columns a grid container creates for items placed beyond its template
+    pub grid_auto_columns: GridTrack,
+    /// The size of the rows a grid container creates for items placed beyond its template
+    pub grid_auto_rows: GridTrack,
+    /// Whether a grid container places items by filling in rows or columns
+    pub grid_auto_flow: GridAutoFlow,
+    /// Which columns this item spans in a grid container
+    pub grid_column: GridLocation,
+    /// Which rows this item spans in a grid container
+    pub grid_row: GridLocation,
+
     /// The fill color of this element
     pub background: Option<Fill>,
 
@@ -251,6 +267,9 @@ pub struct Style {
     /// Box Shadow of the element
     pub box_shadow: SmallVec<[BoxShadow; 2]>,
 
+    /// The radius of the blur applied to what is painted below this element
+    pub backdrop_blur: Option<Pixels>,
+
     /// The text style of this element
     pub text: TextStyleRefinement,
 
@@ -260,6 +279,9 @@ pub struct Style {
     /// The opacity of this element
     pub opacity: Option<f32>,
 
+    /// How this element and its children are blended with what is painted below them
+    pub blend_mode: Option<BlendMode>,
+
     /// Whether to draw a red debugging outline around this element
     #[cfg(debug_assertions)]
     pub debug: bool,
@@ -285,6 +307,77 @@ pub enum Visibility {
     Hidden,
 }
 
+/// The size of a row or column of a grid, similar to a track size of the CSS
+/// `grid-template-columns` property.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum GridTrack {
+    /// The track has a definite length.
+    Length(DefiniteLength),
+    /// The track takes a share of the free space, like `1fr` in CSS, but never gets smaller
+    /// than its content.
+    Fraction(f32),
+    /// The track is sized to fit its content, and grows into free space.
+    #[default]
+    Auto,
+    /// The track is as small as its content can get, like `min-content` in CSS.
+    MinContent,
+    /// The track is as large as its content wants to be, like `max-content` in CSS.
+    MaxContent,
+    /// The track is at least the given length, and takes the given share of the free space
+    /// beyond it, like `minmax(length, 1fr)` in CSS.
+    MinMax(DefiniteLength, f32),
+}
+
+/// How many times a [`GridTemplateTrack::Repeat`] repeats its tracks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GridRepetition {
+    /// The tracks are repeated the given number of times.
+    Count(u16),
+    /// The tracks are repeated as many times as they fit, like `auto-fill` in CSS.
+    AutoFill,
+    /// Like [`GridRepetition::AutoFill`], but repetitions without items collapse, like
+    /// `auto-fit` in CSS.
+    AutoFit,
+}
+
+/// An entry of a grid template, like those of the CSS `grid-template-columns` property.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GridTemplateTrack {
+    /// A single track.
+    Track(GridTrack),
+    /// Tracks repeated a number of times, like `repeat(3, 1fr)` in CSS.
+    Repeat(GridRepetition, Vec<GridTrack>),
+}
+
+impl From<GridTrack> for GridTemplateTrack {
+    fn from(track: GridTrack) -> Self {
+        Self::Track(track)
+    }
+}
+
+/// One end of a [`GridLocation`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GridPlacement {
+    /// The item is placed automatically.
+    #[default]
+    Auto,
+    /// The item starts or ends at the given line, counting from 1. Negative lines count from
+    /// the last line of the template, -1 being the last one.
+    Line(i16),
+    /// The item spans the given number of tracks from its other end.
+    Span(u16),
+}
+
+/// Where a grid item is placed along the rows or columns of its container, similar to the CSS
+/// `grid-row` and `grid-column` properties.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GridLocation {
+    /// The line the item starts at.
+    pub start: GridPlacement,
+    /// The line the item ends at.
+    pub end: GridPlacement,
+}
+
 /// The possible values of the box-shadow property
 #[derive(Clone, Debug)]
 pub struct BoxShadow {
@@ -309,10 +402,18 @@ pub enum WhiteSpace {
 }
 
 /// How to truncate text that overflows the width of the element
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum TextOverflow {
     /// Truncate the text with an ellipsis, same as: `text-overflow: ellipsis;` in CSS
     Ellipsis(&'static str),
+    /// Truncate the start of the text, replacing it with an ellipsis, which keeps the end of
+    /// long file paths visible
+    EllipsisStart(&'static str),
+    /// Truncate the middle of the text, replacing it with an ellipsis, which keeps both the
+    /// start and the end of window titles visible
+    EllipsisMiddle(&'static str),
+    /// Cut the text off at the end without an ellipsis, fading it out over the given width
+    Fade(Pixels),
 }
 
 /// How to align text within the element
@@ -613,6 +714,14 @@ impl Style {
             &self.box_shadow,
         );
 
+        if let Some(radius) = self.backdrop_blur {
+            window.paint_backdrop_blur(
+                bounds,
+                self.corner_radii.to_pixels(bounds.size, rem_size),
+                radius,
+            );
+        }
+
         let background_color = self.background.as_ref().and_then(Fill::color);
         if background_color.map_or(false, |color| !color.is_transparent()) {
             let mut border_color = match background_color {
@@ -747,13 +856,23 @@ impl Default for Style {
             flex_grow: 0.0,
             flex_shrink: 1.0,
             flex_basis: Length::Auto,
+            // Grid
+            grid_template_columns: Vec::new(),
+            grid_template_rows: Vec::new(),
+            grid_auto_columns: GridTrack::Auto,
+            grid_auto_rows: GridTrack::Auto,
+            grid_auto_flow: GridAutoFlow::Row,
+            grid_column: GridLocation::default(),
+            grid_row: GridLocation::default(),
             background: None,
             border_color: None,
             corner_radii: Corners::default(),
             box_shadow: Default::default(),
+            backdrop_blur: None,
             text: TextStyleRefinement::default(),
             mouse_cursor: None,
             opacity: None,
+            blend_mode: None,
 
             #[cfg(debug_assertions)]
             debug: false,
@@ -1,7 +1,9 @@
 use crate::{
-    self as gpui, px, relative, rems, AbsoluteLength, AlignItems, CursorStyle, DefiniteLength,
-    Fill, FlexDirection, FlexWrap, Font, FontStyle, FontWeight, Hsla, JustifyContent, Length,
-    SharedString, StrikethroughStyle, StyleRefinement, TextOverflow, UnderlineStyle, WhiteSpace,
+    self as gpui, px, relative, rems, AbsoluteLength, AlignItems, BlendMode, CursorStyle,
+    DefiniteLength, Fill, FlexDirection, FlexWrap, Font, FontStyle, FontWeight, GridAutoFlow,
+    GridPlacement, GridRepetition, GridTemplateTrack, GridTrack, Hsla, JustifyContent, Length,
+    Pixels, SharedString, StrikethroughStyle, StyleRefinement, TextOverflow, UnderlineStyle,
+    WhiteSpace,
 };
 use crate::{TextAlign, TextStyleRefinement};
 pub use gpui_macros::{
@@ -13,6 +15,15 @@ use taffy::style::{AlignContent, Display};
 
 const ELLIPSIS: &str = "…";
 
+/// The given number of tracks sharing the space equally, like `repeat(count, minmax(0, 1fr))`
+/// in CSS. Unlike plain fractions, the tracks stay equal when their content doesn't fit.
+fn equal_tracks(count: u16) -> GridTemplateTrack {
+    GridTemplateTrack::Repeat(
+        GridRepetition::Count(count),
+        vec![GridTrack::MinMax(px(0.).into(), 1.)],
+    )
+}
+
 /// A trait for elements that can be styled.
 /// Use this to opt-in to a utility CSS-like styling API.
 pub trait Styled: Sized {
@@ -70,6 +81,30 @@ pub trait Styled: Sized {
         self
     }
 
+    /// Sets the truncate overflowing text at its start with an ellipsis (…) if needed.
+    fn text_ellipsis_start(mut self) -> Self {
+        self.text_style()
+            .get_or_insert_with(Default::default)
+            .text_overflow = Some(TextOverflow::EllipsisStart(ELLIPSIS));
+        self
+    }
+
+    /// Sets the truncate overflowing text in its middle with an ellipsis (…) if needed.
+    fn text_ellipsis_middle(mut self) -> Self {
+        self.text_style()
+            .get_or_insert_with(Default::default)
+            .text_overflow = Some(TextOverflow::EllipsisMiddle(ELLIPSIS));
+        self
+    }
+
+    /// Sets the cut overflowing text off, fading out its end over the given width.
+    fn text_fade(mut self, fade_width: impl Into<Pixels>) -> Self {
+        self.text_style()
+            .get_or_insert_with(Default::default)
+            .text_overflow = Some(TextOverflow::Fade(fade_width.into()));
+        self
+    }
+
     /// Sets the text overflow behavior of the element.
     fn text_overflow(mut self, overflow: TextOverflow) -> Self {
         self.text_style()
@@ -227,6 +262,162 @@ pub trait Styled: Sized {
         self
     }
 
+    /// Sets the display type of the element to `grid`.
+    /// [Docs](https://tailwindcss.com/docs/display#grid)
+    fn grid(mut self) -> Self {
+        self.style().display = Some(Display::Grid);
+        self
+    }
+
+    /// Sets the grid to have the given number of equally sized columns.
+    /// [Docs](https://tailwindcss.com/docs/grid-template-columns)
+    fn grid_cols(self, count: u16) -> Self {
+        self.grid_template_columns([equal_tracks(count)])
+    }
+
+    /// Sets the grid to have the given number of equally sized rows.
+    /// [Docs](https://tailwindcss.com/docs/grid-template-rows)
+    fn grid_rows(self, count: u16) -> Self {
+        self.grid_template_rows([equal_tracks(count)])
+    }
+
+    /// Sets the sizes of the columns of the grid.
+    /// [Docs](https://developer.mozilla.org/en-US/docs/Web/CSS/grid-template-columns)
+    fn grid_template_columns(
+        mut self,
+        tracks: impl IntoIterator<Item = impl Into<GridTemplateTrack>>,
+    ) -> Self {
+        self.style().grid_template_columns = Some(tracks.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets the sizes of the rows of the grid.
+    /// [Docs](https://developer.mozilla.org/en-US/docs/Web/CSS/grid-template-rows)
+    fn grid_template_rows(
+        mut self,
+        tracks: impl IntoIterator<Item = impl Into<GridTemplateTrack>>,
+    ) -> Self {
+        self.style().grid_template_rows = Some(tracks.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Sets the size of the columns the grid creates for items placed beyond its template.
+    /// [Docs](https://tailwindcss.com/docs/grid-auto-columns)
+    fn grid_auto_columns(mut self, track: GridTrack) -> Self {
+        self.style().grid_auto_columns = Some(track);
+        self
+    }
+
+    /// Sets the size of the rows the grid creates for items placed beyond its template.
+    /// [Docs](https://tailwindcss.com/docs/grid-auto-rows)
+    fn grid_auto_rows(mut self, track: GridTrack) -> Self {
+        self.style().grid_auto_rows = Some(track);
+        self
+    }
+
+    /// Sets the grid to place items by filling in each row in turn, which is the default.
+    /// [Docs](https://tailwindcss.com/docs/grid-auto-flow)
+    fn grid_flow_row(mut self) -> Self {
+        self.style().grid_auto_flow = Some(GridAutoFlow::Row);
+        self
+    }
+
+    /// Sets the grid to place items by filling in each column in turn.
+    /// [Docs](https://tailwindcss.com/docs/grid-auto-flow)
+    fn grid_flow_col(mut self) -> Self {
+        self.style().grid_auto_flow = Some(GridAutoFlow::Column);
+        self
+    }
+
+    /// Sets the grid to fill in holes left by earlier items with later items that fit.
+    /// [Docs](https://tailwindcss.com/docs/grid-auto-flow)
+    fn grid_flow_dense(mut self) -> Self {
+        self.style().grid_auto_flow = Some(GridAutoFlow::RowDense);
+        self
+    }
+
+    /// Sets the grid item to span the given number of columns.
+    /// [Docs](https://tailwindcss.com/docs/grid-column#spanning-columns)
+    fn col_span(mut self, count: u16) -> Self {
+        let column = self
+            .style()
+            .grid_column
+            .get_or_insert_with(Default::default);
+        column.start = GridPlacement::Span(count);
+        column.end = GridPlacement::Auto;
+        self
+    }
+
+    /// Sets the grid item to span all the columns of the template.
+    /// [Docs](https://tailwindcss.com/docs/grid-column#spanning-columns)
+    fn col_span_full(mut self) -> Self {
+        let column = self
+            .style()
+            .grid_column
+            .get_or_insert_with(Default::default);
+        column.start = GridPlacement::Line(1);
+        column.end = GridPlacement::Line(-1);
+        self
+    }
+
+    /// Sets the column line the grid item starts at, counting from 1.
+    /// [Docs](https://tailwindcss.com/docs/grid-column#starting-and-ending-lines)
+    fn col_start(mut self, line: i16) -> Self {
+        self.style()
+            .grid_column
+            .get_or_insert_with(Default::default)
+            .start = GridPlacement::Line(line);
+        self
+    }
+
+    /// Sets the column line the grid item ends at, counting from 1.
+    /// [Docs](https://tailwindcss.com/docs/grid-column#starting-and-ending-lines)
+    fn col_end(mut self, line: i16) -> Self {
+        self.style()
+            .grid_column
+            .get_or_insert_with(Default::default)
+            .end = GridPlacement::Line(line);
+        self
+    }
+
+    /// Sets the grid item to span the given number of rows.
+    /// [Docs](https://tailwindcss.com/docs/grid-row#spanning-rows)
+    fn row_span(mut self, count: u16) -> Self {
+        let row = self.style().grid_row.get_or_insert_with(Default::default);
+        row.start = GridPlacement::Span(count);
+        row.end = GridPlacement::Auto;
+        self
+    }
+
+    /// Sets the grid item to span all the rows of the template.
+    /// [Docs](https://tailwindcss.com/docs/grid-row#spanning-rows)
+    fn row_span_full(mut self) -> Self {
+        let row = self.style().grid_row.get_or_insert_with(Default::default);
+        row.start = GridPlacement::Line(1);
+        row.end = GridPlacement::Line(-1);
+        self
+    }
+
+    /// Sets the row line the grid item starts at, counting from 1.
+    /// [Docs](https://tailwindcss.com/docs/grid-row#starting-and-ending-lines)
+    fn row_start(mut self, line: i16) -> Self {
+        self.style()
+            .grid_row
+            .get_or_insert_with(Default::default)
+            .start = GridPlacement::Line(line);
+        self
+    }
+
+    /// Sets the row line the grid item ends at, counting from 1.
+    /// [Docs](https://tailwindcss.com/docs/grid-row#starting-and-ending-lines)
+    fn row_end(mut self, line: i16) -> Self {
+        self.style()
+            .grid_row
+            .get_or_insert_with(Default::default)
+            .end = GridPlacement::Line(line);
+        self
+    }
+
     /// Sets the element to align flex items to the start of the container's cross axis.
     /// [Docs](https://tailwindcss.com/docs/align-items#start)
     fn items_start(mut self) -> Self {
@@ -631,6 +822,21 @@ pub trait Styled: Sized {
         self
     }
 
+    /// Sets how this element and its children are blended with what is painted below them,
+    /// e.g. [`BlendMode::Additive`] for glows or [`BlendMode::Multiply`] for dimming.
+    /// [Docs](https://tailwindcss.com/docs/mix-blend-mode)
+    fn blend_mode(mut self, blend_mode: BlendMode) -> Self {
+        self.style().blend_mode = Some(blend_mode);
+        self
+    }
+
+    /// Blurs what is painted below this element, like frosted glass. The radius is the
+    /// standard deviation of the blur.
+    fn backdrop_blur(mut self, radius: impl Into<Pixels>) -> Self {
+        self.style().backdrop_blur = Some(radius.into());
+        self
+    }
+
     /// Draws a debug border around this element.
     #[cfg(debug_assertions)]
     fn debug(mut self) -> Self {
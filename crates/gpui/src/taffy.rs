@@ -1,12 +1,18 @@
 use crate::{
-    AbsoluteLength, App, Bounds, DefiniteLength, Edges, Length, Pixels, Point, Size, Style, Window,
+    AbsoluteLength, App, Bounds, DefiniteLength, Edges, GridLocation, GridPlacement,
+    GridRepetition, GridTemplateTrack, GridTrack, Length, Pixels, Point, Size, Style, Window,
 };
 use collections::{FxHashMap, FxHashSet};
 use smallvec::SmallVec;
 use std::fmt::Debug;
 use taffy::{
-    geometry::{Point as TaffyPoint, Rect as TaffyRect, Size as TaffySize},
-    style::AvailableSpace as TaffyAvailableSpace,
+    geometry::{Line as TaffyLine, Point as TaffyPoint, Rect as TaffyRect, Size as TaffySize},
+    style::{
+        AvailableSpace as TaffyAvailableSpace, GridPlacement as TaffyGridPlacement,
+        GridTrackRepetition, MaxTrackSizingFunction, MinMax, MinTrackSizingFunction,
+        NonRepeatedTrackSizingFunction, TrackSizingFunction,
+    },
+    style_helpers::line,
     tree::NodeId,
     TaffyTree, TraversePartialTree as _,
 };
@@ -136,6 +142,7 @@ impl TaffyLayoutEngine {
         Ok(edges)
     }
 
+    #[profiling::function]
     pub fn compute_layout(
         &mut self,
         id: LayoutId,
@@ -267,7 +274,89 @@ impl ToTaffy<taffy::style::Style> for Style {
             flex_basis: self.flex_basis.to_taffy(rem_size),
             flex_grow: self.flex_grow,
             flex_shrink: self.flex_shrink,
-            ..Default::default() // Ignore grid properties for now
+            grid_template_columns: self
+                .grid_template_columns
+                .iter()
+                .map(|track| track.to_taffy(rem_size))
+                .collect(),
+            grid_template_rows: self
+                .grid_template_rows
+                .iter()
+                .map(|track| track.to_taffy(rem_size))
+                .collect(),
+            grid_auto_columns: vec![self.grid_auto_columns.to_taffy(rem_size)],
+            grid_auto_rows: vec![self.grid_auto_rows.to_taffy(rem_size)],
+            grid_auto_flow: self.grid_auto_flow,
+            grid_column: self.grid_column.to_taffy(rem_size),
+            grid_row: self.grid_row.to_taffy(rem_size),
+            ..Default::default()
+        }
+    }
+}
+
+impl ToTaffy<NonRepeatedTrackSizingFunction> for GridTrack {
+    fn to_taffy(&self, rem_size: Pixels) -> NonRepeatedTrackSizingFunction {
+        let (min, max) = match self {
+            GridTrack::Length(length) => {
+                let length = length.to_taffy(rem_size);
+                (
+                    MinTrackSizingFunction::Fixed(length),
+                    MaxTrackSizingFunction::Fixed(length),
+                )
+            }
+            GridTrack::Fraction(fraction) => (
+                MinTrackSizingFunction::Auto,
+                MaxTrackSizingFunction::Fraction(*fraction),
+            ),
+            GridTrack::Auto => (MinTrackSizingFunction::Auto, MaxTrackSizingFunction::Auto),
+            GridTrack::MinContent => (
+                MinTrackSizingFunction::MinContent,
+                MaxTrackSizingFunction::MinContent,
+            ),
+            GridTrack::MaxContent => (
+                MinTrackSizingFunction::MaxContent,
+                MaxTrackSizingFunction::MaxContent,
+            ),
+            GridTrack::MinMax(min, fraction) => (
+                MinTrackSizingFunction::Fixed(min.to_taffy(rem_size)),
+                MaxTrackSizingFunction::Fraction(*fraction),
+            ),
+        };
+        MinMax { min, max }
+    }
+}
+
+impl ToTaffy<TrackSizingFunction> for GridTemplateTrack {
+    fn to_taffy(&self, rem_size: Pixels) -> TrackSizingFunction {
+        match self {
+            GridTemplateTrack::Track(track) => {
+                TrackSizingFunction::Single(track.to_taffy(rem_size))
+            }
+            GridTemplateTrack::Repeat(repetition, tracks) => TrackSizingFunction::Repeat(
+                match repetition {
+                    GridRepetition::Count(count) => GridTrackRepetition::Count(*count),
+                    GridRepetition::AutoFill => GridTrackRepetition::AutoFill,
+                    GridRepetition::AutoFit => GridTrackRepetition::AutoFit,
+                },
+                tracks
+                    .iter()
+                    .map(|track| track.to_taffy(rem_size))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl ToTaffy<TaffyLine<TaffyGridPlacement>> for GridLocation {
+    fn to_taffy(&self, _rem_size: Pixels) -> TaffyLine<TaffyGridPlacement> {
+        let placement = |placement: GridPlacement| match placement {
+            GridPlacement::Auto => TaffyGridPlacement::Auto,
+            GridPlacement::Line(index) => line(index),
+            GridPlacement::Span(count) => TaffyGridPlacement::Span(count),
+        };
+        TaffyLine {
+            start: placement(self.start),
+            end: placement(self.end),
         }
     }
 }
@@ -494,3 +583,104 @@ impl From<Size<Pixels>> for Size<AvailableSpace> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bounds, point, px, size, Display};
+
+    /// Lays out a grid container with the given children, returning the bounds of the children.
+    fn layout_grid(container: Style, children: Vec<Style>) -> Vec<Bounds<Pixels>> {
+        let mut engine = TaffyLayoutEngine::new();
+        let rem_size = px(16.);
+        let children = children
+            .into_iter()
+            .map(|style| engine.request_layout(style, rem_size, &[]))
+            .collect::<Vec<_>>();
+        let root = engine.request_layout(
+            Style {
+                display: Display::Grid,
+                ..container
+            },
+            rem_size,
+            &children,
+        );
+        let available_space: Size<AvailableSpace> = size(px(1000.), px(1000.)).into();
+        engine
+            .taffy
+            .compute_layout(root.into(), available_space.into())
+            .unwrap();
+        children
+            .into_iter()
+            .map(|child| engine.layout_bounds(child))
+            .collect()
+    }
+
+    fn row_item(grid_column: GridLocation) -> Style {
+        Style {
+            size: size(Length::Auto, px(20.).into()),
+            grid_column,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_grid_column_template() {
+        let container = Style {
+            size: size(px(400.).into(), Length::Auto),
+            grid_template_columns: vec![
+                GridTrack::Length(px(100.).into()).into(),
+                GridTrack::Fraction(1.).into(),
+                GridTrack::Fraction(2.).into(),
+            ],
+            ..Default::default()
+        };
+        let children = vec![row_item(GridLocation::default()); 4];
+        assert_eq!(
+            layout_grid(container, children),
+            [
+                bounds(point(px(0.), px(0.)), size(px(100.), px(20.))),
+                bounds(point(px(100.), px(0.)), size(px(100.), px(20.))),
+                bounds(point(px(200.), px(0.)), size(px(200.), px(20.))),
+                // Items past the template wrap to the next row.
+                bounds(point(px(0.), px(20.)), size(px(100.), px(20.))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_grid_spans_and_gaps() {
+        let container = Style {
+            size: size(px(320.).into(), Length::Auto),
+            gap: size(px(10.).into(), px(10.).into()),
+            grid_template_columns: vec![GridTemplateTrack::Repeat(
+                GridRepetition::Count(3),
+                vec![GridTrack::MinMax(px(0.).into(), 1.)],
+            )],
+            ..Default::default()
+        };
+        let children = vec![
+            row_item(GridLocation {
+                start: GridPlacement::Auto,
+                end: GridPlacement::Span(2),
+            }),
+            row_item(GridLocation::default()),
+            row_item(GridLocation::default()),
+            // The last column, counting lines from the end.
+            row_item(GridLocation {
+                start: GridPlacement::Line(-2),
+                end: GridPlacement::Line(-1),
+            }),
+        ];
+        assert_eq!(
+            layout_grid(container, children),
+            [
+                // A span covers the gaps between the columns it spans.
+                bounds(point(px(0.), px(0.)), size(px(210.), px(20.))),
+                bounds(point(px(220.), px(0.)), size(px(100.), px(20.))),
+                bounds(point(px(0.), px(30.)), size(px(100.), px(20.))),
+                bounds(point(px(220.), px(30.)), size(px(100.), px(20.))),
+            ]
+        );
+    }
+}
@@ -1,6 +1,6 @@
-use crate::{px, FontId, FontRun, Pixels, PlatformTextSystem, SharedString, TextRun};
+use crate::{px, FontId, FontRun, Pixels, PlatformTextSystem, SharedString, TextOverflow, TextRun};
 use collections::HashMap;
-use std::{iter, sync::Arc};
+use std::{iter, ops::Range, sync::Arc};
 
 /// The GPUI line wrapper, used to wrap lines of text to a given width.
 pub struct LineWrapper {
@@ -136,6 +136,154 @@ impl LineWrapper {
         line
     }
 
+    /// Truncate a line of text to the given width as described by a [`TextOverflow`].
+    pub fn truncate_line_with_overflow(
+        &mut self,
+        line: SharedString,
+        truncate_width: Pixels,
+        overflow: TextOverflow,
+        runs: &mut Vec<TextRun>,
+    ) -> SharedString {
+        match overflow {
+            TextOverflow::Ellipsis(ellipsis) => {
+                self.truncate_line(line, truncate_width, Some(ellipsis), runs)
+            }
+            TextOverflow::EllipsisStart(ellipsis) => {
+                self.truncate_line_start(line, truncate_width, ellipsis, runs)
+            }
+            TextOverflow::EllipsisMiddle(ellipsis) => {
+                self.truncate_line_middle(line, truncate_width, ellipsis, runs)
+            }
+            TextOverflow::Fade(fade_width) => {
+                self.fade_line(line, truncate_width, fade_width, runs)
+            }
+        }
+    }
+
+    /// Truncate the start of a line of text to the given width, replacing it with the ellipsis.
+    pub fn truncate_line_start(
+        &mut self,
+        line: SharedString,
+        truncate_width: Pixels,
+        ellipsis: &str,
+        runs: &mut Vec<TextRun>,
+    ) -> SharedString {
+        let ellipsis_width = self.width_for_str(ellipsis);
+        let mut width = px(0.);
+        let mut truncate_ix = line.len();
+        for (ix, c) in line.char_indices().rev() {
+            if width + ellipsis_width < truncate_width {
+                truncate_ix = ix + c.len_utf8();
+            }
+
+            width += self.width_for_char(c);
+
+            if width.floor() > truncate_width {
+                let result = SharedString::from(format!("{}{}", ellipsis, &line[truncate_ix..]));
+                update_runs_after_replacement(0..truncate_ix, ellipsis.len(), runs);
+
+                return result;
+            }
+        }
+
+        line
+    }
+
+    /// Truncate the middle of a line of text to the given width, replacing it with the
+    /// ellipsis. The start and the end of the line are kept about equally wide.
+    pub fn truncate_line_middle(
+        &mut self,
+        line: SharedString,
+        truncate_width: Pixels,
+        ellipsis: &str,
+        runs: &mut Vec<TextRun>,
+    ) -> SharedString {
+        let chars = line
+            .char_indices()
+            .map(|(ix, c)| (ix, c, self.width_for_char(c)))
+            .collect::<Vec<_>>();
+        let width = chars
+            .iter()
+            .fold(px(0.), |width, (_, _, char_width)| width + *char_width);
+        if width.floor() <= truncate_width {
+            return line;
+        }
+
+        let available_width = truncate_width - self.width_for_str(ellipsis);
+        let mut prefix_width = px(0.);
+        let mut prefix_end = 0;
+        for &(ix, c, char_width) in &chars {
+            if prefix_width + char_width > available_width / 2. {
+                break;
+            }
+            prefix_width += char_width;
+            prefix_end = ix + c.len_utf8();
+        }
+
+        let mut suffix_width = px(0.);
+        let mut suffix_start = line.len();
+        for &(ix, _, char_width) in chars.iter().rev() {
+            if ix < prefix_end || prefix_width + suffix_width + char_width > available_width {
+                break;
+            }
+            suffix_width += char_width;
+            suffix_start = ix;
+        }
+
+        let result = SharedString::from(format!(
+            "{}{}{}",
+            &line[..prefix_end],
+            ellipsis,
+            &line[suffix_start..]
+        ));
+        update_runs_after_replacement(prefix_end..suffix_start, ellipsis.len(), runs);
+        result
+    }
+
+    /// Truncate a line of text to the given width without an ellipsis, fading out the
+    /// characters within `fade_width` of its end instead.
+    pub fn fade_line(
+        &mut self,
+        line: SharedString,
+        truncate_width: Pixels,
+        fade_width: Pixels,
+        runs: &mut Vec<TextRun>,
+    ) -> SharedString {
+        let mut width = px(0.);
+        let mut char_bounds = Vec::new();
+        let mut truncate_ix = None;
+        for (ix, c) in line.char_indices() {
+            let char_width = self.width_for_char(c);
+            if (width + char_width).floor() > truncate_width {
+                truncate_ix = Some(ix);
+                break;
+            }
+            char_bounds.push((ix..ix + c.len_utf8(), width, char_width));
+            width += char_width;
+        }
+        let Some(truncate_ix) = truncate_ix else {
+            return line;
+        };
+
+        let line_len = line.len();
+        update_runs_after_replacement(truncate_ix..line_len, 0, runs);
+        let fade_start = truncate_width - fade_width;
+        for (range, x, char_width) in char_bounds.into_iter().rev() {
+            if x + char_width <= fade_start {
+                break;
+            }
+            let center = x + char_width / 2.;
+            let opacity = if fade_width > px(0.) {
+                ((truncate_width - center) / fade_width).clamp(0., 1.)
+            } else {
+                1.
+            };
+            fade_runs(range, opacity, runs);
+        }
+
+        SharedString::from(line[..truncate_ix].to_string())
+    }
+
     pub(crate) fn is_word_char(c: char) -> bool {
         // ASCII alphanumeric characters, for English, numbers: `Hello123`, etc.
         c.is_ascii_alphanumeric() ||
@@ -180,6 +328,11 @@ impl LineWrapper {
         }
     }
 
+    fn width_for_str(&mut self, text: &str) -> Pixels {
+        text.chars()
+            .fold(px(0.), |width, c| width + self.width_for_char(c))
+    }
+
     fn compute_width_for_char(&self, c: char) -> Pixels {
         let mut buffer = [0; 4];
         let buffer = c.encode_utf8(&mut buffer);
@@ -197,20 +350,59 @@ impl LineWrapper {
 }
 
 fn update_runs_after_truncation(result: &str, ellipsis: &str, runs: &mut Vec<TextRun>) {
-    let mut truncate_at = result.len() - ellipsis.len();
-    let mut run_end = None;
-    for (run_index, run) in runs.iter_mut().enumerate() {
-        if run.len <= truncate_at {
-            truncate_at -= run.len;
-        } else {
-            run.len = truncate_at + ellipsis.len();
-            run_end = Some(run_index + 1);
-            break;
+    let truncate_at = result.len() - ellipsis.len();
+    let line_len = runs.iter().map(|run| run.len).sum::<usize>();
+    update_runs_after_replacement(truncate_at..line_len, ellipsis.len(), runs);
+}
+
+/// Updates the runs of a line whose `range` was replaced by `replacement_len` bytes, which take
+/// the style of the run the range starts in. Runs left empty are removed.
+fn update_runs_after_replacement(
+    range: Range<usize>,
+    replacement_len: usize,
+    runs: &mut Vec<TextRun>,
+) {
+    let mut offset = 0;
+    runs.retain_mut(|run| {
+        let run_range = offset..offset + run.len;
+        offset = run_range.end;
+        run.len -= range
+            .end
+            .min(run_range.end)
+            .saturating_sub(range.start.max(run_range.start));
+        if run_range.contains(&range.start) {
+            run.len += replacement_len;
+        }
+        run.len > 0
+    });
+}
+
+/// Splits the runs at the bounds of `range`, multiplying the opacity of its text.
+fn fade_runs(range: Range<usize>, opacity: f32, runs: &mut Vec<TextRun>) {
+    let mut offset = 0;
+    let mut faded_runs = Vec::with_capacity(runs.len() + 2);
+    for run in runs.drain(..) {
+        let run_range = offset..offset + run.len;
+        offset = run_range.end;
+        let start = range.start.clamp(run_range.start, run_range.end);
+        let end = range.end.clamp(run_range.start, run_range.end);
+        for (piece, is_faded) in [
+            (run_range.start..start, false),
+            (start..end, true),
+            (end..run_range.end, false),
+        ] {
+            if piece.is_empty() {
+                continue;
+            }
+            let mut piece_run = run.clone();
+            piece_run.len = piece.len();
+            if is_faded {
+                piece_run.color.a *= opacity;
+            }
+            faded_runs.push(piece_run);
         }
     }
-    if let Some(run_end) = run_end {
-        runs.truncate(run_end);
-    }
+    *runs = faded_runs;
 }
 
 /// A boundary between two lines of text.
@@ -428,6 +620,70 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_truncate_line_start_and_middle() {
+        let mut wrapper = build_wrapper();
+        let text = "aa bbb cccc ddddd eeee ffff gggg";
+
+        let mut runs = generate_test_runs(&[text.len()]);
+        let result = wrapper.truncate_line_start(text.into(), px(220.), "…", &mut runs);
+        assert_eq!(result, "… ddddd eeee ffff gggg");
+        assert_eq!(runs.first().unwrap().len, result.len());
+
+        let mut runs = generate_test_runs(&[text.len()]);
+        let result = wrapper.truncate_line_middle(text.into(), px(220.), "…", &mut runs);
+        assert_eq!(result, "aa bbb ccc…e ffff gggg");
+        assert_eq!(runs.first().unwrap().len, result.len());
+
+        // Text that fits is left alone.
+        let mut runs = generate_test_runs(&[4]);
+        assert_eq!(
+            wrapper.truncate_line_middle("abcd".into(), px(220.), "…", &mut runs),
+            "abcd"
+        );
+        assert_eq!(runs.first().unwrap().len, 4);
+    }
+
+    #[test]
+    fn test_fade_line() {
+        let mut wrapper = build_wrapper();
+        let text = "abcdefghijkl";
+        let mut runs = generate_test_runs(&[text.len()]);
+        for run in &mut runs {
+            run.color = crate::black();
+        }
+
+        let result = wrapper.fade_line(text.into(), px(50.), px(20.), &mut runs);
+        assert_eq!(result, "abcde");
+        assert_eq!(runs.iter().map(|run| run.len).sum::<usize>(), result.len());
+        assert_eq!(runs.first().unwrap().color.a, 1.);
+        let opacities = runs.iter().map(|run| run.color.a).collect::<Vec<_>>();
+        assert!(opacities.windows(2).all(|pair| pair[0] > pair[1]));
+    }
+
+    #[test]
+    fn test_update_runs_after_replacement() {
+        fn perform_test(
+            range: Range<usize>,
+            replacement_len: usize,
+            run_lens: &[usize],
+            result_run_lens: &[usize],
+        ) {
+            let mut dummy_runs = generate_test_runs(run_lens);
+            update_runs_after_replacement(range, replacement_len, &mut dummy_runs);
+            assert_eq!(
+                dummy_runs.iter().map(|run| run.len).collect::<Vec<_>>(),
+                result_run_lens
+            );
+        }
+        // Start truncation, the ellipsis takes the style of the first run.
+        perform_test(0..8, 3, &[4, 4, 4], &[3, 4]);
+        // Middle truncation, the ellipsis takes the style of the run the range starts in.
+        perform_test(2..10, 3, &[4, 4, 4], &[5, 2]);
+        // Middle truncation within a single run.
+        perform_test(4..6, 3, &[4, 4, 4], &[4, 5, 4]);
+    }
+
     #[test]
     fn test_update_run_after_truncation() {
         fn perform_test(result: &str, run_lens: &[usize], result_run_lens: &[usize]) {
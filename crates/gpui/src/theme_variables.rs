@@ -0,0 +1,122 @@
+//! Theme values like colors, spacing and fonts that can change while the application runs, e.g.
+//! when the user edits their theme file, see [`ThemeVariables`].
+
+use std::{path::Path, sync::LazyLock};
+
+use anyhow::{Context as _, Result};
+use collections::HashMap;
+use serde::Deserialize;
+
+use crate::{App, Global, Hsla, Pixels, SharedString};
+
+static EMPTY: LazyLock<ThemeVariables> = LazyLock::new(ThemeVariables::default);
+
+/// Named colors, spacings and fonts to style views with, stored as a global. Views stay up to
+/// date by re-rendering when the global changes:
+///
+/// ```ignore
+/// cx.observe_global::<ThemeVariables>(|_, cx| cx.notify()).detach();
+/// ```
+///
+/// The variables can be loaded from a JSON file like the following one, which
+/// [`App::load_theme_variables`] reloads whenever it changes:
+///
+/// ```json
+/// {
+///   "colors": { "background": "#1e1e2eee", "accent": "#89b4fa" },
+///   "spacing": { "gap": 8 },
+///   "fonts": { "ui": "Inter" }
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ThemeVariables {
+    /// Colors by name, written as hex strings.
+    pub colors: HashMap<SharedString, Hsla>,
+    /// Spacings by name, in pixels.
+    pub spacing: HashMap<SharedString, Pixels>,
+    /// Font families by name.
+    pub fonts: HashMap<SharedString, SharedString>,
+}
+
+impl Global for ThemeVariables {}
+
+impl ThemeVariables {
+    /// Returns the variables of the application, which are empty until they're set with
+    /// [`App::set_theme_variables`] or loaded with [`App::load_theme_variables`].
+    pub fn get(cx: &App) -> &Self {
+        cx.try_global::<Self>().unwrap_or(&EMPTY)
+    }
+
+    /// Parses variables from JSON, see [`ThemeVariables`].
+    pub fn parse(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Reads variables from a JSON file, see [`ThemeVariables`].
+    pub fn load(path: &Path) -> Result<Self> {
+        let json =
+            std::fs::read_to_string(path).with_context(|| format!("failed to read {path:?}"))?;
+        Self::parse(&json).with_context(|| format!("failed to parse {path:?}"))
+    }
+
+    /// Returns the color with the given name.
+    pub fn color(&self, name: &str) -> Option<Hsla> {
+        self.colors.get(name).copied()
+    }
+
+    /// Returns the spacing with the given name.
+    pub fn spacing(&self, name: &str) -> Option<Pixels> {
+        self.spacing.get(name).copied()
+    }
+
+    /// Returns the font family with the given name.
+    pub fn font(&self, name: &str) -> Option<SharedString> {
+        self.fonts.get(name).cloned()
+    }
+}
+
+impl App {
+    /// Replaces the theme variables of the application, notifying the observers of the
+    /// [`ThemeVariables`] global if they changed.
+    pub fn set_theme_variables(&mut self, variables: ThemeVariables) {
+        if self.try_global::<ThemeVariables>() != Some(&variables) {
+            self.set_global(variables);
+        }
+    }
+
+    /// Loads the theme variables of the application from the JSON file at `path`, and reloads
+    /// them whenever it changes until the returned subscription is dropped. Files that fail to
+    /// parse are logged, keeping the previous variables.
+    #[cfg(target_os = "linux")]
+    pub fn load_theme_variables(&mut self, path: impl AsRef<Path>) -> Result<crate::Subscription> {
+        let path = path.as_ref().to_owned();
+        self.set_theme_variables(ThemeVariables::load(&path)?);
+        self.watch_paths([path.clone()], move |_, cx| {
+            match ThemeVariables::load(&path) {
+                Ok(variables) => cx.set_theme_variables(variables),
+                Err(error) => log::error!("failed to reload theme variables: {error:#}"),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{px, rgb, Hsla, ThemeVariables};
+
+    #[test]
+    fn test_parse_theme_variables() {
+        let variables = ThemeVariables::parse(
+            r##"{
+                "colors": { "accent": "#89b4fa" },
+                "spacing": { "gap": 8 }
+            }"##,
+        )
+        .unwrap();
+        assert_eq!(variables.color("accent"), Some(Hsla::from(rgb(0x89b4fa))));
+        assert_eq!(variables.spacing("gap"), Some(px(8.)));
+        assert_eq!(variables.color("background"), None);
+        assert!(variables.fonts.is_empty());
+    }
+}
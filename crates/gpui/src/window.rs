@@ -1,19 +1,23 @@
 use crate::{
-    point, prelude::*, px, size, transparent_black, Action, AnyDrag, AnyElement, AnyTooltip,
-    AnyView, App, AppContext, Arena, Asset, AsyncWindowContext, AvailableSpace, Background, Bounds,
-    BoxShadow, Context, Corners, CursorStyle, Decorations, DevicePixels, DispatchActionListener,
-    DispatchNodeId, DispatchTree, DisplayId, Edges, Effect, Entity, EntityId, EventEmitter,
-    FileDropEvent, FontId, Global, GlobalElementId, GlyphId, GpuSpecs, Hsla, InputHandler, IsZero,
-    KeyBinding, KeyContext, KeyDownEvent, KeyEvent, Keystroke, KeystrokeEvent, LayoutId,
-    LineLayoutIndex, Modifiers, ModifiersChangedEvent, MonochromeSprite, MouseButton, MouseEvent,
-    MouseMoveEvent, MouseUpEvent, Path, Pixels, PlatformAtlas, PlatformDisplay, PlatformInput,
-    PlatformInputHandler, PlatformWindow, Point, PolychromeSprite, PromptLevel, Quad, Render,
-    RenderGlyphParams, RenderImage, RenderImageParams, RenderSvgParams, Replay, ResizeEdge,
-    ScaledPixels, Scene, Shadow, SharedString, Size, StrikethroughStyle, Style, SubscriberSet,
-    Subscription, TaffyLayoutEngine, Task, TextStyle, TextStyleRefinement, TransformationMatrix,
-    Underline, UnderlineStyle, WindowAppearance, WindowBackgroundAppearance, WindowBounds,
-    WindowControls, WindowDecorations, WindowOptions, WindowParams, WindowTextSystem,
-    SMOOTH_SVG_SCALE_FACTOR, SUBPIXEL_VARIANTS,
+    black, blue, deferred_window, ease_in_out, point, prelude::*, px, red, size, transparent_black,
+    white, Action, Anchor, Animation, AntiAliasing, AnyDrag, AnyElement, AnyTooltip, AnyView, App,
+    AppContext, Arena, Asset, AsyncWindowContext, AtlasUsage, AvailableSpace, Background,
+    BlendMode, Blur, Bounds, BoxShadow, ClipId, Context, Corner, Corners, CursorStyle,
+    CustomShader, Decorations, DevicePixels, DispatchActionListener, DispatchNodeId, DispatchTree,
+    DisplayId, Edges, Effect, Entity, EntityId, EventEmitter, FileDropEvent, FocusLoss, FontId,
+    Global, GlobalElementId, GlyphId, GpuSpecs, Hsla, InputHandler, InputSerial, InputSerialKind,
+    IsZero, KeyBinding, KeyContext, KeyDownEvent, KeyEvent, KeyRepeatSettings, Keystroke,
+    KeystrokeEvent, LayerShellSettings, LayerShellShadow, LayoutId, LineLayoutIndex, Modifiers,
+    ModifiersChangedEvent, MonochromeSprite, MouseButton, MouseEvent, MouseMoveEvent, MouseUpEvent,
+    Path, PathClip, PendingWindow, Pixels, PlatformAtlas, PlatformDisplay, PlatformInput,
+    PlatformInputHandler, PlatformWindow, Point, PolychromeSprite, PopupAnchor, PromptLevel, Quad,
+    Render, RenderGlyphParams, RenderImage, RenderImageParams, RenderStats, RenderSvgParams,
+    Replay, ResizeEdge, ScaledPixels, Scene, SeatId, Shadow, SharedString, Size,
+    StrikethroughStyle, Style, SubscriberSet, Subscription, TaffyLayoutEngine, Task, TextRun,
+    TextStyle, TextStyleRefinement, TransformationMatrix, Underline, UnderlineStyle,
+    WindowAppearance, WindowBackgroundAppearance, WindowBounds, WindowControls,
+    WindowCreationError, WindowDecorations, WindowKind, WindowOptions, WindowParams, WindowScale,
+    WindowTextSystem, SMOOTH_SVG_SCALE_FACTOR, SUBPIXEL_VARIANTS,
 };
 use anyhow::{anyhow, Context as _, Result};
 use collections::{FxHashMap, FxHashSet};
@@ -49,8 +53,25 @@ use util::post_inc;
 use util::{measure, ResultExt};
 use uuid::Uuid;
 
+mod accessibility;
+mod auto_hide;
+mod focus_navigation;
+mod input_recording;
+mod layer_shell_animation;
 mod prompts;
 
+pub use accessibility::AccessibleRole;
+pub(crate) use accessibility::{
+    AccessibilityRequest, AccessibilityTree, AccessibleNode, AccessibleNodeId,
+};
+use auto_hide::AutoHideState;
+use focus_navigation::FocusStop;
+pub use focus_navigation::{
+    FocusDirection, FocusDown, FocusLeft, FocusNext, FocusPrevious, FocusRight, FocusUp,
+};
+pub(crate) use input_recording::InputRecorder;
+pub use input_recording::{InputRecording, RecordedInput};
+use layer_shell_animation::{LayerShellAnimations, Margin, Transition};
 pub use prompts::*;
 
 pub(crate) const DEFAULT_WINDOW_SIZE: Size<Pixels> = size(px(1024.), px(700.));
@@ -413,7 +434,7 @@ pub(crate) struct CursorStyleRequest {
 }
 
 /// An identifier for a [Hitbox].
-#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
 pub struct HitboxId(usize);
 
 impl HitboxId {
@@ -476,6 +497,23 @@ pub(crate) struct TooltipRequest {
     tooltip: AnyTooltip,
 }
 
+enum PreparedTooltip {
+    Element(AnyElement),
+    /// A tooltip shown in a popup window, with its bounds relative to this window.
+    Popup(AnyView, Bounds<Pixels>),
+}
+
+/// The root view of a popup window showing a tooltip.
+struct TooltipPopup {
+    view: AnyView,
+}
+
+impl Render for TooltipPopup {
+    fn render(&mut self, _: &mut Window, _: &mut Context<Self>) -> impl IntoElement {
+        self.view.clone()
+    }
+}
+
 pub(crate) struct DeferredDraw {
     current_view: EntityId,
     priority: usize,
@@ -501,6 +539,11 @@ pub(crate) struct Frame {
     pub(crate) input_handlers: Vec<Option<PlatformInputHandler>>,
     pub(crate) tooltip_requests: Vec<Option<TooltipRequest>>,
     pub(crate) cursor_styles: Vec<CursorStyleRequest>,
+    pub(crate) focus_stops: Vec<FocusStop>,
+    pub(crate) accessible_nodes: Vec<AccessibleNode>,
+    /// The ids of the elements that inserted the hitboxes, only tracked while the inspector is
+    /// shown.
+    pub(crate) inspected_element_ids: FxHashMap<HitboxId, Vec<ElementId>>,
     #[cfg(any(test, feature = "test-support"))]
     pub(crate) debug_bounds: FxHashMap<String, Bounds<Pixels>>,
 }
@@ -509,6 +552,8 @@ pub(crate) struct Frame {
 pub(crate) struct PrepaintStateIndex {
     hitboxes_index: usize,
     tooltips_index: usize,
+    focus_stops_index: usize,
+    accessible_nodes_index: usize,
     deferred_draws_index: usize,
     dispatch_tree_index: usize,
     accessed_element_states_index: usize,
@@ -540,6 +585,9 @@ impl Frame {
             input_handlers: Vec::new(),
             tooltip_requests: Vec::new(),
             cursor_styles: Vec::new(),
+            focus_stops: Vec::new(),
+            accessible_nodes: Vec::new(),
+            inspected_element_ids: FxHashMap::default(),
 
             #[cfg(any(test, feature = "test-support"))]
             debug_bounds: FxHashMap::default(),
@@ -555,7 +603,10 @@ impl Frame {
         self.input_handlers.clear();
         self.tooltip_requests.clear();
         self.cursor_styles.clear();
+        self.focus_stops.clear();
+        self.accessible_nodes.clear();
         self.hitboxes.clear();
+        self.inspected_element_ids.clear();
         self.deferred_draws.clear();
         self.focus = None;
     }
@@ -599,6 +650,9 @@ pub struct Window {
     pub(crate) invalidator: WindowInvalidator,
     pub(crate) removed: bool,
     pub(crate) platform_window: Box<dyn PlatformWindow>,
+    /// The parameters of the platform window of a deferred window that wasn't realized yet, see
+    /// [`Window::realize`].
+    pending_realization: Option<PendingWindow>,
     display_id: Option<DisplayId>,
     sprite_atlas: Arc<dyn PlatformAtlas>,
     text_system: Arc<WindowTextSystem>,
@@ -612,10 +666,17 @@ pub struct Window {
     layout_engine: Option<TaffyLayoutEngine>,
     pub(crate) root: Option<AnyView>,
     pub(crate) element_id_stack: SmallVec<[ElementId; 32]>,
+    /// The accessible nodes of the elements being prepainted, with how many children they have
+    /// so far.
+    accessible_node_stack: Vec<(AccessibleNodeId, usize)>,
+    accessible_root_children: usize,
+    /// Whether an assistive technology needs the accessibility tree of the next frame.
+    accessibility_tree_stale: bool,
     pub(crate) text_style_stack: Vec<TextStyleRefinement>,
     pub(crate) rendered_entity_stack: Vec<EntityId>,
     pub(crate) element_offset_stack: Vec<Point<Pixels>>,
     pub(crate) element_opacity: Option<f32>,
+    pub(crate) element_blend_mode: BlendMode,
     pub(crate) content_mask_stack: Vec<ContentMask<Pixels>>,
     pub(crate) requested_autoscroll: Option<Bounds<Pixels>>,
     pub(crate) rendered_frame: Frame,
@@ -623,6 +684,9 @@ pub struct Window {
     pub(crate) next_hitbox_id: HitboxId,
     pub(crate) next_tooltip_id: TooltipId,
     pub(crate) tooltip_bounds: Option<TooltipBounds>,
+    /// The popup window showing a tooltip that didn't fit in this window, with the tooltip's
+    /// view. The window is `None` while the popup is being opened.
+    pub(crate) tooltip_popup: Option<(EntityId, Option<AnyWindowHandle>)>,
     next_frame_callbacks: Rc<RefCell<Vec<FrameCallback>>>,
     pub(crate) dirty_views: FxHashSet<EntityId>,
     focus_listeners: SubscriberSet<(), AnyWindowFocusListener>,
@@ -630,17 +694,40 @@ pub struct Window {
     default_prevented: bool,
     mouse_position: Point<Pixels>,
     mouse_hit_test: HitTest,
+    /// The width of the invisible client side decorations, the band in which the window is resized.
+    client_inset: Pixels,
+    hovered_resize_edge: Option<ResizeEdge>,
     modifiers: Modifiers,
     scale_factor: f32,
+    buffer_scale: u32,
+    pub(crate) scale_observers: SubscriberSet<(), AnyObserver>,
     pub(crate) bounds_observers: SubscriberSet<(), AnyObserver>,
     appearance: WindowAppearance,
     pub(crate) appearance_observers: SubscriberSet<(), AnyObserver>,
+    pub(crate) throttle_observers: SubscriberSet<(), AnyObserver>,
     active: Rc<Cell<bool>>,
     hovered: Rc<Cell<bool>>,
     pub(crate) needs_present: Rc<Cell<bool>>,
     pub(crate) last_input_timestamp: Rc<Cell<Instant>>,
+    animation_time: Rc<Cell<Instant>>,
     pub(crate) refreshing: bool,
+    pub(crate) reactive_rendering: bool,
+    frame_time: Duration,
+    render_stats_overlay: bool,
+    inspector: bool,
+    input_recorder: Option<InputRecorder>,
     pub(crate) activation_observers: SubscriberSet<(), AnyObserver>,
+    keyboard_focus_lost_observers: SubscriberSet<(), AnyObserver>,
+    focus_loss: FocusLoss,
+    /// The shadow layer shell windows draw around the root view.
+    layer_shell_shadow: Option<LayerShellShadow>,
+    /// The edges layer shell windows are anchored to, to find where they are on their display.
+    layer_shell_anchor: Option<Anchor>,
+    layer_shell_margin: Margin,
+    layer_shell_exclusive_zone: Pixels,
+    layer_shell_animations: LayerShellAnimations,
+    auto_hide: Option<AutoHideState>,
+    layer_shell_revealed_observers: SubscriberSet<(), AnyObserver>,
     pub(crate) focus: Option<FocusId>,
     focus_enabled: bool,
     pending_input: Option<PendingInput>,
@@ -703,8 +790,9 @@ impl Window {
     pub(crate) fn new(
         handle: AnyWindowHandle,
         options: WindowOptions,
+        popup_anchor: Option<(AnyWindowHandle, PopupAnchor)>,
         cx: &mut App,
-    ) -> Result<Self> {
+    ) -> Result<Self, WindowCreationError> {
         let WindowOptions {
             window_bounds,
             titlebar,
@@ -717,30 +805,63 @@ impl Window {
             app_id,
             window_min_size,
             window_decorations,
+            reactive_rendering,
+            anti_aliasing,
+            deferred,
         } = options;
 
         let bounds = window_bounds
             .map(|bounds| bounds.get_bounds())
             .unwrap_or_else(|| default_bounds(display_id, cx));
-        let mut platform_window = cx.platform.open_window(
-            handle,
-            WindowParams {
-                bounds,
-                titlebar,
-                kind,
-                is_movable,
-                focus,
-                show,
-                display_id,
-                window_min_size,
-            },
-        )?;
+        let focus_loss = match &kind {
+            WindowKind::LayerShell(settings) => settings.focus_loss(),
+            _ => FocusLoss::Ignore,
+        };
+        let (
+            layer_shell_shadow,
+            layer_shell_anchor,
+            layer_shell_margin,
+            layer_shell_exclusive_zone,
+        ) = match &kind {
+            WindowKind::LayerShell(settings) => (
+                settings.shadow,
+                Some(settings.anchor),
+                settings.margin.unwrap_or_default(),
+                settings.exclusive_zone.unwrap_or_default(),
+            ),
+            _ => (None, None, Margin::default(), Pixels::ZERO),
+        };
+        let auto_hide = match &kind {
+            WindowKind::LayerShell(settings) => AutoHideState::new(settings),
+            _ => None,
+        };
+        let params = WindowParams {
+            bounds,
+            titlebar,
+            kind,
+            is_movable,
+            focus,
+            show,
+            display_id,
+            window_min_size,
+            popup_anchor,
+        };
+        let (mut platform_window, pending_realization) = if deferred {
+            let (platform_window, pending_realization) = deferred_window(params);
+            (
+                Box::new(platform_window) as Box<dyn PlatformWindow>,
+                Some(pending_realization),
+            )
+        } else {
+            (cx.platform.open_window(handle, params)?, None)
+        };
         let display_id = platform_window.display().map(|display| display.id());
         let sprite_atlas = platform_window.sprite_atlas();
         let mouse_position = platform_window.mouse_position();
         let modifiers = platform_window.modifiers();
         let content_size = platform_window.content_size();
         let scale_factor = platform_window.scale_factor();
+        let buffer_scale = platform_window.buffer_scale();
         let appearance = platform_window.appearance();
         let text_system = Arc::new(WindowTextSystem::new(cx.text_system().clone()));
         let invalidator = WindowInvalidator::new();
@@ -749,11 +870,25 @@ impl Window {
         let needs_present = Rc::new(Cell::new(false));
         let next_frame_callbacks: Rc<RefCell<Vec<FrameCallback>>> = Default::default();
         let last_input_timestamp = Rc::new(Cell::new(Instant::now()));
+        let animation_time = Rc::new(Cell::new(Instant::now()));
+        let power_saving = cx.power_saving.clone();
 
         platform_window
             .request_decorations(window_decorations.unwrap_or(WindowDecorations::Server));
+        platform_window.set_reactive_rendering(reactive_rendering);
+        platform_window.set_anti_aliasing(anti_aliasing);
         platform_window.set_background_appearance(window_background);
 
+        // Auto-hiding windows start out hidden.
+        let layer_shell_margin = match &auto_hide {
+            Some(auto_hide) => {
+                let margin = auto_hide.hidden_margin(bounds.size);
+                platform_window.set_layer_shell_margin(margin);
+                margin
+            }
+            None => layer_shell_margin,
+        };
+
         if let Some(ref window_open_state) = window_bounds {
             match window_open_state {
                 WindowBounds::Fullscreen(_) => platform_window.toggle_fullscreen(),
@@ -775,7 +910,16 @@ impl Window {
             let needs_present = needs_present.clone();
             let next_frame_callbacks = next_frame_callbacks.clone();
             let last_input_timestamp = last_input_timestamp.clone();
+            let animation_time = animation_time.clone();
             move |request_frame_options| {
+                // The clock only moves forward, predicted presentation times may run ahead of
+                // the time of frames drawn without one.
+                let frame_time = request_frame_options
+                    .frame_time
+                    .unwrap_or_else(Instant::now)
+                    .max(animation_time.get());
+                animation_time.set(frame_time);
+
                 let next_frame_callbacks = next_frame_callbacks.take();
                 if !next_frame_callbacks.is_empty() {
                     handle
@@ -789,9 +933,12 @@ impl Window {
 
                 // Keep presenting the current scene for 1 extra second since the
                 // last input to prevent the display from underclocking the refresh rate.
+                // Reactive windows leave the GPU idle instead, as do all windows when saving power.
                 let needs_present = request_frame_options.require_presentation
                     || needs_present.get()
-                    || (active.get()
+                    || (!reactive_rendering
+                        && !power_saving.get()
+                        && active.get()
                         && last_input_timestamp.get().elapsed() < Duration::from_secs(1));
 
                 if invalidator.is_dirty() {
@@ -840,17 +987,40 @@ impl Window {
                     .log_err();
             }
         }));
+        #[cfg(all(target_os = "linux", feature = "wayland"))]
+        platform_window.on_dmabuf_feedback_changed(Box::new({
+            let mut cx = cx.to_async();
+            move || {
+                handle
+                    .update(&mut cx, |_, window, _| window.refresh())
+                    .log_err();
+            }
+        }));
+        platform_window.on_throttle_changed(Box::new({
+            let mut cx = cx.to_async();
+            move |throttled| {
+                handle
+                    .update(&mut cx, |_, window, cx| {
+                        window.throttle_changed(throttled, cx)
+                    })
+                    .log_err();
+            }
+        }));
         platform_window.on_active_status_change(Box::new({
             let mut cx = cx.to_async();
             move |active| {
                 handle
                     .update(&mut cx, |_, window, cx| {
+                        let lost_focus = window.active.get() && !active;
                         window.active.set(active);
                         window
                             .activation_observers
                             .clone()
                             .retain(&(), |callback| callback(window, cx));
                         window.refresh();
+                        if lost_focus {
+                            window.keyboard_focus_lost(cx);
+                        }
                     })
                     .log_err();
             }
@@ -859,13 +1029,24 @@ impl Window {
             let mut cx = cx.to_async();
             move |active| {
                 handle
-                    .update(&mut cx, |_, window, _| {
+                    .update(&mut cx, |_, window, cx| {
                         window.hovered.set(active);
+                        window.update_auto_hide(cx);
                         window.refresh();
                     })
                     .log_err();
             }
         }));
+        platform_window.on_accessibility_request(Box::new({
+            let mut cx = cx.to_async();
+            move |request| {
+                handle
+                    .update(&mut cx, |_, window, cx| {
+                        window.handle_accessibility_request(request, cx)
+                    })
+                    .log_err();
+            }
+        }));
         platform_window.on_input({
             let mut cx = cx.to_async();
             Box::new(move |event| {
@@ -880,13 +1061,16 @@ impl Window {
             platform_window.set_app_id(&app_id);
         }
 
-        platform_window.map_window().unwrap();
+        if pending_realization.is_none() {
+            platform_window.map_window().unwrap();
+        }
 
         Ok(Window {
             handle,
             invalidator,
             removed: false,
             platform_window,
+            pending_realization,
             display_id,
             sprite_atlas,
             text_system,
@@ -896,11 +1080,15 @@ impl Window {
             layout_engine: Some(TaffyLayoutEngine::new()),
             root: None,
             element_id_stack: SmallVec::default(),
+            accessible_node_stack: Vec::new(),
+            accessible_root_children: 0,
+            accessibility_tree_stale: false,
             text_style_stack: Vec::new(),
             rendered_entity_stack: Vec::new(),
             element_offset_stack: Vec::new(),
             content_mask_stack: Vec::new(),
             element_opacity: None,
+            element_blend_mode: BlendMode::Normal,
             requested_autoscroll: None,
             rendered_frame: Frame::new(DispatchTree::new(cx.keymap.clone(), cx.actions.clone())),
             next_frame: Frame::new(DispatchTree::new(cx.keymap.clone(), cx.actions.clone())),
@@ -908,23 +1096,45 @@ impl Window {
             next_hitbox_id: HitboxId::default(),
             next_tooltip_id: TooltipId::default(),
             tooltip_bounds: None,
+            tooltip_popup: None,
             dirty_views: FxHashSet::default(),
             focus_listeners: SubscriberSet::new(),
             focus_lost_listeners: SubscriberSet::new(),
             default_prevented: true,
             mouse_position,
             mouse_hit_test: HitTest::default(),
+            client_inset: Pixels::ZERO,
+            hovered_resize_edge: None,
             modifiers,
             scale_factor,
+            buffer_scale,
+            scale_observers: SubscriberSet::new(),
             bounds_observers: SubscriberSet::new(),
             appearance,
             appearance_observers: SubscriberSet::new(),
+            throttle_observers: SubscriberSet::new(),
             active,
             hovered,
             needs_present,
             last_input_timestamp,
+            animation_time,
             refreshing: false,
+            reactive_rendering,
+            frame_time: Duration::ZERO,
+            render_stats_overlay: std::env::var("GPUI_RENDER_STATS")
+                .is_ok_and(|value| value != "0"),
+            inspector: std::env::var("GPUI_INSPECTOR").is_ok_and(|value| value != "0"),
+            input_recorder: None,
             activation_observers: SubscriberSet::new(),
+            keyboard_focus_lost_observers: SubscriberSet::new(),
+            focus_loss,
+            layer_shell_shadow,
+            layer_shell_anchor,
+            layer_shell_margin,
+            layer_shell_exclusive_zone,
+            layer_shell_animations: LayerShellAnimations::default(),
+            auto_hide,
+            layer_shell_revealed_observers: SubscriberSet::new(),
             focus: None,
             focus_enabled: true,
             pending_input: None,
@@ -990,6 +1200,24 @@ impl Window {
         }
     }
 
+    /// Registers a callback to be invoked when the scale factor or the buffer scale of the
+    /// window changes, e.g. after it's moved to another display. This is invoked before the
+    /// observers of the bounds, so scale-dependent assets like cursor images can be reloaded.
+    pub fn on_scale_factor_changed(
+        &self,
+        mut callback: impl FnMut(WindowScale, &mut Window, &mut App) + 'static,
+    ) -> Subscription {
+        let (subscription, activate) = self.scale_observers.insert(
+            (),
+            Box::new(move |window, cx| {
+                callback(window.scale(), window, cx);
+                true
+            }),
+        );
+        activate();
+        subscription
+    }
+
     /// Registers a callback to be invoked when the window appearance changes.
     pub fn observe_window_appearance(
         &self,
@@ -1006,6 +1234,135 @@ impl Window {
         subscription
     }
 
+    /// Registers a callback to be invoked when the platform stops or resumes delivering frames
+    /// to the window, see [`Window::is_throttled`].
+    pub fn observe_window_throttling(
+        &self,
+        mut callback: impl FnMut(&mut Window, &mut App) + 'static,
+    ) -> Subscription {
+        let (subscription, activate) = self.throttle_observers.insert(
+            (),
+            Box::new(move |window, cx| {
+                callback(window, cx);
+                true
+            }),
+        );
+        activate();
+        subscription
+    }
+
+    /// Registers a callback to be invoked when a layer shell window with
+    /// [`KeyboardInteractivity::OnDemand`] loses keyboard focus, like when the user clicks
+    /// elsewhere. Launcher-like windows can dismiss themselves from it, or set
+    /// [`LayerShellSettings::close_on_focus_loss`] to be closed right away.
+    ///
+    /// [`KeyboardInteractivity::OnDemand`]: crate::KeyboardInteractivity::OnDemand
+    /// [`LayerShellSettings::close_on_focus_loss`]: crate::LayerShellSettings::close_on_focus_loss
+    pub fn observe_keyboard_focus_lost(
+        &self,
+        mut callback: impl FnMut(&mut Window, &mut App) + 'static,
+    ) -> Subscription {
+        let (subscription, activate) = self.keyboard_focus_lost_observers.insert(
+            (),
+            Box::new(move |window, cx| {
+                callback(window, cx);
+                true
+            }),
+        );
+        activate();
+        subscription
+    }
+
+    /// Registers a callback to be invoked when a layer shell window with
+    /// [`LayerShellSettings::auto_hide`] is revealed or starts hiding again, see
+    /// [`Window::is_layer_shell_revealed`].
+    ///
+    /// [`LayerShellSettings::auto_hide`]: crate::LayerShellSettings::auto_hide
+    pub fn observe_layer_shell_revealed(
+        &self,
+        mut callback: impl FnMut(&mut Window, &mut App) + 'static,
+    ) -> Subscription {
+        let (subscription, activate) = self.layer_shell_revealed_observers.insert(
+            (),
+            Box::new(move |window, cx| {
+                callback(window, cx);
+                true
+            }),
+        );
+        activate();
+        subscription
+    }
+
+    /// Returns whether an auto-hiding layer shell window is revealed, or revealing itself.
+    /// Windows that don't auto-hide are always revealed.
+    pub fn is_layer_shell_revealed(&self) -> bool {
+        self.auto_hide
+            .as_ref()
+            .map_or(true, |auto_hide| auto_hide.revealed)
+    }
+
+    /// Reveals or hides an auto-hiding layer shell window, like when the app wants to show a
+    /// notification in its bar. It hides again once the pointer leaves it.
+    pub fn set_layer_shell_revealed(&mut self, revealed: bool, cx: &mut App) {
+        let body_size = self.root_bounds().size;
+        let Some(auto_hide) = self.auto_hide.as_mut() else {
+            return;
+        };
+        auto_hide.hide_task = None;
+        if auto_hide.revealed == revealed {
+            return;
+        }
+        auto_hide.revealed = revealed;
+        let margin = if revealed {
+            auto_hide.margin
+        } else {
+            auto_hide.hidden_margin(body_size)
+        };
+        let animation = Animation::new(auto_hide.settings.duration).with_easing(ease_in_out);
+        self.animate_layer_shell_margin(margin, animation);
+        self.layer_shell_revealed_observers
+            .clone()
+            .retain(&(), |callback| callback(self, cx));
+    }
+
+    /// Reveals an auto-hiding window when the pointer enters it, and hides it after the delay
+    /// once the pointer leaves it.
+    fn update_auto_hide(&mut self, cx: &mut App) {
+        if self.hovered.get() {
+            self.set_layer_shell_revealed(true, cx);
+            return;
+        }
+        let handle = self.handle;
+        let Some(auto_hide) = self
+            .auto_hide
+            .as_mut()
+            .filter(|auto_hide| auto_hide.revealed)
+        else {
+            return;
+        };
+        let hide_delay = auto_hide.settings.hide_delay;
+        auto_hide.hide_task = Some(cx.spawn(|mut cx| async move {
+            cx.background_executor().timer(hide_delay).await;
+            handle
+                .update(&mut cx, |_, window, cx| {
+                    window.set_layer_shell_revealed(false, cx)
+                })
+                .ok();
+        }));
+    }
+
+    fn keyboard_focus_lost(&mut self, cx: &mut App) {
+        if self.focus_loss == FocusLoss::Ignore {
+            return;
+        }
+        self.keyboard_focus_lost_observers
+            .clone()
+            .retain(&(), |callback| callback(self, cx));
+        if self.focus_loss == FocusLoss::Close {
+            self.remove_window();
+        }
+    }
+
     /// Replaces the root entity of the window with a new one.
     pub fn replace_root<E>(
         &mut self,
@@ -1044,6 +1401,19 @@ impl Window {
         }
     }
 
+    /// Mark the window as dirty and schedule a frame, even if the window uses reactive rendering
+    /// and nothing was notified, see [`WindowOptions::reactive_rendering`].
+    pub fn request_frame(&mut self) {
+        self.refresh();
+        self.platform_window.request_frame();
+    }
+
+    /// Changes how the edges of paths are anti-aliased, see [`WindowOptions::anti_aliasing`].
+    pub fn set_anti_aliasing(&mut self, anti_aliasing: AntiAliasing) {
+        self.platform_window.set_anti_aliasing(anti_aliasing);
+        self.refresh();
+    }
+
     /// Close this window.
     pub fn remove_window(&mut self) {
         self.removed = true;
@@ -1288,6 +1658,17 @@ impl Window {
         self.on_next_frame(move |_, cx| cx.notify(entity));
     }
 
+    /// Returns the time of the frame being drawn, which animations should be advanced to instead
+    /// of the current time. It moves once per frame, so everything animating in the window
+    /// stays in step, and stops while no frames are drawn, e.g. while the window is throttled.
+    ///
+    /// On Wayland it's the time the compositor is expected to show the frame, predicted from
+    /// the presentation feedback of the previous frames, so animations follow the refresh of
+    /// the output.
+    pub fn animation_time(&self) -> Instant {
+        self.animation_time.get()
+    }
+
     /// Spawn the future returned by the given closure on the application thread pool.
     /// The closure is provided a handle to the current window and an `AsyncWindowContext` for
     /// use within your future.
@@ -1300,13 +1681,48 @@ impl Window {
         cx.spawn(|app| f(AsyncWindowContext::new_context(app, self.handle)))
     }
 
+    /// Returns whether the platform window of this window was created, which deferred windows
+    /// wait for until [`Window::realize`] is called, see [`WindowOptions::deferred`].
+    pub fn is_realized(&self) -> bool {
+        self.pending_realization.is_none()
+    }
+
+    /// Creates the platform window of a deferred window and shows it, see
+    /// [`WindowOptions::deferred`]. Does nothing if the window was realized already.
+    pub fn realize(&mut self, cx: &mut App) -> Result<(), WindowCreationError> {
+        let Some(pending_realization) = self.pending_realization.take() else {
+            return Ok(());
+        };
+        let mut platform_window = pending_realization.open(self.handle, cx.platform.as_ref())?;
+        platform_window
+            .map_window()
+            .map_err(WindowCreationError::Other)?;
+        self.sprite_atlas = platform_window.sprite_atlas();
+        self.active.set(platform_window.is_active());
+        self.hovered.set(platform_window.is_hovered());
+        self.modifiers = platform_window.modifiers();
+        self.mouse_position = platform_window.mouse_position();
+        self.platform_window = platform_window;
+        self.appearance_changed(cx);
+        self.bounds_changed(cx);
+        Ok(())
+    }
+
     fn bounds_changed(&mut self, cx: &mut App) {
+        let previous_scale = self.scale();
         self.scale_factor = self.platform_window.scale_factor();
+        self.buffer_scale = self.platform_window.buffer_scale();
         self.viewport_size = self.platform_window.content_size();
         self.display_id = self.platform_window.display().map(|display| display.id());
 
         self.refresh();
 
+        if self.scale() != previous_scale {
+            self.scale_observers
+                .clone()
+                .retain(&(), |callback| callback(self, cx));
+        }
+
         self.bounds_observers
             .clone()
             .retain(&(), |callback| callback(self, cx));
@@ -1330,6 +1746,23 @@ impl Window {
             .retain(&(), |callback| callback(self, cx));
     }
 
+    fn throttle_changed(&mut self, throttled: bool, cx: &mut App) {
+        if !throttled {
+            self.refresh();
+        }
+
+        self.throttle_observers
+            .clone()
+            .retain(&(), |callback| callback(self, cx));
+    }
+
+    /// Returns whether the compositor has stopped requesting frames for this window, for example
+    /// because it is occluded or its output is turned off (Wayland). While throttled, no frames are
+    /// drawn and animation frame callbacks are deferred until the window becomes visible again.
+    pub fn is_throttled(&self) -> bool {
+        self.platform_window.is_throttled()
+    }
+
     /// Returns the appearance of the current window.
     pub fn appearance(&self) -> WindowAppearance {
         self.appearance
@@ -1360,6 +1793,50 @@ impl Window {
         }
     }
 
+    /// Returns the input seat which delivered the most recent input event to this window.
+    /// This is only meaningful on Wayland, where several seats may be interacting with
+    /// the application at the same time.
+    pub fn input_seat(&self) -> Option<SeatId> {
+        self.platform_window.input_seat()
+    }
+
+    /// Returns the serial of the most recent input event of the given kinds, on the seat which
+    /// delivered the most recent input event to this window (Wayland)
+    ///
+    /// Serials have to be passed along with many protocol requests, e.g. for activation, setting
+    /// the selection or grabs. Use this to issue such requests of your own, with
+    /// [`InputSerialKind::GRAB`] or [`InputSerialKind::FOCUS`] as the kinds.
+    pub fn latest_input_serial(&self, kinds: &[InputSerialKind]) -> Option<InputSerial> {
+        self.platform_window.latest_input_serial(kinds)
+    }
+
+    /// Returns the key repeat rate and delay used for held keys in this window.
+    /// Returns `None` if the platform repeats keys itself.
+    pub fn key_repeat(&self) -> Option<KeyRepeatSettings> {
+        self.platform_window.key_repeat()
+    }
+
+    /// Overrides the key repeat rate and delay used for held keys in this window.
+    /// Pass `None` to follow the user's configuration again.
+    pub fn set_key_repeat(&self, settings: Option<KeyRepeatSettings>) {
+        self.platform_window.set_key_repeat(settings);
+    }
+
+    /// Renders the window at the given scale factor regardless of the displays it's on, e.g. at
+    /// `1.0` on a HiDPI display to save GPU time, with the compositor scaling the frames up.
+    /// Pass `None` to follow the compositor's scale again (Wayland)
+    pub fn set_scale_override(&mut self, scale_factor: Option<f32>, cx: &mut App) {
+        self.platform_window.set_scale_override(scale_factor);
+        self.bounds_changed(cx);
+    }
+
+    /// Returns the compose sequence or dead key being typed into this window, e.g. `´` after
+    /// the dead acute key and before the letter it's combined with. The focused input handler
+    /// receives it as marked text too. Only reported on Wayland.
+    pub fn pending_compose(&self) -> Option<SharedString> {
+        self.platform_window.pending_compose().map(Into::into)
+    }
+
     /// Toggle zoom on the window.
     pub fn zoom_window(&self) {
         self.platform_window.zoom();
@@ -1377,11 +1854,160 @@ impl Window {
         self.platform_window.start_window_move()
     }
 
+    /// Attaches this window to a drag of the pointer button held down in another window of the
+    /// application, so the compositor moves it along with the pointer and leaves it where it's
+    /// dropped, e.g. to tear a tab off into a new window (Wayland)
+    ///
+    /// `offset` is the position of the pointer in this window. Deferred windows need to be
+    /// realized first. Returns a receiver notified when the drag ends, or `None` if the
+    /// compositor doesn't support `xdg_toplevel_drag_v1` or no button is held down.
+    pub fn start_toplevel_drag(&self, offset: Point<Pixels>) -> Option<oneshot::Receiver<()>> {
+        self.platform_window.start_toplevel_drag(offset)
+    }
+
     /// When using client side decorations, set this to the width of the invisible decorations (Wayland and X11)
-    pub fn set_client_inset(&self, inset: Pixels) {
+    ///
+    /// The cursor switches to the matching resize shape while the mouse hovers this band along the
+    /// edges that aren't tiled.
+    pub fn set_client_inset(&mut self, inset: Pixels) {
+        self.client_inset = inset;
         self.platform_window.set_client_inset(inset);
     }
 
+    /// Returns the edge of the client side decorations the mouse is over, if the window can be
+    /// resized from it.
+    fn resize_edge_at_mouse(&self) -> Option<ResizeEdge> {
+        let Decorations::Client { tiling } = self.window_decorations() else {
+            return None;
+        };
+        if self.client_inset <= Pixels::ZERO || self.is_maximized() || self.is_fullscreen() {
+            return None;
+        }
+        ResizeEdge::at_position(
+            self.mouse_position,
+            self.client_inset,
+            self.viewport_size,
+            tiling,
+        )
+    }
+
+    /// Sets the distance of a layer shell window from its anchored edges, as top, right, bottom and left margins (Wayland)
+    pub fn set_layer_shell_margin(&mut self, margin: (Pixels, Pixels, Pixels, Pixels)) {
+        self.layer_shell_animations.margin = None;
+        self.apply_layer_shell_margin(margin);
+    }
+
+    /// Sets the exclusive zone of a layer shell window, the space it keeps other windows out of
+    /// along its anchored edge (Wayland and X11)
+    pub fn set_layer_shell_exclusive_zone(&mut self, exclusive_zone: Pixels) {
+        self.layer_shell_animations.exclusive_zone = None;
+        self.apply_layer_shell_exclusive_zone(exclusive_zone);
+    }
+
+    /// Moves a layer shell window from its current margin to the given one over the course of
+    /// the animation, stepping once per frame. Use it to slide in panels or hide a bar past
+    /// its anchored edge. Setting the margin directly stops the animation.
+    pub fn animate_layer_shell_margin(
+        &mut self,
+        margin: (Pixels, Pixels, Pixels, Pixels),
+        animation: Animation,
+    ) {
+        self.layer_shell_animations.margin =
+            Some(Transition::new(self.layer_shell_margin, margin, animation));
+        self.schedule_layer_shell_animations();
+    }
+
+    /// Changes the exclusive zone of a layer shell window from its current one to the given one
+    /// over the course of the animation, so the windows next to it make room smoothly. Setting
+    /// the exclusive zone directly stops the animation.
+    pub fn animate_layer_shell_exclusive_zone(
+        &mut self,
+        exclusive_zone: Pixels,
+        animation: Animation,
+    ) {
+        self.layer_shell_animations.exclusive_zone = Some(Transition::new(
+            self.layer_shell_exclusive_zone,
+            exclusive_zone,
+            animation,
+        ));
+        self.schedule_layer_shell_animations();
+    }
+
+    /// Returns whether the margin or exclusive zone of this layer shell window is animating.
+    pub fn is_layer_shell_animating(&self) -> bool {
+        self.layer_shell_animations.is_running()
+    }
+
+    fn apply_layer_shell_margin(&mut self, margin: Margin) {
+        if margin != self.layer_shell_margin {
+            self.layer_shell_margin = margin;
+            self.platform_window.set_layer_shell_margin(margin);
+        }
+    }
+
+    fn apply_layer_shell_exclusive_zone(&mut self, exclusive_zone: Pixels) {
+        if exclusive_zone != self.layer_shell_exclusive_zone {
+            self.layer_shell_exclusive_zone = exclusive_zone;
+            self.platform_window
+                .set_layer_shell_exclusive_zone(exclusive_zone);
+        }
+    }
+
+    fn schedule_layer_shell_animations(&mut self) {
+        if !self.layer_shell_animations.scheduled {
+            self.layer_shell_animations.scheduled = true;
+            self.on_next_frame(|window, cx| window.step_layer_shell_animations(cx));
+            // Make sure a frame comes, an idle window may not have one requested.
+            self.refresh();
+        }
+    }
+
+    fn step_layer_shell_animations(&mut self, cx: &App) {
+        self.layer_shell_animations.scheduled = false;
+        let now = self.animation_time();
+        // Jump to the end of the transitions when the user asked for less motion.
+        let reduce_motion = cx.should_reduce_motion();
+        if let Some(transition) = &self.layer_shell_animations.margin {
+            let (margin, done) = if reduce_motion {
+                (transition.to(), true)
+            } else {
+                transition.value_at(now)
+            };
+            if done {
+                self.layer_shell_animations.margin = None;
+            }
+            self.apply_layer_shell_margin(margin);
+        }
+        if let Some(transition) = &self.layer_shell_animations.exclusive_zone {
+            let (exclusive_zone, done) = if reduce_motion {
+                (transition.to(), true)
+            } else {
+                transition.value_at(now)
+            };
+            if done {
+                self.layer_shell_animations.exclusive_zone = None;
+            }
+            self.apply_layer_shell_exclusive_zone(exclusive_zone);
+        }
+        if self.layer_shell_animations.is_running() {
+            self.layer_shell_animations.scheduled = true;
+            self.on_next_frame(|window, cx| window.step_layer_shell_animations(cx));
+        }
+    }
+
+    /// Keeps the window above other windows, or lets it be stacked normally again (X11, and layer shell windows on Wayland,
+    /// which are moved to the overlay layer and back)
+    pub fn set_always_on_top(&self, always_on_top: bool) {
+        self.platform_window.set_always_on_top(always_on_top);
+    }
+
+    /// Shows the window on every workspace, or only on the current one (X11 and macOS, layer shell windows on Wayland
+    /// are always on every workspace, Windows doesn't let apps pin windows to every virtual desktop)
+    pub fn set_on_all_workspaces(&self, on_all_workspaces: bool) {
+        self.platform_window
+            .set_on_all_workspaces(on_all_workspaces);
+    }
+
     /// Returns whether the title bar window controls need to be rendered by the application (Wayland and X11)
     pub fn window_decorations(&self) -> Decorations {
         self.platform_window.window_decorations()
@@ -1392,6 +2018,15 @@ impl Window {
         self.platform_window.window_controls()
     }
 
+    /// Returns the largest size the compositor recommends for the window, e.g. the work area of
+    /// its display without panels, if it told one (Wayland)
+    ///
+    /// Windows should size themselves to fit in it, instead of opening larger than the screen
+    /// they can use. Maximized and fullscreen windows aren't bound by it.
+    pub fn recommended_bounds(&self) -> Option<Size<Pixels>> {
+        self.platform_window.recommended_bounds()
+    }
+
     /// Updates the window's title at the platform level.
     pub fn set_window_title(&mut self, title: &str) {
         self.platform_window.set_title(title);
@@ -1408,6 +2043,15 @@ impl Window {
             .set_background_appearance(background_appearance);
     }
 
+    /// Returns whether [`WindowBackgroundAppearance::Blurred`] blurs the content behind this
+    /// window. Where it isn't supported the background is only transparent.
+    ///
+    /// On Wayland this depends on the compositor supporting the KDE blur protocol or
+    /// ext-background-effect with blur.
+    pub fn is_background_blur_supported(&self) -> bool {
+        self.platform_window.is_background_blur_supported()
+    }
+
     /// Mark the window as dirty at the platform level.
     pub fn set_window_edited(&mut self, edited: bool) {
         self.platform_window.set_edited(edited);
@@ -1421,6 +2065,49 @@ impl Window {
             .find(|display| Some(display.id()) == self.display_id)
     }
 
+    /// Returns the area of the window's display its popups can use, relative to the window: the
+    /// whole display without the exclusive zone of the window, so that the popovers of a bar
+    /// open away from it. On Wayland the display bounds are the logical ones of xdg-output.
+    pub fn usable_display_area(&self, cx: &App) -> Option<Bounds<Pixels>> {
+        let display_bounds = self.display(cx)?.bounds();
+        let Some(anchor) = self.layer_shell_anchor else {
+            return Some(Bounds {
+                origin: display_bounds.origin - self.bounds().origin,
+                size: display_bounds.size,
+            });
+        };
+
+        // Layer shell windows don't know their position on Wayland, so it's derived from their
+        // anchor and margin like the compositor does.
+        let settings = LayerShellSettings {
+            anchor,
+            margin: Some(self.layer_shell_margin),
+            exclusive_zone: Some(self.layer_shell_exclusive_zone),
+            shadow: self.layer_shell_shadow,
+            ..Default::default()
+        };
+        let window_bounds =
+            settings.bounds_within(settings.body_size(self.viewport_size()), display_bounds);
+        let mut area = display_bounds;
+        let (top, right, bottom, left) = self.layer_shell_margin;
+        let zone = self.layer_shell_exclusive_zone;
+        if let Some(edge) = settings.attached_edge().filter(|_| zone > Pixels::ZERO) {
+            if edge == Anchor::TOP {
+                area.origin.y += zone + top;
+                area.size.height -= zone + top;
+            } else if edge == Anchor::BOTTOM {
+                area.size.height -= zone + bottom;
+            } else if edge == Anchor::LEFT {
+                area.origin.x += zone + left;
+                area.size.width -= zone + left;
+            } else {
+                area.size.width -= zone + right;
+            }
+        }
+        area.origin -= window_bounds.origin;
+        Some(area)
+    }
+
     /// Show the platform character palette.
     pub fn show_character_palette(&self) {
         self.platform_window.show_character_palette();
@@ -1433,6 +2120,20 @@ impl Window {
         self.scale_factor
     }
 
+    /// The integer scale the compositor prefers for the buffers of the window, see
+    /// [`WindowScale::buffer_scale`].
+    pub fn buffer_scale(&self) -> u32 {
+        self.buffer_scale
+    }
+
+    /// The scale factor and the buffer scale of the window.
+    pub fn scale(&self) -> WindowScale {
+        WindowScale {
+            scale_factor: self.scale_factor,
+            buffer_scale: self.buffer_scale,
+        }
+    }
+
     /// The size of an em for the base font of the application. Adjusting this value allows the
     /// UI to scale, just like zooming a web page.
     pub fn rem_size(&self) -> Pixels {
@@ -1516,11 +2217,18 @@ impl Window {
     /// the contents of the new [Scene], use [present].
     #[profiling::function]
     pub fn draw(&mut self, cx: &mut App) {
+        // Deferred windows aren't drawn until they're realized, as the stand-in for their
+        // platform window has nowhere to present frames.
+        if !self.is_realized() {
+            return;
+        }
+        let draw_start = Instant::now();
         self.invalidate_entities();
         cx.entities.clear_accessed();
         debug_assert!(self.rendered_entity_stack.is_empty());
         self.invalidator.set_dirty(false);
         self.requested_autoscroll = None;
+        self.accessible_root_children = 0;
 
         // Restore the previously-used input handler.
         if let Some(input_handler) = self.platform_window.take_input_handler() {
@@ -1551,6 +2259,19 @@ impl Window {
         let previous_focus_path = self.rendered_frame.focus_path();
         let previous_window_active = self.rendered_frame.window_active;
         mem::swap(&mut self.rendered_frame, &mut self.next_frame);
+        // If the previous frame was never presented, its damage carries over to this one.
+        let damage = {
+            profiling::scope!("scene diff");
+            self.rendered_frame.scene.diff(&self.next_frame.scene)
+        };
+        self.rendered_frame.scene.damage = if self.needs_present.get() {
+            self.next_frame.scene.damage.union(&damage)
+        } else {
+            damage
+        };
+        if self.platform_window.is_accessibility_active() {
+            self.publish_accessibility_tree();
+        }
         self.next_frame.clear();
         let current_focus_path = self.rendered_frame.focus_path();
         let current_window_active = self.rendered_frame.window_active;
@@ -1587,6 +2308,194 @@ impl Window {
         self.refreshing = false;
         self.invalidator.set_phase(DrawPhase::None);
         self.needs_present.set(true);
+        self.frame_time = draw_start.elapsed();
+    }
+
+    /// Returns statistics about the last frame drawn by this window. Set the `GPUI_RENDER_STATS`
+    /// environment variable to show them in the top left corner of every window.
+    pub fn render_stats(&self) -> RenderStats {
+        RenderStats {
+            frame_time: self.frame_time,
+            ..self.platform_window.render_stats()
+        }
+    }
+
+    /// Limits the memory of the sprite atlas of this window, which caches rasterized glyphs, icons
+    /// and images, to roughly the given number of bytes. Once reached, the tiles used least
+    /// recently are evicted and rasterized again when they're drawn next, so windows showing many
+    /// different images over time don't grow without bounds. `None` removes the limit (Linux)
+    ///
+    /// Windows rendered on the GPU share their atlas, the budget applies to all of them.
+    pub fn set_sprite_atlas_budget(&self, bytes: Option<u64>) {
+        self.sprite_atlas.set_budget(bytes);
+    }
+
+    /// Returns the memory used by the sprite atlas of this window and how many tiles it evicted.
+    pub fn sprite_atlas_usage(&self) -> AtlasUsage {
+        self.sprite_atlas.usage()
+    }
+
+    fn paint_render_stats(&mut self, cx: &mut App) {
+        let stats = self.render_stats();
+        let text = SharedString::from(format!(
+            "{:.2} ms | {} draw calls | atlas {} KiB | {} uploads ({} KiB)",
+            stats.frame_time.as_secs_f64() * 1000.,
+            stats.draw_calls,
+            stats.atlas_bytes / 1024,
+            stats.texture_uploads,
+            stats.uploaded_bytes / 1024,
+        ));
+        let font_size = px(12.);
+        let line_height = px(16.);
+        let run = TextRun {
+            len: text.len(),
+            font: self.text_style().font(),
+            color: white(),
+            background_color: None,
+            underline: None,
+            strikethrough: None,
+        };
+        let Some(line) = self
+            .text_system()
+            .shape_line(text, font_size, &[run])
+            .log_err()
+        else {
+            return;
+        };
+
+        let padding = px(4.);
+        let bounds = Bounds::new(
+            point(padding, padding),
+            size(line.width + padding * 2., line_height + padding * 2.),
+        );
+        self.paint_quad(fill(bounds, black().opacity(0.75)));
+        line.paint(
+            bounds.origin + point(padding, padding),
+            line_height,
+            self,
+            cx,
+        )
+        .log_err();
+    }
+
+    /// Shows or hides the inspector, which outlines the window's hitboxes, labels the hovered one
+    /// with the id of the element that inserted it, and logs what's under the mouse on each
+    /// click. It's drawn over the window's content rather than in a window of its own, so it
+    /// works in any kind of window, layer shell surfaces included.
+    ///
+    /// Set `GPUI_INSPECTOR=1` to show it in every window from the start.
+    pub fn toggle_inspector(&mut self) {
+        self.inspector = !self.inspector;
+        self.refresh();
+    }
+
+    /// Returns whether the inspector is shown.
+    pub fn is_inspector_shown(&self) -> bool {
+        self.inspector
+    }
+
+    fn paint_inspector(&mut self, cx: &mut App) {
+        let hovered = self.mouse_hit_test.0.first().copied();
+        let hitboxes = self.next_frame.hitboxes.clone();
+        let mut label = None;
+        for hitbox in &hitboxes {
+            let bounds = hitbox.bounds.intersect(&hitbox.content_mask.bounds);
+            if Some(hitbox.id) == hovered {
+                self.paint_quad(quad(bounds, 0., blue().opacity(0.2), 1., blue()));
+                label = Some((bounds, self.inspected_element_label(hitbox)));
+            } else {
+                self.paint_quad(outline(bounds, red().opacity(0.5)));
+            }
+        }
+
+        let Some((bounds, text)) = label else {
+            return;
+        };
+        let font_size = px(10.);
+        let line_height = px(12.);
+        let run = TextRun {
+            len: text.len(),
+            font: self.text_style().font(),
+            color: white(),
+            background_color: None,
+            underline: None,
+            strikethrough: None,
+        };
+        let Some(line) = self
+            .text_system()
+            .shape_line(text, font_size, &[run])
+            .log_err()
+        else {
+            return;
+        };
+
+        // Keep the label within the window, which can be only a few pixels taller than the
+        // label itself.
+        let padding = px(1.);
+        let label_size = size(line.width + padding * 2., line_height + padding * 2.);
+        let viewport_size = self.viewport_size();
+        let origin = point(
+            bounds
+                .origin
+                .x
+                .min(viewport_size.width - label_size.width)
+                .max(Pixels::ZERO),
+            bounds
+                .origin
+                .y
+                .min(viewport_size.height - label_size.height)
+                .max(Pixels::ZERO),
+        );
+        self.paint_quad(fill(Bounds::new(origin, label_size), black().opacity(0.75)));
+        line.paint(origin + point(padding, padding), line_height, self, cx)
+            .log_err();
+    }
+
+    fn inspected_element_label(&self, hitbox: &Hitbox) -> SharedString {
+        let id = self
+            .next_frame
+            .inspected_element_ids
+            .get(&hitbox.id)
+            .and_then(|ids| ids.last())
+            .map_or_else(|| "<no id>".to_string(), ToString::to_string);
+        let bounds = hitbox.bounds;
+        format!(
+            "{id} ({}, {}) {}x{}",
+            bounds.origin.x.0, bounds.origin.y.0, bounds.size.width.0, bounds.size.height.0
+        )
+        .into()
+    }
+
+    fn log_inspected_hit_test(&self, position: Point<Pixels>) {
+        let hit_test = self.rendered_frame.hit_test(position);
+        let mut message = format!("hit test at ({}, {}):", position.x.0, position.y.0);
+        for id in &hit_test.0 {
+            let Some(hitbox) = self
+                .rendered_frame
+                .hitboxes
+                .iter()
+                .find(|hitbox| hitbox.id == *id)
+            else {
+                continue;
+            };
+            let path = self
+                .rendered_frame
+                .inspected_element_ids
+                .get(id)
+                .map(|ids| {
+                    ids.iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(" > ")
+                })
+                .unwrap_or_else(|| "<no id>".to_string());
+            message.push_str(&format!(
+                "\n  {path}: {:?}{}",
+                hitbox.bounds,
+                if hitbox.opaque { ", opaque" } else { "" }
+            ));
+        }
+        log::info!(target: "gpui::inspector", "{message}");
     }
 
     fn record_entities_accessed(&mut self, cx: &mut App) {
@@ -1619,13 +2528,15 @@ impl Window {
         profiling::finish_frame!();
     }
 
+    #[profiling::function]
     fn draw_roots(&mut self, cx: &mut App) {
         self.invalidator.set_phase(DrawPhase::Prepaint);
         self.tooltip_bounds.take();
 
         // Layout all root elements.
+        let root_bounds = self.root_bounds();
         let mut root_element = self.root.as_ref().unwrap().clone().into_any();
-        root_element.prepaint_as_root(Point::default(), self.viewport_size.into(), self, cx);
+        root_element.prepaint_as_root(root_bounds.origin, root_bounds.size.into(), self, cx);
 
         let mut sorted_deferred_draws =
             (0..self.next_frame.deferred_draws.len()).collect::<SmallVec<[_; 8]>>();
@@ -1635,6 +2546,7 @@ impl Window {
         let mut prompt_element = None;
         let mut active_drag_element = None;
         let mut tooltip_element = None;
+        let mut tooltip_popup = None;
         if let Some(prompt) = self.prompt.take() {
             let mut element = prompt.view.any_view().into_any();
             element.prepaint_as_root(Point::default(), self.viewport_size.into(), self, cx);
@@ -1647,13 +2559,30 @@ impl Window {
             active_drag_element = Some(element);
             cx.active_drag = Some(active_drag);
         } else {
-            tooltip_element = self.prepaint_tooltip(cx);
+            match self.prepaint_tooltip(cx) {
+                Some(PreparedTooltip::Element(element)) => tooltip_element = Some(element),
+                Some(PreparedTooltip::Popup(view, bounds)) => tooltip_popup = Some((view, bounds)),
+                None => {}
+            }
         }
+        self.update_tooltip_popup(tooltip_popup, cx);
 
         self.mouse_hit_test = self.next_frame.hit_test(self.mouse_position);
 
         // Now actually paint the elements.
         self.invalidator.set_phase(DrawPhase::Paint);
+        if let Some(shadow) = self.layer_shell_shadow {
+            self.paint_shadows(
+                root_bounds,
+                Corners::all(shadow.corner_radius),
+                &[BoxShadow {
+                    color: shadow.color,
+                    offset: shadow.offset,
+                    blur_radius: shadow.blur_radius,
+                    spread_radius: px(0.),
+                }],
+            );
+        }
         root_element.paint(self, cx);
 
         self.paint_deferred_draws(&sorted_deferred_draws, cx);
@@ -1665,9 +2594,27 @@ impl Window {
         } else if let Some(mut tooltip_element) = tooltip_element {
             tooltip_element.paint(self, cx);
         }
+
+        if self.render_stats_overlay {
+            self.paint_render_stats(cx);
+        }
+
+        if self.inspector {
+            self.paint_inspector(cx);
+        }
+    }
+
+    /// The bounds the root view is laid out in: the whole window, except for the space layer
+    /// shell windows keep around it for their shadow.
+    fn root_bounds(&self) -> Bounds<Pixels> {
+        let bounds = Bounds::new(Point::default(), self.viewport_size);
+        match self.layer_shell_shadow {
+            Some(shadow) => bounds.inset(shadow.inset()),
+            None => bounds,
+        }
     }
 
-    fn prepaint_tooltip(&mut self, cx: &mut App) -> Option<AnyElement> {
+    fn prepaint_tooltip(&mut self, cx: &mut App) -> Option<PreparedTooltip> {
         // Use indexing instead of iteration to avoid borrowing self for the duration of the loop.
         for tooltip_request_index in (0..self.next_frame.tooltip_requests.len()).rev() {
             let Some(Some(tooltip_request)) = self
@@ -1690,6 +2637,22 @@ impl Window {
                 size: self.viewport_size(),
             };
 
+            // Tooltips that would be clipped by the window, like the ones of items in a thin
+            // bar, are shown in a popup when the platform can place it against the window.
+            let fits_in_window = tooltip_bounds.right() <= window_bounds.right()
+                && tooltip_bounds.bottom() <= window_bounds.bottom();
+            if !fits_in_window && self.platform_window.supports_popups() {
+                let is_visible =
+                    (tooltip_request.tooltip.check_visible_and_update)(tooltip_bounds, self, cx);
+                if !is_visible {
+                    continue;
+                }
+                return Some(PreparedTooltip::Popup(
+                    tooltip_request.tooltip.view.clone(),
+                    tooltip_bounds,
+                ));
+            }
+
             if tooltip_bounds.right() > window_bounds.right() {
                 let new_x = mouse_position.x - tooltip_bounds.size.width - px(1.);
                 if new_x >= Pixels::ZERO {
@@ -1731,11 +2694,66 @@ impl Window {
                 id: tooltip_request.id,
                 bounds: tooltip_bounds,
             });
-            return Some(element);
+            return Some(PreparedTooltip::Element(element));
         }
         None
     }
 
+    /// Opens a popup for the tooltip prepainted in this frame, if it needs one, and closes the
+    /// popup of the previous tooltip.
+    fn update_tooltip_popup(&mut self, tooltip: Option<(AnyView, Bounds<Pixels>)>, cx: &mut App) {
+        if let (Some((view, _)), Some((shown_view_id, _))) = (&tooltip, &self.tooltip_popup) {
+            if view.entity_id() == *shown_view_id {
+                return;
+            }
+        }
+
+        if let Some((_, Some(popup))) = self.tooltip_popup.take() {
+            cx.defer(move |cx| {
+                popup.update(cx, |_, window, _| window.remove_window()).ok();
+            });
+        }
+
+        let Some((view, bounds)) = tooltip else {
+            return;
+        };
+        let view_id = view.entity_id();
+        self.tooltip_popup = Some((view_id, None));
+        let parent = self.handle;
+        // Windows can't be opened while drawing, so the popup is opened once the frame is done.
+        cx.defer(move |cx| {
+            parent
+                .update(cx, |_, window, cx| {
+                    let options = WindowOptions {
+                        window_bounds: Some(WindowBounds::Windowed(bounds)),
+                        titlebar: None,
+                        focus: false,
+                        window_background: WindowBackgroundAppearance::Transparent,
+                        ..Default::default()
+                    };
+                    // Anchored to the pixel under the mouse, so that the tooltip opens above it
+                    // when it doesn't fit below.
+                    let mouse_position = bounds.origin - point(px(1.), px(1.));
+                    let anchor =
+                        PopupAnchor::new(Bounds::new(mouse_position, size(px(1.), px(1.))))
+                            .attach(Corner::BottomRight);
+                    let popup = cx
+                        .open_popup_window(window, anchor, options, |_, cx| {
+                            cx.new(|_| TooltipPopup { view })
+                        })
+                        .log_err()?;
+                    if window.tooltip_popup == Some((view_id, None)) {
+                        window.tooltip_popup = Some((view_id, Some(popup.into())));
+                    } else {
+                        // The tooltip was hidden while the popup was opened.
+                        popup.update(cx, |_, window, _| window.remove_window()).ok();
+                    }
+                    Some(())
+                })
+                .ok();
+        });
+    }
+
     fn prepaint_deferred_draws(&mut self, deferred_draw_indices: &[usize], cx: &mut App) {
         assert_eq!(self.element_id_stack.len(), 0);
 
@@ -1804,6 +2822,8 @@ impl Window {
         PrepaintStateIndex {
             hitboxes_index: self.next_frame.hitboxes.len(),
             tooltips_index: self.next_frame.tooltip_requests.len(),
+            focus_stops_index: self.next_frame.focus_stops.len(),
+            accessible_nodes_index: self.next_frame.accessible_nodes.len(),
             deferred_draws_index: self.next_frame.deferred_draws.len(),
             dispatch_tree_index: self.next_frame.dispatch_tree.len(),
             accessed_element_states_index: self.next_frame.accessed_element_states.len(),
@@ -1812,17 +2832,34 @@ impl Window {
     }
 
     pub(crate) fn reuse_prepaint(&mut self, range: Range<PrepaintStateIndex>) {
-        self.next_frame.hitboxes.extend(
-            self.rendered_frame.hitboxes[range.start.hitboxes_index..range.end.hitboxes_index]
-                .iter()
-                .cloned(),
-        );
+        let reused_hitboxes =
+            &self.rendered_frame.hitboxes[range.start.hitboxes_index..range.end.hitboxes_index];
+        self.next_frame
+            .hitboxes
+            .extend(reused_hitboxes.iter().cloned());
+        if self.inspector {
+            for hitbox in reused_hitboxes {
+                if let Some(ids) = self.rendered_frame.inspected_element_ids.get(&hitbox.id) {
+                    self.next_frame
+                        .inspected_element_ids
+                        .insert(hitbox.id, ids.clone());
+                }
+            }
+        }
         self.next_frame.tooltip_requests.extend(
             self.rendered_frame.tooltip_requests
                 [range.start.tooltips_index..range.end.tooltips_index]
                 .iter_mut()
                 .map(|request| request.take()),
         );
+        self.next_frame.focus_stops.extend_from_slice(
+            &self.rendered_frame.focus_stops
+                [range.start.focus_stops_index..range.end.focus_stops_index],
+        );
+        self.next_frame.accessible_nodes.extend_from_slice(
+            &self.rendered_frame.accessible_nodes
+                [range.start.accessible_nodes_index..range.end.accessible_nodes_index],
+        );
         self.next_frame.accessed_element_states.extend(
             self.rendered_frame.accessed_element_states[range.start.accessed_element_states_index
                 ..range.end.accessed_element_states_index]
@@ -2011,6 +3048,25 @@ impl Window {
         result
     }
 
+    /// Paint everything painted by the given closure with the given blend mode, or with the
+    /// current one if `None`. This method should only be called during the prepaint or paint
+    /// phase of element drawing.
+    pub fn with_blend_mode<R>(
+        &mut self,
+        blend_mode: Option<BlendMode>,
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> R {
+        let Some(blend_mode) = blend_mode else {
+            return f(self);
+        };
+
+        self.invalidator.debug_assert_paint_or_prepaint();
+        let previous = mem::replace(&mut self.element_blend_mode, blend_mode);
+        let result = f(self);
+        self.element_blend_mode = previous;
+        result
+    }
+
     /// Perform prepaint on child elements in a "retryable" manner, so that any side effects
     /// of prepaints can be discarded before prepainting again. This is used to support autoscroll
     /// where we need to prepaint children to detect the autoscroll bounds, then adjust the
@@ -2025,6 +3081,12 @@ impl Window {
             self.next_frame
                 .tooltip_requests
                 .truncate(index.tooltips_index);
+            self.next_frame
+                .focus_stops
+                .truncate(index.focus_stops_index);
+            self.next_frame
+                .accessible_nodes
+                .truncate(index.accessible_nodes_index);
             self.next_frame
                 .deferred_draws
                 .truncate(index.deferred_draws_index);
@@ -2099,6 +3161,13 @@ impl Window {
         self.element_opacity.unwrap_or(1.0)
     }
 
+    /// Obtain the current blend mode. This method should only be called during the paint
+    /// phase of element drawing.
+    pub(crate) fn element_blend_mode(&self) -> BlendMode {
+        self.invalidator.debug_assert_paint_or_prepaint();
+        self.element_blend_mode
+    }
+
     /// Obtain the current content mask. This method should only be called during element drawing.
     pub fn content_mask(&self) -> ContentMask<Pixels> {
         self.invalidator.debug_assert_paint_or_prepaint();
@@ -2306,11 +3375,14 @@ impl Window {
         let scale_factor = self.scale_factor();
         let content_mask = self.content_mask();
         let opacity = self.element_opacity();
+        let blend_mode = self.element_blend_mode();
         for shadow in shadows {
             let shadow_bounds = (bounds + shadow.offset).dilate(shadow.spread_radius);
             self.next_frame.scene.insert_primitive(Shadow {
                 order: 0,
                 blur_radius: shadow.blur_radius.scale(scale_factor),
+                blend_mode,
+                pad: 0,
                 bounds: shadow_bounds.scale(scale_factor),
                 content_mask: content_mask.scale(scale_factor),
                 corner_radii: corner_radii.scale(scale_factor),
@@ -2319,6 +3391,33 @@ impl Window {
         }
     }
 
+    /// Paint a Gaussian blur of everything painted below `bounds` so far, with rounded corners.
+    /// `radius` is the standard deviation of the blur. Renderers without a GPU leave the
+    /// backdrop as is.
+    ///
+    /// This method should only be called as part of the paint phase of element drawing.
+    pub fn paint_backdrop_blur(
+        &mut self,
+        bounds: Bounds<Pixels>,
+        corner_radii: Corners<Pixels>,
+        radius: Pixels,
+    ) {
+        self.invalidator.debug_assert_paint();
+
+        if radius <= Pixels::ZERO {
+            return;
+        }
+        let scale_factor = self.scale_factor();
+        let content_mask = self.content_mask();
+        self.next_frame.scene.insert_primitive(Blur {
+            order: 0,
+            radius: radius.scale(scale_factor),
+            bounds: bounds.scale(scale_factor),
+            corner_radii: corner_radii.scale(scale_factor),
+            content_mask: content_mask.scale(scale_factor),
+        });
+    }
+
     /// Paint one or more quads into the scene for the next frame at the current stacking context.
     /// Quads are colored rectangular regions with an optional background, border, and corner radius.
     /// see [`fill`](crate::fill), [`outline`](crate::outline), and [`quad`](crate::quad) to construct this type.
@@ -2330,9 +3429,10 @@ impl Window {
         let scale_factor = self.scale_factor();
         let content_mask = self.content_mask();
         let opacity = self.element_opacity();
+        let blend_mode = self.element_blend_mode();
         self.next_frame.scene.insert_primitive(Quad {
             order: 0,
-            pad: 0,
+            blend_mode,
             bounds: quad.bounds.scale(scale_factor),
             content_mask: content_mask.scale(scale_factor),
             background: quad.background.opacity(opacity),
@@ -2354,11 +3454,46 @@ impl Window {
         path.content_mask = content_mask;
         let color: Background = color.into();
         path.color = color.opacity(opacity);
+        path.blend_mode = self.element_blend_mode();
         self.next_frame
             .scene
             .insert_primitive(path.scale(scale_factor));
     }
 
+    /// Paint everything painted by the given closure clipped to the given path, which is
+    /// filled like [`Window::paint_path`] does. Renderers without a GPU only clip to the bounds
+    /// of the path.
+    ///
+    /// This method should only be called as part of the paint phase of element drawing.
+    pub fn with_clip_path<R>(
+        &mut self,
+        mut path: Path<Pixels>,
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> R {
+        self.invalidator.debug_assert_paint();
+
+        let scale_factor = self.scale_factor();
+        let content_mask = self.content_mask();
+        let bounds = path.bounds;
+        path.content_mask = content_mask.clone();
+        self.next_frame.scene.insert_primitive(PathClip {
+            order: 0,
+            id: ClipId(0),
+            bounds: bounds.scale(scale_factor),
+            content_mask: content_mask.scale(scale_factor),
+            path: None,
+        });
+        let result = self.with_content_mask(Some(ContentMask { bounds }), f);
+        self.next_frame.scene.insert_primitive(PathClip {
+            order: 0,
+            id: ClipId(0),
+            bounds: bounds.scale(scale_factor),
+            content_mask: content_mask.scale(scale_factor),
+            path: Some(path.scale(scale_factor)),
+        });
+        result
+    }
+
     /// Paint an underline into the scene for the next frame at the current z-index.
     ///
     /// This method should only be called as part of the paint phase of element drawing.
@@ -2382,10 +3517,11 @@ impl Window {
         };
         let content_mask = self.content_mask();
         let element_opacity = self.element_opacity();
+        let blend_mode = self.element_blend_mode();
 
         self.next_frame.scene.insert_primitive(Underline {
             order: 0,
-            pad: 0,
+            blend_mode,
             bounds: bounds.scale(scale_factor),
             content_mask: content_mask.scale(scale_factor),
             color: style.color.unwrap_or_default().opacity(element_opacity),
@@ -2413,10 +3549,11 @@ impl Window {
         };
         let content_mask = self.content_mask();
         let opacity = self.element_opacity();
+        let blend_mode = self.element_blend_mode();
 
         self.next_frame.scene.insert_primitive(Underline {
             order: 0,
-            pad: 0,
+            blend_mode,
             bounds: bounds.scale(scale_factor),
             content_mask: content_mask.scale(scale_factor),
             thickness: style.thickness.scale(scale_factor),
@@ -2473,9 +3610,10 @@ impl Window {
                 size: tile.bounds.size.map(Into::into),
             };
             let content_mask = self.content_mask().scale(scale_factor);
+            let blend_mode = self.element_blend_mode();
             self.next_frame.scene.insert_primitive(MonochromeSprite {
                 order: 0,
-                pad: 0,
+                blend_mode,
                 bounds,
                 content_mask,
                 color: color.opacity(element_opacity),
@@ -2531,10 +3669,11 @@ impl Window {
             };
             let content_mask = self.content_mask().scale(scale_factor);
             let opacity = self.element_opacity();
+            let blend_mode = self.element_blend_mode();
 
             self.next_frame.scene.insert_primitive(PolychromeSprite {
                 order: 0,
-                pad: 0,
+                blend_mode,
                 grayscale: false,
                 bounds,
                 corner_radii: Default::default(),
@@ -2581,10 +3720,11 @@ impl Window {
             return Ok(());
         };
         let content_mask = self.content_mask().scale(scale_factor);
+        let blend_mode = self.element_blend_mode();
 
         self.next_frame.scene.insert_primitive(MonochromeSprite {
             order: 0,
-            pad: 0,
+            blend_mode,
             bounds: bounds
                 .map_origin(|origin| origin.floor())
                 .map_size(|size| size.ceil()),
@@ -2633,10 +3773,11 @@ impl Window {
         let content_mask = self.content_mask().scale(scale_factor);
         let corner_radii = corner_radii.scale(scale_factor);
         let opacity = self.element_opacity();
+        let blend_mode = self.element_blend_mode();
 
         self.next_frame.scene.insert_primitive(PolychromeSprite {
             order: 0,
-            pad: 0,
+            blend_mode,
             grayscale,
             bounds,
             content_mask,
@@ -2667,6 +3808,129 @@ impl Window {
         });
     }
 
+    /// Paint a custom shader into the scene for the next frame at the current z-index.
+    ///
+    /// This method should only be called as part of the paint phase of element drawing.
+    pub fn paint_shader(&mut self, bounds: Bounds<Pixels>, shader: CustomShader) {
+        use crate::PaintShader;
+
+        self.invalidator.debug_assert_paint();
+
+        let scale_factor = self.scale_factor();
+        let mouse_position = (self.mouse_position() - bounds.origin).scale(scale_factor);
+        let bounds = bounds.scale(scale_factor);
+        let content_mask = self.content_mask().scale(scale_factor);
+        let time = shader.time();
+        let blend_mode = self.element_blend_mode();
+        self.next_frame.scene.insert_primitive(PaintShader {
+            order: 0,
+            bounds,
+            content_mask,
+            shader,
+            mouse_position,
+            time,
+            blend_mode,
+        });
+    }
+
+    /// Paint a dmabuf into the scene for the next frame at the current z-index. Its pixels are
+    /// copied into the sprite atlas the first time it's painted, see [`Dmabuf`](crate::Dmabuf).
+    ///
+    /// This method should only be called as part of the paint phase of element drawing.
+    #[cfg(all(target_os = "linux", feature = "wayland"))]
+    pub fn paint_dmabuf(
+        &mut self,
+        bounds: Bounds<Pixels>,
+        dmabuf: Arc<crate::Dmabuf>,
+    ) -> Result<()> {
+        self.invalidator.debug_assert_paint();
+
+        let scale_factor = self.scale_factor();
+        let bounds = bounds.scale(scale_factor);
+        let params = RenderImageParams {
+            image_id: dmabuf.id(),
+            frame_index: 0,
+        };
+
+        let tile = self
+            .sprite_atlas
+            .get_or_insert_with(&params.into(), &mut || {
+                let size = size(
+                    DevicePixels(dmabuf.width as i32),
+                    DevicePixels(dmabuf.height as i32),
+                );
+                Ok(Some((size, Cow::Owned(dmabuf.read_bgra()?))))
+            })?
+            .expect("Callback above only returns Some");
+        let content_mask = self.content_mask().scale(scale_factor);
+        let opacity = self.element_opacity();
+        let blend_mode = self.element_blend_mode();
+
+        self.next_frame.scene.insert_primitive(PolychromeSprite {
+            order: 0,
+            blend_mode,
+            grayscale: false,
+            bounds,
+            content_mask,
+            corner_radii: Default::default(),
+            tile,
+            opacity,
+        });
+        Ok(())
+    }
+
+    /// Paint a foreign surface into the scene for the next frame at the current z-index, which
+    /// moves it to the origin of `bounds`.
+    ///
+    /// This method should only be called as part of the paint phase of element drawing.
+    #[cfg(all(target_os = "linux", feature = "wayland"))]
+    pub fn paint_foreign_surface(
+        &mut self,
+        bounds: Bounds<Pixels>,
+        surface: crate::ForeignSurface,
+    ) {
+        use crate::PaintSurface;
+
+        self.invalidator.debug_assert_paint();
+
+        let scale_factor = self.scale_factor();
+        let bounds = bounds.scale(scale_factor);
+        let content_mask = self.content_mask().scale(scale_factor);
+        self.next_frame.scene.insert_primitive(PaintSurface {
+            order: 0,
+            bounds,
+            content_mask,
+            foreign_surface: surface,
+        });
+    }
+
+    /// Creates a surface embedded in this window whose content is provided by an external
+    /// producer, see [`ForeignSurface`](crate::ForeignSurface). Returns `None` when the platform
+    /// doesn't support it.
+    #[cfg(all(target_os = "linux", feature = "wayland"))]
+    pub fn create_foreign_surface(&self) -> Option<crate::ForeignSurface> {
+        self.platform_window.create_foreign_surface()
+    }
+
+    /// Returns the buffer formats and modifiers the compositor prefers for the output the window
+    /// is on, which the producers of [`ForeignSurface`](crate::ForeignSurface)s in it should
+    /// allocate their buffers with to be scanned out directly. The window is refreshed when they change, e.g. when it's moved to
+    /// another output. Returns `None` when the platform doesn't report them.
+    #[cfg(all(target_os = "linux", feature = "wayland"))]
+    pub fn dmabuf_feedback(&self) -> Option<crate::DmabufFeedback> {
+        self.platform_window.dmabuf_feedback()
+    }
+
+    /// Removes a dmabuf from the sprite atlas.
+    #[cfg(all(target_os = "linux", feature = "wayland"))]
+    pub fn drop_dmabuf(&mut self, dmabuf: &crate::Dmabuf) {
+        let params = RenderImageParams {
+            image_id: dmabuf.id(),
+            frame_index: 0,
+        };
+        self.sprite_atlas.remove(&params.into());
+    }
+
     /// Removes an image from the sprite atlas.
     pub fn drop_image(&mut self, data: Arc<RenderImage>) -> Result<()> {
         for frame_index in 0..data.frame_count() {
@@ -2783,6 +4047,11 @@ impl Window {
             opaque,
         };
         self.next_frame.hitboxes.push(hitbox.clone());
+        if self.inspector && !self.element_id_stack.is_empty() {
+            self.next_frame
+                .inspected_element_ids
+                .insert(id, self.element_id_stack.to_vec());
+        }
         hitbox
     }
 
@@ -2972,13 +4241,18 @@ impl Window {
     fn reset_cursor_style(&self, cx: &mut App) {
         // Set the cursor only if we're the active window.
         if self.is_window_hovered() {
+            // The resize band of client side decorations takes precedence over the content.
             let style = self
-                .rendered_frame
-                .cursor_styles
-                .iter()
-                .rev()
-                .find(|request| request.hitbox_id.is_hovered(self))
-                .map(|request| request.style)
+                .resize_edge_at_mouse()
+                .map(|edge| edge.cursor_style())
+                .or_else(|| {
+                    self.rendered_frame
+                        .cursor_styles
+                        .iter()
+                        .rev()
+                        .find(|request| request.hitbox_id.is_hovered(self))
+                        .map(|request| request.style)
+                })
                 .unwrap_or(CursorStyle::Arrow);
             cx.platform.set_cursor_style(style);
         }
@@ -3026,10 +4300,29 @@ impl Window {
             .unwrap_or_else(|| action.name().to_string())
     }
 
+    /// Starts recording the input the window receives, restarting any recording in progress.
+    pub fn start_input_recording(&mut self) {
+        self.input_recorder = Some(InputRecorder::new());
+    }
+
+    /// Stops recording the window's input, returning what was recorded since
+    /// [`Window::start_input_recording`].
+    pub fn stop_input_recording(&mut self) -> Option<InputRecording> {
+        self.input_recorder.take().map(InputRecorder::finish)
+    }
+
+    /// Returns whether the window's input is being recorded.
+    pub fn is_recording_input(&self) -> bool {
+        self.input_recorder.is_some()
+    }
+
     /// Dispatch a mouse or keyboard event on the window.
     #[profiling::function]
     pub fn dispatch_event(&mut self, event: PlatformInput, cx: &mut App) -> DispatchEventResult {
         self.last_input_timestamp.set(Instant::now());
+        if let Some(recorder) = self.input_recorder.as_mut() {
+            recorder.record(&event);
+        }
         // Handlers may set this to false by calling `stop_propagation`.
         cx.propagate_event = true;
         // Handlers may set this to true by calling `prevent_default`.
@@ -3041,11 +4334,17 @@ impl Window {
             PlatformInput::MouseMove(mouse_move) => {
                 self.mouse_position = mouse_move.position;
                 self.modifiers = mouse_move.modifiers;
+                if self.inspector {
+                    self.refresh();
+                }
                 PlatformInput::MouseMove(mouse_move)
             }
             PlatformInput::MouseDown(mouse_down) => {
                 self.mouse_position = mouse_down.position;
                 self.modifiers = mouse_down.modifiers;
+                if self.inspector {
+                    self.log_inspected_hit_test(mouse_down.position);
+                }
                 PlatformInput::MouseDown(mouse_down)
             }
             PlatformInput::MouseUp(mouse_up) => {
@@ -3124,8 +4423,10 @@ impl Window {
 
     fn dispatch_mouse_event(&mut self, event: &dyn Any, cx: &mut App) {
         let hit_test = self.rendered_frame.hit_test(self.mouse_position());
-        if hit_test != self.mouse_hit_test {
+        let resize_edge = self.resize_edge_at_mouse();
+        if hit_test != self.mouse_hit_test || resize_edge != self.hovered_resize_edge {
             self.mouse_hit_test = hit_test;
+            self.hovered_resize_edge = resize_edge;
             self.reset_cursor_style(cx);
         }
 
@@ -3489,6 +4790,10 @@ impl Window {
             }
         }
 
+        if self.navigate_focus(action, cx) {
+            return;
+        }
+
         // Bubble phase for global actions.
         if let Some(mut global_listeners) = cx
             .global_action_listeners
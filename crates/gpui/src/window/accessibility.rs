@@ -0,0 +1,295 @@
+//! Describing the elements of a window to assistive technologies like screen readers, see
+//! [`InteractiveElement::role`](crate::InteractiveElement::role). Windows publish the nodes of
+//! the elements drawn in every frame to the platform while an assistive technology reads them.
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+use crate::{
+    App, Bounds, FocusHandle, FocusId, GlobalElementId, Modifiers, MouseButton, MouseDownEvent,
+    MouseMoveEvent, MouseUpEvent, Pixels, PlatformInput, SharedString, Window,
+};
+
+/// What an element is to assistive technologies, which decides how they present it and what
+/// they let users do with it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AccessibleRole {
+    /// A container grouping related elements.
+    Group,
+    /// A button, activated by clicking it.
+    Button,
+    /// A checkbox, see [`InteractiveElement::accessible_toggled`](crate::InteractiveElement::accessible_toggled).
+    CheckBox,
+    /// A switch that is either on or off, see [`InteractiveElement::accessible_toggled`](crate::InteractiveElement::accessible_toggled).
+    Switch,
+    /// A link to another place.
+    Link,
+    /// Static text.
+    Label,
+    /// An image or icon.
+    Image,
+    /// A list of items, like the results of a launcher.
+    List,
+    /// An item of a [`AccessibleRole::List`].
+    ListItem,
+    /// A menu, like a context menu.
+    Menu,
+    /// An item of a [`AccessibleRole::Menu`].
+    MenuItem,
+    /// A field to enter text into.
+    TextInput,
+    /// A control picking a value from a range, like a volume slider.
+    Slider,
+    /// A progress bar.
+    ProgressIndicator,
+    /// A list of tabs.
+    TabList,
+    /// A tab of a [`AccessibleRole::TabList`].
+    Tab,
+    /// A bar of controls, like the items of a panel.
+    Toolbar,
+    /// A dialog.
+    Dialog,
+}
+
+/// Identifies an accessible node across frames.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct AccessibleNodeId(pub(crate) u64);
+
+impl AccessibleNodeId {
+    /// The node of the window itself, the parent of the nodes of the root elements.
+    pub(crate) const ROOT: Self = Self(0);
+}
+
+/// The description of an element drawn in a frame, inserted while prepainting it.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct AccessibleNode {
+    pub(crate) id: AccessibleNodeId,
+    /// The node of the closest ancestor that has one.
+    pub(crate) parent: AccessibleNodeId,
+    pub(crate) role: AccessibleRole,
+    pub(crate) label: Option<SharedString>,
+    pub(crate) toggled: Option<bool>,
+    /// The bounds of the element in the window.
+    pub(crate) bounds: Bounds<Pixels>,
+    pub(crate) focus_id: Option<FocusId>,
+    pub(crate) clickable: bool,
+}
+
+impl AccessibleNode {
+    /// Creates the node of an element, whose id and parent are assigned by
+    /// [`Window::with_accessible_node`].
+    pub(crate) fn new(role: AccessibleRole, bounds: Bounds<Pixels>) -> Self {
+        Self {
+            id: AccessibleNodeId::ROOT,
+            parent: AccessibleNodeId::ROOT,
+            role,
+            label: None,
+            toggled: None,
+            bounds,
+            focus_id: None,
+            clickable: false,
+        }
+    }
+}
+
+/// The accessible nodes of a frame, parents before their children.
+#[derive(Clone, Debug)]
+pub(crate) struct AccessibilityTree {
+    pub(crate) nodes: Vec<AccessibleNode>,
+    /// The node of the focused element, if it has one.
+    pub(crate) focus: Option<AccessibleNodeId>,
+}
+
+/// A request of an assistive technology to a window.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum AccessibilityRequest {
+    /// An assistive technology started reading the window, which needs to publish its tree.
+    Activate,
+    /// Focus the element of a node.
+    Focus(AccessibleNodeId),
+    /// Click the element of a node.
+    Click(AccessibleNodeId),
+}
+
+impl Window {
+    /// Inserts the given node for the element being prepainted, making it the parent of the
+    /// nodes inserted by `f`. Elements without a node are transparent to assistive technologies.
+    ///
+    /// This method should only be called as part of the prepaint phase of element drawing.
+    pub(crate) fn with_accessible_node<R>(
+        &mut self,
+        global_id: Option<&GlobalElementId>,
+        node: Option<AccessibleNode>,
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> R {
+        self.invalidator.debug_assert_prepaint();
+        let Some(mut node) = node else {
+            return f(self);
+        };
+
+        // Elements with an id keep their node across frames, others are identified by their
+        // position among the children of their parent.
+        let (parent, children) = self
+            .accessible_node_stack
+            .last_mut()
+            .map(|(id, children)| (*id, children))
+            .unwrap_or((AccessibleNodeId::ROOT, &mut self.accessible_root_children));
+        let mut hasher = DefaultHasher::new();
+        match global_id {
+            Some(global_id) => global_id.hash(&mut hasher),
+            None => (parent, *children).hash(&mut hasher),
+        }
+        *children += 1;
+        node.id = AccessibleNodeId(hasher.finish().max(1));
+        node.parent = parent;
+
+        self.accessible_node_stack.push((node.id, 0));
+        self.next_frame.accessible_nodes.push(node);
+        let result = f(self);
+        self.accessible_node_stack.pop();
+        result
+    }
+
+    /// Publishes the accessible nodes of the frame that was just drawn, unless they didn't
+    /// change since the previous one.
+    pub(crate) fn publish_accessibility_tree(&mut self) {
+        let nodes = &self.rendered_frame.accessible_nodes;
+        if !self.accessibility_tree_stale
+            && *nodes == self.next_frame.accessible_nodes
+            && self.rendered_frame.focus == self.next_frame.focus
+        {
+            return;
+        }
+        self.accessibility_tree_stale = false;
+
+        let focus = self.rendered_frame.focus.and_then(|focus| {
+            nodes
+                .iter()
+                .rfind(|node| node.focus_id == Some(focus))
+                .map(|node| node.id)
+        });
+        self.platform_window
+            .update_accessibility_tree(AccessibilityTree {
+                nodes: nodes.clone(),
+                focus,
+            });
+    }
+
+    pub(crate) fn handle_accessibility_request(
+        &mut self,
+        request: AccessibilityRequest,
+        cx: &mut App,
+    ) {
+        let node = |window: &Self, id| {
+            window
+                .rendered_frame
+                .accessible_nodes
+                .iter()
+                .find(|node: &&AccessibleNode| node.id == id)
+                .cloned()
+        };
+        match request {
+            AccessibilityRequest::Activate => {
+                self.accessibility_tree_stale = true;
+                self.refresh();
+            }
+            AccessibilityRequest::Focus(id) => {
+                let handle = node(self, id)
+                    .and_then(|node| node.focus_id)
+                    .and_then(|focus_id| FocusHandle::for_id(focus_id, &cx.focus_handles));
+                if let Some(handle) = handle {
+                    self.focus(&handle);
+                }
+            }
+            AccessibilityRequest::Click(id) => {
+                let Some(node) = node(self, id) else {
+                    return;
+                };
+                // Click the center of the element like a pointer would, so it goes through the
+                // same listeners.
+                let position = node.bounds.center();
+                let modifiers = Modifiers::default();
+                self.dispatch_event(
+                    PlatformInput::MouseMove(MouseMoveEvent {
+                        position,
+                        pressed_button: None,
+                        modifiers,
+                    }),
+                    cx,
+                );
+                self.dispatch_event(
+                    PlatformInput::MouseDown(MouseDownEvent {
+                        button: MouseButton::Left,
+                        position,
+                        modifiers,
+                        click_count: 1,
+                        first_mouse: false,
+                    }),
+                    cx,
+                );
+                self.dispatch_event(
+                    PlatformInput::MouseUp(MouseUpEvent {
+                        button: MouseButton::Left,
+                        position,
+                        modifiers,
+                        click_count: 1,
+                    }),
+                    cx,
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        self as gpui, div, prelude::*, px, AccessibleNode, AccessibleNodeId, AccessibleRole,
+        TestAppContext, Window,
+    };
+
+    struct Toolbar;
+
+    impl Render for Toolbar {
+        fn render(&mut self, _: &mut Window, _: &mut Context<Self>) -> impl IntoElement {
+            div().role(AccessibleRole::Toolbar).child(
+                div().child(
+                    div()
+                        .id("mute")
+                        .role(AccessibleRole::Switch)
+                        .accessible_label("Mute")
+                        .accessible_toggled(true)
+                        .size(px(40.))
+                        .on_click(|_, _, _| {}),
+                ),
+            )
+        }
+    }
+
+    #[gpui::test]
+    fn test_accessible_nodes(cx: &mut TestAppContext) {
+        let window = cx.update(|cx| {
+            cx.open_window(Default::default(), |_, cx| cx.new(|_| Toolbar))
+                .unwrap()
+        });
+        cx.run_until_parked();
+
+        let nodes = window
+            .update(cx, |_, window, _| {
+                window.rendered_frame.accessible_nodes.clone()
+            })
+            .unwrap();
+        let [toolbar, switch]: [AccessibleNode; 2] = nodes.try_into().unwrap();
+        assert_eq!(toolbar.role, AccessibleRole::Toolbar);
+        assert_eq!(toolbar.parent, AccessibleNodeId::ROOT);
+        // Elements without a role are skipped.
+        assert_eq!(switch.parent, toolbar.id);
+        assert_eq!(switch.role, AccessibleRole::Switch);
+        assert_eq!(switch.label.as_deref(), Some("Mute"));
+        assert_eq!(switch.toggled, Some(true));
+        assert!(switch.clickable);
+    }
+}
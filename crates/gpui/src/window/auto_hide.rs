@@ -0,0 +1,104 @@
+use crate::{Anchor, AutoHide, LayerShellSettings, Pixels, Size, Task};
+
+use super::layer_shell_animation::Margin;
+
+/// The state of a layer shell window with [`LayerShellSettings::auto_hide`].
+pub(crate) struct AutoHideState {
+    pub(crate) settings: AutoHide,
+    /// The edge the window hides past.
+    edge: Anchor,
+    /// The margin of the revealed window.
+    pub(crate) margin: Margin,
+    pub(crate) revealed: bool,
+    /// Hides the window once the pointer has been away from it for the hide delay.
+    pub(crate) hide_task: Option<Task<()>>,
+}
+
+impl AutoHideState {
+    pub(crate) fn new(settings: &LayerShellSettings) -> Option<Self> {
+        let auto_hide = settings.auto_hide?;
+        let Some(edge) = settings.attached_edge() else {
+            log::warn!("auto-hiding layer shell windows need to be attached to an edge");
+            return None;
+        };
+        Some(Self {
+            settings: auto_hide,
+            edge,
+            margin: settings.margin.unwrap_or_default(),
+            revealed: false,
+            hide_task: None,
+        })
+    }
+
+    /// The margin that moves a window body of the given size past its edge, except for the
+    /// strip.
+    pub(crate) fn hidden_margin(&self, body_size: Size<Pixels>) -> Margin {
+        let extent = if self.edge.intersects(Anchor::TOP | Anchor::BOTTOM) {
+            body_size.height
+        } else {
+            body_size.width
+        };
+        let hidden = (extent - self.settings.strip).max(Pixels::ZERO);
+        let (mut top, mut right, mut bottom, mut left) = self.margin;
+        if self.edge == Anchor::TOP {
+            top -= hidden;
+        } else if self.edge == Anchor::BOTTOM {
+            bottom -= hidden;
+        } else if self.edge == Anchor::LEFT {
+            left -= hidden;
+        } else {
+            right -= hidden;
+        }
+        (top, right, bottom, left)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{px, size};
+
+    fn auto_hide(anchor: Anchor) -> AutoHideState {
+        AutoHideState::new(&LayerShellSettings {
+            anchor,
+            margin: Some((px(4.), px(4.), px(4.), px(4.))),
+            auto_hide: Some(AutoHide {
+                strip: px(2.),
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_hidden_margin_keeps_strip_on_screen() {
+        let body_size = size(px(400.), px(30.));
+        assert_eq!(
+            auto_hide(Anchor::TOP | Anchor::LEFT | Anchor::RIGHT).hidden_margin(body_size),
+            (px(-24.), px(4.), px(4.), px(4.))
+        );
+        assert_eq!(
+            auto_hide(Anchor::BOTTOM).hidden_margin(body_size),
+            (px(4.), px(4.), px(-24.), px(4.))
+        );
+        assert_eq!(
+            auto_hide(Anchor::LEFT).hidden_margin(body_size),
+            (px(4.), px(4.), px(4.), px(-394.))
+        );
+        assert_eq!(
+            auto_hide(Anchor::RIGHT | Anchor::TOP | Anchor::BOTTOM).hidden_margin(body_size),
+            (px(4.), px(-394.), px(4.), px(4.))
+        );
+    }
+
+    #[test]
+    fn test_auto_hide_needs_attached_edge() {
+        let settings = LayerShellSettings {
+            anchor: Anchor::TOP | Anchor::LEFT,
+            auto_hide: Some(AutoHide::default()),
+            ..Default::default()
+        };
+        assert!(AutoHideState::new(&settings).is_none());
+    }
+}
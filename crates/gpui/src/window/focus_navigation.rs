@@ -0,0 +1,273 @@
+//! Moving the focus between the focusable elements of a window with the keyboard, in tab order
+//! or towards a direction, see [`FocusableElement::tab_index`](crate::FocusableElement::tab_index).
+
+use crate::{self as gpui, actions, Action, App, Bounds, FocusHandle, FocusId, Pixels, Window};
+
+// Windows handle these when no element does, bind them to keys like tab and the arrow keys to
+// make the focusable elements of every view keyboard operable.
+actions!(
+    focus,
+    [
+        FocusNext,
+        FocusPrevious,
+        FocusUp,
+        FocusDown,
+        FocusLeft,
+        FocusRight
+    ]
+);
+
+/// A direction to move the focus towards, see [`Window::focus_in_direction`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FocusDirection {
+    /// Towards the top of the window.
+    Up,
+    /// Towards the bottom of the window.
+    Down,
+    /// Towards the left of the window.
+    Left,
+    /// Towards the right of the window.
+    Right,
+}
+
+/// An element keyboard navigation can focus, inserted while prepainting it.
+#[derive(Clone, Debug)]
+pub(crate) struct FocusStop {
+    pub(crate) id: FocusId,
+    pub(crate) tab_index: isize,
+    pub(crate) bounds: Bounds<Pixels>,
+}
+
+impl Window {
+    /// Lets keyboard navigation focus the element with the given handle, see
+    /// [`FocusableElement::tab_index`](crate::FocusableElement::tab_index).
+    ///
+    /// This method should only be called as part of the prepaint phase of element drawing.
+    pub fn insert_focus_stop(
+        &mut self,
+        focus_handle: &FocusHandle,
+        tab_index: isize,
+        bounds: Bounds<Pixels>,
+    ) {
+        self.invalidator.debug_assert_prepaint();
+        self.next_frame.focus_stops.push(FocusStop {
+            id: focus_handle.id,
+            tab_index,
+            bounds,
+        });
+    }
+
+    /// Moves the focus to the next element keyboard navigation can focus, wrapping around to
+    /// the first one. Focuses the first one if none is focused.
+    pub fn focus_next(&mut self, cx: &App) {
+        let stops = self.tab_order();
+        let next = match self.focused_stop(&stops) {
+            Some(ix) => (ix + 1) % stops.len(),
+            None => 0,
+        };
+        if let Some(stop) = stops.get(next) {
+            self.focus_stop(stop.id, cx);
+        }
+    }
+
+    /// Moves the focus to the previous element keyboard navigation can focus, wrapping around
+    /// to the last one. Focuses the last one if none is focused.
+    pub fn focus_previous(&mut self, cx: &App) {
+        let stops = self.tab_order();
+        let previous = match self.focused_stop(&stops) {
+            Some(ix) => (ix + stops.len() - 1) % stops.len(),
+            None => stops.len().saturating_sub(1),
+        };
+        if let Some(stop) = stops.get(previous) {
+            self.focus_stop(stop.id, cx);
+        }
+    }
+
+    /// Moves the focus to the closest element keyboard navigation can focus in the given
+    /// direction, like to the item below in a grid of launcher entries. Past the last element
+    /// in that direction, the focus wraps around to the farthest one in line on the other side.
+    /// Focuses the first element in tab order if none is focused.
+    pub fn focus_in_direction(&mut self, direction: FocusDirection, cx: &App) {
+        let stops = self.tab_order();
+        let target = match self.focused_stop(&stops) {
+            Some(ix) => stop_in_direction(&stops, &stops[ix].bounds, direction),
+            None => (!stops.is_empty()).then_some(0),
+        };
+        if let Some(ix) = target {
+            self.focus_stop(stops[ix].id, cx);
+        }
+    }
+
+    /// Handles the focus navigation actions no element handled, returning whether the action
+    /// was one of them.
+    pub(crate) fn navigate_focus(&mut self, action: &dyn Action, cx: &App) -> bool {
+        if self.rendered_frame.focus_stops.is_empty() {
+            return false;
+        }
+        let action = action.as_any();
+        if action.is::<FocusNext>() {
+            self.focus_next(cx);
+        } else if action.is::<FocusPrevious>() {
+            self.focus_previous(cx);
+        } else if action.is::<FocusUp>() {
+            self.focus_in_direction(FocusDirection::Up, cx);
+        } else if action.is::<FocusDown>() {
+            self.focus_in_direction(FocusDirection::Down, cx);
+        } else if action.is::<FocusLeft>() {
+            self.focus_in_direction(FocusDirection::Left, cx);
+        } else if action.is::<FocusRight>() {
+            self.focus_in_direction(FocusDirection::Right, cx);
+        } else {
+            return false;
+        }
+        true
+    }
+
+    /// Returns the focus stops of the last frame by tab index, then in the order they were
+    /// prepainted.
+    fn tab_order(&self) -> Vec<FocusStop> {
+        let mut stops = self.rendered_frame.focus_stops.clone();
+        stops.sort_by_key(|stop| stop.tab_index);
+        stops
+    }
+
+    /// Returns the stop that is focused, or else the innermost one containing the focus.
+    fn focused_stop(&self, stops: &[FocusStop]) -> Option<usize> {
+        let focused = self.focus?;
+        stops
+            .iter()
+            .position(|stop| stop.id == focused)
+            .or_else(|| {
+                stops
+                    .iter()
+                    .rposition(|stop| stop.id.contains(focused, self))
+            })
+    }
+
+    fn focus_stop(&mut self, id: FocusId, cx: &App) {
+        if let Some(handle) = FocusHandle::for_id(id, &cx.focus_handles) {
+            self.focus(&handle);
+        }
+    }
+}
+
+/// Returns the closest stop in the given direction from `from`, favoring the ones in line with
+/// it, or else the farthest one in line on the other side.
+fn stop_in_direction(
+    stops: &[FocusStop],
+    from: &Bounds<Pixels>,
+    direction: FocusDirection,
+) -> Option<usize> {
+    let center = from.center();
+    // The distance of every stop along the direction and across it.
+    let offsets = stops.iter().enumerate().map(|(ix, stop)| {
+        let delta = stop.bounds.center() - center;
+        let (along, across) = match direction {
+            FocusDirection::Up => (-delta.y, delta.x),
+            FocusDirection::Down => (delta.y, delta.x),
+            FocusDirection::Left => (-delta.x, delta.y),
+            FocusDirection::Right => (delta.x, delta.y),
+        };
+        (ix, along.0, across.0.abs())
+    });
+
+    offsets
+        .clone()
+        .filter(|(_, along, _)| *along > 0.)
+        .min_by(|a, b| (a.1 + 2. * a.2).total_cmp(&(b.1 + 2. * b.2)))
+        .or_else(|| {
+            offsets
+                .filter(|(_, along, _)| *along < 0.)
+                .min_by(|a, b| a.2.total_cmp(&b.2).then(a.1.total_cmp(&b.1)))
+        })
+        .map(|(ix, _, _)| ix)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        self as gpui, div, prelude::*, px, FocusDirection, FocusHandle, FocusNext, FocusPrevious,
+        KeyBinding, Keystroke, TestAppContext, Window, WindowHandle,
+    };
+
+    struct Grid {
+        items: Vec<FocusHandle>,
+    }
+
+    impl Render for Grid {
+        fn render(&mut self, _: &mut Window, _: &mut Context<Self>) -> impl IntoElement {
+            // A 2x2 grid, whose items are tabbed through by column.
+            div()
+                .size_full()
+                .children(self.items.iter().enumerate().map(|(ix, handle)| {
+                    div()
+                        .absolute()
+                        .left(px(100. * (ix % 2) as f32))
+                        .top(px(100. * (ix / 2) as f32))
+                        .size(px(50.))
+                        .track_focus(handle)
+                        .tab_index((ix % 2) as isize)
+                }))
+        }
+    }
+
+    fn open_grid(cx: &mut TestAppContext) -> WindowHandle<Grid> {
+        cx.update(|cx| {
+            cx.open_window(Default::default(), |_, cx| {
+                cx.new(|cx| Grid {
+                    items: (0..4).map(|_| cx.focus_handle()).collect(),
+                })
+            })
+            .unwrap()
+        })
+    }
+
+    fn focused_item(window: WindowHandle<Grid>, cx: &mut TestAppContext) -> Option<usize> {
+        window
+            .update(cx, |grid, window, _| {
+                grid.items.iter().position(|item| item.is_focused(window))
+            })
+            .unwrap()
+    }
+
+    #[gpui::test]
+    fn test_tab_order_wraps_around(cx: &mut TestAppContext) {
+        let window = open_grid(cx);
+        cx.update(|cx| {
+            cx.bind_keys([
+                KeyBinding::new("tab", FocusNext, None),
+                KeyBinding::new("shift-tab", FocusPrevious, None),
+            ]);
+        });
+
+        let mut order = Vec::new();
+        for _ in 0..5 {
+            cx.dispatch_keystroke(*window, Keystroke::parse("tab").unwrap());
+            order.push(focused_item(window, cx));
+        }
+        assert_eq!(order, [Some(0), Some(2), Some(1), Some(3), Some(0)]);
+
+        cx.dispatch_keystroke(*window, Keystroke::parse("shift-tab").unwrap());
+        assert_eq!(focused_item(window, cx), Some(3));
+    }
+
+    #[gpui::test]
+    fn test_focus_in_direction(cx: &mut TestAppContext) {
+        let window = open_grid(cx);
+        let step = |direction, cx: &mut TestAppContext| {
+            window
+                .update(cx, |_, window, cx| window.focus_in_direction(direction, cx))
+                .unwrap();
+            cx.run_until_parked();
+            focused_item(window, cx)
+        };
+
+        assert_eq!(step(FocusDirection::Down, cx), Some(0));
+        assert_eq!(step(FocusDirection::Right, cx), Some(1));
+        assert_eq!(step(FocusDirection::Down, cx), Some(3));
+        assert_eq!(step(FocusDirection::Left, cx), Some(2));
+        // Past the bottom, the focus wraps around to the top of the column.
+        assert_eq!(step(FocusDirection::Down, cx), Some(0));
+        assert_eq!(step(FocusDirection::Up, cx), Some(2));
+    }
+}
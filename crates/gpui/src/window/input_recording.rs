@@ -0,0 +1,112 @@
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::PlatformInput;
+
+/// An input event received by a window, with the time elapsed since the recording started.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordedInput {
+    /// The time elapsed between the start of the recording and the event.
+    pub elapsed: Duration,
+    /// The event, in the window's logical coordinates.
+    pub event: PlatformInput,
+}
+
+/// The input a window received between [`Window::start_input_recording`] and
+/// [`Window::stop_input_recording`].
+///
+/// Recordings serialize to JSON, so one taken in a live session can be saved and replayed in a
+/// test with `VisualTestContext::replay_input`.
+///
+/// [`Window::start_input_recording`]: crate::Window::start_input_recording
+/// [`Window::stop_input_recording`]: crate::Window::stop_input_recording
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct InputRecording {
+    /// The recorded events, in the order they were received.
+    pub events: Vec<RecordedInput>,
+}
+
+impl InputRecording {
+    /// Serializes the recording to JSON.
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Parses a recording serialized with [`InputRecording::to_json`].
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Returns the time between the start of the recording and its last event.
+    pub fn duration(&self) -> Duration {
+        self.events
+            .last()
+            .map_or(Duration::ZERO, |event| event.elapsed)
+    }
+}
+
+pub(crate) struct InputRecorder {
+    started_at: Instant,
+    recording: InputRecording,
+}
+
+impl InputRecorder {
+    pub(crate) fn new() -> Self {
+        Self {
+            started_at: Instant::now(),
+            recording: InputRecording::default(),
+        }
+    }
+
+    pub(crate) fn record(&mut self, event: &PlatformInput) {
+        self.recording.events.push(RecordedInput {
+            elapsed: self.started_at.elapsed(),
+            event: event.clone(),
+        });
+    }
+
+    pub(crate) fn finish(self) -> InputRecording {
+        self.recording
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        self as gpui, div, point, px, Context, InputRecording, InteractiveElement, IntoElement,
+        Modifiers, MouseButton, Render, Styled, TestAppContext, Window,
+    };
+
+    struct ClickCounter {
+        clicks: usize,
+    }
+
+    impl Render for ClickCounter {
+        fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+            div().size_full().on_mouse_down(
+                MouseButton::Left,
+                cx.listener(|this, _, _, _| this.clicks += 1),
+            )
+        }
+    }
+
+    #[gpui::test]
+    fn test_replay_recorded_input(cx: &mut TestAppContext) {
+        let (view, cx) = cx.add_window_view(|_, _| ClickCounter { clicks: 0 });
+
+        cx.update(|window, _| window.start_input_recording());
+        cx.simulate_click(point(px(10.), px(10.)), Modifiers::none());
+        cx.simulate_click(point(px(20.), px(20.)), Modifiers::none());
+        let recording = cx
+            .update(|window, _| window.stop_input_recording())
+            .unwrap();
+        assert_eq!(recording.events.len(), 4);
+        assert_eq!(view.read_with(cx, |view, _| view.clicks), 2);
+
+        let recording = InputRecording::from_json(&recording.to_json().unwrap()).unwrap();
+        cx.replay_input(&recording);
+        assert_eq!(view.read_with(cx, |view, _| view.clicks), 4);
+    }
+}
@@ -0,0 +1,113 @@
+use std::time::Instant;
+
+use crate::{Animation, Pixels};
+
+/// The top, right, bottom and left margins of a layer shell window.
+pub(crate) type Margin = (Pixels, Pixels, Pixels, Pixels);
+
+/// The running animations of a layer shell window's margin and exclusive zone, see
+/// [`Window::animate_layer_shell_margin`](crate::Window::animate_layer_shell_margin).
+#[derive(Default)]
+pub(crate) struct LayerShellAnimations {
+    pub(crate) margin: Option<Transition<Margin>>,
+    pub(crate) exclusive_zone: Option<Transition<Pixels>>,
+    /// Whether the next step is already scheduled for the next frame.
+    pub(crate) scheduled: bool,
+}
+
+impl LayerShellAnimations {
+    pub(crate) fn is_running(&self) -> bool {
+        self.margin.is_some() || self.exclusive_zone.is_some()
+    }
+}
+
+/// A setting animating from one value to another.
+pub(crate) struct Transition<T> {
+    from: T,
+    to: T,
+    started_at: Instant,
+    animation: Animation,
+}
+
+impl<T: Interpolate> Transition<T> {
+    pub(crate) fn new(from: T, to: T, animation: Animation) -> Self {
+        Self {
+            from,
+            to,
+            started_at: Instant::now(),
+            animation,
+        }
+    }
+
+    /// Returns the value at the given time, and whether the transition is done. Transitions
+    /// run once, even with a repeating animation.
+    pub(crate) fn value_at(&self, now: Instant) -> (T, bool) {
+        let duration = self.animation.duration.as_secs_f32();
+        let delta = if duration > 0. {
+            now.saturating_duration_since(self.started_at).as_secs_f32() / duration
+        } else {
+            1.
+        };
+        if delta >= 1. {
+            return (self.to, true);
+        }
+        let delta = (self.animation.easing)(delta);
+        (self.from.interpolate(self.to, delta), false)
+    }
+
+    /// Returns the value the transition ends at.
+    pub(crate) fn to(&self) -> T {
+        self.to
+    }
+}
+
+pub(crate) trait Interpolate: Copy {
+    fn interpolate(self, to: Self, delta: f32) -> Self;
+}
+
+impl Interpolate for Pixels {
+    fn interpolate(self, to: Self, delta: f32) -> Self {
+        self + (to - self) * delta
+    }
+}
+
+impl Interpolate for Margin {
+    fn interpolate(self, to: Self, delta: f32) -> Self {
+        (
+            self.0.interpolate(to.0, delta),
+            self.1.interpolate(to.1, delta),
+            self.2.interpolate(to.2, delta),
+            self.3.interpolate(to.3, delta),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{ease_in_out, px, Animation};
+
+    #[test]
+    fn test_transition_eases_to_target() {
+        let transition = Transition::new(
+            px(0.),
+            px(100.),
+            Animation::new(Duration::from_millis(200)).with_easing(ease_in_out),
+        );
+        let start = transition.started_at;
+
+        assert_eq!(transition.value_at(start), (px(0.), false));
+        assert_eq!(
+            transition.value_at(start + Duration::from_millis(100)),
+            (px(50.), false)
+        );
+        let (early, _) = transition.value_at(start + Duration::from_millis(20));
+        assert!(early < px(10.));
+        assert_eq!(
+            transition.value_at(start + Duration::from_millis(300)),
+            (px(100.), true)
+        );
+    }
+}
@@ -1,6 +1,10 @@
-use std::future::Future;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
 
-use gpui::{App, AppContext, Global, ReadGlobal, Task};
+use gpui::{App, AppContext, AsyncApp, Global, ReadGlobal, Task};
 use tokio::task::JoinError;
 use util::defer;
 
@@ -52,7 +56,71 @@ impl Tokio {
         })
     }
 
-    pub fn handle(cx: &mut App) -> tokio::runtime::Handle {
+    /// Spawns the given future on GPUI's foreground executor within Tokio's runtime, so it can
+    /// use Tokio-based libraries and update entities without sending its results back through
+    /// a channel.
+    pub fn spawn_local<Fut, R>(cx: &App, f: impl FnOnce(AsyncApp) -> Fut) -> Task<R>
+    where
+        Fut: Future<Output = R> + 'static,
+        R: 'static,
+    {
+        let handle = Self::handle(cx);
+        cx.spawn(|cx| Compat::new(handle, f(cx)))
+    }
+
+    /// Spawns the given future on GPUI's background executor within Tokio's runtime.
+    pub fn background_spawn<Fut, R>(cx: &App, future: Fut) -> Task<R>
+    where
+        Fut: Future<Output = R> + Send + 'static,
+        R: Send + 'static,
+    {
+        cx.background_spawn(Self::compat(cx, future))
+    }
+
+    /// Wraps the given future so that GPUI's executors can poll it within Tokio's runtime, which
+    /// Tokio's timers, sockets and the libraries built on them require.
+    pub fn compat<Fut: Future>(cx: &App, future: Fut) -> Compat<Fut> {
+        Compat::new(Self::handle(cx), future)
+    }
+
+    pub fn handle(cx: &App) -> tokio::runtime::Handle {
         GlobalTokio::global(cx).runtime.handle().clone()
     }
 }
+
+/// A future polled within Tokio's runtime, see [`Tokio::compat`].
+pub struct Compat<Fut> {
+    future: Option<Pin<Box<Fut>>>,
+    handle: tokio::runtime::Handle,
+}
+
+impl<Fut> Compat<Fut> {
+    fn new(handle: tokio::runtime::Handle, future: Fut) -> Self {
+        Self {
+            future: Some(Box::pin(future)),
+            handle,
+        }
+    }
+}
+
+impl<Fut: Future> Future for Compat<Fut> {
+    type Output = Fut::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let _guard = this.handle.enter();
+        this.future
+            .as_mut()
+            .expect("future is only taken when dropped")
+            .as_mut()
+            .poll(cx)
+    }
+}
+
+impl<Fut> Drop for Compat<Fut> {
+    fn drop(&mut self) {
+        // Tokio resources held by the future may need the runtime when they're dropped.
+        let _guard = self.handle.enter();
+        self.future.take();
+    }
+}
@@ -0,0 +1,157 @@
+use std::{
+    io::{BufRead, BufReader},
+    path::Path,
+    process::{Child, Command, Stdio},
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail, Context as _, Result};
+use gpui::AsyncApp;
+use parking_lot::Mutex;
+use tempfile::TempDir;
+
+use crate::ProtocolLog;
+
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+const WAIT_TIMEOUT: Duration = Duration::from_secs(5);
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Sway without a bar, Xwayland or anything else that would connect to it besides the app.
+const SWAY_CONFIG: &str = "xwayland disable\noutput * resolution 1920x1080 scale 1\n";
+
+/// A wlroots compositor on the headless backend, with no outputs to show anything on but a
+/// virtual one, and no input devices.
+pub struct HeadlessCompositor {
+    child: Child,
+    runtime_dir: TempDir,
+    socket_name: String,
+    log: Arc<Mutex<ProtocolLog>>,
+}
+
+impl HeadlessCompositor {
+    /// Starts the compositor in a runtime directory of its own, returning once it's listening.
+    pub fn start() -> Result<Self> {
+        let runtime_dir = tempfile::Builder::new()
+            .prefix("gpui-wayland-test")
+            .tempdir()?;
+        let mut command = match std::env::var("GPUI_WAYLAND_TEST_COMPOSITOR") {
+            Ok(command_line) => {
+                let mut args = command_line.split_whitespace();
+                let program = args
+                    .next()
+                    .ok_or_else(|| anyhow!("GPUI_WAYLAND_TEST_COMPOSITOR is empty"))?;
+                let mut command = Command::new(program);
+                command.args(args);
+                command
+            }
+            Err(_) => {
+                let config = runtime_dir.path().join("sway.conf");
+                std::fs::write(&config, SWAY_CONFIG)?;
+                let mut command = Command::new("sway");
+                command.arg("--config").arg(config);
+                command
+            }
+        };
+        let mut child = command
+            .env("XDG_RUNTIME_DIR", runtime_dir.path())
+            .env("WLR_BACKENDS", "headless")
+            .env("WLR_HEADLESS_OUTPUTS", "1")
+            .env("WLR_RENDERER", "pixman")
+            .env("WLR_LIBINPUT_NO_DEVICES", "1")
+            .env("WAYLAND_DEBUG", "server")
+            .env_remove("WAYLAND_DISPLAY")
+            .env_remove("DISPLAY")
+            .env_remove("SWAYSOCK")
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("failed to run {:?}", command.get_program()))?;
+
+        let log = Arc::new(Mutex::new(ProtocolLog::default()));
+        let stderr = child.stderr.take().unwrap();
+        thread::spawn({
+            let log = log.clone();
+            move || {
+                for line in BufReader::new(stderr).lines() {
+                    let Ok(line) = line else {
+                        break;
+                    };
+                    log.lock().push_line(&line);
+                }
+            }
+        });
+
+        let mut compositor = Self {
+            child,
+            runtime_dir,
+            socket_name: String::new(),
+            log,
+        };
+        compositor.socket_name = compositor.wait_for_socket()?;
+        Ok(compositor)
+    }
+
+    fn wait_for_socket(&mut self) -> Result<String> {
+        let started_at = Instant::now();
+        loop {
+            for entry in std::fs::read_dir(self.runtime_dir.path())? {
+                let name = entry?.file_name().to_string_lossy().into_owned();
+                if name.starts_with("wayland-") && !name.ends_with(".lock") {
+                    return Ok(name);
+                }
+            }
+            if let Some(status) = self.child.try_wait()? {
+                bail!("the compositor exited with {status}");
+            }
+            if started_at.elapsed() > STARTUP_TIMEOUT {
+                bail!("the compositor didn't create a socket in {STARTUP_TIMEOUT:?}");
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// The name of the socket to connect to, for `WAYLAND_DISPLAY`.
+    pub fn socket_name(&self) -> &str {
+        &self.socket_name
+    }
+
+    /// The directory the socket is in, for `XDG_RUNTIME_DIR`.
+    pub fn runtime_dir(&self) -> &Path {
+        self.runtime_dir.path()
+    }
+
+    /// The messages exchanged with the compositor so far.
+    pub fn protocol_log(&self) -> ProtocolLog {
+        self.log.lock().clone()
+    }
+
+    /// Waits for the protocol log to get to a state the given function finds something in,
+    /// returning it. The messages are read from the compositor as the app runs, so assertions
+    /// on them should go through this, rather than check the log right after doing something.
+    pub async fn wait_for<T>(
+        &self,
+        cx: &AsyncApp,
+        mut find: impl FnMut(&ProtocolLog) -> Option<T>,
+    ) -> Result<T> {
+        let started_at = Instant::now();
+        loop {
+            if let Some(found) = find(&self.log.lock()) {
+                return Ok(found);
+            }
+            if started_at.elapsed() > WAIT_TIMEOUT {
+                bail!("the protocol log didn't get there in {WAIT_TIMEOUT:?}");
+            }
+            cx.background_executor().timer(POLL_INTERVAL).await;
+        }
+    }
+}
+
+impl Drop for HeadlessCompositor {
+    fn drop(&mut self) {
+        self.child.kill().ok();
+        self.child.wait().ok();
+    }
+}
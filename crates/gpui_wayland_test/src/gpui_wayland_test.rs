@@ -0,0 +1,76 @@
+//! Integration tests for GPUI's Wayland backend, against a real compositor.
+//!
+//! [`run`] starts a headless wlroots compositor, points the app at it and runs a test in it,
+//! where it can open windows like any GPUI app. The compositor logs the messages the app
+//! exchanges with it, so the test can assert on the protocol state it ends up in, like the
+//! size and exclusive zone committed for a layer surface or the configure serials acked:
+//!
+//! ```ignore
+//! gpui_wayland_test::run(|compositor, cx| async move {
+//!     cx.update(|cx| cx.open_window(options, |_, cx| cx.new(|_| Empty)))??;
+//!     let layer_surface = compositor
+//!         .wait_for(&cx, |log| log.layer_surfaces().pop())
+//!         .await?;
+//!     assert_eq!(layer_surface.committed.exclusive_zone, 30);
+//!     Ok(())
+//! });
+//! ```
+//!
+//! The compositor is `sway` by default, another one can be given as a command line with
+//! `GPUI_WAYLAND_TEST_COMPOSITOR`. It has to be built on libwayland-server, whose debug log
+//! is what the messages are read from. Tests need one to be installed, so they're marked
+//! `#[ignore]` and run with `cargo test -p gpui_wayland_test -- --ignored`.
+
+mod compositor;
+mod protocol_log;
+
+pub use compositor::*;
+pub use protocol_log::*;
+
+use std::{cell::RefCell, future::Future, rc::Rc, sync::Arc};
+
+use anyhow::Result;
+use gpui::{Application, AsyncApp};
+use parking_lot::Mutex;
+
+/// Tests share the process' environment, so they take turns.
+static RUNNING: Mutex<()> = Mutex::new(());
+
+/// Runs a test in an app connected to a headless compositor of its own, quitting the app once
+/// it's done. Panics when the compositor can't be started or the test returns an error.
+pub fn run<F, Fut>(test: F)
+where
+    F: 'static + FnOnce(Arc<HeadlessCompositor>, AsyncApp) -> Fut,
+    Fut: 'static + Future<Output = Result<()>>,
+{
+    let _running = RUNNING.lock();
+    let compositor = match HeadlessCompositor::start() {
+        Ok(compositor) => Arc::new(compositor),
+        Err(error) => panic!("the compositor didn't start: {error:#}"),
+    };
+
+    // The platform is picked, and connects, when the app is created.
+    std::env::set_var("WAYLAND_DISPLAY", compositor.socket_name());
+    std::env::set_var("XDG_RUNTIME_DIR", compositor.runtime_dir());
+    std::env::remove_var("DISPLAY");
+    std::env::remove_var("ZED_HEADLESS");
+
+    let outcome = Rc::new(RefCell::new(None));
+    Application::new().run({
+        let outcome = outcome.clone();
+        move |cx| {
+            cx.spawn(|cx| async move {
+                let result = test(compositor, cx.clone()).await;
+                *outcome.borrow_mut() = Some(result);
+                cx.update(|cx| cx.quit()).ok();
+            })
+            .detach();
+        }
+    });
+
+    match outcome.take() {
+        Some(Ok(())) => {}
+        Some(Err(error)) => panic!("{error:?}"),
+        None => panic!("the app quit before the test was done"),
+    }
+}
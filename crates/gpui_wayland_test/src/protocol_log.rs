@@ -0,0 +1,513 @@
+use collections::HashMap;
+
+/// Which way a message went.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// From the app to the compositor.
+    Request,
+    /// From the compositor to the app.
+    Event,
+}
+
+/// A message from the compositor's debug log, like `wl_surface#3.commit()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Message {
+    pub direction: Direction,
+    pub interface: String,
+    pub object_id: u32,
+    pub name: String,
+    /// The arguments as libwayland prints them: numbers, quoted strings, `interface#id` for
+    /// objects, `new id interface#id` for the ones created, and `nil`.
+    pub args: Vec<String>,
+}
+
+impl Message {
+    /// Parses a line of libwayland's debug log, returning `None` for anything else the
+    /// compositor writes to its standard error, and for discarded messages.
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut rest = line.trim();
+        // The timestamp, then the queue, which newer versions add.
+        if let Some(after) = rest.strip_prefix('[') {
+            rest = after.split_once(']')?.1.trim_start();
+        } else {
+            return None;
+        }
+        if let Some(after) = rest.strip_prefix('{') {
+            rest = after.split_once('}')?.1.trim_start();
+        }
+        if rest.starts_with("discarded ") {
+            return None;
+        }
+        let direction = match rest.strip_prefix("->") {
+            Some(after) => {
+                rest = after.trim_start();
+                Direction::Event
+            }
+            None => Direction::Request,
+        };
+
+        let (target, rest) = rest.split_once('(')?;
+        let args = rest.trim_end().strip_suffix(')')?;
+        let (object, name) = target.rsplit_once('.')?;
+        let (interface, object_id) = split_object(object)?;
+        Some(Self {
+            direction,
+            interface: interface.to_string(),
+            object_id,
+            name: name.to_string(),
+            args: split_args(args),
+        })
+    }
+
+    fn is(&self, direction: Direction, interface: &str, name: &str) -> bool {
+        self.direction == direction && self.interface == interface && self.name == name
+    }
+
+    /// The argument at the given index, as a number.
+    pub fn int_arg(&self, index: usize) -> Option<i64> {
+        self.args.get(index)?.parse().ok()
+    }
+
+    /// The argument at the given index, as a string without its quotes.
+    pub fn string_arg(&self, index: usize) -> Option<&str> {
+        self.args.get(index)?.strip_prefix('"')?.strip_suffix('"')
+    }
+
+    /// The id of the object passed or created at the given index.
+    pub fn object_arg(&self, index: usize) -> Option<u32> {
+        let arg = self.args.get(index)?;
+        split_object(arg.strip_prefix("new id ").unwrap_or(arg)).map(|(_, id)| id)
+    }
+}
+
+/// Splits `interface#id`, or `interface@id` as older versions print it.
+fn split_object(object: &str) -> Option<(&str, u32)> {
+    let (interface, id) = object.rsplit_once(['#', '@'])?;
+    Some((interface, id.parse().ok()?))
+}
+
+fn split_args(args: &str) -> Vec<String> {
+    let mut split = Vec::new();
+    let mut current = String::new();
+    let mut in_string = false;
+    let mut chars = args.chars();
+    while let Some(char) = chars.next() {
+        match char {
+            '"' => in_string = !in_string,
+            '\\' if in_string => {
+                current.push(char);
+                if let Some(escaped) = chars.next() {
+                    current.push(escaped);
+                }
+                continue;
+            }
+            ',' if !in_string => {
+                split.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(char);
+    }
+    if !current.trim().is_empty() {
+        split.push(current.trim().to_string());
+    }
+    split
+}
+
+/// The double buffered state of a layer surface, as set by the app.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LayerSurfaceState {
+    pub size: (u32, u32),
+    pub anchor: u32,
+    pub exclusive_zone: i32,
+    /// The top, right, bottom and left margins.
+    pub margin: (i32, i32, i32, i32),
+    pub keyboard_interactivity: u32,
+    pub layer: u32,
+}
+
+/// A `zwlr_layer_surface_v1` the app created.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LayerSurface {
+    pub id: u32,
+    pub surface_id: u32,
+    pub namespace: String,
+    /// The state as of the last commit of the surface.
+    pub committed: LayerSurfaceState,
+    /// Whether the surface was committed at all.
+    pub has_committed: bool,
+    pub pending: LayerSurfaceState,
+    /// The serial and size of each configure event, in order.
+    pub configures: Vec<(u32, (u32, u32))>,
+    pub acked_serials: Vec<u32>,
+    pub closed: bool,
+    pub destroyed: bool,
+}
+
+/// The double buffered state of an xdg toplevel, as set by the app.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ToplevelState {
+    /// The x, y, width and height of the window geometry, if set.
+    pub window_geometry: Option<(i32, i32, i32, i32)>,
+    pub min_size: (i32, i32),
+    pub max_size: (i32, i32),
+}
+
+/// An `xdg_toplevel` the app created.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Toplevel {
+    pub id: u32,
+    pub xdg_surface_id: u32,
+    pub surface_id: u32,
+    pub title: Option<String>,
+    pub app_id: Option<String>,
+    /// The state as of the last commit of the surface.
+    pub committed: ToplevelState,
+    /// Whether the surface was committed at all.
+    pub has_committed: bool,
+    pub pending: ToplevelState,
+    /// The sizes of the toplevel's configure events, in order.
+    pub configured_sizes: Vec<(i32, i32)>,
+    /// The serials of the xdg surface's configure events, in order.
+    pub configured_serials: Vec<u32>,
+    pub acked_serials: Vec<u32>,
+    pub destroyed: bool,
+}
+
+/// The messages exchanged between the app and the compositor.
+///
+/// Object ids are reused once objects are destroyed, and aren't unique between clients, so
+/// this assumes the app is the only client and follows objects from their creation.
+#[derive(Clone, Debug, Default)]
+pub struct ProtocolLog {
+    messages: Vec<Message>,
+}
+
+impl ProtocolLog {
+    pub(crate) fn push_line(&mut self, line: &str) {
+        if let Some(message) = Message::parse(line) {
+            self.messages.push(message);
+        }
+    }
+
+    pub fn messages(&self) -> &[Message] {
+        &self.messages
+    }
+
+    /// The requests with the given name sent on objects of the given interface.
+    pub fn requests<'a>(
+        &'a self,
+        interface: &'a str,
+        name: &'a str,
+    ) -> impl Iterator<Item = &'a Message> + 'a {
+        self.messages
+            .iter()
+            .filter(move |message| message.is(Direction::Request, interface, name))
+    }
+
+    /// The events with the given name sent to objects of the given interface.
+    pub fn events<'a>(
+        &'a self,
+        interface: &'a str,
+        name: &'a str,
+    ) -> impl Iterator<Item = &'a Message> + 'a {
+        self.messages
+            .iter()
+            .filter(move |message| message.is(Direction::Event, interface, name))
+    }
+
+    /// The layer surfaces the app created, in order, including destroyed ones.
+    pub fn layer_surfaces(&self) -> Vec<LayerSurface> {
+        let mut surfaces: Vec<LayerSurface> = Vec::new();
+        let mut live: HashMap<u32, usize> = HashMap::default();
+        for message in &self.messages {
+            let index = live.get(&message.object_id).copied();
+            match (message.direction, message.interface.as_str()) {
+                (Direction::Request, "zwlr_layer_shell_v1")
+                    if message.name == "get_layer_surface" =>
+                {
+                    let (Some(id), Some(surface_id)) =
+                        (message.object_arg(0), message.object_arg(1))
+                    else {
+                        continue;
+                    };
+                    let pending = LayerSurfaceState {
+                        layer: message.int_arg(3).unwrap_or_default() as u32,
+                        ..Default::default()
+                    };
+                    live.insert(id, surfaces.len());
+                    surfaces.push(LayerSurface {
+                        id,
+                        surface_id,
+                        namespace: message.string_arg(4).unwrap_or_default().to_string(),
+                        pending,
+                        ..Default::default()
+                    });
+                }
+                (Direction::Request, "zwlr_layer_surface_v1") => {
+                    let Some(index) = index else {
+                        continue;
+                    };
+                    let surface = &mut surfaces[index];
+                    let int = |index| message.int_arg(index).unwrap_or_default();
+                    match message.name.as_str() {
+                        "set_size" => surface.pending.size = (int(0) as u32, int(1) as u32),
+                        "set_anchor" => surface.pending.anchor = int(0) as u32,
+                        "set_exclusive_zone" => surface.pending.exclusive_zone = int(0) as i32,
+                        "set_margin" => {
+                            surface.pending.margin =
+                                (int(0) as i32, int(1) as i32, int(2) as i32, int(3) as i32)
+                        }
+                        "set_keyboard_interactivity" => {
+                            surface.pending.keyboard_interactivity = int(0) as u32
+                        }
+                        "set_layer" => surface.pending.layer = int(0) as u32,
+                        "ack_configure" => surface.acked_serials.push(int(0) as u32),
+                        "destroy" => {
+                            surface.destroyed = true;
+                            live.remove(&message.object_id);
+                        }
+                        _ => {}
+                    }
+                }
+                (Direction::Event, "zwlr_layer_surface_v1") => {
+                    let Some(index) = index else {
+                        continue;
+                    };
+                    let surface = &mut surfaces[index];
+                    let int = |index| message.int_arg(index).unwrap_or_default();
+                    match message.name.as_str() {
+                        "configure" => surface
+                            .configures
+                            .push((int(0) as u32, (int(1) as u32, int(2) as u32))),
+                        "closed" => surface.closed = true,
+                        _ => {}
+                    }
+                }
+                (Direction::Request, "wl_surface") if message.name == "commit" => {
+                    for &index in live.values() {
+                        let surface: &mut LayerSurface = &mut surfaces[index];
+                        if surface.surface_id == message.object_id {
+                            surface.committed = surface.pending;
+                            surface.has_committed = true;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        surfaces
+    }
+
+    /// The xdg toplevels the app created, in order, including destroyed ones.
+    pub fn toplevels(&self) -> Vec<Toplevel> {
+        let mut toplevels: Vec<Toplevel> = Vec::new();
+        // The wl_surface of each live xdg_surface, and the toplevel it got, if any.
+        let mut xdg_surfaces: HashMap<u32, (u32, Option<usize>)> = HashMap::default();
+        let mut live: HashMap<u32, usize> = HashMap::default();
+        for message in &self.messages {
+            let int = |index| message.int_arg(index).unwrap_or_default();
+            match (message.direction, message.interface.as_str()) {
+                (Direction::Request, "xdg_wm_base") if message.name == "get_xdg_surface" => {
+                    if let (Some(id), Some(surface_id)) =
+                        (message.object_arg(0), message.object_arg(1))
+                    {
+                        xdg_surfaces.insert(id, (surface_id, None));
+                    }
+                }
+                (Direction::Request, "xdg_surface") => {
+                    let Some((surface_id, toplevel)) = xdg_surfaces.get_mut(&message.object_id)
+                    else {
+                        continue;
+                    };
+                    match message.name.as_str() {
+                        "get_toplevel" => {
+                            let Some(id) = message.object_arg(0) else {
+                                continue;
+                            };
+                            *toplevel = Some(toplevels.len());
+                            live.insert(id, toplevels.len());
+                            toplevels.push(Toplevel {
+                                id,
+                                xdg_surface_id: message.object_id,
+                                surface_id: *surface_id,
+                                ..Default::default()
+                            });
+                        }
+                        "set_window_geometry" => {
+                            if let Some(index) = *toplevel {
+                                toplevels[index].pending.window_geometry = Some((
+                                    int(0) as i32,
+                                    int(1) as i32,
+                                    int(2) as i32,
+                                    int(3) as i32,
+                                ));
+                            }
+                        }
+                        "ack_configure" => {
+                            if let Some(index) = *toplevel {
+                                toplevels[index].acked_serials.push(int(0) as u32);
+                            }
+                        }
+                        "destroy" => {
+                            xdg_surfaces.remove(&message.object_id);
+                        }
+                        _ => {}
+                    }
+                }
+                (Direction::Event, "xdg_surface") if message.name == "configure" => {
+                    if let Some((_, Some(index))) = xdg_surfaces.get(&message.object_id) {
+                        toplevels[*index].configured_serials.push(int(0) as u32);
+                    }
+                }
+                (Direction::Request, "xdg_toplevel") => {
+                    let Some(&index) = live.get(&message.object_id) else {
+                        continue;
+                    };
+                    let toplevel = &mut toplevels[index];
+                    match message.name.as_str() {
+                        "set_title" => toplevel.title = message.string_arg(0).map(Into::into),
+                        "set_app_id" => toplevel.app_id = message.string_arg(0).map(Into::into),
+                        "set_min_size" => {
+                            toplevel.pending.min_size = (int(0) as i32, int(1) as i32)
+                        }
+                        "set_max_size" => {
+                            toplevel.pending.max_size = (int(0) as i32, int(1) as i32)
+                        }
+                        "destroy" => {
+                            toplevel.destroyed = true;
+                            live.remove(&message.object_id);
+                        }
+                        _ => {}
+                    }
+                }
+                (Direction::Event, "xdg_toplevel") if message.name == "configure" => {
+                    if let Some(&index) = live.get(&message.object_id) {
+                        toplevels[index]
+                            .configured_sizes
+                            .push((int(0) as i32, int(1) as i32));
+                    }
+                }
+                (Direction::Request, "wl_surface") if message.name == "commit" => {
+                    for &index in live.values() {
+                        let toplevel: &mut Toplevel = &mut toplevels[index];
+                        if toplevel.surface_id == message.object_id {
+                            toplevel.committed = toplevel.pending;
+                            toplevel.has_committed = true;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        toplevels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(lines: &str) -> ProtocolLog {
+        let mut log = ProtocolLog::default();
+        for line in lines.lines() {
+            log.push_line(line);
+        }
+        log
+    }
+
+    #[test]
+    fn test_parse_message() {
+        assert_eq!(
+            Message::parse(
+                "[1234567.890] zwlr_layer_shell_v1#12.get_layer_surface(new id zwlr_layer_surface_v1#30, wl_surface#3, nil, 2, \"a, \\\"b\\\"\")"
+            ),
+            Some(Message {
+                direction: Direction::Request,
+                interface: "zwlr_layer_shell_v1".into(),
+                object_id: 12,
+                name: "get_layer_surface".into(),
+                args: vec![
+                    "new id zwlr_layer_surface_v1#30".into(),
+                    "wl_surface#3".into(),
+                    "nil".into(),
+                    "2".into(),
+                    "\"a, \\\"b\\\"\"".into(),
+                ],
+            })
+        );
+        assert_eq!(
+            Message::parse("[  12.345] {Default Queue}  -> wl_callback@27.done(1234)")
+                .map(|message| (message.direction, message.object_id, message.int_arg(0))),
+            Some((Direction::Event, 27, Some(1234)))
+        );
+        assert_eq!(
+            Message::parse("00:00:00.123 [sway/main.c:100] Starting sway"),
+            None
+        );
+        assert_eq!(
+            Message::parse("[12.345] discarded wl_surface#3.commit()"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_layer_surface_state_applies_on_commit() {
+        let log = log(concat!(
+            "[1.0] zwlr_layer_shell_v1#12.get_layer_surface(new id zwlr_layer_surface_v1#30, wl_surface#3, nil, 2, \"bar\")\n",
+            "[1.0] zwlr_layer_surface_v1#30.set_size(0, 30)\n",
+            "[1.0] zwlr_layer_surface_v1#30.set_anchor(13)\n",
+            "[1.0] wl_surface#3.commit()\n",
+            "[1.0]  -> zwlr_layer_surface_v1#30.configure(7, 1920, 30)\n",
+            "[1.0] zwlr_layer_surface_v1#30.ack_configure(7)\n",
+            "[1.0] zwlr_layer_surface_v1#30.set_exclusive_zone(30)\n",
+        ));
+        let surfaces = log.layer_surfaces();
+        assert_eq!(surfaces.len(), 1);
+        let surface = &surfaces[0];
+        assert_eq!(surface.namespace, "bar");
+        assert_eq!(surface.surface_id, 3);
+        assert_eq!(surface.configures, [(7, (1920, 30))]);
+        assert_eq!(surface.acked_serials, [7]);
+        assert_eq!(
+            surface.committed,
+            LayerSurfaceState {
+                size: (0, 30),
+                anchor: 13,
+                layer: 2,
+                ..Default::default()
+            }
+        );
+        // Not committed yet.
+        assert_eq!(surface.pending.exclusive_zone, 30);
+    }
+
+    #[test]
+    fn test_reused_ids_start_new_toplevels() {
+        let log = log(concat!(
+            "[1.0] xdg_wm_base#10.get_xdg_surface(new id xdg_surface#20, wl_surface#3)\n",
+            "[1.0] xdg_surface#20.get_toplevel(new id xdg_toplevel#21)\n",
+            "[1.0] xdg_toplevel#21.set_title(\"first\")\n",
+            "[1.0] xdg_surface#20.set_window_geometry(0, 0, 400, 300)\n",
+            "[1.0] wl_surface#3.commit()\n",
+            "[1.0] xdg_toplevel#21.destroy()\n",
+            "[1.0] xdg_surface#20.destroy()\n",
+            "[1.0] xdg_wm_base#10.get_xdg_surface(new id xdg_surface#20, wl_surface#4)\n",
+            "[1.0] xdg_surface#20.get_toplevel(new id xdg_toplevel#21)\n",
+            "[1.0] xdg_toplevel#21.set_title(\"second\")\n",
+        ));
+        let toplevels = log.toplevels();
+        assert_eq!(toplevels.len(), 2);
+        assert_eq!(toplevels[0].title.as_deref(), Some("first"));
+        assert!(toplevels[0].destroyed);
+        assert_eq!(
+            toplevels[0].committed.window_geometry,
+            Some((0, 0, 400, 300))
+        );
+        assert_eq!(toplevels[1].title.as_deref(), Some("second"));
+        assert_eq!(toplevels[1].surface_id, 4);
+        assert!(!toplevels[1].has_committed);
+    }
+}
@@ -0,0 +1,47 @@
+use gpui::{
+    point, px, size, Anchor, AppContext as _, Bounds, Empty, LayerShellSettings, WindowBounds,
+    WindowKind, WindowOptions,
+};
+
+#[test]
+#[ignore = "needs a headless wlroots compositor"]
+fn test_layer_shell_bar_reserves_exclusive_zone() {
+    gpui_wayland_test::run(|compositor, cx| async move {
+        cx.update(|cx| {
+            cx.open_window(
+                WindowOptions {
+                    window_bounds: Some(WindowBounds::Windowed(Bounds {
+                        origin: point(px(0.), px(0.)),
+                        size: size(px(0.), px(30.)),
+                    })),
+                    titlebar: None,
+                    kind: WindowKind::LayerShell(LayerShellSettings {
+                        anchor: Anchor::TOP | Anchor::LEFT | Anchor::RIGHT,
+                        exclusive_zone: Some(px(30.)),
+                        namespace: "test-bar".to_string(),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+                |_, cx| cx.new(|_| Empty),
+            )
+        })??;
+
+        // Stretched between the left and right edges of the 1920x1080 output.
+        let layer_surface = compositor
+            .wait_for(&cx, |log| {
+                log.layer_surfaces().into_iter().find(|surface| {
+                    surface.has_committed
+                        && surface
+                            .configures
+                            .last()
+                            .is_some_and(|(serial, _)| surface.acked_serials.contains(serial))
+                })
+            })
+            .await?;
+        assert_eq!(layer_surface.namespace, "test-bar");
+        assert_eq!(layer_surface.configures.last().unwrap().1, (1920, 30));
+        assert_eq!(layer_surface.committed.exclusive_zone, 30);
+        Ok(())
+    });
+}
@@ -104,7 +104,7 @@ pub fn open_prompt_library(
         }
 
         let store = store.await?;
-        cx.update(|cx| {
+        let window = cx.update(|cx| {
             let app_id = ReleaseChannel::global(cx).app_id();
             let bounds = Bounds::centered(None, size(px(1024.0), px(768.0)), cx);
             cx.open_window(
@@ -131,7 +131,8 @@ pub fn open_prompt_library(
                     })
                 },
             )
-        })?
+        })??;
+        Ok(window)
     })
 }
 
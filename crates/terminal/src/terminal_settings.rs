@@ -48,6 +48,14 @@ pub struct TerminalSettings {
     pub max_scroll_history_lines: Option<usize>,
     pub toolbar: Toolbar,
     pub scrollbar: ScrollbarSettings,
+    pub quake_mode: QuakeMode,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct QuakeMode {
+    pub height: Pixels,
+    pub global_shortcut: bool,
+    pub preferred_trigger: String,
 }
 
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
@@ -223,6 +231,9 @@ pub struct TerminalSettingsContent {
     pub toolbar: Option<ToolbarContent>,
     /// Scrollbar-related settings
     pub scrollbar: Option<ScrollbarSettingsContent>,
+    /// Settings for the drop-down terminal, which slides in from the top of
+    /// the screen over other windows.
+    pub quake_mode: Option<QuakeModeContent>,
 }
 
 impl settings::Settings for TerminalSettings {
@@ -329,6 +340,27 @@ pub struct ToolbarContent {
     pub breadcrumbs: Option<bool>,
 }
 
+// Drop-down terminal related settings
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct QuakeModeContent {
+    /// The height of the drop-down terminal.
+    ///
+    /// Default: 400
+    pub height: Option<f32>,
+    /// Whether to register a global shortcut with the desktop that toggles
+    /// the drop-down terminal, even when Zed isn't focused. Only supported on
+    /// Linux, through the GlobalShortcuts portal.
+    ///
+    /// Default: false
+    pub global_shortcut: Option<bool>,
+    /// The trigger to suggest for the global shortcut, following the
+    /// freedesktop shortcuts specification. The desktop may let the user pick
+    /// another one.
+    ///
+    /// Default: "LOGO+grave"
+    pub preferred_trigger: Option<String>,
+}
+
 #[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum CursorShape {
@@ -0,0 +1,232 @@
+use std::time::Duration;
+
+use gpui::{
+    actions, div, ease_in_out, point, px, size, Anchor, Animation, App, AppContext as _, Bounds,
+    Context, Entity, FocusHandle, Focusable, Global, InteractiveElement, IntoElement,
+    KeyboardInteractivity, Layer, LayerShellSettings, ParentElement, Pixels, Render, Styled,
+    Subscription, WeakEntity, Window, WindowBounds, WindowHandle, WindowKind, WindowOptions,
+};
+use project::{terminals::TerminalKind, Project};
+use settings::Settings;
+use terminal::{terminal_settings::TerminalSettings, Event, Terminal};
+use ui::ActiveTheme;
+use util::ResultExt;
+use workspace::{Workspace, WorkspaceId};
+
+use crate::{default_working_directory, terminal_panel::is_enabled_in_workspace, TerminalView};
+
+actions!(terminal_panel, [ToggleQuakeTerminal]);
+
+const SLIDE_DURATION: Duration = Duration::from_millis(150);
+
+pub(crate) fn init(cx: &mut App) {
+    cx.observe_new(|workspace: &mut Workspace, _window, _cx| {
+        workspace.register_action(|workspace, _: &ToggleQuakeTerminal, window, cx| {
+            QuakeTerminal::toggle(workspace, window, cx);
+        });
+    })
+    .detach();
+}
+
+/// Toggles the drop-down terminal of the active workspace window, or of the first one when
+/// none is active, like when a global shortcut is pressed while Zed isn't focused.
+pub fn toggle_quake_terminal(cx: &mut App) {
+    let workspace = cx
+        .active_window()
+        .and_then(|window| window.downcast::<Workspace>())
+        .or_else(|| workspace::local_workspace_windows(cx).into_iter().next());
+    if let Some(workspace) = workspace {
+        workspace
+            .update(cx, |workspace, window, cx| {
+                QuakeTerminal::toggle(workspace, window, cx);
+            })
+            .log_err();
+    }
+}
+
+/// The trigger to suggest for the global shortcut that toggles the drop-down terminal, when
+/// it's enabled in the settings.
+pub fn global_shortcut_trigger(cx: &App) -> Option<String> {
+    let quake_mode = &TerminalSettings::get_global(cx).quake_mode;
+    quake_mode
+        .global_shortcut
+        .then(|| quake_mode.preferred_trigger.clone())
+}
+
+/// The drop-down terminal, shared by all workspaces. Its window is closed while it's hidden,
+/// but the terminal lives on, so the session carries on between summons.
+#[derive(Default)]
+struct QuakeTerminal {
+    terminal: Option<Entity<Terminal>>,
+    window: Option<WindowHandle<QuakeTerminalView>>,
+}
+
+impl Global for QuakeTerminal {}
+
+impl QuakeTerminal {
+    fn toggle(workspace: &mut Workspace, window: &mut Window, cx: &mut Context<Workspace>) {
+        if let Some(handle) = cx.default_global::<Self>().window.take() {
+            // The compositor may have closed the window already.
+            if handle
+                .update(cx, |view, window, cx| view.slide_out(window, cx))
+                .is_ok()
+            {
+                return;
+            }
+        }
+
+        let workspace_id = workspace.database_id();
+        let project = workspace.project().downgrade();
+        if let Some(terminal) = cx.global::<Self>().terminal.clone() {
+            Self::open_window(terminal, workspace.weak_handle(), workspace_id, project, cx);
+            return;
+        }
+
+        if !is_enabled_in_workspace(workspace, cx) {
+            log::error!("terminal not yet supported for remote projects");
+            return;
+        }
+        let working_directory = default_working_directory(workspace, cx);
+        let window_handle = window.window_handle();
+        let terminal = workspace.project().update(cx, |project, cx| {
+            project.create_terminal(TerminalKind::Shell(working_directory), window_handle, cx)
+        });
+        cx.spawn(|workspace, mut cx| async move {
+            let terminal = terminal.await?;
+            cx.update(|cx| {
+                cx.default_global::<Self>().terminal = Some(terminal.clone());
+                Self::open_window(terminal, workspace, workspace_id, project, cx);
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn open_window(
+        terminal: Entity<Terminal>,
+        workspace: WeakEntity<Workspace>,
+        workspace_id: Option<WorkspaceId>,
+        project: WeakEntity<Project>,
+        cx: &mut App,
+    ) {
+        let height = TerminalSettings::get_global(cx).quake_mode.height;
+        let hidden_margin = (-height, px(0.), px(0.), px(0.));
+        let options = WindowOptions {
+            // The width is left to the compositor, which stretches the window between the
+            // edges it's anchored to.
+            window_bounds: Some(WindowBounds::Windowed(Bounds {
+                origin: point(px(0.), px(0.)),
+                size: size(px(0.), height),
+            })),
+            titlebar: None,
+            // Without a display, the compositor opens the window on the focused output.
+            kind: WindowKind::LayerShell(LayerShellSettings {
+                layer: Layer::Overlay,
+                anchor: Anchor::TOP | Anchor::LEFT | Anchor::RIGHT,
+                margin: Some(hidden_margin),
+                keyboard_interactivity: KeyboardInteractivity::OnDemand,
+                namespace: "zed-quake-terminal".to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let Some(handle) = cx
+            .open_window(options, |window, cx| {
+                cx.new(|cx| {
+                    let terminal_view = cx.new(|cx| {
+                        TerminalView::new(
+                            terminal.clone(),
+                            workspace,
+                            workspace_id,
+                            project,
+                            window,
+                            cx,
+                        )
+                    });
+                    QuakeTerminalView::new(terminal, terminal_view, hidden_margin, window, cx)
+                })
+            })
+            .log_err()
+        else {
+            return;
+        };
+        handle
+            .update(cx, |view, window, cx| view.slide_in(window, cx))
+            .log_err();
+        cx.default_global::<Self>().window = Some(handle);
+    }
+}
+
+/// The root view of the drop-down terminal's window.
+struct QuakeTerminalView {
+    terminal_view: Entity<TerminalView>,
+    hidden_margin: (Pixels, Pixels, Pixels, Pixels),
+    _subscription: Subscription,
+}
+
+impl QuakeTerminalView {
+    fn new(
+        terminal: Entity<Terminal>,
+        terminal_view: Entity<TerminalView>,
+        hidden_margin: (Pixels, Pixels, Pixels, Pixels),
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        // Once the shell exits, the next summon starts a new one.
+        let subscription = cx.subscribe_in(&terminal, window, |_, _, event, window, cx| {
+            if let Event::CloseTerminal = event {
+                let quake_terminal = cx.default_global::<QuakeTerminal>();
+                quake_terminal.terminal = None;
+                quake_terminal.window = None;
+                window.remove_window();
+            }
+        });
+        Self {
+            terminal_view,
+            hidden_margin,
+            _subscription: subscription,
+        }
+    }
+
+    fn slide_in(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        window.animate_layer_shell_margin(
+            Default::default(),
+            Animation::new(SLIDE_DURATION).with_easing(ease_in_out),
+        );
+        window.focus(&self.terminal_view.focus_handle(cx));
+    }
+
+    /// Slides the window back past the top of the screen and closes it.
+    fn slide_out(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        window.animate_layer_shell_margin(
+            self.hidden_margin,
+            Animation::new(SLIDE_DURATION).with_easing(ease_in_out),
+        );
+        cx.spawn_in(window, |_, mut cx| async move {
+            cx.background_executor().timer(SLIDE_DURATION).await;
+            cx.update(|window, _| window.remove_window())
+        })
+        .detach_and_log_err(cx);
+    }
+}
+
+impl Focusable for QuakeTerminalView {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.terminal_view.focus_handle(cx)
+    }
+}
+
+impl Render for QuakeTerminalView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        div()
+            // The workspace's actions aren't available here, so handle the toggle as well.
+            .on_action(cx.listener(|this, _: &ToggleQuakeTerminal, window, cx| {
+                cx.default_global::<QuakeTerminal>().window = None;
+                this.slide_out(window, cx);
+            }))
+            .size_full()
+            .bg(cx.theme().colors().terminal_background)
+            .border_b_1()
+            .border_color(cx.theme().colors().border)
+            .child(self.terminal_view.clone())
+    }
+}
@@ -962,7 +962,7 @@ impl TerminalPanel {
     }
 }
 
-fn is_enabled_in_workspace(workspace: &Workspace, cx: &App) -> bool {
+pub(crate) fn is_enabled_in_workspace(workspace: &Workspace, cx: &App) -> bool {
     workspace.project().read(cx).supports_terminal(cx)
 }
 
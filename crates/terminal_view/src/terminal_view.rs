@@ -1,4 +1,5 @@
 mod persistence;
+pub mod quake_terminal;
 pub mod terminal_element;
 pub mod terminal_panel;
 pub mod terminal_scrollbar;
@@ -79,6 +80,7 @@ impl_actions!(terminal, [SendText, SendKeystroke]);
 
 pub fn init(cx: &mut App) {
     terminal_panel::init(cx);
+    quake_terminal::init(cx);
     terminal::init(cx);
 
     register_serializable_item::<TerminalView>(cx);
@@ -514,6 +514,8 @@ fn main() {
         search::init(cx);
         vim::init(cx);
         terminal_view::init(cx);
+        #[cfg(any(target_os = "linux", target_os = "freebsd"))]
+        crate::zed::global_shortcuts::init(cx);
         journal::init(app_state.clone(), cx);
         language_selector::init(cx);
         toolchain_selector::init(cx);
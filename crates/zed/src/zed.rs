@@ -1,4 +1,6 @@
 mod app_menus;
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+pub(crate) mod global_shortcuts;
 pub mod inline_completion_registry;
 #[cfg(any(target_os = "linux", target_os = "freebsd"))]
 pub(crate) mod linux_prompts;
@@ -141,6 +143,7 @@ pub fn build_window_options(display_uuid: Option<Uuid>, cx: &mut App) -> WindowO
         window_bounds: None,
         focus: false,
         show: false,
+        deferred: false,
         kind: WindowKind::Normal,
         is_movable: true,
         display_id: display.map(|display| display.id()),
@@ -151,6 +154,8 @@ pub fn build_window_options(display_uuid: Option<Uuid>, cx: &mut App) -> WindowO
             width: px(360.0),
             height: px(240.0),
         }),
+        reactive_rendering: false,
+        anti_aliasing: Default::default(),
     }
 }
 
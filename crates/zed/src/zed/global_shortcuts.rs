@@ -0,0 +1,73 @@
+//! Summons the drop-down terminal and the launcher with global shortcuts, bound through the
+//! desktop so they work while Zed isn't focused.
+
+use file_finder::{file_finder_settings::FileFinderSettings, launcher};
+use gpui::{
+    App, AppContext as _, Entity, Global, GlobalShortcut, GlobalShortcutEvent, GlobalShortcuts,
+    Subscription,
+};
+use settings::{Settings, SettingsStore};
+use terminal_view::quake_terminal;
+
+const QUAKE_TERMINAL_ID: &str = "toggle-quake-terminal";
+const LAUNCHER_ID: &str = "toggle-launcher";
+
+struct RegisteredShortcuts {
+    shortcuts: Vec<GlobalShortcut>,
+    _global_shortcuts: Entity<GlobalShortcuts>,
+    _subscription: Subscription,
+}
+
+impl Global for RegisteredShortcuts {}
+
+pub fn init(cx: &mut App) {
+    update_global_shortcuts(cx);
+    cx.observe_global::<SettingsStore>(update_global_shortcuts)
+        .detach();
+}
+
+/// Registers the shortcuts enabled in the settings, and again when they change.
+fn update_global_shortcuts(cx: &mut App) {
+    let mut shortcuts = Vec::new();
+    if let Some(trigger) = quake_terminal::global_shortcut_trigger(cx) {
+        shortcuts.push(
+            GlobalShortcut::new(QUAKE_TERMINAL_ID, "Toggle the drop-down terminal")
+                .preferred_trigger(trigger),
+        );
+    }
+    let launcher_settings = &FileFinderSettings::get_global(cx).launcher;
+    if launcher_settings.global_shortcut {
+        shortcuts.push(
+            GlobalShortcut::new(LAUNCHER_ID, "Find a file to open in Zed")
+                .preferred_trigger(launcher_settings.preferred_trigger.clone()),
+        );
+    }
+
+    let registered = cx
+        .try_global::<RegisteredShortcuts>()
+        .map_or(&[][..], |registered| registered.shortcuts.as_slice());
+    if registered == shortcuts.as_slice() {
+        return;
+    }
+    if shortcuts.is_empty() {
+        // Something was registered, or the shortcuts wouldn't have changed.
+        cx.remove_global::<RegisteredShortcuts>();
+        return;
+    }
+
+    let global_shortcuts = cx.new(|cx| GlobalShortcuts::new(shortcuts.clone(), cx));
+    let subscription = cx.subscribe(&global_shortcuts, |_, event, cx| {
+        if let GlobalShortcutEvent::Activated(id) = event {
+            match id.as_ref() {
+                QUAKE_TERMINAL_ID => quake_terminal::toggle_quake_terminal(cx),
+                LAUNCHER_ID => launcher::toggle_launcher(cx),
+                _ => {}
+            }
+        }
+    });
+    cx.set_global(RegisteredShortcuts {
+        shortcuts,
+        _global_shortcuts: global_shortcuts,
+        _subscription: subscription,
+    });
+}